@@ -17,6 +17,7 @@
 //! pointer exactly once with [`read_bytes_from_memory`]. Each `unsafe` block is
 //! annotated with a `// SAFETY:` comment describing assumptions.
 
+use crate::errors::ErrorCode;
 use crate::runtime::read_bytes_from_memory;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::marker::PhantomData;
@@ -83,3 +84,69 @@ impl<T: DeserializeOwned + Default> Secrets<T> {
         }
     }
 }
+
+/// Check that a required config field has a non-blank value.
+///
+/// Applet configs call this from their `validate` so that a missing field is reported by name,
+/// as a `VALIDATION` error, instead of surfacing later as a failed request.
+pub fn require_non_empty(field: &str, value: &str) -> Result<(), String> {
+    if value.trim().is_empty() {
+        return Err(
+            ErrorCode::Validation.tag(format!("config field `{}` is required but empty", field))
+        );
+    }
+    Ok(())
+}
+
+/// Check that a required config field holds an absolute `http(s)://host...` URL.
+pub fn require_http_url(field: &str, value: &str) -> Result<(), String> {
+    require_non_empty(field, value)?;
+    let value = value.trim();
+    let host = value
+        .strip_prefix("https://")
+        .or_else(|| value.strip_prefix("http://"))
+        .map(|rest| rest.split(['/', '?', '#']).next().unwrap_or(""));
+    match host {
+        Some(host) if !host.is_empty() && !value.contains(char::is_whitespace) => Ok(()),
+        _ => Err(ErrorCode::Validation.tag(format!(
+            "config field `{}` must be an http(s) URL such as https://example.com, got `{}`",
+            field, value
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn required_fields_and_urls() {
+        assert!(require_non_empty("username", "admin").is_ok());
+        let missing = require_non_empty("username", "  ").unwrap_err();
+        assert_eq!(
+            missing,
+            "[VALIDATION] config field `username` is required but empty"
+        );
+
+        assert!(require_http_url("base_url", "https://dev1234.service-now.com/").is_ok());
+        assert!(require_http_url("base_url", "http://localhost:8080").is_ok());
+        for bad in [
+            "dev1234.service-now.com",
+            "https://",
+            "https:///path",
+            "ftp://host",
+            "https://a b",
+        ] {
+            let err = require_http_url("base_url", bad).unwrap_err();
+            assert_eq!(
+                ErrorCode::of_tagged(&err),
+                Some(ErrorCode::Validation),
+                "{}",
+                bad
+            );
+        }
+        assert!(require_http_url("base_url", "")
+            .unwrap_err()
+            .contains("is required but empty"));
+    }
+}
@@ -248,3 +248,172 @@ impl WeilError {
         })
     }
 }
+
+/// Coarse category an MCP applet tags its errors with, so agents can branch on the kind of
+/// failure instead of parsing free text.
+///
+/// Tagged messages read `"[CODE] message"`, e.g. `"[NOT_FOUND] HTTP 404: no such page"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// Credentials were missing, rejected or lack the needed permission.
+    Auth,
+    /// The addressed resource doesn't exist or isn't visible.
+    NotFound,
+    /// The upstream service throttled the request.
+    RateLimited,
+    /// The request clashes with the resource's current state.
+    Conflict,
+    /// The request itself was malformed or failed a check.
+    Validation,
+    /// The upstream service failed or answered unexpectedly.
+    Upstream,
+    /// The operation didn't finish in the time allowed.
+    Timeout,
+}
+
+impl ErrorCode {
+    /// Classify a non-2xx HTTP status.
+    pub fn from_http_status(status: u16) -> Self {
+        match status {
+            401 | 403 => ErrorCode::Auth,
+            404 | 410 => ErrorCode::NotFound,
+            409 | 412 => ErrorCode::Conflict,
+            429 => ErrorCode::RateLimited,
+            408 | 504 => ErrorCode::Timeout,
+            400..=499 => ErrorCode::Validation,
+            _ => ErrorCode::Upstream,
+        }
+    }
+
+    /// The code as it appears between the brackets of a tagged message.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::Auth => "AUTH",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::RateLimited => "RATE_LIMITED",
+            ErrorCode::Conflict => "CONFLICT",
+            ErrorCode::Validation => "VALIDATION",
+            ErrorCode::Upstream => "UPSTREAM",
+            ErrorCode::Timeout => "TIMEOUT",
+        }
+    }
+
+    /// Prefix `message` with this code: `"[CODE] message"`.
+    pub fn tag(&self, message: impl std::fmt::Display) -> String {
+        format!("[{}] {}", self.as_str(), message)
+    }
+
+    /// Recover the code from a message produced by [`ErrorCode::tag`].
+    pub fn of_tagged(message: &str) -> Option<Self> {
+        let code = message.strip_prefix('[')?.split_once(']')?.0;
+        [
+            ErrorCode::Auth,
+            ErrorCode::NotFound,
+            ErrorCode::RateLimited,
+            ErrorCode::Conflict,
+            ErrorCode::Validation,
+            ErrorCode::Upstream,
+            ErrorCode::Timeout,
+        ]
+        .into_iter()
+        .find(|candidate| candidate.as_str() == code)
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One row of an MCP's table of remediation hints for the errors it returns.
+#[derive(Debug, Clone, Copy)]
+pub struct DiagnosisRule {
+    /// Applies to messages tagged with this code.
+    pub code: Option<ErrorCode>,
+    /// Applies to messages containing any of these, ignoring ASCII case.
+    pub patterns: &'static [&'static str],
+    /// What the caller can do about the error.
+    pub guidance: &'static str,
+}
+
+/// Guidance from the first of `rules` that applies to `message`, so rules for specific
+/// messages belong before the catch-all rules for their code.
+pub fn diagnosis_for(message: &str, rules: &[DiagnosisRule]) -> Option<&'static str> {
+    let code = ErrorCode::of_tagged(message.trim_start());
+    let message = message.to_ascii_lowercase();
+    rules
+        .iter()
+        .find(|rule| {
+            (rule.code.is_some() && rule.code == code)
+                || rule
+                    .patterns
+                    .iter()
+                    .any(|pattern| message.contains(&pattern.to_ascii_lowercase()))
+        })
+        .map(|rule| rule.guidance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn http_statuses_map_to_codes() {
+        assert_eq!(ErrorCode::from_http_status(401), ErrorCode::Auth);
+        assert_eq!(ErrorCode::from_http_status(403), ErrorCode::Auth);
+        assert_eq!(ErrorCode::from_http_status(404), ErrorCode::NotFound);
+        assert_eq!(ErrorCode::from_http_status(409), ErrorCode::Conflict);
+        assert_eq!(ErrorCode::from_http_status(429), ErrorCode::RateLimited);
+        assert_eq!(ErrorCode::from_http_status(408), ErrorCode::Timeout);
+        assert_eq!(ErrorCode::from_http_status(504), ErrorCode::Timeout);
+        assert_eq!(ErrorCode::from_http_status(400), ErrorCode::Validation);
+        assert_eq!(ErrorCode::from_http_status(422), ErrorCode::Validation);
+        assert_eq!(ErrorCode::from_http_status(500), ErrorCode::Upstream);
+        assert_eq!(ErrorCode::from_http_status(503), ErrorCode::Upstream);
+    }
+
+    #[test]
+    fn tagged_messages_round_trip() {
+        let message = ErrorCode::Timeout.tag("run 42 did not finish within 30s");
+        assert_eq!(message, "[TIMEOUT] run 42 did not finish within 30s");
+        assert_eq!(ErrorCode::of_tagged(&message), Some(ErrorCode::Timeout));
+        assert_eq!(
+            ErrorCode::of_tagged(&ErrorCode::from_http_status(429).tag("HTTP 429: slow down")),
+            Some(ErrorCode::RateLimited)
+        );
+        assert_eq!(ErrorCode::of_tagged("HTTP 404: gone"), None);
+        assert_eq!(ErrorCode::of_tagged("[BOGUS] nope"), None);
+        assert_eq!(
+            serde_json::to_string(&ErrorCode::NotFound).unwrap(),
+            "\"NOT_FOUND\""
+        );
+    }
+
+    #[test]
+    fn diagnosis_uses_first_matching_rule() {
+        const RULES: &[DiagnosisRule] = &[
+            DiagnosisRule {
+                code: None,
+                patterns: &["Space Not Found"],
+                guidance: "check the space",
+            },
+            DiagnosisRule {
+                code: Some(ErrorCode::NotFound),
+                patterns: &["http 404"],
+                guidance: "check the id",
+            },
+        ];
+        assert_eq!(
+            diagnosis_for("[NOT_FOUND] space not found: ENG", RULES),
+            Some("check the space")
+        );
+        assert_eq!(
+            diagnosis_for("[NOT_FOUND] no page 7", RULES),
+            Some("check the id")
+        );
+        assert_eq!(diagnosis_for("HTTP 404: gone", RULES), Some("check the id"));
+        assert_eq!(diagnosis_for("[AUTH] HTTP 401", RULES), None);
+    }
+}
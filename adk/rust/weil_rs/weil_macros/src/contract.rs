@@ -336,11 +336,21 @@ fn wasm_inteface_template(
     method_annotation_kind: SmartContractMethodAnnotationKind,
     macro_name: String,
     is_async: bool,
+    tool_gate: Option<&Ident>,
 ) -> Option<TokenStream> {
     let method_name_str = method_name.to_string();
     let method_name_str_literal = Literal::string(&method_name_str);
     let is_args = method_args.len() != 0;
 
+    let tool_gate_snippet = match tool_gate {
+        Some(tool_gate) => quote! {
+            if let Err(err) = smart_contract.#tool_gate(#method_name_str_literal) {
+                return Err(weil_rs::errors::WeilError::new_function_returned_with_error(#method_name_str_literal.to_string(), err))
+            }
+        },
+        None => quote! {},
+    };
+
     let args_snippet = if is_args {
         quote! {
             let args = match args {
@@ -409,6 +419,8 @@ fn wasm_inteface_template(
                 fn #core_method_name() -> Result<weil_rs::runtime::WeilValue<#struct_path, #return_ty>, weil_rs::errors::WeilError> {
                     #state_and_args_snippet
 
+                    #tool_gate_snippet
+
                     #method_call_control_flow
 
                     Ok(weil_rs::runtime::WeilValue::new_with_state_and_ok_value(smart_contract, result))
@@ -438,6 +450,8 @@ fn wasm_inteface_template(
                 fn #core_method_name() -> Result<#return_ty, weil_rs::errors::WeilError> {
                     #state_and_args_snippet
 
+                    #tool_gate_snippet
+
                     #method_call_control_flow
 
                     Ok(result)
@@ -487,7 +501,10 @@ fn wasm_inteface_template(
     }
 }
 
-pub fn impl_smart_contract_macro(impl_smart_contract: ItemImpl) -> proc_macro::TokenStream {
+pub fn impl_smart_contract_macro(
+    impl_smart_contract: ItemImpl,
+    tool_gate: Option<Ident>,
+) -> proc_macro::TokenStream {
     let mut method_args_struct_vec = vec![];
     let mut wasm_interface_funcs = vec![];
     let mut method_kind_mapping = vec![];
@@ -602,6 +619,7 @@ pub fn impl_smart_contract_macro(impl_smart_contract: ItemImpl) -> proc_macro::T
             method_annotation_kind,
             macro_name,
             is_async,
+            tool_gate.as_ref(),
         ) {
             wasm_interface_funcs.push(tokens);
         }
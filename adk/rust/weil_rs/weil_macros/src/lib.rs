@@ -7,14 +7,17 @@ use contract::{
 use event::{check_trait_item_fn, impl_event_macro};
 use proc_macro::TokenStream;
 use syn::{
-    parse, parse_macro_input, DeriveInput, Ident, ImplItemFn, ItemImpl, LitStr, Meta, TraitItemFn,
+    parse, parse_macro_input, DeriveInput, Ident, ImplItemFn, ItemImpl, ItemTrait, LitStr, Meta,
+    TraitItemFn,
 };
+use tool_schema::impl_tool_schema_macro;
 use weil_type::impl_weil_type_derive;
 
 use crate::contract::{impl_smart_contract_secured_macro, QueryOpaqueKind};
 
 mod contract;
 mod event;
+mod tool_schema;
 mod weil_type;
 
 #[proc_macro_derive(WeilType)]
@@ -23,14 +26,31 @@ pub fn weil_type_derive(input: TokenStream) -> TokenStream {
     impl_weil_type_derive(input)
 }
 
+/// Export the methods of a contract `impl`. With `tool_gate = method`, every exported query and
+/// mutation first calls `self.method("<name>") -> Result<(), String>` and fails with its error,
+/// so a contract can refuse tools (e.g. its configured `disabled_tools`) in one place.
 #[proc_macro_attribute]
-pub fn smart_contract(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn smart_contract(attr: TokenStream, item: TokenStream) -> TokenStream {
     let impl_smart_contract = match parse::<ItemImpl>(item) {
         Ok(syntax_tree) => syntax_tree,
         Err(err) => return proc_macro::TokenStream::from(err.to_compile_error()),
     };
 
-    impl_smart_contract_macro(impl_smart_contract)
+    let mut tool_gate: Option<Ident> = None;
+
+    let smart_contract_parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("tool_gate") {
+            tool_gate = Some(meta.value()?.parse()?);
+
+            return Ok(());
+        }
+
+        Err(meta.error("unsupported smart_contract property"))
+    });
+
+    parse_macro_input!(attr with smart_contract_parser);
+
+    impl_smart_contract_macro(impl_smart_contract, tool_gate)
 }
 
 #[proc_macro_attribute]
@@ -139,6 +159,19 @@ pub fn constructor(_attr: TokenStream, item: TokenStream) -> TokenStream {
     impl_smart_contract_constructor_macro(smart_contract_constructor)
 }
 
+/// Generate the MCP tool schema of a contract trait as its `TOOLS` associated constant, so
+/// `tools()` can return `Self::TOOLS` instead of a hand-maintained copy. Parameters of types the
+/// macro can't see into take their schema from a `#[schema("{...}")]` attribute.
+#[proc_macro_attribute]
+pub fn tool_schema(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let item_trait = match parse::<ItemTrait>(item) {
+        Ok(syntax_tree) => syntax_tree,
+        Err(err) => return proc_macro::TokenStream::from(err.to_compile_error()),
+    };
+
+    impl_tool_schema_macro(item_trait)
+}
+
 #[proc_macro_attribute]
 pub fn event(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let fn_decl = match parse::<TraitItemFn>(item) {
@@ -0,0 +1,423 @@
+use quote::quote;
+use syn::{
+    parse_quote, Attribute, Expr, ExprLit, FnArg, GenericArgument, ItemTrait, Lit, LitStr, Meta,
+    Pat, PathArguments, TraitItem, TraitItemFn, Type,
+};
+
+/// Methods every MCP trait declares for the protocol itself, which are not tools.
+const PROTOCOL_METHODS: &[&str] = &["tools", "prompts"];
+
+/// Adds a `TOOLS` associated constant holding the JSON tool schema of the trait's methods.
+pub fn impl_tool_schema_macro(mut item_trait: ItemTrait) -> proc_macro::TokenStream {
+    let schema = match tool_schema(&item_trait) {
+        Ok(schema) => schema,
+        Err(err) => return proc_macro::TokenStream::from(err.to_compile_error()),
+    };
+
+    strip_schema_attrs(&mut item_trait);
+    item_trait.items.push(parse_quote! {
+        /// JSON schema of the tools, generated from the method signatures and doc comments.
+        const TOOLS: &'static str = #schema;
+    });
+
+    quote! {
+        #item_trait
+    }
+    .into()
+}
+
+/// The tool schema of every `&self` method of the trait other than `tools` and `prompts`.
+///
+/// A tool's description is its doc comment up to the first `#` heading. Parameters are
+/// described by doc lines of the form ``- `name`: description``, and are required unless
+/// their type is an `Option`. A parameter whose type the macro can't see into (an enum or
+/// struct defined elsewhere) can give its schema as `#[schema(r#"{"type": "string"}"#)]`.
+pub fn tool_schema(item_trait: &ItemTrait) -> syn::Result<String> {
+    let mut tools = vec![];
+
+    for item in item_trait.items.iter() {
+        let TraitItem::Fn(method) = item else {
+            continue;
+        };
+
+        let name = method.sig.ident.to_string();
+        if method.sig.receiver().is_none() || PROTOCOL_METHODS.contains(&name.as_str()) {
+            continue;
+        }
+
+        tools.push(function_schema(method)?);
+    }
+
+    Ok(format!("[{}]", tools.join(",")))
+}
+
+fn function_schema(method: &TraitItemFn) -> syn::Result<String> {
+    let doc = doc_lines(&method.attrs);
+    let mut properties = vec![];
+    let mut required = vec![];
+
+    for arg in method.sig.inputs.iter() {
+        let FnArg::Typed(arg) = arg else {
+            continue;
+        };
+
+        let Pat::Ident(pat) = arg.pat.as_ref() else {
+            return Err(syn::Error::new_spanned(
+                &arg.pat,
+                "tool parameters must be plain identifiers",
+            ));
+        };
+
+        let name = pat.ident.to_string();
+        let (inner_ty, is_optional) = match option_inner(&arg.ty) {
+            Some(inner_ty) => (inner_ty, true),
+            None => (arg.ty.as_ref(), false),
+        };
+
+        let mut schema = match schema_attr(&arg.attrs)? {
+            Some(schema) => schema,
+            None => type_schema(inner_ty),
+        };
+        if let Some(description) = param_description(&doc, &name) {
+            schema.push(format!("\"description\":{}", json_string(&description)));
+        }
+
+        properties.push(format!("{}:{{{}}}", json_string(&name), schema.join(",")));
+        if !is_optional {
+            required.push(json_string(&name));
+        }
+    }
+
+    Ok(format!(
+        "{{\"type\":\"function\",\"function\":{{\"name\":{},\"description\":{},\"parameters\":{{\"type\":\"object\",\"properties\":{{{}}},\"required\":[{}]}}}}}}",
+        json_string(&method.sig.ident.to_string()),
+        json_string(&description(&doc)),
+        properties.join(","),
+        required.join(",")
+    ))
+}
+
+/// Members of the JSON object given by a `#[schema("{...}")]` parameter attribute, if any.
+fn schema_attr(attrs: &[Attribute]) -> syn::Result<Option<Vec<String>>> {
+    let Some(attr) = attrs.iter().find(|attr| attr.path().is_ident("schema")) else {
+        return Ok(None);
+    };
+
+    let schema: LitStr = attr.parse_args()?;
+    let value = schema.value();
+    let members = value
+        .trim()
+        .strip_prefix('{')
+        .and_then(|object| object.strip_suffix('}'))
+        .ok_or_else(|| syn::Error::new_spanned(&schema, "#[schema] takes a JSON object"))?
+        .trim();
+
+    Ok(Some(
+        Some(members.to_string())
+            .filter(|members| !members.is_empty())
+            .into_iter()
+            .collect(),
+    ))
+}
+
+/// Remove the `#[schema]` parameter attributes, which only the macro understands.
+fn strip_schema_attrs(item_trait: &mut ItemTrait) {
+    for item in item_trait.items.iter_mut() {
+        let TraitItem::Fn(method) = item else {
+            continue;
+        };
+
+        for arg in method.sig.inputs.iter_mut() {
+            if let FnArg::Typed(arg) = arg {
+                arg.attrs.retain(|attr| !attr.path().is_ident("schema"));
+            }
+        }
+    }
+}
+
+/// The lines of the `///` doc comments in `attrs`.
+fn doc_lines(attrs: &[Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) => match &meta.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(doc), ..
+                }) => Some(doc.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .flat_map(|doc| {
+            doc.lines()
+                .map(|line| line.strip_prefix(' ').unwrap_or(line).to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Doc text before the first heading, without parameter lines, as a single line.
+fn description(doc: &[String]) -> String {
+    let mut in_param = false;
+    let mut lines = vec![];
+
+    for line in doc.iter().take_while(|line| !line.trim().starts_with('#')) {
+        if param_line(line.trim()).is_some() {
+            in_param = true;
+        } else if !(in_param && is_continuation(line)) {
+            in_param = false;
+            if !line.trim().is_empty() {
+                lines.push(line.trim());
+            }
+        }
+    }
+
+    lines.join(" ")
+}
+
+/// Description given for `name` by a ``- `name`: description`` doc line, joined with the
+/// indented lines continuing it.
+fn param_description(doc: &[String], name: &str) -> Option<String> {
+    let start = doc
+        .iter()
+        .position(|line| param_line(line.trim()).is_some_and(|(param, _)| param == name))?;
+    let (_, first) = param_line(doc[start].trim())?;
+
+    let mut description = vec![first];
+    description.extend(
+        doc[start + 1..]
+            .iter()
+            .take_while(|line| is_continuation(line))
+            .map(|line| line.trim()),
+    );
+
+    Some(description.join(" "))
+}
+
+/// Whether a doc line is indented text carrying on the list item above it.
+fn is_continuation(line: &str) -> bool {
+    line.starts_with(char::is_whitespace) && !line.trim().is_empty()
+}
+
+/// Split a ``- `name`: description`` (or ``* `name` - description``) line.
+fn param_line(line: &str) -> Option<(&str, &str)> {
+    let rest = line
+        .strip_prefix("- `")
+        .or_else(|| line.strip_prefix("* `"))?;
+    let (name, description) = rest.split_once('`')?;
+    let description = description
+        .trim_start()
+        .strip_prefix(':')
+        .or_else(|| description.trim_start().strip_prefix('-'))?;
+
+    Some((name, description.trim()))
+}
+
+/// `T` if `ty` is `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    match generic_args(ty)? {
+        ("Option", args) if args.len() == 1 => Some(args[0]),
+        _ => None,
+    }
+}
+
+/// Last path segment of `ty` and the types it is parameterised with.
+fn generic_args(ty: &Type) -> Option<(&'static str, Vec<&Type>)> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+
+    let segment = path.path.segments.last()?;
+    let name = match segment.ident.to_string().as_str() {
+        "Option" => "Option",
+        "Vec" | "VecDeque" | "HashSet" | "BTreeSet" => "Vec",
+        "HashMap" | "BTreeMap" => "Map",
+        _ => return None,
+    };
+
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+
+    let types = args
+        .args
+        .iter()
+        .filter_map(|arg| match arg {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        })
+        .collect();
+
+    Some((name, types))
+}
+
+/// Members of the JSON schema object for a parameter of type `ty`. Types without a JSON
+/// counterpart (structs, `serde_json::Value`, ...) are described as objects.
+fn type_schema(ty: &Type) -> Vec<String> {
+    match ty {
+        Type::Reference(reference) => return type_schema(&reference.elem),
+        Type::Slice(slice) => return array_schema(&slice.elem),
+        Type::Array(array) => return array_schema(&array.elem),
+        _ => {}
+    }
+
+    match generic_args(ty) {
+        Some(("Option", args)) if args.len() == 1 => return type_schema(args[0]),
+        Some(("Vec", args)) if args.len() == 1 => return array_schema(args[0]),
+        Some(("Map", args)) if args.len() == 2 => {
+            return vec![
+                "\"type\":\"object\"".to_string(),
+                format!(
+                    "\"additionalProperties\":{{{}}}",
+                    type_schema(args[1]).join(",")
+                ),
+            ]
+        }
+        _ => {}
+    }
+
+    let json_type = match ty {
+        Type::Path(path) => match path.path.segments.last() {
+            Some(segment) => match segment.ident.to_string().as_str() {
+                "String" | "str" | "char" => "string",
+                "bool" => "boolean",
+                "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64"
+                | "i128" | "isize" => "integer",
+                "f32" | "f64" => "number",
+                _ => "object",
+            },
+            None => "object",
+        },
+        _ => "object",
+    };
+
+    vec![format!("\"type\":\"{}\"", json_type)]
+}
+
+fn array_schema(item: &Type) -> Vec<String> {
+    vec![
+        "\"type\":\"array\"".to_string(),
+        format!("\"items\":{{{}}}", type_schema(item).join(",")),
+    ]
+}
+
+/// `value` as a quoted JSON string.
+fn json_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Names, types, descriptions and required-ness all come from the trait declaration, and
+    /// the constructor and protocol methods are left out.
+    #[test]
+    fn tool_schema_follows_signatures() {
+        let item_trait: ItemTrait = syn::parse_str(
+            r#"
+            trait Catalog {
+                fn new() -> Result<Self, String> where Self: Sized;
+
+                /// Create a "catalog" category.
+                ///
+                /// # Parameters
+                /// - `title`: Category title.
+                /// - `parent_category_id`: Parent category id,
+                ///   when nested.
+                async fn create_category(
+                    &self,
+                    title: String,
+                    limit: u32,
+                    parent_category_id: Option<String>,
+                    tags: Vec<String>,
+                    fields: Option<std::collections::HashMap<String, serde_json::Value>>,
+                ) -> Result<String, String>;
+
+                fn tools(&self) -> String;
+                fn prompts(&self) -> String;
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            tool_schema(&item_trait).unwrap(),
+            concat!(
+                r#"[{"type":"function","function":{"name":"create_category","#,
+                r#""description":"Create a \"catalog\" category.","#,
+                r#""parameters":{"type":"object","properties":{"#,
+                r#""title":{"type":"string","description":"Category title."},"#,
+                r#""limit":{"type":"integer"},"#,
+                r#""parent_category_id":{"type":"string","description":"Parent category id, when nested."},"#,
+                r#""tags":{"type":"array","items":{"type":"string"}},"#,
+                r#""fields":{"type":"object","additionalProperties":{"type":"object"}}},"#,
+                r#""required":["title","limit","tags"]}}}]"#,
+            )
+        );
+    }
+
+    /// A `#[schema]` attribute replaces the schema derived from the type, keeps the doc
+    /// description, and is stripped from the trait the macro emits.
+    #[test]
+    fn schema_attribute_overrides_type() {
+        let mut item_trait: ItemTrait = syn::parse_str(
+            r##"
+            trait Notes {
+                /// Post a note.
+                ///
+                /// - `visibility`: Who sees
+                ///   the note.
+                ///
+                /// Notes are kept.
+                async fn post(
+                    &self,
+                    #[schema(r#"{"type": "string", "enum": ["Public", "Private"]}"#)]
+                    visibility: Visibility,
+                    #[schema("{}")] extra: Option<Extra>,
+                ) -> Result<(), String>;
+            }
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            tool_schema(&item_trait).unwrap(),
+            concat!(
+                r#"[{"type":"function","function":{"name":"post","#,
+                r#""description":"Post a note. Notes are kept.","#,
+                r#""parameters":{"type":"object","properties":{"#,
+                r#""visibility":{"type": "string", "enum": ["Public", "Private"],"#,
+                r#""description":"Who sees the note."},"#,
+                r#""extra":{}},"#,
+                r#""required":["visibility"]}}}]"#,
+            )
+        );
+
+        strip_schema_attrs(&mut item_trait);
+        let TraitItem::Fn(method) = &item_trait.items[0] else {
+            unreachable!();
+        };
+        assert!(method.sig.inputs.iter().all(|arg| match arg {
+            FnArg::Typed(arg) => arg.attrs.is_empty(),
+            FnArg::Receiver(_) => true,
+        }));
+    }
+}
@@ -24,7 +24,7 @@
 //! ```
 
 use serde::{Deserialize, Serialize};
-use weil_macros::{WeilType, constructor, query, smart_contract};
+use weil_macros::{WeilType, constructor, query, smart_contract, tool_schema};
 
 /// Public MCP trait surface for basic integer arithmetic.
 ///
@@ -32,6 +32,7 @@ use weil_macros::{WeilType, constructor, query, smart_contract};
 /// - [`add`] and [`multiply`] compute results deterministically for given inputs.
 /// - [`tools`] provides a JSON schema describing callable functions for MCP agents.
 /// - [`prompts`] is reserved for future prompt templates (currently empty).
+#[tool_schema]
 trait Arithmetic {
     /// Construct a new contract state.
     ///
@@ -62,7 +63,8 @@ trait Arithmetic {
 
     /// JSON schema describing callable tools (for MCP/agent orchestration).
     ///
-    /// The schema enumerates `add` and `multiply` with their parameter shapes.
+    /// The schema enumerates `add` and `multiply` with their parameter shapes, as generated
+    /// into `Self::TOOLS`.
     fn tools(&self) -> String;
 
     /// Placeholder for prompt templates used by agentic flows (currently empty).
@@ -114,60 +116,10 @@ impl Arithmetic for ArithmeticContractState {
 
     /// Machine-readable tool specifications for MCP/agent runtimes.
     ///
-    /// Exposes two functions: `add` and `multiply`, each taking integer `x` and `y`.
+    /// Generated from the [`Arithmetic`] trait by `#[tool_schema]`.
     #[query]
     fn tools(&self) -> String {
-        r#"[
-  {
-    "type": "function",
-    "function": {
-      "name": "add",
-      "description": "adds two numbers\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "x": {
-            "type": "integer",
-            "description": null
-          },
-          "y": {
-            "type": "integer",
-            "description": null
-          }
-        },
-        "required": [
-          "x",
-          "y"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "multiply",
-      "description": "multiply two numbers\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "x": {
-            "type": "integer",
-            "description": null
-          },
-          "y": {
-            "type": "integer",
-            "description": null
-          }
-        },
-        "required": [
-          "x",
-          "y"
-        ]
-      }
-    }
-  }
-]"#
-        .to_string()
+        Self::TOOLS.to_string()
     }
 
     /// Placeholder for prompt templates. Currently returns an empty `prompts` array.
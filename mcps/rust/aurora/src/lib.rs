@@ -29,7 +29,9 @@
 
 use serde::{Deserialize, Serialize};
 use weil_macros::{WeilType, constructor, query, smart_contract};
-use weil_rs::{config::Secrets, db::DB, runtime::Runtime, utils::cleanse_input_string};
+use weil_rs::{
+    config::Secrets, db::DB, errors::ErrorCode, runtime::Runtime, utils::cleanse_input_string,
+};
 
 /// Configuration required to connect to an Aurora (PostgreSQL-compatible) database.
 ///
@@ -156,10 +158,10 @@ impl Aurora for AuroraContractState {
         );
         url += &db_name;
 
-        let rows = DB::query(&url, query_str).map_err(|err| err.to_string())?;
+        let rows = DB::query(&url, query_str).map_err(|err| ErrorCode::Upstream.tag(err))?;
 
         if rows.len() == 0 {
-            return Err("No rows found for this query".to_string());
+            return Err(ErrorCode::NotFound.tag("No rows found for this query"));
         }
 
         Ok(rows)
@@ -182,7 +184,7 @@ impl Aurora for AuroraContractState {
         url += &db_name;
 
         let number_of_rows_affected =
-            DB::execute(&url, statement).map_err(|err| err.to_string())?;
+            DB::execute(&url, statement).map_err(|err| ErrorCode::Upstream.tag(err))?;
 
         Ok(number_of_rows_affected)
     }
@@ -3,6 +3,7 @@ use weil_macros::{constructor, mutate, query, smart_contract, WeilType};
 use weil_rs::collections::map::WeilMap;
 use weil_rs::collections::WeilId;
 use weil_rs::config::Secrets;
+use weil_rs::errors::ErrorCode;
 use weil_rs::http::{HttpClient, HttpMethod};
 use weil_rs::runtime::Runtime;
 use std::collections::{BTreeMap, HashMap};
@@ -23,8 +24,13 @@ pub struct BigQueryConfig {
     client_id: String,
     /// The OAuth client secret.
     client_secret: String,
+    /// User-Agent to send instead of `DEFAULT_USER_AGENT`.
+    user_agent: Option<String>,
 }
 
+/// User-Agent sent with every request unless `user_agent` is set in the config.
+const DEFAULT_USER_AGENT: &str = concat!("wadk-mcp/", env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
 /// Represents metadata for a BigQuery dataset.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Dataset {
@@ -115,6 +121,14 @@ pub struct BigQueryContractState {
 }
 
 impl BigQueryContractState {
+    /// User-Agent for outbound requests: the configured override, or `DEFAULT_USER_AGENT`.
+    fn user_agent(&self) -> String {
+        match self.secrets.config().user_agent.as_deref().map(str::trim) {
+            Some(user_agent) if !user_agent.is_empty() => user_agent.to_string(),
+            _ => DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+
     /// Refreshes the current access token using the stored refresh token.
     ///
     /// Makes a POST request to Google’s OAuth2 endpoint and returns a new token.
@@ -130,6 +144,7 @@ impl BigQueryContractState {
 
         let mut headers: HashMap<String, String> = HashMap::new();
         headers.insert("Content-Type".into(), "application/json".into());
+        headers.insert("User-Agent".into(), self.user_agent());
 
         let response = HttpClient::request("https://oauth2.googleapis.com/token", HttpMethod::Post)
             .headers(headers)
@@ -143,7 +158,7 @@ impl BigQueryContractState {
         if let Some(new_token) = json.get("access_token").and_then(|v| v.as_str()) {
             Ok(new_token.to_string())
         } else {
-            Err(format!("Invalid refresh token response: {:?}", json))
+            Err(ErrorCode::Auth.tag(format!("Invalid refresh token response: {:?}", json)))
         }
     }
 
@@ -158,7 +173,8 @@ impl BigQueryContractState {
     /// * `body` – Optional JSON body to send
     ///
     /// # Returns
-    /// A parsed JSON value (`serde_json::Value`) from the response body.
+    /// A parsed JSON value (`serde_json::Value`) from the response body, or an error tagged with
+    /// the `ErrorCode` of a non-2xx status.
     async fn make_authenticated_request(
         &self,
         method: HttpMethod,
@@ -170,6 +186,7 @@ impl BigQueryContractState {
         let mut headers = HashMap::new();
         headers.insert("Authorization".into(), format!("Bearer {}", config.access_token));
         headers.insert("Content-Type".into(), "application/json".into());
+        headers.insert("User-Agent".into(), self.user_agent());
 
         let response_result = {
             let mut request = HttpClient::request(url, method.clone()).headers(headers.clone());
@@ -179,8 +196,9 @@ impl BigQueryContractState {
             request.send()
         };
 
-        let mut response = match response_result {
+        let response = match response_result {
             Ok(resp) => {
+                let status = resp.status();
                 let text = resp.text();
                 if text.contains("401") || text.to_lowercase().contains("unauthorized") {
 
@@ -195,16 +213,24 @@ impl BigQueryContractState {
 
                     retry_request.send().map_err(|e| format!("HTTP retry failed: {:?}", e))?
                 } else {
-                    return serde_json::from_str::<serde_json::Value>(&text)
-                        .map_err(|e| format!("Failed to parse JSON response: {:?}", e));
+                    return parse_response(status, &text);
                 }
             }
             Err(err) => return Err(format!("HTTP request failed: {:?}", err)),
         };
 
-        response.json::<serde_json::Value>()
-            .map_err(|e| format!("Failed to parse JSON response: {:?}", e))
+        parse_response(response.status(), &response.text())
+    }
+}
+
+/// Parse a BigQuery response body; a non-2xx status is an error tagged with its `ErrorCode`,
+/// carrying BigQuery's explanation.
+fn parse_response(status: u16, text: &str) -> Result<serde_json::Value, String> {
+    if !(200..300).contains(&status) {
+        return Err(ErrorCode::from_http_status(status).tag(format!("HTTP {}: {}", status, text.trim())));
     }
+    serde_json::from_str::<serde_json::Value>(text)
+        .map_err(|e| ErrorCode::Upstream.tag(format!("Failed to parse JSON response: {:?}", e)))
 }
 
 #[smart_contract]
@@ -15,22 +15,30 @@
 
 mod page_body_types;
 mod responses;
+mod storage_to_markdown;
 mod types;
 
-use page_body_types::{CellAttrs, Content, Document, Node, TableAttrs};
+use page_body_types::{CellAttrs, Content, Document, Mark, Node, TableAttrs};
 use responses::{CreateBlogPostResponse, CreatePageResponse};
 use serde_json::{Value, json};
+use storage_to_markdown::storage_to_markdown;
 use types::*;
 use weil_rs::runtime::Runtime;
+use weil_rs::traits::WeilType;
 
+use std::cell::RefCell;
 use std::collections::{BTreeMap, HashMap};
 use std::u64;
 
 use base64::Engine;
 use base64::prelude::BASE64_STANDARD;
+use mcp_http::ids::{json_id, parse_id};
+use mcp_http::pagination::{PaginationLimits, drain_pages};
+use mcp_http::tools::{TOOL_DISABLED, check_tool_enabled, filter_tools};
 use serde::{Deserialize, Serialize};
 use weil_macros::{WeilType, constructor, mutate, query, smart_contract};
-use weil_rs::config::Secrets;
+use weil_rs::config::{Secrets, require_http_url, require_non_empty};
+use weil_rs::errors::{DiagnosisRule, ErrorCode, diagnosis_for};
 use weil_rs::http::{HttpClient, HttpMethod};
 
 /// Constants used for API requests and content formatting.
@@ -45,16 +53,91 @@ const TEXT: &str = "text";
 const LIMIT: &str = "limit";
 const DEPTH: &str = "depth";
 
+/// Path roots of the Confluence REST APIs, relative to `confluence_url`.
+const REST_V2: &str = "wiki/api/v2";
+const REST_V1: &str = "wiki/rest/api";
+/// Reactions have no public REST API; this is the endpoint the Confluence web UI uses.
+const REACTIONS_API: &str = "wiki/rest/reactions/1.0";
+
+/// Emoji shortnames accepted as reactions, with the emoji id Confluence stores for each.
+const REACTION_EMOJIS: &[(&str, &str)] = &[
+    ("thumbsup", "1f44d"),
+    ("thumbsdown", "1f44e"),
+    ("clap", "1f44f"),
+    ("heart", "2764"),
+    ("tada", "1f389"),
+    ("smile", "1f604"),
+    ("eyes", "1f440"),
+    ("white_check_mark", "2705"),
+    ("rocket", "1f680"),
+    ("raised_hands", "1f64c"),
+];
+
+/// User-Agent sent with every request unless `user_agent` is set in the config.
+const DEFAULT_USER_AGENT: &str = concat!(
+    "wadk-mcp/",
+    env!("CARGO_PKG_NAME"),
+    "/",
+    env!("CARGO_PKG_VERSION")
+);
+
 /// Configuration for authenticating to Confluence Cloud.
 ///
 /// Values are normally provided via the chain `Secrets` mechanism. `api_key` is the Confluence
 /// API token associated with `email`. `confluence_url` should be the base like
-/// `https://your-domain.atlassian.net`.
+/// `https://your-domain.atlassian.net`. `user_agent` optionally replaces `DEFAULT_USER_AGENT`.
+/// `default_parent_by_space` keeps pages created by space name off the space root.
+/// `disabled_tools` names tools that refuse to run and are left out of `tools()`.
 #[derive(Debug, Serialize, Deserialize, WeilType, Default)]
 pub struct ConfluenceConfig {
     confluence_url: String,
     email: String,
     api_key: String,
+    user_agent: Option<String>,
+    /// Parent page ID, by space name, for pages created by space name without a parent.
+    default_parent_by_space: Option<BTreeMap<String, u64>>,
+    #[serde(default)]
+    disabled_tools: Vec<String>,
+}
+
+impl ConfluenceConfig {
+    /// Check that the URL and credentials are all set, naming the first field that isn't.
+    fn validate(&self) -> Result<(), String> {
+        require_http_url("confluence_url", &self.confluence_url)?;
+        require_non_empty("email", &self.email)?;
+        require_non_empty("api_key", &self.api_key)
+    }
+
+    /// User-Agent for outbound requests: the configured override, or `DEFAULT_USER_AGENT`.
+    fn user_agent(&self) -> String {
+        match self.user_agent.as_deref().map(str::trim) {
+            Some(user_agent) if !user_agent.is_empty() => user_agent.to_string(),
+            _ => DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+
+    /// Configured default parent page for new pages in `space_name`, if any.
+    fn default_parent(&self, space_name: &str) -> Option<u64> {
+        self.default_parent_by_space
+            .as_ref()?
+            .get(space_name)
+            .copied()
+    }
+}
+
+/// Headers sent with every Confluence request: JSON content type, Basic auth and User-Agent.
+fn request_headers(config: &ConfluenceConfig) -> HashMap<String, String> {
+    HashMap::from([
+        ("Content-Type".to_string(), "application/json".to_string()),
+        (
+            "Authorization".to_string(),
+            format!(
+                "Basic {}",
+                BASE64_STANDARD.encode(format!("{}:{}", config.email, config.api_key))
+            ),
+        ),
+        ("User-Agent".to_string(), config.user_agent()),
+    ])
 }
 
 /// Response type for listing spaces
@@ -87,6 +170,17 @@ trait Confluence {
     /// List all Confluence spaces (paginated under the hood).
     async fn list_spaces(&self) -> Result<Vec<SpaceSummary>, String>;
 
+    /// Create a space with the given key (uppercase letters and digits) and name.
+    async fn create_space(
+        &self,
+        key: String,
+        name: String,
+        description: Option<String>,
+    ) -> Result<SpaceSummary, String>;
+
+    /// Archive a space by ID. Archived spaces stay readable but drop out of search and navigation.
+    async fn archive_space(&self, space_id: u64) -> Result<SpaceSummary, String>;
+
     /// Create a page in a space by numeric `space_id` using Atlas Doc Format (ADF).
     async fn create_page_by_space_id(
         &self,
@@ -95,6 +189,24 @@ trait Confluence {
         content: String,
     ) -> Result<CreatePageResponse, String>;
 
+    /// Create a page from a Confluence content template, filling `{{key}}` placeholders from the
+    /// `substitutions` JSON object.
+    async fn create_page_from_template(
+        &self,
+        space_id: u64,
+        template_id: String,
+        title: String,
+        substitutions: String,
+    ) -> Result<CreatePageResponse, String>;
+
+    /// Save a page's storage-format body as a page template in a space, returning the template ID.
+    async fn save_page_as_template(
+        &self,
+        page_id: u64,
+        template_name: String,
+        space_id: u64,
+    ) -> Result<String, String>;
+
     /// Create a page with a table under a **parent page** identified by name, resolving `space_name` and `parent_page_name`.
     async fn create_page_with_table_by_space_name_with_parent_page(
         &self,
@@ -131,6 +243,17 @@ trait Confluence {
         rows: String,
     ) -> Result<CreatePageResponse, String>;
 
+    /// Create a page with a table whose columns have their own alignment and width, under an optional parent page.
+    async fn create_page_with_rich_table(
+        &self,
+        space_name: String,
+        title: String,
+        parent_page_name: Option<String>,
+        headers: String,
+        rows: String,
+        columns: String,
+    ) -> Result<CreatePageResponse, String>;
+
     /// Append text content to a page by numeric ID (storage representation).
     async fn append_to_page_by_id(
         &self,
@@ -138,6 +261,13 @@ trait Confluence {
         content: String,
     ) -> Result<ContentDetails, String>;
 
+    /// Append text content to a page unless the same text is already on it.
+    async fn append_if_absent(
+        &self,
+        page_id: u64,
+        content: String,
+    ) -> Result<ContentDetails, String>;
+
     /// Upload an IMFS file and append a **table** to a page resolved by name and space.
     async fn import_file_and_append_table_to_page_by_page_name(
         &self,
@@ -189,8 +319,30 @@ trait Confluence {
         content: String,
     ) -> Result<CreatePageResponse, String>;
 
+    /// Create many pages in a space, pausing (and reporting where to resume) on a rate limit.
+    async fn create_pages_bulk(
+        &self,
+        space_id: u64,
+        pages: Vec<BulkPageInput>,
+        start_index: Option<u32>,
+    ) -> Result<BulkImportReport, String>;
+
     /// Get page details by numeric ID. Body format can be requested via query.
-    async fn get_page_by_id(&self, page_id: u64) -> Result<ContentDetails, String>;
+    /// With `include_child_count`, the number of direct child pages is filled in as well, and
+    /// `include_labels` / `include_restrictions` nest the page's labels and restrictions.
+    async fn get_page_by_id(
+        &self,
+        page_id: u64,
+        include_child_count: bool,
+        include_labels: Option<bool>,
+        include_restrictions: Option<bool>,
+    ) -> Result<ContentDetails, String>;
+
+    /// Get a page's body as Markdown, converted from its storage format.
+    async fn get_page_as_markdown(&self, page_id: u64) -> Result<String, String>;
+
+    /// Number of times a page has been viewed, optionally only since `from` (`yyyy-mm-dd`).
+    async fn get_page_views(&self, page_id: u64, from: Option<String>) -> Result<u64, String>;
 
     /// Get page details by page title and space name.
     async fn get_page_by_name(
@@ -217,9 +369,59 @@ trait Confluence {
         new_content: String,
     ) -> Result<ContentDetails, String>;
 
+    /// List every version of a page, oldest first.
+    async fn list_page_versions(&self, page_id: u64) -> Result<Vec<PageVersionInfo>, String>;
+
+    /// Republish an earlier version of a page as its new current version.
+    async fn restore_page_version(
+        &self,
+        page_id: u64,
+        version_number: u32,
+    ) -> Result<ContentDetails, String>;
+
     /// Delete a page by ID.
     async fn delete_page(&self, page_id: u64) -> Result<DeleteResult, String>;
 
+    /// Move a page `before`/`after` a sibling, or `append` it as the last child of its parent.
+    async fn reorder_page(
+        &self,
+        page_id: u64,
+        position: String,
+        target_sibling_id: Option<u64>,
+    ) -> Result<(), String>;
+
+    /// Make `new_parent_id` the parent of a page.
+    async fn move_page(&self, page_id: u64, new_parent_id: u64) -> Result<ContentDetails, String>;
+
+    /// Duplicate a page under a new title, in its own space or in `target_space_id`.
+    async fn copy_page(
+        &self,
+        page_id: u64,
+        new_title: String,
+        target_space_id: Option<u64>,
+    ) -> Result<CreatePageResponse, String>;
+
+    /// Move each of `page_ids` under `new_parent_id`, reporting every page's outcome.
+    async fn move_pages_bulk(
+        &self,
+        page_ids: Vec<u64>,
+        new_parent_id: u64,
+    ) -> Result<Vec<PageMoveResult>, String>;
+
+    /// Watch a page as the configured user; a no-op if already watching.
+    async fn watch_page(&self, page_id: u64) -> Result<WatchStatus, String>;
+
+    /// Stop watching a page as the configured user; a no-op if not watching.
+    async fn unwatch_page(&self, page_id: u64) -> Result<WatchStatus, String>;
+
+    /// Restrict updates of a page to the configured user, remembering who could update it
+    /// before; read restrictions are kept.
+    async fn lock_page_for_editing(&self, page_id: u64) -> Result<PageRestrictions, String>;
+
+    /// Undo `lock_page_for_editing`, putting back the update restrictions the page had before
+    /// or, if those are unknown, clearing them so updates are inherited again.
+    async fn unlock_page(&self, page_id: u64) -> Result<PageRestrictions, String>;
+
     /// Create a blog post by space ID (ADF paragraph content).
     async fn create_blog_post_by_space_id(
         &self,
@@ -291,6 +493,12 @@ trait Confluence {
         space_name: String,
     ) -> Result<Vec<Label>, String>;
 
+    /// Add a global label to a page by page ID.
+    async fn add_page_label(&self, page_id: u64, label: String) -> Result<Label, String>;
+
+    /// Remove a label from a page by page ID.
+    async fn remove_page_label(&self, page_id: u64, label: String) -> Result<DeleteResult, String>;
+
     /// List labels in a space by ID.
     async fn list_space_labels_by_id(&self, space_id: u64) -> Result<Vec<Label>, String>;
 
@@ -307,6 +515,31 @@ trait Confluence {
         space_name: String,
     ) -> Result<Vec<Label>, String>;
 
+    /// Attach an IMFS file to a page.
+    async fn upload_attachment(
+        &self,
+        page_id: u64,
+        file_name: String,
+        content_type: String,
+        file_descriptor: String,
+    ) -> Result<AttachmentInfo, String>;
+
+    /// List the files attached to a page.
+    async fn list_attachments(&self, page_id: u64) -> Result<Vec<AttachmentInfo>, String>;
+
+    /// Write a page's body and attachments into an IMFS folder, with a manifest of its contents.
+    async fn export_page_bundle_to_imfs(
+        &self,
+        page_id: u64,
+        bundle_name: String,
+    ) -> Result<String, String>;
+
+    /// React to a page, blog post or comment with an emoji shortname.
+    async fn add_reaction(&self, content_id: u64, emoji: String) -> Result<(), String>;
+
+    /// Take back the configured user's emoji reaction on a page, blog post or comment.
+    async fn remove_reaction(&self, content_id: u64, emoji: String) -> Result<(), String>;
+
     /// Create a **footer** comment on a page by page ID (string form).
     async fn create_footer_comment_on_page_by_page_id(
         &self,
@@ -360,6 +593,22 @@ trait Confluence {
         space_name: String,
     ) -> Result<Vec<CommentDetails>, String>;
 
+    /// Create an **inline** comment on a page, anchored to an occurrence of `text_selection`.
+    async fn create_inline_comment(
+        &self,
+        page_id: u64,
+        content: String,
+        text_selection: String,
+        text_selection_match_count: u32,
+        text_selection_match_index: u32,
+    ) -> Result<CommentDetails, String>;
+
+    /// List **inline** comments on a page by ID.
+    async fn list_page_inline_comments_by_id(
+        &self,
+        page_id: u64,
+    ) -> Result<Vec<CommentDetails>, String>;
+
     /// Update a comment by ID (storage representation).
     async fn update_comment(
         &self,
@@ -386,6 +635,9 @@ trait Confluence {
     /// Get the children of a comment (thread replies).
     async fn get_comment_children(&self, comment_id: u64) -> Result<Vec<CommentDetails>, String>;
 
+    /// Export a page's full footer comment thread (replies included) as a nested markdown list.
+    async fn export_comments_markdown(&self, page_id: u64) -> Result<String, String>;
+
     /// List permissions configured on a given space.
     async fn list_space_permissions(&self, space_id: u64) -> Result<Vec<SpacePermission>, String>;
 
@@ -398,6 +650,9 @@ trait Confluence {
         page_id: u64,
     ) -> Result<PageHierarchyResponse, String>;
 
+    /// Export a page and its descendant pages as plain text under breadcrumb headers.
+    async fn export_subtree_text(&self, page_id: u64, max_pages: u32) -> Result<String, String>;
+
     /// Get ancestors of a page by ID.
     async fn get_page_ancestors_by_page_id(
         &self,
@@ -427,11 +682,46 @@ trait Confluence {
         space_name: String,
     ) -> Result<Vec<ContentDetails>, String>;
 
+    // --- Search ---
+
+    /// Full-text search across several spaces, returning hits ranked by relevance.
+    async fn search_spaces(
+        &self,
+        text: String,
+        space_keys: Vec<String>,
+        limit: u32,
+    ) -> Result<Vec<SearchHit>, String>;
+
+    /// Search content with a CQL query, following result pages up to `limit` matches.
+    async fn search_content(
+        &self,
+        cql: String,
+        limit: Option<u32>,
+    ) -> Result<Vec<SearchResult>, String>;
+
+    /// Add `labels` to each page matched by `cql`, up to `max` pages.
+    async fn label_search_results(
+        &self,
+        cql: String,
+        labels: Vec<String>,
+        max: u32,
+    ) -> Result<BulkResult<u64>, String>;
+
+    /// Pages in a space modified at or after `since`, oldest change first.
+    async fn pages_changed_since(
+        &self,
+        space_id: u64,
+        since: String,
+    ) -> Result<Vec<ContentDetails>, String>;
+
     /// JSON schema of callable tools for LLM function-calling.
     fn tools(&self) -> String;
 
     /// Optional prompt pack (currently empty placeholder).
     fn prompts(&self) -> String;
+
+    /// Remediation hint for an error message returned by one of the tools.
+    fn diagnose(&self, error_message: String) -> String;
 }
 
 /// Parse a `SpaceSummary` from a raw JSON value returned by Confluence.
@@ -474,12 +764,104 @@ async fn get_imfs_file_content(file_descriptor: String) -> Result<String, String
     Ok(file_content)
 }
 
+/// Write `content` to `filepath` through the IMFS contract, returning the new file's descriptor.
+async fn write_imfs_file(filepath: String, content: String) -> Result<String, String> {
+    // parameter definition for the cross contract call to imfs
+    #[derive(Serialize, Deserialize)]
+    struct Args {
+        filepath: String,
+        content: String,
+    }
+
+    let args = Args { filepath, content };
+
+    // SAFETY: `imfs` is a systemic applet
+    let contract_addr = Runtime::contract_id_for_name("imfs").unwrap();
+
+    Runtime::call_contract::<String>(
+        contract_addr,
+        "write".to_string(),
+        Some(serde_json::to_string(&args).unwrap()),
+    )
+    .map_err(|err| err.to_string())
+}
+
 /// Persistent contract state for the Confluence MCP server.
 ///
 /// Holds `Secrets<ConfluenceConfig>` which encapsulate credentials and base URL.
 #[derive(Serialize, Deserialize, WeilType)]
 pub struct ConfluenceContractState {
     secrets: Secrets<ConfluenceConfig>,
+    /// GET responses already read during the current call; never persisted.
+    #[serde(skip)]
+    responses: ResponseCache,
+}
+
+/// GET responses memoized for the rest of the current call, keyed by method, endpoint and query.
+///
+/// Contract state is loaded afresh for every call and this field isn't persisted, so an entry
+/// never outlives the tool call that read it: it only saves reading the same page (or the same
+/// page of a listing) again within one call, e.g. while resolving several names. Writes evict
+/// what they may have made stale.
+#[derive(Serialize, Deserialize, Default, Debug)]
+struct ResponseCache(RefCell<BTreeMap<String, (u16, String)>>);
+
+impl WeilType for ResponseCache {}
+
+impl ResponseCache {
+    /// Key for a request; the query is part of it, so each page of a listing is its own entry.
+    fn key(method: HttpMethod, endpoint: &str, query_params: &[(String, String)]) -> String {
+        let query: Vec<String> = query_params
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        format!("{} {}?{}", method.to_string(), endpoint, query.join("&"))
+    }
+
+    fn get(&self, key: &str) -> Option<(u16, String)> {
+        self.0.borrow().get(key).cloned()
+    }
+
+    fn insert(&self, key: String, status: u16, body: String) {
+        self.0.borrow_mut().insert(key, (status, body));
+    }
+
+    /// Drop what a write to `endpoint` may have made stale: the entries of the page or
+    /// content item it names, or everything when it names none (creating a page changes
+    /// listings, for instance).
+    fn evict_for_write(&self, endpoint: &str) {
+        let mut cache = self.0.borrow_mut();
+        match written_content_id(endpoint) {
+            Some(id) => cache.retain(|key, _| !endpoint_mentions_content(key, id)),
+            None => cache.clear(),
+        }
+    }
+}
+
+/// The path segments of an endpoint, without its query string.
+fn endpoint_segments(endpoint: &str) -> impl Iterator<Item = &str> {
+    let path = endpoint.split(['?', ' ']).next().unwrap_or_default();
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+/// Id of the page (v2 `pages/{id}`) or content item (v1 `content/{id}`) an endpoint writes to.
+fn written_content_id(endpoint: &str) -> Option<&str> {
+    let mut segments = endpoint_segments(endpoint);
+    while let Some(segment) = segments.next() {
+        if segment == "pages" || segment == "content" {
+            return segments
+                .next()
+                .filter(|id| id.chars().all(|c| c.is_ascii_digit()));
+        }
+    }
+    None
+}
+
+/// Whether a cache key's endpoint is about the page or content item `id`.
+fn endpoint_mentions_content(key: &str, id: &str) -> bool {
+    // Keys start with the method, then the endpoint
+    let endpoint = key.split_once(' ').map_or(key, |(_, endpoint)| endpoint);
+    written_content_id(endpoint) == Some(id)
 }
 
 /// Convert a Confluence timestamp string (`TIMESTAMP_NTX`) to a shorter, readable form.
@@ -497,14 +879,28 @@ fn create_paragraph_node(content: String) -> Node {
             text: Some(content),
             r#type: TEXT.to_string(),
         }]),
+        marks: None,
     }
 }
 
 impl ConfluenceContractState {
+    /// Headers for a request with the configured credentials.
+    fn request_headers(&self) -> HashMap<String, String> {
+        request_headers(&self.secrets.config())
+    }
+
+    /// Refuse to run a tool listed in `disabled_tools` in the config. This is the contract's
+    /// `tool_gate`, so every exported method goes through it before doing anything else.
+    fn ensure_tool_enabled(&self, tool: &str) -> Result<(), String> {
+        check_tool_enabled(&self.secrets.config().disabled_tools, tool)
+    }
+
     /// Make an authenticated HTTP request to Confluence REST v2 with optional query/body.
     ///
     /// Returns `(status_code, body_text)` or an error string if the status doesn't match
     /// `expected_status_code`.
+    ///
+    /// A GET already answered during this call is served from the response cache.
     async fn make_request(
         &self,
         method: HttpMethod,
@@ -513,26 +909,77 @@ impl ConfluenceContractState {
         body: Option<String>,
         expected_status_code: u16,
     ) -> Result<(u16, String), String> {
-        let url = format!(
-            "{}/wiki/api/v2/{}",
-            self.secrets.config().confluence_url,
-            endpoint
-        );
+        let cache_key = match method {
+            HttpMethod::Get => Some(ResponseCache::key(method, endpoint, &query_params)),
+            _ => None,
+        };
+        if let Some(response) = cache_key.as_deref().and_then(|key| self.responses.get(key)) {
+            return Ok(response);
+        }
 
-        let headers = HashMap::from([
-            ("Content-Type".to_string(), "application/json".to_string()),
-            (
-                "Authorization".to_string(),
-                format!(
-                    "Basic {}",
-                    BASE64_STANDARD.encode(format!(
-                        "{}:{}",
-                        self.secrets.config().email,
-                        self.secrets.config().api_key
-                    ))
-                ),
-            ),
-        ]);
+        let (status, text) = self.send_request(method, endpoint, query_params, body)?;
+
+        if status != expected_status_code {
+            return Err(http_error(status, &text));
+        }
+
+        if let Some(key) = cache_key {
+            self.responses.insert(key, status, text.clone());
+        }
+        Ok((status, text))
+    }
+
+    /// Like `make_request`, but against the v1 REST API, for operations v2 doesn't offer
+    /// (e.g. adding or removing labels).
+    async fn make_v1_request(
+        &self,
+        method: HttpMethod,
+        endpoint: &str,
+        query_params: Vec<(String, String)>,
+        body: Option<String>,
+        expected_status_code: u16,
+    ) -> Result<(u16, String), String> {
+        let (status, text) = self.send_request_to(REST_V1, method, endpoint, query_params, body)?;
+
+        if status != expected_status_code {
+            return Err(http_error(status, &text));
+        }
+
+        Ok((status, text))
+    }
+
+    /// Send an authenticated request to Confluence REST v2 and return `(status_code, body_text)`
+    /// whatever the status, leaving its interpretation to the caller.
+    fn send_request(
+        &self,
+        method: HttpMethod,
+        endpoint: &str,
+        query_params: Vec<(String, String)>,
+        body: Option<String>,
+    ) -> Result<(u16, String), String> {
+        self.send_request_to(REST_V2, method, endpoint, query_params, body)
+    }
+
+    /// Send an authenticated request under `api_root` (`REST_V2` or `REST_V1`).
+    ///
+    /// A few operations (e.g. archiving a space) only exist in the v1 REST API.
+    fn send_request_to(
+        &self,
+        api_root: &str,
+        method: HttpMethod,
+        endpoint: &str,
+        query_params: Vec<(String, String)>,
+        body: Option<String>,
+    ) -> Result<(u16, String), String> {
+        let config = self.secrets.config();
+        config.validate()?;
+        let url = format!("{}/{}/{}", config.confluence_url, api_root, endpoint);
+
+        if !matches!(method, HttpMethod::Get | HttpMethod::Head) {
+            self.responses.evict_for_write(endpoint);
+        }
+
+        let headers = self.request_headers();
 
         let mut request = HttpClient::request(&url, method)
             .headers(headers)
@@ -543,19 +990,13 @@ impl ConfluenceContractState {
         }
 
         let response = request.send().map_err(|err| err.to_string())?;
-        let status = response.status();
-        let text = response.text();
-
-        if status != expected_status_code {
-            return Err(format!("HTTP {}: {}", status, text));
-        }
-
-        Ok((status, text))
+        Ok((response.status(), response.text()))
     }
 
     /// Drain all pages of a paginated `ListResponse<T>` by following `links.next`.
     ///
-    /// Returns a single concatenated `Vec<T>`.
+    /// Returns a single concatenated `Vec<T>`, or a `PaginationLimitExceeded` error if the
+    /// listing runs past the shared `PaginationLimits`.
     async fn process_complete_response<T>(
         &self,
         list_response: ListResponse<T>,
@@ -563,39 +1004,22 @@ impl ConfluenceContractState {
     where
         T: for<'de> Deserialize<'de>,
     {
-        let mut vec_list = list_response.results;
-        self.get_complete_response(&mut vec_list, list_response.links.next)?;
-
-        Ok(vec_list)
+        drain_pages(
+            list_response.results,
+            list_response.links.next,
+            PaginationLimits::default(),
+            |next_url| self.get_next_page(&next_url),
+        )
+        .map_err(|err| err.to_string())
     }
 
-    /// Internal recursion that walks `links.next` until exhausted, pushing into `vec_items`.
-    fn get_complete_response<T>(
-        &self,
-        vec_items: &mut Vec<T>,
-        next_url: Option<String>,
-    ) -> Result<(), String>
+    /// Fetch the page behind a `links.next` URL, returning its items and the following link.
+    fn get_next_page<T>(&self, next_url: &str) -> Result<(Vec<T>, Option<String>), String>
     where
         T: for<'de> Deserialize<'de>,
     {
-        let Some(next_url) = next_url else {
-            return Ok(());
-        };
         let url = format!("{}/{}", self.secrets.config().confluence_url, next_url);
-        let headers = HashMap::from([
-            ("Content-Type".to_string(), "application/json".to_string()),
-            (
-                "Authorization".to_string(),
-                format!(
-                    "Basic {}",
-                    BASE64_STANDARD.encode(format!(
-                        "{}:{}",
-                        self.secrets.config().email,
-                        self.secrets.config().api_key
-                    ))
-                ),
-            ),
-        ]);
+        let headers = self.request_headers();
 
         let request = HttpClient::request(&url, HttpMethod::Get).headers(headers);
         let response = request.send().map_err(|err| err.to_string())?;
@@ -607,70 +1031,244 @@ impl ConfluenceContractState {
         let list_response: ListResponse<T> =
             serde_json::from_str(&response_text).map_err(|err| err.to_string())?;
 
-        let next_page_url = list_response.links.next;
-        list_response
-            .results
-            .into_iter()
-            .for_each(|item| vec_items.push(item));
+        Ok((list_response.results, list_response.links.next))
+    }
 
-        if next_page_url.is_some() {
-            self.get_complete_response(vec_items, next_page_url)?;
+    /// Add labels to a piece of content in one v1 call; `body` comes from `label_request_body`.
+    fn add_labels(&self, content_id: u64, body: &str) -> Result<(), String> {
+        let endpoint = format!("content/{}/label", content_id);
+        let (status, response) = self.send_request_to(
+            REST_V1,
+            HttpMethod::Post,
+            &endpoint,
+            vec![],
+            Some(body.to_string()),
+        )?;
+        if status != 200 {
+            return Err(http_error(status, &response));
         }
         Ok(())
     }
 
-    /// Resolve a human space name to its numeric `space_id`.
-    async fn get_space_id_from_name(&self, space_name: String) -> Result<u64, String> {
-        let space_list = self.list_spaces().await?;
-        let space = space_list
-            .into_iter()
-            .find(|s| s.name == space_name)
-            .ok_or_else(|| "Space not found".to_string())?;
-        Ok(space.id.parse::<u64>().unwrap())
-    }
-
-    /// Resolve page ID from `page_name` scoped to `space_name`.
+    /// Count the direct child pages of a page without fetching them.
     ///
-    /// If multiple pages share the title within a space, this returns one arbitrarily (as per API).
-    async fn get_page_id_from_name(
-        &self,
-        page_name: String,
-        space_name: String,
-    ) -> Result<u64, String> {
-        let space_id = self.get_space_id_from_name(space_name.clone()).await?;
+    /// The v2 children endpoints don't report totals, so this runs a one-result CQL search
+    /// and reads its `totalSize`.
+    fn count_child_pages(&self, page_id: u64) -> Result<u32, String> {
         let query_params = vec![
-            ("title".to_string(), page_name.clone()),
-            ("space-id".to_string(), space_id.to_string()),
+            (
+                "cql".to_string(),
+                format!("type=page AND parent={}", page_id),
+            ),
+            (LIMIT.to_string(), "1".to_string()),
         ];
-        let endpoint = format!("spaces/{}/pages", space_id);
-        let response = self
-            .make_request(HttpMethod::Get, &endpoint, query_params, None, 200)
-            .await
-            .map_err(|err| err.to_string())?
-            .1;
+        let (status, response) =
+            self.send_request_to(REST_V1, HttpMethod::Get, "search", query_params, None)?;
+        if status != 200 {
+            return Err(http_error(status, &response));
+        }
+        parse_search_total_size(&response)
+    }
 
-        let page_list: ListResponse<ContentDetails> =
-            serde_json::from_str(&response).map_err(|err| err.to_string())?;
-        let page = page_list
-            .results
-            .iter()
-            .find(|s| s.title == page_name)
-            .ok_or_else(|| {
-                format!(
-                    "page not found for the given page_name: {page_name} and space : {space_name}"
-                )
-            })?;
+    /// Bring the configured user's watch on a page to `watch`, checking the current state first.
+    fn set_page_watch(&self, page_id: u64, watch: bool) -> Result<WatchStatus, String> {
+        let endpoint = format!("user/watch/content/{}", page_id);
+        apply_watch(
+            page_id,
+            watch,
+            || self.send_request_to(REST_V1, HttpMethod::Get, &endpoint, vec![], None),
+            |method| self.send_request_to(REST_V1, method, &endpoint, vec![], None),
+        )
+    }
 
-        Ok(page.id.parse::<u64>().unwrap())
+    /// Append `page_id` as the last child of `new_parent_id` and return the moved page.
+    ///
+    /// `parent_ancestor_ids` are the ancestors of the new parent; the move is refused when the
+    /// page is one of them, since it would end up beneath itself.
+    async fn move_page_under(
+        &self,
+        page_id: u64,
+        new_parent_id: u64,
+        parent_ancestor_ids: &[u64],
+    ) -> Result<ContentDetails, String> {
+        check_move_cycle(page_id, new_parent_id, parent_ancestor_ids)?;
+        let endpoint = move_page_endpoint(page_id, "append", Some(new_parent_id), None)?;
+        let (status, response) =
+            self.send_request_to(REST_V1, HttpMethod::Put, &endpoint, vec![], None)?;
+        if status != 200 {
+            return Err(http_error(status, &response));
+        }
+        self.get_page_by_id(page_id, false, None, None).await
     }
 
-    /// Resolve blog post ID from `blog_post_name` scoped to `space_name`.
-    async fn get_blog_post_id_from_name(
+    /// Read and update restrictions set on a page (v1 restrictions API).
+    fn get_page_restrictions(&self, page_id: u64) -> Result<PageRestrictions, String> {
+        let endpoint = format!("content/{}/restriction/byOperation", page_id);
+        let query_params = vec![(
+            "expand".to_string(),
+            "restrictions.user,restrictions.group".to_string(),
+        )];
+        let (status, response) =
+            self.send_request_to(REST_V1, HttpMethod::Get, &endpoint, query_params, None)?;
+        if status != 200 {
+            return Err(http_error(status, &response));
+        }
+        parse_page_restrictions(&response)
+    }
+
+    /// Replace every restriction on a page with `restrictions`. The PUT needs at least one
+    /// operation, so removing them all is a DELETE instead.
+    fn set_page_restrictions(
         &self,
-        blog_post_name: String,
-        space_name: String,
-    ) -> Result<u64, String> {
-        let space_id = self.get_space_id_from_name(space_name.clone()).await?;
+        page_id: u64,
+        restrictions: &PageRestrictions,
+    ) -> Result<(), String> {
+        let endpoint = format!("content/{}/restriction", page_id);
+        let body = restrictions_request_body(restrictions);
+        let (status, response) = if body.is_empty() {
+            self.send_request_to(REST_V1, HttpMethod::Delete, &endpoint, vec![], None)?
+        } else {
+            let body = Value::Array(body).to_string();
+            self.send_request_to(REST_V1, HttpMethod::Put, &endpoint, vec![], Some(body))?
+        };
+        if status != 200 && status != 204 {
+            return Err(http_error(status, &response));
+        }
+        Ok(())
+    }
+
+    /// Account ID of the configured user.
+    fn current_account_id(&self) -> Result<String, String> {
+        let (status, response) =
+            self.send_request_to(REST_V1, HttpMethod::Get, "user/current", vec![], None)?;
+        if status != 200 {
+            return Err(http_error(status, &response));
+        }
+        serde_json::from_str::<Value>(&response).map_err(|e| e.to_string())?["accountId"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| format!("Unexpected current user response: {}", response))
+    }
+
+    /// The update restrictions a locked page had before `lock_page_for_editing`, or `None`
+    /// when the page is not locked.
+    fn edit_lock_prior(&self, page_id: u64) -> Result<Option<OperationRestrictions>, String> {
+        let endpoint = format!("content/{}/property/{}", page_id, EDIT_LOCK_PROPERTY);
+        let (status, response) =
+            self.send_request_to(REST_V1, HttpMethod::Get, &endpoint, vec![], None)?;
+        match status {
+            200 => {
+                let property: Value = serde_json::from_str(&response).map_err(|e| e.to_string())?;
+                serde_json::from_value(property["value"].clone())
+                    .map(Some)
+                    .map_err(|e| format!("Unexpected {} property: {}", EDIT_LOCK_PROPERTY, e))
+            }
+            404 => Ok(None),
+            _ => Err(http_error(status, &response)),
+        }
+    }
+
+    /// Keep `prior` on the page itself, so the lock survives the contract state.
+    fn store_edit_lock_prior(
+        &self,
+        page_id: u64,
+        prior: &OperationRestrictions,
+    ) -> Result<(), String> {
+        let endpoint = format!("content/{}/property", page_id);
+        let body = json!({ "key": EDIT_LOCK_PROPERTY, "value": prior }).to_string();
+        let (status, response) =
+            self.send_request_to(REST_V1, HttpMethod::Post, &endpoint, vec![], Some(body))?;
+        if status != 200 {
+            return Err(http_error(status, &response));
+        }
+        Ok(())
+    }
+
+    /// Download an attachment's content through its `download_link`.
+    fn download_attachment(&self, attachment: &AttachmentInfo) -> Result<String, String> {
+        let link = attachment
+            .download_link
+            .as_deref()
+            .ok_or_else(|| format!("attachment {} has no download link", attachment.id))?;
+        // Download links are relative to the site's `/wiki` base.
+        let (status, response) = self.send_request_to(
+            "wiki",
+            HttpMethod::Get,
+            link.trim_start_matches('/'),
+            vec![],
+            None,
+        )?;
+        if status != 200 {
+            return Err(http_error(status, &response));
+        }
+        Ok(response)
+    }
+
+    /// Look up what a reaction on `content_id` attaches to, via the v1 content endpoint
+    /// (the only one that reports a comment's container).
+    async fn reaction_target(&self, content_id: u64) -> Result<ReactionTarget, String> {
+        let endpoint = format!("content/{}", content_id);
+        let response = self
+            .make_v1_request(
+                HttpMethod::Get,
+                &endpoint,
+                vec![("expand".to_string(), "container".to_string())],
+                None,
+                200,
+            )
+            .await?
+            .1;
+        let content: Value = serde_json::from_str(&response).map_err(|err| err.to_string())?;
+        reaction_target(&content)
+    }
+
+    /// Resolve a human space name to its numeric `space_id`.
+    ///
+    /// Names are matched against `list_spaces`, whose pages the response cache keeps for the rest
+    /// of the call, so resolving several names in one call lists the spaces only once.
+    async fn get_space_id_from_name(&self, space_name: String) -> Result<u64, String> {
+        let space_list = self.list_spaces().await?;
+        let space = space_list
+            .into_iter()
+            .find(|s| s.name == space_name)
+            .ok_or_else(|| "Space not found".to_string())?;
+        parse_id(&space.id)
+    }
+
+    /// Resolve page ID from `page_name` scoped to `space_name`.
+    ///
+    /// Every page of results is read, and a title shared by several pages is an error listing
+    /// their IDs rather than an arbitrary pick.
+    async fn get_page_id_from_name(
+        &self,
+        page_name: String,
+        space_name: String,
+    ) -> Result<u64, String> {
+        let space_id = self.get_space_id_from_name(space_name.clone()).await?;
+        let query_params = vec![
+            ("title".to_string(), page_name.clone()),
+            ("space-id".to_string(), space_id.to_string()),
+        ];
+        let endpoint = format!("spaces/{}/pages", space_id);
+        let response = self
+            .make_request(HttpMethod::Get, &endpoint, query_params, None, 200)
+            .await
+            .map_err(|err| err.to_string())?
+            .1;
+
+        let page_list: ListResponse<ContentDetails> =
+            serde_json::from_str(&response).map_err(|err| err.to_string())?;
+        let pages = self.process_complete_response(page_list).await?;
+        unique_page_id(&pages, &page_name, &space_name)
+    }
+
+    /// Resolve blog post ID from `blog_post_name` scoped to `space_name`.
+    async fn get_blog_post_id_from_name(
+        &self,
+        blog_post_name: String,
+        space_name: String,
+    ) -> Result<u64, String> {
+        let space_id = self.get_space_id_from_name(space_name.clone()).await?;
         let query_params = vec![
             ("title".to_string(), blog_post_name.clone()),
             ("space-id".to_string(), space_id.to_string()),
@@ -693,7 +1291,7 @@ impl ConfluenceContractState {
                 )
             })?;
 
-        Ok(blog_post.id.parse::<u64>().unwrap())
+        parse_id(&blog_post.id)
     }
 
     /// Fetch page details with body returned in **Atlas Doc Format (ADF)**.
@@ -715,16 +1313,7 @@ impl ConfluenceContractState {
         parent_id: Option<u64>,
         document: Document,
     ) -> Result<responses::CreatePageResponse, String> {
-        let req_body = types::CreateContentRequest {
-            space_id,
-            title: &title,
-            parent_id,
-            body: types::AtlasDocFormatBodyStr {
-                value: serde_json::to_string(&document).map_err(|err| err.to_string())?,
-                representation: ATLAS_DOC_FORMAT.to_string(),
-            },
-        };
-        let body = serde_json::to_string(&req_body).map_err(|e| e.to_string())?;
+        let body = create_page_request_body(space_id, &title, parent_id, &document)?;
         let response = self
             .make_request(HttpMethod::Post, "/pages", vec![], Some(body), 200)
             .await?
@@ -732,14 +1321,7 @@ impl ConfluenceContractState {
         let create_content_response: CreateContentDetails =
             serde_json::from_str(&response).map_err(|e| e.to_string())?;
 
-        Ok(responses::CreatePageResponse {
-            page_id: create_content_response.id,
-            title: create_content_response.title,
-            status: create_content_response.status,
-            space_id: create_content_response.space_id,
-            created_at: create_content_response.created_at,
-            parent_type: create_content_response.parent_type,
-        })
+        Ok(page_created(create_content_response))
     }
 
     /// Convenience to build a simple ADF with a leading paragraph and a generated table,
@@ -753,7 +1335,18 @@ impl ConfluenceContractState {
         rows: String,
     ) -> Result<CreatePageResponse, String> {
         let table = deserialize_and_create_table_node(headers, rows)?;
+        self.create_table_page(space_id, title, parent_id, table)
+            .await
+    }
 
+    /// Create a page holding a leading paragraph and `table`.
+    async fn create_table_page(
+        &self,
+        space_id: u64,
+        title: String,
+        parent_id: Option<u64>,
+        table: Node,
+    ) -> Result<CreatePageResponse, String> {
         let adf_doc = Document {
             version: Some(1),
             r#type: "doc".into(),
@@ -763,6 +1356,7 @@ impl ConfluenceContractState {
                         r#type: "text".into(),
                         text: Some("Table generated from Icarus: ".into()),
                     }]),
+                    marks: None,
                 },
                 table,
             ],
@@ -772,1269 +1366,4643 @@ impl ConfluenceContractState {
     }
 }
 
-/// Pull a `storage`-format body value from a `ContentDetails` body, or empty string if missing.
-fn get_content_from_body(body: Option<Body>) -> String {
-    let body_str = match body {
-        None => String::new(),
-        Some(body) => match body.storage {
-            None => String::new(),
-            Some(storage_body) => storage_body.value,
-        },
+/// Positions accepted by the v1 content move endpoint.
+const MOVE_POSITIONS: [&str; 3] = ["append", "before", "after"];
+
+/// v1 endpoint that moves `page_id` to `position` relative to `target_id`.
+///
+/// `before`/`after` need the sibling to place the page next to. For `append` the target is the
+/// parent the page becomes the last child of, which `parent_id` supplies when no target is given.
+fn move_page_endpoint(
+    page_id: u64,
+    position: &str,
+    target_id: Option<u64>,
+    parent_id: Option<u64>,
+) -> Result<String, String> {
+    if !MOVE_POSITIONS.contains(&position) {
+        return Err(format!(
+            "Invalid position '{}', expected one of: {}",
+            position,
+            MOVE_POSITIONS.join(", ")
+        ));
+    }
+    let target_id = match (position, target_id) {
+        (_, Some(target_id)) => target_id,
+        ("append", None) => {
+            parent_id.ok_or_else(|| format!("Page {} has no parent to append under", page_id))?
+        }
+        (_, None) => {
+            return Err(format!(
+                "target_sibling_id is required for position '{}'",
+                position
+            ));
+        }
     };
-    body_str
+    if target_id == page_id {
+        return Err("A page can't be moved relative to itself".to_string());
+    }
+    Ok(format!(
+        "content/{}/move/{}/{}",
+        page_id, position, target_id
+    ))
 }
 
-/// Deserialize headers/rows JSON strings and produce an ADF `Node::Table`.
-fn deserialize_and_create_table_node(headers: String, rows: String) -> Result<Node, String> {
-    let headers_parsed: Vec<String> = serde_json::from_str(&headers)
-        .map_err(|err| format!("invalid header passed , {:?}", err))?;
+/// Refuse to put `page_id` under `new_parent_id` when the parent is the page itself or lies
+/// beneath it, i.e. the page appears among the parent's ancestors.
+fn check_move_cycle(
+    page_id: u64,
+    new_parent_id: u64,
+    parent_ancestor_ids: &[u64],
+) -> Result<(), String> {
+    if page_id == new_parent_id || parent_ancestor_ids.contains(&page_id) {
+        return Err(move_cycle_error(page_id, new_parent_id));
+    }
+    Ok(())
+}
 
-    let rows_parsed: Vec<Vec<Value>> =
-        serde_json::from_str(&rows).map_err(|err| format!("invalid rows passed : {:?}", err))?;
+/// Same check as `check_move_cycle`, made from the other end: the new parent must not be the
+/// page itself or among `page_descendant_ids`.
+fn check_move_into_subtree(
+    page_id: u64,
+    new_parent_id: u64,
+    page_descendant_ids: &[u64],
+) -> Result<(), String> {
+    if page_id == new_parent_id || page_descendant_ids.contains(&new_parent_id) {
+        return Err(move_cycle_error(page_id, new_parent_id));
+    }
+    Ok(())
+}
 
-    create_table_node(headers_parsed, rows_parsed)
+fn move_cycle_error(page_id: u64, new_parent_id: u64) -> String {
+    ErrorCode::Validation.tag(format!(
+        "page {} can't be moved under {}: the new parent is the page itself or one of its descendants",
+        page_id, new_parent_id
+    ))
 }
 
-/// Internal bag for parsed table data (headers + rows) coming from IMFS files.
-struct TableFields {
-    headers: Vec<String>,
-    rows: Vec<Vec<Value>>,
+/// Serialize the v2 page update that keeps `page` as is but hangs it under `new_parent_id`.
+fn move_page_request_body(page: &ContentDetails, new_parent_id: u64) -> Result<String, String> {
+    let storage = page
+        .body
+        .as_ref()
+        .and_then(|body| body.storage.as_ref())
+        .ok_or_else(|| format!("page {} has no storage-format body", page.id))?;
+    Ok(json!({
+        "id": page.id,
+        "status": "current",
+        "title": page.title,
+        "parentId": new_parent_id.to_string(),
+        "body": {
+            "representation": STORAGE,
+            "value": storage.value,
+        },
+        "version": {
+            "number": page.version.number + 1,
+        },
+    })
+    .to_string())
 }
 
-/// Fetch a file via IMFS and convert it into table headers/rows.
-///
-/// The file is expected to be a JSON array of serialized maps (one per row). Keys are used as
-/// headers; values become cells. Ordering is derived from `BTreeMap`'s sorted key iteration.
-async fn get_table_from_imfs_file(file_descriptor: String) -> Result<TableFields, String> {
-    let serialized_response = get_imfs_file_content(file_descriptor).await?;
-    let row_list: Vec<String> = serde_json::from_str(&serialized_response).map_err(|err| {
-        format!(
-            "invalid file response , deserialization failed at first try : {}",
-            err.to_string()
-        )
-    })?;
-    let mut col_row_map: Vec<BTreeMap<String, Value>> = Vec::new();
+/// Serialize the create-page payload for a copy of `source` (fetched with its ADF body).
+fn copy_page_request_body(
+    source: &ContentDetails,
+    new_title: &str,
+    target_space_id: Option<u64>,
+) -> Result<String, String> {
+    let adf = source
+        .body
+        .as_ref()
+        .and_then(|body| body.atlas_doc_format.clone())
+        .ok_or_else(|| format!("page {} has no ADF body", source.id))?;
+    let source_space_id = parse_id(&source.space_id)?;
+    let space_id = target_space_id.unwrap_or(source_space_id);
+    let parent_id = match &source.parent_id {
+        Some(parent_id) if space_id == source_space_id => Some(parse_id(parent_id)?),
+        _ => None,
+    };
+    let req_body = types::CreateContentRequest {
+        space_id,
+        title: new_title,
+        parent_id,
+        body: adf,
+    };
+    serde_json::to_string(&req_body).map_err(|e| e.to_string())
+}
 
-    for row in row_list {
-        let row_map: BTreeMap<String, Value> = serde_json::from_str(&row).map_err(|err| {
+/// Serialize the page update that republishes `version` on top of `current`.
+fn restore_version_request(
+    page_id: u64,
+    current: &ContentDetails,
+    version: &ContentDetails,
+) -> Result<String, String> {
+    let restored = version.version.number;
+    if restored >= current.version.number {
+        return Err(ErrorCode::Validation.tag(format!(
+            "version {} of page {} is not older than the current version {}",
+            restored, page_id, current.version.number
+        )));
+    }
+    let storage = version
+        .body
+        .as_ref()
+        .and_then(|body| body.storage.as_ref())
+        .ok_or_else(|| {
             format!(
-                "invalid data, failed to deserialize this map: {}",
-                err.to_string()
+                "version {} of page {} has no storage-format body",
+                restored, page_id
             )
         })?;
-        col_row_map.push(row_map);
-    }
+    let request = UpdateContentRequest {
+        id: page_id,
+        status: "current",
+        title: &version.title,
+        space_id: None,
+        body: StorageBody {
+            value: storage.value.clone(),
+            representation: STORAGE.to_string(),
+        },
+        version: PageVersion {
+            number: current.version.number + 1,
+            message: Some(format!("Restored version {}", restored)),
+            author_id: None,
+            created_at: None,
+        },
+    };
+    serde_json::to_string(&request).map_err(|e| e.to_string())
+}
 
-    let mut is_first_iteration = true;
-    let mut header_vec: Vec<String> = Vec::new();
-    let mut row_vec: Vec<Vec<Value>> = Vec::new();
+/// Serialize an inline comment request, rejecting an empty selection or a match index that
+/// can't point at one of the `match_count` occurrences.
+fn inline_comment_request_body(
+    page_id: u64,
+    content: String,
+    text_selection: String,
+    match_count: u32,
+    match_index: u32,
+) -> Result<String, String> {
+    if text_selection.trim().is_empty() {
+        return Err(ErrorCode::Validation.tag("text_selection must not be empty"));
+    }
+    if match_index >= match_count {
+        return Err(ErrorCode::Validation.tag(format!(
+            "text_selection_match_index {} does not point at one of the {} occurrences",
+            match_index, match_count
+        )));
+    }
+    let req_body = types::CreateInlineCommentRequest {
+        page_id,
+        body: types::StorageBody {
+            value: content,
+            representation: STORAGE.to_string(),
+        },
+        inline_comment_properties: types::InlineCommentProperties {
+            text_selection,
+            text_selection_match_count: match_count,
+            text_selection_match_index: match_index,
+        },
+    };
+    serde_json::to_string(&req_body).map_err(|e| e.to_string())
+}
 
-    for row_map in col_row_map {
-        let mut row = Vec::new();
-        // NOTE: relying on rust BTreeMap's property where this iterator visits keys in sorted
-        // order, therefore i can assume that row values will always be in same order w.r.t. the
-        // header
-        for (i, (key, value)) in row_map.into_iter().enumerate() {
-            if is_first_iteration {
-                header_vec.push(key.clone());
-            } else {
-                let Some(header_value) = header_vec.get(i) else {
-                    return Err(format!(
-                        "invalid data received from file, index out of bound"
-                    ));
-                };
-                if !key.eq(header_value) {
-                    return Err(format!(
-                        "invalid data received from file, header value different for different row"
-                    ));
-                }
-            }
-            row.push(value);
-        }
-        if is_first_iteration {
-            is_first_iteration = false;
-        }
-        row_vec.push(row);
+/// Pair a page with the outcome of moving it.
+fn page_move_result(page_id: u64, outcome: Result<ContentDetails, String>) -> PageMoveResult {
+    match outcome {
+        Ok(page) => PageMoveResult {
+            page_id,
+            page: Some(page),
+            error: None,
+        },
+        Err(error) => PageMoveResult {
+            page_id,
+            page: None,
+            error: Some(error),
+        },
     }
-    Ok(TableFields {
-        headers: header_vec,
-        rows: row_vec,
-    })
 }
 
-/// Build an ADF `Node::Table` from string headers and JSON values.
-///
-/// `headers` must be the same length as each row. Non-string JSON values are stringified.
-fn create_table_node(headers: Vec<String>, rows: Vec<Vec<Value>>) -> Result<Node, String> {
-    let mut header_columns = Vec::new();
-    for header_value in headers {
-        let header = Node::TableHeader {
-            attrs: CellAttrs {
-                colspan: 1,
-                rowspan: 1,
-            },
-            content: vec![Node::Paragraph {
-                content: Some(vec![Content {
-                    r#type: "text".into(),
-                    text: Some(header_value),
-                }]),
-            }],
-        };
-        header_columns.push(header);
+/// Check a space key against Confluence's format: one or more uppercase letters or digits.
+fn validate_space_key(key: &str) -> Result<(), String> {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+    {
+        Ok(())
+    } else {
+        Err(format!(
+            "invalid space key {:?}: it must match ^[A-Z0-9]+$",
+            key
+        ))
     }
+}
 
-    let header_row = Node::TableRow {
-        content: header_columns,
+/// Serialize the v2 create-space payload, validating the key first.
+fn create_space_request_body(
+    key: &str,
+    name: &str,
+    description: Option<&str>,
+) -> Result<String, String> {
+    validate_space_key(key)?;
+    if name.trim().is_empty() {
+        return Err("space name must not be empty".to_string());
+    }
+    let req_body = types::CreateSpaceRequest {
+        key,
+        name,
+        description: description.map(|value| types::SpaceDescription {
+            value,
+            representation: "plain",
+        }),
     };
+    serde_json::to_string(&req_body).map_err(|e| e.to_string())
+}
 
-    let mut table_rows = Vec::new();
-    table_rows.push(header_row);
+/// Quote `value` as a CQL string literal, escaping backslashes and double quotes.
+fn cql_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
 
-    for row in rows {
-        let mut row_cells = Vec::new();
-        for col in row {
-            let text = match col {
-                Value::String(s) => s,
-                _ => col.to_string(),
-            };
-            let cell = Node::TableCell {
-                attrs: CellAttrs {
-                    colspan: 1,
-                    rowspan: 1,
-                },
-                content: vec![Node::Paragraph {
-                    content: Some(vec![Content {
-                        r#type: "text".into(),
-                        text: Some(text),
-                    }]),
-                }],
-            };
-            row_cells.push(cell);
-        }
-        table_rows.push(Node::TableRow { content: row_cells });
+/// Build the CQL for a full-text search restricted to `space_keys`.
+fn build_space_search_cql(text: &str, space_keys: &[String]) -> Result<String, String> {
+    if text.trim().is_empty() {
+        return Err("search text must not be empty".to_string());
     }
-
-    Ok(Node::Table {
-        attrs: TableAttrs {
-            layout: "default".into(),
-            width: None,
-            local_id: None,
-            is_number_column_enabled: Some(false),
-        },
-        content: table_rows,
-    })
+    if space_keys.is_empty() {
+        return Err("at least one space key is required".to_string());
+    }
+    for key in space_keys {
+        validate_space_key(key)?;
+    }
+    let spaces = space_keys
+        .iter()
+        .map(|key| cql_quote(key))
+        .collect::<Vec<_>>()
+        .join(",");
+    Ok(format!(
+        "text ~ {} and space in ({})",
+        cql_quote(text.trim()),
+        spaces
+    ))
 }
 
-#[smart_contract]
-impl Confluence for ConfluenceContractState {
-    /// Initialize `ConfluenceContractState` with an empty `Secrets<ConfluenceConfig>`.
-    #[constructor]
-    fn new() -> Result<Self, String>
-    where
-        Self: Sized,
-    {
-        Ok(Self {
-            secrets: Secrets::<ConfluenceConfig>::new(),
-        })
+/// Most changed pages returned by one `pages_changed_since` call.
+const CHANGED_PAGES_LIMIT: u32 = 100;
+
+/// Normalize `since` to a CQL date literal: `YYYY-MM-DD` or `YYYY-MM-DD HH:MM`.
+///
+/// ISO 8601 timestamps (e.g. a page's `version.createdAt`) are cut to the minute, the finest
+/// precision CQL dates have; seconds and the offset are dropped.
+fn cql_date(since: &str) -> Result<String, String> {
+    let since = since.trim();
+    let invalid = || {
+        format!(
+            "invalid timestamp {:?}: expected YYYY-MM-DD, YYYY-MM-DD HH:MM or ISO 8601",
+            since
+        )
+    };
+    let number = |range: std::ops::Range<usize>, min: u32, max: u32| {
+        since
+            .get(range)
+            .filter(|digits| digits.chars().all(|c| c.is_ascii_digit()))
+            .and_then(|digits| digits.parse::<u32>().ok())
+            .filter(|value| (min..=max).contains(value))
+    };
+
+    let bytes = since.as_bytes();
+    if since.len() < 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return Err(invalid());
+    }
+    number(0..4, 1900, 9999).ok_or_else(invalid)?;
+    number(5..7, 1, 12).ok_or_else(invalid)?;
+    number(8..10, 1, 31).ok_or_else(invalid)?;
+    if since.len() == 10 {
+        return Ok(since.to_string());
     }
 
-    /// List all spaces (internally traverses pagination up to Confluence max page size).
-    #[query]
-    async fn list_spaces(&self) -> Result<Vec<SpaceSummary>, String> {
-        let endpoint = "/spaces";
-        let response: ListResponse<SpaceSummary> = serde_json::from_str(
-            &self
-                .make_request(
-                    HttpMethod::Get,
-                    endpoint,
-                    vec![(LIMIT.to_string(), 250.to_string())],
-                    None,
-                    200,
-                )
-                .await?
-                .1,
-        )
-        .map_err(|err| err.to_string())?;
-        self.process_complete_response(response).await
+    if since.len() < 16 || !matches!(bytes[10], b' ' | b'T') || bytes[13] != b':' {
+        return Err(invalid());
+    }
+    number(11..13, 0, 23).ok_or_else(invalid)?;
+    number(14..16, 0, 59).ok_or_else(invalid)?;
+    if since.len() > 16 && bytes[10] == b' ' {
+        return Err(invalid());
     }
+    Ok(format!("{} {}", &since[..10], &since[11..16]))
+}
 
-    // --- Page CRUD ---
+/// Build the CQL for pages in `space_key` modified at or after `since`, oldest change first.
+fn build_changed_since_cql(space_key: &str, since: &str) -> Result<String, String> {
+    validate_space_key(space_key)?;
+    Ok(format!(
+        "type = page and space = {} and lastmodified >= {} order by lastmodified asc",
+        cql_quote(space_key),
+        cql_quote(&cql_date(since)?)
+    ))
+}
 
-    /// Create a page by `space_id`, wrapping the provided `content` in a simple ADF paragraph.
-    #[query]
-    async fn create_page_by_space_id(
-        &self,
-        space_id: u64,
-        title: String,
-        content: String,
-    ) -> Result<responses::CreatePageResponse, String> {
-        let document = Document {
-            r#type: DOC.to_string(),
-            content: vec![create_paragraph_node(content)],
-            version: None,
-        };
-        self.create_page(space_id, title, None, document).await
-    }
+/// Keep the pages listed in `ids`, in that order.
+fn order_pages_by_ids(pages: Vec<ContentDetails>, ids: &[u64]) -> Vec<ContentDetails> {
+    let mut by_id: HashMap<String, ContentDetails> = pages
+        .into_iter()
+        .map(|page| (page.id.clone(), page))
+        .collect();
+    ids.iter()
+        .filter_map(|id| by_id.remove(&id.to_string()))
+        .collect()
+}
 
-    /// Create a page **with table** under a named parent page within a space.
-    #[query]
-    async fn create_page_with_table_by_space_name_with_parent_page(
-        &self,
-        space_name: String,
-        title: String,
-        parent_page_name: String,
-        headers: String,
-        rows: String,
-    ) -> Result<CreatePageResponse, String> {
-        let space_id = self.get_space_id_from_name(space_name.clone()).await?;
-        let parent_page_id = self
-            .get_page_id_from_name(parent_page_name, space_name)
-            .await?;
-        self.create_table(space_id, title, Some(parent_page_id), headers, rows)
-            .await
-    }
+/// Turn a v1 search response into ranked `SearchHit`s.
+///
+/// Confluence returns results best match first but doesn't expose its relevance score, so the
+/// score is derived from the rank: 1.0 for the first hit, falling evenly towards 0. Highlight
+/// markers (`@@@hl@@@`) and markup are stripped from excerpts.
+fn parse_search_hits(response: &str) -> Result<Vec<SearchHit>, String> {
+    let value: Value = serde_json::from_str(response).map_err(|e| e.to_string())?;
+    let results = value["results"]
+        .as_array()
+        .ok_or_else(|| "search response has no results".to_string())?;
+
+    let total = results.len();
+    let hits = results
+        .iter()
+        .enumerate()
+        .map(|(rank, result)| {
+            let content = &result["content"];
+            SearchHit {
+                id: content["id"].as_str().unwrap_or("").to_string(),
+                title: content["title"]
+                    .as_str()
+                    .or_else(|| result["title"].as_str())
+                    .unwrap_or("")
+                    .to_string(),
+                space_key: content["space"]["key"].as_str().unwrap_or("").to_string(),
+                excerpt: search_excerpt(result),
+                score: (total - rank) as f64 / total as f64,
+            }
+        })
+        .collect();
+    Ok(hits)
+}
 
-    /// Create a page **with table** in a space by name (no parent).
-    #[query]
-    async fn create_page_with_table_by_space_name_without_parent_page(
-        &self,
-        space_name: String,
-        title: String,
-        headers: String,
-        rows: String,
-    ) -> Result<CreatePageResponse, String> {
-        let space_id = self.get_space_id_from_name(space_name).await?;
-        self.create_table(space_id, title, None, headers, rows)
-            .await
+/// Plain-text excerpt of a v1 search result, without highlight markers (`@@@hl@@@`) or markup.
+fn search_excerpt(result: &Value) -> String {
+    let excerpt = result["excerpt"]
+        .as_str()
+        .unwrap_or("")
+        .replace("@@@hl@@@", "")
+        .replace("@@@endhl@@@", "");
+    storage_to_inline_text(&excerpt)
+}
+
+/// Matches returned by `search_content` when no limit is given.
+const SEARCH_DEFAULT_LIMIT: u32 = 25;
+
+/// Largest page of results requested from the v1 search endpoint.
+const SEARCH_PAGE_SIZE: u32 = 100;
+
+/// Parse one page of a v1 search response into `SearchResult`s and the path of the next page.
+///
+/// v1 `_links.next` is relative to the `/wiki` context path (`/rest/api/search?cursor=...`), so
+/// it is returned prefixed with `wiki` to fit `get_next_page`, which resolves paths against the
+/// site URL.
+fn parse_search_results(response: &str) -> Result<(Vec<SearchResult>, Option<String>), String> {
+    let value: Value = serde_json::from_str(response).map_err(|e| e.to_string())?;
+    let results = value["results"]
+        .as_array()
+        .ok_or_else(|| "search response has no results".to_string())?
+        .iter()
+        .map(|result| {
+            let content = &result["content"];
+            SearchResult {
+                content_id: content["id"].as_str().unwrap_or("").to_string(),
+                title: content["title"]
+                    .as_str()
+                    .or_else(|| result["title"].as_str())
+                    .unwrap_or("")
+                    .to_string(),
+                content_type: content["type"]
+                    .as_str()
+                    .or_else(|| result["entityType"].as_str())
+                    .unwrap_or("")
+                    .to_string(),
+                space_key: content["space"]["key"]
+                    .as_str()
+                    .or_else(|| {
+                        result["resultGlobalContainer"]["displayUrl"]
+                            .as_str()
+                            .and_then(|url| url.strip_prefix("/spaces/"))
+                    })
+                    .unwrap_or("")
+                    .to_string(),
+                excerpt: search_excerpt(result),
+            }
+        })
+        .collect();
+    let next = value["_links"]["next"]
+        .as_str()
+        .map(|next| format!("wiki{}", next));
+    Ok((results, next))
+}
+
+/// Follow search result pages from `first_page` until `limit` matches are collected or there are
+/// no more, returning at most `limit` of them.
+fn collect_search_results<F>(
+    first_page: (Vec<SearchResult>, Option<String>),
+    limit: usize,
+    mut fetch_next: F,
+) -> Result<Vec<SearchResult>, String>
+where
+    F: FnMut(&str) -> Result<(Vec<SearchResult>, Option<String>), String>,
+{
+    let (results, next) = first_page;
+    let mut collected = results.len();
+    let mut results = drain_pages(
+        results,
+        next.filter(|_| collected < limit),
+        PaginationLimits::default(),
+        |next_url: String| {
+            let (page, next) = fetch_next(&next_url)?;
+            collected += page.len();
+            Ok((page, next.filter(|_| collected < limit)))
+        },
+    )
+    .map_err(|err| err.to_string())?;
+    results.truncate(limit);
+    Ok(results)
+}
+
+/// IDs of the content items in a v1 search response, in result order.
+fn parse_search_content_ids(response: &str) -> Result<Vec<u64>, String> {
+    let value: Value = serde_json::from_str(response).map_err(|e| e.to_string())?;
+    value["results"]
+        .as_array()
+        .ok_or_else(|| "search response has no results".to_string())?
+        .iter()
+        .map(|result| json_id(&result["content"]["id"]))
+        .collect()
+}
+
+/// Body of a v1 add-labels request: every label as a `global` label, in one call.
+///
+/// Confluence labels are lowercased on save and can't contain whitespace, so those are rejected
+/// here instead of failing page by page.
+fn label_request_body(labels: &[String]) -> Result<String, String> {
+    if labels.is_empty() {
+        return Err("at least one label is required".to_string());
     }
+    let labels = labels
+        .iter()
+        .map(|label| {
+            let label = label.trim();
+            if label.is_empty() || label.chars().any(char::is_whitespace) {
+                return Err(format!(
+                    "invalid label '{}': labels must be non-empty and contain no spaces",
+                    label
+                ));
+            }
+            Ok(json!({ "prefix": "global", "name": label.to_lowercase() }))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(Value::Array(labels).to_string())
+}
 
-    /// Import an IMFS file and create a **table page** under a parent page.
-    #[query]
-    async fn import_file_and_create_page_with_table_with_parent_page(
-        &self,
-        space_name: String,
-        title: String,
-        parent_page_name: String,
-        file_descriptor: String,
-    ) -> Result<CreatePageResponse, String> {
-        let table_fields = get_table_from_imfs_file(file_descriptor).await?;
-        let header_serialized = serde_json::to_string(&table_fields.headers).unwrap(); // safe
-        // to unwrap since deserializing
-        let row_serialized = serde_json::to_string(&table_fields.rows).unwrap();
+/// Find the label called `name` in a v1 add-labels response, which lists every label on the
+/// content after the update.
+fn find_added_label(response: &str, name: &str) -> Result<Label, String> {
+    let response: Value = serde_json::from_str(response).map_err(|err| err.to_string())?;
+    let label = response["results"]
+        .as_array()
+        .and_then(|labels| labels.iter().find(|label| label["name"] == name))
+        .ok_or_else(|| format!("label '{}' is missing from the response", name))?;
+    Ok(Label {
+        id: json_id(&label["id"])?.to_string(),
+        name: name.to_string(),
+        prefix: label["prefix"].as_str().unwrap_or("global").to_string(),
+    })
+}
 
-        self.create_page_with_table_by_space_name_with_parent_page(
-            space_name,
-            title,
-            parent_page_name,
-            header_serialized,
-            row_serialized,
-        )
-        .await
+/// Apply `add_labels` to each of `page_ids`, stopping after `max` pages, and collect the
+/// per-page outcomes.
+fn label_pages<F>(page_ids: &[u64], max: usize, mut add_labels: F) -> BulkResult<u64>
+where
+    F: FnMut(u64) -> Result<(), String>,
+{
+    let mut result = BulkResult {
+        succeeded: Vec::new(),
+        failed: Vec::new(),
+    };
+    for &page_id in page_ids.iter().take(max) {
+        match add_labels(page_id) {
+            Ok(()) => result.succeeded.push(page_id),
+            Err(error) => result.failed.push(BulkFailure {
+                item: page_id,
+                error,
+            }),
+        }
     }
+    result
+}
 
-    /// Import an IMFS file and create a **table page** (no parent).
-    #[query]
-    async fn import_file_and_create_page_with_table_without_parent_page(
-        &self,
-        space_name: String,
-        title: String,
-        file_descriptor: String,
-    ) -> Result<CreatePageResponse, String> {
-        let table_fields = get_table_from_imfs_file(file_descriptor).await?;
-        let header_serialized = serde_json::to_string(&table_fields.headers).unwrap(); // safe
-        // to unwrap since deserializing
-        let row_serialized = serde_json::to_string(&table_fields.rows).unwrap();
+/// Error for an unexpected status, tagged with its category so agents can branch on it.
+fn http_error(status: u16, body: &str) -> String {
+    ErrorCode::from_http_status(status).tag(format!("HTTP {}: {}", status, body))
+}
 
-        self.create_page_with_table_by_space_name_without_parent_page(
-            space_name,
-            title,
-            header_serialized,
-            row_serialized,
-        )
-        .await
+/// Look up whether the user watches the page (`current`), and only if that differs from `watch`
+/// send the POST (watch) or DELETE (unwatch) through `change`. Both return `(status, body)`.
+fn apply_watch<C, S>(
+    page_id: u64,
+    watch: bool,
+    current: C,
+    change: S,
+) -> Result<WatchStatus, String>
+where
+    C: FnOnce() -> Result<(u16, String), String>,
+    S: FnOnce(HttpMethod) -> Result<(u16, String), String>,
+{
+    let (status, response) = current()?;
+    if status != 200 {
+        return Err(http_error(status, &response));
     }
+    let watching = serde_json::from_str::<Value>(&response).map_err(|e| e.to_string())?["watching"]
+        .as_bool()
+        .ok_or_else(|| format!("Unexpected watch status response: {}", response))?;
 
-    /// Append a **table** to an existing page resolved by name/space, preserving existing content.
-    #[query]
-    async fn append_table_to_page_by_page_name(
-        &self,
-        page_name: String,
-        space_name: String,
-        headers: String,
-        rows: String,
-    ) -> Result<ContentDetails, String> {
-        let page_id = self.get_page_id_from_name(page_name, space_name).await?;
-        let page_details = self.get_page_adf_doc_format(page_id).await?;
-        let Some(page_body) = page_details.body else {
-            return Err("page body none".to_string());
+    if watching != watch {
+        let method = if watch {
+            HttpMethod::Post
+        } else {
+            HttpMethod::Delete
         };
+        let (status, response) = change(method)?;
+        if status != 204 && status != 200 {
+            return Err(http_error(status, &response));
+        }
+    }
+    Ok(WatchStatus {
+        page_id: page_id.to_string(),
+        watching: watch,
+        changed: watching != watch,
+    })
+}
 
-        let Some(document_str) = page_body.atlas_doc_format else {
-            return Err("page body received isn't of atlas doc format".to_string());
+/// Turn a v1 restrictions-by-operation response into the users and groups allowed to read and
+/// update the page. Users are identified by account ID and groups by name.
+fn parse_page_restrictions(response: &str) -> Result<PageRestrictions, String> {
+    let value: Value = serde_json::from_str(response).map_err(|e| e.to_string())?;
+    let operation = |name: &str| {
+        let restrictions = &value[name]["restrictions"];
+        let collect = |kind: &str, key: &str| {
+            restrictions[kind]["results"]
+                .as_array()
+                .map(|results| {
+                    results
+                        .iter()
+                        .filter_map(|result| result[key].as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default()
         };
-        let mut document: Document = serde_json::from_str(&document_str.value).map_err(|err| {
-            format!(
-                "Unable to deserialize Document json to Document Struct {}",
-                err.to_string()
-            )
-        })?;
-
-        let table = deserialize_and_create_table_node(headers, rows)?;
+        OperationRestrictions {
+            users: collect("user", "accountId"),
+            groups: collect("group", "name"),
+        }
+    };
+    Ok(PageRestrictions {
+        read: operation("read"),
+        update: operation("update"),
+    })
+}
 
-        document.content.push(Node::Paragraph {
-            content: Some(vec![Content {
-                r#type: "text".into(),
-                text: Some("Table generated from Icarus: ".into()),
-            }]),
-        });
-        document.content.push(table);
+/// Content property in which a locked page keeps the update restrictions it had before.
+const EDIT_LOCK_PROPERTY: &str = "wadk-edit-lock";
 
-        let version_number = page_details.version.number + 1;
-        let req_body = types::UpdateContentRequestAtlasDocFormat {
-            id: page_id,
-            status: "current",
-            title: &page_details.title,
-            space_id: None,
-            body: AtlasDocFormatBodyStr {
-                value: serde_json::to_string(&document).map_err(|err| err.to_string())?,
-                representation: ATLAS_DOC_FORMAT.to_string(),
-            },
-            version: types::PageVersion {
-                number: version_number,
-                message: None,
-            },
-        };
-        let body = serde_json::to_string(&req_body).map_err(|e| e.to_string())?;
-        let endpoint = format!("/pages/{}", page_id);
-        let response = self
-            .make_request(HttpMethod::Put, &endpoint, vec![], Some(body), 200)
-            .await?
-            .1;
-        serde_json::from_str(&response).map_err(|e| e.to_string())
+/// Restrictions that keep who may read a page and limit updates to `account_id`.
+fn locked_restrictions(current: PageRestrictions, account_id: &str) -> PageRestrictions {
+    PageRestrictions {
+        read: current.read,
+        update: OperationRestrictions {
+            users: vec![account_id.to_string()],
+            groups: Vec::new(),
+        },
     }
+}
 
-    /// Create a page by `space_name` using ADF paragraph content.
-    #[query]
-    async fn create_page_by_space_name(
-        &self,
-        space_name: String,
-        title: String,
-        content: String,
-    ) -> Result<CreatePageResponse, String> {
-        let space_id = self.get_space_id_from_name(space_name).await?;
-        self.create_page_by_space_id(space_id, title, content).await
+/// Restrictions that keep who may read a page and give updates back to `prior`; without a
+/// prior state, updates are left unrestricted and so inherited from the ancestors.
+fn unlocked_restrictions(
+    current: PageRestrictions,
+    prior: Option<OperationRestrictions>,
+) -> PageRestrictions {
+    PageRestrictions {
+        read: current.read,
+        update: prior.unwrap_or_default(),
     }
+}
 
-    /// Append text to an existing page by ID (storage representation).
-    #[query]
-    async fn append_to_page_by_id(
-        &self,
-        page_id: u64,
-        content: String,
-    ) -> Result<ContentDetails, String> {
-        // get the page content for this id
-        let page = self.get_page_by_id(page_id.clone()).await?;
-        let original_body = get_content_from_body(page.body);
+/// Operations for a v1 restrictions PUT, which replaces all of a page's restrictions. An
+/// operation without users or groups is left out, which lifts its restriction.
+fn restrictions_request_body(restrictions: &PageRestrictions) -> Vec<Value> {
+    [
+        ("read", &restrictions.read),
+        ("update", &restrictions.update),
+    ]
+    .into_iter()
+    .filter(|(_, allowed)| !allowed.users.is_empty() || !allowed.groups.is_empty())
+    .map(|(operation, allowed)| {
+        let users: Vec<Value> = allowed
+            .users
+            .iter()
+            .map(|account_id| json!({ "type": "known", "accountId": account_id }))
+            .collect();
+        let groups: Vec<Value> = allowed
+            .groups
+            .iter()
+            .map(|name| json!({ "type": "group", "name": name }))
+            .collect();
+        json!({
+            "operation": operation,
+            "restrictions": { "user": users, "group": groups }
+        })
+    })
+    .collect()
+}
 
-        // append to the content , and then send the update
-        let new_content = format!("{original_body}\n{content}");
-        self.update_page_by_id(page_id, None, page.title, new_content)
-            .await
+/// Read `totalSize` from a v1 search response, i.e. the number of matches across all pages of
+/// results rather than just the ones returned.
+fn parse_search_total_size(response: &str) -> Result<u32, String> {
+    let value: Value = serde_json::from_str(response).map_err(|e| e.to_string())?;
+    value["totalSize"]
+        .as_u64()
+        .map(|total| total as u32)
+        .ok_or_else(|| "search response has no totalSize".to_string())
+}
+
+/// Serialize the v2 create-page payload for an ADF `document`.
+fn create_page_request_body(
+    space_id: u64,
+    title: &str,
+    parent_id: Option<u64>,
+    document: &Document,
+) -> Result<String, String> {
+    let req_body = types::CreateContentRequest {
+        space_id,
+        title,
+        parent_id,
+        body: types::AtlasDocFormatBodyStr {
+            value: serde_json::to_string(document).map_err(|err| err.to_string())?,
+            representation: ATLAS_DOC_FORMAT.to_string(),
+        },
+    };
+    serde_json::to_string(&req_body).map_err(|e| e.to_string())
+}
+
+/// ID of the only page in `pages` titled `page_name`.
+fn unique_page_id(
+    pages: &[ContentDetails],
+    page_name: &str,
+    space_name: &str,
+) -> Result<u64, String> {
+    let matches: Vec<&ContentDetails> = pages.iter().filter(|p| p.title == page_name).collect();
+    match matches.as_slice() {
+        [] => Err(format!(
+            "page not found for the given page_name: {page_name} and space : {space_name}"
+        )),
+        [page] => parse_id(&page.id),
+        _ => {
+            let ids: Vec<&str> = matches.iter().map(|p| p.id.as_str()).collect();
+            Err(ErrorCode::Validation.tag(format!(
+                "{} pages in space {space_name} are titled {page_name:?}; use a page id instead: {}",
+                ids.len(),
+                ids.join(", ")
+            )))
+        }
     }
+}
 
-    /// Import an IMFS file and append a **table** to a page resolved by name/space.
-    #[query]
-    async fn import_file_and_append_table_to_page_by_page_name(
-        &self,
-        file_descriptor: String,
-        page_name: String,
-        space_name: String,
-    ) -> Result<ContentDetails, String> {
-        let table_fields = get_table_from_imfs_file(file_descriptor).await?;
-        let serialized_header_vec =
-            serde_json::to_string(&table_fields.headers).map_err(|err| err.to_string())?;
-        let serialized_row_vec =
-            serde_json::to_string(&table_fields.rows).map_err(|err| err.to_string())?;
-        self.append_table_to_page_by_page_name(
-            page_name,
-            space_name,
-            serialized_header_vec,
-            serialized_row_vec,
-        )
-        .await
+/// Summarise the details of a freshly created page.
+fn page_created(details: CreateContentDetails) -> responses::CreatePageResponse {
+    responses::CreatePageResponse {
+        page_id: details.id,
+        title: details.title,
+        status: details.status,
+        space_id: details.space_id,
+        created_at: details.created_at,
+        parent_type: details.parent_type,
     }
+}
 
-    /// Append text to a page by name/space (storage representation).
-    #[query]
-    async fn append_to_page_by_page_name(
-        &self,
-        page_name: String,
-        space_name: String,
-        content: String,
-    ) -> Result<ContentDetails, String> {
-        let page_id = self.get_page_id_from_name(page_name, space_name).await?;
-        self.append_to_page_by_id(page_id, content).await
-    }
+/// Escape text for use inside storage-format (XHTML) markup.
+fn escape_storage_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
 
-    /// Get page details by numeric ID, requesting `storage` body format.
-    #[query]
-    async fn get_page_by_id(&self, page_id: u64) -> Result<types::ContentDetails, String> {
-        let endpoint = format!("pages/{}", page_id);
-        let query_params = vec![(BODY_FORMAT.to_string(), STORAGE.to_string())];
-        let response = self
-            .make_request(HttpMethod::Get, &endpoint, query_params, None, 200)
-            .await?
-            .1;
-        serde_json::from_str(&response).map_err(|e| e.to_string())
-    }
+/// Replace each `{{key}}` placeholder in `template` with the matching value of the
+/// `substitutions` JSON object.
+///
+/// String values are inserted as text, other values in their JSON form; both are escaped for
+/// storage format. Placeholders without a substitution are left as they are.
+fn apply_template_substitutions(template: &str, substitutions: &str) -> Result<String, String> {
+    let substitutions: Value = serde_json::from_str(substitutions)
+        .map_err(|err| format!("substitutions is not valid JSON: {}", err))?;
+    let Value::Object(substitutions) = substitutions else {
+        return Err(
+            "substitutions must be a JSON object of placeholder names to values".to_string(),
+        );
+    };
 
-    /// Create a page by `space_id` and **parent page id**, using ADF paragraph content.
-    #[query]
-    async fn create_page_by_space_id_with_parent_page_id(
-        &self,
-        space_id: u64,
-        title: String,
-        parent_id: u64,
-        content: String,
-    ) -> Result<CreatePageResponse, String> {
-        let document = Document {
-            r#type: DOC.to_string(),
-            content: vec![create_paragraph_node(content)],
-            version: None,
+    let mut content = template.to_string();
+    for (key, value) in &substitutions {
+        let value = match value {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
         };
-        self.create_page(space_id, title, Some(parent_id), document)
-            .await
+        content = content.replace(&format!("{{{{{}}}}}", key), &escape_storage_text(&value));
     }
+    Ok(content)
+}
 
-    /// Create a page by `space_name` and **parent page name**.
-    #[query]
-    async fn create_page_by_space_name_with_parent_page_name(
-        &self,
-        space_name: String,
-        title: String,
-        parent_page_name: String,
-        content: String,
-    ) -> Result<CreatePageResponse, String> {
-        let space_id = self.get_space_id_from_name(space_name.clone()).await?;
-        let parent_page_id = self
-            .get_page_id_from_name(parent_page_name, space_name)
-            .await?;
-        self.create_page_by_space_id_with_parent_page_id(space_id, title, parent_page_id, content)
-            .await
-    }
+/// Storage-format body of a v1 content template response.
+fn parse_template_storage(template_json: &str) -> Result<String, String> {
+    let template: Value = serde_json::from_str(template_json).map_err(|err| err.to_string())?;
+    template
+        .pointer("/body/storage/value")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| "template has no storage-format body".to_string())
+}
 
-    /// Get page details by `page_name` and `space_name` (storage body format).
-    #[query]
-    async fn get_page_by_name(
-        &self,
-        page_name: String,
-        space_name: String,
-    ) -> Result<ContentDetails, String> {
-        let page_id = self.get_page_id_from_name(page_name, space_name).await?;
-        let endpoint = format!("pages/{}?body-format=storage", page_id);
-        let response = self
-            .make_request(HttpMethod::Get, &endpoint, vec![], None, 200)
-            .await?
-            .1;
-        serde_json::from_str(&response).map_err(|e| e.to_string())
+/// Serialize the v1 create-template payload for a page template holding `page`'s storage body.
+fn page_template_request_body(
+    template_name: &str,
+    space_key: &str,
+    page: &ContentDetails,
+) -> Result<String, String> {
+    if template_name.trim().is_empty() {
+        return Err("template_name must not be empty".to_string());
     }
+    let storage = page
+        .body
+        .as_ref()
+        .and_then(|body| body.storage.as_ref())
+        .ok_or_else(|| format!("page {} has no storage-format body", page.id))?;
+    let body = serde_json::json!({
+        "name": template_name,
+        "templateType": "page",
+        "description": format!("Created from page \"{}\"", page.title),
+        "body": {
+            "storage": {
+                "value": storage.value,
+                "representation": STORAGE,
+            },
+        },
+        "space": { "key": space_key },
+    });
+    Ok(body.to_string())
+}
 
-    /// Update page content/title by ID. If `space_id` is `None`, it is not changed.
-    #[query]
-    async fn update_page_by_id(
-        &self,
-        page_id: u64,
-        space_id: Option<u64>,
-        new_title: String,
-        new_content: String,
-    ) -> Result<types::ContentDetails, String> {
-        let version_number = {
-            // Fetch the current version number if not provided
-            let page_details = self.get_page_by_id(page_id).await?;
-            page_details.version.number + 1
-        };
+/// View count in a v1 analytics views response (`{"id": ..., "count": ...}`).
+fn parse_page_views(response: &str) -> Result<u64, String> {
+    let views: Value = serde_json::from_str(response).map_err(|err| err.to_string())?;
+    views["count"]
+        .as_u64()
+        .ok_or_else(|| format!("analytics response has no view count: {}", response))
+}
 
-        let req_body = types::UpdateContentRequest {
-            id: page_id,
-            status: "current",
-            title: &new_title,
-            space_id,
-            body: types::StorageBody {
-                value: new_content,
-                representation: "storage".to_string(),
-            },
-            version: types::PageVersion {
-                number: version_number,
-                message: None,
-            },
-        };
-        let body = serde_json::to_string(&req_body).map_err(|e| e.to_string())?;
-        let endpoint = format!("pages/{}", page_id);
-        let response = self
-            .make_request(HttpMethod::Put, &endpoint, vec![], Some(body), 200)
-            .await?
-            .1;
-        serde_json::from_str(&response).map_err(|e| e.to_string())
+/// Error for a failed analytics call. Sites without analytics (Free plan, or the feature turned
+/// off) answer 403 or 501, which is worth spelling out instead of passing the raw response on.
+fn page_views_error(status: u16, body: &str) -> String {
+    match status {
+        403 | 501 => ErrorCode::from_http_status(status).tag(format!(
+            "page analytics are not available on this Confluence site or to this user (HTTP {}): {}",
+            status, body
+        )),
+        _ => http_error(status, body),
     }
+}
 
-    /// Update page by name/space (resolves IDs) with new title/content.
-    #[query]
-    async fn update_page_by_name(
-        &self,
-        page_name: String,
-        space_name: String,
-        new_title: String,
-        new_content: String,
-    ) -> Result<ContentDetails, String> {
-        let space_id = self.get_space_id_from_name(space_name.clone()).await?;
-        let page_id = self.get_page_id_from_name(page_name, space_name).await?;
-        self.update_page_by_id(page_id, Some(space_id), new_title, new_content)
-            .await
+/// The content a reaction is on, and the page or blog post that contains it.
+#[derive(Debug, PartialEq)]
+struct ReactionTarget {
+    content_id: String,
+    content_type: String,
+    container_id: String,
+    container_type: String,
+}
+
+/// Emoji id for a supported shortname, given with or without the surrounding colons.
+fn reaction_emoji_id(emoji: &str) -> Result<&'static str, String> {
+    let shortname = emoji.trim().trim_matches(':');
+    REACTION_EMOJIS
+        .iter()
+        .find(|(name, _)| *name == shortname)
+        .map(|(_, id)| *id)
+        .ok_or_else(|| {
+            let supported: Vec<&str> = REACTION_EMOJIS.iter().map(|(name, _)| *name).collect();
+            ErrorCode::Validation.tag(format!(
+                "unsupported reaction {:?}; expected one of {}",
+                emoji,
+                supported.join(", ")
+            ))
+        })
+}
+
+/// Reaction target of v1 content fetched with `expand=container`. Pages and blog posts
+/// contain themselves; a comment's container is the page or blog post it is on.
+fn reaction_target(content: &Value) -> Result<ReactionTarget, String> {
+    let content_id = content["id"]
+        .as_str()
+        .ok_or_else(|| "content response has no id".to_string())?;
+    let content_type = content["type"].as_str().unwrap_or_default();
+    let (container_id, container_type) = match content_type {
+        "page" | "blogpost" => (content_id, content_type),
+        "comment" => (
+            content["container"]["id"]
+                .as_str()
+                .ok_or_else(|| format!("comment {} has no container", content_id))?,
+            content["container"]["type"].as_str().unwrap_or("page"),
+        ),
+        other => {
+            return Err(ErrorCode::Validation.tag(format!(
+                "reactions are only supported on pages, blog posts and comments, not {:?}",
+                other
+            )));
+        }
+    };
+    Ok(ReactionTarget {
+        content_id: content_id.to_string(),
+        content_type: content_type.to_string(),
+        container_id: container_id.to_string(),
+        container_type: container_type.to_string(),
+    })
+}
+
+/// Body of a reactions `POST`.
+fn reaction_request_body(target: &ReactionTarget, emoji_id: &str) -> Value {
+    json!({
+        "containerId": target.container_id,
+        "containerType": target.container_type,
+        "contentId": target.content_id,
+        "contentType": target.content_type,
+        "emojiId": emoji_id,
+    })
+}
+
+/// Query of a reactions `DELETE`, which takes the same fields as the add body.
+fn reaction_query_params(target: &ReactionTarget, emoji_id: &str) -> Vec<(String, String)> {
+    [
+        ("containerId", &target.container_id),
+        ("containerType", &target.container_type),
+        ("contentId", &target.content_id),
+        ("contentType", &target.content_type),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value.clone()))
+    .chain([("emojiId".to_string(), emoji_id.to_string())])
+    .collect()
+}
+
+/// A multipart boundary that does not occur in `content`.
+fn multipart_boundary(content: &str) -> String {
+    let mut boundary = "wadk-confluence-attachment".to_string();
+    let mut attempt = 0;
+    while content.contains(&boundary) {
+        attempt += 1;
+        boundary = format!("wadk-confluence-attachment-{}", attempt);
     }
+    boundary
+}
 
-    /// Delete a page by ID. Returns success flag and message.
-    #[query]
-    async fn delete_page(&self, page_id: u64) -> Result<types::DeleteResult, String> {
-        let endpoint = format!("pages/{}", page_id);
-        let (status, response_text) = self
-            .make_request(HttpMethod::Delete, &endpoint, vec![], None, 204)
-            .await?;
-        if status == 204 {
-            return Ok(DeleteResult {
-                success: true,
-                message: format!("Page with {page_id} Deleted"),
-            });
-        } else {
-            return Ok(DeleteResult {
-                success: false,
-                message: response_text,
-            });
+/// Build the `multipart/form-data` body for a v1 attachment upload: a `file` part holding
+/// `content`, and `minorEdit` so watchers aren't notified of the upload.
+///
+/// Parts are CRLF-delimited. `boundary` must not occur in `content` (see `multipart_boundary`),
+/// and the file name and content type are rejected if they contain line breaks, which would
+/// end their header line early.
+fn attachment_multipart_body(
+    boundary: &str,
+    file_name: &str,
+    content_type: &str,
+    content: &str,
+) -> Result<String, String> {
+    if file_name.trim().is_empty() {
+        return Err(ErrorCode::Validation.tag("file_name must not be empty"));
+    }
+    for (field, value) in [("file_name", file_name), ("content_type", content_type)] {
+        if value.contains(['\r', '\n']) {
+            return Err(
+                ErrorCode::Validation.tag(format!("{} must not contain line breaks", field))
+            );
         }
     }
+    let content_type = match content_type.trim() {
+        "" => "application/octet-stream",
+        content_type => content_type,
+    };
+    Ok(format!(
+        "--{boundary}\r\n\
+         Content-Disposition: form-data; name=\"file\"; filename=\"{file_name}\"\r\n\
+         Content-Type: {content_type}\r\n\
+         \r\n\
+         {content}\r\n\
+         --{boundary}\r\n\
+         Content-Disposition: form-data; name=\"minorEdit\"\r\n\
+         \r\n\
+         true\r\n\
+         --{boundary}--\r\n",
+        file_name = file_name.replace('"', "%22"),
+    ))
+}
 
-    // --- Blog Post CRUD ---
+/// The attachment created by a v1 upload, which answers with a one-item content list.
+fn parse_uploaded_attachment(response: &str) -> Result<AttachmentInfo, String> {
+    let list: Value = serde_json::from_str(response).map_err(|err| err.to_string())?;
+    let attachment = &list["results"][0];
+    let id = attachment["id"]
+        .as_str()
+        .ok_or_else(|| format!("upload response has no attachment: {}", response))?;
+    Ok(AttachmentInfo {
+        id: id.to_string(),
+        title: attachment["title"].as_str().unwrap_or_default().to_string(),
+        media_type: attachment["extensions"]["mediaType"]
+            .as_str()
+            .map(str::to_string),
+        file_size: attachment["extensions"]["fileSize"].as_u64(),
+        download_link: attachment["_links"]["download"]
+            .as_str()
+            .map(str::to_string),
+    })
+}
 
-    /// Create a blog post by space ID using a simple ADF paragraph.
-    #[query]
-    async fn create_blog_post_by_space_id(
-        &self,
-        space_id: u64,
-        title: String,
-        content: String,
-    ) -> Result<CreateBlogPostResponse, String> {
-        let document = Document {
-            r#type: DOC.to_string(),
-            content: vec![create_paragraph_node(content)],
-            version: None,
-        };
+/// File names inside a page bundle exported by `export_page_bundle_to_imfs`.
+const BUNDLE_BODY_FILE: &str = "page.storage.xml";
+const BUNDLE_MANIFEST_FILE: &str = "manifest.json";
 
-        let req_body = types::CreateContentRequest {
-            space_id, // safe to unwrap
-            title: &title,
-            parent_id: None,
-            body: types::AtlasDocFormatBodyStr {
-                value: serde_json::to_string(&document).map_err(|err| err.to_string())?,
-                representation: ATLAS_DOC_FORMAT.to_string(),
-            },
-        };
-        let body = serde_json::to_string(&req_body).map_err(|e| e.to_string())?;
-        let response = self
-            .make_request(HttpMethod::Post, "blogposts", vec![], Some(body), 200)
-            .await?
-            .1;
-        let blogpost_details: types::BlogPostDetails =
-            serde_json::from_str(&response).map_err(|e| e.to_string())?;
-        let body_str = get_content_from_body(blogpost_details.body);
+/// The IMFS folder a bundle is written to: `bundle_name` without surrounding slashes, refused
+/// when empty or when a `..` segment would lead out of it.
+fn bundle_folder(bundle_name: &str) -> Result<String, String> {
+    let folder = bundle_name.trim().trim_matches('/');
+    if folder.is_empty() {
+        return Err(ErrorCode::Validation.tag("bundle_name must not be empty"));
+    }
+    if folder.split('/').any(|segment| segment == "..") {
+        return Err(ErrorCode::Validation.tag(format!(
+            "bundle_name must not contain '..' segments, got '{}'",
+            bundle_name
+        )));
+    }
+    Ok(folder.to_string())
+}
 
-        Ok(CreateBlogPostResponse {
-            blog_post_id: blogpost_details.id,
-            title: blogpost_details.title,
-            status: blogpost_details.status,
-            space_id: blogpost_details.space_id,
-            version: blogpost_details.version,
-            body: body_str,
-            created_at: blogpost_details.created_at,
-            author_id: blogpost_details.author_id,
+/// Where an attachment goes in the bundle: `attachments/` and its file name, with separators
+/// replaced so the name stays a single path segment.
+fn bundle_attachment_path(folder: &str, attachment: &AttachmentInfo) -> String {
+    let name = attachment.title.replace(['/', '\\'], "_");
+    let name = match name.as_str() {
+        "" | "." | ".." => attachment.id.clone(),
+        _ => name,
+    };
+    format!("{}/attachments/{}", folder, name)
+}
+
+/// Manifest of a page bundle: the page it was exported from, then every file written into the
+/// bundle with its IMFS descriptor, the storage-format body first.
+fn page_bundle_manifest(
+    page: &ContentDetails,
+    folder: &str,
+    body_descriptor: &str,
+    attachments: &[(AttachmentInfo, String)],
+) -> Value {
+    let mut files = vec![json!({
+        "kind": "body",
+        "path": format!("{}/{}", folder, BUNDLE_BODY_FILE),
+        "format": STORAGE,
+        "file_descriptor": body_descriptor,
+    })];
+    files.extend(attachments.iter().map(|(attachment, descriptor)| {
+        json!({
+            "kind": "attachment",
+            "path": bundle_attachment_path(folder, attachment),
+            "attachment_id": attachment.id,
+            "title": attachment.title,
+            "media_type": attachment.media_type,
+            "file_size": attachment.file_size,
+            "file_descriptor": descriptor,
         })
-    }
+    }));
+    json!({
+        "page_id": page.id,
+        "title": page.title,
+        "space_id": page.space_id,
+        "version": page.version.number,
+        "files": files,
+    })
+}
 
-    /// Create a blog post by space name.
-    #[query]
-    async fn create_blog_post_by_space_name(
-        &self,
-        space_name: String,
-        title: String,
-        content: String,
-    ) -> Result<CreateBlogPostResponse, String> {
-        let space_id = self.get_space_id_from_name(space_name).await?;
-        self.create_blog_post_by_space_id(space_id, title, content)
-            .await
+/// ID of the template in a v1 create-template response.
+fn parse_template_id(response: &str) -> Result<String, String> {
+    let template: Value = serde_json::from_str(response).map_err(|err| err.to_string())?;
+    template["templateId"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| format!("template response has no templateId: {}", response))
+}
+
+/// Suggested wait, in seconds, before resuming a bulk run that hit a rate limit.
+///
+/// The runtime's HTTP client does not expose response headers, so neither `Retry-After` nor
+/// `X-RateLimit-*` can be read; this is a conservative default instead.
+const RATE_LIMIT_BACKOFF_SECS: u64 = 60;
+
+/// Drive a bulk page import, one create call per page starting at `start_index`.
+///
+/// `send` performs the create call for the page at the given index and returns
+/// `(status_code, body_text)`. Pages that fail for any other reason are recorded and skipped, but
+/// a `429 Too Many Requests` pauses the run: nothing further is sent, so the remaining quota is
+/// not burned on requests that would be throttled too, and the report says where to resume.
+fn run_bulk_page_import<F>(
+    pages: &[BulkPageInput],
+    start_index: usize,
+    mut send: F,
+) -> BulkImportReport
+where
+    F: FnMut(usize) -> Result<(u16, String), String>,
+{
+    let mut results = Vec::new();
+
+    for (index, page) in pages.iter().enumerate().skip(start_index) {
+        let (success, page_id, error) = match send(index) {
+            Ok((429, _)) => {
+                return BulkImportReport {
+                    results,
+                    completed: false,
+                    resume_from: Some(index as u32),
+                    retry_after_secs: Some(RATE_LIMIT_BACKOFF_SECS),
+                };
+            }
+            Ok((200, text)) => match serde_json::from_str::<CreateContentDetails>(&text) {
+                Ok(created) => (true, Some(created.id), None),
+                Err(err) => (false, None, Some(err.to_string())),
+            },
+            Ok((status, text)) => (false, None, Some(http_error(status, &text))),
+            Err(err) => (false, None, Some(err)),
+        };
+        results.push(BulkItemResult {
+            index: index as u32,
+            title: page.title.clone(),
+            success,
+            page_id,
+            error,
+        });
     }
 
-    /// Get blog post by name/space.
-    #[query]
-    async fn get_blog_post_by_name(
-        &self,
-        blog_post_name: String,
-        space_name: String,
-    ) -> Result<ContentDetails, String> {
-        let blog_post_id = self
-            .get_blog_post_id_from_name(blog_post_name, space_name)
-            .await?;
-        self.get_blog_post_by_id(blog_post_id).await
+    BulkImportReport {
+        results,
+        completed: true,
+        resume_from: None,
+        retry_after_secs: None,
     }
+}
 
-    /// Get blog post by ID (storage format).
-    #[query]
-    async fn get_blog_post_by_id(
-        &self,
-        blog_post_id: u64,
-    ) -> Result<types::ContentDetails, String> {
-        let endpoint = format!("blogposts/{}", blog_post_id);
-        let query_params = vec![("body-format".to_string(), "storage".to_string())];
-        let response = self
-            .make_request(HttpMethod::Get, &endpoint, query_params, None, 200)
-            .await?
-            .1;
-        serde_json::from_str(&response).map_err(|e| e.to_string())
-    }
+/// Pull a `storage`-format body value from a `ContentDetails` body, or empty string if missing.
+fn get_content_from_body(body: Option<Body>) -> String {
+    let body_str = match body {
+        None => String::new(),
+        Some(body) => match body.storage {
+            None => String::new(),
+            Some(storage_body) => storage_body.value,
+        },
+    };
+    body_str
+}
 
-    /// Update a blog post by ID. If `new_version_number` is `None`, the current version is fetched and incremented.
-    #[query]
-    async fn update_blog_post_by_id(
-        &self,
-        blog_post_id: u64,
-        space_id: Option<u64>,
-        new_title: String,
-        new_content: String,
-        new_version_number: Option<u32>,
-    ) -> Result<types::BlogPostDetails, String> {
-        let version_number = match new_version_number {
-            Some(num) => num,
-            None => {
-                // Fetch the current version number if not provided
-                let blog_post_details = self.get_blog_post_by_id(blog_post_id).await?;
-                blog_post_details.version.number + 1
+/// Flatten a storage-format (XHTML) body into a single line of text by dropping tags and
+/// collapsing whitespace. Only the handful of entities Confluence emits for plain prose are
+/// decoded.
+fn storage_to_inline_text(storage: &str) -> String {
+    let mut text = String::with_capacity(storage.len());
+    let mut in_tag = false;
+    for ch in storage.chars() {
+        match ch {
+            '<' => {
+                in_tag = true;
+                text.push(' ');
             }
-        };
-        let req_body = types::UpdateContentRequest {
-            id: blog_post_id,
-            status: "current",
-            title: &new_title,
-            space_id,
-            body: types::StorageBody {
-                value: new_content,
-                representation: "storage".to_string(),
-            },
-            version: types::PageVersion {
-                number: version_number,
-                message: None,
-            },
-        };
-        let body = serde_json::to_string(&req_body).map_err(|e| e.to_string())?;
-        let endpoint = format!("blogposts/{}", blog_post_id);
-        let response = self
-            .make_request(HttpMethod::Put, &endpoint, vec![], Some(body), 200)
-            .await?
-            .1;
-        serde_json::from_str(&response).map_err(|e| e.to_string())
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(ch),
+            _ => {}
+        }
     }
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
 
-    /// Update a blog post by name/space, forwarding to the ID-based updater.
-    #[query]
-    async fn update_blog_post_by_name(
-        &self,
-        blog_post_name: String,
-        space_name: String,
-        new_title: String,
-        new_content: String,
-        new_version_number: Option<u32>,
-    ) -> Result<BlogPostDetails, String> {
-        let blog_post_id = self
-            .get_blog_post_id_from_name(blog_post_name, space_name)
-            .await?;
-        self.update_blog_post_by_id(
-            blog_post_id,
-            None,
-            new_title,
-            new_content,
-            new_version_number,
-        )
-        .await
-    }
+/// Whether the text of `content` already appears in the storage-format `body`.
+///
+/// Both sides are flattened with `storage_to_inline_text`, so differences in markup and
+/// whitespace don't count as new content.
+fn content_already_present(body: &str, content: &str) -> bool {
+    let needle = storage_to_inline_text(content);
+    needle.is_empty() || storage_to_inline_text(body).contains(&needle)
+}
 
-    /// Delete a blog post by ID. Returns success flag and message.
-    #[query]
-    async fn delete_blog_post(&self, blog_post_id: u64) -> Result<types::DeleteResult, String> {
-        let endpoint = format!("blogposts/{}", blog_post_id);
-        let (status, response_text) = self
-            .make_request(HttpMethod::Delete, &endpoint, vec![], None, 204)
-            .await?;
-        if status == 204 {
-            return Ok(DeleteResult {
-                success: true,
-                message: format!("Blog post with {blog_post_id} Deleted"),
-            });
-        } else {
-            return Ok(DeleteResult {
-                success: false,
-                message: response_text,
-            });
+/// Order the pages of a subtree depth first from the root, pairing each page ID with its
+/// breadcrumb (`Root > Child > Grandchild`), and keep at most `max_pages` of them.
+///
+/// `descendants` is the flat list returned by the descendants endpoint; children keep the order
+/// they appear in there. Anything that isn't a page is left out.
+fn order_subtree(
+    root_id: &str,
+    root_title: &str,
+    descendants: &[PageHierarchyItem],
+    max_pages: usize,
+) -> Vec<(String, String)> {
+    let mut children: BTreeMap<&str, Vec<&PageHierarchyItem>> = BTreeMap::new();
+    for item in descendants {
+        if item
+            .content_type
+            .as_deref()
+            .is_some_and(|kind| kind != "page")
+        {
+            continue;
+        }
+        if let Some(parent_id) = item.parent_id.as_deref() {
+            children.entry(parent_id).or_default().push(item);
         }
     }
 
-    /// List blog posts in a space by **ID**, with pagination drained into a single result.
-    #[query]
-    async fn list_blog_posts_in_space_by_id(
-        &self,
-        space_id: u64,
-    ) -> Result<types::BlogPostHierarchyResponse, String> {
-        let endpoint = format!("spaces/{}/blogposts", space_id);
-        let response = self
-            .make_request(
-                HttpMethod::Get,
-                &endpoint,
-                vec![(LIMIT.to_string(), 250.to_string())],
-                None,
-                200,
-            )
-            .await?
-            .1;
-        let list: types::ListResponse<types::BlogPostHierarchyItem> =
-            serde_json::from_str(&response).map_err(|e| e.to_string())?;
-        let vec_list = self.process_complete_response(list).await?;
-        Ok(types::BlogPostHierarchyResponse {
-            total_count: vec_list.len(),
-            results: vec_list,
-        })
+    let mut ordered = Vec::new();
+    let mut pending = vec![(root_id.to_string(), root_title.to_string())];
+    while let Some((id, breadcrumb)) = pending.pop() {
+        if ordered.len() == max_pages {
+            break;
+        }
+        if let Some(kids) = children.get(id.as_str()) {
+            pending.extend(
+                kids.iter()
+                    .rev()
+                    .map(|kid| (kid.id.clone(), format!("{} > {}", breadcrumb, kid.title))),
+            );
+        }
+        ordered.push((id, breadcrumb));
     }
+    ordered
+}
 
-    /// List blog posts in a space by **name**.
-    #[query]
-    async fn list_blog_posts_in_space_by_name(
-        &self,
-        space_name: String,
-    ) -> Result<BlogPostHierarchyResponse, String> {
-        let space_id = self.get_space_id_from_name(space_name).await?;
-        self.list_blog_posts_in_space_by_id(space_id).await
+/// Concatenate `(breadcrumb, text)` sections under `## breadcrumb` headers.
+fn render_subtree_text(sections: &[(String, String)]) -> String {
+    sections
+        .iter()
+        .map(|(breadcrumb, text)| format!("## {}\n\n{}\n", breadcrumb, text))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render a comment thread as a nested markdown list.
+///
+/// `thread` holds `(depth, comment)` pairs in pre-order, i.e. every reply directly follows its
+/// parent (or an earlier sibling's subtree) with `depth` one greater than the parent's. Each entry
+/// is rendered as `- **author** (timestamp): text`, indented two spaces per level.
+fn render_comments_markdown(thread: &[(usize, CommentDetails)]) -> String {
+    let mut markdown = String::new();
+    for (depth, comment) in thread {
+        let author = comment
+            .version
+            .author_id
+            .as_deref()
+            .unwrap_or("unknown author");
+        let timestamp = comment
+            .version
+            .created_at
+            .as_deref()
+            .unwrap_or("unknown time");
+        let text = match &comment.body {
+            Some(Body {
+                storage: Some(storage),
+                ..
+            }) => storage_to_inline_text(&storage.value),
+            _ => String::new(),
+        };
+        markdown.push_str(&"  ".repeat(*depth));
+        markdown.push_str(&format!("- **{}** ({}): {}\n", author, timestamp, text));
     }
+    markdown
+}
 
-    // --- Label Management ---
+/// Deserialize headers/rows JSON strings and produce an ADF `Node::Table`.
+fn deserialize_and_create_table_node(headers: String, rows: String) -> Result<Node, String> {
+    let headers_parsed: Vec<String> = serde_json::from_str(&headers)
+        .map_err(|err| format!("invalid header passed , {:?}", err))?;
 
-    /// List labels on a page by ID (de-paginated).
-    #[query]
-    async fn list_page_labels_by_id(&self, page_id: u64) -> Result<Vec<types::Label>, String> {
-        let endpoint = format!("pages/{}/labels", page_id);
-        let response = self
-            .make_request(
-                HttpMethod::Get,
-                &endpoint,
-                vec![(LIMIT.to_string(), 250.to_string())],
-                None,
-                200,
-            )
-            .await?
-            .1;
-        let list: ListResponse<types::Label> =
-            serde_json::from_str(&response).map_err(|e| e.to_string())?;
+    let rows_parsed: Vec<Vec<Value>> =
+        serde_json::from_str(&rows).map_err(|err| format!("invalid rows passed : {:?}", err))?;
 
-        self.process_complete_response(list).await
-    }
+    create_table_node(headers_parsed, rows_parsed)
+}
 
-    /// List labels on a page by page name/space.
-    #[query]
-    async fn list_page_labels_by_name(
-        &self,
-        page_name: String,
-        space_name: String,
-    ) -> Result<Vec<Label>, String> {
-        let page_id = self.get_page_id_from_name(page_name, space_name).await?;
-        self.list_page_labels_by_id(page_id).await
+/// Alignments a table column accepts, as ADF names them.
+const COLUMN_ALIGNMENTS: [&str; 3] = ["start", "center", "end"];
+
+/// Deserialize a JSON list of column specs and check each alignment and width.
+fn parse_column_specs(columns: &str) -> Result<Vec<ColumnSpec>, String> {
+    let specs: Vec<ColumnSpec> = serde_json::from_str(columns)
+        .map_err(|err| ErrorCode::Validation.tag(format!("invalid columns passed: {}", err)))?;
+
+    for (i, spec) in specs.iter().enumerate() {
+        if let Some(align) = &spec.align
+            && !COLUMN_ALIGNMENTS.contains(&align.as_str())
+        {
+            return Err(ErrorCode::Validation.tag(format!(
+                "column {} has alignment {:?}; expected one of {}",
+                i,
+                align,
+                COLUMN_ALIGNMENTS.join(", ")
+            )));
+        }
+        if spec.width == Some(0) {
+            return Err(ErrorCode::Validation.tag(format!("column {} has a width of 0", i)));
+        }
     }
+    Ok(specs)
+}
 
-    /// List labels in a space by ID.
-    #[query]
-    async fn list_space_labels_by_id(&self, space_id: u64) -> Result<Vec<types::Label>, String> {
-        let endpoint = format!("spaces/{}/labels", space_id);
-        let response = self
-            .make_request(
-                HttpMethod::Get,
-                &endpoint,
-                vec![(LIMIT.to_string(), 250.to_string())],
-                None,
-                200,
-            )
-            .await?
-            .1;
-        let list: ListResponse<types::Label> =
-            serde_json::from_str(&response).map_err(|e| e.to_string())?;
-        self.process_complete_response(list).await
+/// Paragraph holding `text`, aligned per `spec`. Start alignment is the default and has no mark.
+fn table_cell_paragraph(text: String, spec: Option<&ColumnSpec>) -> Node {
+    let marks = match spec.and_then(|spec| spec.align.as_deref()) {
+        Some(align) if align != "start" => Some(vec![Mark {
+            r#type: "alignment".into(),
+            attrs: Some(json!({ "align": align })),
+        }]),
+        _ => None,
+    };
+    Node::Paragraph {
+        content: Some(vec![Content {
+            r#type: "text".into(),
+            text: Some(text),
+        }]),
+        marks,
     }
+}
 
-    /// List labels in a space by name.
-    #[query]
-    async fn list_space_labels_by_name(&self, space_name: String) -> Result<Vec<Label>, String> {
-        let space_id = self.get_space_id_from_name(space_name).await?;
-        self.list_space_labels_by_id(space_id).await
+/// Cell attrs for a single cell, carrying the column width from `spec` if it has one.
+fn table_cell_attrs(spec: Option<&ColumnSpec>) -> CellAttrs {
+    CellAttrs {
+        colspan: 1,
+        rowspan: 1,
+        colwidth: spec.and_then(|spec| spec.width).map(|width| vec![width]),
     }
+}
 
-    /// List labels on a blog post by ID.
-    #[query]
-    async fn list_blog_post_labels_by_id(
-        &self,
-        blog_post_id: u64,
-    ) -> Result<Vec<types::Label>, String> {
-        let endpoint = format!("blogposts/{}/labels", blog_post_id);
-        let response = self
-            .make_request(
-                HttpMethod::Get,
-                &endpoint,
-                vec![(LIMIT.to_string(), 250.to_string())],
-                None,
-                200,
+/// Internal bag for parsed table data (headers + rows) coming from IMFS files.
+struct TableFields {
+    headers: Vec<String>,
+    rows: Vec<Vec<Value>>,
+}
+
+/// Fetch a file via IMFS and convert it into table headers/rows.
+///
+/// The file is expected to be a JSON array of serialized maps (one per row). Keys are used as
+/// headers; values become cells. Ordering is derived from `BTreeMap`'s sorted key iteration.
+async fn get_table_from_imfs_file(file_descriptor: String) -> Result<TableFields, String> {
+    let serialized_response = get_imfs_file_content(file_descriptor).await?;
+    let row_list: Vec<String> = serde_json::from_str(&serialized_response).map_err(|err| {
+        format!(
+            "invalid file response , deserialization failed at first try : {}",
+            err.to_string()
+        )
+    })?;
+    let mut col_row_map: Vec<BTreeMap<String, Value>> = Vec::new();
+
+    for row in row_list {
+        let row_map: BTreeMap<String, Value> = serde_json::from_str(&row).map_err(|err| {
+            format!(
+                "invalid data, failed to deserialize this map: {}",
+                err.to_string()
             )
-            .await?
-            .1;
-        let list: ListResponse<types::Label> =
-            serde_json::from_str(&response).map_err(|e| e.to_string())?;
-        self.process_complete_response(list).await
+        })?;
+        col_row_map.push(row_map);
     }
 
-    /// List labels on a blog post by name/space.
-    #[query]
-    async fn list_blog_post_labels_by_name(
-        &self,
-        blog_post_name: String,
-        space_name: String,
-    ) -> Result<Vec<Label>, String> {
-        let blog_post_id = self
-            .get_blog_post_id_from_name(blog_post_name, space_name)
-            .await?;
-        self.list_blog_post_labels_by_id(blog_post_id).await
+    let mut is_first_iteration = true;
+    let mut header_vec: Vec<String> = Vec::new();
+    let mut row_vec: Vec<Vec<Value>> = Vec::new();
+
+    for row_map in col_row_map {
+        let mut row = Vec::new();
+        // NOTE: relying on rust BTreeMap's property where this iterator visits keys in sorted
+        // order, therefore i can assume that row values will always be in same order w.r.t. the
+        // header
+        for (i, (key, value)) in row_map.into_iter().enumerate() {
+            if is_first_iteration {
+                header_vec.push(key.clone());
+            } else {
+                let Some(header_value) = header_vec.get(i) else {
+                    return Err(format!(
+                        "invalid data received from file, index out of bound"
+                    ));
+                };
+                if !key.eq(header_value) {
+                    return Err(format!(
+                        "invalid data received from file, header value different for different row"
+                    ));
+                }
+            }
+            row.push(value);
+        }
+        if is_first_iteration {
+            is_first_iteration = false;
+        }
+        row_vec.push(row);
     }
+    Ok(TableFields {
+        headers: header_vec,
+        rows: row_vec,
+    })
+}
 
-    // --- Comment Management ---
+/// Build an ADF `Node::Table` from string headers and JSON values.
+///
+/// `headers` must be the same length as each row. Non-string JSON values are stringified.
+fn create_table_node(headers: Vec<String>, rows: Vec<Vec<Value>>) -> Result<Node, String> {
+    create_table_node_with_columns(headers, rows, &[])
+}
 
-    /// Create a **footer** comment on a page by page ID (string-typed ID for compatibility).
+/// Like [`create_table_node`], with the alignment and width of each column taken from
+/// `columns`. Columns past the end of `columns` keep the defaults; when every column has a
+/// width the table is as wide as their sum.
+fn create_table_node_with_columns(
+    headers: Vec<String>,
+    rows: Vec<Vec<Value>>,
+    columns: &[ColumnSpec],
+) -> Result<Node, String> {
+    if columns.len() > headers.len() {
+        return Err(ErrorCode::Validation.tag(format!(
+            "{} column specs given for {} columns",
+            columns.len(),
+            headers.len()
+        )));
+    }
+
+    let table_width = if !headers.is_empty() && columns.len() == headers.len() {
+        columns
+            .iter()
+            .map(|spec| spec.width.map(f64::from))
+            .sum::<Option<f64>>()
+    } else {
+        None
+    };
+
+    let mut header_columns = Vec::new();
+    for (i, header_value) in headers.into_iter().enumerate() {
+        let header = Node::TableHeader {
+            attrs: table_cell_attrs(columns.get(i)),
+            content: vec![table_cell_paragraph(header_value, columns.get(i))],
+        };
+        header_columns.push(header);
+    }
+
+    let header_row = Node::TableRow {
+        content: header_columns,
+    };
+
+    let mut table_rows = Vec::new();
+    table_rows.push(header_row);
+
+    for row in rows {
+        let mut row_cells = Vec::new();
+        for (i, col) in row.into_iter().enumerate() {
+            let text = match col {
+                Value::String(s) => s,
+                _ => col.to_string(),
+            };
+            let cell = Node::TableCell {
+                attrs: table_cell_attrs(columns.get(i)),
+                content: vec![table_cell_paragraph(text, columns.get(i))],
+            };
+            row_cells.push(cell);
+        }
+        table_rows.push(Node::TableRow { content: row_cells });
+    }
+
+    Ok(Node::Table {
+        attrs: TableAttrs {
+            layout: "default".into(),
+            width: table_width,
+            local_id: None,
+            is_number_column_enabled: Some(false),
+        },
+        content: table_rows,
+    })
+}
+
+/// What to do about the errors the tools return, checked in order by `diagnose`: hints for
+/// specific messages first, then one per error code.
+const DIAGNOSIS_RULES: &[DiagnosisRule] = &[
+    DiagnosisRule {
+        code: None,
+        patterns: &["config field"],
+        guidance: "Set confluence_url (e.g. https://<site>.atlassian.net), email and api_key in the MCP secrets.",
+    },
+    DiagnosisRule {
+        code: None,
+        patterns: &[TOOL_DISABLED],
+        guidance: "This tool is listed in disabled_tools for this deployment. Use another tool, or ask the administrator to enable it.",
+    },
+    DiagnosisRule {
+        code: None,
+        patterns: &["Space not found"],
+        guidance: "Verify the space exists and its name is spelled as in Confluence; list_spaces or search_spaces show the spaces the configured user can see.",
+    },
+    DiagnosisRule {
+        code: None,
+        patterns: &[
+            "page not found for the given page_name",
+            "blog post not found",
+        ],
+        guidance: "Titles are matched exactly within the space; check the title and space name, or find the page with search_content and use its id.",
+    },
+    DiagnosisRule {
+        code: None,
+        patterns: &["PaginationLimitExceeded"],
+        guidance: "The listing is too large to fetch in one call; narrow it, e.g. to one space or a subtree, or search with a more specific query.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::Auth),
+        patterns: &["HTTP 401", "HTTP 403"],
+        guidance: "Check the credentials: email and api_key must belong to the same Atlassian account, and the API token may have expired or been revoked, so create a new one and update the MCP secrets. A 403 means the account lacks permission on the space or the page is restricted.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::NotFound),
+        patterns: &["HTTP 404"],
+        guidance: "Verify the page, space or content id exists on this site; content the configured user can't view, including restricted pages, is reported as not found.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::RateLimited),
+        patterns: &["HTTP 429"],
+        guidance: "Reduce the call rate: wait about a minute before retrying, and fetch pages in bulk rather than one by one.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::Conflict),
+        patterns: &[],
+        guidance: "The page changed since it was read, or a page with that title already exists in the space; re-read the page and retry on its current version, or choose another title.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::Validation),
+        patterns: &[],
+        guidance: "Confluence rejected the request; check the ids, titles and body content against the tool's parameters.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::Timeout),
+        patterns: &[],
+        guidance: "Confluence took too long to answer; retry, and split large requests such as bulk imports into smaller ones.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::Upstream),
+        patterns: &[],
+        guidance: "Confluence failed or answered unexpectedly; retry shortly, and check the Atlassian status page if it keeps failing.",
+    },
+];
+
+#[smart_contract(tool_gate = ensure_tool_enabled)]
+impl Confluence for ConfluenceContractState {
+    /// Initialize `ConfluenceContractState` with an empty `Secrets<ConfluenceConfig>`.
+    #[constructor]
+    fn new() -> Result<Self, String>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            secrets: Secrets::<ConfluenceConfig>::new(),
+            responses: ResponseCache::default(),
+        })
+    }
+
+    /// List all spaces (internally traverses pagination up to Confluence max page size).
     #[query]
-    async fn create_footer_comment_on_page_by_page_id(
+    async fn list_spaces(&self) -> Result<Vec<SpaceSummary>, String> {
+        let endpoint = "/spaces";
+        let response: ListResponse<SpaceSummary> = serde_json::from_str(
+            &self
+                .make_request(
+                    HttpMethod::Get,
+                    endpoint,
+                    vec![(LIMIT.to_string(), 250.to_string())],
+                    None,
+                    200,
+                )
+                .await?
+                .1,
+        )
+        .map_err(|err| err.to_string())?;
+        self.process_complete_response(response).await
+    }
+
+    /// Create a space via the v2 spaces endpoint after validating the key format.
+    #[query]
+    async fn create_space(
         &self,
-        page_id: String,
-        content: String,
-    ) -> Result<types::CommentDetails, String> {
-        let req_body = types::CreateCommentRequest {
-            page_id: Some(page_id.parse().unwrap()),
-            blog_post_id: None,
-            parent_comment_id: None,
-            body: types::StorageBody {
-                value: content,
-                representation: "storage".to_string(),
-            },
-            space_id: None,
-        };
-        let body = serde_json::to_string(&req_body).map_err(|e| e.to_string())?;
+        key: String,
+        name: String,
+        description: Option<String>,
+    ) -> Result<SpaceSummary, String> {
+        let body = create_space_request_body(&key, &name, description.as_deref())?;
+        let (status, response) =
+            self.send_request(HttpMethod::Post, "spaces", vec![], Some(body))?;
+        if status != 200 && status != 201 {
+            return Err(http_error(status, &response));
+        }
+        serde_json::from_str(&response).map_err(|e| e.to_string())
+    }
+
+    /// Archive a space by ID.
+    ///
+    /// The v2 API cannot change a space's status, so the key is resolved through v2 and the
+    /// archival is done with the v1 space update endpoint.
+    #[query]
+    async fn archive_space(&self, space_id: u64) -> Result<SpaceSummary, String> {
+        let endpoint = format!("spaces/{}", space_id);
         let response = self
-            .make_request(HttpMethod::Post, "footer-comments", vec![], Some(body), 201)
+            .make_request(HttpMethod::Get, &endpoint, vec![], None, 200)
             .await?
             .1;
-        serde_json::from_str(&response).map_err(|e| e.to_string())
+        let mut space: SpaceSummary = serde_json::from_str(&response).map_err(|e| e.to_string())?;
+
+        let body = json!({ "name": space.name, "status": "archived" }).to_string();
+        let endpoint = format!("space/{}", space.key);
+        let (status, response) =
+            self.send_request_to(REST_V1, HttpMethod::Put, &endpoint, vec![], Some(body))?;
+        if status != 200 {
+            return Err(http_error(status, &response));
+        }
+
+        space.status = "archived".to_string();
+        Ok(space)
     }
 
-    /// Create a **footer** comment on a page by resolving page name/space.
+    // --- Page CRUD ---
+
+    /// Create a page by `space_id`, wrapping the provided `content` in a simple ADF paragraph.
     #[query]
-    async fn create_footer_comment_on_page_by_page_name(
+    async fn create_page_by_space_id(
         &self,
-        page_name: String,
-        space_name: String,
+        space_id: u64,
+        title: String,
         content: String,
-    ) -> Result<CommentDetails, String> {
-        let page_id = self.get_page_id_from_name(page_name, space_name).await?;
-        self.create_footer_comment_on_page_by_page_id(page_id.to_string(), content)
-            .await
+    ) -> Result<responses::CreatePageResponse, String> {
+        let document = Document {
+            r#type: DOC.to_string(),
+            content: vec![create_paragraph_node(content)],
+            version: None,
+        };
+        self.create_page(space_id, title, None, document).await
     }
 
-    /// Reply to a **footer** comment by parent ID.
+    /// Create a page from a content template (fetched through the v1 API) after filling in its
+    /// `{{key}}` placeholders. The page keeps the template's storage-format markup.
     #[query]
-    async fn reply_footer_comment(
+    async fn create_page_from_template(
         &self,
-        parent_comment_id: u64,
-        content: String,
-    ) -> Result<types::CommentDetails, String> {
-        let req_body = types::CreateCommentRequest {
-            page_id: None,
-            blog_post_id: None,
-            parent_comment_id: Some(parent_comment_id),
-            body: types::StorageBody {
-                value: content,
-                representation: "storage".to_string(),
+        space_id: u64,
+        template_id: String,
+        title: String,
+        substitutions: String,
+    ) -> Result<CreatePageResponse, String> {
+        // Reject bad substitutions before any request is made.
+        apply_template_substitutions("", &substitutions)?;
+
+        let endpoint = format!("template/{}", template_id);
+        let query_params = vec![("expand".to_string(), "body.storage".to_string())];
+        let (status, response) =
+            self.send_request_to(REST_V1, HttpMethod::Get, &endpoint, query_params, None)?;
+        if status != 200 {
+            return Err(http_error(status, &response));
+        }
+        let content =
+            apply_template_substitutions(&parse_template_storage(&response)?, &substitutions)?;
+
+        let body = serde_json::json!({
+            "spaceId": space_id,
+            "status": "current",
+            "title": title,
+            "body": {
+                "representation": STORAGE,
+                "value": content,
             },
-            space_id: None,
-        };
-        let body = serde_json::to_string(&req_body).map_err(|e| e.to_string())?;
+        });
         let response = self
-            .make_request(HttpMethod::Post, "footer-comments", vec![], Some(body), 201)
+            .make_request(
+                HttpMethod::Post,
+                "pages",
+                vec![],
+                Some(body.to_string()),
+                200,
+            )
             .await?
             .1;
-        serde_json::from_str(&response).map_err(|e| e.to_string())
+        let create_content_response: CreateContentDetails =
+            serde_json::from_str(&response).map_err(|e| e.to_string())?;
+
+        Ok(page_created(create_content_response))
     }
 
-    /// List **direct** footer comments on a page by ID (body in storage format).
+    /// Turn a page into a reusable space template (v1 template API), keeping its storage-format
+    /// markup as the template body.
     #[query]
-    async fn list_page_direct_footer_comments_by_id(
+    async fn save_page_as_template(
         &self,
         page_id: u64,
-    ) -> Result<Vec<types::CommentDetails>, String> {
-        let endpoint = format!("pages/{}/footer-comments", page_id);
-        let query_params = vec![
-            ("body-format".to_string(), "storage".to_string()),
-            (LIMIT.to_string(), 250.to_string()),
-        ];
+        template_name: String,
+        space_id: u64,
+    ) -> Result<String, String> {
+        let page = self.get_page_by_id(page_id, false, None, None).await?;
+
+        let endpoint = format!("spaces/{}", space_id);
         let response = self
-            .make_request(HttpMethod::Get, &endpoint, query_params, None, 200)
+            .make_request(HttpMethod::Get, &endpoint, vec![], None, 200)
             .await?
             .1;
-        let list: ListResponse<types::CommentDetails> =
-            serde_json::from_str(&response).map_err(|e| e.to_string())?;
-        self.process_complete_response(list).await
+        let space: SpaceSummary = serde_json::from_str(&response).map_err(|e| e.to_string())?;
+
+        let body = page_template_request_body(&template_name, &space.key, &page)?;
+        let (status, response) =
+            self.send_request_to(REST_V1, HttpMethod::Post, "template", vec![], Some(body))?;
+        if status != 200 {
+            return Err(http_error(status, &response));
+        }
+        parse_template_id(&response)
     }
 
-    /// List **direct** footer comments on a page by name/space.
+    /// Create a page **with table** under a named parent page within a space.
     #[query]
-    async fn list_page_direct_footer_comments_by_name(
+    async fn create_page_with_table_by_space_name_with_parent_page(
         &self,
-        page_name: String,
         space_name: String,
-    ) -> Result<Vec<CommentDetails>, String> {
-        let page_id = self.get_page_id_from_name(page_name, space_name).await?;
-        self.list_page_direct_footer_comments_by_id(page_id).await
+        title: String,
+        parent_page_name: String,
+        headers: String,
+        rows: String,
+    ) -> Result<CreatePageResponse, String> {
+        let space_id = self.get_space_id_from_name(space_name.clone()).await?;
+        let parent_page_id = self
+            .get_page_id_from_name(parent_page_name, space_name)
+            .await?;
+        self.create_table(space_id, title, Some(parent_page_id), headers, rows)
+            .await
     }
 
-    /// Update a comment by ID (increments version automatically).
+    /// Create a page **with table** in a space by name (no parent).
     #[query]
-    async fn update_comment(
+    async fn create_page_with_table_by_space_name_without_parent_page(
         &self,
-        comment_id: u64,
-        new_content: String,
-    ) -> Result<types::CommentDetails, String> {
-        let comment_details = self.get_footer_comment_by_id(comment_id).await?;
-        let req_body = types::UpdateCommentRequest {
-            id: comment_id,
-            version: types::PageVersion {
-                number: comment_details.version.number + 1,
-                message: None,
-            },
-            body: types::StorageBody {
-                value: new_content,
-                representation: "storage".to_string(),
-            },
-        };
-        let body = serde_json::to_string(&req_body).map_err(|e| e.to_string())?;
-        let endpoint = format!("footer-comments/{}", comment_id);
-        let response = self
-            .make_request(HttpMethod::Put, &endpoint, vec![], Some(body), 200)
-            .await?
-            .1;
-        serde_json::from_str(&response).map_err(|e| e.to_string())
+        space_name: String,
+        title: String,
+        headers: String,
+        rows: String,
+    ) -> Result<CreatePageResponse, String> {
+        let space_id = self.get_space_id_from_name(space_name).await?;
+        self.create_table(space_id, title, None, headers, rows)
+            .await
     }
 
-    /// Delete a comment by ID. Returns success flag and message.
+    /// Create a page with a table laid out per `columns` (a JSON list of `ColumnSpec`),
+    /// optionally under a parent page.
     #[query]
-    async fn delete_comment(&self, comment_id: u64) -> Result<types::DeleteResult, String> {
-        let endpoint = format!("footer-comments/{}", comment_id);
-        let response = self
-            .make_request(HttpMethod::Delete, &endpoint, vec![], None, 204)
-            .await?;
-        if response.0 == 204 {
-            return Ok(DeleteResult {
-                success: true,
-                message: format!("Comment with {comment_id} Deleted"),
-            });
-        } else {
-            return Ok(DeleteResult {
-                success: false,
-                message: response.1,
-            });
-        }
+    async fn create_page_with_rich_table(
+        &self,
+        space_name: String,
+        title: String,
+        parent_page_name: Option<String>,
+        headers: String,
+        rows: String,
+        columns: String,
+    ) -> Result<CreatePageResponse, String> {
+        let headers: Vec<String> = serde_json::from_str(&headers)
+            .map_err(|err| format!("invalid header passed , {:?}", err))?;
+        let rows: Vec<Vec<Value>> = serde_json::from_str(&rows)
+            .map_err(|err| format!("invalid rows passed : {:?}", err))?;
+        let columns = parse_column_specs(&columns)?;
+        let table = create_table_node_with_columns(headers, rows, &columns)?;
+
+        let space_id = self.get_space_id_from_name(space_name.clone()).await?;
+        let parent_id = match parent_page_name {
+            Some(parent_page_name) => Some(
+                self.get_page_id_from_name(parent_page_name, space_name)
+                    .await?,
+            ),
+            None => None,
+        };
+        self.create_table_page(space_id, title, parent_id, table)
+            .await
     }
 
-    /// Create a **footer** comment on a blog post by ID.
+    /// Import an IMFS file and create a **table page** under a parent page.
     #[query]
-    async fn create_footer_comment_on_blog_post_by_id(
+    async fn import_file_and_create_page_with_table_with_parent_page(
         &self,
-        blog_post_id: u64,
-        content: String,
-    ) -> Result<types::CommentDetails, String> {
-        let req_body = types::CreateCommentRequest {
-            page_id: None,
-            blog_post_id: Some(blog_post_id),
-            parent_comment_id: None,
-            body: types::StorageBody {
-                value: content,
-                representation: "storage".to_string(),
-            },
-            space_id: None,
-        };
-        let body = serde_json::to_string(&req_body).map_err(|e| e.to_string())?;
-        let response = self
-            .make_request(HttpMethod::Post, "footer-comments", vec![], Some(body), 201)
-            .await?
-            .1;
-        serde_json::from_str(&response).map_err(|e| e.to_string())
+        space_name: String,
+        title: String,
+        parent_page_name: String,
+        file_descriptor: String,
+    ) -> Result<CreatePageResponse, String> {
+        let table_fields = get_table_from_imfs_file(file_descriptor).await?;
+        let header_serialized = serde_json::to_string(&table_fields.headers).unwrap(); // safe
+        // to unwrap since deserializing
+        let row_serialized = serde_json::to_string(&table_fields.rows).unwrap();
+
+        self.create_page_with_table_by_space_name_with_parent_page(
+            space_name,
+            title,
+            parent_page_name,
+            header_serialized,
+            row_serialized,
+        )
+        .await
     }
 
-    /// Create a **footer** comment on a blog post by name/space.
+    /// Import an IMFS file and create a **table page** (no parent).
     #[query]
-    async fn create_footer_comment_on_blog_post_by_name(
+    async fn import_file_and_create_page_with_table_without_parent_page(
         &self,
-        blog_post_name: String,
         space_name: String,
-        content: String,
-    ) -> Result<CommentDetails, String> {
-        let blog_post_id = self
-            .get_blog_post_id_from_name(blog_post_name, space_name)
-            .await?;
-        self.create_footer_comment_on_blog_post_by_id(blog_post_id, content)
-            .await
+        title: String,
+        file_descriptor: String,
+    ) -> Result<CreatePageResponse, String> {
+        let table_fields = get_table_from_imfs_file(file_descriptor).await?;
+        let header_serialized = serde_json::to_string(&table_fields.headers).unwrap(); // safe
+        // to unwrap since deserializing
+        let row_serialized = serde_json::to_string(&table_fields.rows).unwrap();
+
+        self.create_page_with_table_by_space_name_without_parent_page(
+            space_name,
+            title,
+            header_serialized,
+            row_serialized,
+        )
+        .await
     }
 
-    /// Get a **footer** comment by ID (storage body format).
+    /// Append a **table** to an existing page resolved by name/space, preserving existing content.
     #[query]
-    async fn get_footer_comment_by_id(
+    async fn append_table_to_page_by_page_name(
         &self,
-        comment_id: u64,
-    ) -> Result<types::CommentDetails, String> {
-        let endpoint = format!("footer-comments/{}", comment_id);
-        let query_params = vec![("body-format".to_string(), "storage".to_string())];
+        page_name: String,
+        space_name: String,
+        headers: String,
+        rows: String,
+    ) -> Result<ContentDetails, String> {
+        let page_id = self.get_page_id_from_name(page_name, space_name).await?;
+        let page_details = self.get_page_adf_doc_format(page_id).await?;
+        let Some(page_body) = page_details.body else {
+            return Err("page body none".to_string());
+        };
+
+        let Some(document_str) = page_body.atlas_doc_format else {
+            return Err("page body received isn't of atlas doc format".to_string());
+        };
+        let mut document: Document = serde_json::from_str(&document_str.value).map_err(|err| {
+            format!(
+                "Unable to deserialize Document json to Document Struct {}",
+                err.to_string()
+            )
+        })?;
+
+        let table = deserialize_and_create_table_node(headers, rows)?;
+
+        document.content.push(Node::Paragraph {
+            content: Some(vec![Content {
+                r#type: "text".into(),
+                text: Some("Table generated from Icarus: ".into()),
+            }]),
+            marks: None,
+        });
+        document.content.push(table);
+
+        let version_number = page_details.version.number + 1;
+        let req_body = types::UpdateContentRequestAtlasDocFormat {
+            id: page_id,
+            status: "current",
+            title: &page_details.title,
+            space_id: None,
+            body: AtlasDocFormatBodyStr {
+                value: serde_json::to_string(&document).map_err(|err| err.to_string())?,
+                representation: ATLAS_DOC_FORMAT.to_string(),
+            },
+            version: types::PageVersion {
+                number: version_number,
+                message: None,
+                author_id: None,
+                created_at: None,
+            },
+        };
+        let body = serde_json::to_string(&req_body).map_err(|e| e.to_string())?;
+        let endpoint = format!("/pages/{}", page_id);
         let response = self
-            .make_request(HttpMethod::Get, &endpoint, query_params, None, 200)
+            .make_request(HttpMethod::Put, &endpoint, vec![], Some(body), 200)
             .await?
             .1;
         serde_json::from_str(&response).map_err(|e| e.to_string())
     }
 
-    /// List **direct** footer comments on a blog post by ID (body in storage format).
+    /// Create a page by `space_name` using ADF paragraph content.
     #[query]
-    async fn list_blog_post_direct_footer_comments_by_id(
+    async fn create_page_by_space_name(
         &self,
-        blog_post_id: u64,
-    ) -> Result<Vec<types::CommentDetails>, String> {
-        let endpoint = format!("blogposts/{}/footer-comments", blog_post_id);
-        let query_params = vec![
-            ("body-format".to_string(), "storage".to_string()),
-            (LIMIT.to_string(), 250.to_string()),
-        ];
-        let response = self
-            .make_request(HttpMethod::Get, &endpoint, query_params, None, 200)
-            .await?
-            .1;
-        let list: ListResponse<types::CommentDetails> =
-            serde_json::from_str(&response).map_err(|e| e.to_string())?;
-        self.process_complete_response(list).await
+        space_name: String,
+        title: String,
+        content: String,
+    ) -> Result<CreatePageResponse, String> {
+        let default_parent = self.secrets.config().default_parent(&space_name);
+        let space_id = self.get_space_id_from_name(space_name).await?;
+        match default_parent {
+            Some(parent_id) => {
+                self.create_page_by_space_id_with_parent_page_id(
+                    space_id, title, parent_id, content,
+                )
+                .await
+            }
+            None => self.create_page_by_space_id(space_id, title, content).await,
+        }
     }
 
-    /// List **direct** footer comments on a blog post by name/space.
+    /// Append text to an existing page by ID (storage representation).
     #[query]
-    async fn list_blog_post_direct_footer_comments_by_name(
+    async fn append_to_page_by_id(
         &self,
-        blog_post_name: String,
-        space_name: String,
-    ) -> Result<Vec<CommentDetails>, String> {
-        let blog_post_id = self
-            .get_blog_post_id_from_name(blog_post_name, space_name)
-            .await?;
-        self.list_blog_post_direct_footer_comments_by_id(blog_post_id)
+        page_id: u64,
+        content: String,
+    ) -> Result<ContentDetails, String> {
+        // get the page content for this id
+        let page = self.get_page_by_id(page_id, false, None, None).await?;
+        let original_body = get_content_from_body(page.body);
+
+        // append to the content , and then send the update
+        let new_content = format!("{original_body}\n{content}");
+        self.update_page_by_id(page_id, None, page.title, new_content)
             .await
     }
 
-    /// Get children (replies) of a comment by ID.
+    /// Append text to a page by ID only if its text isn't already there, so re-running an
+    /// append step leaves the page (and its version) untouched.
     #[query]
-    async fn get_comment_children(&self, comment_id: u64) -> Result<Vec<CommentDetails>, String> {
-        let endpoint = format!("footer-comments/{}/children", comment_id);
-        let v = vec![
-            (BODY_FORMAT.to_string(), STORAGE.to_string()),
-            (LIMIT.to_string(), 250.to_string()),
-        ];
-        let response = self
-            .make_request(HttpMethod::Get, &endpoint, v, None, 200)
-            .await?
-            .1;
+    async fn append_if_absent(
+        &self,
+        page_id: u64,
+        content: String,
+    ) -> Result<ContentDetails, String> {
+        let page = self.get_page_by_id(page_id, false, None, None).await?;
+        let original_body = page
+            .body
+            .as_ref()
+            .and_then(|body| body.storage.as_ref())
+            .map(|storage| storage.value.as_str())
+            .unwrap_or("");
+
+        if content_already_present(original_body, &content) {
+            return Ok(page);
+        }
 
-        let list: ListResponse<types::CommentDetails> =
-            serde_json::from_str(&response).map_err(|err| err.to_string())?;
-        self.process_complete_response(list).await
+        let new_content = format!("{original_body}\n{content}");
+        self.update_page_by_id(page_id, None, page.title, new_content)
+            .await
     }
 
-    // --- Space Permissions ---
-
-    /// List permissions on a space by ID.
+    /// Import an IMFS file and append a **table** to a page resolved by name/space.
     #[query]
-    async fn list_space_permissions(
+    async fn import_file_and_append_table_to_page_by_page_name(
         &self,
-        space_id: u64,
-    ) -> Result<Vec<types::SpacePermission>, String> {
-        let endpoint = format!("spaces/{}/permissions", space_id);
-        let response = self
-            .make_request(
-                HttpMethod::Get,
-                &endpoint,
-                vec![(LIMIT.to_string(), 250.to_string())],
-                None,
-                200,
-            )
-            .await?
-            .1;
-        let list: ListResponse<types::SpacePermission> =
-            serde_json::from_str(&response).map_err(|e| e.to_string())?;
-        self.process_complete_response(list).await
-    }
+        file_descriptor: String,
+        page_name: String,
+        space_name: String,
+    ) -> Result<ContentDetails, String> {
+        let table_fields = get_table_from_imfs_file(file_descriptor).await?;
+        let serialized_header_vec =
+            serde_json::to_string(&table_fields.headers).map_err(|err| err.to_string())?;
+        let serialized_row_vec =
+            serde_json::to_string(&table_fields.rows).map_err(|err| err.to_string())?;
+        self.append_table_to_page_by_page_name(
+            page_name,
+            space_name,
+            serialized_header_vec,
+            serialized_row_vec,
+        )
+        .await
+    }
 
-    // --- Hierarchy ---
+    /// Append text to a page by name/space (storage representation).
+    #[query]
+    async fn append_to_page_by_page_name(
+        &self,
+        page_name: String,
+        space_name: String,
+        content: String,
+    ) -> Result<ContentDetails, String> {
+        let page_id = self.get_page_id_from_name(page_name, space_name).await?;
+        self.append_to_page_by_id(page_id, content).await
+    }
 
-    /// Get direct children of a page by ID.
+    /// Get page details by numeric ID, requesting `storage` body format.
     #[query]
-    async fn get_page_children(
+    async fn get_page_by_id(
         &self,
         page_id: u64,
-    ) -> Result<types::PageHierarchyResponse, String> {
-        let endpoint = format!("pages/{}/direct-children", page_id);
+        include_child_count: bool,
+        include_labels: Option<bool>,
+        include_restrictions: Option<bool>,
+    ) -> Result<types::ContentDetails, String> {
+        let endpoint = format!("pages/{}", page_id);
+        let query_params = vec![(BODY_FORMAT.to_string(), STORAGE.to_string())];
         let response = self
-            .make_request(HttpMethod::Get, &endpoint, vec![], None, 200)
+            .make_request(HttpMethod::Get, &endpoint, query_params, None, 200)
             .await?
             .1;
-        let list: types::ListResponse<types::PageHierarchyItem> =
+        let mut page: types::ContentDetails =
             serde_json::from_str(&response).map_err(|e| e.to_string())?;
-        Ok(types::PageHierarchyResponse {
-            total_count: list.results.len(),
-            results: list.results,
-        })
+        if include_child_count {
+            page.child_count = Some(self.count_child_pages(page_id)?);
+        }
+        if include_labels.unwrap_or(false) {
+            page.labels = Some(self.list_page_labels_by_id(page_id).await?);
+        }
+        if include_restrictions.unwrap_or(false) {
+            page.restrictions = Some(self.get_page_restrictions(page_id)?);
+        }
+        Ok(page)
     }
 
-    /// Get descendants of a page by ID (limited depth; internally de-paginates).
+    /// Get a page's storage body converted to Markdown. Elements without a Markdown
+    /// equivalent, including unknown macros, are reduced to their text.
     #[query]
-    async fn get_page_descendants_by_page_id(
+    async fn get_page_as_markdown(&self, page_id: u64) -> Result<String, String> {
+        let page = self.get_page_by_id(page_id, false, None, None).await?;
+        Ok(storage_to_markdown(&get_content_from_body(page.body)))
+    }
+
+    /// Page view count from the v1 analytics API, which only exists on plans with analytics.
+    #[query]
+    async fn get_page_views(&self, page_id: u64, from: Option<String>) -> Result<u64, String> {
+        let endpoint = format!("analytics/content/{}/views", page_id);
+        let query_params = match from.as_deref().map(str::trim) {
+            Some(from) if !from.is_empty() => vec![("fromDate".to_string(), from.to_string())],
+            _ => vec![],
+        };
+        let (status, response) =
+            self.send_request_to(REST_V1, HttpMethod::Get, &endpoint, query_params, None)?;
+        if status != 200 {
+            return Err(page_views_error(status, &response));
+        }
+        parse_page_views(&response)
+    }
+
+    /// Create a page by `space_id` and **parent page id**, using ADF paragraph content.
+    #[query]
+    async fn create_page_by_space_id_with_parent_page_id(
         &self,
-        page_id: u64,
-    ) -> Result<types::PageHierarchyResponse, String> {
-        let endpoint = format!("pages/{}/descendants", page_id);
-        let query_params = vec![
-            (LIMIT.to_string(), "250".to_string()),
-            (DEPTH.to_string(), "5".to_string()),
-        ];
+        space_id: u64,
+        title: String,
+        parent_id: u64,
+        content: String,
+    ) -> Result<CreatePageResponse, String> {
+        let document = Document {
+            r#type: DOC.to_string(),
+            content: vec![create_paragraph_node(content)],
+            version: None,
+        };
+        self.create_page(space_id, title, Some(parent_id), document)
+            .await
+    }
+
+    /// Create a page by `space_name` and **parent page name**.
+    #[query]
+    async fn create_page_by_space_name_with_parent_page_name(
+        &self,
+        space_name: String,
+        title: String,
+        parent_page_name: String,
+        content: String,
+    ) -> Result<CreatePageResponse, String> {
+        let space_id = self.get_space_id_from_name(space_name.clone()).await?;
+        let parent_page_id = self
+            .get_page_id_from_name(parent_page_name, space_name)
+            .await?;
+        self.create_page_by_space_id_with_parent_page_id(space_id, title, parent_page_id, content)
+            .await
+    }
+
+    /// Create pages one after another (ADF paragraph content), starting at `start_index`.
+    ///
+    /// On `429 Too Many Requests` the run stops instead of failing the remaining pages; call again
+    /// with the reported `resume_from` once `retry_after_secs` has passed.
+    #[query]
+    async fn create_pages_bulk(
+        &self,
+        space_id: u64,
+        pages: Vec<BulkPageInput>,
+        start_index: Option<u32>,
+    ) -> Result<BulkImportReport, String> {
+        let start_index = start_index.unwrap_or(0) as usize;
+        if start_index > pages.len() {
+            return Err(format!(
+                "start_index {} is past the end of the {} pages",
+                start_index,
+                pages.len()
+            ));
+        }
+
+        Ok(run_bulk_page_import(&pages, start_index, |index| {
+            let page = &pages[index];
+            let document = Document {
+                r#type: DOC.to_string(),
+                content: vec![create_paragraph_node(page.content.clone())],
+                version: None,
+            };
+            let body = create_page_request_body(space_id, &page.title, page.parent_id, &document)?;
+            self.send_request(HttpMethod::Post, "pages", vec![], Some(body))
+        }))
+    }
+
+    /// Get page details by `page_name` and `space_name` (storage body format).
+    #[query]
+    async fn get_page_by_name(
+        &self,
+        page_name: String,
+        space_name: String,
+    ) -> Result<ContentDetails, String> {
+        let page_id = self.get_page_id_from_name(page_name, space_name).await?;
+        let endpoint = format!("pages/{}?body-format=storage", page_id);
         let response = self
-            .make_request(HttpMethod::Get, &endpoint, query_params, None, 200)
+            .make_request(HttpMethod::Get, &endpoint, vec![], None, 200)
             .await?
             .1;
-        let list: types::ListResponse<types::PageHierarchyItem> =
-            serde_json::from_str(&response).map_err(|e| e.to_string())?;
-        let results = self.process_complete_response(list).await?;
-        Ok(types::PageHierarchyResponse {
-            total_count: results.len(),
-            results,
-        })
+        serde_json::from_str(&response).map_err(|e| e.to_string())
     }
 
-    /// Get ancestors of a page by ID.
+    /// Update page content/title by ID. If `space_id` is `None`, it is not changed.
     #[query]
-    async fn get_page_ancestors_by_page_id(
+    async fn update_page_by_id(
         &self,
         page_id: u64,
-    ) -> Result<Vec<PageAncestor>, String> {
-        let endpoint = format!("pages/{}/ancestors", page_id);
-        let query_params = vec![(LIMIT.to_string(), "250".to_string())];
+        space_id: Option<u64>,
+        new_title: String,
+        new_content: String,
+    ) -> Result<types::ContentDetails, String> {
+        let version_number = {
+            // Fetch the current version number if not provided
+            let page_details = self.get_page_by_id(page_id, false, None, None).await?;
+            page_details.version.number + 1
+        };
 
+        let req_body = types::UpdateContentRequest {
+            id: page_id,
+            status: "current",
+            title: &new_title,
+            space_id,
+            body: types::StorageBody {
+                value: new_content,
+                representation: "storage".to_string(),
+            },
+            version: types::PageVersion {
+                number: version_number,
+                message: None,
+                author_id: None,
+                created_at: None,
+            },
+        };
+        let body = serde_json::to_string(&req_body).map_err(|e| e.to_string())?;
+        let endpoint = format!("pages/{}", page_id);
         let response = self
-            .make_request(HttpMethod::Get, &endpoint, query_params, None, 200)
+            .make_request(HttpMethod::Put, &endpoint, vec![], Some(body), 200)
             .await?
             .1;
-        let list: ListResponse<PageAncestor> =
-            serde_json::from_str(&response).map_err(|e| e.to_string())?;
-
-        self.process_complete_response(list).await
+        serde_json::from_str(&response).map_err(|e| e.to_string())
     }
 
-    /// Get descendants of a page by resolving name/space.
+    /// Update page by name/space (resolves IDs) with new title/content.
     #[query]
-    async fn get_page_descendants_by_page_name(
+    async fn update_page_by_name(
         &self,
         page_name: String,
         space_name: String,
-    ) -> Result<types::PageHierarchyResponse, String> {
+        new_title: String,
+        new_content: String,
+    ) -> Result<ContentDetails, String> {
+        let space_id = self.get_space_id_from_name(space_name.clone()).await?;
         let page_id = self.get_page_id_from_name(page_name, space_name).await?;
-        self.get_page_descendants_by_page_id(page_id).await
+        self.update_page_by_id(page_id, Some(space_id), new_title, new_content)
+            .await
     }
 
-    /// Get ancestors of a page by resolving name/space.
+    /// List a page's versions, following pagination.
     #[query]
-    async fn get_page_ancestors_by_page_name(
+    async fn list_page_versions(
         &self,
-        page_name: String,
-        space_name: String,
-    ) -> Result<Vec<PageAncestor>, String> {
-        let page_id = self.get_page_id_from_name(page_name, space_name).await?;
-        self.get_page_ancestors_by_page_id(page_id).await
+        page_id: u64,
+    ) -> Result<Vec<types::PageVersionInfo>, String> {
+        let endpoint = format!("pages/{}/versions", page_id);
+        let query_params = vec![
+            (LIMIT.to_string(), 250.to_string()),
+            ("sort".to_string(), "modified-date".to_string()),
+        ];
+        let response = self
+            .make_request(HttpMethod::Get, &endpoint, query_params, None, 200)
+            .await?
+            .1;
+        let list: ListResponse<types::PageVersionInfo> =
+            serde_json::from_str(&response).map_err(|e| e.to_string())?;
+
+        self.process_complete_response(list).await
     }
 
-    /// Get all pages in a space by ID (de-paginated).
+    /// Restore an earlier version. The v2 API has no restore operation, so the old version's
+    /// title and storage body are read and published again as a new version.
     #[query]
-    async fn get_pages_in_space_by_id(&self, space_id: u64) -> Result<Vec<ContentDetails>, String> {
-        let endpoint = format!("spaces/{}/pages", space_id);
-
-        let response: ListResponse<ContentDetails> = serde_json::from_str(
-            &self
-                .make_request(
-                    HttpMethod::Get,
-                    &endpoint,
-                    vec![(LIMIT.to_string(), 250.to_string())],
-                    None,
-                    200,
-                )
-                .await?
-                .1,
-        )
-        .map_err(|e| e.to_string())?;
+    async fn restore_page_version(
+        &self,
+        page_id: u64,
+        version_number: u32,
+    ) -> Result<types::ContentDetails, String> {
+        let current = self.get_page_by_id(page_id, false, None, None).await?;
+        let endpoint = format!("pages/{}", page_id);
+        let query_params = vec![
+            (BODY_FORMAT.to_string(), STORAGE.to_string()),
+            ("version".to_string(), version_number.to_string()),
+        ];
+        let response = self
+            .make_request(HttpMethod::Get, &endpoint, query_params, None, 200)
+            .await?
+            .1;
+        let version: types::ContentDetails =
+            serde_json::from_str(&response).map_err(|e| e.to_string())?;
 
-        self.process_complete_response(response).await
+        let body = restore_version_request(page_id, &current, &version)?;
+        let response = self
+            .make_request(HttpMethod::Put, &endpoint, vec![], Some(body), 200)
+            .await?
+            .1;
+        serde_json::from_str(&response).map_err(|e| e.to_string())
     }
 
-    /// Get all pages in a space by name.
+    /// Delete a page by ID. Returns success flag and message.
     #[query]
-    async fn get_pages_in_space_by_name(
-        &self,
-        space_name: String,
-    ) -> Result<Vec<ContentDetails>, String> {
-        let space_id = self.get_space_id_from_name(space_name.clone()).await?;
-        self.get_pages_in_space_by_id(space_id)
-            .await
-            .map_err(|e| e.to_string())
+    async fn delete_page(&self, page_id: u64) -> Result<types::DeleteResult, String> {
+        let endpoint = format!("pages/{}", page_id);
+        let (status, response_text) = self
+            .make_request(HttpMethod::Delete, &endpoint, vec![], None, 204)
+            .await?;
+        if status == 204 {
+            return Ok(DeleteResult {
+                success: true,
+                message: format!("Page with {page_id} Deleted"),
+            });
+        } else {
+            return Ok(DeleteResult {
+                success: false,
+                message: response_text,
+            });
+        }
     }
 
-    /// JSON schema describing exposed tools for LLM function-calling.
+    /// Reorder a page among its siblings through the v1 move endpoint.
     #[query]
-    fn tools(&self) -> String {
-        r#"[
+    async fn reorder_page(
+        &self,
+        page_id: u64,
+        position: String,
+        target_sibling_id: Option<u64>,
+    ) -> Result<(), String> {
+        // Only an untargeted append needs the page's current parent.
+        let parent_id = if position == "append" && target_sibling_id.is_none() {
+            let page = self.get_page_by_id(page_id, false, None, None).await?;
+            page.parent_id.as_deref().map(parse_id).transpose()?
+        } else {
+            None
+        };
+
+        let endpoint = move_page_endpoint(page_id, &position, target_sibling_id, parent_id)?;
+        let (status, response) =
+            self.send_request_to(REST_V1, HttpMethod::Put, &endpoint, vec![], None)?;
+        if status != 200 {
+            return Err(http_error(status, &response));
+        }
+        Ok(())
+    }
+
+    /// Move a page under a new parent by updating its `parentId` (v2 page update).
+    ///
+    /// The page's descendants are listed first and the move is refused when the new parent is
+    /// one of them. Descendants are only known down to the depth
+    /// `get_page_descendants_by_page_id` walks.
+    #[query]
+    async fn move_page(
+        &self,
+        page_id: u64,
+        new_parent_id: u64,
+    ) -> Result<types::ContentDetails, String> {
+        let descendant_ids = self
+            .get_page_descendants_by_page_id(page_id)
+            .await?
+            .results
+            .iter()
+            .map(|descendant| parse_id(&descendant.id))
+            .collect::<Result<Vec<u64>, String>>()?;
+        check_move_into_subtree(page_id, new_parent_id, &descendant_ids)?;
+
+        let page = self.get_page_by_id(page_id, false, None, None).await?;
+        let body = move_page_request_body(&page, new_parent_id)?;
+        let endpoint = format!("pages/{}", page_id);
+        let response = self
+            .make_request(HttpMethod::Put, &endpoint, vec![], Some(body), 200)
+            .await?
+            .1;
+        serde_json::from_str(&response).map_err(|e| e.to_string())
+    }
+
+    /// Copy a page's ADF body into a new page. Within the same space the copy is created next
+    /// to the original; in another space it goes to the space root.
+    #[query]
+    async fn copy_page(
+        &self,
+        page_id: u64,
+        new_title: String,
+        target_space_id: Option<u64>,
+    ) -> Result<CreatePageResponse, String> {
+        let source = self.get_page_adf_doc_format(page_id).await?;
+        let body = copy_page_request_body(&source, &new_title, target_space_id)?;
+        let response = self
+            .make_request(HttpMethod::Post, "/pages", vec![], Some(body), 200)
+            .await?
+            .1;
+        let create_content_response: CreateContentDetails =
+            serde_json::from_str(&response).map_err(|e| e.to_string())?;
+
+        Ok(page_created(create_content_response))
+    }
+
+    /// Move several pages under one new parent, one v1 move per page.
+    ///
+    /// The new parent's ancestors are read once up front so every page can be checked for a
+    /// cycle; a failed move is recorded and the remaining pages are still attempted.
+    #[query]
+    async fn move_pages_bulk(
+        &self,
+        page_ids: Vec<u64>,
+        new_parent_id: u64,
+    ) -> Result<Vec<PageMoveResult>, String> {
+        let parent_ancestor_ids = self
+            .get_page_ancestors_by_page_id(new_parent_id)
+            .await?
+            .iter()
+            .map(|ancestor| parse_id(&ancestor.id))
+            .collect::<Result<Vec<u64>, String>>()?;
+
+        let mut results = Vec::with_capacity(page_ids.len());
+        for page_id in page_ids {
+            let outcome = self
+                .move_page_under(page_id, new_parent_id, &parent_ancestor_ids)
+                .await;
+            results.push(page_move_result(page_id, outcome));
+        }
+        Ok(results)
+    }
+
+    /// Watch a page as the configured user through the v1 watch endpoint.
+    #[query]
+    async fn watch_page(&self, page_id: u64) -> Result<WatchStatus, String> {
+        self.set_page_watch(page_id, true)
+    }
+
+    /// Stop watching a page as the configured user.
+    #[query]
+    async fn unwatch_page(&self, page_id: u64) -> Result<WatchStatus, String> {
+        self.set_page_watch(page_id, false)
+    }
+
+    /// Limit updates of a page to the configured user while an edit is in progress.
+    #[query]
+    async fn lock_page_for_editing(&self, page_id: u64) -> Result<PageRestrictions, String> {
+        let account_id = self.current_account_id()?;
+        let current = self.get_page_restrictions(page_id)?;
+        // Locking twice must not record the lock itself as the prior state
+        if self.edit_lock_prior(page_id)?.is_none() {
+            self.store_edit_lock_prior(page_id, &current.update)?;
+        }
+        self.set_page_restrictions(page_id, &locked_restrictions(current, &account_id))?;
+        self.get_page_restrictions(page_id)
+    }
+
+    /// Release a lock taken with `lock_page_for_editing`.
+    #[query]
+    async fn unlock_page(&self, page_id: u64) -> Result<PageRestrictions, String> {
+        let current = self.get_page_restrictions(page_id)?;
+        let prior = self.edit_lock_prior(page_id)?;
+        let remembered = prior.is_some();
+        self.set_page_restrictions(page_id, &unlocked_restrictions(current, prior))?;
+        if remembered {
+            let endpoint = format!("content/{}/property/{}", page_id, EDIT_LOCK_PROPERTY);
+            let (status, response) =
+                self.send_request_to(REST_V1, HttpMethod::Delete, &endpoint, vec![], None)?;
+            if status != 204 && status != 200 {
+                return Err(http_error(status, &response));
+            }
+        }
+        self.get_page_restrictions(page_id)
+    }
+
+    // --- Blog Post CRUD ---
+
+    /// Create a blog post by space ID using a simple ADF paragraph.
+    #[query]
+    async fn create_blog_post_by_space_id(
+        &self,
+        space_id: u64,
+        title: String,
+        content: String,
+    ) -> Result<CreateBlogPostResponse, String> {
+        let document = Document {
+            r#type: DOC.to_string(),
+            content: vec![create_paragraph_node(content)],
+            version: None,
+        };
+
+        let req_body = types::CreateContentRequest {
+            space_id, // safe to unwrap
+            title: &title,
+            parent_id: None,
+            body: types::AtlasDocFormatBodyStr {
+                value: serde_json::to_string(&document).map_err(|err| err.to_string())?,
+                representation: ATLAS_DOC_FORMAT.to_string(),
+            },
+        };
+        let body = serde_json::to_string(&req_body).map_err(|e| e.to_string())?;
+        let response = self
+            .make_request(HttpMethod::Post, "blogposts", vec![], Some(body), 200)
+            .await?
+            .1;
+        let blogpost_details: types::BlogPostDetails =
+            serde_json::from_str(&response).map_err(|e| e.to_string())?;
+        let body_str = get_content_from_body(blogpost_details.body);
+
+        Ok(CreateBlogPostResponse {
+            blog_post_id: blogpost_details.id,
+            title: blogpost_details.title,
+            status: blogpost_details.status,
+            space_id: blogpost_details.space_id,
+            version: blogpost_details.version,
+            body: body_str,
+            created_at: blogpost_details.created_at,
+            author_id: blogpost_details.author_id,
+        })
+    }
+
+    /// Create a blog post by space name.
+    #[query]
+    async fn create_blog_post_by_space_name(
+        &self,
+        space_name: String,
+        title: String,
+        content: String,
+    ) -> Result<CreateBlogPostResponse, String> {
+        let space_id = self.get_space_id_from_name(space_name).await?;
+        self.create_blog_post_by_space_id(space_id, title, content)
+            .await
+    }
+
+    /// Get blog post by name/space.
+    #[query]
+    async fn get_blog_post_by_name(
+        &self,
+        blog_post_name: String,
+        space_name: String,
+    ) -> Result<ContentDetails, String> {
+        let blog_post_id = self
+            .get_blog_post_id_from_name(blog_post_name, space_name)
+            .await?;
+        self.get_blog_post_by_id(blog_post_id).await
+    }
+
+    /// Get blog post by ID (storage format).
+    #[query]
+    async fn get_blog_post_by_id(
+        &self,
+        blog_post_id: u64,
+    ) -> Result<types::ContentDetails, String> {
+        let endpoint = format!("blogposts/{}", blog_post_id);
+        let query_params = vec![("body-format".to_string(), "storage".to_string())];
+        let response = self
+            .make_request(HttpMethod::Get, &endpoint, query_params, None, 200)
+            .await?
+            .1;
+        serde_json::from_str(&response).map_err(|e| e.to_string())
+    }
+
+    /// Update a blog post by ID. If `new_version_number` is `None`, the current version is fetched and incremented.
+    #[query]
+    async fn update_blog_post_by_id(
+        &self,
+        blog_post_id: u64,
+        space_id: Option<u64>,
+        new_title: String,
+        new_content: String,
+        new_version_number: Option<u32>,
+    ) -> Result<types::BlogPostDetails, String> {
+        let version_number = match new_version_number {
+            Some(num) => num,
+            None => {
+                // Fetch the current version number if not provided
+                let blog_post_details = self.get_blog_post_by_id(blog_post_id).await?;
+                blog_post_details.version.number + 1
+            }
+        };
+        let req_body = types::UpdateContentRequest {
+            id: blog_post_id,
+            status: "current",
+            title: &new_title,
+            space_id,
+            body: types::StorageBody {
+                value: new_content,
+                representation: "storage".to_string(),
+            },
+            version: types::PageVersion {
+                number: version_number,
+                message: None,
+                author_id: None,
+                created_at: None,
+            },
+        };
+        let body = serde_json::to_string(&req_body).map_err(|e| e.to_string())?;
+        let endpoint = format!("blogposts/{}", blog_post_id);
+        let response = self
+            .make_request(HttpMethod::Put, &endpoint, vec![], Some(body), 200)
+            .await?
+            .1;
+        serde_json::from_str(&response).map_err(|e| e.to_string())
+    }
+
+    /// Update a blog post by name/space, forwarding to the ID-based updater.
+    #[query]
+    async fn update_blog_post_by_name(
+        &self,
+        blog_post_name: String,
+        space_name: String,
+        new_title: String,
+        new_content: String,
+        new_version_number: Option<u32>,
+    ) -> Result<BlogPostDetails, String> {
+        let blog_post_id = self
+            .get_blog_post_id_from_name(blog_post_name, space_name)
+            .await?;
+        self.update_blog_post_by_id(
+            blog_post_id,
+            None,
+            new_title,
+            new_content,
+            new_version_number,
+        )
+        .await
+    }
+
+    /// Delete a blog post by ID. Returns success flag and message.
+    #[query]
+    async fn delete_blog_post(&self, blog_post_id: u64) -> Result<types::DeleteResult, String> {
+        let endpoint = format!("blogposts/{}", blog_post_id);
+        let (status, response_text) = self
+            .make_request(HttpMethod::Delete, &endpoint, vec![], None, 204)
+            .await?;
+        if status == 204 {
+            return Ok(DeleteResult {
+                success: true,
+                message: format!("Blog post with {blog_post_id} Deleted"),
+            });
+        } else {
+            return Ok(DeleteResult {
+                success: false,
+                message: response_text,
+            });
+        }
+    }
+
+    /// List blog posts in a space by **ID**, with pagination drained into a single result.
+    #[query]
+    async fn list_blog_posts_in_space_by_id(
+        &self,
+        space_id: u64,
+    ) -> Result<types::BlogPostHierarchyResponse, String> {
+        let endpoint = format!("spaces/{}/blogposts", space_id);
+        let response = self
+            .make_request(
+                HttpMethod::Get,
+                &endpoint,
+                vec![(LIMIT.to_string(), 250.to_string())],
+                None,
+                200,
+            )
+            .await?
+            .1;
+        let list: types::ListResponse<types::BlogPostHierarchyItem> =
+            serde_json::from_str(&response).map_err(|e| e.to_string())?;
+        let vec_list = self.process_complete_response(list).await?;
+        Ok(types::BlogPostHierarchyResponse {
+            total_count: vec_list.len(),
+            results: vec_list,
+        })
+    }
+
+    /// List blog posts in a space by **name**.
+    #[query]
+    async fn list_blog_posts_in_space_by_name(
+        &self,
+        space_name: String,
+    ) -> Result<BlogPostHierarchyResponse, String> {
+        let space_id = self.get_space_id_from_name(space_name).await?;
+        self.list_blog_posts_in_space_by_id(space_id).await
+    }
+
+    // --- Label Management ---
+
+    /// List labels on a page by ID (de-paginated).
+    #[query]
+    async fn list_page_labels_by_id(&self, page_id: u64) -> Result<Vec<types::Label>, String> {
+        let endpoint = format!("pages/{}/labels", page_id);
+        let response = self
+            .make_request(
+                HttpMethod::Get,
+                &endpoint,
+                vec![(LIMIT.to_string(), 250.to_string())],
+                None,
+                200,
+            )
+            .await?
+            .1;
+        let list: ListResponse<types::Label> =
+            serde_json::from_str(&response).map_err(|e| e.to_string())?;
+
+        self.process_complete_response(list).await
+    }
+
+    /// List labels on a page by page name/space.
+    #[query]
+    async fn list_page_labels_by_name(
+        &self,
+        page_name: String,
+        space_name: String,
+    ) -> Result<Vec<Label>, String> {
+        let page_id = self.get_page_id_from_name(page_name, space_name).await?;
+        self.list_page_labels_by_id(page_id).await
+    }
+
+    /// Add a label to a page through the v1 label endpoint (v2 labels are read-only).
+    #[query]
+    async fn add_page_label(&self, page_id: u64, label: String) -> Result<Label, String> {
+        let body = label_request_body(std::slice::from_ref(&label))?;
+        let endpoint = format!("content/{}/label", page_id);
+        let response = self
+            .make_v1_request(HttpMethod::Post, &endpoint, vec![], Some(body), 200)
+            .await?
+            .1;
+        find_added_label(&response, &label.trim().to_lowercase())
+    }
+
+    /// Remove a label from a page through the v1 label endpoint.
+    #[query]
+    async fn remove_page_label(&self, page_id: u64, label: String) -> Result<DeleteResult, String> {
+        let endpoint = format!("content/{}/label", page_id);
+        let query_params = vec![("name".to_string(), label.trim().to_lowercase())];
+        self.make_v1_request(HttpMethod::Delete, &endpoint, query_params, None, 204)
+            .await?;
+        Ok(DeleteResult {
+            success: true,
+            message: format!("Label '{}' removed from page {}", label.trim(), page_id),
+        })
+    }
+
+    /// List labels in a space by ID.
+    #[query]
+    async fn list_space_labels_by_id(&self, space_id: u64) -> Result<Vec<types::Label>, String> {
+        let endpoint = format!("spaces/{}/labels", space_id);
+        let response = self
+            .make_request(
+                HttpMethod::Get,
+                &endpoint,
+                vec![(LIMIT.to_string(), 250.to_string())],
+                None,
+                200,
+            )
+            .await?
+            .1;
+        let list: ListResponse<types::Label> =
+            serde_json::from_str(&response).map_err(|e| e.to_string())?;
+        self.process_complete_response(list).await
+    }
+
+    /// List labels in a space by name.
+    #[query]
+    async fn list_space_labels_by_name(&self, space_name: String) -> Result<Vec<Label>, String> {
+        let space_id = self.get_space_id_from_name(space_name).await?;
+        self.list_space_labels_by_id(space_id).await
+    }
+
+    /// List labels on a blog post by ID.
+    #[query]
+    async fn list_blog_post_labels_by_id(
+        &self,
+        blog_post_id: u64,
+    ) -> Result<Vec<types::Label>, String> {
+        let endpoint = format!("blogposts/{}/labels", blog_post_id);
+        let response = self
+            .make_request(
+                HttpMethod::Get,
+                &endpoint,
+                vec![(LIMIT.to_string(), 250.to_string())],
+                None,
+                200,
+            )
+            .await?
+            .1;
+        let list: ListResponse<types::Label> =
+            serde_json::from_str(&response).map_err(|e| e.to_string())?;
+        self.process_complete_response(list).await
+    }
+
+    /// List labels on a blog post by name/space.
+    #[query]
+    async fn list_blog_post_labels_by_name(
+        &self,
+        blog_post_name: String,
+        space_name: String,
+    ) -> Result<Vec<Label>, String> {
+        let blog_post_id = self
+            .get_blog_post_id_from_name(blog_post_name, space_name)
+            .await?;
+        self.list_blog_post_labels_by_id(blog_post_id).await
+    }
+
+    // --- Attachments ---
+
+    /// Upload an IMFS file as a page attachment through the v1 multipart endpoint. An
+    /// attachment with the same file name is rejected by Confluence rather than versioned.
+    #[query]
+    async fn upload_attachment(
+        &self,
+        page_id: u64,
+        file_name: String,
+        content_type: String,
+        file_descriptor: String,
+    ) -> Result<AttachmentInfo, String> {
+        let content = get_imfs_file_content(file_descriptor).await?;
+        let boundary = multipart_boundary(&content);
+        let body = attachment_multipart_body(&boundary, &file_name, &content_type, &content)?;
+
+        let config = self.secrets.config();
+        config.validate()?;
+        let url = format!(
+            "{}/{}/content/{}/child/attachment",
+            config.confluence_url, REST_V1, page_id
+        );
+        let mut headers = request_headers(&config);
+        headers.insert(
+            "Content-Type".to_string(),
+            format!("multipart/form-data; boundary={}", boundary),
+        );
+        // Confluence refuses multipart uploads without this XSRF opt-out.
+        headers.insert("X-Atlassian-Token".to_string(), "no-check".to_string());
+
+        let response = HttpClient::request(&url, HttpMethod::Post)
+            .headers(headers)
+            .body(body)
+            .send()
+            .map_err(|err| err.to_string())?;
+        let (status, response) = (response.status(), response.text());
+        if status != 200 {
+            return Err(http_error(status, &response));
+        }
+        parse_uploaded_attachment(&response)
+    }
+
+    /// List a page's attachments (v2), following pagination.
+    #[query]
+    async fn list_attachments(&self, page_id: u64) -> Result<Vec<AttachmentInfo>, String> {
+        let endpoint = format!("pages/{}/attachments", page_id);
+        let response = self
+            .make_request(
+                HttpMethod::Get,
+                &endpoint,
+                vec![(LIMIT.to_string(), 250.to_string())],
+                None,
+                200,
+            )
+            .await?
+            .1;
+        let list: ListResponse<AttachmentInfo> =
+            serde_json::from_str(&response).map_err(|e| e.to_string())?;
+        self.process_complete_response(list).await
+    }
+
+    /// Export a page to the IMFS folder `bundle_name`: its storage body as `page.storage.xml`,
+    /// each attachment under `attachments/`, and a `manifest.json` listing them, whose
+    /// descriptor is returned. IMFS files hold text, so attachments are stored as the text
+    /// their download decodes to.
+    #[query]
+    async fn export_page_bundle_to_imfs(
+        &self,
+        page_id: u64,
+        bundle_name: String,
+    ) -> Result<String, String> {
+        let folder = bundle_folder(&bundle_name)?;
+        let mut page = self.get_page_by_id(page_id, false, None, None).await?;
+        let body = get_content_from_body(page.body.take());
+        let body_descriptor =
+            write_imfs_file(format!("{}/{}", folder, BUNDLE_BODY_FILE), body).await?;
+
+        let mut attachments = Vec::new();
+        for attachment in self.list_attachments(page_id).await? {
+            let content = self.download_attachment(&attachment)?;
+            let descriptor =
+                write_imfs_file(bundle_attachment_path(&folder, &attachment), content).await?;
+            attachments.push((attachment, descriptor));
+        }
+
+        let manifest = page_bundle_manifest(&page, &folder, &body_descriptor, &attachments);
+        let manifest = serde_json::to_string_pretty(&manifest).map_err(|err| err.to_string())?;
+        write_imfs_file(format!("{}/{}", folder, BUNDLE_MANIFEST_FILE), manifest).await
+    }
+
+    // --- Reactions ---
+
+    /// Add the configured user's reaction. Reacting twice with the same emoji is not an error.
+    #[query]
+    async fn add_reaction(&self, content_id: u64, emoji: String) -> Result<(), String> {
+        let emoji_id = reaction_emoji_id(&emoji)?;
+        let target = self.reaction_target(content_id).await?;
+        let body = serde_json::to_string(&reaction_request_body(&target, emoji_id))
+            .map_err(|err| err.to_string())?;
+        let (status, response) = self.send_request_to(
+            REACTIONS_API,
+            HttpMethod::Post,
+            "reactions",
+            vec![],
+            Some(body),
+        )?;
+        if status != 200 && status != 201 {
+            return Err(http_error(status, &response));
+        }
+        Ok(())
+    }
+
+    /// Remove the configured user's reaction.
+    #[query]
+    async fn remove_reaction(&self, content_id: u64, emoji: String) -> Result<(), String> {
+        let emoji_id = reaction_emoji_id(&emoji)?;
+        let target = self.reaction_target(content_id).await?;
+        let (status, response) = self.send_request_to(
+            REACTIONS_API,
+            HttpMethod::Delete,
+            "reactions",
+            reaction_query_params(&target, emoji_id),
+            None,
+        )?;
+        if status != 200 && status != 204 {
+            return Err(http_error(status, &response));
+        }
+        Ok(())
+    }
+
+    // --- Comment Management ---
+
+    /// Create a **footer** comment on a page by page ID (string-typed ID for compatibility).
+    #[query]
+    async fn create_footer_comment_on_page_by_page_id(
+        &self,
+        page_id: String,
+        content: String,
+    ) -> Result<types::CommentDetails, String> {
+        let req_body = types::CreateCommentRequest {
+            page_id: Some(parse_id(&page_id)?),
+            blog_post_id: None,
+            parent_comment_id: None,
+            body: types::StorageBody {
+                value: content,
+                representation: "storage".to_string(),
+            },
+            space_id: None,
+        };
+        let body = serde_json::to_string(&req_body).map_err(|e| e.to_string())?;
+        let response = self
+            .make_request(HttpMethod::Post, "footer-comments", vec![], Some(body), 201)
+            .await?
+            .1;
+        serde_json::from_str(&response).map_err(|e| e.to_string())
+    }
+
+    /// Create a **footer** comment on a page by resolving page name/space.
+    #[query]
+    async fn create_footer_comment_on_page_by_page_name(
+        &self,
+        page_name: String,
+        space_name: String,
+        content: String,
+    ) -> Result<CommentDetails, String> {
+        let page_id = self.get_page_id_from_name(page_name, space_name).await?;
+        self.create_footer_comment_on_page_by_page_id(page_id.to_string(), content)
+            .await
+    }
+
+    /// Reply to a **footer** comment by parent ID.
+    #[query]
+    async fn reply_footer_comment(
+        &self,
+        parent_comment_id: u64,
+        content: String,
+    ) -> Result<types::CommentDetails, String> {
+        let req_body = types::CreateCommentRequest {
+            page_id: None,
+            blog_post_id: None,
+            parent_comment_id: Some(parent_comment_id),
+            body: types::StorageBody {
+                value: content,
+                representation: "storage".to_string(),
+            },
+            space_id: None,
+        };
+        let body = serde_json::to_string(&req_body).map_err(|e| e.to_string())?;
+        let response = self
+            .make_request(HttpMethod::Post, "footer-comments", vec![], Some(body), 201)
+            .await?
+            .1;
+        serde_json::from_str(&response).map_err(|e| e.to_string())
+    }
+
+    /// List **direct** footer comments on a page by ID (body in storage format).
+    #[query]
+    async fn list_page_direct_footer_comments_by_id(
+        &self,
+        page_id: u64,
+    ) -> Result<Vec<types::CommentDetails>, String> {
+        let endpoint = format!("pages/{}/footer-comments", page_id);
+        let query_params = vec![
+            ("body-format".to_string(), "storage".to_string()),
+            (LIMIT.to_string(), 250.to_string()),
+        ];
+        let response = self
+            .make_request(HttpMethod::Get, &endpoint, query_params, None, 200)
+            .await?
+            .1;
+        let list: ListResponse<types::CommentDetails> =
+            serde_json::from_str(&response).map_err(|e| e.to_string())?;
+        self.process_complete_response(list).await
+    }
+
+    /// List **direct** footer comments on a page by name/space.
+    #[query]
+    async fn list_page_direct_footer_comments_by_name(
+        &self,
+        page_name: String,
+        space_name: String,
+    ) -> Result<Vec<CommentDetails>, String> {
+        let page_id = self.get_page_id_from_name(page_name, space_name).await?;
+        self.list_page_direct_footer_comments_by_id(page_id).await
+    }
+
+    /// Create an **inline** comment anchored to a text selection on a page.
+    ///
+    /// `text_selection_match_count` is how often the selected text occurs in the page and
+    /// `text_selection_match_index` (0-based) picks the occurrence to anchor to; Confluence
+    /// rejects the comment unless they describe an actual occurrence.
+    #[query]
+    async fn create_inline_comment(
+        &self,
+        page_id: u64,
+        content: String,
+        text_selection: String,
+        text_selection_match_count: u32,
+        text_selection_match_index: u32,
+    ) -> Result<types::CommentDetails, String> {
+        let body = inline_comment_request_body(
+            page_id,
+            content,
+            text_selection,
+            text_selection_match_count,
+            text_selection_match_index,
+        )?;
+        let response = self
+            .make_request(HttpMethod::Post, "inline-comments", vec![], Some(body), 200)
+            .await?
+            .1;
+        serde_json::from_str(&response).map_err(|e| e.to_string())
+    }
+
+    /// List **inline** comments on a page by ID (body in storage format).
+    #[query]
+    async fn list_page_inline_comments_by_id(
+        &self,
+        page_id: u64,
+    ) -> Result<Vec<types::CommentDetails>, String> {
+        let endpoint = format!("pages/{}/inline-comments", page_id);
+        let query_params = vec![
+            (BODY_FORMAT.to_string(), STORAGE.to_string()),
+            (LIMIT.to_string(), 250.to_string()),
+        ];
+        let response = self
+            .make_request(HttpMethod::Get, &endpoint, query_params, None, 200)
+            .await?
+            .1;
+        let list: ListResponse<types::CommentDetails> =
+            serde_json::from_str(&response).map_err(|e| e.to_string())?;
+        self.process_complete_response(list).await
+    }
+
+    /// Update a comment by ID (increments version automatically).
+    #[query]
+    async fn update_comment(
+        &self,
+        comment_id: u64,
+        new_content: String,
+    ) -> Result<types::CommentDetails, String> {
+        let comment_details = self.get_footer_comment_by_id(comment_id).await?;
+        let req_body = types::UpdateCommentRequest {
+            id: comment_id,
+            version: types::PageVersion {
+                number: comment_details.version.number + 1,
+                message: None,
+                author_id: None,
+                created_at: None,
+            },
+            body: types::StorageBody {
+                value: new_content,
+                representation: "storage".to_string(),
+            },
+        };
+        let body = serde_json::to_string(&req_body).map_err(|e| e.to_string())?;
+        let endpoint = format!("footer-comments/{}", comment_id);
+        let response = self
+            .make_request(HttpMethod::Put, &endpoint, vec![], Some(body), 200)
+            .await?
+            .1;
+        serde_json::from_str(&response).map_err(|e| e.to_string())
+    }
+
+    /// Delete a comment by ID. Returns success flag and message.
+    #[query]
+    async fn delete_comment(&self, comment_id: u64) -> Result<types::DeleteResult, String> {
+        let endpoint = format!("footer-comments/{}", comment_id);
+        let response = self
+            .make_request(HttpMethod::Delete, &endpoint, vec![], None, 204)
+            .await?;
+        if response.0 == 204 {
+            return Ok(DeleteResult {
+                success: true,
+                message: format!("Comment with {comment_id} Deleted"),
+            });
+        } else {
+            return Ok(DeleteResult {
+                success: false,
+                message: response.1,
+            });
+        }
+    }
+
+    /// Create a **footer** comment on a blog post by ID.
+    #[query]
+    async fn create_footer_comment_on_blog_post_by_id(
+        &self,
+        blog_post_id: u64,
+        content: String,
+    ) -> Result<types::CommentDetails, String> {
+        let req_body = types::CreateCommentRequest {
+            page_id: None,
+            blog_post_id: Some(blog_post_id),
+            parent_comment_id: None,
+            body: types::StorageBody {
+                value: content,
+                representation: "storage".to_string(),
+            },
+            space_id: None,
+        };
+        let body = serde_json::to_string(&req_body).map_err(|e| e.to_string())?;
+        let response = self
+            .make_request(HttpMethod::Post, "footer-comments", vec![], Some(body), 201)
+            .await?
+            .1;
+        serde_json::from_str(&response).map_err(|e| e.to_string())
+    }
+
+    /// Create a **footer** comment on a blog post by name/space.
+    #[query]
+    async fn create_footer_comment_on_blog_post_by_name(
+        &self,
+        blog_post_name: String,
+        space_name: String,
+        content: String,
+    ) -> Result<CommentDetails, String> {
+        let blog_post_id = self
+            .get_blog_post_id_from_name(blog_post_name, space_name)
+            .await?;
+        self.create_footer_comment_on_blog_post_by_id(blog_post_id, content)
+            .await
+    }
+
+    /// Get a **footer** comment by ID (storage body format).
+    #[query]
+    async fn get_footer_comment_by_id(
+        &self,
+        comment_id: u64,
+    ) -> Result<types::CommentDetails, String> {
+        let endpoint = format!("footer-comments/{}", comment_id);
+        let query_params = vec![("body-format".to_string(), "storage".to_string())];
+        let response = self
+            .make_request(HttpMethod::Get, &endpoint, query_params, None, 200)
+            .await?
+            .1;
+        serde_json::from_str(&response).map_err(|e| e.to_string())
+    }
+
+    /// List **direct** footer comments on a blog post by ID (body in storage format).
+    #[query]
+    async fn list_blog_post_direct_footer_comments_by_id(
+        &self,
+        blog_post_id: u64,
+    ) -> Result<Vec<types::CommentDetails>, String> {
+        let endpoint = format!("blogposts/{}/footer-comments", blog_post_id);
+        let query_params = vec![
+            ("body-format".to_string(), "storage".to_string()),
+            (LIMIT.to_string(), 250.to_string()),
+        ];
+        let response = self
+            .make_request(HttpMethod::Get, &endpoint, query_params, None, 200)
+            .await?
+            .1;
+        let list: ListResponse<types::CommentDetails> =
+            serde_json::from_str(&response).map_err(|e| e.to_string())?;
+        self.process_complete_response(list).await
+    }
+
+    /// List **direct** footer comments on a blog post by name/space.
+    #[query]
+    async fn list_blog_post_direct_footer_comments_by_name(
+        &self,
+        blog_post_name: String,
+        space_name: String,
+    ) -> Result<Vec<CommentDetails>, String> {
+        let blog_post_id = self
+            .get_blog_post_id_from_name(blog_post_name, space_name)
+            .await?;
+        self.list_blog_post_direct_footer_comments_by_id(blog_post_id)
+            .await
+    }
+
+    /// Get children (replies) of a comment by ID.
+    #[query]
+    async fn get_comment_children(&self, comment_id: u64) -> Result<Vec<CommentDetails>, String> {
+        let endpoint = format!("footer-comments/{}/children", comment_id);
+        let v = vec![
+            (BODY_FORMAT.to_string(), STORAGE.to_string()),
+            (LIMIT.to_string(), 250.to_string()),
+        ];
+        let response = self
+            .make_request(HttpMethod::Get, &endpoint, v, None, 200)
+            .await?
+            .1;
+
+        let list: ListResponse<types::CommentDetails> =
+            serde_json::from_str(&response).map_err(|err| err.to_string())?;
+        self.process_complete_response(list).await
+    }
+
+    /// Export all footer comments of a page, replies included, as a nested markdown list.
+    ///
+    /// The thread is walked depth-first so that replies are listed under their parent comment.
+    #[query]
+    async fn export_comments_markdown(&self, page_id: u64) -> Result<String, String> {
+        let mut pending: Vec<(usize, CommentDetails)> = self
+            .list_page_direct_footer_comments_by_id(page_id)
+            .await?
+            .into_iter()
+            .rev()
+            .map(|comment| (0, comment))
+            .collect();
+        let mut thread = Vec::new();
+
+        while let Some((depth, comment)) = pending.pop() {
+            let comment_id = parse_id(&comment.id)?;
+            let children = self.get_comment_children(comment_id).await?;
+            pending.extend(children.into_iter().rev().map(|child| (depth + 1, child)));
+            thread.push((depth, comment));
+        }
+
+        if thread.is_empty() {
+            return Ok(format!("_No comments on page {}._\n", page_id));
+        }
+        Ok(render_comments_markdown(&thread))
+    }
+
+    // --- Space Permissions ---
+
+    /// List permissions on a space by ID.
+    #[query]
+    async fn list_space_permissions(
+        &self,
+        space_id: u64,
+    ) -> Result<Vec<types::SpacePermission>, String> {
+        let endpoint = format!("spaces/{}/permissions", space_id);
+        let response = self
+            .make_request(
+                HttpMethod::Get,
+                &endpoint,
+                vec![(LIMIT.to_string(), 250.to_string())],
+                None,
+                200,
+            )
+            .await?
+            .1;
+        let list: ListResponse<types::SpacePermission> =
+            serde_json::from_str(&response).map_err(|e| e.to_string())?;
+        self.process_complete_response(list).await
+    }
+
+    // --- Hierarchy ---
+
+    /// Get direct children of a page by ID.
+    #[query]
+    async fn get_page_children(
+        &self,
+        page_id: u64,
+    ) -> Result<types::PageHierarchyResponse, String> {
+        let endpoint = format!("pages/{}/direct-children", page_id);
+        let response = self
+            .make_request(HttpMethod::Get, &endpoint, vec![], None, 200)
+            .await?
+            .1;
+        let list: types::ListResponse<types::PageHierarchyItem> =
+            serde_json::from_str(&response).map_err(|e| e.to_string())?;
+        Ok(types::PageHierarchyResponse {
+            total_count: list.results.len(),
+            results: list.results,
+        })
+    }
+
+    /// Get descendants of a page by ID (limited depth; internally de-paginates).
+    #[query]
+    async fn get_page_descendants_by_page_id(
+        &self,
+        page_id: u64,
+    ) -> Result<types::PageHierarchyResponse, String> {
+        let endpoint = format!("pages/{}/descendants", page_id);
+        let query_params = vec![
+            (LIMIT.to_string(), "250".to_string()),
+            (DEPTH.to_string(), "5".to_string()),
+        ];
+        let response = self
+            .make_request(HttpMethod::Get, &endpoint, query_params, None, 200)
+            .await?
+            .1;
+        let list: types::ListResponse<types::PageHierarchyItem> =
+            serde_json::from_str(&response).map_err(|e| e.to_string())?;
+        let results = self.process_complete_response(list).await?;
+        Ok(types::PageHierarchyResponse {
+            total_count: results.len(),
+            results,
+        })
+    }
+
+    /// Export a page subtree as plain text for RAG ingestion.
+    ///
+    /// Pages are visited depth first from `page_id` (which counts towards `max_pages`), and each
+    /// is rendered as `## Root > ... > Title` followed by its body flattened to text. Descendants
+    /// are only known down to the depth `get_page_descendants_by_page_id` walks, and non-page
+    /// content such as whiteboards is skipped.
+    #[query]
+    async fn export_subtree_text(&self, page_id: u64, max_pages: u32) -> Result<String, String> {
+        if max_pages == 0 {
+            return Err("max_pages must be at least 1".to_string());
+        }
+
+        let root = self.get_page_by_id(page_id, false, None, None).await?;
+        let descendants = if max_pages > 1 {
+            self.get_page_descendants_by_page_id(page_id).await?.results
+        } else {
+            Vec::new()
+        };
+
+        let mut sections = Vec::new();
+        for (id, breadcrumb) in
+            order_subtree(&root.id, &root.title, &descendants, max_pages as usize)
+        {
+            let body = if id == root.id {
+                root.body.clone()
+            } else {
+                self.get_page_by_id(parse_id(&id)?, false, None, None)
+                    .await?
+                    .body
+            };
+            sections.push((
+                breadcrumb,
+                storage_to_inline_text(&get_content_from_body(body)),
+            ));
+        }
+
+        Ok(render_subtree_text(&sections))
+    }
+
+    /// Get ancestors of a page by ID.
+    #[query]
+    async fn get_page_ancestors_by_page_id(
+        &self,
+        page_id: u64,
+    ) -> Result<Vec<PageAncestor>, String> {
+        let endpoint = format!("pages/{}/ancestors", page_id);
+        let query_params = vec![(LIMIT.to_string(), "250".to_string())];
+
+        let response = self
+            .make_request(HttpMethod::Get, &endpoint, query_params, None, 200)
+            .await?
+            .1;
+        let list: ListResponse<PageAncestor> =
+            serde_json::from_str(&response).map_err(|e| e.to_string())?;
+
+        self.process_complete_response(list).await
+    }
+
+    /// Get descendants of a page by resolving name/space.
+    #[query]
+    async fn get_page_descendants_by_page_name(
+        &self,
+        page_name: String,
+        space_name: String,
+    ) -> Result<types::PageHierarchyResponse, String> {
+        let page_id = self.get_page_id_from_name(page_name, space_name).await?;
+        self.get_page_descendants_by_page_id(page_id).await
+    }
+
+    /// Get ancestors of a page by resolving name/space.
+    #[query]
+    async fn get_page_ancestors_by_page_name(
+        &self,
+        page_name: String,
+        space_name: String,
+    ) -> Result<Vec<PageAncestor>, String> {
+        let page_id = self.get_page_id_from_name(page_name, space_name).await?;
+        self.get_page_ancestors_by_page_id(page_id).await
+    }
+
+    /// Get all pages in a space by ID (de-paginated).
+    #[query]
+    async fn get_pages_in_space_by_id(&self, space_id: u64) -> Result<Vec<ContentDetails>, String> {
+        let endpoint = format!("spaces/{}/pages", space_id);
+
+        let response: ListResponse<ContentDetails> = serde_json::from_str(
+            &self
+                .make_request(
+                    HttpMethod::Get,
+                    &endpoint,
+                    vec![(LIMIT.to_string(), 250.to_string())],
+                    None,
+                    200,
+                )
+                .await?
+                .1,
+        )
+        .map_err(|e| e.to_string())?;
+
+        self.process_complete_response(response).await
+    }
+
+    /// Get all pages in a space by name.
+    #[query]
+    async fn get_pages_in_space_by_name(
+        &self,
+        space_name: String,
+    ) -> Result<Vec<ContentDetails>, String> {
+        let space_id = self.get_space_id_from_name(space_name.clone()).await?;
+        self.get_pages_in_space_by_id(space_id)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    // --- Search ---
+
+    /// Full-text CQL search over pages and blog posts in `space_keys` (v1 search API).
+    #[query]
+    async fn search_spaces(
+        &self,
+        text: String,
+        space_keys: Vec<String>,
+        limit: u32,
+    ) -> Result<Vec<SearchHit>, String> {
+        let cql = build_space_search_cql(&text, &space_keys)?;
+        let query_params = vec![
+            ("cql".to_string(), cql),
+            (LIMIT.to_string(), limit.clamp(1, 100).to_string()),
+            ("expand".to_string(), "content.space".to_string()),
+        ];
+        let (status, response) =
+            self.send_request_to(REST_V1, HttpMethod::Get, "search", query_params, None)?;
+        if status != 200 {
+            return Err(http_error(status, &response));
+        }
+        parse_search_hits(&response)
+    }
+
+    /// Run a CQL search through the v1 search API (v2 has none), following `_links.next` until
+    /// `limit` matches (25 by default) have been collected. The CQL is percent-encoded as a query
+    /// parameter by the HTTP client.
+    #[query]
+    async fn search_content(
+        &self,
+        cql: String,
+        limit: Option<u32>,
+    ) -> Result<Vec<SearchResult>, String> {
+        if cql.trim().is_empty() {
+            return Err("cql must not be empty".to_string());
+        }
+        let limit = limit.unwrap_or(SEARCH_DEFAULT_LIMIT).max(1);
+        let query_params = vec![
+            ("cql".to_string(), cql.trim().to_string()),
+            (LIMIT.to_string(), limit.min(SEARCH_PAGE_SIZE).to_string()),
+            ("expand".to_string(), "content.space".to_string()),
+        ];
+        let (status, response) =
+            self.send_request_to(REST_V1, HttpMethod::Get, "search", query_params, None)?;
+        if status != 200 {
+            return Err(http_error(status, &response));
+        }
+
+        collect_search_results(
+            parse_search_results(&response)?,
+            limit as usize,
+            |next_url| {
+                let url = format!("{}/{}", self.secrets.config().confluence_url, next_url);
+                let response = HttpClient::request(&url, HttpMethod::Get)
+                    .headers(self.request_headers())
+                    .send()
+                    .map_err(|err| err.to_string())?;
+                if response.status() != 200 {
+                    let status = response.status();
+                    return Err(http_error(status, &response.text()));
+                }
+                parse_search_results(&response.text())
+            },
+        )
+    }
+
+    /// Label every content item a CQL search matches (at most `max`, capped at 100), one
+    /// add-labels call per item, reporting each item's outcome.
+    #[query]
+    async fn label_search_results(
+        &self,
+        cql: String,
+        labels: Vec<String>,
+        max: u32,
+    ) -> Result<BulkResult<u64>, String> {
+        let body = label_request_body(&labels)?;
+        let max = max.clamp(1, 100);
+        let query_params = vec![
+            ("cql".to_string(), cql),
+            (LIMIT.to_string(), max.to_string()),
+        ];
+        let (status, response) =
+            self.send_request_to(REST_V1, HttpMethod::Get, "search", query_params, None)?;
+        if status != 200 {
+            return Err(http_error(status, &response));
+        }
+        let page_ids = parse_search_content_ids(&response)?;
+
+        Ok(label_pages(&page_ids, max as usize, |page_id| {
+            self.add_labels(page_id, &body)
+        }))
+    }
+
+    /// Pages in a space modified at or after `since`, oldest change first (at most 100).
+    ///
+    /// The space key for the CQL search is resolved through v2, and the matched pages are then
+    /// read in one v2 call and put back in the search's order.
+    #[query]
+    async fn pages_changed_since(
+        &self,
+        space_id: u64,
+        since: String,
+    ) -> Result<Vec<ContentDetails>, String> {
+        let endpoint = format!("spaces/{}", space_id);
+        let response = self
+            .make_request(HttpMethod::Get, &endpoint, vec![], None, 200)
+            .await?
+            .1;
+        let space: SpaceSummary = serde_json::from_str(&response).map_err(|e| e.to_string())?;
+
+        let query_params = vec![
+            (
+                "cql".to_string(),
+                build_changed_since_cql(&space.key, &since)?,
+            ),
+            (LIMIT.to_string(), CHANGED_PAGES_LIMIT.to_string()),
+        ];
+        let (status, response) =
+            self.send_request_to(REST_V1, HttpMethod::Get, "search", query_params, None)?;
+        if status != 200 {
+            return Err(http_error(status, &response));
+        }
+        let page_ids = parse_search_content_ids(&response)?;
+        if page_ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let ids = page_ids
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        let query_params = vec![
+            ("id".to_string(), ids),
+            (LIMIT.to_string(), CHANGED_PAGES_LIMIT.to_string()),
+        ];
+        let response: ListResponse<ContentDetails> = serde_json::from_str(
+            &self
+                .make_request(HttpMethod::Get, "pages", query_params, None, 200)
+                .await?
+                .1,
+        )
+        .map_err(|e| e.to_string())?;
+
+        Ok(order_pages_by_ids(response.results, &page_ids))
+    }
+
+    /// JSON schema describing exposed tools for LLM function-calling.
+    #[query]
+    fn tools(&self) -> String {
+        let tools = r#"[
+  {
+    "type": "function",
+    "function": {
+      "name": "list_spaces",
+      "description": "list spaces in confluence\n",
+      "parameters": {
+        "type": "object",
+        "properties": {},
+        "required": []
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "create_space",
+      "description": "create a new space in confluence\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "key": {
+            "type": "string",
+            "description": "space key, made of uppercase letters and digits only, e.g. ENG2\n"
+          },
+          "name": {
+            "type": "string",
+            "description": "name of the space\n"
+          },
+          "description": {
+            "type": "string",
+            "description": "plain-text description of the space (optional)\n"
+          }
+        },
+        "required": [
+          "key",
+          "name"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "archive_space",
+      "description": "archive a space, providing the space id\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "space_id": {
+            "type": "integer",
+            "description": "id of the space, integer\n"
+          }
+        },
+        "required": [
+          "space_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "create_page_by_space_id",
+      "description": "create a page in confluence, prividing the space id. The content should be in Confluence Native Markup Language.\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "space_id": {
+            "type": "integer",
+            "description": "id of the space, integer\n"
+          },
+          "title": {
+            "type": "string",
+            "description": "\n"
+          },
+          "content": {
+            "type": "string",
+            "description": "\n"
+          }
+        },
+        "required": [
+          "space_id",
+          "title",
+          "content"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "create_page_from_template",
+      "description": "create a page in a space from a Confluence content template, replacing each {{key}} placeholder in the template with its value from substitutions\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "space_id": {
+            "type": "integer",
+            "description": "id of the space, integer\n"
+          },
+          "template_id": {
+            "type": "string",
+            "description": "id of the content template\n"
+          },
+          "title": {
+            "type": "string",
+            "description": "title of the new page\n"
+          },
+          "substitutions": {
+            "type": "string",
+            "description": "JSON object mapping placeholder names to values, e.g. {\"owner\": \"Jane\"} fills {{owner}}\n"
+          }
+        },
+        "required": [
+          "space_id",
+          "template_id",
+          "title",
+          "substitutions"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "save_page_as_template",
+      "description": "save a page as a reusable page template in a space, copying its content; returns the id of the new template, which create_page_from_template accepts\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "id of the page to copy, integer\n"
+          },
+          "template_name": {
+            "type": "string",
+            "description": "name of the new template\n"
+          },
+          "space_id": {
+            "type": "integer",
+            "description": "id of the space the template belongs to, integer\n"
+          }
+        },
+        "required": [
+          "page_id",
+          "template_name",
+          "space_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "create_page_with_table_by_space_name_with_parent_page",
+      "description": "create page with table structured input as content, with a specified parent page\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "space_name": {
+            "type": "string",
+            "description": "space name\n"
+          },
+          "title": {
+            "type": "string",
+            "description": "new page's title\n"
+          },
+          "parent_page_name": {
+            "type": "string",
+            "description": "name of the parent page\n"
+          },
+          "headers": {
+            "type": "string",
+            "description": "headers or column names of the table. format : [header1, header2, header3, ...] , i.e. a serialized version of a list. Use double quotes, not single quotes.\n"
+          },
+          "rows": {
+            "type": "string",
+            "description": "Complete row data as a single JSON string. Pass the entire 2D array structure as one stringified JSON value. Example value: \\\"[[\\\\\\\"row11\\\\\\\", \\\\\\\"row12\\\\\\\"], [\\\\\\\"row21\\\\\\\", \\\\\\\"row22\\\\\\\"]]\\\" (note this is ONE string, not an array). Do not pass multiple array elements - serialize everything into a single string. Use double quotes, not single quotes.\n"
+          }
+        },
+        "required": [
+          "space_name",
+          "title",
+          "parent_page_name",
+          "headers",
+          "rows"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "import_file_and_create_page_with_table_with_parent_page",
+      "description": "Reads a file from the encoded filedescriptor (like ey9320... ), and then create page with table structured input as content, with a specified parent page.\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "space_name": {
+            "type": "string",
+            "description": ""
+          },
+          "title": {
+            "type": "string",
+            "description": ""
+          },
+          "parent_page_name": {
+            "type": "string",
+            "description": ""
+          },
+          "file_descriptor": {
+            "type": "string",
+            "description": "The base64 encoded file descriptor to the file to read and upload\n"
+          }
+        },
+        "required": [
+          "space_name",
+          "title",
+          "parent_page_name",
+          "file_descriptor"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "import_file_and_create_page_with_table_without_parent_page",
+      "description": "Reads a file from the encoded filedescriptor (like ey9320... ), and then create page with table structured input as content, without a specified parent page.\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "space_name": {
+            "type": "string",
+            "description": ""
+          },
+          "title": {
+            "type": "string",
+            "description": ""
+          },
+          "file_descriptor": {
+            "type": "string",
+            "description": "The base64 encoded file descriptor to the file to read and upload\n"
+          }
+        },
+        "required": [
+          "space_name",
+          "title",
+          "file_descriptor"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "create_page_with_table_by_space_name_without_parent_page",
+      "description": "create page with table structured input as content, without a specified parent page\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "space_name": {
+            "type": "string",
+            "description": ""
+          },
+          "title": {
+            "type": "string",
+            "description": "new page's title\n"
+          },
+          "headers": {
+            "type": "string",
+            "description": "headers or column names of the table. format : [header1, header2, header3, ...] , i.e. a serialized version of a list. Use double quotes, not single quotes.\n"
+          },
+          "rows": {
+            "type": "string",
+            "description": "Complete row data as a single JSON string. Pass the entire 2D array structure as one stringified JSON value. Example value: \\\"[[\\\\\\\"row11\\\\\\\", \\\\\\\"row12\\\\\\\"], [\\\\\\\"row21\\\\\\\", \\\\\\\"row22\\\\\\\"]]\\\" (note this is ONE string, not an array). Do not pass multiple array elements - serialize everything into a single string. Use double quotes, not single quotes.\n"
+          }
+        },
+        "required": [
+          "space_name",
+          "title",
+          "headers",
+          "rows"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "create_page_with_rich_table",
+      "description": "create page with a table whose columns each have their own alignment and width, with an optional parent page\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "space_name": {
+            "type": "string",
+            "description": "space name\n"
+          },
+          "title": {
+            "type": "string",
+            "description": "new page's title\n"
+          },
+          "parent_page_name": {
+            "type": "string",
+            "description": "name of the parent page (optional)\n"
+          },
+          "headers": {
+            "type": "string",
+            "description": "headers or column names of the table, as a serialized JSON list of strings, e.g. [\"Name\", \"Cost\"]\n"
+          },
+          "rows": {
+            "type": "string",
+            "description": "row data as one serialized JSON 2D array, e.g. [[\"Disk\", 120], [\"RAM\", 80]]\n"
+          },
+          "columns": {
+            "type": "string",
+            "description": "layout of each column, in order, as a serialized JSON list of objects with an optional align (start, center or end) and an optional width in pixels, e.g. [{\"width\": 200}, {\"align\": \"end\", \"width\": 100}]; columns left out keep the defaults\n"
+          }
+        },
+        "required": [
+          "space_name",
+          "title",
+          "headers",
+          "rows",
+          "columns"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "append_to_page_by_id",
+      "description": "append content to page , in confluence. The content should be in Confluence Native Markup Language.\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": ""
+          },
+          "content": {
+            "type": "string",
+            "description": ""
+          }
+        },
+        "required": [
+          "page_id",
+          "content"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "append_if_absent",
+      "description": "append content to a page only if that text is not already on the page, so repeating the step does not duplicate it. The content should be in Confluence Native Markup Language.
+",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "id of the page to append to
+"
+          },
+          "content": {
+            "type": "string",
+            "description": "content to append
+"
+          }
+        },
+        "required": [
+          "page_id",
+          "content"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "import_file_and_append_table_to_page_by_page_name",
+      "description": "Reads a file from the encoded filedescriptor (like ey9320... ) and appends content in table structured format, to a given page.\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "file_descriptor": {
+            "type": "string",
+            "description": "The base64 encoded file descriptor to the file to read and upload\n"
+          },
+          "page_name": {
+            "type": "string",
+            "description": ""
+          },
+          "space_name": {
+            "type": "string",
+            "description": ""
+          }
+        },
+        "required": [
+          "file_descriptor",
+          "page_name",
+          "space_name"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "append_to_page_by_page_name",
+      "description": "append content to page , in confluence. The content should be in Confluence Native Markup Language.\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_name": {
+            "type": "string",
+            "description": ""
+          },
+          "space_name": {
+            "type": "string",
+            "description": ""
+          },
+          "content": {
+            "type": "string",
+            "description": ""
+          }
+        },
+        "required": [
+          "page_name",
+          "space_name",
+          "content"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "append_table_to_page_by_page_name",
+      "description": "append content in table structured format , to a given page.\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_name": {
+            "type": "string",
+            "description": ""
+          },
+          "space_name": {
+            "type": "string",
+            "description": ""
+          },
+          "headers": {
+            "type": "string",
+            "description": "headers or column names of the table. format : [header1, header2, header3, ...] , i.e. a serialized version of a list. Use double quotes, not single quotes.\n"
+          },
+          "rows": {
+            "type": "string",
+            "description": "Complete row data as a single JSON string. Pass the entire 2D array structure as one stringified JSON value. Example value: \\\"[[\\\\\\\"row11\\\\\\\", \\\\\\\"row12\\\\\\\"], [\\\\\\\"row21\\\\\\\", \\\\\\\"row22\\\\\\\"]]\\\" (note this is ONE string, not an array). Do not pass multiple array elements - serialize everything into a single string. Use double quotes, not single quotes.\n"
+          }
+        },
+        "required": [
+          "page_name",
+          "space_name",
+          "headers",
+          "rows"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "create_page_by_space_name",
+      "description": "create a page in confluence, providing the space name. The content should be in Confluence Native Markup Language.\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "space_name": {
+            "type": "string",
+            "description": "name of the space\n"
+          },
+          "title": {
+            "type": "string",
+            "description": "\n"
+          },
+          "content": {
+            "type": "string",
+            "description": "\n"
+          }
+        },
+        "required": [
+          "space_name",
+          "title",
+          "content"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "create_page_by_space_id_with_parent_page_id",
+      "description": "create a page in confluence, prividing the space id and parent page id. The content should be in Confluence Native Markup Language.\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "space_id": {
+            "type": "integer",
+            "description": "id of the space, integer\n"
+          },
+          "title": {
+            "type": "string",
+            "description": "\n"
+          },
+          "parent_id": {
+            "type": "integer",
+            "description": "id of the parent page\n"
+          },
+          "content": {
+            "type": "string",
+            "description": "\n"
+          }
+        },
+        "required": [
+          "space_id",
+          "title",
+          "parent_id",
+          "content"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "create_page_by_space_name_with_parent_page_name",
+      "description": "create a page in confluence, providing the space name and parent page name. The content should be in Confluence Native Markup Language.\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "space_name": {
+            "type": "string",
+            "description": "name of the space\n"
+          },
+          "title": {
+            "type": "string",
+            "description": "\n"
+          },
+          "parent_page_name": {
+            "type": "string",
+            "description": "id of the parent page\n"
+          },
+          "content": {
+            "type": "string",
+            "description": "\n"
+          }
+        },
+        "required": [
+          "space_name",
+          "title",
+          "parent_page_name",
+          "content"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "create_pages_bulk",
+      "description": "create many pages in a space in one run. If Confluence rate limits the run, it pauses and reports resume_from and retry_after_secs; call again with start_index set to resume_from after waiting.\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "space_id": {
+            "type": "integer",
+            "description": "id of the space, integer\n"
+          },
+          "pages": {
+            "type": "array",
+            "description": "pages to create, in order\n",
+            "items": {
+              "type": "object",
+              "properties": {
+                "title": {
+                  "type": "string"
+                },
+                "content": {
+                  "type": "string"
+                },
+                "parent_id": {
+                  "type": "integer",
+                  "description": "id of the parent page (optional)"
+                }
+              },
+              "required": [
+                "title",
+                "content"
+              ]
+            }
+          },
+          "start_index": {
+            "type": "integer",
+            "description": "index of the first page to create, used to resume a paused run (optional, defaults to 0)\n"
+          }
+        },
+        "required": [
+          "space_id",
+          "pages"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "get_page_by_id",
+      "description": "get page information by id\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "id of the page. get it from the get_pages_in_space or create_page function. this is an integer\n"
+          },
+          "include_child_count": {
+            "type": "boolean",
+            "description": "whether to also return child_count, the number of direct child pages, without fetching them\n"
+          },
+          "include_labels": {
+            "type": "boolean",
+            "description": "whether to also return the page's labels (optional, defaults to false)\n"
+          },
+          "include_restrictions": {
+            "type": "boolean",
+            "description": "whether to also return the users and groups the page's read and update operations are restricted to (optional, defaults to false)\n"
+          }
+        },
+        "required": [
+          "page_id",
+          "include_child_count"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "upload_attachment",
+      "description": "attach a file from IMFS to a page. Fails if the page already has an attachment with that file name\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "id of the page to attach the file to, integer\n"
+          },
+          "file_name": {
+            "type": "string",
+            "description": "file name to give the attachment, e.g. report.csv\n"
+          },
+          "content_type": {
+            "type": "string",
+            "description": "MIME type of the file, e.g. text/csv (empty for application/octet-stream)\n"
+          },
+          "file_descriptor": {
+            "type": "string",
+            "description": "The base64 encoded file descriptor to the file to read and upload\n"
+          }
+        },
+        "required": [
+          "page_id",
+          "file_name",
+          "content_type",
+          "file_descriptor"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "list_attachments",
+      "description": "list the files attached to a page, with their size, type and download link\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "id of the page, integer\n"
+          }
+        },
+        "required": [
+          "page_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "export_page_bundle_to_imfs",
+      "description": "write a page's storage-format body and all its attachments into an IMFS folder, with a manifest.json listing each file and its descriptor; returns the manifest's file descriptor\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "id of the page, integer\n"
+          },
+          "bundle_name": {
+            "type": "string",
+            "description": "IMFS folder to write the bundle to, e.g. exports/runbook-2024\n"
+          }
+        },
+        "required": [
+          "page_id",
+          "bundle_name"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "add_reaction",
+      "description": "react to a page, blog post or comment with an emoji as the configured user\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "content_id": {
+            "type": "integer",
+            "description": "id of the page, blog post or comment, integer\n"
+          },
+          "emoji": {
+            "type": "string",
+            "description": "emoji shortname, one of thumbsup, thumbsdown, clap, heart, tada, smile, eyes, white_check_mark, rocket, raised_hands\n"
+          }
+        },
+        "required": [
+          "content_id",
+          "emoji"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "remove_reaction",
+      "description": "remove the configured user's emoji reaction from a page, blog post or comment\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "content_id": {
+            "type": "integer",
+            "description": "id of the page, blog post or comment, integer\n"
+          },
+          "emoji": {
+            "type": "string",
+            "description": "emoji shortname the reaction was made with, e.g. thumbsup\n"
+          }
+        },
+        "required": [
+          "content_id",
+          "emoji"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "get_page_views",
+      "description": "get how many times a page has been viewed, optionally counting only views since a date. Needs a Confluence plan with page analytics\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "id of the page. get it from the get_pages_in_space or create_page function. this is an integer\n"
+          },
+          "from": {
+            "type": "string",
+            "description": "only count views on or after this date, formatted yyyy-mm-dd (optional, defaults to all time)\n"
+          }
+        },
+        "required": [
+          "page_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "get_page_as_markdown",
+      "description": "get the body of a page converted to Markdown (headings, lists, tables, links, emphasis and code blocks; other macros are reduced to their text)\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "id of the page. get it from the get_pages_in_space or create_page function. this is an integer\n"
+          }
+        },
+        "required": [
+          "page_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "get_page_by_name",
+      "description": "get page information by page title\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_name": {
+            "type": "string",
+            "description": "name of the page\n"
+          },
+          "space_name": {
+            "type": "string",
+            "description": "name of the space\n"
+          }
+        },
+        "required": [
+          "page_name",
+          "space_name"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "update_page_by_id",
+      "description": "update an existing page by page id. The content should be in Confluence Native Markup Language.\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "id of the page. passed as integer\n"
+          },
+          "space_id": {
+            "type": "integer",
+            "description": "id of the space. passed as integer\n"
+          },
+          "new_title": {
+            "type": "string",
+            "description": "\n"
+          },
+          "new_content": {
+            "type": "string",
+            "description": "\n"
+          }
+        },
+        "required": [
+          "page_id",
+          "new_title",
+          "new_content"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "update_page_by_name",
+      "description": "updata an existing page by page name. The content should be in Confluence Native Markup Language.\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_name": {
+            "type": "string",
+            "description": "name of the page.\n"
+          },
+          "space_name": {
+            "type": "string",
+            "description": "name of the space\n"
+          },
+          "new_title": {
+            "type": "string",
+            "description": "\n"
+          },
+          "new_content": {
+            "type": "string",
+            "description": "\n"
+          }
+        },
+        "required": [
+          "page_name",
+          "space_name",
+          "new_title",
+          "new_content"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "list_page_versions",
+      "description": "list the version history of a page: number, message, creation time and author of each version\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "id of the page. get it from the get_pages_in_space or create_page function. this is an integer\n"
+          }
+        },
+        "required": [
+          "page_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "restore_page_version",
+      "description": "restore an earlier version of a page by publishing its title and content as a new version\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "id of the page. get it from the get_pages_in_space or create_page function. this is an integer\n"
+          },
+          "version_number": {
+            "type": "integer",
+            "description": "number of the version to restore. get it from list_page_versions\n"
+          }
+        },
+        "required": [
+          "page_id",
+          "version_number"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "delete_page",
+      "description": "delete a page by id\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "id of the page. get it from the get_pages_in_space or create_page function, passed as integer\n"
+          }
+        },
+        "required": [
+          "page_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "move_page",
+      "description": "move a page under a new parent page, keeping its content. Moving a page under itself or one of its descendants is refused\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "id of the page to move, integer\n"
+          },
+          "new_parent_id": {
+            "type": "integer",
+            "description": "id of the page to move it under, integer\n"
+          }
+        },
+        "required": [
+          "page_id",
+          "new_parent_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "copy_page",
+      "description": "copy a page's content into a new page with a new title. Within the same space the copy gets the same parent as the original; copies into another space are created at its root\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "id of the page to copy, integer\n"
+          },
+          "new_title": {
+            "type": "string",
+            "description": "title of the copy\n"
+          },
+          "target_space_id": {
+            "type": "integer",
+            "description": "id of the space to create the copy in (optional, defaults to the original's space)\n"
+          }
+        },
+        "required": [
+          "page_id",
+          "new_title"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "move_pages_bulk",
+      "description": "move several pages under a new parent page, appending each as its last child; every page gets its own outcome and one failure does not stop the rest. Moving a page under itself or one of its descendants is refused\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_ids": {
+            "type": "array",
+            "items": {
+              "type": "integer"
+            },
+            "description": "ids of the pages to move, integers\n"
+          },
+          "new_parent_id": {
+            "type": "integer",
+            "description": "id of the page to move them under, integer\n"
+          }
+        },
+        "required": [
+          "page_ids",
+          "new_parent_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "watch_page",
+      "description": "start watching a page as the configured user, so they are notified of changes; does nothing if already watching\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "id of the page, integer\n"
+          }
+        },
+        "required": [
+          "page_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "unwatch_page",
+      "description": "stop watching a page as the configured user; does nothing if not watching\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "id of the page, integer\n"
+          }
+        },
+        "required": [
+          "page_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "lock_page_for_editing",
+      "description": "stop others editing a page by limiting updates to the configured user; read restrictions are kept, and unlock_page puts the previous update restrictions back\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "id of the page, integer\n"
+          }
+        },
+        "required": [
+          "page_id"
+        ]
+      }
+    }
+  },
   {
     "type": "function",
     "function": {
-      "name": "list_spaces",
-      "description": "list spaces in confluence\n",
+      "name": "unlock_page",
+      "description": "release a lock_page_for_editing lock, restoring the update restrictions from before it or, if those are unknown, letting updates inherit again\n",
       "parameters": {
         "type": "object",
-        "properties": {},
-        "required": []
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "id of the page, integer\n"
+          }
+        },
+        "required": [
+          "page_id"
+        ]
       }
     }
   },
   {
     "type": "function",
     "function": {
-      "name": "create_page_by_space_id",
-      "description": "create a page in confluence, prividing the space id. The content should be in Confluence Native Markup Language.\n",
+      "name": "reorder_page",
+      "description": "change where a page sits among its siblings: \"before\" or \"after\" a sibling page, or \"append\" to put it last (under target_sibling_id if given, which then becomes the new parent)\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "id of the page to move, integer\n"
+          },
+          "position": {
+            "type": "string",
+            "enum": [
+              "append",
+              "before",
+              "after"
+            ],
+            "description": "one of append, before, after\n"
+          },
+          "target_sibling_id": {
+            "type": "integer",
+            "description": "id of the sibling page to place it before or after, integer; required for before/after\n"
+          }
+        },
+        "required": [
+          "page_id",
+          "position"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "create_blog_post_by_space_id",
+      "description": "create a blog post in a space by providing the space_id. The content should be in Confluence Native Markup Language.\n",
       "parameters": {
         "type": "object",
         "properties": {
           "space_id": {
             "type": "integer",
-            "description": "id of the space, integer\n"
+            "description": "id of the space, passed as integer\n"
           },
           "title": {
             "type": "string",
@@ -2056,38 +6024,28 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "create_page_with_table_by_space_name_with_parent_page",
-      "description": "create page with table structured input as content, with a specified parent page\n",
+      "name": "create_blog_post_by_space_name",
+      "description": "create a blog post in a space , providing the space_name. The content should be in Confluence Native Markup Language.\n",
       "parameters": {
         "type": "object",
         "properties": {
           "space_name": {
             "type": "string",
-            "description": "space name\n"
+            "description": "name of the space.\n"
           },
           "title": {
             "type": "string",
-            "description": "new page's title\n"
-          },
-          "parent_page_name": {
-            "type": "string",
-            "description": "name of the parent page\n"
-          },
-          "headers": {
-            "type": "string",
-            "description": "headers or column names of the table. format : [header1, header2, header3, ...] , i.e. a serialized version of a list. Use double quotes, not single quotes.\n"
+            "description": "\n"
           },
-          "rows": {
+          "content": {
             "type": "string",
-            "description": "Complete row data as a single JSON string. Pass the entire 2D array structure as one stringified JSON value. Example value: \\\"[[\\\\\\\"row11\\\\\\\", \\\\\\\"row12\\\\\\\"], [\\\\\\\"row21\\\\\\\", \\\\\\\"row22\\\\\\\"]]\\\" (note this is ONE string, not an array). Do not pass multiple array elements - serialize everything into a single string. Use double quotes, not single quotes.\n"
+            "description": "\n"
           }
         },
         "required": [
           "space_name",
           "title",
-          "parent_page_name",
-          "headers",
-          "rows"
+          "content"
         ]
       }
     }
@@ -2095,33 +6053,42 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "import_file_and_create_page_with_table_with_parent_page",
-      "description": "Reads a file from the encoded filedescriptor (like ey9320... ), and then create page with table structured input as content, with a specified parent page.\n",
+      "name": "get_blog_post_by_id",
+      "description": "get blog post information by id.\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "space_name": {
-            "type": "string",
-            "description": ""
-          },
-          "title": {
+          "blog_post_id": {
+            "type": "integer",
+            "description": "id of the blog post, passed as integer\n"
+          }
+        },
+        "required": [
+          "blog_post_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "get_blog_post_by_name",
+      "description": "get blog post information by name\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "blog_post_name": {
             "type": "string",
-            "description": ""
+            "description": "name of the blog post\n"
           },
-          "parent_page_name": {
+          "space_name": {
             "type": "string",
             "description": ""
-          },
-          "file_descriptor": {
-            "type": "string",
-            "description": "The base64 encoded file descriptor to the file to read and upload\n"
           }
         },
         "required": [
-          "space_name",
-          "title",
-          "parent_page_name",
-          "file_descriptor"
+          "blog_post_name",
+          "space_name"
         ]
       }
     }
@@ -2129,28 +6096,36 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "import_file_and_create_page_with_table_without_parent_page",
-      "description": "Reads a file from the encoded filedescriptor (like ey9320... ), and then create page with table structured input as content, without a specified parent page.\n",
+      "name": "update_blog_post_by_id",
+      "description": "update an existing blog post by id. The content should be in Confluence Native Markup Language.\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "space_name": {
-            "type": "string",
-            "description": ""
+          "blog_post_id": {
+            "type": "integer",
+            "description": "id of the blog post, passed as integer\n"
           },
-          "title": {
+          "space_id": {
+            "type": "integer",
+            "description": "id of the space, passed as integer\n"
+          },
+          "new_title": {
             "type": "string",
-            "description": ""
+            "description": "\n"
           },
-          "file_descriptor": {
+          "new_content": {
             "type": "string",
-            "description": "The base64 encoded file descriptor to the file to read and upload\n"
+            "description": "\n"
+          },
+          "new_version_number": {
+            "type": "integer",
+            "description": "\n"
           }
         },
         "required": [
-          "space_name",
-          "title",
-          "file_descriptor"
+          "blog_post_id",
+          "new_title",
+          "new_content"
         ]
       }
     }
@@ -2158,33 +6133,37 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "create_page_with_table_by_space_name_without_parent_page",
-      "description": "create page with table structured input as content, without a specified parent page\n",
+      "name": "update_blog_post_by_name",
+      "description": "update an existing blog post by name. The content should be in Confluence Native Markup Language.\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "space_name": {
+          "blog_post_name": {
             "type": "string",
-            "description": ""
+            "description": "name of the blog post\n"
           },
-          "title": {
+          "space_name": {
             "type": "string",
-            "description": "new page's title\n"
+            "description": "name of the space\n"
           },
-          "headers": {
+          "new_title": {
             "type": "string",
-            "description": "headers or column names of the table. format : [header1, header2, header3, ...] , i.e. a serialized version of a list. Use double quotes, not single quotes.\n"
+            "description": "\n"
           },
-          "rows": {
+          "new_content": {
             "type": "string",
-            "description": "Complete row data as a single JSON string. Pass the entire 2D array structure as one stringified JSON value. Example value: \\\"[[\\\\\\\"row11\\\\\\\", \\\\\\\"row12\\\\\\\"], [\\\\\\\"row21\\\\\\\", \\\\\\\"row22\\\\\\\"]]\\\" (note this is ONE string, not an array). Do not pass multiple array elements - serialize everything into a single string. Use double quotes, not single quotes.\n"
+            "description": "\n"
+          },
+          "new_version_number": {
+            "type": "integer",
+            "description": "\n"
           }
         },
         "required": [
+          "blog_post_name",
           "space_name",
-          "title",
-          "headers",
-          "rows"
+          "new_title",
+          "new_content"
         ]
       }
     }
@@ -2192,23 +6171,18 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "append_to_page_by_id",
-      "description": "append content to page , in confluence. The content should be in Confluence Native Markup Language.\n",
+      "name": "delete_blog_post",
+      "description": "delete a blog post by id\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "page_id": {
+          "blog_post_id": {
             "type": "integer",
-            "description": ""
-          },
-          "content": {
-            "type": "string",
-            "description": ""
+            "description": "id of the blog post. get it from the list_blog_posts_in_space or create_blog_post function, passed as integer\n"
           }
         },
         "required": [
-          "page_id",
-          "content"
+          "blog_post_id"
         ]
       }
     }
@@ -2216,27 +6190,36 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "import_file_and_append_table_to_page_by_page_name",
-      "description": "Reads a file from the encoded filedescriptor (like ey9320... ) and appends content in table structured format, to a given page.\n",
+      "name": "list_blog_posts_in_space_by_id",
+      "description": "list all blog posts in a space, with space_id passed in argument.\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "space_id": {
+            "type": "integer",
+            "description": "space id, passed as integer\n"
+          }
+        },
+        "required": [
+          "space_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "list_blog_posts_in_space_by_name",
+      "description": "list all blog posts in a space, with space_name passed in argument\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "file_descriptor": {
-            "type": "string",
-            "description": "The base64 encoded file descriptor to the file to read and upload\n"
-          },
-          "page_name": {
-            "type": "string",
-            "description": ""
-          },
           "space_name": {
             "type": "string",
-            "description": ""
+            "description": "\n"
           }
         },
         "required": [
-          "file_descriptor",
-          "page_name",
           "space_name"
         ]
       }
@@ -2245,28 +6228,42 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "append_to_page_by_page_name",
-      "description": "append content to page , in confluence. The content should be in Confluence Native Markup Language.\n",
+      "name": "list_page_labels_by_id",
+      "description": "list all labels on a page, providing page id\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "page id , passed as integer\n"
+          }
+        },
+        "required": [
+          "page_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "list_page_labels_by_name",
+      "description": "list all labels on a page, providing page name\n",
       "parameters": {
         "type": "object",
         "properties": {
           "page_name": {
             "type": "string",
-            "description": ""
-          },
-          "space_name": {
-            "type": "string",
-            "description": ""
+            "description": "\n"
           },
-          "content": {
+          "space_name": {
             "type": "string",
             "description": ""
           }
         },
         "required": [
           "page_name",
-          "space_name",
-          "content"
+          "space_name"
         ]
       }
     }
@@ -2274,33 +6271,23 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "append_table_to_page_by_page_name",
-      "description": "append content in table structured format , to a given page.\n",
+      "name": "add_page_label",
+      "description": "add a label to a page, providing page id; labels are stored in lowercase and can't contain spaces\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "page_name": {
-            "type": "string",
-            "description": ""
-          },
-          "space_name": {
-            "type": "string",
-            "description": ""
-          },
-          "headers": {
-            "type": "string",
-            "description": "headers or column names of the table. format : [header1, header2, header3, ...] , i.e. a serialized version of a list. Use double quotes, not single quotes.\n"
+          "page_id": {
+            "type": "integer",
+            "description": "page id, passed as integer\n"
           },
-          "rows": {
+          "label": {
             "type": "string",
-            "description": "Complete row data as a single JSON string. Pass the entire 2D array structure as one stringified JSON value. Example value: \\\"[[\\\\\\\"row11\\\\\\\", \\\\\\\"row12\\\\\\\"], [\\\\\\\"row21\\\\\\\", \\\\\\\"row22\\\\\\\"]]\\\" (note this is ONE string, not an array). Do not pass multiple array elements - serialize everything into a single string. Use double quotes, not single quotes.\n"
+            "description": "label to add, without spaces\n"
           }
         },
         "required": [
-          "page_name",
-          "space_name",
-          "headers",
-          "rows"
+          "page_id",
+          "label"
         ]
       }
     }
@@ -2308,28 +6295,23 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "create_page_by_space_name",
-      "description": "create a page in confluence, providing the space name. The content should be in Confluence Native Markup Language.\n",
+      "name": "remove_page_label",
+      "description": "remove a label from a page, providing page id\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "space_name": {
-            "type": "string",
-            "description": "name of the space\n"
-          },
-          "title": {
-            "type": "string",
-            "description": "\n"
+          "page_id": {
+            "type": "integer",
+            "description": "page id, passed as integer\n"
           },
-          "content": {
+          "label": {
             "type": "string",
-            "description": "\n"
+            "description": "label to remove\n"
           }
         },
         "required": [
-          "space_name",
-          "title",
-          "content"
+          "page_id",
+          "label"
         ]
       }
     }
@@ -2337,33 +6319,18 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "create_page_by_space_id_with_parent_page_id",
-      "description": "create a page in confluence, prividing the space id and parent page id. The content should be in Confluence Native Markup Language.\n",
+      "name": "list_space_labels_by_id",
+      "description": "list all labels in a space , providing space id\n",
       "parameters": {
         "type": "object",
         "properties": {
           "space_id": {
             "type": "integer",
-            "description": "id of the space, integer\n"
-          },
-          "title": {
-            "type": "string",
-            "description": "\n"
-          },
-          "parent_id": {
-            "type": "integer",
-            "description": "id of the parent page\n"
-          },
-          "content": {
-            "type": "string",
-            "description": "\n"
+            "description": "space id , passed as integer\n"
           }
         },
         "required": [
-          "space_id",
-          "title",
-          "parent_id",
-          "content"
+          "space_id"
         ]
       }
     }
@@ -2371,33 +6338,18 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "create_page_by_space_name_with_parent_page_name",
-      "description": "create a page in confluence, providing the space name and parent page name. The content should be in Confluence Native Markup Language.\n",
+      "name": "list_space_labels_by_name",
+      "description": "list all labels in a space , providing space name\n",
       "parameters": {
         "type": "object",
         "properties": {
           "space_name": {
-            "type": "string",
-            "description": "name of the space\n"
-          },
-          "title": {
-            "type": "string",
-            "description": "\n"
-          },
-          "parent_page_name": {
-            "type": "string",
-            "description": "id of the parent page\n"
-          },
-          "content": {
             "type": "string",
             "description": "\n"
           }
         },
         "required": [
-          "space_name",
-          "title",
-          "parent_page_name",
-          "content"
+          "space_name"
         ]
       }
     }
@@ -2405,18 +6357,18 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "get_page_by_id",
-      "description": "get page information by id\n",
+      "name": "list_blog_post_labels_by_id",
+      "description": "list all labels on a blog post, providing blog post id\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "page_id": {
+          "blog_post_id": {
             "type": "integer",
-            "description": "id of the page. get it from the get_pages_in_space or create_page function. this is an integer\n"
+            "description": "blog post id , passed as integer\n"
           }
         },
         "required": [
-          "page_id"
+          "blog_post_id"
         ]
       }
     }
@@ -2424,22 +6376,22 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "get_page_by_name",
-      "description": "get page information by page title\n",
+      "name": "list_blog_post_labels_by_name",
+      "description": "list all labels on a blog post, providing blog post name\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "page_name": {
+          "blog_post_name": {
             "type": "string",
-            "description": "name of the page\n"
+            "description": "\n"
           },
           "space_name": {
             "type": "string",
-            "description": "name of the space\n"
+            "description": ""
           }
         },
         "required": [
-          "page_name",
+          "blog_post_name",
           "space_name"
         ]
       }
@@ -2448,32 +6400,23 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "update_page_by_id",
-      "description": "update an existing page by page id. The content should be in Confluence Native Markup Language.\n",
+      "name": "create_footer_comment_on_page_by_page_id",
+      "description": "create a footer comment on a page, by providing the page id\n",
       "parameters": {
         "type": "object",
         "properties": {
           "page_id": {
-            "type": "integer",
-            "description": "id of the page. passed as integer\n"
-          },
-          "space_id": {
-            "type": "integer",
-            "description": "id of the space. passed as integer\n"
-          },
-          "new_title": {
             "type": "string",
-            "description": "\n"
+            "description": "page id, passed as integer value\n"
           },
-          "new_content": {
+          "content": {
             "type": "string",
             "description": "\n"
           }
         },
         "required": [
           "page_id",
-          "new_title",
-          "new_content"
+          "content"
         ]
       }
     }
@@ -2481,33 +6424,28 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "update_page_by_name",
-      "description": "updata an existing page by page name. The content should be in Confluence Native Markup Language.\n",
+      "name": "create_footer_comment_on_page_by_page_name",
+      "description": "create a footer comment on a page, by providing the page name\n",
       "parameters": {
         "type": "object",
         "properties": {
           "page_name": {
             "type": "string",
-            "description": "name of the page.\n"
+            "description": ""
           },
           "space_name": {
             "type": "string",
-            "description": "name of the space\n"
-          },
-          "new_title": {
-            "type": "string",
-            "description": "\n"
+            "description": ""
           },
-          "new_content": {
+          "content": {
             "type": "string",
-            "description": "\n"
+            "description": ""
           }
         },
         "required": [
           "page_name",
           "space_name",
-          "new_title",
-          "new_content"
+          "content"
         ]
       }
     }
@@ -2515,18 +6453,23 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "delete_page",
-      "description": "delete a page by id\n",
+      "name": "create_footer_comment_on_blog_post_by_id",
+      "description": "create a footer comment on a blog post, by providing the blog post id\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "page_id": {
+          "blog_post_id": {
             "type": "integer",
-            "description": "id of the page. get it from the get_pages_in_space or create_page function, passed as integer\n"
+            "description": "blog post id, passed as integer\n"
+          },
+          "content": {
+            "type": "string",
+            "description": "\n"
           }
         },
         "required": [
-          "page_id"
+          "blog_post_id",
+          "content"
         ]
       }
     }
@@ -2534,16 +6477,16 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "create_blog_post_by_space_id",
-      "description": "create a blog post in a space by providing the space_id. The content should be in Confluence Native Markup Language.\n",
+      "name": "create_footer_comment_on_blog_post_by_name",
+      "description": "create a footer comment on a blog post, by providing the blog post name\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "space_id": {
-            "type": "integer",
-            "description": "id of the space, passed as integer\n"
+          "blog_post_name": {
+            "type": "string",
+            "description": "\n"
           },
-          "title": {
+          "space_name": {
             "type": "string",
             "description": "\n"
           },
@@ -2553,8 +6496,8 @@ impl Confluence for ConfluenceContractState {
           }
         },
         "required": [
-          "space_id",
-          "title",
+          "blog_post_name",
+          "space_name",
           "content"
         ]
       }
@@ -2563,18 +6506,14 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "create_blog_post_by_space_name",
-      "description": "create a blog post in a space , providing the space_name. The content should be in Confluence Native Markup Language.\n",
+      "name": "reply_footer_comment",
+      "description": "reply to a footer comment\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "space_name": {
-            "type": "string",
-            "description": "name of the space.\n"
-          },
-          "title": {
-            "type": "string",
-            "description": "\n"
+          "parent_comment_id": {
+            "type": "integer",
+            "description": "parent commit id, paseed as integer\n"
           },
           "content": {
             "type": "string",
@@ -2582,8 +6521,7 @@ impl Confluence for ConfluenceContractState {
           }
         },
         "required": [
-          "space_name",
-          "title",
+          "parent_comment_id",
           "content"
         ]
       }
@@ -2592,18 +6530,18 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "get_blog_post_by_id",
-      "description": "get blog post information by id.\n",
+      "name": "get_footer_comment_by_id",
+      "description": "get a footer comment by id\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "blog_post_id": {
+          "comment_id": {
             "type": "integer",
-            "description": "id of the blog post, passed as integer\n"
+            "description": "comment id, passed as integer\n"
           }
         },
         "required": [
-          "blog_post_id"
+          "comment_id"
         ]
       }
     }
@@ -2611,23 +6549,76 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "get_blog_post_by_name",
-      "description": "get blog post information by name\n",
+      "name": "create_inline_comment",
+      "description": "create an inline comment on a page, attached to a piece of text in it. The match count and index must describe an actual occurrence of the selected text, or Confluence rejects the comment\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "blog_post_name": {
+          "page_id": {
+            "type": "integer",
+            "description": "page id, passed as integer\n"
+          },
+          "content": {
             "type": "string",
-            "description": "name of the blog post\n"
+            "description": "the comment, in Confluence storage format\n"
           },
-          "space_name": {
+          "text_selection": {
             "type": "string",
-            "description": ""
+            "description": "the text in the page to attach the comment to; must not be empty\n"
+          },
+          "text_selection_match_count": {
+            "type": "integer",
+            "description": "how many times the selected text occurs in the page\n"
+          },
+          "text_selection_match_index": {
+            "type": "integer",
+            "description": "which occurrence of the selected text to attach to, starting at 0 and less than the match count\n"
+          }
+        },
+        "required": [
+          "page_id",
+          "content",
+          "text_selection",
+          "text_selection_match_count",
+          "text_selection_match_index"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "list_page_inline_comments_by_id",
+      "description": "list inline comments on a page, providing the page id\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "page id, passed as integer\n"
+          }
+        },
+        "required": [
+          "page_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "list_page_direct_footer_comments_by_id",
+      "description": "list direct footer comments on a page, providing the page id\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "page_id": {
+            "type": "integer",
+            "description": "page id, passed as integer\n"
           }
         },
         "required": [
-          "blog_post_name",
-          "space_name"
+          "page_id"
         ]
       }
     }
@@ -2635,36 +6626,23 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "update_blog_post_by_id",
-      "description": "update an existing blog post by id. The content should be in Confluence Native Markup Language.\n",
+      "name": "list_page_direct_footer_comments_by_name",
+      "description": "list direct footer comments on a page, providing the page name\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "blog_post_id": {
-            "type": "integer",
-            "description": "id of the blog post, passed as integer\n"
-          },
-          "space_id": {
-            "type": "integer",
-            "description": "id of the space, passed as integer\n"
-          },
-          "new_title": {
+          "page_name": {
             "type": "string",
             "description": "\n"
           },
-          "new_content": {
+          "space_name": {
             "type": "string",
-            "description": "\n"
-          },
-          "new_version_number": {
-            "type": "integer",
-            "description": "\n"
+            "description": ""
           }
         },
         "required": [
-          "blog_post_id",
-          "new_title",
-          "new_content"
+          "page_name",
+          "space_name"
         ]
       }
     }
@@ -2672,36 +6650,22 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "update_blog_post_by_name",
-      "description": "update an existing blog post by name. The content should be in Confluence Native Markup Language.\n",
+      "name": "update_comment",
+      "description": "update a comment\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "blog_post_name": {
-            "type": "string",
-            "description": "name of the blog post\n"
-          },
-          "space_name": {
-            "type": "string",
-            "description": "name of the space\n"
-          },
-          "new_title": {
-            "type": "string",
-            "description": "\n"
+          "comment_id": {
+            "type": "integer",
+            "description": "comment id, passed as integer\n"
           },
           "new_content": {
             "type": "string",
             "description": "\n"
-          },
-          "new_version_number": {
-            "type": "integer",
-            "description": "\n"
           }
         },
         "required": [
-          "blog_post_name",
-          "space_name",
-          "new_title",
+          "comment_id",
           "new_content"
         ]
       }
@@ -2710,18 +6674,18 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "delete_blog_post",
-      "description": "delete a blog post by id\n",
+      "name": "delete_comment",
+      "description": "delete a comment by id\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "blog_post_id": {
+          "comment_id": {
             "type": "integer",
-            "description": "id of the blog post. get it from the list_blog_posts_in_space or create_blog_post function, passed as integer\n"
+            "description": "comment id, passed as integer\n"
           }
         },
         "required": [
-          "blog_post_id"
+          "comment_id"
         ]
       }
     }
@@ -2729,18 +6693,18 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "list_blog_posts_in_space_by_id",
-      "description": "list all blog posts in a space, with space_id passed in argument.\n",
+      "name": "list_blog_post_direct_footer_comments_by_id",
+      "description": "list direct footer comments on a blog post, providing the blog post id\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "space_id": {
+          "blog_post_id": {
             "type": "integer",
-            "description": "space id, passed as integer\n"
+            "description": "blog post id, passed as integer\n"
           }
         },
         "required": [
-          "space_id"
+          "blog_post_id"
         ]
       }
     }
@@ -2748,17 +6712,22 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "list_blog_posts_in_space_by_name",
-      "description": "list all blog posts in a space, with space_name passed in argument\n",
+      "name": "list_blog_post_direct_footer_comments_by_name",
+      "description": "list direct footer comments on a blog post, providing the blog post name\n",
       "parameters": {
         "type": "object",
         "properties": {
+          "blog_post_name": {
+            "type": "string",
+            "description": "\n"
+          },
           "space_name": {
             "type": "string",
             "description": "\n"
           }
         },
         "required": [
+          "blog_post_name",
           "space_name"
         ]
       }
@@ -2767,18 +6736,18 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "list_page_labels_by_id",
-      "description": "list all labels on a page, providing page id\n",
+      "name": "get_comment_children",
+      "description": "get children of a comment, providing the comment id\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "page_id": {
+          "comment_id": {
             "type": "integer",
-            "description": "page id , passed as integer\n"
+            "description": "\n"
           }
         },
         "required": [
-          "page_id"
+          "comment_id"
         ]
       }
     }
@@ -2786,23 +6755,18 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "list_page_labels_by_name",
-      "description": "list all labels on a page, providing page name\n",
+      "name": "export_comments_markdown",
+      "description": "export the full footer comment thread of a page, replies included, as a nested markdown list with the author and timestamp of each comment\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "page_name": {
-            "type": "string",
-            "description": "\n"
-          },
-          "space_name": {
-            "type": "string",
-            "description": ""
+          "page_id": {
+            "type": "integer",
+            "description": "page id, passed as integer\n"
           }
         },
         "required": [
-          "page_name",
-          "space_name"
+          "page_id"
         ]
       }
     }
@@ -2810,14 +6774,14 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "list_space_labels_by_id",
-      "description": "list all labels in a space , providing space id\n",
+      "name": "list_space_permissions",
+      "description": "list all permissions for a space\n",
       "parameters": {
         "type": "object",
         "properties": {
           "space_id": {
             "type": "integer",
-            "description": "space id , passed as integer\n"
+            "description": "space id, passed as integer\n"
           }
         },
         "required": [
@@ -2829,18 +6793,18 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "list_space_labels_by_name",
-      "description": "list all labels in a space , providing space name\n",
+      "name": "get_page_children",
+      "description": "get the direct children of a page\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "space_name": {
-            "type": "string",
-            "description": "\n"
+          "page_id": {
+            "type": "integer",
+            "description": "page id , passed as integer\n"
           }
         },
         "required": [
-          "space_name"
+          "page_id"
         ]
       }
     }
@@ -2848,18 +6812,18 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "list_blog_post_labels_by_id",
-      "description": "list all labels on a blog post, providing blog post id\n",
+      "name": "get_page_descendants_by_page_id",
+      "description": "get all descendants of a page, providing the page id\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "blog_post_id": {
+          "page_id": {
             "type": "integer",
-            "description": "blog post id , passed as integer\n"
+            "description": "page id, passed as integer\n"
           }
         },
         "required": [
-          "blog_post_id"
+          "page_id"
         ]
       }
     }
@@ -2867,23 +6831,23 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "list_blog_post_labels_by_name",
-      "description": "list all labels on a blog post, providing blog post name\n",
+      "name": "export_subtree_text",
+      "description": "export a page and its descendant pages as plain text for retrieval pipelines, each page under a markdown heading with its breadcrumb, depth first\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "blog_post_name": {
-            "type": "string",
-            "description": "\n"
+          "page_id": {
+            "type": "integer",
+            "description": "id of the root page\n"
           },
-          "space_name": {
-            "type": "string",
-            "description": ""
+          "max_pages": {
+            "type": "integer",
+            "description": "maximum number of pages to include, counting the root\n"
           }
         },
         "required": [
-          "blog_post_name",
-          "space_name"
+          "page_id",
+          "max_pages"
         ]
       }
     }
@@ -2891,23 +6855,18 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "create_footer_comment_on_page_by_page_id",
-      "description": "create a footer comment on a page, by providing the page id\n",
+      "name": "get_page_ancestors_by_page_id",
+      "description": "get all ancestors of a page, providing the page id\n",
       "parameters": {
         "type": "object",
         "properties": {
           "page_id": {
-            "type": "string",
-            "description": "page id, passed as integer value\n"
-          },
-          "content": {
-            "type": "string",
-            "description": "\n"
+            "type": "integer",
+            "description": "page id , passed as integer\n"
           }
         },
         "required": [
-          "page_id",
-          "content"
+          "page_id"
         ]
       }
     }
@@ -2915,52 +6874,23 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "create_footer_comment_on_page_by_page_name",
-      "description": "create a footer comment on a page, by providing the page name\n",
+      "name": "get_page_descendants_by_page_name",
+      "description": "get all descendants of a page, providing the page name\n",
       "parameters": {
         "type": "object",
         "properties": {
           "page_name": {
             "type": "string",
-            "description": ""
+            "description": "\n"
           },
           "space_name": {
-            "type": "string",
-            "description": ""
-          },
-          "content": {
-            "type": "string",
-            "description": ""
-          }
-        },
-        "required": [
-          "page_name",
-          "space_name",
-          "content"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "create_footer_comment_on_blog_post_by_id",
-      "description": "create a footer comment on a blog post, by providing the blog post id\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "blog_post_id": {
-            "type": "integer",
-            "description": "blog post id, passed as integer\n"
-          },
-          "content": {
             "type": "string",
             "description": "\n"
           }
         },
         "required": [
-          "blog_post_id",
-          "content"
+          "page_name",
+          "space_name"
         ]
       }
     }
@@ -2968,28 +6898,23 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "create_footer_comment_on_blog_post_by_name",
-      "description": "create a footer comment on a blog post, by providing the blog post name\n",
+      "name": "get_page_ancestors_by_page_name",
+      "description": "get all ancestors of a page, providing the page name\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "blog_post_name": {
+          "page_name": {
             "type": "string",
             "description": "\n"
           },
           "space_name": {
             "type": "string",
             "description": "\n"
-          },
-          "content": {
-            "type": "string",
-            "description": "\n"
           }
         },
         "required": [
-          "blog_post_name",
-          "space_name",
-          "content"
+          "page_name",
+          "space_name"
         ]
       }
     }
@@ -2997,23 +6922,18 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "reply_footer_comment",
-      "description": "reply to a footer comment\n",
+      "name": "get_pages_in_space_by_id",
+      "description": "get all pages in a space with the given id\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "parent_comment_id": {
+          "space_id": {
             "type": "integer",
-            "description": "parent commit id, paseed as integer\n"
-          },
-          "content": {
-            "type": "string",
-            "description": "\n"
+            "description": "space id, passed as integer\n"
           }
         },
         "required": [
-          "parent_comment_id",
-          "content"
+          "space_id"
         ]
       }
     }
@@ -3021,37 +6941,41 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "get_footer_comment_by_id",
-      "description": "get a footer comment by id\n",
+      "name": "get_pages_in_space_by_name",
+      "description": "get all pages in a space with the given space name\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "comment_id": {
-            "type": "integer",
-            "description": "comment id, passed as integer\n"
+          "space_name": {
+            "type": "string",
+            "description": "\n"
           }
         },
         "required": [
-          "comment_id"
+          "space_name"
         ]
       }
     }
   },
   {
     "type": "function",
-    "function": {
-      "name": "list_page_direct_footer_comments_by_id",
-      "description": "list direct footer comments on a page, providing the page id\n",
+    "function": {
+      "name": "search_content",
+      "description": "search Confluence content with a CQL query, returning each match's id, title, type, space key and an excerpt; follows result pages until limit matches are found\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "page_id": {
+          "cql": {
+            "type": "string",
+            "description": "CQL query, e.g. type = page AND text ~ \"incident\" AND space = OPS ORDER BY lastmodified DESC\n"
+          },
+          "limit": {
             "type": "integer",
-            "description": "page id, passed as integer\n"
+            "description": "maximum number of matches to return (optional, default 25)\n"
           }
         },
         "required": [
-          "page_id"
+          "cql"
         ]
       }
     }
@@ -3059,23 +6983,31 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "list_page_direct_footer_comments_by_name",
-      "description": "list direct footer comments on a page, providing the page name\n",
+      "name": "search_spaces",
+      "description": "full-text search across several spaces, returning hits ranked by relevance with an excerpt\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "page_name": {
+          "text": {
             "type": "string",
-            "description": "\n"
+            "description": "text to search for\n"
           },
-          "space_name": {
-            "type": "string",
-            "description": ""
+          "space_keys": {
+            "type": "array",
+            "items": {
+              "type": "string"
+            },
+            "description": "keys of the spaces to search, e.g. [\"ENG\", \"OPS\"]\n"
+          },
+          "limit": {
+            "type": "integer",
+            "description": "maximum number of hits to return (1 to 100)\n"
           }
         },
         "required": [
-          "page_name",
-          "space_name"
+          "text",
+          "space_keys",
+          "limit"
         ]
       }
     }
@@ -3083,23 +7015,31 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "update_comment",
-      "description": "update a comment\n",
+      "name": "label_search_results",
+      "description": "add labels to every page matched by a CQL query (at most max pages), reporting which pages were labelled and which failed\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "comment_id": {
-            "type": "integer",
-            "description": "comment id, passed as integer\n"
-          },
-          "new_content": {
+          "cql": {
             "type": "string",
-            "description": "\n"
+            "description": "CQL query selecting the content to label, e.g. space = ENG AND type = page AND title ~ \"runbook\"\n"
+          },
+          "labels": {
+            "type": "array",
+            "items": {
+              "type": "string"
+            },
+            "description": "labels to add, lowercase without spaces\n"
+          },
+          "max": {
+            "type": "integer",
+            "description": "maximum number of matched pages to label (1 to 100)\n"
           }
         },
         "required": [
-          "comment_id",
-          "new_content"
+          "cql",
+          "labels",
+          "max"
         ]
       }
     }
@@ -3107,18 +7047,23 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "delete_comment",
-      "description": "delete a comment by id\n",
+      "name": "pages_changed_since",
+      "description": "pages in a space modified at or after a timestamp, oldest change first, so a polling agent can checkpoint on the last one it saw (at most 100 per call)\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "comment_id": {
+          "space_id": {
             "type": "integer",
-            "description": "comment id, passed as integer\n"
+            "description": "space id, passed as integer\n"
+          },
+          "since": {
+            "type": "string",
+            "description": "YYYY-MM-DD, YYYY-MM-DD HH:MM or an ISO 8601 timestamp such as a page's version.createdAt; precision is to the minute\n"
           }
         },
         "required": [
-          "comment_id"
+          "space_id",
+          "since"
         ]
       }
     }
@@ -3126,234 +7071,1284 @@ impl Confluence for ConfluenceContractState {
   {
     "type": "function",
     "function": {
-      "name": "list_blog_post_direct_footer_comments_by_id",
-      "description": "list direct footer comments on a blog post, providing the blog post id\n",
+      "name": "diagnose",
+      "description": "explain an error message returned by another tool and suggest how to fix it, e.g. a new API token after an auth failure or fewer calls after a rate limit\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "blog_post_id": {
-            "type": "integer",
-            "description": "blog post id, passed as integer\n"
+          "error_message": {
+            "type": "string",
+            "description": "the error message exactly as the failed tool returned it\n"
           }
         },
         "required": [
-          "blog_post_id"
+          "error_message"
         ]
       }
     }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "list_blog_post_direct_footer_comments_by_name",
-      "description": "list direct footer comments on a blog post, providing the blog post name\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "blog_post_name": {
-            "type": "string",
-            "description": "\n"
-          },
-          "space_name": {
-            "type": "string",
-            "description": "\n"
-          }
-        },
-        "required": [
-          "blog_post_name",
-          "space_name"
-        ]
-      }
+  }
+]"#;
+        filter_tools(tools, &self.secrets.config().disabled_tools)
+    }
+
+    /// Placeholder for prompt packs used by agentic flows.
+    #[query]
+    fn prompts(&self) -> String {
+        "{\"prompts\": []}".to_string()
+    }
+
+    /// Turn a tool's error message into a suggested fix.
+    #[query]
+    fn diagnose(&self, error_message: String) -> String {
+        diagnosis_for(&error_message, DIAGNOSIS_RULES)
+            .unwrap_or("No specific guidance for this error; check the tool's arguments and the HTTP status and message Confluence returned.")
+            .to_string()
+    }
+    // Add more tools here following the same pattern for all operations (blog posts, labels, comments, properties, etc.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Column alignments become paragraph marks and widths become `colwidth` on every cell of
+    /// the column, with the table as wide as the columns when all of them have a width.
+    #[test]
+    fn table_columns_set_alignment_and_width() {
+        let columns =
+            parse_column_specs(r#"[{"width": 200}, {"align": "end", "width": 100}]"#).unwrap();
+        let table = create_table_node_with_columns(
+            vec!["Item".to_string(), "Cost".to_string()],
+            vec![vec![json!("Disk"), json!(120)]],
+            &columns,
+        )
+        .unwrap();
+        let table = serde_json::to_value(&table).unwrap();
+
+        assert_eq!(table["attrs"]["width"], json!(300.0));
+        for row in table["content"].as_array().unwrap() {
+            let [item, cost] = row["content"].as_array().unwrap().as_slice() else {
+                panic!("expected two cells: {row}");
+            };
+            assert_eq!(item["attrs"]["colwidth"], json!([200]));
+            assert!(item["content"][0].get("marks").is_none());
+            assert_eq!(cost["attrs"]["colwidth"], json!([100]));
+            assert_eq!(
+                cost["content"][0]["marks"],
+                json!([{"type": "alignment", "attrs": {"align": "end"}}])
+            );
+        }
+
+        let plain = create_table_node(vec!["Item".to_string()], vec![]).unwrap();
+        let plain = serde_json::to_value(&plain).unwrap();
+        assert_eq!(plain["attrs"]["width"], Value::Null);
+        assert_eq!(
+            plain["content"][0]["content"][0]["attrs"],
+            json!({"colspan": 1, "rowspan": 1})
+        );
+
+        let err = parse_column_specs(r#"[{"align": "justify"}]"#).unwrap_err();
+        assert_eq!(ErrorCode::of_tagged(&err), Some(ErrorCode::Validation));
+        assert!(parse_column_specs(r#"[{"width": 0}]"#).is_err());
+        assert!(create_table_node_with_columns(vec![], vec![], &columns).is_err());
+    }
+
+    /// An entry is served for the rest of the call, keyed by method, endpoint and query.
+    #[test]
+    fn response_cache_serves_repeated_reads() {
+        let cache = ResponseCache::default();
+        let query = vec![("limit".to_string(), "250".to_string())];
+        let key = ResponseCache::key(HttpMethod::Get, "/spaces", &query);
+        assert_eq!(key, "GET /spaces?limit=250");
+
+        assert_eq!(cache.get(&key), None);
+        cache.insert(key.clone(), 200, "spaces".to_string());
+        assert_eq!(cache.get(&key), Some((200, "spaces".to_string())));
+        assert_eq!(
+            cache.get(&ResponseCache::key(HttpMethod::Get, "/spaces", &[])),
+            None
+        );
+        assert_eq!(
+            serde_json::to_string(&ConfluenceContractState::new().unwrap()).unwrap(),
+            serde_json::to_string(&json!({"secrets": Secrets::<ConfluenceConfig>::new()})).unwrap()
+        );
+    }
+
+    /// A write to a page drops that page's entries (v1 or v2, any sub-resource) and keeps the
+    /// rest; a write naming no page drops everything.
+    #[test]
+    fn response_cache_evicts_entries_of_written_page() {
+        let cache = ResponseCache::default();
+        let query = vec![("body-format".to_string(), "storage".to_string())];
+        let page = ResponseCache::key(HttpMethod::Get, "/pages/42", &query);
+        let labels = ResponseCache::key(HttpMethod::Get, "/pages/42/labels", &[]);
+        let other = ResponseCache::key(HttpMethod::Get, "/pages/421", &[]);
+        for key in [&page, &labels, &other] {
+            cache.insert(key.clone(), 200, String::new());
+        }
+
+        cache.evict_for_write("content/42?expand=version");
+        assert_eq!(cache.get(&page), None);
+        assert_eq!(cache.get(&labels), None);
+        assert!(cache.get(&other).is_some());
+
+        cache.evict_for_write("/pages");
+        assert_eq!(cache.get(&other), None);
+    }
+
+    /// The space filter quotes every key and the search text has its quotes escaped.
+    #[test]
+    fn space_search_cql_lists_quoted_spaces() {
+        let cql = build_space_search_cql(
+            r#"release "notes""#,
+            &["ENG".to_string(), "OPS".to_string()],
+        )
+        .unwrap();
+        assert_eq!(
+            cql,
+            r#"text ~ "release \"notes\"" and space in ("ENG","OPS")"#
+        );
+        assert!(build_space_search_cql("notes", &[]).is_err());
+        assert!(build_space_search_cql("notes", &["eng\" or 1=1".to_string()]).is_err());
+    }
+
+    /// Watching sends a POST only when not yet watching, unwatching a DELETE only when watching,
+    /// and a rejected change surfaces the HTTP error.
+    #[test]
+    fn watch_and_unwatch_only_change_when_needed() {
+        let status = |watching: bool| Ok((200, json!({ "watching": watching }).to_string()));
+        let method_name = |method: HttpMethod| match method {
+            HttpMethod::Post => "POST",
+            HttpMethod::Delete => "DELETE",
+            _ => "other",
+        };
+
+        let mut sent = Vec::new();
+        let watched = apply_watch(
+            42,
+            true,
+            || status(false),
+            |method| {
+                sent.push(method_name(method));
+                Ok((204, String::new()))
+            },
+        )
+        .unwrap();
+        assert_eq!(sent, vec!["POST"]);
+        assert_eq!(
+            watched,
+            WatchStatus {
+                page_id: "42".to_string(),
+                watching: true,
+                changed: true
+            }
+        );
+
+        let already = apply_watch(
+            42,
+            true,
+            || status(true),
+            |_| panic!("already watching, nothing should be sent"),
+        )
+        .unwrap();
+        assert!(already.watching && !already.changed);
+
+        let mut sent = Vec::new();
+        let unwatched = apply_watch(
+            42,
+            false,
+            || status(true),
+            |method| {
+                sent.push(method_name(method));
+                Ok((204, String::new()))
+            },
+        )
+        .unwrap();
+        assert_eq!(sent, vec!["DELETE"]);
+        assert!(!unwatched.watching && unwatched.changed);
+        assert!(
+            !apply_watch(42, false, || status(false), |_| panic!("not watching"))
+                .unwrap()
+                .changed
+        );
+
+        let err = apply_watch(42, true, || status(false), |_| Ok((403, "no".to_string())));
+        assert_eq!(err.unwrap_err(), "[AUTH] HTTP 403: no");
+        assert!(apply_watch(42, true, || Ok((404, String::new())), |_| unreachable!()).is_err());
+    }
+
+    /// Restrictions are read per operation, and labels and restrictions are nested on the page
+    /// only once they have been fetched.
+    #[test]
+    fn page_labels_and_restrictions_nest_when_requested() {
+        let response = json!({
+            "read": {
+                "operation": "read",
+                "restrictions": {
+                    "user": {"results": [{"type": "known", "accountId": "5b10ac8d82e05b22cc7d4ef5"}], "size": 1},
+                    "group": {"results": [{"type": "group", "name": "confluence-admins"}], "size": 1}
+                }
+            },
+            "update": {
+                "operation": "update",
+                "restrictions": {
+                    "user": {"results": [], "size": 0},
+                    "group": {"results": [{"type": "group", "name": "editors"}], "size": 1}
+                }
+            }
+        })
+        .to_string();
+        let restrictions = parse_page_restrictions(&response).unwrap();
+        assert_eq!(restrictions.read.users, vec!["5b10ac8d82e05b22cc7d4ef5"]);
+        assert_eq!(restrictions.read.groups, vec!["confluence-admins"]);
+        assert!(restrictions.update.users.is_empty());
+        assert_eq!(restrictions.update.groups, vec!["editors"]);
+        assert_eq!(
+            parse_page_restrictions("{}").unwrap(),
+            PageRestrictions::default()
+        );
+
+        let mut page: ContentDetails = serde_json::from_value(json!({
+            "id": "42",
+            "title": "Runbook",
+            "status": "current",
+            "spaceId": "7",
+            "version": {"number": 3}
+        }))
+        .unwrap();
+        let plain = serde_json::to_value(&page).unwrap();
+        assert!(plain.get("labels").is_none());
+        assert!(plain.get("restrictions").is_none());
+
+        page.labels = Some(vec![Label {
+            id: "1".to_string(),
+            name: "ops".to_string(),
+            prefix: "global".to_string(),
+        }]);
+        page.restrictions = Some(restrictions);
+        let detailed = serde_json::to_value(&page).unwrap();
+        assert_eq!(detailed["labels"][0]["name"], "ops");
+        assert_eq!(detailed["restrictions"]["update"]["groups"][0], "editors");
+    }
+
+    /// Locking limits updates to the integration user and keeps read restrictions; unlocking
+    /// restores the earlier update restrictions, or drops the operation so it inherits.
+    #[test]
+    fn lock_and_unlock_restriction_payloads() {
+        let restricted = |users: &[&str], groups: &[&str]| OperationRestrictions {
+            users: users.iter().map(|user| user.to_string()).collect(),
+            groups: groups.iter().map(|group| group.to_string()).collect(),
+        };
+        let current = PageRestrictions {
+            read: restricted(&[], &["confluence-users"]),
+            update: restricted(&["5b10ac8d82e05b22cc7d4ef5"], &["editors"]),
+        };
+
+        let locked = locked_restrictions(current, "integration-bot");
+        assert_eq!(
+            Value::Array(restrictions_request_body(&locked)),
+            json!([
+                {
+                    "operation": "read",
+                    "restrictions": {
+                        "user": [],
+                        "group": [{ "type": "group", "name": "confluence-users" }]
+                    }
+                },
+                {
+                    "operation": "update",
+                    "restrictions": {
+                        "user": [{ "type": "known", "accountId": "integration-bot" }],
+                        "group": []
+                    }
+                }
+            ])
+        );
+
+        let prior = restricted(&["5b10ac8d82e05b22cc7d4ef5"], &["editors"]);
+        let restored = unlocked_restrictions(locked, Some(prior));
+        let body = restrictions_request_body(&restored);
+        assert_eq!(
+            body[1]["restrictions"]["user"][0]["accountId"],
+            "5b10ac8d82e05b22cc7d4ef5"
+        );
+        assert_eq!(body[1]["restrictions"]["group"][0]["name"], "editors");
+
+        let unlocked = unlocked_restrictions(
+            locked_restrictions(PageRestrictions::default(), "integration-bot"),
+            None,
+        );
+        assert_eq!(unlocked, PageRestrictions::default());
+        assert!(restrictions_request_body(&unlocked).is_empty());
+    }
+
+    /// Representative tool errors map to guidance for their cause; specific messages win over
+    /// the hint for their error code.
+    #[test]
+    fn diagnose_maps_errors_to_guidance() {
+        let guidance = |message: &str| diagnosis_for(message, DIAGNOSIS_RULES).unwrap_or_default();
+        assert!(guidance(&http_error(401, "Unauthorized")).contains("create a new one"));
+        assert!(guidance(&http_error(404, "No content found with id")).contains("Verify the page"));
+        assert!(guidance(&http_error(429, "Rate limit exceeded")).contains("Reduce the call rate"));
+        assert!(guidance(&http_error(409, "Version must be incremented")).contains("re-read"));
+        assert!(guidance("Space not found").contains("list_spaces"));
+        assert!(
+            guidance("page not found for the given page_name: Runbook and space : ENG")
+                .contains("Titles are matched exactly")
+        );
+        assert!(
+            guidance("[VALIDATION] config field `api_key` is required but empty")
+                .contains("MCP secrets")
+        );
+        assert_eq!(diagnosis_for("something unexpected", DIAGNOSIS_RULES), None);
+    }
+
+    /// The change feed filters on lastmodified from the normalized timestamp and orders the
+    /// oldest change first; malformed timestamps are rejected.
+    #[test]
+    fn changed_since_cql_filters_and_orders_by_lastmodified() {
+        assert_eq!(
+            build_changed_since_cql("ENG", "2024-05-01T09:30:15.123Z").unwrap(),
+            r#"type = page and space = "ENG" and lastmodified >= "2024-05-01 09:30" order by lastmodified asc"#
+        );
+        assert_eq!(cql_date("2024-05-01").unwrap(), "2024-05-01");
+        assert_eq!(cql_date(" 2024-05-01 23:59 ").unwrap(), "2024-05-01 23:59");
+        for invalid in [
+            "",
+            "yesterday",
+            "2024-13-01",
+            "2024-05-01 24:00",
+            "2024-05-01 09:30:00",
+            "2024-05-01\" or 1=1",
+        ] {
+            assert!(cql_date(invalid).is_err(), "{}", invalid);
+        }
+        assert!(build_changed_since_cql("eng", "2024-05-01").is_err());
+    }
+
+    /// Excerpts lose their highlight markers and hits are scored by rank.
+    #[test]
+    fn search_hits_parse_excerpt_and_rank() {
+        let response = json!({
+            "results": [
+                {
+                    "content": {"id": "101", "title": "Release notes", "space": {"key": "ENG"}},
+                    "excerpt": "The @@@hl@@@release@@@endhl@@@ &amp; rollout\nplan"
+                },
+                {
+                    "content": {"id": "202", "title": "Runbook", "space": {"key": "OPS"}},
+                    "excerpt": ""
+                }
+            ],
+            "totalSize": 2
+        })
+        .to_string();
+
+        let hits = parse_search_hits(&response).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].id, "101");
+        assert_eq!(hits[0].space_key, "ENG");
+        assert_eq!(hits[0].excerpt, "The release & rollout plan");
+        assert_eq!(hits[0].score, 1.0);
+        assert_eq!(hits[1].title, "Runbook");
+        assert_eq!(hits[1].score, 0.5);
+    }
+
+    /// Results carry the content's id, type and space, the next link is rebased onto the wiki
+    /// context path, and pages are only followed until the limit is reached.
+    #[test]
+    fn search_content_follows_next_until_limit() {
+        let result = |id: &str, kind: &str| {
+            json!({
+                "content": {"id": id, "type": kind, "title": format!("Doc {}", id), "space": {"key": "OPS"}},
+                "excerpt": "@@@hl@@@incident@@@endhl@@@ review"
+            })
+        };
+        let first = json!({
+            "results": [result("1", "page"), result("2", "blogpost")],
+            "_links": {"next": "/rest/api/search?cql=text~incident&limit=2&cursor=abc"}
+        })
+        .to_string();
+        let (results, next) = parse_search_results(&first).unwrap();
+        assert_eq!(results[1].content_id, "2");
+        assert_eq!(results[1].content_type, "blogpost");
+        assert_eq!(results[1].space_key, "OPS");
+        assert_eq!(results[1].excerpt, "incident review");
+        assert_eq!(
+            next.as_deref(),
+            Some("wiki/rest/api/search?cql=text~incident&limit=2&cursor=abc")
+        );
+
+        let mut fetched = Vec::new();
+        let collected = collect_search_results((results, next), 3, |next_url| {
+            fetched.push(next_url.to_string());
+            let page = json!({
+                "results": [result("3", "page"), result("4", "page")],
+                "_links": {"next": "/rest/api/search?cursor=def"}
+            });
+            parse_search_results(&page.to_string())
+        })
+        .unwrap();
+        assert_eq!(fetched.len(), 1);
+        let ids: Vec<&str> = collected.iter().map(|r| r.content_id.as_str()).collect();
+        assert_eq!(ids, vec!["1", "2", "3"]);
+    }
+
+    /// Positions are checked against append/before/after, before and after need a sibling, and
+    /// an untargeted append goes under the page's current parent.
+    #[test]
+    fn reorder_page_request() {
+        assert_eq!(
+            move_page_endpoint(10, "before", Some(11), None).unwrap(),
+            "content/10/move/before/11"
+        );
+        assert_eq!(
+            move_page_endpoint(10, "after", Some(11), Some(1)).unwrap(),
+            "content/10/move/after/11"
+        );
+        assert_eq!(
+            move_page_endpoint(10, "append", None, Some(1)).unwrap(),
+            "content/10/move/append/1"
+        );
+
+        assert!(move_page_endpoint(10, "first", Some(11), None).is_err());
+        assert!(move_page_endpoint(10, "before", None, Some(1)).is_err());
+        assert!(move_page_endpoint(10, "append", None, None).is_err());
+        assert!(move_page_endpoint(10, "after", Some(10), None).is_err());
+    }
+
+    /// Restoring republishes the old title and body as the next version; restoring the current
+    /// version is rejected.
+    #[test]
+    fn restore_version_request_republishes_old_body() {
+        let current: ContentDetails = serde_json::from_value(json!({
+            "id": "42", "title": "Runbook v3", "status": "current", "spaceId": "7",
+            "version": {"number": 5}
+        }))
+        .unwrap();
+        let old: ContentDetails = serde_json::from_value(json!({
+            "id": "42", "title": "Runbook", "status": "current", "spaceId": "7",
+            "version": {"number": 2},
+            "body": {"storage": {"representation": "storage", "value": "<p>old steps</p>"}}
+        }))
+        .unwrap();
+
+        let request: Value =
+            serde_json::from_str(&restore_version_request(42, &current, &old).unwrap()).unwrap();
+        assert_eq!(request["title"], "Runbook");
+        assert_eq!(request["body"]["value"], "<p>old steps</p>");
+        assert_eq!(request["version"]["number"], 6);
+        assert_eq!(request["version"]["message"], "Restored version 2");
+
+        let err = restore_version_request(42, &current, &current).unwrap_err();
+        assert_eq!(ErrorCode::of_tagged(&err), Some(ErrorCode::Validation));
+    }
+
+    /// A comment reaction names the comment as content and its page as container, in both the
+    /// add body and the remove query; unknown emoji and content types are refused.
+    #[test]
+    fn reaction_requests_target_comment_and_container() {
+        let comment = reaction_target(&json!({
+            "id": "98311",
+            "type": "comment",
+            "container": {"id": "98305", "type": "page"}
+        }))
+        .unwrap();
+        let emoji_id = reaction_emoji_id(":thumbsup:").unwrap();
+
+        assert_eq!(
+            reaction_request_body(&comment, emoji_id),
+            json!({
+                "containerId": "98305",
+                "containerType": "page",
+                "contentId": "98311",
+                "contentType": "comment",
+                "emojiId": "1f44d"
+            })
+        );
+        let params = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        assert_eq!(
+            reaction_query_params(&comment, emoji_id),
+            params(&[
+                ("containerId", "98305"),
+                ("containerType", "page"),
+                ("contentId", "98311"),
+                ("contentType", "comment"),
+                ("emojiId", "1f44d"),
+            ])
+        );
+
+        let blog_post = reaction_target(&json!({"id": "77", "type": "blogpost"})).unwrap();
+        assert_eq!(
+            reaction_query_params(&blog_post, reaction_emoji_id("tada").unwrap())[..2],
+            params(&[("containerId", "77"), ("containerType", "blogpost")])[..]
+        );
+
+        for err in [
+            reaction_emoji_id("party_parrot").unwrap_err(),
+            reaction_target(&json!({"id": "5", "type": "attachment"})).unwrap_err(),
+        ] {
+            assert_eq!(ErrorCode::of_tagged(&err), Some(ErrorCode::Validation));
+        }
+    }
+
+    /// The file goes in a CRLF-delimited `file` part between boundaries that don't clash with
+    /// the content, and header-breaking names are refused.
+    #[test]
+    fn attachment_multipart_body_frames_file() {
+        let content = "id,name\n1,\"Ada\"\n";
+        let boundary = multipart_boundary(content);
+        assert_eq!(boundary, "wadk-confluence-attachment");
+        assert_eq!(
+            multipart_boundary("--wadk-confluence-attachment-- inside"),
+            "wadk-confluence-attachment-1"
+        );
+
+        let body =
+            attachment_multipart_body(&boundary, "people \"v2\".csv", "text/csv", content).unwrap();
+        assert_eq!(
+            body,
+            "--wadk-confluence-attachment\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"people %22v2%22.csv\"\r\n\
+             Content-Type: text/csv\r\n\
+             \r\n\
+             id,name\n1,\"Ada\"\n\r\n\
+             --wadk-confluence-attachment\r\n\
+             Content-Disposition: form-data; name=\"minorEdit\"\r\n\
+             \r\n\
+             true\r\n\
+             --wadk-confluence-attachment--\r\n"
+        );
+        assert!(
+            attachment_multipart_body(&boundary, "a.txt", "", "x")
+                .unwrap()
+                .contains("Content-Type: application/octet-stream\r\n")
+        );
+        for (name, content_type) in [
+            ("", "text/plain"),
+            ("a\r\nb.txt", "text/plain"),
+            ("a.txt", "text/plain\nX: y"),
+        ] {
+            let err = attachment_multipart_body(&boundary, name, content_type, "x").unwrap_err();
+            assert_eq!(ErrorCode::of_tagged(&err), Some(ErrorCode::Validation));
+        }
+
+        let uploaded = parse_uploaded_attachment(
+            r#"{"results": [{"id": "att98305", "title": "people.csv",
+                "extensions": {"mediaType": "text/csv", "fileSize": 17},
+                "_links": {"download": "/download/attachments/42/people.csv"}}], "size": 1}"#,
+        )
+        .unwrap();
+        assert_eq!(uploaded.id, "att98305");
+        assert_eq!(uploaded.file_size, Some(17));
+        assert_eq!(uploaded.media_type.as_deref(), Some("text/csv"));
+    }
+
+    /// The manifest lists the body and then each attachment, with their paths in the bundle
+    /// and descriptors; attachment names can't escape the folder.
+    #[test]
+    fn page_bundle_manifest_lists_body_and_attachments() {
+        let page: ContentDetails = serde_json::from_str(
+            r#"{"id": "42", "title": "Runbook", "status": "current", "spaceId": "7",
+                "version": {"number": 3}}"#,
+        )
+        .unwrap();
+        let attachment = |id: &str, title: &str| AttachmentInfo {
+            id: id.to_string(),
+            title: title.to_string(),
+            media_type: Some("text/csv".to_string()),
+            file_size: Some(17),
+            download_link: Some(format!("/download/attachments/42/{}", title)),
+        };
+        let attachments = vec![
+            (attachment("att1", "people.csv"), "fd-people".to_string()),
+            (
+                attachment("att2", "../secrets/keys.csv"),
+                "fd-keys".to_string(),
+            ),
+        ];
+
+        let folder = bundle_folder(" /exports/runbook/ ").unwrap();
+        let manifest = page_bundle_manifest(&page, &folder, "fd-body", &attachments);
+        assert_eq!(manifest["page_id"], "42");
+        assert_eq!(manifest["version"], 3);
+        assert_eq!(
+            manifest["files"],
+            json!([
+                {"kind": "body", "path": "exports/runbook/page.storage.xml", "format": "storage",
+                 "file_descriptor": "fd-body"},
+                {"kind": "attachment", "path": "exports/runbook/attachments/people.csv",
+                 "attachment_id": "att1", "title": "people.csv", "media_type": "text/csv",
+                 "file_size": 17, "file_descriptor": "fd-people"},
+                {"kind": "attachment", "path": "exports/runbook/attachments/.._secrets_keys.csv",
+                 "attachment_id": "att2", "title": "../secrets/keys.csv", "media_type": "text/csv",
+                 "file_size": 17, "file_descriptor": "fd-keys"}
+            ])
+        );
+
+        for bundle_name in ["", " / ", "exports/../../etc"] {
+            let err = bundle_folder(bundle_name).unwrap_err();
+            assert_eq!(ErrorCode::of_tagged(&err), Some(ErrorCode::Validation));
+        }
+    }
+
+    /// The count is read from an analytics response, and a site without analytics gets an
+    /// explanation rather than a bare status.
+    #[test]
+    fn page_views_parse_and_explain_missing_analytics() {
+        assert_eq!(
+            parse_page_views(r#"{"id": 65538, "count": 1234}"#),
+            Ok(1234)
+        );
+        assert!(parse_page_views(r#"{"id": 65538}"#).is_err());
+
+        let unavailable = page_views_error(403, "");
+        assert!(unavailable.contains("analytics are not available"));
+        assert_eq!(ErrorCode::of_tagged(&unavailable), Some(ErrorCode::Auth));
+        assert_eq!(
+            ErrorCode::of_tagged(&page_views_error(404, "")),
+            Some(ErrorCode::NotFound)
+        );
+    }
+
+    /// The selection is sent as `inlineCommentProperties`; empty selections and out-of-range
+    /// match indexes are refused before calling Confluence.
+    #[test]
+    fn inline_comment_request_anchors_selection() {
+        let request: Value = serde_json::from_str(
+            &inline_comment_request_body(
+                42,
+                "<p>Typo?</p>".to_string(),
+                "recieve".to_string(),
+                2,
+                1,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            request,
+            json!({
+                "pageId": 42,
+                "body": {"value": "<p>Typo?</p>", "representation": "storage"},
+                "inlineCommentProperties": {
+                    "textSelection": "recieve",
+                    "textSelectionMatchCount": 2,
+                    "textSelectionMatchIndex": 1
+                }
+            })
+        );
+
+        for (selection, count, index) in [(" ", 1, 0), ("recieve", 2, 2), ("recieve", 0, 0)] {
+            let err = inline_comment_request_body(
+                42,
+                "c".to_string(),
+                selection.to_string(),
+                count,
+                index,
+            )
+            .unwrap_err();
+            assert_eq!(ErrorCode::of_tagged(&err), Some(ErrorCode::Validation));
+        }
+    }
+
+    /// Two pages sharing a title are reported with both IDs instead of one being picked.
+    #[test]
+    fn unique_page_id_rejects_title_collisions() {
+        let page = |id: &str, title: &str| -> ContentDetails {
+            serde_json::from_value(json!({
+                "id": id, "title": title, "status": "current", "spaceId": "7",
+                "version": {"number": 1}
+            }))
+            .unwrap()
+        };
+        let pages = [
+            page("101", "Runbook"),
+            page("102", "Onboarding"),
+            page("103", "Runbook"),
+        ];
+
+        assert_eq!(unique_page_id(&pages, "Onboarding", "ENG"), Ok(102));
+        let err = unique_page_id(&pages, "Runbook", "ENG").unwrap_err();
+        assert_eq!(ErrorCode::of_tagged(&err), Some(ErrorCode::Validation));
+        assert!(err.contains("101, 103"), "{err}");
+        assert!(
+            unique_page_id(&pages, "Missing", "ENG")
+                .unwrap_err()
+                .contains("not found")
+        );
+    }
+
+    /// Moving keeps the body and bumps the version, and a page can't be moved into its own subtree.
+    #[test]
+    fn move_page_request_sets_parent() {
+        let page: ContentDetails = serde_json::from_value(json!({
+            "id": "42", "title": "Runbook", "status": "current", "spaceId": "7",
+            "parentId": "10", "version": {"number": 3},
+            "body": {"storage": {"representation": "storage", "value": "<p>steps</p>"}}
+        }))
+        .unwrap();
+        let request: Value =
+            serde_json::from_str(&move_page_request_body(&page, 99).unwrap()).unwrap();
+        assert_eq!(request["parentId"], "99");
+        assert_eq!(request["title"], "Runbook");
+        assert_eq!(request["body"]["value"], "<p>steps</p>");
+        assert_eq!(request["version"]["number"], 4);
+
+        assert!(check_move_into_subtree(42, 99, &[43, 44]).is_ok());
+        for new_parent_id in [42, 44] {
+            let err = check_move_into_subtree(42, new_parent_id, &[43, 44]).unwrap_err();
+            assert_eq!(ErrorCode::of_tagged(&err), Some(ErrorCode::Validation));
+        }
+    }
+
+    /// A copy keeps the source's ADF body, and stays beside the source only within its space.
+    #[test]
+    fn copy_page_request_places_copy() {
+        let adf = r#"{"type":"doc","version":1,"content":[]}"#;
+        let source: ContentDetails = serde_json::from_value(json!({
+            "id": "42", "title": "Runbook", "status": "current", "spaceId": "7",
+            "parentId": "10", "version": {"number": 3},
+            "body": {"atlas_doc_format": {"representation": "atlas_doc_format", "value": adf}}
+        }))
+        .unwrap();
+
+        let same_space: Value =
+            serde_json::from_str(&copy_page_request_body(&source, "Runbook (copy)", None).unwrap())
+                .unwrap();
+        assert_eq!(
+            same_space,
+            json!({
+                "spaceId": 7, "title": "Runbook (copy)", "parentId": 10,
+                "body": {"value": adf, "representation": "atlas_doc_format"}
+            })
+        );
+
+        let other_space: Value =
+            serde_json::from_str(&copy_page_request_body(&source, "Runbook", Some(8)).unwrap())
+                .unwrap();
+        assert_eq!(other_space["spaceId"], 8);
+        assert!(other_space.get("parentId").is_none());
+    }
+
+    /// A page may go under an unrelated parent but not under itself or a page beneath it, and each
+    /// page's outcome is reported separately.
+    #[test]
+    fn move_pages_bulk_rejects_cycles() {
+        // New parent 30 sits under 1 -> 10 -> 20.
+        let parent_ancestor_ids = [1, 10, 20];
+        let page: ContentDetails = serde_json::from_value(json!({
+            "id": "40",
+            "title": "Moved",
+            "status": "current",
+            "spaceId": "7",
+            "parentId": "30",
+            "version": {"number": 2}
+        }))
+        .unwrap();
+
+        assert!(check_move_cycle(40, 30, &parent_ancestor_ids).is_ok());
+        let moved = page_move_result(40, Ok(page));
+
+        let cyclic = check_move_cycle(10, 30, &parent_ancestor_ids).unwrap_err();
+        assert_eq!(ErrorCode::of_tagged(&cyclic), Some(ErrorCode::Validation));
+        assert!(check_move_cycle(30, 30, &parent_ancestor_ids).is_err());
+        let rejected = page_move_result(10, Err(cyclic.clone()));
+
+        assert_eq!(moved.page_id, 40);
+        assert_eq!(moved.page.unwrap().parent_id.as_deref(), Some("30"));
+        assert!(moved.error.is_none());
+        assert_eq!(rejected.page_id, 10);
+        assert!(rejected.page.is_none());
+        assert_eq!(rejected.error, Some(cyclic));
+    }
+
+    /// A single label is sent as one lowercase global label, and the added label is picked out of
+    /// the page's full label list whether its id comes back as a string or a number.
+    #[test]
+    fn add_page_label_request_and_response() {
+        assert_eq!(
+            label_request_body(&["Postmortem".to_string()]).unwrap(),
+            r#"[{"name":"postmortem","prefix":"global"}]"#
+        );
+        let response = r#"{"results": [
+            {"prefix": "global", "name": "ops", "id": "1001", "label": "ops"},
+            {"prefix": "global", "name": "postmortem", "id": 1002, "label": "postmortem"}
+        ], "start": 0, "limit": 200, "size": 2}"#;
+        let label = find_added_label(response, "postmortem").unwrap();
+        assert_eq!(
+            (
+                label.id.as_str(),
+                label.name.as_str(),
+                label.prefix.as_str()
+            ),
+            ("1002", "postmortem", "global")
+        );
+        assert!(find_added_label(response, "missing").is_err());
+    }
+
+    /// Every matched page up to the cap gets the labels, and a failure on one page is reported
+    /// without stopping the others.
+    #[test]
+    fn label_search_results_applies_up_to_cap() {
+        let response = r#"{"results": [
+            {"content": {"id": "101", "type": "page"}},
+            {"content": {"id": "102", "type": "page"}},
+            {"content": {"id": "103", "type": "page"}},
+            {"content": {"id": "104", "type": "page"}}
+        ], "totalSize": 4}"#;
+        let page_ids = parse_search_content_ids(response).unwrap();
+        let body = label_request_body(&["Runbook".to_string(), "ops".to_string()]).unwrap();
+        assert_eq!(
+            body,
+            r#"[{"name":"runbook","prefix":"global"},{"name":"ops","prefix":"global"}]"#
+        );
+
+        let mut labelled = Vec::new();
+        let result = label_pages(&page_ids, 3, |page_id| {
+            labelled.push(page_id);
+            if page_id == 102 {
+                Err("HTTP 403: not permitted".to_string())
+            } else {
+                Ok(())
+            }
+        });
+
+        assert_eq!(labelled, vec![101, 102, 103]);
+        assert_eq!(result.succeeded, vec![101, 103]);
+        assert_eq!(
+            result.failed,
+            vec![BulkFailure {
+                item: 102,
+                error: "HTTP 403: not permitted".to_string()
+            }]
+        );
+        assert!(label_request_body(&["two words".to_string()]).is_err());
+        assert!(label_request_body(&[]).is_err());
+    }
+
+    /// Every occurrence of a placeholder is replaced, values are escaped for storage format and
+    /// unknown placeholders survive; substitutions must be an object.
+    #[test]
+    fn template_placeholders_are_substituted() {
+        let template = "<h1>{{service}} runbook</h1><p>Owner: {{owner}}</p><p>{{service}} v{{version}}</p><p>{{missing}}</p>";
+        let filled = apply_template_substitutions(
+            template,
+            r#"{"service": "Billing", "owner": "Ops & Infra", "version": 2}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            filled,
+            "<h1>Billing runbook</h1><p>Owner: Ops &amp; Infra</p><p>Billing v2</p><p>{{missing}}</p>"
+        );
+        assert!(apply_template_substitutions(template, r#"["service"]"#).is_err());
+        assert!(apply_template_substitutions(template, "not json").is_err());
+    }
+
+    /// The template is a page template in the target space whose storage body is the source
+    /// page's, and a page without a storage body can't become one.
+    #[test]
+    fn page_template_copies_page_body() {
+        let storage =
+            r#"<h1>Runbook</h1><ac:structured-macro ac:name="toc"/><p>Steps &amp; checks</p>"#;
+        let mut page: ContentDetails = serde_json::from_value(json!({
+            "id": "42",
+            "title": "Billing runbook",
+            "status": "current",
+            "spaceId": "7",
+            "version": {"number": 3},
+            "body": {"storage": {"representation": "storage", "value": storage}}
+        }))
+        .unwrap();
+
+        let body = page_template_request_body("Runbook template", "OPS", &page).unwrap();
+        assert_eq!(parse_template_storage(&body).unwrap(), storage);
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["name"], "Runbook template");
+        assert_eq!(body["templateType"], "page");
+        assert_eq!(body["space"]["key"], "OPS");
+        assert_eq!(
+            parse_template_id(r#"{"templateId": "98306", "name": "Runbook template"}"#).unwrap(),
+            "98306"
+        );
+
+        assert!(page_template_request_body(" ", "OPS", &page).is_err());
+        page.body = None;
+        assert!(page_template_request_body("Runbook template", "OPS", &page).is_err());
+    }
+
+    /// Appending the same content twice only changes the page the first time, whatever the
+    /// whitespace of the repeat.
+    #[test]
+    fn append_if_absent_second_append_is_noop() {
+        let body = "<p>Intro</p>";
+        let content = "<p>Deploy   window:
+ Friday</p>";
+
+        assert!(!content_already_present(body, content));
+        let appended = format!(
+            "{body}
+{content}"
+        );
+
+        assert!(content_already_present(&appended, content));
+        assert!(content_already_present(
+            &appended,
+            "<p>Deploy window: Friday</p>"
+        ));
+        assert!(!content_already_present(
+            &appended,
+            "<p>Deploy window: Monday</p>"
+        ));
+    }
+
+    /// Requests identify the crate and version by default, and the configured User-Agent
+    /// otherwise.
+    #[test]
+    fn request_headers_carry_user_agent() {
+        let mut config = ConfluenceConfig {
+            confluence_url: "https://example.atlassian.net".to_string(),
+            email: "bot@example.com".to_string(),
+            api_key: "token".to_string(),
+            user_agent: None,
+            default_parent_by_space: None,
+            disabled_tools: vec![],
+        };
+        let headers = request_headers(&config);
+        assert_eq!(
+            headers["User-Agent"],
+            format!("wadk-mcp/confluence/{}", env!("CARGO_PKG_VERSION"))
+        );
+        assert!(headers["Authorization"].starts_with("Basic "));
+
+        config.user_agent = Some("acme-agent/2.0".to_string());
+        assert_eq!(request_headers(&config)["User-Agent"], "acme-agent/2.0");
+    }
+
+    /// A config with a blank or malformed field is rejected with that field's name.
+    #[test]
+    fn config_validation_names_missing_field() {
+        let valid = || ConfluenceConfig {
+            confluence_url: "https://example.atlassian.net".to_string(),
+            email: "bot@example.com".to_string(),
+            api_key: "token".to_string(),
+            ..Default::default()
+        };
+        assert!(valid().validate().is_ok());
+
+        let cases = [
+            (
+                ConfluenceConfig {
+                    confluence_url: String::new(),
+                    ..valid()
+                },
+                "confluence_url",
+            ),
+            (
+                ConfluenceConfig {
+                    confluence_url: "example.atlassian.net".to_string(),
+                    ..valid()
+                },
+                "confluence_url",
+            ),
+            (
+                ConfluenceConfig {
+                    email: " ".to_string(),
+                    ..valid()
+                },
+                "email",
+            ),
+            (
+                ConfluenceConfig {
+                    api_key: String::new(),
+                    ..valid()
+                },
+                "api_key",
+            ),
+        ];
+        for (config, field) in cases {
+            let err = config.validate().unwrap_err();
+            assert!(err.contains(&format!("`{}`", field)), "{}", err);
+            assert_eq!(ErrorCode::of_tagged(&err), Some(ErrorCode::Validation));
+        }
+    }
+
+    /// A space with a configured default parent gets it, other spaces and configs without the
+    /// setting create pages at the space root.
+    #[test]
+    fn default_parent_applies_to_configured_space() {
+        let config: ConfluenceConfig = serde_json::from_value(json!({
+            "confluence_url": "https://example.atlassian.net",
+            "email": "bot@example.com",
+            "api_key": "token",
+            "default_parent_by_space": {"Engineering": 65538, "Ops": 98305}
+        }))
+        .unwrap();
+        assert_eq!(config.default_parent("Engineering"), Some(65538));
+        assert_eq!(config.default_parent("Marketing"), None);
+
+        let config: ConfluenceConfig = serde_json::from_value(json!({
+            "confluence_url": "https://example.atlassian.net",
+            "email": "bot@example.com",
+            "api_key": "token"
+        }))
+        .unwrap();
+        assert_eq!(config.default_parent("Engineering"), None);
     }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "get_comment_children",
-      "description": "get children of a comment, providing the comment id\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "comment_id": {
-            "type": "integer",
-            "description": "\n"
-          }
-        },
-        "required": [
-          "comment_id"
-        ]
-      }
+
+    fn hierarchy_item(id: &str, title: &str, parent_id: &str) -> PageHierarchyItem {
+        PageHierarchyItem {
+            id: id.to_string(),
+            title: title.to_string(),
+            status: "current".to_string(),
+            parent_id: Some(parent_id.to_string()),
+            space_id: None,
+            depth: None,
+            content_type: Some("page".to_string()),
+        }
     }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "list_space_permissions",
-      "description": "list all permissions for a space\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "space_id": {
-            "type": "integer",
-            "description": "space id, passed as integer\n"
-          }
-        },
-        "required": [
-          "space_id"
-        ]
-      }
+
+    /// Subtree pages come depth first with full breadcrumbs, non-pages are skipped and the
+    /// page cap (which includes the root) is honoured.
+    #[test]
+    fn subtree_export_orders_depth_first_and_caps_pages() {
+        let mut whiteboard = hierarchy_item("5", "Sketches", "1");
+        whiteboard.content_type = Some("whiteboard".to_string());
+        let descendants = vec![
+            hierarchy_item("2", "Design", "1"),
+            hierarchy_item("3", "Ops", "1"),
+            whiteboard,
+            hierarchy_item("4", "API", "2"),
+        ];
+
+        let ordered = order_subtree("1", "Handbook", &descendants, 10);
+        assert_eq!(
+            ordered,
+            vec![
+                ("1".to_string(), "Handbook".to_string()),
+                ("2".to_string(), "Handbook > Design".to_string()),
+                ("4".to_string(), "Handbook > Design > API".to_string()),
+                ("3".to_string(), "Handbook > Ops".to_string()),
+            ]
+        );
+
+        let capped = order_subtree("1", "Handbook", &descendants, 2);
+        assert_eq!(
+            capped.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(),
+            vec!["1", "2"]
+        );
+
+        let text = render_subtree_text(&[
+            ("Handbook".to_string(), "Welcome.".to_string()),
+            ("Handbook > Design".to_string(), "Principles.".to_string()),
+        ]);
+        assert_eq!(
+            text,
+            "## Handbook\n\nWelcome.\n\n## Handbook > Design\n\nPrinciples.\n"
+        );
     }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "get_page_children",
-      "description": "get the direct children of a page\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "page_id": {
-            "type": "integer",
-            "description": "page id , passed as integer\n"
-          }
-        },
-        "required": [
-          "page_id"
-        ]
-      }
+
+    fn comment(id: &str, author: &str, created_at: &str, storage: &str) -> CommentDetails {
+        CommentDetails {
+            id: id.to_string(),
+            status: "current".to_string(),
+            page_id: Some("42".to_string()),
+            blog_post_id: None,
+            parent_comment_id: None,
+            version: PageVersion {
+                number: 1,
+                message: None,
+                author_id: Some(author.to_string()),
+                created_at: Some(created_at.to_string()),
+            },
+            body: Some(Body {
+                storage: Some(StorageBody {
+                    representation: STORAGE.to_string(),
+                    value: storage.to_string(),
+                }),
+                atlas_doc_format: None,
+            }),
+            title: None,
+        }
     }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "get_page_descendants_by_page_id",
-      "description": "get all descendants of a page, providing the page id\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "page_id": {
-            "type": "integer",
-            "description": "page id, passed as integer\n"
-          }
-        },
-        "required": [
-          "page_id"
-        ]
-      }
+
+    fn bulk_pages(count: usize) -> Vec<BulkPageInput> {
+        (0..count)
+            .map(|i| BulkPageInput {
+                title: format!("Page {}", i),
+                content: format!("content {}", i),
+                parent_id: None,
+            })
+            .collect()
     }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "get_page_ancestors_by_page_id",
-      "description": "get all ancestors of a page, providing the page id\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "page_id": {
-            "type": "integer",
-            "description": "page id , passed as integer\n"
-          }
-        },
-        "required": [
-          "page_id"
-        ]
-      }
+
+    fn created(index: usize) -> Result<(u16, String), String> {
+        Ok((
+            200,
+            format!(
+                r#"{{"id": "{}", "title": "Page {}", "status": "current", "spaceId": "7"}}"#,
+                100 + index,
+                index
+            ),
+        ))
     }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "get_page_descendants_by_page_name",
-      "description": "get all descendants of a page, providing the page name\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "page_name": {
-            "type": "string",
-            "description": "\n"
-          },
-          "space_name": {
-            "type": "string",
-            "description": "\n"
-          }
-        },
-        "required": [
-          "page_name",
-          "space_name"
-        ]
-      }
+
+    /// A 429 mid-batch pauses the run at that page, and resuming from the reported index
+    /// creates the rest without repeating earlier pages.
+    #[test]
+    fn test_bulk_import_pauses_on_rate_limit_and_resumes() {
+        let pages = bulk_pages(4);
+
+        let mut sent = Vec::new();
+        let first_run = run_bulk_page_import(&pages, 0, |index| {
+            sent.push(index);
+            if index == 2 {
+                Ok((429, "Rate limit exceeded".to_string()))
+            } else {
+                created(index)
+            }
+        });
+        assert_eq!(sent, vec![0, 1, 2]);
+        assert!(!first_run.completed);
+        assert_eq!(first_run.resume_from, Some(2));
+        assert_eq!(first_run.retry_after_secs, Some(RATE_LIMIT_BACKOFF_SECS));
+        assert_eq!(first_run.results.len(), 2);
+        assert!(first_run.results.iter().all(|r| r.success));
+
+        let mut resumed = Vec::new();
+        let second_run =
+            run_bulk_page_import(&pages, first_run.resume_from.unwrap() as usize, |index| {
+                resumed.push(index);
+                created(index)
+            });
+        assert_eq!(resumed, vec![2, 3]);
+        assert!(second_run.completed);
+        assert_eq!(second_run.resume_from, None);
+        let ids: Vec<_> = second_run
+            .results
+            .iter()
+            .map(|r| (r.index, r.page_id.clone().unwrap()))
+            .collect();
+        assert_eq!(ids, vec![(2, "102".to_string()), (3, "103".to_string())]);
     }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "get_page_ancestors_by_page_name",
-      "description": "get all ancestors of a page, providing the page name\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "page_name": {
-            "type": "string",
-            "description": "\n"
-          },
-          "space_name": {
-            "type": "string",
-            "description": "\n"
-          }
-        },
-        "required": [
-          "page_name",
-          "space_name"
-        ]
-      }
+
+    /// Non rate-limit failures are recorded per page without stopping the run.
+    #[test]
+    fn test_bulk_import_records_other_failures() {
+        let pages = bulk_pages(3);
+        let report = run_bulk_page_import(&pages, 0, |index| match index {
+            1 => Ok((400, "title already exists".to_string())),
+            _ => created(index),
+        });
+        assert!(report.completed);
+        assert_eq!(report.results.len(), 3);
+        assert!(!report.results[1].success);
+        assert_eq!(
+            report.results[1].error.as_deref(),
+            Some("[VALIDATION] HTTP 400: title already exists")
+        );
     }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "get_pages_in_space_by_id",
-      "description": "get all pages in a space with the given id\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "space_id": {
-            "type": "integer",
-            "description": "space id, passed as integer\n"
-          }
-        },
-        "required": [
-          "space_id"
-        ]
-      }
+
+    /// Space keys must be non-empty and consist of uppercase ASCII letters and digits only.
+    #[test]
+    fn test_validate_space_key() {
+        assert!(validate_space_key("ENG").is_ok());
+        assert!(validate_space_key("TEAM42").is_ok());
+        assert!(validate_space_key("").is_err());
+        assert!(validate_space_key("eng").is_err());
+        assert!(validate_space_key("ENG-1").is_err());
+        assert!(validate_space_key("ENG TEAM").is_err());
+        assert!(validate_space_key("ÉNG").is_err());
     }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "get_pages_in_space_by_name",
-      "description": "get all pages in a space with the given space name\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "space_name": {
-            "type": "string",
-            "description": "\n"
-          }
-        },
-        "required": [
-          "space_name"
-        ]
-      }
+
+    /// The create payload carries the key and name, and a plain-text description only if given.
+    #[test]
+    fn test_create_space_request_body() {
+        let body: Value = serde_json::from_str(
+            &create_space_request_body("ENG", "Engineering", Some("Team docs")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "key": "ENG",
+                "name": "Engineering",
+                "description": { "value": "Team docs", "representation": "plain" }
+            })
+        );
+
+        let body: Value =
+            serde_json::from_str(&create_space_request_body("ENG", "Engineering", None).unwrap())
+                .unwrap();
+        assert_eq!(body, json!({ "key": "ENG", "name": "Engineering" }));
+
+        assert!(create_space_request_body("eng", "Engineering", None).is_err());
+        assert!(create_space_request_body("ENG", "  ", None).is_err());
     }
-  }
-]"#.to_string()
+
+    /// The child count comes from `totalSize`, not from the (deliberately truncated) results.
+    #[test]
+    fn test_parse_search_total_size() {
+        let response = r#"{
+            "results": [{"content": {"id": "101", "type": "page", "title": "Child 1"}}],
+            "start": 0,
+            "limit": 1,
+            "size": 1,
+            "totalSize": 37,
+            "_links": {"next": "/rest/api/search?cql=parent=100&limit=1&start=1"}
+        }"#;
+        assert_eq!(parse_search_total_size(response).unwrap(), 37);
+
+        let leaf = r#"{"results": [], "start": 0, "limit": 1, "size": 0, "totalSize": 0}"#;
+        assert_eq!(parse_search_total_size(leaf).unwrap(), 0);
+
+        assert!(parse_search_total_size(r#"{"results": []}"#).is_err());
     }
 
-    /// Placeholder for prompt packs used by agentic flows.
-    #[query]
-    fn prompts(&self) -> String {
-        "{\"prompts\": []}".to_string()
+    /// A root comment with a nested reply chain and a second root renders as an indented list.
+    #[test]
+    fn test_render_comments_markdown() {
+        let thread = vec![
+            (
+                0,
+                comment("1", "alice", "2024-01-01T10:00:00Z", "<p>Looks good</p>"),
+            ),
+            (
+                1,
+                comment(
+                    "2",
+                    "bob",
+                    "2024-01-01T11:00:00Z",
+                    "<p>Agreed &amp; merged</p>",
+                ),
+            ),
+            (
+                2,
+                comment("3", "alice", "2024-01-01T12:00:00Z", "<p>Thanks!</p>"),
+            ),
+            (
+                0,
+                comment(
+                    "4",
+                    "carol",
+                    "2024-01-02T09:00:00Z",
+                    "<p>One\n  more <b>thing</b></p>",
+                ),
+            ),
+        ];
+
+        let expected = concat!(
+            "- **alice** (2024-01-01T10:00:00Z): Looks good\n",
+            "  - **bob** (2024-01-01T11:00:00Z): Agreed & merged\n",
+            "    - **alice** (2024-01-01T12:00:00Z): Thanks!\n",
+            "- **carol** (2024-01-02T09:00:00Z): One more thing\n",
+        );
+        assert_eq!(render_comments_markdown(&thread), expected);
     }
-    // Add more tools here following the same pattern for all operations (blog posts, labels, comments, properties, etc.)
 }
@@ -14,6 +14,8 @@ pub enum Node {
     Paragraph {
         #[serde(default)]
         content: Option<Vec<Content>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        marks: Option<Vec<Mark>>,
     },
     #[serde(rename = "text")]
     Text { text: String },
@@ -46,6 +48,14 @@ pub struct Content {
     pub text: Option<String>,
 }
 
+/// A node mark such as `alignment`; `attrs` depend on the mark type.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Mark {
+    pub r#type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attrs: Option<serde_json::Value>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct TableAttrs {
@@ -60,4 +70,7 @@ pub struct TableAttrs {
 pub struct CellAttrs {
     pub colspan: u32,
     pub rowspan: u32,
+    /// Width of the cell's column in pixels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub colwidth: Option<Vec<u32>>,
 }
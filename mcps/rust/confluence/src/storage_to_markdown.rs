@@ -0,0 +1,566 @@
+//! Conversion of Confluence storage-format (XHTML) bodies into Markdown.
+//!
+//! Storage bodies are parsed leniently into a small element tree and rendered back out as
+//! Markdown. Paragraphs, headings, lists, tables, links, emphasis and the `code` macro are
+//! translated; any other element or macro is rendered as its text content, so unsupported
+//! markup never makes the conversion fail.
+
+/// Elements that are never closed in storage format, even when not written as `<br/>`.
+const VOID_ELEMENTS: [&str; 4] = ["br", "hr", "img", "col"];
+
+#[derive(Debug)]
+enum StorageNode {
+    Element(StorageElement),
+    Text(String),
+}
+
+#[derive(Debug)]
+struct StorageElement {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<StorageNode>,
+}
+
+impl StorageElement {
+    fn attr(&self, key: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn child_elements<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a StorageElement> {
+        self.children.iter().filter_map(move |child| match child {
+            StorageNode::Element(element) if element.name == name => Some(element),
+            _ => None,
+        })
+    }
+
+    fn is_code_macro(&self) -> bool {
+        self.name == "ac:structured-macro" && self.attr("ac:name") == Some("code")
+    }
+}
+
+/// Convert a storage-format body into Markdown.
+pub fn storage_to_markdown(storage: &str) -> String {
+    let mut blocks = Vec::new();
+    render_blocks(&parse_storage(storage), &mut blocks);
+    blocks.join("\n\n")
+}
+
+// --- Parsing ---
+
+/// Parse storage markup into a node tree. Unmatched closing tags are ignored and unclosed
+/// elements are closed at the end of the input.
+fn parse_storage(input: &str) -> Vec<StorageNode> {
+    let mut stack = vec![StorageElement {
+        name: String::new(),
+        attrs: Vec::new(),
+        children: Vec::new(),
+    }];
+    let mut rest = input;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = after.find("]]>").unwrap_or(after.len());
+            push_node(&mut stack, StorageNode::Text(after[..end].to_string()));
+            rest = after.get(end + 3..).unwrap_or("");
+        } else if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = after.find('>').unwrap_or(after.len());
+            close_element(&mut stack, &after[..end].trim().to_ascii_lowercase());
+            rest = after.get(end + 1..).unwrap_or("");
+        } else if rest.starts_with('<') && rest[1..].starts_with(|c: char| c.is_ascii_alphabetic())
+        {
+            let end = tag_end(rest);
+            let tag = rest[1..end].trim_end_matches('>');
+            let self_closing = tag.ends_with('/');
+            let element = parse_tag(tag.trim_end_matches('/'));
+            if self_closing || VOID_ELEMENTS.contains(&element.name.as_str()) {
+                push_node(&mut stack, StorageNode::Element(element));
+            } else {
+                stack.push(element);
+            }
+            rest = &rest[end..];
+        } else {
+            let end = match rest.strip_prefix('<') {
+                Some(after) => after.find('<').map_or(rest.len(), |i| i + 1),
+                None => rest.find('<').unwrap_or(rest.len()),
+            };
+            push_node(&mut stack, StorageNode::Text(decode_entities(&rest[..end])));
+            rest = &rest[end..];
+        }
+    }
+    while stack.len() > 1 {
+        close_top(&mut stack);
+    }
+    stack.pop().map(|root| root.children).unwrap_or_default()
+}
+
+fn push_node(stack: &mut [StorageElement], node: StorageNode) {
+    if let Some(parent) = stack.last_mut() {
+        parent.children.push(node);
+    }
+}
+
+fn close_top(stack: &mut Vec<StorageElement>) {
+    if let Some(element) = stack.pop() {
+        push_node(stack, StorageNode::Element(element));
+    }
+}
+
+/// Close the innermost open element called `name`, along with anything left open inside it.
+fn close_element(stack: &mut Vec<StorageElement>, name: &str) {
+    if let Some(index) = stack
+        .iter()
+        .skip(1)
+        .rposition(|element| element.name == name)
+    {
+        while stack.len() > index + 1 {
+            close_top(stack);
+        }
+    }
+}
+
+/// Byte offset just past the `>` ending the tag at the start of `input`, skipping quoted
+/// attribute values.
+fn tag_end(input: &str) -> usize {
+    let mut quote = None;
+    for (index, ch) in input.char_indices() {
+        match (quote, ch) {
+            (None, '"' | '\'') => quote = Some(ch),
+            (Some(open), _) if ch == open => quote = None,
+            (None, '>') => return index + 1,
+            _ => {}
+        }
+    }
+    input.len()
+}
+
+/// Parse the inside of an opening tag (`name attr="value" ...`) into an element.
+fn parse_tag(tag: &str) -> StorageElement {
+    let tag = tag.trim();
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let mut attrs = Vec::new();
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let key_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let key = rest[..key_end].to_ascii_lowercase();
+        rest = rest[key_end..].trim_start();
+        let mut value = String::new();
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (raw, remaining) = match after.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let body = &after[1..];
+                    let end = body.find(quote).unwrap_or(body.len());
+                    (&body[..end], body.get(end + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            value = decode_entities(raw);
+            rest = remaining.trim_start();
+        }
+        if !key.is_empty() {
+            attrs.push((key, value));
+        }
+    }
+    StorageElement {
+        name: tag[..name_end].to_ascii_lowercase(),
+        attrs,
+        children: Vec::new(),
+    }
+}
+
+/// Decode the named entities Confluence emits plus numeric character references. Anything
+/// unrecognised is kept verbatim.
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let decoded = after
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| decode_entity(&after[..end]).map(|ch| (ch, end)));
+        match decoded {
+            Some((ch, end)) => {
+                out.push(ch);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        "ndash" => Some('\u{2013}'),
+        "mdash" => Some('\u{2014}'),
+        "hellip" => Some('\u{2026}'),
+        "lsquo" => Some('\u{2018}'),
+        "rsquo" => Some('\u{2019}'),
+        "ldquo" => Some('\u{201c}'),
+        "rdquo" => Some('\u{201d}'),
+        _ => {
+            let number = name.strip_prefix('#')?;
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+// --- Rendering ---
+
+fn is_heading(name: &str) -> bool {
+    matches!(name, "h1" | "h2" | "h3" | "h4" | "h5" | "h6")
+}
+
+/// Whether a node has to be rendered as its own Markdown block rather than inline text.
+fn is_block(node: &StorageNode) -> bool {
+    match node {
+        StorageNode::Text(_) => false,
+        StorageNode::Element(element) => match element.name.as_str() {
+            "p" | "ul" | "ol" | "table" | "pre" | "blockquote" | "hr" => true,
+            name if is_heading(name) => true,
+            _ => element.is_code_macro() || element.children.iter().any(is_block),
+        },
+    }
+}
+
+/// Render a run of sibling nodes as Markdown blocks, gathering stray inline content into
+/// paragraphs.
+fn render_blocks(nodes: &[StorageNode], blocks: &mut Vec<String>) {
+    let mut inline = String::new();
+    for node in nodes {
+        match node {
+            StorageNode::Element(element) if is_block(node) => {
+                push_paragraph(&inline, blocks);
+                inline.clear();
+                render_block(element, blocks);
+            }
+            _ => inline.push_str(&render_inline(node)),
+        }
+    }
+    push_paragraph(&inline, blocks);
+}
+
+fn push_paragraph(text: &str, blocks: &mut Vec<String>) {
+    let text = tidy_inline(text);
+    if !text.is_empty() {
+        blocks.push(text);
+    }
+}
+
+fn render_block(element: &StorageElement, blocks: &mut Vec<String>) {
+    match element.name.as_str() {
+        "p" => push_paragraph(&render_inline_children(element), blocks),
+        name if is_heading(name) => {
+            let text = tidy_inline(&render_inline_children(element)).replace('\n', " ");
+            if !text.is_empty() {
+                blocks.push(format!(
+                    "{} {}",
+                    "#".repeat(usize::from(name.as_bytes()[1] - b'0')),
+                    text
+                ));
+            }
+        }
+        "ul" | "ol" => {
+            let mut lines = Vec::new();
+            render_list(element, "", &mut lines);
+            if !lines.is_empty() {
+                blocks.push(lines.join("\n"));
+            }
+        }
+        "table" => blocks.extend(render_table(element)),
+        "pre" => blocks.push(code_fence("", &text_content(element))),
+        "blockquote" => {
+            let mut inner = Vec::new();
+            render_blocks(&element.children, &mut inner);
+            if !inner.is_empty() {
+                let quoted = inner
+                    .join("\n\n")
+                    .lines()
+                    .map(|line| format!("> {}", line).trim_end().to_string())
+                    .collect::<Vec<_>>();
+                blocks.push(quoted.join("\n"));
+            }
+        }
+        "hr" => blocks.push("---".to_string()),
+        _ if element.is_code_macro() => blocks.push(render_code_macro(element)),
+        _ => render_blocks(&element.children, blocks),
+    }
+}
+
+/// Render a `ul`/`ol` as Markdown list lines, nesting child lists under their item.
+fn render_list(list: &StorageElement, indent: &str, lines: &mut Vec<String>) {
+    let ordered = list.name == "ol";
+    for (index, item) in list.child_elements("li").enumerate() {
+        let marker = if ordered {
+            format!("{}. ", index + 1)
+        } else {
+            "- ".to_string()
+        };
+        let child_indent = format!("{}{}", indent, " ".repeat(marker.len()));
+        let mut text = String::new();
+        let mut nested = Vec::new();
+        for child in &item.children {
+            match child {
+                StorageNode::Element(element) if element.name == "ul" || element.name == "ol" => {
+                    render_list(element, &child_indent, &mut nested)
+                }
+                StorageNode::Element(element)
+                    if is_block(child) && element.name != "p" && !is_heading(&element.name) =>
+                {
+                    let mut blocks = Vec::new();
+                    render_block(element, &mut blocks);
+                    for line in blocks.join("\n\n").lines() {
+                        nested.push(if line.is_empty() {
+                            String::new()
+                        } else {
+                            format!("{}{}", child_indent, line)
+                        });
+                    }
+                }
+                _ => text.push_str(&render_inline(child)),
+            }
+        }
+        let text = tidy_inline(&text).replace('\n', &format!("\n{}", child_indent));
+        lines.push(
+            format!("{}{}{}", indent, marker, text)
+                .trim_end()
+                .to_string(),
+        );
+        lines.extend(nested);
+    }
+}
+
+/// Render a table as a pipe table. The first row is used as the header row.
+fn render_table(table: &StorageElement) -> Option<String> {
+    let mut rows = Vec::new();
+    collect_rows(table, &mut rows);
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return None;
+    }
+    let mut lines = Vec::with_capacity(rows.len() + 1);
+    for (index, mut row) in rows.into_iter().enumerate() {
+        row.resize(columns, String::new());
+        lines.push(format!("| {} |", row.join(" | ")));
+        if index == 0 {
+            lines.push(format!("|{}", " --- |".repeat(columns)));
+        }
+    }
+    Some(lines.join("\n"))
+}
+
+/// Collect the cell text of every `tr` under `element`, looking through `thead`/`tbody` but
+/// not into nested tables.
+fn collect_rows(element: &StorageElement, rows: &mut Vec<Vec<String>>) {
+    for child in &element.children {
+        let StorageNode::Element(child) = child else {
+            continue;
+        };
+        match child.name.as_str() {
+            "tr" => rows.push(
+                child
+                    .children
+                    .iter()
+                    .filter_map(|cell| match cell {
+                        StorageNode::Element(cell) if cell.name == "th" || cell.name == "td" => {
+                            Some(
+                                tidy_inline(&render_inline_children(cell))
+                                    .split_whitespace()
+                                    .collect::<Vec<_>>()
+                                    .join(" ")
+                                    .replace('|', "\\|"),
+                            )
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            "table" => {}
+            _ => collect_rows(child, rows),
+        }
+    }
+}
+
+/// Render a `code` macro as a fenced block, keeping its `language` parameter.
+fn render_code_macro(element: &StorageElement) -> String {
+    let language = element
+        .child_elements("ac:parameter")
+        .find(|parameter| parameter.attr("ac:name") == Some("language"))
+        .map(text_content)
+        .unwrap_or_default();
+    let body = element
+        .child_elements("ac:plain-text-body")
+        .map(text_content)
+        .collect::<String>();
+    code_fence(language.trim(), &body)
+}
+
+fn code_fence(language: &str, body: &str) -> String {
+    format!("```{}\n{}\n```", language, body.trim_matches('\n'))
+}
+
+fn render_inline_children(element: &StorageElement) -> String {
+    element.children.iter().map(render_inline).collect()
+}
+
+/// Render a node as inline Markdown. Block elements met in an inline position (e.g. a
+/// paragraph inside a table cell) are flattened to their text.
+fn render_inline(node: &StorageNode) -> String {
+    let element = match node {
+        StorageNode::Text(text) => return collapse_whitespace(text),
+        StorageNode::Element(element) => element,
+    };
+    match element.name.as_str() {
+        "strong" | "b" => wrap_inline("**", &render_inline_children(element)),
+        "em" | "i" => wrap_inline("*", &render_inline_children(element)),
+        "code" => format!("`{}`", text_content(element)),
+        "a" => {
+            let text = tidy_inline(&render_inline_children(element));
+            match element.attr("href") {
+                Some(href) if text.is_empty() => format!("[{}]({})", href, href),
+                Some(href) => format!("[{}]({})", text, href),
+                None => text,
+            }
+        }
+        "br" => "\n".to_string(),
+        "ac:parameter" => String::new(),
+        "ac:plain-text-body" => text_content(element),
+        _ if element.is_code_macro() => {
+            let body = element
+                .child_elements("ac:plain-text-body")
+                .map(text_content)
+                .collect::<String>();
+            format!("`{}`", body.trim())
+        }
+        _ if is_block(node) => format!(" {} ", render_inline_children(element)),
+        _ => render_inline_children(element),
+    }
+}
+
+/// Wrap inline text in an emphasis marker, keeping surrounding whitespace outside of it.
+fn wrap_inline(marker: &str, inner: &str) -> String {
+    let text = inner.trim();
+    if text.is_empty() {
+        return inner.to_string();
+    }
+    let leading = if inner.starts_with(char::is_whitespace) {
+        " "
+    } else {
+        ""
+    };
+    let trailing = if inner.ends_with(char::is_whitespace) {
+        " "
+    } else {
+        ""
+    };
+    format!("{}{}{}{}{}", leading, marker, text, marker, trailing)
+}
+
+/// Concatenated raw text of every descendant, as needed for code.
+fn text_content(element: &StorageElement) -> String {
+    element
+        .children
+        .iter()
+        .map(|child| match child {
+            StorageNode::Text(text) => text.clone(),
+            StorageNode::Element(element) => text_content(element),
+        })
+        .collect()
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut last_was_space = false;
+    for ch in text.chars() {
+        if ch.is_whitespace() {
+            if !last_was_space {
+                out.push(' ');
+            }
+            last_was_space = true;
+        } else {
+            out.push(ch);
+            last_was_space = false;
+        }
+    }
+    out
+}
+
+/// Collapse the spacing of rendered inline text, keeping the line breaks from `<br>`.
+fn tidy_inline(text: &str) -> String {
+    text.split('\n')
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_nested_lists() {
+        let storage = "<ul><li><p>Plan</p><ol><li>Draft <strong>outline</strong></li>\
+            <li>Review<ul><li>with <a href=\"https://example.com/team\">the team</a></li></ul></li>\
+            </ol></li><li>Ship &amp; announce</li></ul>";
+        assert_eq!(
+            storage_to_markdown(storage),
+            "- Plan\n  1. Draft **outline**\n  2. Review\n     - with [the team](https://example.com/team)\n- Ship & announce"
+        );
+    }
+
+    #[test]
+    fn converts_table_with_header_row() {
+        let storage = "<h2>Owners</h2><table data-layout=\"default\"><tbody>\
+            <tr><th><p><strong>Service</strong></p></th><th><p>Owner</p></th></tr>\
+            <tr><td><p>api</p></td><td><p>Alice</p></td></tr>\
+            <tr><td><p>a | b</p></td></tr>\
+            </tbody></table>";
+        assert_eq!(
+            storage_to_markdown(storage),
+            "## Owners\n\n| **Service** | Owner |\n| --- | --- |\n| api | Alice |\n| a \\| b |  |"
+        );
+    }
+
+    /// The code macro becomes a fenced block; other macros fall back to their body text.
+    #[test]
+    fn converts_code_macro_and_degrades_unknown_macros() {
+        let storage = "<p>Run <em>this</em>:</p>\
+            <ac:structured-macro ac:name=\"code\" ac:schema-version=\"1\">\
+            <ac:parameter ac:name=\"language\">bash</ac:parameter>\
+            <ac:plain-text-body><![CDATA[cargo test\n]]></ac:plain-text-body></ac:structured-macro>\
+            <ac:structured-macro ac:name=\"info\"><ac:parameter ac:name=\"title\">Note</ac:parameter>\
+            <ac:rich-text-body><p>Needs <code>rustup</code>.</p></ac:rich-text-body></ac:structured-macro>\
+            <p>Status: <ac:structured-macro ac:name=\"jira\"><ac:parameter ac:name=\"key\">OPS-1</ac:parameter></ac:structured-macro>done</p>";
+        assert_eq!(
+            storage_to_markdown(storage),
+            "Run *this*:\n\n```bash\ncargo test\n```\n\nNeeds `rustup`.\n\nStatus: done"
+        );
+    }
+}
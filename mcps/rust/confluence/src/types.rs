@@ -12,6 +12,29 @@ pub struct PageVersion {
     /// Optional message describing the changes in this version
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
+    /// Account ID of the user who created this version, if returned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author_id: Option<String>,
+    /// Creation timestamp of this version (ISO 8601 format), if returned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+}
+
+/// One entry of a page's version history
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PageVersionInfo {
+    /// The version number
+    pub number: u32,
+    /// Message the editor left with this version, if any
+    #[serde(default)]
+    pub message: Option<String>,
+    /// Creation timestamp of this version (ISO 8601 format)
+    #[serde(default)]
+    pub created_at: Option<String>,
+    /// Account ID of the user who created this version
+    #[serde(default)]
+    pub author_id: Option<String>,
 }
 
 /// Represents the body content of a Confluence page or blog post
@@ -78,6 +101,115 @@ pub struct DeleteResult {
     pub message: String,
 }
 
+// --- Bulk Operation Structures ---
+
+/// A page to create as part of a bulk import
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BulkPageInput {
+    /// Title of the page
+    pub title: String,
+    /// Plain-text content, placed in a single paragraph
+    pub content: String,
+    /// ID of the parent page, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<u64>,
+}
+
+/// Outcome of one item of a bulk operation
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BulkItemResult {
+    /// Position of the item in the request
+    pub index: u32,
+    /// Title of the page the item refers to
+    pub title: String,
+    /// Whether the item succeeded
+    pub success: bool,
+    /// ID of the created page, on success
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_id: Option<String>,
+    /// Why the item failed, on failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Report of a bulk import run, which may have paused on a rate limit
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BulkImportReport {
+    /// Per-item outcomes for the items attempted in this run
+    pub results: Vec<BulkItemResult>,
+    /// Whether every item was attempted
+    pub completed: bool,
+    /// Index to pass as `start_index` to resume a paused run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resume_from: Option<u32>,
+    /// Suggested wait in seconds before resuming a paused run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+}
+
+/// An item a bulk operation could not apply to, with the reason
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct BulkFailure<T> {
+    /// The item that failed
+    pub item: T,
+    /// Why it failed
+    pub error: String,
+}
+
+/// Per-item outcome of applying one operation to many items
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct BulkResult<T> {
+    /// Items the operation was applied to
+    pub succeeded: Vec<T>,
+    /// Items it failed on
+    pub failed: Vec<BulkFailure<T>>,
+}
+
+/// Outcome of moving one page in a bulk move
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PageMoveResult {
+    /// ID of the page that was to be moved
+    pub page_id: u64,
+    /// The page under its new parent, on success
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<ContentDetails>,
+    /// Why the move failed, on failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// --- Search Structures ---
+
+/// One ranked result of a content search
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SearchHit {
+    /// ID of the matching page or blog post
+    pub id: String,
+    /// Title of the matching content
+    pub title: String,
+    /// Key of the space the content lives in
+    pub space_key: String,
+    /// Plain-text excerpt around the match
+    pub excerpt: String,
+    /// Relevance between 0 and 1, highest first
+    pub score: f64,
+}
+
+/// One match of a CQL content search
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SearchResult {
+    /// ID of the matching content
+    pub content_id: String,
+    /// Title of the matching content
+    pub title: String,
+    /// Kind of content, e.g. `page`, `blogpost`, `comment` or `attachment`
+    pub content_type: String,
+    /// Key of the space the content lives in
+    pub space_key: String,
+    /// Plain-text excerpt around the match
+    pub excerpt: String,
+}
+
 // --- Page & Blog Post Structures ---
 
 /// Summary information about Confluence content
@@ -128,6 +260,15 @@ pub struct ContentDetails {
     /// Type of the parent content, if any
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parent_type: Option<String>,
+    /// Number of direct child pages, filled in only when requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub child_count: Option<u32>,
+    /// Labels on the page, filled in only when requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<Label>>,
+    /// Read and update restrictions on the page, filled in only when requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restrictions: Option<PageRestrictions>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -166,6 +307,28 @@ pub struct CreateContentRequest<'a> {
     pub body: AtlasDocFormatBodyStr,
 }
 
+/// Where an inline comment is anchored: the `text_selection_match_index`-th (0-based) of the
+/// `text_selection_match_count` occurrences of `text_selection` in the page
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InlineCommentProperties {
+    pub text_selection: String,
+    pub text_selection_match_count: u32,
+    pub text_selection_match_index: u32,
+}
+
+/// Request to create an inline comment on a page
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateInlineCommentRequest {
+    /// ID of the page to comment on
+    pub page_id: u64,
+    /// The comment body in storage format
+    pub body: StorageBody,
+    /// The text selection the comment is attached to
+    pub inline_comment_properties: InlineCommentProperties,
+}
+
 /// Request to update existing content in Confluence
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -220,6 +383,27 @@ pub struct SpaceSummary {
     pub status: String,
 }
 
+/// Request to create a new space
+#[derive(Serialize, Debug)]
+pub struct CreateSpaceRequest<'a> {
+    /// Key of the space (uppercase letters and digits)
+    pub key: &'a str,
+    /// Name of the space
+    pub name: &'a str,
+    /// Description of the space, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<SpaceDescription<'a>>,
+}
+
+/// Description attached to a space on creation
+#[derive(Serialize, Debug)]
+pub struct SpaceDescription<'a> {
+    /// The description text
+    pub value: &'a str,
+    /// Format of the description (e.g. 'plain')
+    pub representation: &'a str,
+}
+
 // --- Label Structures ---
 
 /// Represents a label in Confluence
@@ -234,6 +418,27 @@ pub struct Label {
     pub prefix: String,
 }
 
+// --- Attachment Structures ---
+
+/// A file attached to a page
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentInfo {
+    /// Unique identifier of the attachment (e.g. `att123456`)
+    pub id: String,
+    /// File name of the attachment
+    pub title: String,
+    /// MIME type of the file
+    #[serde(default)]
+    pub media_type: Option<String>,
+    /// Size of the file in bytes
+    #[serde(default)]
+    pub file_size: Option<u64>,
+    /// Download link, relative to the site's `/wiki` base
+    #[serde(default)]
+    pub download_link: Option<String>,
+}
+
 // --- Comment Structures ---
 
 /// Returned for both footer and inline comments.
@@ -294,6 +499,36 @@ pub struct UpdateCommentRequest {
     pub body: StorageBody,
 }
 
+// --- Page Restriction Structures ---
+
+/// Users and groups an operation on a page is limited to; both empty means anyone with space
+/// permission may perform it
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct OperationRestrictions {
+    /// Account IDs of the users allowed
+    pub users: Vec<String>,
+    /// Names of the groups allowed
+    pub groups: Vec<String>,
+}
+
+/// Restrictions set directly on a page (inherited ones from ancestors are not included)
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct PageRestrictions {
+    pub read: OperationRestrictions,
+    pub update: OperationRestrictions,
+}
+
+// --- Watch Structures ---
+
+/// Whether the integration user watches a page after a watch or unwatch call
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct WatchStatus {
+    pub page_id: String,
+    pub watching: bool,
+    /// False when the page was already in the requested state and nothing was sent
+    pub changed: bool,
+}
+
 // --- Space Permission (Role) Structures ---
 
 /// Represents a permission (role) on a space
@@ -379,6 +614,17 @@ pub struct BlogPostDetails {
     pub author_id: Option<String>,
 }
 
+/// Layout of one column of a generated table
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ColumnSpec {
+    /// Text alignment of the column's cells: "start", "center" or "end"
+    #[serde(default)]
+    pub align: Option<String>,
+    /// Width of the column in pixels
+    #[serde(default)]
+    pub width: Option<u32>,
+}
+
 // --- Page Hierarchy Structures ---
 
 /// Represents a page in a hierarchy
@@ -400,6 +646,9 @@ pub struct PageHierarchyItem {
     /// Depth of the page in the hierarchy, if any
     #[serde(skip_serializing_if = "Option::is_none")]
     pub depth: Option<u32>,
+    /// Kind of content (e.g., 'page', 'whiteboard', 'database'), if returned
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
 }
 
 /// Response containing a list of pages in a hierarchy
@@ -6,6 +6,7 @@ use weil_rs::http::{HttpClient, HttpMethod};
 pub struct AuthClient {
     base_url: String,
     token: String,
+    user_agent: String,
 }
 
 impl AuthClient {
@@ -16,14 +17,33 @@ impl AuthClient {
         Self {
             base_url,
             token: personal_access_token.to_string(),
+            user_agent: crate::DEFAULT_USER_AGENT.to_string(),
         }
     }
 
+    /// Create an AuthClient for account-level APIs, rooted at `/accounts/{account_id}` on the account console
+    pub fn for_account(account_url: &str, account_id: &str, personal_access_token: &str) -> Self {
+        let base_url = format!("{}/api/2.0/accounts/{}", account_url.trim_end_matches('/'), account_id);
+
+        Self {
+            base_url,
+            token: personal_access_token.to_string(),
+            user_agent: crate::DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+
+    /// Override the User-Agent sent with every request
+    pub fn with_user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = user_agent;
+        self
+    }
+
     /// Get headers with authentication
     fn get_headers(&self) -> HashMap<String, String> {
         let mut headers = HashMap::new();
         headers.insert("Authorization".to_string(), format!("Bearer {}", self.token));
         headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert("User-Agent".to_string(), self.user_agent.clone());
         headers
     }
 
@@ -37,7 +57,7 @@ impl AuthClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -54,7 +74,7 @@ impl AuthClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -84,7 +104,24 @@ impl AuthClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
+        }
+
+        let response_text = response.text();
+        Ok(response_text)
+    }
+
+    /// List all users in the account (account-level SCIM; requires a client built with `for_account`)
+    pub async fn list_account_users(&self) -> Result<String, String> {
+        let url = format!("{}/scim/v2/Users", self.base_url);
+
+        let response = HttpClient::request(&url, HttpMethod::Get)
+            .headers(self.get_headers())
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if response.status() < 200 || response.status() >= 300 {
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
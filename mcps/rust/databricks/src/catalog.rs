@@ -1,10 +1,50 @@
+use serde_json::Value;
 use std::collections::HashMap;
 use weil_rs::http::{HttpClient, HttpMethod};
 
+/// Securable types whose grants can be read and changed
+const SECURABLE_TYPES: [&str; 4] = ["catalog", "schema", "table", "function"];
+
+/// Check `securable_type` is one the permissions API is used with here, returning it lowercased
+pub fn validate_securable_type(securable_type: &str) -> Result<String, String> {
+    let normalized = securable_type.trim().to_lowercase();
+    if SECURABLE_TYPES.contains(&normalized.as_str()) {
+        Ok(normalized)
+    } else {
+        Err(format!("unknown securable type '{}', expected one of: {}", securable_type, SECURABLE_TYPES.join(", ")))
+    }
+}
+
+/// Parse and check a grants PATCH body: `{"changes": [{"principal": ..., "add": [...], "remove": [...]}]}`
+pub fn parse_grant_changes(changes: &str) -> Result<Value, String> {
+    let body: Value = serde_json::from_str(changes).map_err(|e| format!("Invalid changes JSON: {}", e))?;
+    let entries = body.get("changes").and_then(Value::as_array).ok_or("changes must be an object with a \"changes\" array")?;
+    if entries.is_empty() {
+        return Err("changes must list at least one principal".to_string());
+    }
+    for entry in entries {
+        match entry.get("principal").and_then(Value::as_str) {
+            Some(principal) if !principal.trim().is_empty() => {}
+            _ => return Err("each change needs a non-empty \"principal\"".to_string()),
+        }
+        for field in ["add", "remove"] {
+            let valid = entry.get(field).is_none_or(|privileges| privileges.as_array().is_some_and(|privileges| privileges.iter().all(Value::is_string)));
+            if !valid {
+                return Err(format!("\"{}\" must be a list of privilege names", field));
+            }
+        }
+        if entry.get("add").is_none() && entry.get("remove").is_none() {
+            return Err("each change needs \"add\" or \"remove\"".to_string());
+        }
+    }
+    Ok(body)
+}
+
 /// Catalog management functions for Databricks Unity Catalog
 pub struct CatalogClient {
     base_url: String,
     token: String,
+    user_agent: String,
 }
 
 impl CatalogClient {
@@ -15,14 +55,22 @@ impl CatalogClient {
         Self {
             base_url,
             token: personal_access_token.to_string(),
+            user_agent: crate::DEFAULT_USER_AGENT.to_string(),
         }
     }
 
+    /// Override the User-Agent sent with every request
+    pub fn with_user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = user_agent;
+        self
+    }
+
     /// Get headers with authentication
     fn get_headers(&self) -> HashMap<String, String> {
         let mut headers = HashMap::new();
         headers.insert("Authorization".to_string(), format!("Bearer {}", self.token));
         headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert("User-Agent".to_string(), self.user_agent.clone());
         headers
     }
 
@@ -36,7 +84,7 @@ impl CatalogClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -53,7 +101,7 @@ impl CatalogClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -70,7 +118,7 @@ impl CatalogClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -87,7 +135,7 @@ impl CatalogClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -105,7 +153,7 @@ impl CatalogClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -123,7 +171,45 @@ impl CatalogClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
+        }
+
+        let response_text = response.text();
+        Ok(response_text)
+    }
+
+    /// Get the grants on a securable, e.g. `("table", "main.sales.orders")`
+    pub async fn get_grants(&self, securable_type: String, full_name: String) -> Result<String, String> {
+        let securable_type = validate_securable_type(&securable_type)?;
+        let url = format!("{}/unity-catalog/permissions/{}/{}", self.base_url, securable_type, full_name);
+
+        let response = HttpClient::request(&url, HttpMethod::Get)
+            .headers(self.get_headers())
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if response.status() < 200 || response.status() >= 300 {
+            return Err(crate::api_error(response.status(), Some(response.text())));
+        }
+
+        let response_text = response.text();
+        Ok(response_text)
+    }
+
+    /// Add and remove privileges on a securable for one or more principals
+    pub async fn update_grants(&self, securable_type: String, full_name: String, changes: String) -> Result<String, String> {
+        let securable_type = validate_securable_type(&securable_type)?;
+        let body = parse_grant_changes(&changes)?;
+        let url = format!("{}/unity-catalog/permissions/{}/{}", self.base_url, securable_type, full_name);
+
+        let response = HttpClient::request(&url, HttpMethod::Patch)
+            .headers(self.get_headers())
+            .json(&body)
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if response.status() < 200 || response.status() >= 300 {
+            return Err(crate::api_error(response.status(), Some(response.text())));
         }
 
         let response_text = response.text();
@@ -140,10 +226,37 @@ impl CatalogClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
         Ok(response_text)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Known securable types pass in any case; anything else is refused before a request is made.
+    #[test]
+    fn test_validate_securable_type() {
+        assert_eq!(validate_securable_type("table").unwrap(), "table");
+        assert_eq!(validate_securable_type(" Schema ").unwrap(), "schema");
+        assert!(validate_securable_type("volume").unwrap_err().contains("catalog, schema, table, function"));
+        assert!(validate_securable_type("").is_err());
+    }
+
+    /// A changes body needs principals with privilege lists to add or remove.
+    #[test]
+    fn test_parse_grant_changes() {
+        let body = parse_grant_changes(r#"{"changes": [{"principal": "data-engineers", "add": ["SELECT", "MODIFY"]}, {"principal": "analysts", "remove": ["MODIFY"]}]}"#).unwrap();
+        assert_eq!(body["changes"][0]["add"][1], "MODIFY");
+
+        assert!(parse_grant_changes("[]").is_err());
+        assert!(parse_grant_changes(r#"{"changes": []}"#).is_err());
+        assert!(parse_grant_changes(r#"{"changes": [{"add": ["SELECT"]}]}"#).unwrap_err().contains("principal"));
+        assert!(parse_grant_changes(r#"{"changes": [{"principal": "analysts"}]}"#).is_err());
+        assert!(parse_grant_changes(r#"{"changes": [{"principal": "analysts", "add": "SELECT"}]}"#).is_err());
+    }
+}
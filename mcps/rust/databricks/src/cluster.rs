@@ -1,11 +1,51 @@
+use serde::{Deserialize, Serialize};
 use serde_json;
 use std::collections::HashMap;
+use weil_macros::WeilType;
 use weil_rs::http::{HttpClient, HttpMethod};
 
+/// The parts of a cluster's description agents usually need
+#[derive(Debug, Serialize, Deserialize, WeilType, Default, PartialEq)]
+pub struct ClusterInfo {
+    pub cluster_id: String,
+    #[serde(default)]
+    pub cluster_name: String,
+    /// Lifecycle state such as `PENDING`, `RUNNING` or `TERMINATED`
+    #[serde(default)]
+    pub state: String,
+    #[serde(default)]
+    pub spark_version: String,
+    /// Absent when the cluster draws its nodes from an instance pool
+    #[serde(default)]
+    pub node_type_id: Option<String>,
+    /// Absent for autoscaling clusters, which report `autoscale` instead
+    #[serde(default)]
+    pub num_workers: Option<i32>,
+}
+
+/// Body of `clusters/list`; `clusters` is omitted when the workspace has none
+#[derive(Deserialize)]
+struct ClusterList {
+    #[serde(default)]
+    clusters: Vec<ClusterInfo>,
+}
+
+/// Parse a `clusters/list` response
+pub fn parse_cluster_list(response: &str) -> Result<Vec<ClusterInfo>, String> {
+    let list: ClusterList = serde_json::from_str(response).map_err(|e| format!("Failed to parse cluster list: {}", e))?;
+    Ok(list.clusters)
+}
+
+/// Parse a `clusters/get` response
+pub fn parse_cluster_info(response: &str) -> Result<ClusterInfo, String> {
+    serde_json::from_str(response).map_err(|e| format!("Failed to parse cluster: {}", e))
+}
+
 /// Cluster management operations for Databricks
 pub struct ClusterClient {
     base_url: String,
     token: String,
+    user_agent: String,
 }
 
 impl ClusterClient {
@@ -16,19 +56,32 @@ impl ClusterClient {
         Self {
             base_url,
             token: personal_access_token.to_string(),
+            user_agent: crate::DEFAULT_USER_AGENT.to_string(),
         }
     }
 
+    /// Override the User-Agent sent with every request
+    pub fn with_user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = user_agent;
+        self
+    }
+
     /// Get headers with authentication
     fn get_headers(&self) -> HashMap<String, String> {
         let mut headers = HashMap::new();
         headers.insert("Authorization".to_string(), format!("Bearer {}", self.token));
         headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert("User-Agent".to_string(), self.user_agent.clone());
         headers
     }
 
     /// List all clusters
-    pub async fn list_clusters(&self) -> Result<String, String> {
+    pub async fn list_clusters(&self) -> Result<Vec<ClusterInfo>, String> {
+        parse_cluster_list(&self.list_clusters_raw().await?)
+    }
+
+    /// List all clusters, returning the API response as is
+    pub async fn list_clusters_raw(&self) -> Result<String, String> {
         let url = format!("{}/clusters/list", self.base_url);
         
         let response = HttpClient::request(&url, HttpMethod::Get)
@@ -37,7 +90,7 @@ impl ClusterClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -45,7 +98,12 @@ impl ClusterClient {
     }
 
     /// Get a specific cluster by ID
-    pub async fn get_cluster(&self, cluster_id: String) -> Result<String, String> {
+    pub async fn get_cluster(&self, cluster_id: String) -> Result<ClusterInfo, String> {
+        parse_cluster_info(&self.get_cluster_raw(cluster_id).await?)
+    }
+
+    /// Get a specific cluster by ID, returning the API response as is
+    pub async fn get_cluster_raw(&self, cluster_id: String) -> Result<String, String> {
         let url = format!("{}/clusters/get?cluster_id={}", self.base_url, cluster_id);
         
         let response = HttpClient::request(&url, HttpMethod::Get)
@@ -54,7 +112,7 @@ impl ClusterClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -82,10 +140,42 @@ impl ClusterClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
         Ok(response_text)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fixed-size and autoscaling clusters both parse, and an empty workspace lists no clusters.
+    #[test]
+    fn test_parse_clusters() {
+        let response = r#"{"clusters": [
+            {"cluster_id": "0123-456789-abcde", "cluster_name": "etl", "state": "RUNNING", "spark_version": "14.3.x-scala2.12",
+             "node_type_id": "i3.xlarge", "num_workers": 2, "autotermination_minutes": 30},
+            {"cluster_id": "0123-456789-fghij", "cluster_name": "adhoc", "state": "TERMINATED", "spark_version": "15.4.x-scala2.12",
+             "instance_pool_id": "pool-1", "autoscale": {"min_workers": 1, "max_workers": 4}}
+        ]}"#;
+        let clusters = parse_cluster_list(response).unwrap();
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0], ClusterInfo {
+            cluster_id: "0123-456789-abcde".to_string(),
+            cluster_name: "etl".to_string(),
+            state: "RUNNING".to_string(),
+            spark_version: "14.3.x-scala2.12".to_string(),
+            node_type_id: Some("i3.xlarge".to_string()),
+            num_workers: Some(2),
+        });
+        assert_eq!(clusters[1].node_type_id, None);
+        assert_eq!(clusters[1].num_workers, None);
+
+        assert!(parse_cluster_list("{}").unwrap().is_empty());
+        assert_eq!(parse_cluster_info(r#"{"cluster_id": "c1", "state": "PENDING"}"#).unwrap().state, "PENDING");
+        assert!(parse_cluster_info("{}").is_err());
+    }
+}
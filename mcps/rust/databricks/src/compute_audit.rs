@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use weil_macros::WeilType;
+
+/// Longest cluster auto-termination accepted when `max_cluster_autotermination_mins` is not configured
+pub const DEFAULT_MAX_CLUSTER_AUTOTERMINATION_MINS: u32 = 60;
+/// Longest warehouse auto-stop accepted when `max_warehouse_auto_stop_mins` is not configured
+pub const DEFAULT_MAX_WAREHOUSE_AUTO_STOP_MINS: u32 = 30;
+
+/// Upper bounds, in minutes, for idle compute to keep running
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutostopThresholds {
+    pub max_cluster_autotermination_mins: u32,
+    pub max_warehouse_auto_stop_mins: u32,
+}
+
+impl Default for AutostopThresholds {
+    fn default() -> Self {
+        Self {
+            max_cluster_autotermination_mins: DEFAULT_MAX_CLUSTER_AUTOTERMINATION_MINS,
+            max_warehouse_auto_stop_mins: DEFAULT_MAX_WAREHOUSE_AUTO_STOP_MINS,
+        }
+    }
+}
+
+/// A cluster or SQL warehouse whose idle shutdown is disabled or longer than allowed
+#[derive(Debug, Serialize, Deserialize, WeilType, Default, PartialEq)]
+pub struct ComputeAuditFinding {
+    /// `cluster` or `warehouse`
+    pub resource_type: String,
+    pub resource_id: String,
+    pub name: String,
+    /// Configured auto-termination (clusters) or auto-stop (warehouses) in minutes; 0 means disabled
+    pub current_minutes: u32,
+    /// Setting to apply, i.e. the configured threshold
+    pub recommended_minutes: u32,
+    pub issue: String,
+}
+
+#[derive(Deserialize)]
+struct ClusterList {
+    #[serde(default)]
+    clusters: Vec<ClusterAutotermination>,
+}
+
+/// Databricks omits `autotermination_minutes` when it is disabled
+#[derive(Deserialize)]
+struct ClusterAutotermination {
+    cluster_id: String,
+    #[serde(default)]
+    cluster_name: String,
+    #[serde(default)]
+    autotermination_minutes: u32,
+    /// `JOB` clusters are torn down with their run, so auto-termination does not apply to them
+    #[serde(default)]
+    cluster_source: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WarehouseList {
+    #[serde(default)]
+    warehouses: Vec<WarehouseAutoStop>,
+}
+
+#[derive(Deserialize)]
+struct WarehouseAutoStop {
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    auto_stop_mins: u32,
+}
+
+/// Build a finding if `current` (0 meaning disabled) is not within `max`
+fn autostop_finding(resource_type: &str, resource_id: String, name: String, setting: &str, current: u32, max: u32) -> Option<ComputeAuditFinding> {
+    let issue = if current == 0 {
+        format!("{} is disabled", setting)
+    } else if current > max {
+        format!("{} of {} minutes exceeds the {}-minute limit", setting, current, max)
+    } else {
+        return None;
+    };
+    Some(ComputeAuditFinding {
+        resource_type: resource_type.to_string(),
+        resource_id,
+        name,
+        current_minutes: current,
+        recommended_minutes: max,
+        issue,
+    })
+}
+
+/// Audit `clusters/list` and `sql/warehouses` responses against `thresholds`, clusters first
+pub fn audit_autostop(clusters_response: &str, warehouses_response: &str, thresholds: AutostopThresholds) -> Result<Vec<ComputeAuditFinding>, String> {
+    let clusters: ClusterList = serde_json::from_str(clusters_response).map_err(|e| format!("Failed to parse cluster list: {}", e))?;
+    let warehouses: WarehouseList = serde_json::from_str(warehouses_response).map_err(|e| format!("Failed to parse warehouse list: {}", e))?;
+
+    let cluster_findings = clusters.clusters.into_iter()
+        .filter(|cluster| cluster.cluster_source.as_deref() != Some("JOB"))
+        .filter_map(|cluster| autostop_finding("cluster", cluster.cluster_id, cluster.cluster_name, "auto-termination", cluster.autotermination_minutes, thresholds.max_cluster_autotermination_mins));
+    let warehouse_findings = warehouses.warehouses.into_iter()
+        .filter_map(|warehouse| autostop_finding("warehouse", warehouse.id, warehouse.name, "auto-stop", warehouse.auto_stop_mins, thresholds.max_warehouse_auto_stop_mins));
+    Ok(cluster_findings.chain(warehouse_findings).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Disabled or over-long idle shutdown is flagged; compliant compute and job clusters are not.
+    #[test]
+    fn test_audit_autostop() {
+        let clusters = r#"{"clusters": [
+            {"cluster_id": "0123-456789-abcde", "cluster_name": "etl", "autotermination_minutes": 30},
+            {"cluster_id": "0123-456789-fghij", "cluster_name": "always-on", "cluster_source": "UI"},
+            {"cluster_id": "0123-456789-klmno", "cluster_name": "job-run", "cluster_source": "JOB"}
+        ]}"#;
+        let warehouses = r#"{"warehouses": [
+            {"id": "wh1", "name": "bi", "auto_stop_mins": 120},
+            {"id": "wh2", "name": "serverless", "auto_stop_mins": 10}
+        ]}"#;
+
+        let findings = audit_autostop(clusters, warehouses, AutostopThresholds::default()).unwrap();
+        assert_eq!(findings, vec![
+            ComputeAuditFinding {
+                resource_type: "cluster".to_string(),
+                resource_id: "0123-456789-fghij".to_string(),
+                name: "always-on".to_string(),
+                current_minutes: 0,
+                recommended_minutes: 60,
+                issue: "auto-termination is disabled".to_string(),
+            },
+            ComputeAuditFinding {
+                resource_type: "warehouse".to_string(),
+                resource_id: "wh1".to_string(),
+                name: "bi".to_string(),
+                current_minutes: 120,
+                recommended_minutes: 30,
+                issue: "auto-stop of 120 minutes exceeds the 30-minute limit".to_string(),
+            },
+        ]);
+
+        let strict = AutostopThresholds { max_cluster_autotermination_mins: 20, max_warehouse_auto_stop_mins: 5 };
+        assert_eq!(audit_autostop(clusters, warehouses, strict).unwrap().len(), 4);
+        assert!(audit_autostop("{}", "{}", AutostopThresholds::default()).unwrap().is_empty());
+    }
+}
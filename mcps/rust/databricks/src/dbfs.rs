@@ -1,11 +1,121 @@
+use serde::{Deserialize, Serialize};
 use serde_json;
-use std::collections::HashMap;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+use weil_macros::WeilType;
 use weil_rs::http::{HttpClient, HttpMethod};
+use weil_rs::runtime::Runtime;
+
+/// A file or directory found by `list_dbfs_files_recursive`
+#[derive(Debug, Serialize, Deserialize, WeilType, PartialEq)]
+pub struct DbfsFileInfo {
+    pub path: String,
+    pub is_dir: bool,
+    /// Size in bytes; 0 for directories
+    #[serde(default)]
+    pub file_size: u64,
+}
+
+#[derive(Deserialize)]
+struct DbfsListing {
+    #[serde(default)]
+    files: Vec<DbfsFileInfo>,
+}
+
+/// Deepest a recursive listing goes, whatever `max_depth` asks for
+pub const MAX_DBFS_WALK_DEPTH: u32 = 32;
+/// Most entries a recursive listing returns before giving up on the tree as too large
+pub const MAX_DBFS_WALK_ENTRIES: usize = 10_000;
+
+/// Walk the tree under `root` breadth-first, listing each directory with `list` (given its path, returning the
+/// `dbfs/list` response). `max_depth` counts levels below `root`: 1 lists `root` alone, and it is capped at
+/// `MAX_DBFS_WALK_DEPTH`. A directory is only listed once, even if it is reached again, and the walk fails rather
+/// than return more than `MAX_DBFS_WALK_ENTRIES` entries.
+pub fn walk_dbfs_tree<F>(root: &str, max_depth: Option<u32>, mut list: F) -> Result<Vec<DbfsFileInfo>, String>
+where
+    F: FnMut(&str) -> Result<String, String>,
+{
+    if max_depth == Some(0) {
+        return Err("max_depth must be greater than 0".to_string());
+    }
+    let max_depth = max_depth.unwrap_or(MAX_DBFS_WALK_DEPTH).min(MAX_DBFS_WALK_DEPTH);
+    let normalize = |path: &str| match path.trim_end_matches('/') {
+        "" => "/".to_string(),
+        path => path.to_string(),
+    };
+
+    let mut entries = Vec::new();
+    let mut visited = HashSet::new();
+    let mut pending = VecDeque::from([(normalize(root), 1)]);
+    while let Some((dir, depth)) = pending.pop_front() {
+        if !visited.insert(dir.clone()) {
+            continue;
+        }
+        let listing: DbfsListing = serde_json::from_str(&list(&dir)?).map_err(|e| format!("Failed to parse listing of {}: {}", dir, e))?;
+        for entry in listing.files {
+            if entry.is_dir && depth < max_depth {
+                pending.push_back((normalize(&entry.path), depth + 1));
+            }
+            entries.push(entry);
+        }
+        if entries.len() > MAX_DBFS_WALK_ENTRIES {
+            return Err(format!("{} holds more than {} entries; list a subdirectory or lower max_depth", root, MAX_DBFS_WALK_ENTRIES));
+        }
+    }
+    Ok(entries)
+}
+
+/// Bytes sent per `add-block` call; their base64 encoding is exactly 1 MiB, the most DBFS accepts in one block
+pub const DBFS_BLOCK_BYTES: usize = 768 * 1024;
+
+/// Upload `content` through the DBFS streaming API: `create` a handle (replacing an existing file only with
+/// `overwrite`), `add-block` it in `DBFS_BLOCK_BYTES` pieces, then `close` it. `send` is given each call's endpoint
+/// (e.g. `dbfs/create`) and body. If a block fails the handle is still closed, so it isn't left open on the server,
+/// and the block's error is returned. Returns the number of bytes written.
+pub fn stream_dbfs_upload<F>(path: &str, content: &[u8], overwrite: bool, mut send: F) -> Result<u64, String>
+where
+    F: FnMut(&str, Value) -> Result<String, String>,
+{
+    let created: Value = serde_json::from_str(&send("dbfs/create", serde_json::json!({ "path": path, "overwrite": overwrite }))?)
+        .map_err(|e| format!("Failed to parse create response: {}", e))?;
+    let handle = created["handle"].as_i64().ok_or_else(|| format!("create response has no handle: {}", created))?;
+
+    for block in content.chunks(DBFS_BLOCK_BYTES) {
+        let data = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, block);
+        if let Err(err) = send("dbfs/add-block", serde_json::json!({ "handle": handle, "data": data })) {
+            // Best effort: the block's error is the one worth reporting
+            let _ = send("dbfs/close", serde_json::json!({ "handle": handle }));
+            return Err(err);
+        }
+    }
+
+    send("dbfs/close", serde_json::json!({ "handle": handle }))?;
+    Ok(content.len() as u64)
+}
+
+/// Read a file's content from the IMFS applet by its file descriptor
+async fn read_imfs_file(file_descriptor: String) -> Result<String, String> {
+    #[derive(Serialize, Deserialize)]
+    struct Args {
+        file_descriptor: String,
+    }
+
+    // SAFETY: `imfs` is a systemic applet
+    let contract_addr = Runtime::contract_id_for_name("imfs").unwrap();
+
+    Runtime::call_contract::<String>(
+        contract_addr,
+        "read".to_string(),
+        Some(serde_json::to_string(&Args { file_descriptor }).unwrap()),
+    )
+    .map_err(|err| err.to_string())
+}
 
 /// DBFS (Databricks File System) operations
 pub struct DbfsClient {
     base_url: String,
     token: String,
+    user_agent: String,
 }
 
 impl DbfsClient {
@@ -16,14 +126,22 @@ impl DbfsClient {
         Self {
             base_url,
             token: personal_access_token.to_string(),
+            user_agent: crate::DEFAULT_USER_AGENT.to_string(),
         }
     }
 
+    /// Override the User-Agent sent with every request
+    pub fn with_user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = user_agent;
+        self
+    }
+
     /// Get headers with authentication
     fn get_headers(&self) -> HashMap<String, String> {
         let mut headers = HashMap::new();
         headers.insert("Authorization".to_string(), format!("Bearer {}", self.token));
         headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert("User-Agent".to_string(), self.user_agent.clone());
         headers
     }
 
@@ -37,13 +155,30 @@ impl DbfsClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
         Ok(response_text)
     }
 
+    /// List everything under a directory, descending up to `max_depth` levels
+    pub async fn list_dbfs_files_recursive(&self, path: String, max_depth: Option<u32>) -> Result<Vec<DbfsFileInfo>, String> {
+        walk_dbfs_tree(&path, max_depth, |dir| {
+            let url = format!("{}/dbfs/list", self.base_url);
+            let response = HttpClient::request(&url, HttpMethod::Get)
+                .headers(self.get_headers())
+                .query(vec![("path".to_string(), dir.to_string())])
+                .send()
+                .map_err(|e| format!("Request failed: {}", e))?;
+
+            if response.status() < 200 || response.status() >= 300 {
+                return Err(crate::api_error(response.status(), Some(response.text())));
+            }
+            Ok(response.text())
+        })
+    }
+
     /// Get file information
     pub async fn get_dbfs_file_info(&self, path: String) -> Result<String, String> {
         let url = format!("{}/dbfs/get-status?path={}", self.base_url, urlencoding::encode(&path));
@@ -54,7 +189,7 @@ impl DbfsClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -77,7 +212,7 @@ impl DbfsClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -100,7 +235,7 @@ impl DbfsClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -123,7 +258,7 @@ impl DbfsClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -151,13 +286,32 @@ impl DbfsClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
         Ok(response_text)
     }
 
+    /// Write a file stored in IMFS to DBFS in blocks, for files too large for a single `put`; returns the bytes written
+    pub async fn write_dbfs_file_streaming(&self, path: String, file_descriptor: String, overwrite: bool) -> Result<u64, String> {
+        let content = read_imfs_file(file_descriptor).await?;
+
+        stream_dbfs_upload(&path, content.as_bytes(), overwrite, |endpoint, request| {
+            let url = format!("{}/{}", self.base_url, endpoint);
+            let response = HttpClient::request(&url, HttpMethod::Post)
+                .headers(self.get_headers())
+                .json(&request)
+                .send()
+                .map_err(|e| format!("Request failed: {}", e))?;
+
+            if response.status() < 200 || response.status() >= 300 {
+                return Err(crate::api_error(response.status(), Some(response.text())));
+            }
+            Ok(response.text())
+        })
+    }
+
     /// Read file contents from DBFS
     pub async fn read_dbfs_file(&self, path: String, offset: Option<i64>, length: Option<i64>) -> Result<String, String> {
         let mut url = format!("{}/dbfs/read?path={}", self.base_url, urlencoding::encode(&path));
@@ -176,7 +330,7 @@ impl DbfsClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -215,7 +369,7 @@ impl DbfsClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -232,10 +386,115 @@ impl DbfsClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
         Ok(response_text)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Content goes out in 1 MiB base64 blocks between `create` and `close`, and the byte count is returned.
+    #[test]
+    fn test_stream_dbfs_upload() {
+        let content = vec![7u8; DBFS_BLOCK_BYTES * 2 + 10];
+        let mut calls = Vec::new();
+        let written = stream_dbfs_upload("/tmp/big.bin", &content, true, |endpoint, request| {
+            calls.push((endpoint.to_string(), request));
+            Ok(if endpoint == "dbfs/create" { r#"{"handle": 9}"#.to_string() } else { "{}".to_string() })
+        })
+        .unwrap();
+
+        assert_eq!(written, content.len() as u64);
+        let endpoints: Vec<&str> = calls.iter().map(|(endpoint, _)| endpoint.as_str()).collect();
+        assert_eq!(endpoints, ["dbfs/create", "dbfs/add-block", "dbfs/add-block", "dbfs/add-block", "dbfs/close"]);
+        assert_eq!(calls[0].1, serde_json::json!({ "path": "/tmp/big.bin", "overwrite": true }));
+        assert_eq!(calls[1].1["handle"], 9);
+        assert_eq!(calls[1].1["data"].as_str().unwrap().len(), 1024 * 1024);
+        let last_block = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, calls[3].1["data"].as_str().unwrap()).unwrap();
+        assert_eq!(last_block.len(), 10);
+        assert_eq!(calls[4].1, serde_json::json!({ "handle": 9 }));
+    }
+
+    /// Directories are listed breadth-first down to `max_depth`, each only once, and a root given with a trailing
+    /// slash is the same directory.
+    #[test]
+    fn test_walk_dbfs_tree() {
+        let listing = |dir: &str| -> Result<String, String> {
+            Ok(match dir {
+                "/mnt/raw" => r#"{"files": [
+                    {"path": "/mnt/raw/2024", "is_dir": true, "file_size": 0},
+                    {"path": "/mnt/raw/README.md", "is_dir": false, "file_size": 120}
+                ]}"#,
+                "/mnt/raw/2024" => r#"{"files": [
+                    {"path": "/mnt/raw/2024/01", "is_dir": true, "file_size": 0},
+                    {"path": "/mnt/raw/", "is_dir": true, "file_size": 0}
+                ]}"#,
+                "/mnt/raw/2024/01" => r#"{"files": [{"path": "/mnt/raw/2024/01/orders.parquet", "is_dir": false, "file_size": 2048}]}"#,
+                _ => "{}",
+            }
+            .to_string())
+        };
+
+        let mut listed = Vec::new();
+        let entries = walk_dbfs_tree("/mnt/raw/", None, |dir| {
+            listed.push(dir.to_string());
+            listing(dir)
+        })
+        .unwrap();
+        assert_eq!(listed, ["/mnt/raw", "/mnt/raw/2024", "/mnt/raw/2024/01"]);
+        let paths: Vec<&str> = entries.iter().map(|entry| entry.path.as_str()).collect();
+        assert_eq!(paths, ["/mnt/raw/2024", "/mnt/raw/README.md", "/mnt/raw/2024/01", "/mnt/raw/", "/mnt/raw/2024/01/orders.parquet"]);
+        assert_eq!(entries[1], DbfsFileInfo { path: "/mnt/raw/README.md".to_string(), is_dir: false, file_size: 120 });
+
+        let shallow = walk_dbfs_tree("/mnt/raw", Some(1), listing).unwrap();
+        assert_eq!(shallow.len(), 2);
+        assert!(walk_dbfs_tree("/mnt/raw", Some(0), listing).is_err());
+    }
+
+    /// A tree with more entries than the cap is refused rather than returned in part.
+    #[test]
+    fn test_walk_dbfs_tree_caps_entries() {
+        let mut next_dir = 0;
+        let err = walk_dbfs_tree("/", None, |_| {
+            next_dir += 1;
+            let files: Vec<Value> = (0..1000)
+                .map(|i| serde_json::json!({ "path": format!("/d{}/f{}", next_dir, i), "is_dir": i == 0 }))
+                .collect();
+            Ok(serde_json::json!({ "files": files }).to_string())
+        })
+        .unwrap_err();
+        assert!(err.contains("more than 10000 entries"), "{}", err);
+    }
+
+    /// A failed block still closes the handle and reports the block's error; a failed create sends nothing else.
+    #[test]
+    fn test_stream_dbfs_upload_closes_handle_on_error() {
+        let content = vec![0u8; DBFS_BLOCK_BYTES * 3];
+        let mut endpoints = Vec::new();
+        let err = stream_dbfs_upload("/tmp/big.bin", &content, false, |endpoint, _| {
+            endpoints.push(endpoint.to_string());
+            match endpoints.len() {
+                1 => Ok(r#"{"handle": 3}"#.to_string()),
+                3 => Err("[UPSTREAM] API Error: HTTP 500".to_string()),
+                _ => Ok("{}".to_string()),
+            }
+        })
+        .unwrap_err();
+        assert_eq!(err, "[UPSTREAM] API Error: HTTP 500");
+        assert_eq!(endpoints, ["dbfs/create", "dbfs/add-block", "dbfs/add-block", "dbfs/close"]);
+
+        let mut calls = 0;
+        let err = stream_dbfs_upload("/tmp/big.bin", &content, false, |_, _| {
+            calls += 1;
+            Err("[CONFLICT] API Error: HTTP 409 - RESOURCE_ALREADY_EXISTS".to_string())
+        })
+        .unwrap_err();
+        assert!(err.contains("RESOURCE_ALREADY_EXISTS"));
+        assert_eq!(calls, 1);
+    }
+}
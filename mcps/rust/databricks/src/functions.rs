@@ -49,6 +49,7 @@ pub struct ListFunctionsResponse {
 pub struct FunctionsClient {
     workspace_url: String,
     pat_token: String,
+    user_agent: String,
 }
 
 impl FunctionsClient {
@@ -56,14 +57,22 @@ impl FunctionsClient {
         Self {
             workspace_url: workspace_url.to_string(),
             pat_token: pat_token.to_string(),
+            user_agent: crate::DEFAULT_USER_AGENT.to_string(),
         }
     }
 
+    /// Override the User-Agent sent with every request
+    pub fn with_user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = user_agent;
+        self
+    }
+
     /// Get headers with authentication
     fn get_headers(&self) -> HashMap<String, String> {
         let mut headers = HashMap::new();
         headers.insert("Authorization".to_string(), format!("Bearer {}", self.pat_token));
         headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert("User-Agent".to_string(), self.user_agent.clone());
         headers
     }
 
@@ -79,7 +88,7 @@ impl FunctionsClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -98,7 +107,7 @@ impl FunctionsClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -119,7 +128,7 @@ impl FunctionsClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -138,7 +147,7 @@ impl FunctionsClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
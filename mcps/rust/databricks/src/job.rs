@@ -1,47 +1,178 @@
+use mcp_http::ids::{json_id, parse_id};
+use serde::{Deserialize, Serialize};
 use serde_json;
+use serde_json::Value;
 use std::collections::HashMap;
+use weil_macros::WeilType;
+use weil_rs::errors::ErrorCode;
 use weil_rs::http::{HttpClient, HttpMethod};
 
+/// Status checks `run_job_and_wait` makes when not given `max_checks`
+pub const DEFAULT_MAX_STATUS_CHECKS: u32 = 60;
+/// Most status checks one `run_job_and_wait` call may make. The checks are sent back to back, so
+/// a larger budget would only hammer the workspace; a longer run is followed with `get_job_run`.
+pub const MAX_STATUS_CHECKS: u32 = 300;
+/// Life cycle states after which a run will not change again
+const TERMINAL_LIFE_CYCLE_STATES: [&str; 3] = ["TERMINATED", "SKIPPED", "INTERNAL_ERROR"];
+
+/// Outcome of a job run that reached a terminal state
+#[derive(Debug, Serialize, Deserialize, WeilType, PartialEq)]
+pub struct JobRunResult {
+    pub run_id: u64,
+    /// `SUCCESS`, `FAILED`, `CANCELED`, ...; absent when the run was skipped before it started
+    pub result_state: Option<String>,
+    pub state_message: String,
+    pub run_page_url: String,
+}
+
+/// Read the `run_id` from a `jobs/run-now` response
+pub fn parse_run_id(response: &str) -> Result<u64, String> {
+    let value: Value = serde_json::from_str(response).map_err(|e| format!("Failed to parse run-now response: {}", e))?;
+    json_id(&value["run_id"]).map_err(|_| format!("run-now response has no run_id: {}", response))
+}
+
+/// Turn a `jobs/runs/get` response into a result, or `None` while the run is still going
+pub fn parse_terminal_run(response: &str) -> Result<Option<JobRunResult>, String> {
+    let value: Value = serde_json::from_str(response).map_err(|e| format!("Failed to parse run: {}", e))?;
+    let state = &value["state"];
+    let life_cycle_state = state["life_cycle_state"].as_str().unwrap_or_default();
+    if !TERMINAL_LIFE_CYCLE_STATES.contains(&life_cycle_state) {
+        return Ok(None);
+    }
+    Ok(Some(JobRunResult {
+        run_id: json_id(&value["run_id"]).map_err(|_| format!("run has no run_id: {}", response))?,
+        result_state: state["result_state"].as_str().map(str::to_string),
+        state_message: state["state_message"].as_str().unwrap_or_default().to_string(),
+        run_page_url: value["run_page_url"].as_str().unwrap_or_default().to_string(),
+    }))
+}
+
+/// Fetch the run up to `max_checks` times until it is terminal, failing with an `ErrorCode::Timeout`
+/// error otherwise so a run that is still going can be told apart from a failed one. The runtime has
+/// no timer, so the checks are sent back to back and the error reports how many were made.
+pub fn wait_for_run<F: FnMut() -> Result<String, String>>(run_id: u64, max_checks: u32, mut fetch: F) -> Result<JobRunResult, String> {
+    let max_checks = status_check_budget(Some(max_checks))?;
+    for _ in 0..max_checks {
+        if let Some(result) = parse_terminal_run(&fetch()?)? {
+            return Ok(result);
+        }
+    }
+    Err(ErrorCode::Timeout.tag(format!("run {} was still going after {} status checks", run_id, max_checks)))
+}
+
+/// Status checks to make for the requested `max_checks`: `DEFAULT_MAX_STATUS_CHECKS` when unset, and
+/// refused unless it is between 1 and `MAX_STATUS_CHECKS`.
+pub fn status_check_budget(max_checks: Option<u32>) -> Result<u32, String> {
+    match max_checks.unwrap_or(DEFAULT_MAX_STATUS_CHECKS) {
+        checks @ 1..=MAX_STATUS_CHECKS => Ok(checks),
+        checks => Err(ErrorCode::Validation.tag(format!("max_checks must be between 1 and {}, got {}", MAX_STATUS_CHECKS, checks))),
+    }
+}
+
+/// `jobs/update` body setting the schedule's `pause_status` on the job described by `job` (a
+/// `jobs/get` response). `new_settings.schedule` replaces the whole schedule, so every other
+/// schedule field is copied over unchanged.
+pub fn build_schedule_update(job: &Value, pause_status: &str) -> Result<Value, String> {
+    let job_id = json_id(&job["job_id"]).map_err(|_| "job response has no job_id")?;
+    let mut schedule = match job["settings"].get("schedule") {
+        Some(schedule @ Value::Object(_)) => schedule.clone(),
+        _ => return Err(format!("job {} has no schedule to pause or resume", job_id)),
+    };
+    schedule["pause_status"] = pause_status.into();
+    Ok(serde_json::json!({
+        "job_id": job_id,
+        "new_settings": { "schedule": schedule }
+    }))
+}
+
+/// Most runs `jobs/runs/list` returns per page
+const RUNS_LIST_PAGE_SIZE: u32 = 25;
+/// Runs `list_job_runs` returns when not given a limit
+pub const DEFAULT_JOB_RUNS_LIMIT: u32 = 25;
+
+/// Gather up to `limit` runs of a job, newest first, from `jobs/runs/list` pages fetched with
+/// `fetch`, which is given each page's query. `active_only` keeps only pending and running runs.
+pub fn collect_job_runs<F>(job_id: &str, limit: Option<u32>, active_only: bool, mut fetch: F) -> Result<String, String>
+where
+    F: FnMut(Vec<(String, String)>) -> Result<String, String>,
+{
+    let job_id = parse_id(job_id).map_err(|_| format!("job_id must be a number, got {:?}", job_id))?;
+    let limit = limit.unwrap_or(DEFAULT_JOB_RUNS_LIMIT);
+
+    crate::collect_token_pages("runs", Some(limit), |page_token, page_size| {
+        let mut query = vec![
+            ("job_id".to_string(), job_id.to_string()),
+            ("active_only".to_string(), active_only.to_string()),
+            ("limit".to_string(), page_size.min(RUNS_LIST_PAGE_SIZE).to_string()),
+        ];
+        if let Some(page_token) = page_token {
+            query.push(("page_token".to_string(), page_token.to_string()));
+        }
+        fetch(query)
+    })
+}
+
 /// Job management functions for Databricks
 pub struct JobClient {
     base_url: String,
+    /// Jobs API 2.1 root, which reports multi-task runs in full
+    base_url_v21: String,
     token: String,
+    user_agent: String,
 }
 
 impl JobClient {
     /// Create a new JobClient instance
     pub fn new(workspace_url: &str, personal_access_token: &str) -> Self {
         let base_url = format!("{}/api/2.0", workspace_url.trim_end_matches('/'));
+        let base_url_v21 = format!("{}/api/2.1", workspace_url.trim_end_matches('/'));
         
         Self {
             base_url,
+            base_url_v21,
             token: personal_access_token.to_string(),
+            user_agent: crate::DEFAULT_USER_AGENT.to_string(),
         }
     }
 
+    /// Override the User-Agent sent with every request
+    pub fn with_user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = user_agent;
+        self
+    }
+
     /// Get headers with authentication
     fn get_headers(&self) -> HashMap<String, String> {
         let mut headers = HashMap::new();
         headers.insert("Authorization".to_string(), format!("Bearer {}", self.token));
         headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert("User-Agent".to_string(), self.user_agent.clone());
         headers
     }
 
-    /// List all jobs
-    pub async fn list_jobs(&self) -> Result<String, String> {
-        let url = format!("{}/jobs/list", self.base_url);
-        
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(self.get_headers())
-            .send()
-            .map_err(|e| format!("Request failed: {}", e))?;
+    /// List jobs through the 2.1 API, following `has_more`/`next_page_token` up to `max_results` jobs
+    pub async fn list_jobs(&self, max_results: Option<u32>) -> Result<String, String> {
+        let url = format!("{}/jobs/list", self.base_url_v21);
 
-        if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
-        }
+        crate::collect_token_pages("jobs", max_results, |page_token, page_size| {
+            let mut query = vec![("limit".to_string(), page_size.to_string())];
+            if let Some(page_token) = page_token {
+                query.push(("page_token".to_string(), page_token.to_string()));
+            }
 
-        let response_text = response.text();
-        Ok(response_text)
+            let response = HttpClient::request(&url, HttpMethod::Get)
+                .headers(self.get_headers())
+                .query(query)
+                .send()
+                .map_err(|e| format!("Request failed: {}", e))?;
+
+            if response.status() < 200 || response.status() >= 300 {
+                return Err(crate::api_error(response.status(), None));
+            }
+
+            let response_text = response.text();
+            Ok(response_text)
+        })
     }
 
     /// Get job details
@@ -54,7 +185,7 @@ impl JobClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -76,13 +207,32 @@ impl JobClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
         Ok(response_text)
     }
 
+    /// Run a job now and check its status until it reaches a terminal state or `max_checks` runs out
+    pub async fn run_job_and_wait(&self, job_id: String, max_checks: Option<u32>) -> Result<JobRunResult, String> {
+        let max_checks = status_check_budget(max_checks)?;
+        let run_id = parse_run_id(&self.run_job_now(job_id).await?)?;
+        let url = format!("{}/jobs/runs/get?run_id={}", self.base_url_v21, run_id);
+
+        wait_for_run(run_id, max_checks, || {
+            let response = HttpClient::request(&url, HttpMethod::Get)
+                .headers(self.get_headers())
+                .send()
+                .map_err(|e| format!("Request failed: {}", e))?;
+
+            if response.status() < 200 || response.status() >= 300 {
+                return Err(crate::api_error(response.status(), Some(response.text())));
+            }
+            Ok(response.text())
+        })
+    }
+
     /// Get job run details
     pub async fn get_job_run(&self, run_id: String) -> Result<String, String> {
         let url = format!("{}/jobs/runs/get?run_id={}", self.base_url, run_id);
@@ -93,13 +243,70 @@ impl JobClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
         Ok(response_text)
     }
 
+    /// List a job's most recent runs through the 2.1 API, following `next_page_token` up to `limit` runs
+    pub async fn list_job_runs(&self, job_id: String, limit: Option<u32>, active_only: Option<bool>) -> Result<String, String> {
+        let url = format!("{}/jobs/runs/list", self.base_url_v21);
+
+        collect_job_runs(&job_id, limit, active_only.unwrap_or(false), |query| {
+            let response = HttpClient::request(&url, HttpMethod::Get)
+                .headers(self.get_headers())
+                .query(query)
+                .send()
+                .map_err(|e| format!("Request failed: {}", e))?;
+
+            if response.status() < 200 || response.status() >= 300 {
+                return Err(crate::api_error(response.status(), Some(response.text())));
+            }
+            Ok(response.text())
+        })
+    }
+
+    /// Pause a job's schedule; runs already going are not affected
+    pub async fn pause_job(&self, job_id: String) -> Result<String, String> {
+        self.set_schedule_pause_status(job_id, "PAUSED")
+    }
+
+    /// Resume a job's paused schedule
+    pub async fn resume_job(&self, job_id: String) -> Result<String, String> {
+        self.set_schedule_pause_status(job_id, "UNPAUSED")
+    }
+
+    /// Read the job's schedule and write it back with `pause_status`, returning the new schedule
+    fn set_schedule_pause_status(&self, job_id: String, pause_status: &str) -> Result<String, String> {
+        let url = format!("{}/jobs/get?job_id={}", self.base_url_v21, job_id);
+        let response = HttpClient::request(&url, HttpMethod::Get)
+            .headers(self.get_headers())
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if response.status() < 200 || response.status() >= 300 {
+            return Err(crate::api_error(response.status(), Some(response.text())));
+        }
+
+        let job: Value = serde_json::from_str(&response.text()).map_err(|e| format!("Failed to parse job: {}", e))?;
+        let request = build_schedule_update(&job, pause_status)?;
+
+        let url = format!("{}/jobs/update", self.base_url_v21);
+        let response = HttpClient::request(&url, HttpMethod::Post)
+            .headers(self.get_headers())
+            .json(&request)
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if response.status() < 200 || response.status() >= 300 {
+            return Err(crate::api_error(response.status(), Some(response.text())));
+        }
+
+        Ok(request["new_settings"]["schedule"].to_string())
+    }
+
     /// Cancel a job run
     pub async fn cancel_job_run(&self, run_id: String) -> Result<String, String> {
         let url = format!("{}/jobs/runs/cancel", self.base_url);
@@ -115,10 +322,113 @@ impl JobClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
         Ok(response_text)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(life_cycle_state: &str, result_state: Option<&str>) -> String {
+        let mut state = serde_json::json!({ "life_cycle_state": life_cycle_state, "state_message": "" });
+        if let Some(result_state) = result_state {
+            state["result_state"] = result_state.into();
+        }
+        serde_json::json!({ "run_id": 42, "run_page_url": "https://adb-1.azuredatabricks.net/#job/7/run/42", "state": state }).to_string()
+    }
+
+    /// Pausing and resuming rewrite only pause_status, and jobs without a schedule are refused.
+    #[test]
+    fn test_build_schedule_update() {
+        let job = serde_json::json!({
+            "job_id": 7,
+            "settings": {
+                "name": "nightly",
+                "schedule": { "quartz_cron_expression": "0 0 2 * * ?", "timezone_id": "Europe/Berlin", "pause_status": "UNPAUSED" },
+                "max_concurrent_runs": 1
+            }
+        });
+        let paused = build_schedule_update(&job, "PAUSED").unwrap();
+        assert_eq!(paused, serde_json::json!({
+            "job_id": 7,
+            "new_settings": {
+                "schedule": { "quartz_cron_expression": "0 0 2 * * ?", "timezone_id": "Europe/Berlin", "pause_status": "PAUSED" }
+            }
+        }));
+        let resumed = build_schedule_update(&job, "UNPAUSED").unwrap();
+        assert_eq!(resumed["new_settings"]["schedule"], job["settings"]["schedule"]);
+
+        let unscheduled = serde_json::json!({ "job_id": 8, "settings": { "name": "adhoc" } });
+        assert_eq!(build_schedule_update(&unscheduled, "PAUSED").unwrap_err(), "job 8 has no schedule to pause or resume");
+        assert!(build_schedule_update(&serde_json::json!({}), "PAUSED").is_err());
+    }
+
+    /// Polling stops at the first terminal state and reports how the run ended.
+    #[test]
+    fn test_wait_for_run() {
+        let mut responses = vec![run("PENDING", None), run("RUNNING", None), run("TERMINATED", Some("SUCCESS"))].into_iter();
+        let result = wait_for_run(42, 5, || Ok(responses.next().unwrap())).unwrap();
+        assert_eq!(result, JobRunResult {
+            run_id: 42,
+            result_state: Some("SUCCESS".to_string()),
+            state_message: String::new(),
+            run_page_url: "https://adb-1.azuredatabricks.net/#job/7/run/42".to_string(),
+        });
+        assert_eq!(responses.len(), 0);
+
+        assert_eq!(parse_terminal_run(&run("SKIPPED", None)).unwrap().unwrap().result_state, None);
+        assert!(parse_terminal_run(&run("INTERNAL_ERROR", Some("FAILED"))).unwrap().is_some());
+        assert_eq!(parse_run_id(r#"{"run_id": 42, "number_in_job": 42}"#).unwrap(), 42);
+        assert!(parse_run_id("{}").is_err());
+    }
+
+    /// Each page asks for the job's runs with the filters, capped at the endpoint's page size, and
+    /// the second page follows the first page's token.
+    #[test]
+    fn test_collect_job_runs() {
+        let mut queries = Vec::new();
+        let runs = collect_job_runs(" 7 ", Some(30), true, |query| {
+            queries.push(query);
+            Ok(match queries.len() {
+                1 => r#"{"runs": [{"run_id": 2}, {"run_id": 1}], "has_more": true, "next_page_token": "next"}"#,
+                _ => r#"{"runs": [{"run_id": 0}], "has_more": false}"#,
+            }.to_string())
+        }).unwrap();
+
+        let param = |name: &str, value: &str| (name.to_string(), value.to_string());
+        assert_eq!(queries, vec![
+            vec![param("job_id", "7"), param("active_only", "true"), param("limit", "25")],
+            vec![param("job_id", "7"), param("active_only", "true"), param("limit", "25"), param("page_token", "next")],
+        ]);
+        assert_eq!(serde_json::from_str::<Value>(&runs).unwrap(), serde_json::json!({
+            "runs": [{"run_id": 2}, {"run_id": 1}, {"run_id": 0}],
+            "has_more": false
+        }));
+
+        let mut queries = Vec::new();
+        collect_job_runs("7", Some(3), false, |query| { queries.push(query); Ok(r#"{"runs": []}"#.to_string()) }).unwrap();
+        assert_eq!(queries, vec![vec![param("job_id", "7"), param("active_only", "false"), param("limit", "3")]]);
+        assert!(collect_job_runs("nightly", None, false, |_| unreachable!()).is_err());
+    }
+
+    /// A run still going when the checks are spent yields the timeout error, not a failure.
+    #[test]
+    fn test_wait_for_run_timeout() {
+        let mut calls = 0;
+        let err = wait_for_run(42, 3, || { calls += 1; Ok(run("RUNNING", None)) }).unwrap_err();
+        assert_eq!(err, "[TIMEOUT] run 42 was still going after 3 status checks");
+        assert_eq!(calls, 3);
+
+        assert!(wait_for_run(42, 0, || unreachable!()).unwrap_err().starts_with("[VALIDATION]"));
+        assert!(wait_for_run(42, MAX_STATUS_CHECKS + 1, || unreachable!()).unwrap_err().starts_with("[VALIDATION]"));
+        assert!(wait_for_run(42, u32::MAX, || unreachable!()).unwrap_err().starts_with("[VALIDATION]"));
+        assert_eq!(status_check_budget(None), Ok(DEFAULT_MAX_STATUS_CHECKS));
+        assert_eq!(status_check_budget(Some(MAX_STATUS_CHECKS)), Ok(MAX_STATUS_CHECKS));
+        assert_eq!(wait_for_run(42, 3, || Err(crate::api_error(403, None))).unwrap_err(), "[AUTH] API Error: HTTP 403");
+    }
+}
@@ -1,35 +1,168 @@
 
 use serde::{Deserialize, Serialize};
 use weil_macros::{constructor, query, smart_contract, WeilType};
-use weil_rs::config::Secrets;
+use weil_rs::config::{require_http_url, require_non_empty, Secrets};
+use weil_rs::errors::{diagnosis_for, DiagnosisRule, ErrorCode};
+use mcp_http::tools::{check_tool_enabled, filter_tools, TOOL_DISABLED};
 
 mod auth;
 mod sql;
 mod dbfs;
 mod cluster;
+mod compute_audit;
 mod model_registry;
 mod model_serving;
 mod job;
 mod catalog;
 mod functions;
 mod pipeline;
+mod libraries;
+mod secrets_api;
+mod workspace;
+mod repos;
 
 use auth::AuthClient;
-use sql::SqlClient;
-use dbfs::DbfsClient;
-use cluster::ClusterClient;
+use sql::{AlertOptions, QueryHistoryEntry, SqlClient, SqlResult, TableStats};
+use dbfs::{DbfsClient, DbfsFileInfo};
+use cluster::{ClusterClient, ClusterInfo};
+use compute_audit::{AutostopThresholds, ComputeAuditFinding};
 use model_registry::ModelRegistryClient;
 use model_serving::ModelServingClient;
-use job::JobClient;
+use job::{JobClient, JobRunResult};
 use catalog::CatalogClient;
 use functions::FunctionsClient;
 use pipeline::PipelineClient;
+use libraries::LibrariesClient;
+use secrets_api::{SecretMetadata, SecretScope, SecretsClient};
+use workspace::WorkspaceClient;
+use repos::{RepoInfo, ReposClient};
+
+/// User-Agent sent with every request unless overridden in the config
+pub const DEFAULT_USER_AGENT: &str = concat!("wadk-mcp/", env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Error for a non-2xx Databricks response, tagged `[CODE]` by status so agents can branch on it
+pub fn api_error(status: u16, detail: Option<String>) -> String {
+    let message = match detail {
+        Some(detail) => format!("API Error: HTTP {} - {}", status, detail),
+        None => format!("API Error: HTTP {}", status),
+    };
+    ErrorCode::from_http_status(status).tag(message)
+}
+
+/// Largest page requested from a token-paginated list endpoint
+pub const LIST_PAGE_SIZE: u32 = 100;
+
+/// Follow `next_page_token` through a paginated list, gathering each page's `items_key` array
+/// into one `{items_key: [...], "has_more": bool}` response. `fetch` is given the page token
+/// (none for the first page) and the page size to ask for. Listing stops when the API reports
+/// no more pages (no token, or `has_more: false`) or once `max_results` items are gathered, in
+/// which case `has_more` tells whether anything was left out.
+pub fn collect_token_pages<F>(items_key: &str, max_results: Option<u32>, mut fetch: F) -> Result<String, String>
+where
+    F: FnMut(Option<&str>, u32) -> Result<String, String>,
+{
+    if max_results == Some(0) {
+        return Err("max_results must be greater than 0".to_string());
+    }
+    let max_results = max_results.map(|max| max as usize);
+    let mut items: Vec<serde_json::Value> = Vec::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let remaining = max_results.map_or(LIST_PAGE_SIZE as usize, |max| max.saturating_sub(items.len()));
+        let page_size = remaining.min(LIST_PAGE_SIZE as usize) as u32;
+        let page: serde_json::Value = serde_json::from_str(&fetch(page_token.as_deref(), page_size)?)
+            .map_err(|e| format!("Failed to parse list response: {}", e))?;
+        if let Some(page_items) = page[items_key].as_array() {
+            items.extend(page_items.iter().cloned());
+        }
 
+        page_token = page["next_page_token"].as_str().filter(|token| !token.is_empty()).map(str::to_string);
+        let more = page_token.is_some() && page["has_more"].as_bool() != Some(false);
+        let capped = max_results.is_some_and(|max| items.len() >= max);
+        if !more || capped {
+            if let Some(max) = max_results {
+                items.truncate(max);
+            }
+            return Ok(serde_json::json!({ items_key: items, "has_more": more && capped }).to_string());
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, WeilType, Default)]
 pub struct DatabricksConfig {
     pat_token: String,
     workspace_url: String,
+    /// Account console URL (e.g. `https://accounts.cloud.databricks.com`), only needed for
+    /// account-level APIs such as account SCIM and budgets.
+    account_url: Option<String>,
+    /// Account ID used in account-level API paths.
+    account_id: Option<String>,
+    /// User-Agent to send instead of `DEFAULT_USER_AGENT`.
+    user_agent: Option<String>,
+    /// Longest cluster auto-termination `audit_compute_autostop` accepts, in minutes.
+    max_cluster_autotermination_mins: Option<u32>,
+    /// Longest SQL warehouse auto-stop `audit_compute_autostop` accepts, in minutes.
+    max_warehouse_auto_stop_mins: Option<u32>,
+    /// Tools that refuse to run and are left out of `tools()`, e.g. `["delete_repo"]`.
+    #[serde(default)]
+    disabled_tools: Vec<String>,
+}
+
+/// Which Databricks host an API lives on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ApiScope {
+    /// Served by the workspace deployment, e.g. `https://<workspace>.cloud.databricks.com`.
+    Workspace,
+    /// Served by the account console and scoped to `/accounts/{account_id}`.
+    Account,
+}
+
+impl DatabricksConfig {
+    /// Check the token and workspace URL are set, and that an account URL, if given, is a URL.
+    fn validate(&self) -> Result<(), String> {
+        require_non_empty("pat_token", &self.pat_token)?;
+        require_http_url("workspace_url", &self.workspace_url)?;
+        match self.account_url.as_deref() {
+            Some(url) if !url.trim().is_empty() => require_http_url("account_url", url),
+            _ => Ok(()),
+        }
+    }
+
+    /// Resolve the host URL an API of the given scope must be sent to.
+    fn base_url_for(&self, scope: ApiScope) -> Result<String, String> {
+        match scope {
+            ApiScope::Workspace => Ok(self.workspace_url.clone()),
+            ApiScope::Account => match self.account_url.as_deref().map(str::trim) {
+                Some(url) if !url.is_empty() => Ok(url.to_string()),
+                _ => Err("account_url is not set in the Databricks config; it is required for account-level APIs".to_string()),
+            },
+        }
+    }
+
+    /// Account ID for account-level APIs, erroring if it has not been configured.
+    fn account_id(&self) -> Result<&str, String> {
+        match self.account_id.as_deref().map(str::trim) {
+            Some(id) if !id.is_empty() => Ok(id),
+            _ => Err("account_id is not set in the Databricks config; it is required for account-level APIs".to_string()),
+        }
+    }
+
+    /// User-Agent for outbound requests: the configured override, or `DEFAULT_USER_AGENT`.
+    fn user_agent(&self) -> String {
+        match self.user_agent.as_deref().map(str::trim) {
+            Some(user_agent) if !user_agent.is_empty() => user_agent.to_string(),
+            _ => DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+
+    /// Idle shutdown limits for `audit_compute_autostop`, falling back to the defaults for unset or zero values.
+    fn autostop_thresholds(&self) -> AutostopThresholds {
+        let defaults = AutostopThresholds::default();
+        AutostopThresholds {
+            max_cluster_autotermination_mins: self.max_cluster_autotermination_mins.filter(|mins| *mins > 0).unwrap_or(defaults.max_cluster_autotermination_mins),
+            max_warehouse_auto_stop_mins: self.max_warehouse_auto_stop_mins.filter(|mins| *mins > 0).unwrap_or(defaults.max_warehouse_auto_stop_mins),
+        }
+    }
 }
 
 trait Databricks {
@@ -39,25 +172,51 @@ trait Databricks {
     async fn list_users(&self) -> Result<String, String>;
     async fn get_user(&self, user_id: String) -> Result<String, String>;
     async fn create_user(&self, username: String, email: String, display_name: Option<String>) -> Result<String, String>;
+    async fn list_account_users(&self) -> Result<String, String>;
     async fn execute_sql(&self, query_str: String, warehouse_id: String) -> Result<String, String>;
+    async fn execute_sql_all(&self, query_str: String, warehouse_id: String) -> Result<SqlResult, String>;
+    async fn normalize_sql(&self, query_str: String) -> String;
+    async fn get_usage(&self, start_date: String, end_date: String, group_by: Option<String>, warehouse_id: String) -> Result<String, String>;
+    async fn get_table_stats(&self, full_table_name: String, warehouse_id: String) -> Result<TableStats, String>;
     async fn list_sql_warehouses(&self, warehouse_id: String) -> Result<String, String>;
     async fn start_sql_warehouse(&self, warehouse_id: String) -> Result<String, String>;
     async fn stop_sql_warehouse(&self, warehouse_id: String) -> Result<String, String>;
     async fn create_sql_warehouse(&self, name: String, cluster_size: String, min_num_clusters: i32, max_num_clusters: i32, auto_stop_mins: i32) -> Result<String, String>;
     async fn list_dbfs_files(&self, path: String) -> Result<String, String>;
+    async fn list_dbfs_files_recursive(&self, path: String, max_depth: Option<u32>) -> Result<Vec<DbfsFileInfo>, String>;
     async fn get_dbfs_file_info(&self, path: String) -> Result<String, String>;
     async fn delete_dbfs_file(&self, path: String) -> Result<String, String>;
     async fn move_dbfs_file(&self, source_path: String, destination_path: String) -> Result<String, String>;
     async fn copy_dbfs_file(&self, source_path: String, destination_path: String) -> Result<String, String>;
     async fn write_dbfs_file(&self, path: String, content: String, overwrite: bool) -> Result<String, String>;
+    async fn write_dbfs_file_streaming(&self, path: String, file_descriptor: String, overwrite: bool) -> Result<u64, String>;
     async fn read_dbfs_file(&self, path: String, offset: Option<i64>, length: Option<i64>) -> Result<String, String>;
-    async fn list_clusters(&self) -> Result<String, String>;
-    async fn get_cluster(&self, cluster_id: String) -> Result<String, String>;
+    async fn list_clusters(&self) -> Result<Vec<ClusterInfo>, String>;
+    async fn list_clusters_raw(&self) -> Result<String, String>;
+    async fn get_cluster(&self, cluster_id: String) -> Result<ClusterInfo, String>;
+    async fn get_cluster_raw(&self, cluster_id: String) -> Result<String, String>;
     async fn create_cluster(&self, name: String, spark_version: String, node_type: String, num_workers: i32) -> Result<String, String>;
+    async fn audit_compute_autostop(&self) -> Result<Vec<ComputeAuditFinding>, String>;
+    async fn install_library(&self, cluster_id: String, library_json: String) -> Result<String, String>;
+    async fn uninstall_library(&self, cluster_id: String, library_json: String) -> Result<String, String>;
+    async fn list_libraries(&self, cluster_id: String) -> Result<String, String>;
+    async fn create_secret_scope(&self, name: String) -> Result<String, String>;
+    async fn list_secret_scopes(&self) -> Result<Vec<SecretScope>, String>;
+    async fn put_secret(&self, scope: String, key: String, value: String) -> Result<String, String>;
+    async fn list_secrets(&self, scope: String) -> Result<Vec<SecretMetadata>, String>;
+    async fn delete_secret(&self, scope: String, key: String) -> Result<String, String>;
     async fn list_sql_queries(&self, user_id: String, include_metrics: Option<bool>) -> Result<String, String>;
+    async fn list_query_history(&self, warehouse_id: Option<String>, user_id: Option<String>, max_results: Option<u32>) -> Result<Vec<QueryHistoryEntry>, String>;
     async fn create_directory(&self, path: String) -> Result<String, String>;
     async fn list_workspace_directory(&self, path: String) -> Result<String, String>;
-    async fn list_registered_models(&self) -> Result<String, String>;
+    async fn export_notebook(&self, path: String, format: String) -> Result<String, String>;
+    async fn import_notebook(&self, path: String, format: String, language: String, content_b64: String, overwrite: bool) -> Result<String, String>;
+    async fn list_repos(&self) -> Result<Vec<RepoInfo>, String>;
+    async fn create_repo(&self, url: String, provider: String, path: Option<String>) -> Result<RepoInfo, String>;
+    async fn get_repo(&self, repo_id: String) -> Result<RepoInfo, String>;
+    async fn update_repo(&self, repo_id: String, branch: Option<String>, tag: Option<String>) -> Result<RepoInfo, String>;
+    async fn delete_repo(&self, repo_id: String) -> Result<String, String>;
+    async fn list_registered_models(&self, max_results: Option<u32>) -> Result<String, String>;
     async fn get_registered_model(&self, name: String) -> Result<String, String>;
     async fn create_registered_model(&self, name: String, description: Option<String>) -> Result<String, String>;
     async fn list_model_versions(&self, name: String) -> Result<String, String>;
@@ -70,19 +229,27 @@ trait Databricks {
     async fn update_serving_endpoint(&self, name: String, configuration: String) -> Result<String, String>;
     async fn delete_serving_endpoint(&self, name: String) -> Result<String, String>;
     async fn get_serving_endpoint_logs(&self, name: String, lines: Option<i32>) -> Result<String, String>;
-    async fn query_serving_endpoint(&self, name: String, data: String) -> Result<String, String>;
-    async fn list_jobs(&self) -> Result<String, String>;
+    async fn query_serving_endpoint(&self, name: String, data: String, validate_input: Option<bool>) -> Result<String, String>;
+    async fn set_endpoint_rate_limit(&self, name: String, calls: u32, per_seconds: u32, principal: Option<String>) -> Result<String, String>;
+    async fn list_jobs(&self, max_results: Option<u32>) -> Result<String, String>;
     async fn get_job(&self, job_id: String) -> Result<String, String>;
     async fn run_job_now(&self, job_id: String) -> Result<String, String>;
+    async fn run_job_and_wait(&self, job_id: String, max_checks: Option<u32>) -> Result<JobRunResult, String>;
     async fn get_job_run(&self, run_id: String) -> Result<String, String>;
+    async fn list_job_runs(&self, job_id: String, limit: Option<u32>, active_only: Option<bool>) -> Result<String, String>;
     async fn cancel_job_run(&self, run_id: String) -> Result<String, String>;
-    async fn create_sql_alert(&self, name: String, query_id: String, column: String, op: String, threshold: String, rearm: i32) -> Result<String, String>;
+    async fn pause_job(&self, job_id: String) -> Result<String, String>;
+    async fn resume_job(&self, job_id: String) -> Result<String, String>;
+    async fn create_sql_alert(&self, name: String, query_id: String, options: AlertOptions, notification_ids: Option<Vec<String>>) -> Result<String, String>;
+    async fn list_notification_destinations(&self) -> Result<String, String>;
     async fn list_catalogs(&self) -> Result<String, String>;
     async fn get_catalog(&self, catalog_name: String) -> Result<String, String>;
     async fn list_schemas(&self, catalog_name: String) -> Result<String, String>;
     async fn get_schema(&self, catalog_name: String, schema_name: String) -> Result<String, String>;
     async fn list_tables(&self, catalog_name: String, schema_name: String) -> Result<String, String>;
     async fn get_table(&self, catalog_name: String, schema_name: String, table_name: String) -> Result<String, String>;
+    async fn get_grants(&self, securable_type: String, full_name: String) -> Result<String, String>;
+    async fn update_grants(&self, securable_type: String, full_name: String, changes: String) -> Result<String, String>;
     async fn list_metastores(&self) -> Result<String, String>;
     async fn list_functions(&self, catalog_name: String, schema_name: String) -> Result<String, String>;
     async fn get_function(&self, function_name: String) -> Result<String, String>;
@@ -98,6 +265,7 @@ trait Databricks {
     
     fn tools(&self) -> String;
     fn prompts(&self) -> String;
+    fn diagnose(&self, error_message: String) -> String;
 }
 
 #[derive(Serialize, Deserialize, WeilType)]
@@ -106,7 +274,82 @@ pub struct DatabricksContractState {
     secrets: Secrets<DatabricksConfig>,
 }
 
-#[smart_contract]
+impl DatabricksContractState {
+    /// The configured settings, refused before any request is made if they are incomplete.
+    fn config(&self) -> Result<DatabricksConfig, String> {
+        let config = self.secrets.config();
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Refuse to run a tool listed in `disabled_tools` in the config. This is the contract's
+    /// `tool_gate`, so every exported method goes through it before doing anything else.
+    fn ensure_tool_enabled(&self, tool: &str) -> Result<(), String> {
+        check_tool_enabled(&self.secrets.config().disabled_tools, tool)
+    }
+}
+
+/// Remediation hints `diagnose` picks from, first match wins: Databricks error codes and
+/// messages first, then a fallback per `ErrorCode`
+const DIAGNOSIS_RULES: &[DiagnosisRule] = &[
+    DiagnosisRule {
+        code: None,
+        patterns: &["config field", "account_url is not set"],
+        guidance: "Set workspace_url and pat_token in the MCP secrets, plus account_url and account_id for account-level tools such as billing and account users.",
+    },
+    DiagnosisRule {
+        code: None,
+        patterns: &[TOOL_DISABLED],
+        guidance: "This tool is listed in disabled_tools for this deployment. Use another tool, or ask the administrator to enable it.",
+    },
+    DiagnosisRule {
+        code: None,
+        patterns: &["INVALID_STATE"],
+        guidance: "The resource isn't in a state that allows this yet; start the cluster or SQL warehouse first, or wait for the running operation to finish, then retry.",
+    },
+    DiagnosisRule {
+        code: None,
+        patterns: &["RESOURCE_ALREADY_EXISTS"],
+        guidance: "Something with that name or path already exists; pick another name, or update or delete the existing one.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::Auth),
+        patterns: &["HTTP 401", "HTTP 403", "PERMISSION_DENIED", "Invalid access token"],
+        guidance: "Check pat_token: the personal access token may be wrong, expired or revoked, so generate a new one under User Settings > Developer and update the MCP secrets. A 403 means the token's user lacks permission on the resource.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::NotFound),
+        patterns: &["HTTP 404", "RESOURCE_DOES_NOT_EXIST"],
+        guidance: "Verify the id or path exists in this workspace, e.g. with list_clusters, list_jobs or list_workspace_directory, and that workspace_url points at the right workspace.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::RateLimited),
+        patterns: &["HTTP 429", "REQUEST_LIMIT_EXCEEDED"],
+        guidance: "Reduce the call rate: wait before retrying, poll run and statement status less often, and ask for larger pages instead of many small calls.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::Timeout),
+        patterns: &[],
+        guidance: "The operation is still running or Databricks is slow; check its status later, e.g. with get_job_run, rather than starting it again.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::Conflict),
+        patterns: &[],
+        guidance: "The request clashes with the resource's current state; fetch it again and retry against what is there now.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::Validation),
+        patterns: &["INVALID_PARAMETER_VALUE", "MALFORMED_REQUEST"],
+        guidance: "Databricks rejected an argument; the message names the field, so check it against the tool's parameters.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::Upstream),
+        patterns: &["TEMPORARILY_UNAVAILABLE"],
+        guidance: "Databricks failed on its side; retry after a short wait, and check the workspace's status page if it keeps failing.",
+    },
+];
+
+#[smart_contract(tool_gate = ensure_tool_enabled)]
 impl Databricks for DatabricksContractState {
     #[constructor]
     fn new() -> Result<Self, String>
@@ -121,218 +364,406 @@ impl Databricks for DatabricksContractState {
 
     #[query]
     async fn list_users(&self) -> Result<String, String> {
-        let config = self.secrets.config();
-        let auth_client = AuthClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let workspace_url = config.base_url_for(ApiScope::Workspace)?;
+        let auth_client = AuthClient::new(&workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         auth_client.list_users().await
     }
 
     #[query]
     async fn get_user(&self, user_id: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let auth_client = AuthClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let workspace_url = config.base_url_for(ApiScope::Workspace)?;
+        let auth_client = AuthClient::new(&workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         auth_client.get_user(user_id).await
     }
 
     #[query]
     async fn create_user(&self, username: String, email: String, display_name: Option<String>) -> Result<String, String> {
-        let config = self.secrets.config();
-        let auth_client = AuthClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let workspace_url = config.base_url_for(ApiScope::Workspace)?;
+        let auth_client = AuthClient::new(&workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         auth_client.create_user(username, email, display_name).await
     }
 
+    #[query]
+    async fn list_account_users(&self) -> Result<String, String> {
+        let config = self.config()?;
+        let account_url = config.base_url_for(ApiScope::Account)?;
+        let auth_client = AuthClient::for_account(&account_url, config.account_id()?, &config.pat_token).with_user_agent(config.user_agent());
+        auth_client.list_account_users().await
+    }
+
     #[query]
     async fn execute_sql(&self, query_str: String, warehouse_id: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         sql_client.execute_sql(query_str, warehouse_id).await
     }
 
+    #[query]
+    async fn execute_sql_all(&self, query_str: String, warehouse_id: String) -> Result<SqlResult, String> {
+        let config = self.config()?;
+        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        sql_client.execute_sql_all(query_str, warehouse_id).await
+    }
+
+    #[query]
+    async fn normalize_sql(&self, query_str: String) -> String {
+        sql::normalize_sql(&query_str)
+    }
+
+    #[query]
+    async fn get_usage(&self, start_date: String, end_date: String, group_by: Option<String>, warehouse_id: String) -> Result<String, String> {
+        let (statement, parameters) = sql::build_usage_query(&start_date, &end_date, group_by.as_deref())?;
+        let config = self.config()?;
+        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        sql_client.execute_sql_with_parameters(statement, parameters, warehouse_id).await
+    }
+
+    #[query]
+    async fn get_table_stats(&self, full_table_name: String, warehouse_id: String) -> Result<TableStats, String> {
+        let config = self.config()?;
+        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        sql_client.get_table_stats(full_table_name, warehouse_id).await
+    }
+
     #[query]
     async fn list_sql_warehouses(&self, warehouse_id: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         sql_client.list_sql_warehouses(warehouse_id).await
     }
 
     #[query]
     async fn start_sql_warehouse(&self, warehouse_id: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         sql_client.start_sql_warehouse(warehouse_id).await
     }
 
     #[query]
     async fn stop_sql_warehouse(&self, warehouse_id: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         sql_client.stop_sql_warehouse(warehouse_id).await
     }
 
     #[query]
     async fn create_sql_warehouse(&self, name: String, cluster_size: String, min_num_clusters: i32, max_num_clusters: i32, auto_stop_mins: i32) -> Result<String, String> {
-        let config = self.secrets.config();
-        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         sql_client.create_sql_warehouse(name, cluster_size, min_num_clusters, max_num_clusters, auto_stop_mins).await
     }
 
     #[query]
     async fn list_dbfs_files(&self, path: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         dbfs_client.list_dbfs_files(path).await
     }
 
+    #[query]
+    async fn list_dbfs_files_recursive(&self, path: String, max_depth: Option<u32>) -> Result<Vec<DbfsFileInfo>, String> {
+        let config = self.config()?;
+        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        dbfs_client.list_dbfs_files_recursive(path, max_depth).await
+    }
+
     #[query]
     async fn get_dbfs_file_info(&self, path: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         dbfs_client.get_dbfs_file_info(path).await
     }
 
     #[query]
     async fn delete_dbfs_file(&self, path: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         dbfs_client.delete_dbfs_file(path).await
     }
 
     #[query]
     async fn move_dbfs_file(&self, source_path: String, destination_path: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         dbfs_client.move_dbfs_file(source_path, destination_path).await
     }
 
     #[query]
     async fn copy_dbfs_file(&self, source_path: String, destination_path: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         dbfs_client.copy_dbfs_file(source_path, destination_path).await
     }
 
     #[query]
     async fn write_dbfs_file(&self, path: String, content: String, overwrite: bool) -> Result<String, String> {
-        let config = self.secrets.config();
-        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         dbfs_client.write_dbfs_file(path, content, overwrite).await
     }
 
+    #[query]
+    async fn write_dbfs_file_streaming(&self, path: String, file_descriptor: String, overwrite: bool) -> Result<u64, String> {
+        let config = self.config()?;
+        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        dbfs_client.write_dbfs_file_streaming(path, file_descriptor, overwrite).await
+    }
+
     #[query]
     async fn read_dbfs_file(&self, path: String, offset: Option<i64>, length: Option<i64>) -> Result<String, String> {
-        let config = self.secrets.config();
-        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         dbfs_client.read_dbfs_file(path, offset, length).await
     }
 
     #[query]
-    async fn list_clusters(&self) -> Result<String, String> {
-        let config = self.secrets.config();
-        let cluster_client = ClusterClient::new(&config.workspace_url, &config.pat_token);
+    async fn list_clusters(&self) -> Result<Vec<ClusterInfo>, String> {
+        let config = self.config()?;
+        let cluster_client = ClusterClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         cluster_client.list_clusters().await
     }
 
     #[query]
-    async fn get_cluster(&self, cluster_id: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let cluster_client = ClusterClient::new(&config.workspace_url, &config.pat_token);
+    async fn list_clusters_raw(&self) -> Result<String, String> {
+        let config = self.config()?;
+        let cluster_client = ClusterClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        cluster_client.list_clusters_raw().await
+    }
+
+    #[query]
+    async fn get_cluster(&self, cluster_id: String) -> Result<ClusterInfo, String> {
+        let config = self.config()?;
+        let cluster_client = ClusterClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         cluster_client.get_cluster(cluster_id).await
     }
 
+    #[query]
+    async fn get_cluster_raw(&self, cluster_id: String) -> Result<String, String> {
+        let config = self.config()?;
+        let cluster_client = ClusterClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        cluster_client.get_cluster_raw(cluster_id).await
+    }
+
     #[query]
     async fn create_cluster(&self, name: String, spark_version: String, node_type: String, num_workers: i32) -> Result<String, String> {
-        let config = self.secrets.config();
-        let cluster_client = ClusterClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let cluster_client = ClusterClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         cluster_client.create_cluster(name, spark_version, node_type, num_workers).await
     }
 
+    #[query]
+    async fn audit_compute_autostop(&self) -> Result<Vec<ComputeAuditFinding>, String> {
+        let config = self.config()?;
+        let cluster_client = ClusterClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        let clusters = cluster_client.list_clusters_raw().await?;
+        let warehouses = sql_client.list_sql_warehouses(String::new()).await?;
+        compute_audit::audit_autostop(&clusters, &warehouses, config.autostop_thresholds())
+    }
+
+    #[query]
+    async fn install_library(&self, cluster_id: String, library_json: String) -> Result<String, String> {
+        let config = self.config()?;
+        let libraries_client = LibrariesClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        libraries_client.install_library(cluster_id, library_json).await
+    }
+
+    #[query]
+    async fn uninstall_library(&self, cluster_id: String, library_json: String) -> Result<String, String> {
+        let config = self.config()?;
+        let libraries_client = LibrariesClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        libraries_client.uninstall_library(cluster_id, library_json).await
+    }
+
+    #[query]
+    async fn list_libraries(&self, cluster_id: String) -> Result<String, String> {
+        let config = self.config()?;
+        let libraries_client = LibrariesClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        libraries_client.list_libraries(cluster_id).await
+    }
+
+    #[query]
+    async fn create_secret_scope(&self, name: String) -> Result<String, String> {
+        let config = self.config()?;
+        let secrets_client = SecretsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        secrets_client.create_secret_scope(name).await
+    }
+
+    #[query]
+    async fn list_secret_scopes(&self) -> Result<Vec<SecretScope>, String> {
+        let config = self.config()?;
+        let secrets_client = SecretsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        secrets_client.list_secret_scopes().await
+    }
+
+    #[query]
+    async fn put_secret(&self, scope: String, key: String, value: String) -> Result<String, String> {
+        let config = self.config()?;
+        let secrets_client = SecretsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        secrets_client.put_secret(scope, key, value).await
+    }
+
+    #[query]
+    async fn list_secrets(&self, scope: String) -> Result<Vec<SecretMetadata>, String> {
+        let config = self.config()?;
+        let secrets_client = SecretsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        secrets_client.list_secrets(scope).await
+    }
+
+    #[query]
+    async fn delete_secret(&self, scope: String, key: String) -> Result<String, String> {
+        let config = self.config()?;
+        let secrets_client = SecretsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        secrets_client.delete_secret(scope, key).await
+    }
+
     #[query]
     async fn list_sql_queries(&self, user_id: String, include_metrics: Option<bool>) -> Result<String, String> {
-        let config = self.secrets.config();
-        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         sql_client.list_sql_queries(user_id, include_metrics).await
     }
 
+    #[query]
+    async fn list_query_history(&self, warehouse_id: Option<String>, user_id: Option<String>, max_results: Option<u32>) -> Result<Vec<QueryHistoryEntry>, String> {
+        let config = self.config()?;
+        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        sql_client.list_query_history(warehouse_id, user_id, max_results).await
+    }
+
     #[query]
     async fn create_directory(&self, path: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         dbfs_client.create_directory(path).await
     }
 
     #[query]
     async fn list_workspace_directory(&self, path: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let dbfs_client = DbfsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         dbfs_client.list_workspace_directory(path).await
     }
 
     #[query]
-    async fn list_registered_models(&self) -> Result<String, String> {
-        let config = self.secrets.config();
-        let model_registry_client = ModelRegistryClient::new(&config.workspace_url, &config.pat_token);
-        model_registry_client.list_registered_models().await
+    async fn export_notebook(&self, path: String, format: String) -> Result<String, String> {
+        let config = self.config()?;
+        let workspace_client = WorkspaceClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        workspace_client.export_notebook(path, format).await
+    }
+
+    #[query]
+    async fn import_notebook(&self, path: String, format: String, language: String, content_b64: String, overwrite: bool) -> Result<String, String> {
+        let config = self.config()?;
+        let workspace_client = WorkspaceClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        workspace_client.import_notebook(path, format, language, content_b64, overwrite).await
+    }
+
+    #[query]
+    async fn list_repos(&self) -> Result<Vec<RepoInfo>, String> {
+        let config = self.config()?;
+        let repos_client = ReposClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        repos_client.list_repos().await
+    }
+
+    #[query]
+    async fn create_repo(&self, url: String, provider: String, path: Option<String>) -> Result<RepoInfo, String> {
+        let config = self.config()?;
+        let repos_client = ReposClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        repos_client.create_repo(url, provider, path).await
+    }
+
+    #[query]
+    async fn get_repo(&self, repo_id: String) -> Result<RepoInfo, String> {
+        let config = self.config()?;
+        let repos_client = ReposClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        repos_client.get_repo(repo_id).await
+    }
+
+    #[query]
+    async fn update_repo(&self, repo_id: String, branch: Option<String>, tag: Option<String>) -> Result<RepoInfo, String> {
+        let config = self.config()?;
+        let repos_client = ReposClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        repos_client.update_repo(repo_id, branch, tag).await
+    }
+
+    #[query]
+    async fn delete_repo(&self, repo_id: String) -> Result<String, String> {
+        let config = self.config()?;
+        let repos_client = ReposClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        repos_client.delete_repo(repo_id).await
+    }
+
+    #[query]
+    async fn list_registered_models(&self, max_results: Option<u32>) -> Result<String, String> {
+        let config = self.config()?;
+        let model_registry_client = ModelRegistryClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        model_registry_client.list_registered_models(max_results).await
     }
 
     #[query]
     async fn get_registered_model(&self, name: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let model_registry_client = ModelRegistryClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let model_registry_client = ModelRegistryClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         model_registry_client.get_registered_model(name).await
     }
 
     #[query]
     async fn create_registered_model(&self, name: String, description: Option<String>) -> Result<String, String> {
-        let config = self.secrets.config();
-        let model_registry_client = ModelRegistryClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let model_registry_client = ModelRegistryClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         model_registry_client.create_registered_model(name, description).await
     }
 
     #[query]
     async fn list_model_versions(&self, name: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let model_registry_client = ModelRegistryClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let model_registry_client = ModelRegistryClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         model_registry_client.list_model_versions(name).await
     }
 
     #[query]
     async fn get_model_version(&self, name: String, version: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let model_registry_client = ModelRegistryClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let model_registry_client = ModelRegistryClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         model_registry_client.get_model_version(name, version).await
     }
 
     #[query]
     async fn set_model_version_stage(&self, name: String, version: String, stage: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let model_registry_client = ModelRegistryClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let model_registry_client = ModelRegistryClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         model_registry_client.set_model_version_stage(name, version, stage).await
     }
 
     #[query]
     async fn delete_registered_model(&self, name: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let model_registry_client = ModelRegistryClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let model_registry_client = ModelRegistryClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         model_registry_client.delete_registered_model(name).await
     }
 
     #[query]
     async fn list_serving_endpoints(&self) -> Result<String, String> {
-        let config = self.secrets.config();
-        let model_serving_client = ModelServingClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let model_serving_client = ModelServingClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         model_serving_client.list_serving_endpoints().await
     }
 
     #[query]
     async fn get_serving_endpoint(&self, name: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let model_serving_client = ModelServingClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let model_serving_client = ModelServingClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         model_serving_client.get_serving_endpoint(name).await
     }
 
     #[query]
     async fn create_serving_endpoint(&self, name: String, configuration: String) -> Result<String, String> {
-        let secrets_config = self.secrets.config();
-        let model_serving_client = ModelServingClient::new(&secrets_config.workspace_url, &secrets_config.pat_token);
+        let secrets_config = self.config()?;
+        let model_serving_client = ModelServingClient::new(&secrets_config.workspace_url, &secrets_config.pat_token).with_user_agent(secrets_config.user_agent());
         let config_json: serde_json::Value = serde_json::from_str(&configuration)
             .map_err(|e| format!("Invalid JSON config: {}", e))?;
         model_serving_client.create_serving_endpoint(name, config_json).await
@@ -340,8 +771,8 @@ impl Databricks for DatabricksContractState {
 
     #[query]
     async fn update_serving_endpoint(&self, name: String, configuration: String) -> Result<String, String> {
-        let secrets_config = self.secrets.config();
-        let model_serving_client = ModelServingClient::new(&secrets_config.workspace_url, &secrets_config.pat_token);
+        let secrets_config = self.config()?;
+        let model_serving_client = ModelServingClient::new(&secrets_config.workspace_url, &secrets_config.pat_token).with_user_agent(secrets_config.user_agent());
         let config_json: serde_json::Value = serde_json::from_str(&configuration)
             .map_err(|e| format!("Invalid JSON config: {}", e))?;
         model_serving_client.update_serving_endpoint(name, config_json).await
@@ -349,136 +780,200 @@ impl Databricks for DatabricksContractState {
 
     #[query]
     async fn delete_serving_endpoint(&self, name: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let model_serving_client = ModelServingClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let model_serving_client = ModelServingClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         model_serving_client.delete_serving_endpoint(name).await
     }
 
     #[query]
     async fn get_serving_endpoint_logs(&self, name: String, lines: Option<i32>) -> Result<String, String> {
-        let config = self.secrets.config();
-        let model_serving_client = ModelServingClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let model_serving_client = ModelServingClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         model_serving_client.get_serving_endpoint_logs(name, lines).await
     }
 
     #[query]
-    async fn query_serving_endpoint(&self, name: String, data: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let model_serving_client = ModelServingClient::new(&config.workspace_url, &config.pat_token);
+    async fn query_serving_endpoint(&self, name: String, data: String, validate_input: Option<bool>) -> Result<String, String> {
+        let config = self.config()?;
+        let model_serving_client = ModelServingClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         let data_json: serde_json::Value = serde_json::from_str(&data)
             .map_err(|e| format!("Invalid JSON data: {}", e))?;
+        if validate_input.unwrap_or(false) {
+            model_serving::validate_serving_input(&data_json)?;
+        }
         model_serving_client.query_serving_endpoint(name, data_json).await
     }
 
     #[query]
-    async fn list_jobs(&self) -> Result<String, String> {
-        let config = self.secrets.config();
-        let job_client = JobClient::new(&config.workspace_url, &config.pat_token);
-        job_client.list_jobs().await
+    async fn set_endpoint_rate_limit(&self, name: String, calls: u32, per_seconds: u32, principal: Option<String>) -> Result<String, String> {
+        let config = self.config()?;
+        let model_serving_client = ModelServingClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        let limit = model_serving::build_rate_limit(calls, per_seconds, principal.as_deref())?;
+        model_serving_client.set_endpoint_rate_limit(name, limit).await
+    }
+
+    #[query]
+    async fn list_jobs(&self, max_results: Option<u32>) -> Result<String, String> {
+        let config = self.config()?;
+        let job_client = JobClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        job_client.list_jobs(max_results).await
     }
 
     #[query]
     async fn get_job(&self, job_id: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let job_client = JobClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let job_client = JobClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         job_client.get_job(job_id).await
     }
 
     #[query]
     async fn run_job_now(&self, job_id: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let job_client = JobClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let job_client = JobClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         job_client.run_job_now(job_id).await
     }
 
+    #[query]
+    async fn run_job_and_wait(&self, job_id: String, max_checks: Option<u32>) -> Result<JobRunResult, String> {
+        let config = self.config()?;
+        let job_client = JobClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        job_client.run_job_and_wait(job_id, max_checks).await
+    }
+
     #[query]
     async fn get_job_run(&self, run_id: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let job_client = JobClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let job_client = JobClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         job_client.get_job_run(run_id).await
     }
 
+    #[query]
+    async fn list_job_runs(&self, job_id: String, limit: Option<u32>, active_only: Option<bool>) -> Result<String, String> {
+        let config = self.config()?;
+        let job_client = JobClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        job_client.list_job_runs(job_id, limit, active_only).await
+    }
+
     #[query]
     async fn cancel_job_run(&self, run_id: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let job_client = JobClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let job_client = JobClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         job_client.cancel_job_run(run_id).await
     }
 
     #[query]
-    async fn create_sql_alert(&self, name: String, query_id: String, column: String, op: String, threshold: String, rearm: i32) -> Result<String, String> {
-        let config = self.secrets.config();
-        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token);
-        sql_client.create_sql_alert(name, query_id, column, op, threshold, rearm).await
+    async fn pause_job(&self, job_id: String) -> Result<String, String> {
+        let config = self.config()?;
+        let job_client = JobClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        job_client.pause_job(job_id).await
+    }
+
+    #[query]
+    async fn resume_job(&self, job_id: String) -> Result<String, String> {
+        let config = self.config()?;
+        let job_client = JobClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        job_client.resume_job(job_id).await
+    }
+
+    #[query]
+    async fn create_sql_alert(&self, name: String, query_id: String, options: AlertOptions, notification_ids: Option<Vec<String>>) -> Result<String, String> {
+        let config = self.config()?;
+        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        let alert = sql_client.create_sql_alert(name, query_id, options).await?;
+        match notification_ids {
+            Some(ids) if !ids.is_empty() => sql_client.subscribe_alert(alert, ids).await,
+            _ => Ok(alert),
+        }
+    }
+
+    #[query]
+    async fn list_notification_destinations(&self) -> Result<String, String> {
+        let config = self.config()?;
+        let sql_client = SqlClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        sql_client.list_notification_destinations().await
     }
 
     #[query]
     async fn list_catalogs(&self) -> Result<String, String> {
-        let config = self.secrets.config();
-        let catalog_client = CatalogClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let catalog_client = CatalogClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         catalog_client.list_catalogs().await
     }
 
     #[query]
     async fn get_catalog(&self, catalog_name: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let catalog_client = CatalogClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let catalog_client = CatalogClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         catalog_client.get_catalog(catalog_name).await
     }
 
     #[query]
     async fn list_schemas(&self, catalog_name: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let catalog_client = CatalogClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let catalog_client = CatalogClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         catalog_client.list_schemas(catalog_name).await
     }
 
     #[query]
     async fn get_schema(&self, catalog_name: String, schema_name: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let catalog_client = CatalogClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let catalog_client = CatalogClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         catalog_client.get_schema(catalog_name, schema_name).await
     }
 
     #[query]
     async fn list_tables(&self, catalog_name: String, schema_name: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let catalog_client = CatalogClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let catalog_client = CatalogClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         catalog_client.list_tables(catalog_name, schema_name).await
     }
 
     #[query]
     async fn get_table(&self, catalog_name: String, schema_name: String, table_name: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let catalog_client = CatalogClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let catalog_client = CatalogClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         catalog_client.get_table(catalog_name, schema_name, table_name).await
     }
 
+    #[query]
+    async fn get_grants(&self, securable_type: String, full_name: String) -> Result<String, String> {
+        let config = self.config()?;
+        let catalog_client = CatalogClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        catalog_client.get_grants(securable_type, full_name).await
+    }
+
+    #[query]
+    async fn update_grants(&self, securable_type: String, full_name: String, changes: String) -> Result<String, String> {
+        let config = self.config()?;
+        let catalog_client = CatalogClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
+        catalog_client.update_grants(securable_type, full_name, changes).await
+    }
+
     #[query]
     async fn list_metastores(&self) -> Result<String, String> {
-        let config = self.secrets.config();
-        let catalog_client = CatalogClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let catalog_client = CatalogClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         catalog_client.list_metastores().await
     }
 
     #[query]
     async fn list_functions(&self, catalog_name: String, schema_name: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let functions_client = FunctionsClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let functions_client = FunctionsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         functions_client.list_functions(&catalog_name, &schema_name).await
     }
 
     #[query]
     async fn get_function(&self, function_name: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let functions_client = FunctionsClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let functions_client = FunctionsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         functions_client.get_function(&function_name).await
     }
 
     #[query]
     async fn create_function(&self, name: String, catalog_name: String, schema_name: String, input_params: String, data_type: String, language: String, routine_definition: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let functions_client = FunctionsClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let functions_client = FunctionsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         
         // Parse input_params JSON string into Vec<FunctionParameter>
         // If input_params is empty array "[]", set to None to avoid API issues
@@ -520,22 +1015,22 @@ impl Databricks for DatabricksContractState {
 
     #[query]
     async fn delete_function(&self, function_name: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let functions_client = FunctionsClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let functions_client = FunctionsClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         functions_client.delete_function(&function_name).await
     }
 
     #[query]
     async fn list_pipelines(&self) -> Result<String, String> {
-        let config = self.secrets.config();
-        let pipeline_client = PipelineClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let pipeline_client = PipelineClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         pipeline_client.list_pipelines().await
     }
 
     #[query]
     async fn create_pipeline(&self, name: String, catalog: String, target: String, notebook_path: String, continuous: bool) -> Result<String, String> {
-        let config = self.secrets.config();
-        let pipeline_client = PipelineClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let pipeline_client = PipelineClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         
         let request = pipeline::PipelineCreateRequest {
             name,
@@ -555,15 +1050,15 @@ impl Databricks for DatabricksContractState {
 
     #[query]
     async fn get_pipeline(&self, pipeline_id: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let pipeline_client = PipelineClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let pipeline_client = PipelineClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         pipeline_client.get_pipeline(pipeline_id).await
     }
 
     #[query]
     async fn update_pipeline(&self, pipeline_id: String, name: Option<String>, catalog: Option<String>, target: Option<String>, notebook_path: Option<String>, continuous: Option<bool>) -> Result<String, String> {
-        let config = self.secrets.config();
-        let pipeline_client = PipelineClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let pipeline_client = PipelineClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         
         let mut request = pipeline::PipelineUpdateRequest {
             name,
@@ -587,29 +1082,29 @@ impl Databricks for DatabricksContractState {
 
     #[query]
     async fn delete_pipeline(&self, pipeline_id: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let pipeline_client = PipelineClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let pipeline_client = PipelineClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         pipeline_client.delete_pipeline(pipeline_id).await
     }
 
     #[query]
     async fn execute_pipeline(&self, pipeline_id: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let pipeline_client = PipelineClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let pipeline_client = PipelineClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         pipeline_client.execute_pipeline(pipeline_id).await
     }
 
     #[query]
     async fn get_pipeline_events(&self, pipeline_id: String) -> Result<String, String> {
-        let config = self.secrets.config();
-        let pipeline_client = PipelineClient::new(&config.workspace_url, &config.pat_token);
+        let config = self.config()?;
+        let pipeline_client = PipelineClient::new(&config.workspace_url, &config.pat_token).with_user_agent(config.user_agent());
         pipeline_client.get_pipeline_events(pipeline_id).await
     }
 
 
     #[query]
     fn tools(&self) -> String {
-        r#"[
+        let tools = r#"[
   {
     "type": "function",
     "function": {
@@ -669,6 +1164,18 @@ impl Databricks for DatabricksContractState {
       }
     }
   },
+  {
+    "type": "function",
+    "function": {
+      "name": "list_account_users",
+      "description": "get all users at the databricks account level (requires account_url and account_id in the config)\n",
+      "parameters": {
+        "type": "object",
+        "properties": {},
+        "required": []
+      }
+    }
+  },
   {
     "type": "function",
     "function": {
@@ -696,17 +1203,22 @@ impl Databricks for DatabricksContractState {
   {
     "type": "function",
     "function": {
-      "name": "list_sql_warehouses",
-      "description": "get all warehouses\n",
+      "name": "execute_sql_all",
+      "description": "run an sql query on databricks, wait for it to finish and return all rows of the result (across every result chunk) with the column names and types\n",
       "parameters": {
         "type": "object",
         "properties": {
+          "query_str": {
+            "type": "string",
+            "description": "the raw sql to run\n"
+          },
           "warehouse_id": {
             "type": "string",
             "description": "the id of the warehouse to run this query in\n"
           }
         },
         "required": [
+          "query_str",
           "warehouse_id"
         ]
       }
@@ -715,18 +1227,18 @@ impl Databricks for DatabricksContractState {
   {
     "type": "function",
     "function": {
-      "name": "start_sql_warehouse",
-      "description": "start a warehouse\n",
+      "name": "normalize_sql",
+      "description": "normalize an sql query without running it: comments are removed, whitespace is collapsed and keywords are lowercased, so queries that differ only in layout become identical\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "warehouse_id": {
+          "query_str": {
             "type": "string",
-            "description": "the id of the warehouse to start\n"
+            "description": "the raw sql to normalize\n"
           }
         },
         "required": [
-          "warehouse_id"
+          "query_str"
         ]
       }
     }
@@ -734,17 +1246,31 @@ impl Databricks for DatabricksContractState {
   {
     "type": "function",
     "function": {
-      "name": "stop_sql_warehouse",
-      "description": "start a warehouse\n",
+      "name": "get_usage",
+      "description": "get the DBUs consumed between two dates from the system.billing.usage table, optionally grouped\n",
       "parameters": {
         "type": "object",
         "properties": {
+          "start_date": {
+            "type": "string",
+            "description": "first day of the range, YYYY-MM-DD\n"
+          },
+          "end_date": {
+            "type": "string",
+            "description": "last day of the range (inclusive), YYYY-MM-DD\n"
+          },
+          "group_by": {
+            "type": "string",
+            "description": "column to aggregate by: usage_date, workspace_id, sku_name, cloud, billing_origin_product or usage_type (optional)\n"
+          },
           "warehouse_id": {
             "type": "string",
-            "description": "the id of the warehouse to stop\n"
+            "description": "the id of the warehouse to run the usage query in\n"
           }
         },
         "required": [
+          "start_date",
+          "end_date",
           "warehouse_id"
         ]
       }
@@ -753,38 +1279,23 @@ impl Databricks for DatabricksContractState {
   {
     "type": "function",
     "function": {
-      "name": "create_sql_warehouse",
-      "description": "create a warehouse\n",
+      "name": "get_table_stats",
+      "description": "get the number of rows, total size in bytes, number of files and storage format of a table, to judge how expensive querying it will be\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "name": {
+          "full_table_name": {
             "type": "string",
-            "description": "name of the warehourse\n"
+            "description": "three-part table name, catalog.schema.table\n"
           },
-          "cluster_size": {
+          "warehouse_id": {
             "type": "string",
-            "description": "cluster size of the warehourse\n"
-          },
-          "min_num_clusters": {
-            "type": "integer",
-            "description": "minimum number of clusters\n"
-          },
-          "max_num_clusters": {
-            "type": "integer",
-            "description": "maximum number of clusters\n"
-          },
-          "auto_stop_mins": {
-            "type": "integer",
-            "description": "time in minutes of inactivity for stopping\n"
+            "description": "the id of the warehouse to run the count and DESCRIBE DETAIL in\n"
           }
         },
         "required": [
-          "name",
-          "cluster_size",
-          "min_num_clusters",
-          "max_num_clusters",
-          "auto_stop_mins"
+          "full_table_name",
+          "warehouse_id"
         ]
       }
     }
@@ -792,14 +1303,133 @@ impl Databricks for DatabricksContractState {
   {
     "type": "function",
     "function": {
-      "name": "list_dbfs_files",
-      "description": "list files in databricks file system\n",
+      "name": "list_sql_warehouses",
+      "description": "get all warehouses\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "path": {
+          "warehouse_id": {
             "type": "string",
-            "description": "the path to look for files in\n"
+            "description": "the id of the warehouse to run this query in\n"
+          }
+        },
+        "required": [
+          "warehouse_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "start_sql_warehouse",
+      "description": "start a warehouse\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "warehouse_id": {
+            "type": "string",
+            "description": "the id of the warehouse to start\n"
+          }
+        },
+        "required": [
+          "warehouse_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "stop_sql_warehouse",
+      "description": "start a warehouse\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "warehouse_id": {
+            "type": "string",
+            "description": "the id of the warehouse to stop\n"
+          }
+        },
+        "required": [
+          "warehouse_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "create_sql_warehouse",
+      "description": "create a warehouse\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "name": {
+            "type": "string",
+            "description": "name of the warehourse\n"
+          },
+          "cluster_size": {
+            "type": "string",
+            "description": "cluster size of the warehourse\n"
+          },
+          "min_num_clusters": {
+            "type": "integer",
+            "description": "minimum number of clusters\n"
+          },
+          "max_num_clusters": {
+            "type": "integer",
+            "description": "maximum number of clusters\n"
+          },
+          "auto_stop_mins": {
+            "type": "integer",
+            "description": "time in minutes of inactivity for stopping\n"
+          }
+        },
+        "required": [
+          "name",
+          "cluster_size",
+          "min_num_clusters",
+          "max_num_clusters",
+          "auto_stop_mins"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "list_dbfs_files",
+      "description": "list files in databricks file system\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "path": {
+            "type": "string",
+            "description": "the path to look for files in\n"
+          }
+        },
+        "required": [
+          "path"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "list_dbfs_files_recursive",
+      "description": "list files in databricks file system, descending into subdirectories breadth-first; fails if the tree holds more than 10000 entries\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "path": {
+            "type": "string",
+            "description": "the directory to list\n"
+          },
+          "max_depth": {
+            "type": "integer",
+            "description": "how many levels to descend, 1 listing only the directory itself; defaults to and is capped at 32\n"
           }
         },
         "required": [
@@ -923,6 +1553,35 @@ impl Databricks for DatabricksContractState {
       }
     }
   },
+  {
+    "type": "function",
+    "function": {
+      "name": "write_dbfs_file_streaming",
+      "description": "write a file stored in IMFS to databricks file system in 1 MiB blocks, for files too large for write_dbfs_file; returns the number of bytes written\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "path": {
+            "type": "string",
+            "description": "the DBFS path of the file to write\n"
+          },
+          "file_descriptor": {
+            "type": "string",
+            "description": "IMFS file descriptor of the content to upload\n"
+          },
+          "overwrite": {
+            "type": "boolean",
+            "description": "whether to replace an existing file at the path\n"
+          }
+        },
+        "required": [
+          "path",
+          "file_descriptor",
+          "overwrite"
+        ]
+      }
+    }
+  },
   {
     "type": "function",
     "function": {
@@ -933,19 +1592,446 @@ impl Databricks for DatabricksContractState {
         "properties": {
           "path": {
             "type": "string",
-            "description": "the path of the file\n"
+            "description": "the path of the file\n"
+          },
+          "offset": {
+            "type": "integer",
+            "description": "the offset to read from\n"
+          },
+          "length": {
+            "type": "integer",
+            "description": "length to read\n"
+          }
+        },
+        "required": [
+          "path"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "list_clusters",
+      "description": "list all clusters with their id, name, state, spark version, node type and worker count\n",
+      "parameters": {
+        "type": "object",
+        "properties": {},
+        "required": []
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "list_clusters_raw",
+      "description": "list all clusters, returning the full Databricks response as JSON\n",
+      "parameters": {
+        "type": "object",
+        "properties": {},
+        "required": []
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "get_cluster",
+      "description": "get a cluster's id, name, state, spark version, node type and worker count\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "cluster_id": {
+            "type": "string",
+            "description": "the id of the cluster\n"
+          }
+        },
+        "required": [
+          "cluster_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "get_cluster_raw",
+      "description": "get a cluster, returning the full Databricks response as JSON\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "cluster_id": {
+            "type": "string",
+            "description": "the id of the cluster\n"
+          }
+        },
+        "required": [
+          "cluster_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "create_cluster",
+      "description": "create a cluster\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "name": {
+            "type": "string",
+            "description": "the name of the cluster\n"
+          },
+          "spark_version": {
+            "type": "string",
+            "description": "the versions of spark\n"
+          },
+          "node_type": {
+            "type": "string",
+            "description": "the node type\n"
+          },
+          "num_workers": {
+            "type": "integer",
+            "description": "the number of workers\n"
+          }
+        },
+        "required": [
+          "name",
+          "spark_version",
+          "node_type",
+          "num_workers"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "audit_compute_autostop",
+      "description": "find clusters and SQL warehouses whose idle auto-termination/auto-stop is disabled or longer than the configured limit, with the recommended setting for each\n",
+      "parameters": {
+        "type": "object",
+        "properties": {},
+        "required": []
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "install_library",
+      "description": "install a library on a cluster\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "cluster_id": {
+            "type": "string",
+            "description": "the id of the cluster\n"
+          },
+          "library_json": {
+            "type": "string",
+            "description": "the library as JSON with one of pypi, maven, jar or whl, e.g. {\"pypi\": {\"package\": \"pandas==2.2.0\"}} or {\"jar\": \"dbfs:/libs/udfs.jar\"}\n"
+          }
+        },
+        "required": [
+          "cluster_id",
+          "library_json"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "uninstall_library",
+      "description": "uninstall a library from a cluster; it is removed when the cluster restarts\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "cluster_id": {
+            "type": "string",
+            "description": "the id of the cluster\n"
+          },
+          "library_json": {
+            "type": "string",
+            "description": "the library as JSON with one of pypi, maven, jar or whl, e.g. {\"pypi\": {\"package\": \"pandas==2.2.0\"}} or {\"jar\": \"dbfs:/libs/udfs.jar\"}\n"
+          }
+        },
+        "required": [
+          "cluster_id",
+          "library_json"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "list_libraries",
+      "description": "list the libraries on a cluster with their install status\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "cluster_id": {
+            "type": "string",
+            "description": "the id of the cluster\n"
+          }
+        },
+        "required": [
+          "cluster_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "create_secret_scope",
+      "description": "create a workspace-managed secret scope\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "name": {
+            "type": "string",
+            "description": "name of the scope (letters, digits, '-', '_' or '.', up to 128 characters)\n"
+          }
+        },
+        "required": [
+          "name"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "list_secret_scopes",
+      "description": "list all secret scopes with their backend type\n",
+      "parameters": {
+        "type": "object",
+        "properties": {},
+        "required": []
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "put_secret",
+      "description": "create or overwrite a secret in a scope\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "scope": {
+            "type": "string",
+            "description": "name of the scope\n"
+          },
+          "key": {
+            "type": "string",
+            "description": "key of the secret\n"
+          },
+          "value": {
+            "type": "string",
+            "description": "the secret value as plain text (not base64-encoded)\n"
+          }
+        },
+        "required": [
+          "scope",
+          "key",
+          "value"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "list_secrets",
+      "description": "list the secret keys in a scope with when each was last updated; values are never returned\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "scope": {
+            "type": "string",
+            "description": "name of the scope\n"
+          }
+        },
+        "required": [
+          "scope"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "delete_secret",
+      "description": "delete a secret from a scope\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "scope": {
+            "type": "string",
+            "description": "name of the scope\n"
+          },
+          "key": {
+            "type": "string",
+            "description": "key of the secret to delete\n"
+          }
+        },
+        "required": [
+          "scope",
+          "key"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "list_sql_queries",
+      "description": "list all sql queries\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "user_id": {
+            "type": "string",
+            "description": "id of the user\n"
+          },
+          "include_metrics": {
+            "type": "boolean",
+            "description": "whether to include metrics\n"
+          }
+        },
+        "required": [
+          "user_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "list_query_history",
+      "description": "list recent SQL statement executions, newest first, with their status, duration, rows produced and who ran them; follows pagination up to max_results\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "warehouse_id": {
+            "type": "string",
+            "description": "only statements run on this SQL warehouse (optional)\n"
+          },
+          "user_id": {
+            "type": "string",
+            "description": "only statements run by this user, by numeric user id (optional)\n"
+          },
+          "max_results": {
+            "type": "integer",
+            "description": "most statements to return (optional, 100 by default)\n"
+          }
+        },
+        "required": []
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "create_directory",
+      "description": "create a directory in workspace\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "path": {
+            "type": "string",
+            "description": "the path to create the directory\n"
+          }
+        },
+        "required": [
+          "path"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "list_workspace_directory",
+      "description": "list contents of a workspace directory (use this for workspace paths like /Users/...)\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "path": {
+            "type": "string",
+            "description": "the workspace path to list\n"
+          }
+        },
+        "required": [
+          "path"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "export_notebook",
+      "description": "export a workspace notebook, returning its content base64-encoded\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "path": {
+            "type": "string",
+            "description": "absolute workspace path of the notebook, e.g. /Users/someone@example.com/etl\n"
+          },
+          "format": {
+            "type": "string",
+            "description": "export format: SOURCE, HTML, JUPYTER, DBC, R_MARKDOWN or AUTO\n"
+          }
+        },
+        "required": [
+          "path",
+          "format"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "import_notebook",
+      "description": "import a notebook into the workspace from base64-encoded content\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "path": {
+            "type": "string",
+            "description": "absolute workspace path to import the notebook to\n"
+          },
+          "format": {
+            "type": "string",
+            "description": "format of the content: SOURCE, HTML, JUPYTER, DBC, R_MARKDOWN or AUTO\n"
+          },
+          "language": {
+            "type": "string",
+            "description": "notebook language: PYTHON, SQL, SCALA or R; required for SOURCE, may be empty for other formats\n"
           },
-          "offset": {
-            "type": "integer",
-            "description": "the offset to read from\n"
+          "content_b64": {
+            "type": "string",
+            "description": "the notebook content, base64-encoded\n"
           },
-          "length": {
-            "type": "integer",
-            "description": "length to read\n"
+          "overwrite": {
+            "type": "boolean",
+            "description": "whether to replace an existing notebook at the path\n"
           }
         },
         "required": [
-          "path"
+          "path",
+          "format",
+          "language",
+          "content_b64",
+          "overwrite"
         ]
       }
     }
@@ -953,8 +2039,8 @@ impl Databricks for DatabricksContractState {
   {
     "type": "function",
     "function": {
-      "name": "list_clusters",
-      "description": "list all clusters\n",
+      "name": "list_repos",
+      "description": "list the Git folders (repos) in the workspace with their checked-out branch and head commit\n",
       "parameters": {
         "type": "object",
         "properties": {},
@@ -965,52 +2051,27 @@ impl Databricks for DatabricksContractState {
   {
     "type": "function",
     "function": {
-      "name": "get_cluster",
-      "description": "get a cluster\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "cluster_id": {
-            "type": "string",
-            "description": "the id of the cluster\n"
-          }
-        },
-        "required": [
-          "cluster_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "create_cluster",
-      "description": "create a cluster\n",
+      "name": "create_repo",
+      "description": "clone a remote Git repository into the workspace as a Git folder\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "name": {
+          "url": {
             "type": "string",
-            "description": "the name of the cluster\n"
+            "description": "URL of the remote Git repository\n"
           },
-          "spark_version": {
+          "provider": {
             "type": "string",
-            "description": "the versions of spark\n"
+            "description": "Git provider: gitHub, gitHubEnterprise, bitbucketCloud, bitbucketServer, gitLab, gitLabEnterpriseEdition, azureDevOpsServices or awsCodeCommit\n"
           },
-          "node_type": {
+          "path": {
             "type": "string",
-            "description": "the node type\n"
-          },
-          "num_workers": {
-            "type": "integer",
-            "description": "the number of workers\n"
+            "description": "absolute workspace path for the repo, e.g. /Repos/someone@example.com/etl (optional, defaults to the caller's Repos folder)\n"
           }
         },
         "required": [
-          "name",
-          "spark_version",
-          "node_type",
-          "num_workers"
+          "url",
+          "provider"
         ]
       }
     }
@@ -1018,22 +2079,18 @@ impl Databricks for DatabricksContractState {
   {
     "type": "function",
     "function": {
-      "name": "list_sql_queries",
-      "description": "list all sql queries\n",
+      "name": "get_repo",
+      "description": "get a Git folder (repo) by id\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "user_id": {
+          "repo_id": {
             "type": "string",
-            "description": "id of the user\n"
-          },
-          "include_metrics": {
-            "type": "boolean",
-            "description": "whether to include metrics\n"
+            "description": "numeric id of the repo\n"
           }
         },
         "required": [
-          "user_id"
+          "repo_id"
         ]
       }
     }
@@ -1041,18 +2098,26 @@ impl Databricks for DatabricksContractState {
   {
     "type": "function",
     "function": {
-      "name": "create_directory",
-      "description": "create a directory in workspace\n",
+      "name": "update_repo",
+      "description": "check out a branch or tag in a repo, pulling it from the remote; give exactly one of branch and tag\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "path": {
+          "repo_id": {
             "type": "string",
-            "description": "the path to create the directory\n"
+            "description": "numeric id of the repo\n"
+          },
+          "branch": {
+            "type": "string",
+            "description": "branch to check out (optional)\n"
+          },
+          "tag": {
+            "type": "string",
+            "description": "tag to check out (optional)\n"
           }
         },
         "required": [
-          "path"
+          "repo_id"
         ]
       }
     }
@@ -1060,18 +2125,18 @@ impl Databricks for DatabricksContractState {
   {
     "type": "function",
     "function": {
-      "name": "list_workspace_directory",
-      "description": "list contents of a workspace directory (use this for workspace paths like /Users/...)\n",
+      "name": "delete_repo",
+      "description": "delete a Git folder (repo) from the workspace; the remote repository is not affected\n",
       "parameters": {
         "type": "object",
         "properties": {
-          "path": {
+          "repo_id": {
             "type": "string",
-            "description": "the workspace path to list\n"
+            "description": "numeric id of the repo\n"
           }
         },
         "required": [
-          "path"
+          "repo_id"
         ]
       }
     }
@@ -1080,10 +2145,15 @@ impl Databricks for DatabricksContractState {
     "type": "function",
     "function": {
       "name": "list_registered_models",
-      "description": "list all registered models in the model registry\n",
+      "description": "list all registered models in the model registry, following pagination; has_more is true if max_results cut the list short\n",
       "parameters": {
         "type": "object",
-        "properties": {},
+        "properties": {
+          "max_results": {
+            "type": "integer",
+            "description": "most models to return (optional, all by default)\n"
+          }
+        },
         "required": []
       }
     }
@@ -1357,6 +2427,10 @@ impl Databricks for DatabricksContractState {
           "data": {
             "type": "string",
             "description": "JSON data for prediction\n"
+          },
+          "validate_input": {
+            "type": "boolean",
+            "description": "check before sending that data is in dataframe_split or instances format (optional, defaults to false; leave off for chat or completions endpoints)\n"
           }
         },
         "required": [
@@ -1366,14 +2440,52 @@ impl Databricks for DatabricksContractState {
       }
     }
   },
+  {
+    "type": "function",
+    "function": {
+      "name": "set_endpoint_rate_limit",
+      "description": "set a rate limit on a serving endpoint, for the whole endpoint or for one user, group or service principal, keeping the rest of its ai gateway config\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "name": {
+            "type": "string",
+            "description": "name of the serving endpoint\n"
+          },
+          "calls": {
+            "type": "integer",
+            "description": "number of calls allowed per period\n"
+          },
+          "per_seconds": {
+            "type": "integer",
+            "description": "length of the period in seconds; only 60 (one minute) is supported\n"
+          },
+          "principal": {
+            "type": "string",
+            "description": "user email, group name or service principal application id the limit applies to (optional, limits the whole endpoint when omitted)\n"
+          }
+        },
+        "required": [
+          "name",
+          "calls",
+          "per_seconds"
+        ]
+      }
+    }
+  },
   {
     "type": "function",
     "function": {
       "name": "list_jobs",
-      "description": "list all jobs in databricks\n",
+      "description": "list all jobs in databricks, following pagination; has_more is true if max_results cut the list short\n",
       "parameters": {
         "type": "object",
-        "properties": {},
+        "properties": {
+          "max_results": {
+            "type": "integer",
+            "description": "most jobs to return (optional, all by default)\n"
+          }
+        },
         "required": []
       }
     }
@@ -1416,6 +2528,29 @@ impl Databricks for DatabricksContractState {
       }
     }
   },
+  {
+    "type": "function",
+    "function": {
+      "name": "run_job_and_wait",
+      "description": "run a job now and check its status until it finishes, returning its result state, state message and run page URL; checks are made back to back with no delay (60 by default), and it fails with an error starting with '[TIMEOUT]' if the run is still going after max_checks checks, so follow a long-running job with get_job_run\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "job_id": {
+            "type": "string",
+            "description": "the id of the job to run\n"
+          },
+          "max_checks": {
+            "type": "integer",
+            "description": "how many times to check the run's status before giving up (optional, defaults to 60, at most 300)\n"
+          }
+        },
+        "required": [
+          "job_id"
+        ]
+      }
+    }
+  },
   {
     "type": "function",
     "function": {
@@ -1435,6 +2570,33 @@ impl Databricks for DatabricksContractState {
       }
     }
   },
+  {
+    "type": "function",
+    "function": {
+      "name": "list_job_runs",
+      "description": "list the most recent runs of a job, newest first, e.g. to spot flaky or long-running jobs\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "job_id": {
+            "type": "string",
+            "description": "the id of the job\n"
+          },
+          "limit": {
+            "type": "integer",
+            "description": "maximum number of runs to return (optional, defaults to 25)\n"
+          },
+          "active_only": {
+            "type": "boolean",
+            "description": "only list runs that are pending or running (optional, defaults to false)\n"
+          }
+        },
+        "required": [
+          "job_id"
+        ]
+      }
+    }
+  },
   {
     "type": "function",
     "function": {
@@ -1454,6 +2616,44 @@ impl Databricks for DatabricksContractState {
       }
     }
   },
+  {
+    "type": "function",
+    "function": {
+      "name": "pause_job",
+      "description": "pause a job's schedule so it stops triggering new runs; returns the updated schedule\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "job_id": {
+            "type": "string",
+            "description": "the id of the scheduled job\n"
+          }
+        },
+        "required": [
+          "job_id"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "resume_job",
+      "description": "resume a paused job schedule; returns the updated schedule\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "job_id": {
+            "type": "string",
+            "description": "the id of the scheduled job\n"
+          }
+        },
+        "required": [
+          "job_id"
+        ]
+      }
+    }
+  },
   {
     "type": "function",
     "function": {
@@ -1470,34 +2670,62 @@ impl Databricks for DatabricksContractState {
             "type": "string",
             "description": "id of the query to monitor\n"
           },
-          "column": {
-            "type": "string",
-            "description": "column to monitor\n"
-          },
-          "op": {
-            "type": "string",
-            "description": "comparison operator\n"
-          },
-          "threshold": {
-            "type": "string",
-            "description": "threshold value\n"
+          "options": {
+            "type": "object",
+            "properties": {
+              "column": {
+                "type": "string",
+                "description": "column to monitor\n"
+              },
+              "op": {
+                "type": "string",
+                "description": "comparison operator (>, <, >=, <=, ==, !=)\n"
+              },
+              "threshold": {
+                "type": "string",
+                "description": "threshold value\n"
+              },
+              "rearm": {
+                "type": "integer",
+                "description": "rearm count (0 for no rearm)\n"
+              }
+            },
+            "required": [
+              "column",
+              "op",
+              "threshold",
+              "rearm"
+            ],
+            "description": "when the alert triggers\n"
           },
-          "rearm": {
-            "type": "integer",
-            "description": "rearm count (0 for no rearm)\n"
+          "notification_ids": {
+            "type": "array",
+            "items": {
+              "type": "string"
+            },
+            "description": "ids of notification destinations to subscribe to the alert (optional)\n"
           }
         },
         "required": [
           "name",
           "query_id",
-          "column",
-          "op",
-          "threshold",
-          "rearm"
+          "options"
         ]
       }
     }
   },
+  {
+    "type": "function",
+    "function": {
+      "name": "list_notification_destinations",
+      "description": "list the notification destinations (email, Slack, webhook, ...) that alerts can notify\n",
+      "parameters": {
+        "type": "object",
+        "properties": {},
+        "required": []
+      }
+    }
+  },
   {
     "type": "function",
     "function": {
@@ -1625,6 +2853,59 @@ impl Databricks for DatabricksContractState {
       }
     }
   },
+  {
+    "type": "function",
+    "function": {
+      "name": "get_grants",
+      "description": "get the privileges granted on a Unity Catalog securable, per principal\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "securable_type": {
+            "type": "string",
+            "description": "type of the securable: catalog, schema, table or function\n"
+          },
+          "full_name": {
+            "type": "string",
+            "description": "full name of the securable, e.g. main.sales.orders for a table\n"
+          }
+        },
+        "required": [
+          "securable_type",
+          "full_name"
+        ]
+      }
+    }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "update_grants",
+      "description": "grant and revoke privileges on a Unity Catalog securable\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "securable_type": {
+            "type": "string",
+            "description": "type of the securable: catalog, schema, table or function\n"
+          },
+          "full_name": {
+            "type": "string",
+            "description": "full name of the securable, e.g. main.sales.orders for a table\n"
+          },
+          "changes": {
+            "type": "string",
+            "description": "JSON of the form {\"changes\": [{\"principal\": \"analysts\", \"add\": [\"SELECT\"], \"remove\": [\"MODIFY\"]}]}\n"
+          }
+        },
+        "required": [
+          "securable_type",
+          "full_name",
+          "changes"
+        ]
+      }
+    }
+  },
   {
     "type": "function",
     "function": {
@@ -1913,8 +3194,28 @@ impl Databricks for DatabricksContractState {
         ]
       }
     }
+  },
+  {
+    "type": "function",
+    "function": {
+      "name": "diagnose",
+      "description": "explain an error message returned by another tool and suggest how to fix it, e.g. a new access token after an auth failure or fewer calls after a rate limit\n",
+      "parameters": {
+        "type": "object",
+        "properties": {
+          "error_message": {
+            "type": "string",
+            "description": "the error message exactly as the failed tool returned it\n"
+          }
+        },
+        "required": [
+          "error_message"
+        ]
+      }
+    }
   }
-]"#.to_string()
+]"#;
+        filter_tools(tools, &self.secrets.config().disabled_tools)
     }
 
     #[query]
@@ -1923,5 +3224,141 @@ impl Databricks for DatabricksContractState {
   "prompts": []
 }"#.to_string()
     }
+
+    #[query]
+    fn diagnose(&self, error_message: String) -> String {
+        diagnosis_for(&error_message, DIAGNOSIS_RULES)
+            .unwrap_or("No specific guidance for this error; check the tool's arguments and the error_code and message Databricks returned.")
+            .to_string()
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(account_url: Option<&str>, account_id: Option<&str>) -> DatabricksConfig {
+        DatabricksConfig {
+            pat_token: "dapi-test".to_string(),
+            workspace_url: "https://adb-123.azuredatabricks.net".to_string(),
+            account_url: account_url.map(str::to_string),
+            account_id: account_id.map(str::to_string),
+            user_agent: None,
+            max_cluster_autotermination_mins: None,
+            max_warehouse_auto_stop_mins: None,
+            disabled_tools: vec![],
+        }
+    }
+
+    /// Configured audit limits override the defaults; unset or zero limits keep them.
+    #[test]
+    fn test_autostop_thresholds() {
+        assert_eq!(config(None, None).autostop_thresholds(), AutostopThresholds::default());
+        let configured = DatabricksConfig { max_cluster_autotermination_mins: Some(20), max_warehouse_auto_stop_mins: Some(0), ..config(None, None) };
+        assert_eq!(configured.autostop_thresholds(), AutostopThresholds { max_cluster_autotermination_mins: 20, max_warehouse_auto_stop_mins: 30 });
+    }
+
+    /// A blank token or a workspace URL without a scheme is reported by field name; an unset account URL is fine.
+    #[test]
+    fn test_config_validation() {
+        assert!(config(None, None).validate().is_ok());
+        let cases = [
+            (DatabricksConfig { pat_token: " ".to_string(), ..config(None, None) }, "pat_token"),
+            (DatabricksConfig { workspace_url: String::new(), ..config(None, None) }, "workspace_url"),
+            (DatabricksConfig { workspace_url: "adb-123.azuredatabricks.net".to_string(), ..config(None, None) }, "workspace_url"),
+            (config(Some("accounts.cloud.databricks.com"), None), "account_url"),
+        ];
+        for (config, field) in cases {
+            let err = config.validate().unwrap_err();
+            assert!(err.contains(&format!("`{}`", field)), "{}", err);
+            assert_eq!(ErrorCode::of_tagged(&err), Some(ErrorCode::Validation));
+        }
+    }
+
+    /// Workspace and account scopes resolve to their own hosts, and account calls fail clearly when unconfigured.
+    #[test]
+    fn test_base_url_routing() {
+        let configured = config(Some("https://accounts.azuredatabricks.net"), Some("acc-1"));
+        assert_eq!(configured.base_url_for(ApiScope::Workspace).unwrap(), "https://adb-123.azuredatabricks.net");
+        assert_eq!(configured.base_url_for(ApiScope::Account).unwrap(), "https://accounts.azuredatabricks.net");
+        assert_eq!(configured.account_id().unwrap(), "acc-1");
+
+        let workspace_only = config(None, None);
+        assert_eq!(workspace_only.base_url_for(ApiScope::Workspace).unwrap(), "https://adb-123.azuredatabricks.net");
+        assert!(workspace_only.base_url_for(ApiScope::Account).unwrap_err().contains("account_url"));
+        assert!(config(Some("  "), Some("acc-1")).base_url_for(ApiScope::Account).is_err());
+        assert!(config(Some("https://accounts.azuredatabricks.net"), None).account_id().unwrap_err().contains("account_id"));
+    }
+
+    /// Pages are followed by token until the API runs out, and max_results stops early and says so.
+    #[test]
+    fn test_collect_token_pages() {
+        let pages = [
+            r#"{"jobs": [{"job_id": 1}, {"job_id": 2}], "has_more": true, "next_page_token": "p2"}"#,
+            r#"{"jobs": [{"job_id": 3}], "has_more": false}"#,
+        ];
+        let mut requested = Vec::new();
+        let all = collect_token_pages("jobs", None, |token, size| {
+            requested.push((token.map(str::to_string), size));
+            Ok(pages[requested.len() - 1].to_string())
+        }).unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&all).unwrap(), serde_json::json!({
+            "jobs": [{"job_id": 1}, {"job_id": 2}, {"job_id": 3}],
+            "has_more": false
+        }));
+        assert_eq!(requested, vec![(None, LIST_PAGE_SIZE), (Some("p2".to_string()), LIST_PAGE_SIZE)]);
+
+        let mut calls = 0;
+        let capped = collect_token_pages("jobs", Some(1), |_, size| {
+            calls += 1;
+            assert_eq!(size, 1);
+            Ok(pages[0].to_string())
+        }).unwrap();
+        assert_eq!(calls, 1);
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&capped).unwrap(), serde_json::json!({ "jobs": [{"job_id": 1}], "has_more": true }));
+
+        let models = collect_token_pages("registered_models", None, |token, _| {
+            Ok(match token {
+                None => r#"{"registered_models": [{"name": "a"}], "next_page_token": "t"}"#,
+                Some(_) => r#"{"registered_models": [{"name": "b"}]}"#,
+            }.to_string())
+        }).unwrap();
+        assert!(models.contains(r#""name":"b""#));
+        assert_eq!(collect_token_pages("jobs", None, |_, _| Ok("{}".to_string())).unwrap(), r#"{"has_more":false,"jobs":[]}"#);
+        assert!(collect_token_pages("jobs", Some(0), |_, _| unreachable!()).is_err());
+    }
+
+    /// Clients send the crate's User-Agent by default and the configured one when it is set.
+    #[test]
+    fn test_user_agent() {
+        assert_eq!(DEFAULT_USER_AGENT, format!("wadk-mcp/databricks/{}", env!("CARGO_PKG_VERSION")));
+
+        let default_config = config(None, None);
+        assert_eq!(default_config.user_agent(), DEFAULT_USER_AGENT);
+        let headers = SqlClient::new(&default_config.workspace_url, &default_config.pat_token)
+            .with_user_agent(default_config.user_agent())
+            .get_headers();
+        assert_eq!(headers.get("User-Agent").map(String::as_str), Some(DEFAULT_USER_AGENT));
+
+        let overridden = DatabricksConfig { user_agent: Some("acme-agent/2.0".to_string()), ..config(None, None) };
+        assert_eq!(overridden.user_agent(), "acme-agent/2.0");
+    }
+
+    /// API errors map to guidance by Databricks error code where one is given, else by status.
+    #[test]
+    fn test_diagnose() {
+        let guidance = |message: &str| diagnosis_for(message, DIAGNOSIS_RULES).unwrap_or_default();
+        let body = |error_code: &str| Some(format!(r#"{{"error_code": "{}", "message": "..."}}"#, error_code));
+
+        assert!(guidance(&api_error(401, None)).contains("generate a new one"));
+        assert!(guidance(&api_error(403, body("PERMISSION_DENIED"))).contains("lacks permission"));
+        assert!(guidance(&api_error(404, body("RESOURCE_DOES_NOT_EXIST"))).contains("Verify the id or path"));
+        assert!(guidance(&api_error(429, body("REQUEST_LIMIT_EXCEEDED"))).contains("Reduce the call rate"));
+        assert!(guidance(&api_error(400, body("INVALID_STATE"))).contains("start the cluster"));
+        assert!(guidance(&api_error(400, body("RESOURCE_ALREADY_EXISTS"))).contains("already exists"));
+        assert!(guidance(&api_error(400, body("INVALID_PARAMETER_VALUE"))).contains("names the field"));
+        assert!(guidance("[TIMEOUT] run 42 was still going after 3 status checks").contains("get_job_run"));
+        assert!(guidance("[VALIDATION] config field `pat_token` is required but empty").contains("MCP secrets"));
+        assert_eq!(diagnosis_for("something unexpected", DIAGNOSIS_RULES), None);
+    }
+}
@@ -0,0 +1,154 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use weil_rs::http::{HttpClient, HttpMethod};
+
+/// Library types that can be installed on a cluster
+const LIBRARY_TYPES: [&str; 4] = ["pypi", "maven", "jar", "whl"];
+
+/// Validate a library spec such as `{"pypi": {"package": "pandas==2.2.0"}}` or
+/// `{"jar": "dbfs:/libs/udfs.jar"}`: exactly one recognised library type with its required fields.
+pub fn validate_library_spec(spec: &Value) -> Result<(), String> {
+    let object = spec.as_object().ok_or("library spec must be a JSON object")?;
+    if object.len() != 1 {
+        return Err(format!("library spec must have exactly one of: {}", LIBRARY_TYPES.join(", ")));
+    }
+    let (kind, value) = object.iter().next().unwrap();
+
+    match kind.as_str() {
+        "pypi" | "maven" => {
+            let field = if kind == "pypi" { "package" } else { "coordinates" };
+            match value.get(field).and_then(Value::as_str) {
+                Some(text) if !text.trim().is_empty() => Ok(()),
+                _ => Err(format!("{} library needs a non-empty \"{}\"", kind, field)),
+            }
+        }
+        "jar" | "whl" => match value.as_str() {
+            Some(path) if !path.trim().is_empty() => Ok(()),
+            _ => Err(format!("{} library must be a non-empty path", kind)),
+        },
+        other => Err(format!("unsupported library type '{}', expected one of: {}", other, LIBRARY_TYPES.join(", "))),
+    }
+}
+
+/// Build the install/uninstall request body for a single library on a cluster
+pub fn build_library_request(cluster_id: &str, library_json: &str) -> Result<Value, String> {
+    let spec: Value = serde_json::from_str(library_json).map_err(|e| format!("Invalid library JSON: {}", e))?;
+    validate_library_spec(&spec)?;
+    Ok(serde_json::json!({
+        "cluster_id": cluster_id,
+        "libraries": [spec]
+    }))
+}
+
+/// Cluster library management operations for Databricks
+pub struct LibrariesClient {
+    base_url: String,
+    token: String,
+    user_agent: String,
+}
+
+impl LibrariesClient {
+    /// Create a new LibrariesClient instance
+    pub fn new(workspace_url: &str, personal_access_token: &str) -> Self {
+        let base_url = format!("{}/api/2.0", workspace_url.trim_end_matches('/'));
+
+        Self {
+            base_url,
+            token: personal_access_token.to_string(),
+            user_agent: crate::DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+
+    /// Override the User-Agent sent with every request
+    pub fn with_user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = user_agent;
+        self
+    }
+
+    /// Get headers with authentication
+    fn get_headers(&self) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        headers.insert("Authorization".to_string(), format!("Bearer {}", self.token));
+        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert("User-Agent".to_string(), self.user_agent.clone());
+        headers
+    }
+
+    /// Install a library on a cluster
+    pub async fn install_library(&self, cluster_id: String, library_json: String) -> Result<String, String> {
+        let request = build_library_request(&cluster_id, &library_json)?;
+        self.post("install", &request)
+    }
+
+    /// Uninstall a library from a cluster; it is removed once the cluster restarts
+    pub async fn uninstall_library(&self, cluster_id: String, library_json: String) -> Result<String, String> {
+        let request = build_library_request(&cluster_id, &library_json)?;
+        self.post("uninstall", &request)
+    }
+
+    /// List the libraries on a cluster along with their install status
+    pub async fn list_libraries(&self, cluster_id: String) -> Result<String, String> {
+        let url = format!("{}/libraries/cluster-status", self.base_url);
+
+        let response = HttpClient::request(&url, HttpMethod::Get)
+            .headers(self.get_headers())
+            .query(vec![("cluster_id".to_string(), cluster_id)])
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if response.status() < 200 || response.status() >= 300 {
+            return Err(crate::api_error(response.status(), None));
+        }
+
+        let response_text = response.text();
+        Ok(response_text)
+    }
+
+    /// POST a request body to a libraries endpoint
+    fn post(&self, action: &str, request: &Value) -> Result<String, String> {
+        let url = format!("{}/libraries/{}", self.base_url, action);
+
+        let response = HttpClient::request(&url, HttpMethod::Post)
+            .headers(self.get_headers())
+            .json(request)
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if response.status() < 200 || response.status() >= 300 {
+            return Err(crate::api_error(response.status(), Some(response.text())));
+        }
+
+        let response_text = response.text();
+        Ok(response_text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The install body wraps the single validated spec for the given cluster.
+    #[test]
+    fn test_build_library_request() {
+        let body = build_library_request("0123-456789-abcde", r#"{"pypi": {"package": "pandas==2.2.0", "repo": "https://pypi.org/simple"}}"#).unwrap();
+        assert_eq!(body, serde_json::json!({
+            "cluster_id": "0123-456789-abcde",
+            "libraries": [{ "pypi": { "package": "pandas==2.2.0", "repo": "https://pypi.org/simple" } }]
+        }));
+        assert!(build_library_request("c", "not json").is_err());
+    }
+
+    /// Each supported type needs its own field; unknown types and mixed specs are refused.
+    #[test]
+    fn test_validate_library_spec() {
+        assert!(validate_library_spec(&serde_json::json!({ "maven": { "coordinates": "com.databricks:spark-xml_2.12:0.18.0" } })).is_ok());
+        assert!(validate_library_spec(&serde_json::json!({ "jar": "dbfs:/libs/udfs.jar" })).is_ok());
+        assert!(validate_library_spec(&serde_json::json!({ "whl": "/Workspace/libs/tool-1.0-py3-none-any.whl" })).is_ok());
+
+        assert!(validate_library_spec(&serde_json::json!({ "pypi": { "repo": "https://pypi.org/simple" } })).is_err());
+        assert!(validate_library_spec(&serde_json::json!({ "jar": "" })).is_err());
+        assert!(validate_library_spec(&serde_json::json!({ "cran": { "package": "ggplot2" } })).unwrap_err().contains("unsupported library type"));
+        assert!(validate_library_spec(&serde_json::json!({ "jar": "a.jar", "whl": "b.whl" })).is_err());
+        assert!(validate_library_spec(&serde_json::json!(["pypi"])).is_err());
+    }
+}
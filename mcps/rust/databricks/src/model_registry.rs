@@ -6,6 +6,7 @@ use weil_rs::http::{HttpClient, HttpMethod};
 pub struct ModelRegistryClient {
     base_url: String,
     token: String,
+    user_agent: String,
 }
 
 impl ModelRegistryClient {
@@ -16,37 +17,50 @@ impl ModelRegistryClient {
         Self {
             base_url,
             token: personal_access_token.to_string(),
+            user_agent: crate::DEFAULT_USER_AGENT.to_string(),
         }
     }
 
+    /// Override the User-Agent sent with every request
+    pub fn with_user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = user_agent;
+        self
+    }
+
     /// Get headers with authentication
     fn get_headers(&self) -> HashMap<String, String> {
         let mut headers = HashMap::new();
         headers.insert("Authorization".to_string(), format!("Bearer {}", self.token));
         headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert("User-Agent".to_string(), self.user_agent.clone());
         headers
     }
 
-    /// List all registered models
-    pub async fn list_registered_models(&self) -> Result<String, String> {
+    /// List registered models, following `next_page_token` up to `max_results` models
+    pub async fn list_registered_models(&self, max_results: Option<u32>) -> Result<String, String> {
         let url = format!("{}/registered-models/search", self.base_url);
-        
-        let request = serde_json::json!({
-            "max_results": 100
-        });
-        
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(self.get_headers())
-            .json(&request)
-            .send()
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
-        }
 
-        let response_text = response.text();
-        Ok(response_text)
+        crate::collect_token_pages("registered_models", max_results, |page_token, page_size| {
+            let mut request = serde_json::json!({
+                "max_results": page_size
+            });
+            if let Some(page_token) = page_token {
+                request["page_token"] = page_token.into();
+            }
+
+            let response = HttpClient::request(&url, HttpMethod::Post)
+                .headers(self.get_headers())
+                .json(&request)
+                .send()
+                .map_err(|e| format!("Request failed: {}", e))?;
+
+            if response.status() < 200 || response.status() >= 300 {
+                return Err(crate::api_error(response.status(), None));
+            }
+
+            let response_text = response.text();
+            Ok(response_text)
+        })
     }
 
     /// Get details of a specific registered model
@@ -64,7 +78,7 @@ impl ModelRegistryClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -90,7 +104,7 @@ impl ModelRegistryClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -113,7 +127,7 @@ impl ModelRegistryClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -136,7 +150,7 @@ impl ModelRegistryClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -160,7 +174,7 @@ impl ModelRegistryClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -182,7 +196,7 @@ impl ModelRegistryClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
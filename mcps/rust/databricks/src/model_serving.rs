@@ -2,10 +2,109 @@ use serde_json;
 use std::collections::HashMap;
 use weil_rs::http::{HttpClient, HttpMethod};
 
+/// Tabular input formats accepted by model serving endpoints that are checked client-side
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServingInputFormat {
+    /// `{"dataframe_split": {"columns": [...], "data": [[...], ...]}}`
+    DataframeSplit,
+    /// `{"instances": [...]}` (TensorFlow serving style)
+    Instances,
+}
+
+/// Detect which recognised format a serving request body uses, checking its shape so that a
+/// malformed payload fails here with a precise message instead of as an opaque HTTP 400.
+pub fn validate_serving_input(data: &serde_json::Value) -> Result<ServingInputFormat, String> {
+    let object = data.as_object()
+        .ok_or_else(|| "Serving input must be a JSON object with a \"dataframe_split\" or \"instances\" key".to_string())?;
+
+    match (object.get("dataframe_split"), object.get("instances")) {
+        (Some(_), Some(_)) => Err("Serving input must use only one of \"dataframe_split\" and \"instances\"".to_string()),
+        (Some(split), None) => {
+            let columns = split.get("columns").and_then(|columns| columns.as_array())
+                .ok_or_else(|| "dataframe_split.columns must be an array of column names".to_string())?;
+            if let Some(position) = columns.iter().position(|column| !column.is_string()) {
+                return Err(format!("dataframe_split.columns[{}] must be a string", position));
+            }
+            let rows = split.get("data").and_then(|data| data.as_array())
+                .ok_or_else(|| "dataframe_split.data must be an array of rows".to_string())?;
+            for (index, row) in rows.iter().enumerate() {
+                let values = row.as_array()
+                    .ok_or_else(|| format!("dataframe_split.data[{}] must be an array", index))?;
+                if values.len() != columns.len() {
+                    return Err(format!("dataframe_split.data[{}] has {} values but there are {} columns", index, values.len(), columns.len()));
+                }
+            }
+            Ok(ServingInputFormat::DataframeSplit)
+        }
+        (None, Some(instances)) => match instances.as_array() {
+            Some(instances) if !instances.is_empty() => Ok(ServingInputFormat::Instances),
+            Some(_) => Err("instances must not be empty".to_string()),
+            None => Err("instances must be an array".to_string()),
+        },
+        (None, None) => {
+            let keys = object.keys().map(String::as_str).collect::<Vec<_>>().join(", ");
+            Err(format!("Serving input has neither \"dataframe_split\" nor \"instances\" (found keys: {})", keys))
+        }
+    }
+}
+
+/// Renewal periods a serving endpoint rate limit can use, as (seconds, API name)
+pub const RATE_LIMIT_PERIODS: &[(u32, &str)] = &[(60, "minute")];
+
+/// Build one AI Gateway rate limit allowing `calls` every `per_seconds`.
+///
+/// Without a principal the limit applies to the whole endpoint. A principal containing `@` is
+/// taken as a user, one shaped like a UUID as a service principal (application id), and any
+/// other name as a group.
+pub fn build_rate_limit(calls: u32, per_seconds: u32, principal: Option<&str>) -> Result<serde_json::Value, String> {
+    if calls == 0 {
+        return Err("calls must be at least 1".to_string());
+    }
+    let renewal_period = RATE_LIMIT_PERIODS.iter()
+        .find(|(seconds, _)| *seconds == per_seconds)
+        .map(|(_, period)| *period)
+        .ok_or_else(|| {
+            let allowed = RATE_LIMIT_PERIODS.iter().map(|(seconds, _)| seconds.to_string()).collect::<Vec<_>>().join(", ");
+            format!("Unsupported per_seconds {}: rate limits renew every {} seconds", per_seconds, allowed)
+        })?;
+
+    let mut limit = serde_json::json!({ "calls": calls, "renewal_period": renewal_period });
+    match principal.map(str::trim) {
+        None => limit["key"] = "endpoint".into(),
+        Some("") => return Err("principal must not be empty".to_string()),
+        Some(principal) => {
+            let is_uuid = principal.len() == 36
+                && principal.chars().enumerate().all(|(index, c)| match index {
+                    8 | 13 | 18 | 23 => c == '-',
+                    _ => c.is_ascii_hexdigit(),
+                });
+            let key = if principal.contains('@') { "user" } else if is_uuid { "service_principal" } else { "user_group" };
+            limit["key"] = key.into();
+            limit["principal"] = principal.into();
+        }
+    }
+    Ok(limit)
+}
+
+/// The endpoint's AI Gateway config with `limit` added, replacing any rate limit for the same
+/// key and principal, so guardrails and other limits survive the update.
+pub fn merge_rate_limit(ai_gateway: &serde_json::Value, limit: serde_json::Value) -> serde_json::Value {
+    let mut gateway = match ai_gateway {
+        serde_json::Value::Object(_) => ai_gateway.clone(),
+        _ => serde_json::json!({}),
+    };
+    let mut limits: Vec<serde_json::Value> = gateway["rate_limits"].as_array().cloned().unwrap_or_default();
+    limits.retain(|existing| existing["key"] != limit["key"] || existing["principal"] != limit["principal"]);
+    limits.push(limit);
+    gateway["rate_limits"] = serde_json::Value::Array(limits);
+    gateway
+}
+
 /// Model Serving functions for Databricks
 pub struct ModelServingClient {
     base_url: String,
     token: String,
+    user_agent: String,
 }
 
 impl ModelServingClient {
@@ -16,14 +115,22 @@ impl ModelServingClient {
         Self {
             base_url,
             token: personal_access_token.to_string(),
+            user_agent: crate::DEFAULT_USER_AGENT.to_string(),
         }
     }
 
+    /// Override the User-Agent sent with every request
+    pub fn with_user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = user_agent;
+        self
+    }
+
     /// Get headers with authentication
     fn get_headers(&self) -> HashMap<String, String> {
         let mut headers = HashMap::new();
         headers.insert("Authorization".to_string(), format!("Bearer {}", self.token));
         headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert("User-Agent".to_string(), self.user_agent.clone());
         headers
     }
 
@@ -37,7 +144,7 @@ impl ModelServingClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -54,7 +161,7 @@ impl ModelServingClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -77,7 +184,7 @@ impl ModelServingClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -99,7 +206,7 @@ impl ModelServingClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -116,7 +223,7 @@ impl ModelServingClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -137,7 +244,29 @@ impl ModelServingClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
+        }
+
+        let response_text = response.text();
+        Ok(response_text)
+    }
+
+    /// Set a rate limit on a serving endpoint through its AI Gateway config, keeping the rest of
+    /// the current config
+    pub async fn set_endpoint_rate_limit(&self, name: String, limit: serde_json::Value) -> Result<String, String> {
+        let endpoint: serde_json::Value = serde_json::from_str(&self.get_serving_endpoint(name.clone()).await?)
+            .map_err(|e| format!("Failed to parse serving endpoint: {}", e))?;
+        let request = merge_rate_limit(&endpoint["ai_gateway"], limit);
+
+        let url = format!("{}/serving-endpoints/{}/ai-gateway", self.base_url, name);
+        let response = HttpClient::request(&url, HttpMethod::Put)
+            .headers(self.get_headers())
+            .json(&request)
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if response.status() < 200 || response.status() >= 300 {
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -155,10 +284,69 @@ impl ModelServingClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
         Ok(response_text)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Both recognised formats are detected when well formed.
+    #[test]
+    fn test_validate_serving_input_formats() {
+        let split = serde_json::json!({
+            "dataframe_split": { "columns": ["age", "income"], "data": [[34, 52000], [51, 87000]] }
+        });
+        assert_eq!(validate_serving_input(&split), Ok(ServingInputFormat::DataframeSplit));
+
+        let instances = serde_json::json!({ "instances": [{ "age": 34 }, { "age": 51 }] });
+        assert_eq!(validate_serving_input(&instances), Ok(ServingInputFormat::Instances));
+    }
+
+    /// Payloads matching neither format, or malformed versions of them, fail with a precise message.
+    #[test]
+    fn test_validate_serving_input_rejects_invalid() {
+        let ragged = serde_json::json!({ "dataframe_split": { "columns": ["age", "income"], "data": [[34]] } });
+        assert_eq!(validate_serving_input(&ragged).unwrap_err(), "dataframe_split.data[0] has 1 values but there are 2 columns");
+
+        let unknown = serde_json::json!({ "rows": [[1, 2]] });
+        assert!(validate_serving_input(&unknown).unwrap_err().contains("found keys: rows"));
+
+        assert_eq!(validate_serving_input(&serde_json::json!({ "instances": [] })).unwrap_err(), "instances must not be empty");
+        assert!(validate_serving_input(&serde_json::json!([1, 2])).is_err());
+    }
+
+    /// Rate limits are keyed by the kind of principal, and replace an existing limit for the same
+    /// principal while leaving the rest of the gateway config alone.
+    #[test]
+    fn test_rate_limit_body() {
+        assert_eq!(build_rate_limit(100, 60, None).unwrap(), serde_json::json!({ "calls": 100, "renewal_period": "minute", "key": "endpoint" }));
+        assert_eq!(build_rate_limit(5, 60, Some("ana@example.com")).unwrap()["key"], "user");
+        assert_eq!(build_rate_limit(5, 60, Some("6f1e9c4a-2b3d-4e5f-8a9b-0c1d2e3f4a5b")).unwrap()["key"], "service_principal");
+        assert_eq!(build_rate_limit(5, 60, Some("data-science")).unwrap()["key"], "user_group");
+        assert!(build_rate_limit(5, 3600, None).unwrap_err().contains("every 60 seconds"));
+        assert!(build_rate_limit(0, 60, None).is_err());
+
+        let gateway = serde_json::json!({
+            "guardrails": { "input": { "pii": { "behavior": "BLOCK" } } },
+            "rate_limits": [
+                { "calls": 10, "key": "endpoint", "renewal_period": "minute" },
+                { "calls": 2, "key": "user", "principal": "ana@example.com", "renewal_period": "minute" }
+            ]
+        });
+        let merged = merge_rate_limit(&gateway, build_rate_limit(50, 60, None).unwrap());
+        assert_eq!(merged["guardrails"], gateway["guardrails"]);
+        let limits = merged["rate_limits"].as_array().unwrap();
+        assert_eq!(limits.len(), 2);
+        assert_eq!(limits[0]["principal"], "ana@example.com");
+        assert_eq!(limits[1]["calls"], 50);
+
+        let fresh = merge_rate_limit(&serde_json::Value::Null, build_rate_limit(1, 60, None).unwrap());
+        assert_eq!(fresh["rate_limits"].as_array().unwrap().len(), 1);
+    }
+}
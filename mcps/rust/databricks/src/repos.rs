@@ -0,0 +1,234 @@
+use serde::{Deserialize, Serialize};
+use mcp_http::ids::parse_id;
+use serde_json::Value;
+use std::collections::HashMap;
+use weil_macros::WeilType;
+use weil_rs::http::{HttpClient, HttpMethod};
+
+/// Git providers the repos API accepts, in the casing it expects
+const GIT_PROVIDERS: [&str; 8] = [
+    "gitHub",
+    "gitHubEnterprise",
+    "bitbucketCloud",
+    "bitbucketServer",
+    "gitLab",
+    "gitLabEnterpriseEdition",
+    "azureDevOpsServices",
+    "awsCodeCommit",
+];
+
+/// A Git folder (repo) in the workspace
+#[derive(Debug, Serialize, Deserialize, WeilType, PartialEq)]
+pub struct RepoInfo {
+    pub id: u64,
+    pub url: String,
+    /// Checked-out branch; absent when a tag or detached commit is checked out
+    #[serde(default)]
+    pub branch: Option<String>,
+    #[serde(default)]
+    pub head_commit_id: Option<String>,
+    /// Workspace path, e.g. `/Repos/someone@example.com/etl`
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RepoList {
+    #[serde(default)]
+    repos: Vec<RepoInfo>,
+}
+
+/// The provider's canonical name, matched case-insensitively, e.g. `github` -> `gitHub`
+pub fn normalize_git_provider(provider: &str) -> Result<&'static str, String> {
+    GIT_PROVIDERS
+        .iter()
+        .find(|known| known.eq_ignore_ascii_case(provider.trim()))
+        .copied()
+        .ok_or_else(|| format!("unsupported git provider '{}', expected one of: {}", provider, GIT_PROVIDERS.join(", ")))
+}
+
+/// Repo ids are numbers; parsing keeps anything else out of the request path
+fn parse_repo_id(repo_id: &str) -> Result<String, String> {
+    parse_id(repo_id)
+        .map(|id| id.to_string())
+        .map_err(|_| format!("repo_id must be a number, got '{}'", repo_id))
+}
+
+/// Body of `repos/create`; without a path the repo is created under the caller's `/Repos` folder
+pub fn build_create_repo_request(url: &str, provider: &str, path: Option<&str>) -> Result<Value, String> {
+    if url.trim().is_empty() {
+        return Err("url must not be empty".to_string());
+    }
+    let mut request = serde_json::json!({
+        "url": url.trim(),
+        "provider": normalize_git_provider(provider)?
+    });
+    if let Some(path) = path.map(str::trim).filter(|path| !path.is_empty()) {
+        if !path.starts_with('/') {
+            return Err(format!("repo path must be absolute, got '{}'", path));
+        }
+        request["path"] = path.into();
+    }
+    Ok(request)
+}
+
+/// Body of the repo PATCH that checks out `branch` or `tag`; the API takes exactly one of them
+pub fn build_update_repo_request(branch: Option<&str>, tag: Option<&str>) -> Result<Value, String> {
+    let branch = branch.map(str::trim).filter(|branch| !branch.is_empty());
+    let tag = tag.map(str::trim).filter(|tag| !tag.is_empty());
+    match (branch, tag) {
+        (Some(branch), None) => Ok(serde_json::json!({ "branch": branch })),
+        (None, Some(tag)) => Ok(serde_json::json!({ "tag": tag })),
+        (Some(_), Some(_)) => Err("give either branch or tag, not both".to_string()),
+        (None, None) => Err("give the branch or tag to check out".to_string()),
+    }
+}
+
+/// Parse a single repo, as returned by `repos/get`, `repos/create` and the update PATCH
+pub fn parse_repo(response: &str) -> Result<RepoInfo, String> {
+    serde_json::from_str(response).map_err(|e| format!("Failed to parse repo: {}", e))
+}
+
+/// Git folder (Repos) management for Databricks
+pub struct ReposClient {
+    base_url: String,
+    token: String,
+    user_agent: String,
+}
+
+impl ReposClient {
+    /// Create a new ReposClient instance
+    pub fn new(workspace_url: &str, personal_access_token: &str) -> Self {
+        let base_url = format!("{}/api/2.0", workspace_url.trim_end_matches('/'));
+
+        Self {
+            base_url,
+            token: personal_access_token.to_string(),
+            user_agent: crate::DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+
+    /// Override the User-Agent sent with every request
+    pub fn with_user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = user_agent;
+        self
+    }
+
+    /// Get headers with authentication
+    fn get_headers(&self) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        headers.insert("Authorization".to_string(), format!("Bearer {}", self.token));
+        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert("User-Agent".to_string(), self.user_agent.clone());
+        headers
+    }
+
+    /// List every repo in the workspace, following `next_page_token`
+    pub async fn list_repos(&self) -> Result<Vec<RepoInfo>, String> {
+        let listing = crate::collect_token_pages("repos", None, |page_token, _| {
+            let query = match page_token {
+                Some(page_token) => vec![("next_page_token".to_string(), page_token.to_string())],
+                None => Vec::new(),
+            };
+            self.send(HttpMethod::Get, "", query, None)
+        })?;
+        let list: RepoList = serde_json::from_str(&listing).map_err(|e| format!("Failed to parse repos: {}", e))?;
+        Ok(list.repos)
+    }
+
+    /// Clone a remote Git repository into the workspace
+    pub async fn create_repo(&self, url: String, provider: String, path: Option<String>) -> Result<RepoInfo, String> {
+        let request = build_create_repo_request(&url, &provider, path.as_deref())?;
+        parse_repo(&self.send(HttpMethod::Post, "", Vec::new(), Some(&request))?)
+    }
+
+    /// Get a repo by id
+    pub async fn get_repo(&self, repo_id: String) -> Result<RepoInfo, String> {
+        let repo_id = parse_repo_id(&repo_id)?;
+        parse_repo(&self.send(HttpMethod::Get, &repo_id, Vec::new(), None)?)
+    }
+
+    /// Check out a branch or tag, pulling it from the remote first
+    pub async fn update_repo(&self, repo_id: String, branch: Option<String>, tag: Option<String>) -> Result<RepoInfo, String> {
+        let repo_id = parse_repo_id(&repo_id)?;
+        let request = build_update_repo_request(branch.as_deref(), tag.as_deref())?;
+        parse_repo(&self.send(HttpMethod::Patch, &repo_id, Vec::new(), Some(&request))?)
+    }
+
+    /// Delete a repo from the workspace; the remote is not affected
+    pub async fn delete_repo(&self, repo_id: String) -> Result<String, String> {
+        let repo_id = parse_repo_id(&repo_id)?;
+        self.send(HttpMethod::Delete, &repo_id, Vec::new(), None)
+    }
+
+    /// Send a request to `repos` or, with a non-empty `repo_id`, to `repos/{repo_id}`
+    fn send(&self, method: HttpMethod, repo_id: &str, query: Vec<(String, String)>, body: Option<&Value>) -> Result<String, String> {
+        let url = match repo_id {
+            "" => format!("{}/repos", self.base_url),
+            repo_id => format!("{}/repos/{}", self.base_url, repo_id),
+        };
+
+        let mut request = HttpClient::request(&url, method)
+            .headers(self.get_headers())
+            .query(query);
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let response = request.send().map_err(|e| format!("Request failed: {}", e))?;
+
+        if response.status() < 200 || response.status() >= 300 {
+            return Err(crate::api_error(response.status(), Some(response.text())));
+        }
+
+        let response_text = response.text();
+        Ok(response_text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Exactly one of branch and tag is sent; both or neither is refused.
+    #[test]
+    fn test_build_update_repo_request() {
+        assert_eq!(build_update_repo_request(Some("main"), None).unwrap(), serde_json::json!({ "branch": "main" }));
+        assert_eq!(build_update_repo_request(Some(" "), Some("v1.2.0")).unwrap(), serde_json::json!({ "tag": "v1.2.0" }));
+        assert!(build_update_repo_request(Some("main"), Some("v1.2.0")).unwrap_err().contains("not both"));
+        assert!(build_update_repo_request(None, None).is_err());
+    }
+
+    /// Providers are matched case-insensitively and sent in the API's casing, with the path
+    /// only when given.
+    #[test]
+    fn test_build_create_repo_request() {
+        let request = build_create_repo_request("https://github.com/acme/etl.git", "github", Some("/Repos/ci/etl")).unwrap();
+        assert_eq!(request, serde_json::json!({
+            "url": "https://github.com/acme/etl.git",
+            "provider": "gitHub",
+            "path": "/Repos/ci/etl"
+        }));
+        let request = build_create_repo_request("https://dev.azure.com/acme/_git/etl", "AZUREDEVOPSSERVICES", None).unwrap();
+        assert_eq!(request["provider"], "azureDevOpsServices");
+        assert!(request.get("path").is_none());
+
+        assert!(build_create_repo_request("https://svn.acme.com/etl", "svn", None).unwrap_err().contains("unsupported git provider"));
+        assert!(build_create_repo_request("https://github.com/acme/etl.git", "gitHub", Some("Repos/etl")).is_err());
+        assert!(parse_repo_id("../clusters").is_err());
+        assert_eq!(parse_repo_id(" 42 ").unwrap(), "42");
+    }
+
+    /// Repos parse with their checked-out branch and head commit; a tag checkout has no branch.
+    #[test]
+    fn test_parse_repo() {
+        let repo = parse_repo(r#"{"id": 123, "url": "https://github.com/acme/etl.git", "provider": "gitHub", "path": "/Repos/ci/etl", "branch": "main", "head_commit_id": "7e0847e"}"#).unwrap();
+        assert_eq!(repo, RepoInfo {
+            id: 123,
+            url: "https://github.com/acme/etl.git".to_string(),
+            branch: Some("main".to_string()),
+            head_commit_id: Some("7e0847e".to_string()),
+            path: Some("/Repos/ci/etl".to_string()),
+        });
+        assert_eq!(parse_repo(r#"{"id": 124, "url": "u", "head_commit_id": "abc"}"#).unwrap().branch, None);
+    }
+}
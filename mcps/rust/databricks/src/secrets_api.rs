@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use weil_macros::WeilType;
+use weil_rs::http::{HttpClient, HttpMethod};
+
+/// Longest scope or key name the secrets API accepts
+const MAX_SECRET_NAME_LEN: usize = 128;
+
+/// A secret scope in the workspace
+#[derive(Debug, Serialize, Deserialize, WeilType, PartialEq)]
+pub struct SecretScope {
+    pub name: String,
+    /// `DATABRICKS` for workspace-managed scopes, `AZURE_KEYVAULT` for Key Vault-backed ones
+    #[serde(default)]
+    pub backend_type: Option<String>,
+}
+
+/// A secret as listed by the API: its key and when it last changed, never its value
+#[derive(Debug, Serialize, Deserialize, WeilType, PartialEq)]
+pub struct SecretMetadata {
+    pub key: String,
+    /// Milliseconds since the epoch
+    #[serde(default)]
+    pub last_updated_timestamp: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct ScopeList {
+    #[serde(default)]
+    scopes: Vec<SecretScope>,
+}
+
+#[derive(Deserialize)]
+struct SecretList {
+    #[serde(default)]
+    secrets: Vec<SecretMetadata>,
+}
+
+/// Check a scope or key name: 1-128 characters from letters, digits, `-`, `_` and `.`
+pub fn validate_secret_name(kind: &str, name: &str) -> Result<(), String> {
+    let valid_chars = name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if name.is_empty() || name.len() > MAX_SECRET_NAME_LEN || !valid_chars {
+        return Err(format!("invalid secret {} '{}': use 1-{} letters, digits, '-', '_' or '.'", kind, name, MAX_SECRET_NAME_LEN));
+    }
+    Ok(())
+}
+
+/// Body of `secrets/put`. The value goes in `string_value` as plain UTF-8 text; the API only
+/// expects base64 in `bytes_value`, which is for binary secrets.
+pub fn build_put_secret_request(scope: &str, key: &str, value: &str) -> Result<Value, String> {
+    validate_secret_name("scope", scope)?;
+    validate_secret_name("key", key)?;
+    Ok(serde_json::json!({
+        "scope": scope,
+        "key": key,
+        "string_value": value
+    }))
+}
+
+/// Parse a `secrets/scopes/list` response; `scopes` is omitted when there are none
+pub fn parse_secret_scopes(response: &str) -> Result<Vec<SecretScope>, String> {
+    let list: ScopeList = serde_json::from_str(response).map_err(|e| format!("Failed to parse secret scopes: {}", e))?;
+    Ok(list.scopes)
+}
+
+/// Parse a `secrets/list` response; `secrets` is omitted when the scope is empty
+pub fn parse_secret_list(response: &str) -> Result<Vec<SecretMetadata>, String> {
+    let list: SecretList = serde_json::from_str(response).map_err(|e| format!("Failed to parse secrets: {}", e))?;
+    Ok(list.secrets)
+}
+
+/// Secret scope and secret management for Databricks
+pub struct SecretsClient {
+    base_url: String,
+    token: String,
+    user_agent: String,
+}
+
+impl SecretsClient {
+    /// Create a new SecretsClient instance
+    pub fn new(workspace_url: &str, personal_access_token: &str) -> Self {
+        let base_url = format!("{}/api/2.0", workspace_url.trim_end_matches('/'));
+
+        Self {
+            base_url,
+            token: personal_access_token.to_string(),
+            user_agent: crate::DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+
+    /// Override the User-Agent sent with every request
+    pub fn with_user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = user_agent;
+        self
+    }
+
+    /// Get headers with authentication
+    fn get_headers(&self) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        headers.insert("Authorization".to_string(), format!("Bearer {}", self.token));
+        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert("User-Agent".to_string(), self.user_agent.clone());
+        headers
+    }
+
+    /// Create a workspace-managed secret scope
+    pub async fn create_secret_scope(&self, name: String) -> Result<String, String> {
+        validate_secret_name("scope", &name)?;
+        self.post("scopes/create", &serde_json::json!({ "scope": name }))
+    }
+
+    /// List all secret scopes
+    pub async fn list_secret_scopes(&self) -> Result<Vec<SecretScope>, String> {
+        parse_secret_scopes(&self.get("scopes/list", Vec::new())?)
+    }
+
+    /// Create or overwrite a secret
+    pub async fn put_secret(&self, scope: String, key: String, value: String) -> Result<String, String> {
+        let request = build_put_secret_request(&scope, &key, &value)?;
+        self.post("put", &request)
+    }
+
+    /// List the keys in a scope with their last-updated timestamps
+    pub async fn list_secrets(&self, scope: String) -> Result<Vec<SecretMetadata>, String> {
+        validate_secret_name("scope", &scope)?;
+        parse_secret_list(&self.get("list", vec![("scope".to_string(), scope)])?)
+    }
+
+    /// Delete a secret from a scope
+    pub async fn delete_secret(&self, scope: String, key: String) -> Result<String, String> {
+        validate_secret_name("scope", &scope)?;
+        validate_secret_name("key", &key)?;
+        self.post("delete", &serde_json::json!({ "scope": scope, "key": key }))
+    }
+
+    /// GET a secrets endpoint
+    fn get(&self, action: &str, query: Vec<(String, String)>) -> Result<String, String> {
+        let url = format!("{}/secrets/{}", self.base_url, action);
+
+        let response = HttpClient::request(&url, HttpMethod::Get)
+            .headers(self.get_headers())
+            .query(query)
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if response.status() < 200 || response.status() >= 300 {
+            return Err(crate::api_error(response.status(), Some(response.text())));
+        }
+
+        let response_text = response.text();
+        Ok(response_text)
+    }
+
+    /// POST a request body to a secrets endpoint
+    fn post(&self, action: &str, request: &Value) -> Result<String, String> {
+        let url = format!("{}/secrets/{}", self.base_url, action);
+
+        let response = HttpClient::request(&url, HttpMethod::Post)
+            .headers(self.get_headers())
+            .json(request)
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if response.status() < 200 || response.status() >= 300 {
+            return Err(crate::api_error(response.status(), Some(response.text())));
+        }
+
+        let response_text = response.text();
+        Ok(response_text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The value is sent verbatim as string_value, and bad scope or key names are refused.
+    #[test]
+    fn test_build_put_secret_request() {
+        let body = build_put_secret_request("etl-prod", "db.password", "s3cr3t/+=").unwrap();
+        assert_eq!(body, serde_json::json!({ "scope": "etl-prod", "key": "db.password", "string_value": "s3cr3t/+=" }));
+        assert!(body.get("bytes_value").is_none());
+
+        assert!(build_put_secret_request("etl prod", "k", "v").unwrap_err().contains("scope"));
+        assert!(build_put_secret_request("etl", "", "v").unwrap_err().contains("key"));
+        assert!(validate_secret_name("scope", &"a".repeat(129)).is_err());
+        assert!(validate_secret_name("scope", &"a".repeat(128)).is_ok());
+    }
+
+    /// Listings carry keys and timestamps only, and empty responses list nothing.
+    #[test]
+    fn test_parse_secret_listings() {
+        let secrets = parse_secret_list(r#"{"secrets": [{"key": "db.password", "last_updated_timestamp": 1700000000000}]}"#).unwrap();
+        assert_eq!(secrets, vec![SecretMetadata { key: "db.password".to_string(), last_updated_timestamp: Some(1700000000000) }]);
+        assert!(parse_secret_list("{}").unwrap().is_empty());
+
+        let scopes = parse_secret_scopes(r#"{"scopes": [{"name": "etl-prod", "backend_type": "DATABRICKS"}]}"#).unwrap();
+        assert_eq!(scopes[0].backend_type.as_deref(), Some("DATABRICKS"));
+        assert!(parse_secret_scopes("{}").unwrap().is_empty());
+    }
+}
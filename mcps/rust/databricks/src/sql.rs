@@ -1,11 +1,407 @@
+use mcp_http::ids::parse_id;
+use serde::{Deserialize, Serialize};
 use serde_json;
+use serde_json::Value;
 use std::collections::HashMap;
+use weil_macros::WeilType;
+use weil_rs::errors::ErrorCode;
 use weil_rs::http::{HttpClient, HttpMethod};
 
+/// Status checks `execute_sql_all` makes after the submit call's own wait. The runtime has no
+/// timer, so these are sent back to back.
+const STATEMENT_STATUS_POLLS: u32 = 30;
+
+/// A result column as described by the statement manifest
+#[derive(Debug, Serialize, Deserialize, WeilType, PartialEq)]
+pub struct ColumnSchema {
+    pub name: String,
+    /// SQL type as written in DDL, e.g. `STRING`, `DECIMAL(10,2)`
+    pub type_text: String,
+}
+
+/// Every row of a statement's result, gathered from all of its chunks
+#[derive(Debug, Serialize, Deserialize, WeilType, PartialEq)]
+pub struct SqlResult {
+    pub columns: Vec<ColumnSchema>,
+    /// Values rendered as strings by the API; `None` for SQL NULL
+    pub rows: Vec<Vec<Option<String>>>,
+}
+
+/// Row count and storage footprint of a table
+#[derive(Debug, Serialize, Deserialize, WeilType, PartialEq)]
+pub struct TableStats {
+    pub full_table_name: String,
+    pub row_count: u64,
+    /// Total size of the table's current files; `None` when `DESCRIBE DETAIL` doesn't report it
+    pub size_in_bytes: Option<u64>,
+    pub num_files: Option<u64>,
+    /// Storage format, e.g. `delta`
+    pub format: Option<String>,
+}
+
+/// One statement from the SQL query history
+#[derive(Debug, Serialize, Deserialize, WeilType, PartialEq)]
+pub struct QueryHistoryEntry {
+    pub query_id: String,
+    /// e.g. `QUEUED`, `RUNNING`, `FINISHED`, `FAILED` or `CANCELED`
+    pub status: Option<String>,
+    pub query_text: Option<String>,
+    /// Wall-clock time of the execution; `None` while it is still running
+    pub duration_ms: Option<u64>,
+    pub rows_produced: Option<u64>,
+    /// Name of the user who ran the statement
+    pub executed_by: Option<String>,
+}
+
+/// When a SQL alert triggers: the query result `column` compared with `op` against `threshold`
+#[derive(Debug, Serialize, Deserialize, WeilType, PartialEq)]
+pub struct AlertOptions {
+    pub column: String,
+    /// One of `>`, `<`, `>=`, `<=`, `==`, `!=`
+    pub op: String,
+    /// Numeric value, given as text
+    pub threshold: String,
+    /// Rearm count; 0 for no rearm
+    pub rearm: i32,
+}
+
+/// Body of a `preview/sql/alerts` request; the threshold is sent as a number, as the API expects
+pub fn build_alert_request(name: &str, query_id: &str, options: &AlertOptions) -> Result<Value, String> {
+    let threshold: f64 = options.threshold.trim().parse()
+        .map_err(|_| format!("Invalid threshold value: {}", options.threshold))?;
+    Ok(serde_json::json!({
+        "name": name,
+        "query_id": query_id,
+        "options": {
+            "column": options.column,
+            "op": options.op,
+            "value": threshold
+        },
+        "rearm": options.rearm
+    }))
+}
+
+/// Whether the statement has succeeded (`true`) or is still `PENDING`/`RUNNING` (`false`);
+/// `FAILED`, `CANCELED` and `CLOSED` statements are errors carrying the statement's message.
+pub fn statement_succeeded(statement: &Value) -> Result<bool, String> {
+    let status = &statement["status"];
+    match status["state"].as_str().unwrap_or_default() {
+        "SUCCEEDED" => Ok(true),
+        "PENDING" | "RUNNING" => Ok(false),
+        state => {
+            let message = status["error"]["message"].as_str().unwrap_or("no error message");
+            Err(format!("Statement {} {}: {}", statement["statement_id"].as_str().unwrap_or_default(), state, message))
+        }
+    }
+}
+
+/// Columns listed in a statement's `manifest.schema.columns`
+pub fn parse_column_schema(statement: &Value) -> Vec<ColumnSchema> {
+    statement["manifest"]["schema"]["columns"]
+        .as_array()
+        .map(|columns| {
+            columns
+                .iter()
+                .map(|column| ColumnSchema {
+                    name: column["name"].as_str().unwrap_or_default().to_string(),
+                    type_text: column["type_text"].as_str().unwrap_or_default().to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Rows of one result chunk's `data_array`
+fn chunk_rows(chunk: &Value) -> Vec<Vec<Option<String>>> {
+    chunk["data_array"]
+        .as_array()
+        .map(|rows| {
+            rows.iter()
+                .map(|row| row.as_array().map(|values| values.iter().map(|value| value.as_str().map(str::to_string)).collect()).unwrap_or_default())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Assemble a succeeded statement's result: the first chunk comes inline in `statement.result`,
+/// and each `next_chunk_index` is fetched through `fetch_chunk` until there is none.
+pub fn assemble_sql_result<F: FnMut(u64) -> Result<String, String>>(statement: &Value, mut fetch_chunk: F) -> Result<SqlResult, String> {
+    let mut rows = Vec::new();
+    let mut chunk = statement["result"].clone();
+    loop {
+        rows.extend(chunk_rows(&chunk));
+        match chunk["next_chunk_index"].as_u64() {
+            Some(index) => {
+                chunk = serde_json::from_str(&fetch_chunk(index)?).map_err(|e| format!("Failed to parse result chunk {}: {}", index, e))?;
+            }
+            None => break,
+        }
+    }
+    Ok(SqlResult { columns: parse_column_schema(statement), rows })
+}
+
+/// Check that `full_table_name` is `catalog.schema.table` and return it with each part
+/// backquoted, ready to splice into a statement. Table names can't be statement parameters, so
+/// each part is limited to letters, digits, `_` and `-`.
+pub fn quote_table_name(full_table_name: &str) -> Result<String, String> {
+    let parts: Vec<&str> = full_table_name.split('.').collect();
+    let valid_part = |part: &&str| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if parts.len() != 3 || !parts.iter().all(valid_part) {
+        return Err(ErrorCode::Validation.tag(format!("Invalid table name '{}': expected catalog.schema.table", full_table_name)));
+    }
+    Ok(parts.iter().map(|part| format!("`{}`", part)).collect::<Vec<_>>().join("."))
+}
+
+/// First-row value of `column` in a statement result
+fn first_row_value<'a>(result: &'a SqlResult, column: &str) -> Option<&'a str> {
+    let index = result.columns.iter().position(|schema| schema.name == column)?;
+    result.rows.first()?.get(index)?.as_deref()
+}
+
+/// Combine the result of `SELECT COUNT(*)` (a single value) with the result of `DESCRIBE DETAIL`
+pub fn assemble_table_stats(full_table_name: &str, count: &SqlResult, detail: &SqlResult) -> Result<TableStats, String> {
+    let row_count = count.rows.first().and_then(|row| row.first()).and_then(|value| value.as_deref())
+        .ok_or_else(|| format!("COUNT(*) on {} returned no value", full_table_name))?;
+    let row_count = row_count.parse().map_err(|e| format!("Invalid row count '{}' for {}: {}", row_count, full_table_name, e))?;
+    Ok(TableStats {
+        full_table_name: full_table_name.to_string(),
+        row_count,
+        size_in_bytes: first_row_value(detail, "sizeInBytes").and_then(|value| value.parse().ok()),
+        num_files: first_row_value(detail, "numFiles").and_then(|value| value.parse().ok()),
+        format: first_row_value(detail, "format").map(str::to_string),
+    })
+}
+
+/// Columns of `system.billing.usage` that usage can be grouped by
+pub const USAGE_GROUP_BY_COLUMNS: &[&str] = &[
+    "usage_date",
+    "workspace_id",
+    "sku_name",
+    "cloud",
+    "billing_origin_product",
+    "usage_type",
+];
+
+/// Check that `date` is a real calendar date in `YYYY-MM-DD` form
+fn validate_date(date: &str) -> Result<(), String> {
+    let invalid = || format!("Invalid date '{}': expected YYYY-MM-DD", date);
+    let parts: Vec<&str> = date.split('-').collect();
+    if parts.len() != 3 || parts[0].len() != 4 || parts[1].len() != 2 || parts[2].len() != 2 {
+        return Err(invalid());
+    }
+    if !parts.iter().all(|part| part.chars().all(|c| c.is_ascii_digit())) {
+        return Err(invalid());
+    }
+    let year: u32 = parts[0].parse().map_err(|_| invalid())?;
+    let month: u32 = parts[1].parse().map_err(|_| invalid())?;
+    let day: u32 = parts[2].parse().map_err(|_| invalid())?;
+    let leap = (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400);
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return Err(invalid()),
+    };
+    if day == 0 || day > days_in_month {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
+/// Build the DBU usage query over `system.billing.usage` for an inclusive date range.
+///
+/// The dates are bound as `:start_date`/`:end_date` statement parameters; the grouping column
+/// cannot be a parameter, so it is checked against `USAGE_GROUP_BY_COLUMNS` instead.
+/// Returns the statement and its parameters.
+pub fn build_usage_query(start_date: &str, end_date: &str, group_by: Option<&str>) -> Result<(String, Vec<serde_json::Value>), String> {
+    validate_date(start_date)?;
+    validate_date(end_date)?;
+    // zero-padded ISO dates order the same as strings
+    if start_date > end_date {
+        return Err(format!("start_date {} is after end_date {}", start_date, end_date));
+    }
+
+    let filter = "FROM system.billing.usage WHERE usage_unit = 'DBU' AND usage_date BETWEEN :start_date AND :end_date";
+    let statement = match group_by.map(str::trim).filter(|column| !column.is_empty()) {
+        Some(column) => {
+            if !USAGE_GROUP_BY_COLUMNS.contains(&column) {
+                return Err(format!("Unsupported group_by '{}': expected one of {}", column, USAGE_GROUP_BY_COLUMNS.join(", ")));
+            }
+            format!("SELECT {column}, SUM(usage_quantity) AS total_dbus {filter} GROUP BY {column} ORDER BY total_dbus DESC")
+        }
+        None => format!("SELECT SUM(usage_quantity) AS total_dbus {filter}"),
+    };
+
+    let parameters = vec![
+        serde_json::json!({ "name": "start_date", "value": start_date, "type": "DATE" }),
+        serde_json::json!({ "name": "end_date", "value": end_date, "type": "DATE" }),
+    ];
+    Ok((statement, parameters))
+}
+
+/// Subscription payloads attaching each notification destination to an alert
+pub fn build_alert_subscriptions(alert_id: &str, notification_ids: &[String]) -> Vec<serde_json::Value> {
+    notification_ids
+        .iter()
+        .map(|destination_id| serde_json::json!({ "alert_id": alert_id, "destination_id": destination_id }))
+        .collect()
+}
+
+/// Statements `list_query_history` returns when not given `max_results`
+pub const DEFAULT_QUERY_HISTORY_LIMIT: u32 = 100;
+
+/// Body of a `sql/history/queries` request; the filter narrows the history to a warehouse and/or a user (by numeric id)
+pub fn build_query_history_request(warehouse_id: Option<&str>, user_id: Option<&str>, page_size: u32, page_token: Option<&str>) -> Result<Value, String> {
+    let mut filter_by = serde_json::Map::new();
+    if let Some(warehouse_id) = warehouse_id.map(str::trim).filter(|id| !id.is_empty()) {
+        filter_by.insert("warehouse_ids".to_string(), serde_json::json!([warehouse_id]));
+    }
+    if let Some(user_id) = user_id.map(str::trim).filter(|id| !id.is_empty()) {
+        let user_id = parse_id(user_id).map_err(|_| format!("user_id must be a number, got {:?}", user_id))?;
+        filter_by.insert("user_ids".to_string(), serde_json::json!([user_id]));
+    }
+
+    let mut request = serde_json::json!({ "filter_by": filter_by, "max_results": page_size });
+    if let Some(page_token) = page_token {
+        request["page_token"] = page_token.into();
+    }
+    Ok(request)
+}
+
+/// Read a query history entry; rows produced come from `metrics` when the top-level count is absent
+fn parse_query_history_entry(query: &Value) -> Result<QueryHistoryEntry, String> {
+    let text = |key: &str| query[key].as_str().map(str::to_string);
+    Ok(QueryHistoryEntry {
+        query_id: text("query_id").ok_or_else(|| format!("query history entry has no query_id: {}", query))?,
+        status: text("status"),
+        query_text: text("query_text"),
+        duration_ms: query["duration"].as_u64(),
+        rows_produced: query["rows_produced"].as_u64().or_else(|| query["metrics"]["rows_produced_count"].as_u64()),
+        executed_by: text("user_name").or_else(|| text("executed_as_user_name")),
+    })
+}
+
+/// Gather up to `max_results` statements from `sql/history/queries` pages fetched with `fetch`, which is given each
+/// page's request body
+pub fn collect_query_history<F>(warehouse_id: Option<&str>, user_id: Option<&str>, max_results: Option<u32>, mut fetch: F) -> Result<Vec<QueryHistoryEntry>, String>
+where
+    F: FnMut(Value) -> Result<String, String>,
+{
+    let max_results = max_results.unwrap_or(DEFAULT_QUERY_HISTORY_LIMIT);
+
+    let history = crate::collect_token_pages("res", Some(max_results), |page_token, page_size| {
+        let request = build_query_history_request(warehouse_id, user_id, page_size, page_token)?;
+        let mut page: Value = serde_json::from_str(&fetch(request)?).map_err(|e| format!("Failed to parse query history: {}", e))?;
+        // This API reports more pages with `has_next_page` rather than `has_more`
+        page["has_more"] = page["has_next_page"].as_bool().unwrap_or(false).into();
+        Ok(page.to_string())
+    })?;
+    let history: Value = serde_json::from_str(&history).map_err(|e| format!("Failed to parse query history: {}", e))?;
+    history["res"].as_array().map(Vec::as_slice).unwrap_or_default().iter().map(parse_query_history_entry).collect()
+}
+
+/// Keywords lowercased by `normalize_sql`; any other word keeps its case, as identifiers may be case-sensitive
+const SQL_KEYWORDS: &[&str] = &[
+    "all", "alter", "and", "as", "asc", "between", "by", "case", "create", "cross", "delete", "desc", "describe", "distinct",
+    "drop", "else", "end", "except", "exists", "false", "from", "full", "group", "having", "in", "inner", "insert", "intersect",
+    "into", "is", "join", "left", "like", "limit", "merge", "not", "null", "offset", "on", "or", "order", "outer", "over",
+    "partition", "right", "select", "set", "show", "table", "then", "true", "union", "update", "using", "values", "view",
+    "when", "where", "with",
+];
+
+/// Canonical form of a statement, so that statements differing only in layout compare equal: comments are dropped,
+/// whitespace runs become a single space and keywords are lowercased. String literals and quoted identifiers are kept
+/// as written.
+pub fn normalize_sql(sql: &str) -> String {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut normalized = String::with_capacity(sql.len());
+    let mut pending_space = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c.is_whitespace() {
+            pending_space = true;
+            i += 1;
+            continue;
+        }
+        if c == '-' && next == Some('-') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            pending_space = true;
+            continue;
+        }
+        if c == '/' && next == Some('*') {
+            // Bracketed comments nest in Databricks SQL
+            let mut depth = 0;
+            while i < chars.len() {
+                if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+                    depth += 1;
+                    i += 2;
+                } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+            pending_space = true;
+            continue;
+        }
+
+        if pending_space && !normalized.is_empty() {
+            normalized.push(' ');
+        }
+        pending_space = false;
+
+        if c == '\'' || c == '"' || c == '`' {
+            // Copy the quoted text through its closing quote; a backslash escapes the next character and a doubled quote is a literal quote
+            normalized.push(c);
+            i += 1;
+            while i < chars.len() {
+                normalized.push(chars[i]);
+                if chars[i] == '\\' && c != '`' {
+                    if let Some(&escaped) = chars.get(i + 1) {
+                        normalized.push(escaped);
+                    }
+                    i += 2;
+                } else if chars[i] == c && chars.get(i + 1) == Some(&c) {
+                    normalized.push(c);
+                    i += 2;
+                } else if chars[i] == c {
+                    i += 1;
+                    break;
+                } else {
+                    i += 1;
+                }
+            }
+        } else if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let lowercase = word.to_lowercase();
+            normalized.push_str(if SQL_KEYWORDS.contains(&lowercase.as_str()) { &lowercase } else { &word });
+        } else {
+            normalized.push(c);
+            i += 1;
+        }
+    }
+    normalized
+}
+
 /// SQL warehouse and query execution functions for Databricks
 pub struct SqlClient {
     base_url: String,
     token: String,
+    user_agent: String,
 }
 
 impl SqlClient {
@@ -16,27 +412,44 @@ impl SqlClient {
         Self {
             base_url,
             token: personal_access_token.to_string(),
+            user_agent: crate::DEFAULT_USER_AGENT.to_string(),
         }
     }
 
+    /// Override the User-Agent sent with every request
+    pub fn with_user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = user_agent;
+        self
+    }
+
     /// Get headers with authentication
-    fn get_headers(&self) -> HashMap<String, String> {
+    pub(crate) fn get_headers(&self) -> HashMap<String, String> {
         let mut headers = HashMap::new();
         headers.insert("Authorization".to_string(), format!("Bearer {}", self.token));
         headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert("User-Agent".to_string(), self.user_agent.clone());
         headers
     }
 
     /// Execute a SQL statement
     pub async fn execute_sql(&self, query_str: String, warehouse_id: String) -> Result<String, String> {
+        self.execute_sql_with_parameters(query_str, Vec::new(), warehouse_id).await
+    }
+
+    /// Execute a SQL statement whose `:name` markers are bound from `parameters`
+    /// (each `{"name", "value", "type"}`)
+    pub async fn execute_sql_with_parameters(&self, query_str: String, parameters: Vec<serde_json::Value>, warehouse_id: String) -> Result<String, String> {
         let url = format!("{}/sql/statements", self.base_url);
         
-        let request = serde_json::json!({
+        let mut request = serde_json::json!({
             "statement": query_str,
             "warehouse_id": warehouse_id,
             "wait_timeout": "20s",
             "on_wait_timeout": "CANCEL"
         });
+        if !parameters.is_empty() {
+            request["parameters"] = serde_json::Value::Array(parameters);
+        }
         
         let response = HttpClient::request(&url, HttpMethod::Post)
             .headers(self.get_headers())
@@ -45,13 +458,65 @@ impl SqlClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
         Ok(response_text)
     }
 
+    /// Run a statement to completion and return every row of its result with the column types
+    pub async fn execute_sql_all(&self, query_str: String, warehouse_id: String) -> Result<SqlResult, String> {
+        let request = serde_json::json!({
+            "statement": query_str,
+            "warehouse_id": warehouse_id,
+            "wait_timeout": "50s",
+            "on_wait_timeout": "CONTINUE",
+            "disposition": "INLINE",
+            "format": "JSON_ARRAY"
+        });
+        let mut statement = self.send_json(HttpMethod::Post, &format!("{}/sql/statements", self.base_url), Some(&request))?;
+        let statement_id = statement["statement_id"].as_str().ok_or("statement response has no statement_id")?.to_string();
+        let statement_url = format!("{}/sql/statements/{}", self.base_url, statement_id);
+
+        let mut polls = 0;
+        while !statement_succeeded(&statement)? {
+            if polls == STATEMENT_STATUS_POLLS {
+                return Err(ErrorCode::Timeout.tag(format!("statement {} is still running", statement_id)));
+            }
+            polls += 1;
+            statement = self.send_json(HttpMethod::Get, &statement_url, None)?;
+        }
+
+        assemble_sql_result(&statement, |index| {
+            self.send_json(HttpMethod::Get, &format!("{}/result/chunks/{}", statement_url, index), None)
+                .map(|chunk| chunk.to_string())
+        })
+    }
+
+    /// Count a table's rows and read its size and file count from `DESCRIBE DETAIL`
+    pub async fn get_table_stats(&self, full_table_name: String, warehouse_id: String) -> Result<TableStats, String> {
+        let table = quote_table_name(&full_table_name)?;
+        let count = self.execute_sql_all(format!("SELECT COUNT(*) FROM {}", table), warehouse_id.clone()).await?;
+        let detail = self.execute_sql_all(format!("DESCRIBE DETAIL {}", table), warehouse_id).await?;
+        assemble_table_stats(&full_table_name, &count, &detail)
+    }
+
+    /// Send a statement API request and parse the JSON response
+    fn send_json(&self, method: HttpMethod, url: &str, body: Option<&Value>) -> Result<Value, String> {
+        let mut request = HttpClient::request(url, method).headers(self.get_headers());
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let response = request.send().map_err(|e| format!("Request failed: {}", e))?;
+
+        if response.status() < 200 || response.status() >= 300 {
+            return Err(crate::api_error(response.status(), Some(response.text())));
+        }
+
+        serde_json::from_str(&response.text()).map_err(|e| format!("Failed to parse statement response: {}", e))
+    }
+
     /// List SQL warehouses
     pub async fn list_sql_warehouses(&self, _warehouse_id: String) -> Result<String, String> {
         let url = format!("{}/sql/warehouses", self.base_url);
@@ -62,7 +527,7 @@ impl SqlClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -80,7 +545,7 @@ impl SqlClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -98,7 +563,7 @@ impl SqlClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -127,13 +592,31 @@ impl SqlClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
         Ok(response_text)
     }
 
+    /// Recent statement executions, newest first, optionally for one warehouse and/or user
+    pub async fn list_query_history(&self, warehouse_id: Option<String>, user_id: Option<String>, max_results: Option<u32>) -> Result<Vec<QueryHistoryEntry>, String> {
+        let url = format!("{}/sql/history/queries", self.base_url);
+
+        collect_query_history(warehouse_id.as_deref(), user_id.as_deref(), max_results, |request| {
+            let response = HttpClient::request(&url, HttpMethod::Get)
+                .headers(self.get_headers())
+                .json(&request)
+                .send()
+                .map_err(|e| format!("Request failed: {}", e))?;
+
+            if response.status() < 200 || response.status() >= 300 {
+                return Err(crate::api_error(response.status(), Some(response.text())));
+            }
+            Ok(response.text())
+        })
+    }
+
     /// List SQL queries
     pub async fn list_sql_queries(&self, user_id: String, include_metrics: Option<bool>) -> Result<String, String> {
         let mut url = format!("{}/sql/queries?user_id={}", self.base_url, user_id);
@@ -148,7 +631,7 @@ impl SqlClient {
             .map_err(|e| format!("Request failed: {}", e))?;
 
         if response.status() < 200 || response.status() >= 300 {
-            return Err(format!("API Error: HTTP {}", response.status()));
+            return Err(crate::api_error(response.status(), None));
         }
 
         let response_text = response.text();
@@ -157,24 +640,10 @@ impl SqlClient {
 
 
     /// Create a SQL alert
-    pub async fn create_sql_alert(&self, name: String, query_id: String, column: String, op: String, threshold: String, rearm: i32) -> Result<String, String> {
+    pub async fn create_sql_alert(&self, name: String, query_id: String, options: AlertOptions) -> Result<String, String> {
         let url = format!("{}/preview/sql/alerts", self.base_url);
-        
-        // Parse threshold as a number (the API expects a numeric value, not a string)
-        let threshold_value: f64 = threshold.parse()
-            .map_err(|_| format!("Invalid threshold value: {}", threshold))?;
-        
-        let request = serde_json::json!({
-            "name": name,
-            "query_id": query_id,
-            "options": {
-                "column": column,
-                "op": op,
-                "value": threshold_value
-            },
-            "rearm": rearm
-        });
-        
+        let request = build_alert_request(&name, &query_id, &options)?;
+
         let response = HttpClient::request(&url, HttpMethod::Post)
             .headers(self.get_headers())
             .json(&request)
@@ -184,11 +653,260 @@ impl SqlClient {
         if response.status() < 200 || response.status() >= 300 {
             let status = response.status();
             let error_text = response.text();
-            return Err(format!("API Error: HTTP {} - {}", status, error_text));
+            return Err(crate::api_error(status, Some(error_text)));
+        }
+
+        let response_text = response.text();
+        Ok(response_text)
+    }
+
+    /// Subscribe notification destinations to a newly created alert, returning the alert
+    /// response with the created `subscriptions` added to it
+    pub async fn subscribe_alert(&self, alert_response: String, notification_ids: Vec<String>) -> Result<String, String> {
+        let mut alert: serde_json::Value = serde_json::from_str(&alert_response)
+            .map_err(|e| format!("Failed to parse alert response: {}", e))?;
+        let alert_id = alert["id"].as_str()
+            .ok_or_else(|| format!("Alert response has no id: {}", alert_response))?
+            .to_string();
+
+        let subscriptions_url = format!("{}/preview/sql/alerts/{}/subscriptions", self.base_url, alert_id);
+        let mut subscriptions = Vec::new();
+        for subscription in build_alert_subscriptions(&alert_id, &notification_ids) {
+            let response = HttpClient::request(&subscriptions_url, HttpMethod::Post)
+                .headers(self.get_headers())
+                .json(&subscription)
+                .send()
+                .map_err(|e| format!("Request failed: {}", e))?;
+
+            if response.status() < 200 || response.status() >= 300 {
+                let status = response.status();
+                let error_text = response.text();
+                return Err(format!("Alert {} was created but subscribing {} failed: HTTP {} - {}", alert_id, subscription["destination_id"], status, error_text));
+            }
+
+            subscriptions.push(serde_json::from_str(&response.text()).unwrap_or(subscription));
+        }
+
+        alert["subscriptions"] = serde_json::Value::Array(subscriptions);
+        Ok(alert.to_string())
+    }
+
+    /// List the notification destinations (email, Slack, webhook, ...) configured in the workspace
+    pub async fn list_notification_destinations(&self) -> Result<String, String> {
+        let url = format!("{}/notification-destinations", self.base_url);
+
+        let response = HttpClient::request(&url, HttpMethod::Get)
+            .headers(self.get_headers())
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if response.status() < 200 || response.status() >= 300 {
+            let status = response.status();
+            let error_text = response.text();
+            return Err(crate::api_error(status, Some(error_text)));
         }
 
         let response_text = response.text();
         Ok(response_text)
     }
 
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The date range is bound as parameters and the grouping column is selected, grouped and ordered on.
+    #[test]
+    fn test_build_usage_query() {
+        let (statement, parameters) = build_usage_query("2024-01-01", "2024-01-31", Some("sku_name")).unwrap();
+        assert_eq!(
+            statement,
+            "SELECT sku_name, SUM(usage_quantity) AS total_dbus FROM system.billing.usage \
+             WHERE usage_unit = 'DBU' AND usage_date BETWEEN :start_date AND :end_date \
+             GROUP BY sku_name ORDER BY total_dbus DESC"
+        );
+        assert_eq!(parameters[0], serde_json::json!({ "name": "start_date", "value": "2024-01-01", "type": "DATE" }));
+        assert_eq!(parameters[1], serde_json::json!({ "name": "end_date", "value": "2024-01-31", "type": "DATE" }));
+
+        let (ungrouped, _) = build_usage_query("2024-02-29", "2024-02-29", None).unwrap();
+        assert!(ungrouped.starts_with("SELECT SUM(usage_quantity) AS total_dbus FROM system.billing.usage"));
+        assert!(!ungrouped.contains("GROUP BY"));
+    }
+
+    /// Malformed or impossible dates, reversed ranges and unknown grouping columns are rejected.
+    #[test]
+    fn test_build_usage_query_validation() {
+        assert!(build_usage_query("2024-1-01", "2024-01-31", None).is_err());
+        assert!(build_usage_query("2023-02-29", "2023-03-01", None).is_err());
+        assert!(build_usage_query("2024-13-01", "2024-12-31", None).is_err());
+        assert!(build_usage_query("2024-02-01", "2024-01-31", None).is_err());
+        assert!(build_usage_query("2024-01-01", "2024-01-31", Some("sku_name; DROP TABLE x")).is_err());
+    }
+
+    /// Comments go and whitespace collapses, but comment markers and spacing inside literals are untouched.
+    #[test]
+    fn test_normalize_sql() {
+        let sql = "SELECT  id, -- the key\n\tName /* display /* nested */ name */\nFROM   Sales.Orders\r\nWHERE note = '-- not a comment /* kept */'\n  AND `Odd  Column` <> \"a  b\" ";
+        assert_eq!(
+            normalize_sql(sql),
+            "select id, Name from Sales.Orders where note = '-- not a comment /* kept */' and `Odd  Column` <> \"a  b\""
+        );
+        assert_eq!(normalize_sql("select 'it''s -- fine', 'a\\'b' -- gone"), "select 'it''s -- fine', 'a\\'b'");
+        assert_eq!(normalize_sql("Select\n*\nFrom t"), normalize_sql("select * from t"));
+        assert_eq!(normalize_sql("  -- only a comment\n"), "");
+    }
+
+    /// Rows from the inline first chunk and every following chunk are joined under the manifest's columns.
+    #[test]
+    fn test_assemble_sql_result() {
+        let statement = serde_json::json!({
+            "statement_id": "01ef-stmt",
+            "status": { "state": "SUCCEEDED" },
+            "manifest": {
+                "schema": { "column_count": 2, "columns": [
+                    { "name": "state", "type_text": "STRING", "type_name": "STRING", "position": 0 },
+                    { "name": "deaths", "type_text": "BIGINT", "type_name": "LONG", "position": 1 }
+                ]},
+                "total_chunk_count": 2
+            },
+            "result": { "chunk_index": 0, "data_array": [["Florida", "100"], ["Ohio", null]], "next_chunk_index": 1 }
+        });
+        let mut fetched = Vec::new();
+        let result = assemble_sql_result(&statement, |index| {
+            fetched.push(index);
+            Ok(r#"{"chunk_index": 1, "data_array": [["Texas", "42"]]}"#.to_string())
+        }).unwrap();
+
+        assert_eq!(fetched, vec![1]);
+        assert_eq!(result.columns, vec![
+            ColumnSchema { name: "state".to_string(), type_text: "STRING".to_string() },
+            ColumnSchema { name: "deaths".to_string(), type_text: "BIGINT".to_string() },
+        ]);
+        assert_eq!(result.rows, vec![
+            vec![Some("Florida".to_string()), Some("100".to_string())],
+            vec![Some("Ohio".to_string()), None],
+            vec![Some("Texas".to_string()), Some("42".to_string())],
+        ]);
+    }
+
+    /// Pending and running statements keep polling; failures carry the statement's error message.
+    #[test]
+    fn test_statement_succeeded() {
+        assert!(statement_succeeded(&serde_json::json!({ "status": { "state": "SUCCEEDED" } })).unwrap());
+        assert!(!statement_succeeded(&serde_json::json!({ "status": { "state": "PENDING" } })).unwrap());
+        assert!(!statement_succeeded(&serde_json::json!({ "status": { "state": "RUNNING" } })).unwrap());
+        let failed = serde_json::json!({
+            "statement_id": "01ef-stmt",
+            "status": { "state": "FAILED", "error": { "error_code": "BAD_REQUEST", "message": "[TABLE_OR_VIEW_NOT_FOUND] covid.deaths" } }
+        });
+        assert_eq!(statement_succeeded(&failed).unwrap_err(), "Statement 01ef-stmt FAILED: [TABLE_OR_VIEW_NOT_FOUND] covid.deaths");
+        assert!(statement_succeeded(&serde_json::json!({ "status": { "state": "CANCELED" } })).is_err());
+    }
+
+    /// The count comes from the single COUNT(*) value and size/files/format from the DESCRIBE DETAIL columns by name.
+    #[test]
+    fn test_assemble_table_stats() {
+        let count = SqlResult {
+            columns: vec![ColumnSchema { name: "count(1)".to_string(), type_text: "BIGINT".to_string() }],
+            rows: vec![vec![Some("1250000".to_string())]],
+        };
+        let detail = SqlResult {
+            columns: ["format", "id", "name", "numFiles", "sizeInBytes"]
+                .iter()
+                .map(|name| ColumnSchema { name: name.to_string(), type_text: "STRING".to_string() })
+                .collect(),
+            rows: vec![vec![
+                Some("delta".to_string()),
+                Some("8f1c".to_string()),
+                Some("main.sales.orders".to_string()),
+                Some("12".to_string()),
+                Some("52428800".to_string()),
+            ]],
+        };
+
+        assert_eq!(assemble_table_stats("main.sales.orders", &count, &detail).unwrap(), TableStats {
+            full_table_name: "main.sales.orders".to_string(),
+            row_count: 1_250_000,
+            size_in_bytes: Some(52_428_800),
+            num_files: Some(12),
+            format: Some("delta".to_string()),
+        });
+        let empty = SqlResult { columns: count.columns, rows: Vec::new() };
+        assert!(assemble_table_stats("main.sales.orders", &empty, &detail).is_err());
+
+        assert_eq!(quote_table_name("main.sales.orders").unwrap(), "`main`.`sales`.`orders`");
+        assert!(quote_table_name("sales.orders").is_err());
+        assert!(quote_table_name("main.sales.orders; DROP TABLE x").is_err());
+        assert!(quote_table_name("main.sales.`orders`").is_err());
+    }
+
+    /// The alert condition is sent under `options` with a numeric threshold; a non-numeric one is refused.
+    #[test]
+    fn test_build_alert_request() {
+        let options = AlertOptions { column: "errors".to_string(), op: ">".to_string(), threshold: " 2.5".to_string(), rearm: 0 };
+        assert_eq!(build_alert_request("Errors", "q-1", &options).unwrap(), serde_json::json!({
+            "name": "Errors",
+            "query_id": "q-1",
+            "options": { "column": "errors", "op": ">", "value": 2.5 },
+            "rearm": 0
+        }));
+        assert!(build_alert_request("Errors", "q-1", &AlertOptions { threshold: "high".to_string(), ..options }).is_err());
+    }
+
+    /// Every notification destination becomes a subscription on the alert, and none are sent without destinations.
+    #[test]
+    fn test_build_alert_subscriptions() {
+        let subscriptions = build_alert_subscriptions("alert-1", &["dest-a".to_string(), "dest-b".to_string()]);
+        assert_eq!(subscriptions, vec![
+            serde_json::json!({ "alert_id": "alert-1", "destination_id": "dest-a" }),
+            serde_json::json!({ "alert_id": "alert-1", "destination_id": "dest-b" }),
+        ]);
+        assert!(build_alert_subscriptions("alert-1", &[]).is_empty());
+    }
+
+    /// History pages are followed until `max_results` entries are gathered, every page carrying the filter; a
+    /// non-numeric user id is refused before any request is sent.
+    #[test]
+    fn test_collect_query_history() {
+        let pages = [
+            r#"{"res": [
+                {"query_id": "q1", "status": "FINISHED", "query_text": "SELECT 1", "duration": 120, "rows_produced": 1, "user_name": "ana@example.com"},
+                {"query_id": "q2", "status": "RUNNING", "query_text": "SELECT * FROM sales", "metrics": {"rows_produced_count": 40}, "user_name": "ana@example.com"}
+            ], "next_page_token": "t1", "has_next_page": true}"#,
+            r#"{"res": [{"query_id": "q3", "status": "FAILED"}, {"query_id": "q4"}], "next_page_token": "t2", "has_next_page": true}"#,
+        ];
+        let mut requests = Vec::new();
+        let history = collect_query_history(Some("wh-1"), Some("42"), Some(3), |request| {
+            requests.push(request);
+            Ok(pages[requests.len() - 1].to_string())
+        })
+        .unwrap();
+
+        assert_eq!(history.iter().map(|entry| entry.query_id.as_str()).collect::<Vec<_>>(), ["q1", "q2", "q3"]);
+        assert_eq!(history[0], QueryHistoryEntry {
+            query_id: "q1".to_string(),
+            status: Some("FINISHED".to_string()),
+            query_text: Some("SELECT 1".to_string()),
+            duration_ms: Some(120),
+            rows_produced: Some(1),
+            executed_by: Some("ana@example.com".to_string()),
+        });
+        assert_eq!(history[1].rows_produced, Some(40));
+        assert_eq!(history[1].duration_ms, None);
+        assert_eq!(requests[0], serde_json::json!({
+            "filter_by": { "warehouse_ids": ["wh-1"], "user_ids": [42] },
+            "max_results": 3
+        }));
+        assert_eq!(requests[1]["page_token"], "t1");
+        assert_eq!(requests[1]["max_results"], 1);
+
+        let mut fetched = false;
+        let result = collect_query_history(None, Some("ana@example.com"), None, |_| {
+            fetched = true;
+            Ok("{}".to_string())
+        });
+        assert!(result.unwrap_err().contains("user_id must be a number"));
+        assert!(!fetched);
+    }
+}
@@ -0,0 +1,172 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde_json::Value;
+use std::collections::HashMap;
+use weil_rs::http::{HttpClient, HttpMethod};
+
+/// Notebook formats `workspace/export` and `workspace/import` both accept
+pub const NOTEBOOK_FORMATS: [&str; 6] = ["SOURCE", "HTML", "JUPYTER", "DBC", "R_MARKDOWN", "AUTO"];
+/// Notebook languages; only `SOURCE` imports need one, as the other formats carry their own
+pub const NOTEBOOK_LANGUAGES: [&str; 4] = ["PYTHON", "SQL", "SCALA", "R"];
+
+/// Upper-case `value` and check it is one of `allowed`, naming the parameter otherwise
+fn validate_enum(name: &str, value: &str, allowed: &[&str]) -> Result<String, String> {
+    let normalized = value.trim().to_uppercase();
+    if allowed.contains(&normalized.as_str()) {
+        Ok(normalized)
+    } else {
+        Err(format!("unsupported {} '{}', expected one of: {}", name, value, allowed.join(", ")))
+    }
+}
+
+/// Workspace paths are absolute, e.g. `/Users/someone@example.com/etl`
+fn validate_workspace_path(path: &str) -> Result<(), String> {
+    if path.starts_with('/') {
+        Ok(())
+    } else {
+        Err(format!("workspace path must be absolute, got '{}'", path))
+    }
+}
+
+/// Build the `workspace/import` request body, checking the format, the language (required for
+/// `SOURCE`, optional otherwise) and that the content is valid base64
+pub fn build_import_request(path: &str, format: &str, language: &str, content_b64: &str, overwrite: bool) -> Result<Value, String> {
+    validate_workspace_path(path)?;
+    let format = validate_enum("format", format, &NOTEBOOK_FORMATS)?;
+    let language = match language.trim() {
+        "" if format == "SOURCE" => return Err(format!("language is required for SOURCE imports, expected one of: {}", NOTEBOOK_LANGUAGES.join(", "))),
+        "" => None,
+        language => Some(validate_enum("language", language, &NOTEBOOK_LANGUAGES)?),
+    };
+    STANDARD.decode(content_b64.trim()).map_err(|e| format!("content_b64 is not valid base64: {}", e))?;
+
+    let mut request = serde_json::json!({
+        "path": path,
+        "format": format,
+        "content": content_b64.trim(),
+        "overwrite": overwrite
+    });
+    if let Some(language) = language {
+        request["language"] = language.into();
+    }
+    Ok(request)
+}
+
+/// Read the base64 `content` from a `workspace/export` response
+pub fn parse_export_content(response: &str) -> Result<String, String> {
+    let value: Value = serde_json::from_str(response).map_err(|e| format!("Failed to parse export response: {}", e))?;
+    value["content"].as_str().map(str::to_string).ok_or_else(|| format!("export response has no content: {}", response))
+}
+
+/// Workspace notebook import/export for Databricks
+pub struct WorkspaceClient {
+    base_url: String,
+    token: String,
+    user_agent: String,
+}
+
+impl WorkspaceClient {
+    /// Create a new WorkspaceClient instance
+    pub fn new(workspace_url: &str, personal_access_token: &str) -> Self {
+        let base_url = format!("{}/api/2.0", workspace_url.trim_end_matches('/'));
+
+        Self {
+            base_url,
+            token: personal_access_token.to_string(),
+            user_agent: crate::DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+
+    /// Override the User-Agent sent with every request
+    pub fn with_user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = user_agent;
+        self
+    }
+
+    /// Get headers with authentication
+    fn get_headers(&self) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        headers.insert("Authorization".to_string(), format!("Bearer {}", self.token));
+        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert("User-Agent".to_string(), self.user_agent.clone());
+        headers
+    }
+
+    /// Export a notebook in `format`, returning its base64-encoded content
+    pub async fn export_notebook(&self, path: String, format: String) -> Result<String, String> {
+        validate_workspace_path(&path)?;
+        let format = validate_enum("format", &format, &NOTEBOOK_FORMATS)?;
+        let url = format!("{}/workspace/export", self.base_url);
+
+        let response = HttpClient::request(&url, HttpMethod::Get)
+            .headers(self.get_headers())
+            .query(vec![("path".to_string(), path), ("format".to_string(), format)])
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if response.status() < 200 || response.status() >= 300 {
+            return Err(crate::api_error(response.status(), Some(response.text())));
+        }
+
+        parse_export_content(&response.text())
+    }
+
+    /// Import base64-encoded notebook content to `path`, replacing an existing notebook only with `overwrite`
+    pub async fn import_notebook(&self, path: String, format: String, language: String, content_b64: String, overwrite: bool) -> Result<String, String> {
+        let request = build_import_request(&path, &format, &language, &content_b64, overwrite)?;
+        let url = format!("{}/workspace/import", self.base_url);
+
+        let response = HttpClient::request(&url, HttpMethod::Post)
+            .headers(self.get_headers())
+            .json(&request)
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if response.status() < 200 || response.status() >= 300 {
+            return Err(crate::api_error(response.status(), Some(response.text())));
+        }
+
+        let response_text = response.text();
+        Ok(response_text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Format and language are normalised to the API's enums, and language is left out for
+    /// formats that carry their own.
+    #[test]
+    fn test_build_import_request() {
+        let content = STANDARD.encode("print('hello')");
+        let request = build_import_request("/Users/ana@example.com/etl", "source", "python", &content, true).unwrap();
+        assert_eq!(request, serde_json::json!({
+            "path": "/Users/ana@example.com/etl",
+            "format": "SOURCE",
+            "language": "PYTHON",
+            "content": content,
+            "overwrite": true
+        }));
+
+        let jupyter = build_import_request("/Shared/report", "JUPYTER", "", &content, false).unwrap();
+        assert!(jupyter.get("language").is_none());
+    }
+
+    /// Unknown enums, a missing SOURCE language, relative paths and bad base64 are refused.
+    #[test]
+    fn test_build_import_request_rejects_invalid_input() {
+        let content = STANDARD.encode("SELECT 1");
+        assert_eq!(
+            build_import_request("/Shared/q", "PDF", "SQL", &content, false).unwrap_err(),
+            "unsupported format 'PDF', expected one of: SOURCE, HTML, JUPYTER, DBC, R_MARKDOWN, AUTO"
+        );
+        assert!(build_import_request("/Shared/q", "SOURCE", "JAVA", &content, false).unwrap_err().contains("unsupported language"));
+        assert!(build_import_request("/Shared/q", "SOURCE", " ", &content, false).unwrap_err().contains("language is required"));
+        assert!(build_import_request("Shared/q", "SOURCE", "SQL", &content, false).is_err());
+        assert!(build_import_request("/Shared/q", "SOURCE", "SQL", "not base64!", false).is_err());
+
+        assert_eq!(parse_export_content(r#"{"content": "cHJpbnQoMSk=", "file_type": "py"}"#).unwrap(), "cHJpbnQoMSk=");
+        assert!(parse_export_content("{}").is_err());
+    }
+}
@@ -28,7 +28,8 @@
 use serde::{Deserialize, Serialize};
 use weil_macros::{WeilType, constructor, query, smart_contract};
 use weil_rs::{
-    collections::plottable::Plottable, config::Secrets, datadog::DatadogClient, runtime::Runtime,
+    collections::plottable::Plottable, config::Secrets, datadog::DatadogClient, errors::ErrorCode,
+    runtime::Runtime,
 };
 
 /// Datadog API credentials and site configuration.
@@ -125,7 +126,8 @@ impl Datadog for DatadogContractState {
         &self,
         human_time: String,
     ) -> Result<FromToInterval, String> {
-        let interval = Runtime::parse_human_time(&human_time).map_err(|err| err.to_string())?;
+        let interval =
+            Runtime::parse_human_time(&human_time).map_err(|err| ErrorCode::Validation.tag(err))?;
 
         Ok(FromToInterval {
             from: interval.from,
@@ -153,8 +155,12 @@ impl Datadog for DatadogContractState {
 
         let client = DatadogClient::new(config);
 
-        let parsed_from = from.parse::<i64>().map_err(|err| err.to_string())?;
-        let parsed_to = to.parse::<i64>().map_err(|err| err.to_string())?;
+        let parsed_from = from
+            .parse::<i64>()
+            .map_err(|err| ErrorCode::Validation.tag(format!("from: {}", err)))?;
+        let parsed_to = to
+            .parse::<i64>()
+            .map_err(|err| ErrorCode::Validation.tag(format!("to: {}", err)))?;
 
         let mut plot = Plottable::new_with_time_series()
             .label(format!("plot for: {}", query_str))
@@ -163,10 +169,10 @@ impl Datadog for DatadogContractState {
 
         let result = client
             .query(query_str, parsed_from, parsed_to)
-            .map_err(|err| err.to_string())?;
+            .map_err(|err| ErrorCode::Upstream.tag(err))?;
 
         if result.series.len() == 0 {
-            return Err("no series data returned in response from Datadog".to_string());
+            return Err(ErrorCode::NotFound.tag("no series data returned in response from Datadog"));
         }
 
         for series in result.series {
@@ -192,7 +198,9 @@ impl Datadog for DatadogContractState {
         );
 
         let client = DatadogClient::new(config);
-        let metrics = client.list_metrics().map_err(|err| err.to_string())?;
+        let metrics = client
+            .list_metrics()
+            .map_err(|err| ErrorCode::Upstream.tag(err))?;
 
         Ok(metrics.data.iter().map(|x| x.id.to_string()).collect())
     }
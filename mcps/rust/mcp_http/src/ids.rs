@@ -0,0 +1,68 @@
+//! Reading numeric ids out of API responses and tool arguments.
+//!
+//! Confluence v2 returns ids as strings while v1 returns numbers, and Databricks ids (jobs, runs,
+//! repos, users) are 64-bit integers that callers hand over as text. These helpers accept every
+//! form and reject anything that isn't a non-negative integer fitting in a `u64`, rather than
+//! truncating it or panicking.
+
+use serde_json::Value;
+
+/// Parse a numeric id given as text, ignoring surrounding whitespace.
+pub fn parse_id(id: &str) -> Result<u64, String> {
+    id.trim()
+        .parse::<u64>()
+        .map_err(|err| format!("invalid id {:?}: {}", id, err))
+}
+
+/// An id sent as a JSON string or number, as text. Numbers are written out digit for digit, so
+/// a 64-bit id survives where a round trip through `f64` would not.
+pub fn id_text(value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(text) => Ok(text.trim().to_string()),
+        Value::Number(number) => Ok(number.to_string()),
+        other => Err(format!("invalid id {}: expected a string or number", other)),
+    }
+}
+
+/// A numeric id sent as a JSON string or number.
+pub fn json_id(value: &Value) -> Result<u64, String> {
+    match value {
+        Value::Number(number) => number
+            .as_u64()
+            .ok_or_else(|| format!("invalid id {}: expected a non-negative integer", number)),
+        other => parse_id(&id_text(other)?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Ids are accepted as text and in both JSON forms.
+    #[test]
+    fn ids_accept_string_and_number() {
+        assert_eq!(parse_id("98765"), Ok(98765));
+        assert_eq!(parse_id(" 42 "), Ok(42));
+        assert_eq!(json_id(&json!("98765")), Ok(98765));
+        assert_eq!(json_id(&json!(18446744073709551615u64)), Ok(u64::MAX));
+        assert_eq!(
+            id_text(&json!(18446744073709551615u64)),
+            Ok("18446744073709551615".to_string())
+        );
+        assert_eq!(id_text(&json!(" a83820b5 ")), Ok("a83820b5".to_string()));
+    }
+
+    /// Negative, fractional, non-numeric and non-scalar ids are errors rather than panics.
+    #[test]
+    fn ids_reject_invalid_input() {
+        assert!(parse_id("12a").is_err());
+        assert!(parse_id("").is_err());
+        assert!(parse_id("-1").is_err());
+        assert!(json_id(&json!(-1)).is_err());
+        assert!(json_id(&json!(1.5)).is_err());
+        assert!(json_id(&json!(null)).is_err());
+        assert!(json_id(&json!(["1"])).is_err());
+        assert!(id_text(&json!({"value": "1"})).is_err());
+    }
+}
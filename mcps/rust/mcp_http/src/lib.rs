@@ -0,0 +1,408 @@
+//! # Shared HTTP plumbing for the Rust MCP servers
+//!
+//! Every MCP talks to a JSON REST API the same way: build the auth and User-Agent headers,
+//! send, check the status, then parse the body (often out of an envelope such as
+//! ServiceNow's `{"result": ...}`). [`JsonApi`] does that once.
+//!
+//! ## Usage (pseudocode)
+//! ```text
+//! let api = JsonApi::new()
+//!     .basic_auth(&config.username, &config.password)
+//!     .user_agent(&user_agent)
+//!     .envelope("result")
+//!     .max_retries(2);
+//! let incident: Incident = api.get_json(&url, query)?;
+//! let created: Incident = api.post_json(&url, vec![], &payload)?;
+//! ```
+//!
+//! ## Error Semantics
+//! Errors are `String`s tagged with a [`weil_rs::errors::ErrorCode`]. A non-2xx response goes
+//! through the API's error mapper ([`default_error`] unless one is set), so a server can keep
+//! its own wording. A body that can't be parsed is an `[UPSTREAM]` error quoting the body.
+//!
+//! [`ids`] reads numeric ids that APIs send as either JSON strings or numbers,
+//! [`pagination`] bounds how far a listing is followed, and [`tools`] handles the
+//! `disabled_tools` config.
+
+pub mod ids;
+pub mod pagination;
+pub mod tools;
+
+use std::collections::HashMap;
+
+use base64::Engine;
+use base64::engine::general_purpose;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use weil_rs::errors::ErrorCode;
+use weil_rs::http::{HttpClient, HttpMethod, HttpResponse};
+
+/// Turns a non-2xx status and its body into the error returned to the caller.
+pub type ErrorMapper = fn(u16, &str) -> String;
+
+/// Settings shared by every request made to one JSON API: headers (credentials, User-Agent,
+/// ...), the response envelope, the retry budget and how failures are reported.
+#[derive(Clone)]
+pub struct JsonApi {
+    headers: HashMap<String, String>,
+    envelope: Option<String>,
+    max_retries: u32,
+    error_mapper: ErrorMapper,
+}
+
+impl Default for JsonApi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonApi {
+    /// An API that accepts JSON, with no credentials, envelope or retries.
+    pub fn new() -> Self {
+        let mut headers = HashMap::new();
+        headers.insert("Accept".to_string(), "application/json".to_string());
+        Self {
+            headers,
+            envelope: None,
+            max_retries: 0,
+            error_mapper: default_error,
+        }
+    }
+
+    /// Authenticate with HTTP Basic credentials.
+    pub fn basic_auth(self, username: &str, password: &str) -> Self {
+        self.header("Authorization", &basic_auth_header(username, password))
+    }
+
+    /// Authenticate with a bearer token.
+    pub fn bearer_auth(self, token: &str) -> Self {
+        self.header("Authorization", &format!("Bearer {}", token))
+    }
+
+    pub fn user_agent(self, user_agent: &str) -> Self {
+        self.header("User-Agent", user_agent)
+    }
+
+    /// Send `name: value` with every request, replacing any earlier value.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Read response bodies from the `field` member of a JSON object rather than the whole body.
+    pub fn envelope(mut self, field: &str) -> Self {
+        self.envelope = Some(field.to_string());
+        self
+    }
+
+    /// Resend an idempotent request answered with a transient status (see [`is_transient_status`]
+    /// and [`is_idempotent`]) up to `max_retries` times.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Report non-2xx responses with `error_mapper` instead of [`default_error`].
+    pub fn error_mapper(mut self, error_mapper: ErrorMapper) -> Self {
+        self.error_mapper = error_mapper;
+        self
+    }
+
+    /// `GET url` and parse the (unwrapped) response.
+    pub fn get_json<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        query: Vec<(String, String)>,
+    ) -> Result<T, String> {
+        let body = self.send(HttpMethod::Get, url, query, None)?;
+        self.parse(&body)
+    }
+
+    /// `POST` `body` as JSON and parse the (unwrapped) response.
+    pub fn post_json<T: DeserializeOwned, B: Serialize + ?Sized>(
+        &self,
+        url: &str,
+        query: Vec<(String, String)>,
+        body: &B,
+    ) -> Result<T, String> {
+        self.send_json(HttpMethod::Post, url, query, body)
+    }
+
+    /// `PUT` `body` as JSON and parse the (unwrapped) response.
+    pub fn put_json<T: DeserializeOwned, B: Serialize + ?Sized>(
+        &self,
+        url: &str,
+        query: Vec<(String, String)>,
+        body: &B,
+    ) -> Result<T, String> {
+        self.send_json(HttpMethod::Put, url, query, body)
+    }
+
+    /// `PATCH` `body` as JSON and parse the (unwrapped) response.
+    pub fn patch_json<T: DeserializeOwned, B: Serialize + ?Sized>(
+        &self,
+        url: &str,
+        query: Vec<(String, String)>,
+        body: &B,
+    ) -> Result<T, String> {
+        self.send_json(HttpMethod::Patch, url, query, body)
+    }
+
+    /// `DELETE url`, ignoring the response body.
+    pub fn delete(&self, url: &str, query: Vec<(String, String)>) -> Result<(), String> {
+        self.send(HttpMethod::Delete, url, query, None).map(|_| ())
+    }
+
+    fn send_json<T: DeserializeOwned, B: Serialize + ?Sized>(
+        &self,
+        method: HttpMethod,
+        url: &str,
+        query: Vec<(String, String)>,
+        body: &B,
+    ) -> Result<T, String> {
+        let body = serde_json::to_string(body).map_err(|err| err.to_string())?;
+        let response = self.send(method, url, query, Some(body))?;
+        self.parse(&response)
+    }
+
+    /// Send a request with the API's headers, retrying transient failures of idempotent methods,
+    /// and return the body of a 2xx response as is. A `body` is sent as JSON unless a `Content-Type` header is set.
+    pub fn send(
+        &self,
+        method: HttpMethod,
+        url: &str,
+        query: Vec<(String, String)>,
+        body: Option<String>,
+    ) -> Result<String, String> {
+        let mut headers = self.headers.clone();
+        if body.is_some() {
+            headers
+                .entry("Content-Type".to_string())
+                .or_insert_with(|| "application/json".to_string());
+        }
+
+        let response = retry_while_transient(
+            self.max_retries,
+            method,
+            || {
+                let mut request = HttpClient::request(url, method)
+                    .headers(headers.clone())
+                    .query(query.clone());
+                if let Some(body) = &body {
+                    request = request.body(body.clone());
+                }
+                request.send().map_err(|err| err.to_string())
+            },
+            HttpResponse::status,
+        )?;
+
+        let status = response.status();
+        let text = response.text();
+        if (200..300).contains(&status) {
+            Ok(text)
+        } else {
+            Err((self.error_mapper)(status, &text))
+        }
+    }
+
+    /// Parse a response body, unwrapping the envelope if the API has one.
+    pub fn parse<T: DeserializeOwned>(&self, body: &str) -> Result<T, String> {
+        let parse_error = |err: serde_json::Error| {
+            ErrorCode::Upstream.tag(format!(
+                "Failed to parse response: {}. Response was: {}",
+                err, body
+            ))
+        };
+
+        match &self.envelope {
+            None => serde_json::from_str(body).map_err(parse_error),
+            Some(field) => {
+                let mut value: serde_json::Value =
+                    serde_json::from_str(body).map_err(parse_error)?;
+                let inner = value.get_mut(field.as_str()).map(serde_json::Value::take);
+                let inner = inner.ok_or_else(|| {
+                    ErrorCode::Upstream.tag(format!(
+                        "Failed to parse response: no `{}` field. Response was: {}",
+                        field, body
+                    ))
+                })?;
+                serde_json::from_value(inner).map_err(parse_error)
+            }
+        }
+    }
+}
+
+/// `Authorization` value for HTTP Basic credentials (RFC 7617).
+pub fn basic_auth_header(username: &str, password: &str) -> String {
+    let credentials = format!("{}:{}", username, password);
+    format!(
+        "Basic {}",
+        general_purpose::STANDARD.encode(credentials.as_bytes())
+    )
+}
+
+/// `[CODE] HTTP status: body`, with the code derived from the status.
+pub fn default_error(status: u16, body: &str) -> String {
+    ErrorCode::from_http_status(status).tag(format!("HTTP {}: {}", status, body.trim()))
+}
+
+/// Whether a response is worth retrying: a rate limit (429) or a gateway or availability error
+/// (502, 503, 504). Other 5xx errors may come from a write that was partly applied, so they are
+/// returned rather than repeated.
+pub fn is_transient_status(status: u16) -> bool {
+    matches!(status, 429 | 502 | 503 | 504)
+}
+
+/// Whether sending a request twice has the same effect as sending it once (GET, HEAD, PUT,
+/// DELETE). A gateway error can arrive after the upstream already applied a POST or PATCH, so
+/// repeating one could create a duplicate record or apply the change twice.
+pub fn is_idempotent(method: HttpMethod) -> bool {
+    matches!(
+        method,
+        HttpMethod::Get | HttpMethod::Head | HttpMethod::Put | HttpMethod::Delete
+    )
+}
+
+/// Call `send` until it returns a non-transient response or `max_retries` retries have been
+/// spent, returning the last response either way. A non-idempotent `method` is sent once.
+/// Transport errors are returned immediately.
+pub fn retry_while_transient<T>(
+    max_retries: u32,
+    method: HttpMethod,
+    mut send: impl FnMut() -> Result<T, String>,
+    status: impl Fn(&T) -> u16,
+) -> Result<T, String> {
+    let max_retries = if is_idempotent(method) {
+        max_retries
+    } else {
+        0
+    };
+    let mut retries = 0;
+    loop {
+        let response = send()?;
+        if retries >= max_retries || !is_transient_status(status(&response)) {
+            return Ok(response);
+        }
+        retries += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Record {
+        sys_id: String,
+    }
+
+    /// An enveloped body is read from its field, and a missing field or malformed body is an
+    /// upstream error quoting the response.
+    #[test]
+    fn parse_unwraps_envelope() {
+        let api = JsonApi::new().envelope("result");
+        assert_eq!(
+            api.parse::<Vec<Record>>(r#"{"result": [{"sys_id": "abc"}]}"#),
+            Ok(vec![Record {
+                sys_id: "abc".to_string()
+            }])
+        );
+
+        let err = api.parse::<Record>(r#"{"error": {}}"#).unwrap_err();
+        assert_eq!(ErrorCode::of_tagged(&err), Some(ErrorCode::Upstream));
+        assert!(err.contains("no `result` field"), "{err}");
+
+        let err = JsonApi::new().parse::<Record>("<html>").unwrap_err();
+        assert!(err.ends_with("Response was: <html>"), "{err}");
+        assert_eq!(
+            JsonApi::new().parse::<Record>(r#"{"sys_id": "x"}"#),
+            Ok(Record {
+                sys_id: "x".to_string()
+            })
+        );
+    }
+
+    /// Credentials become headers, and failures are tagged by status unless mapped otherwise.
+    #[test]
+    fn headers_and_errors() {
+        let api = JsonApi::new()
+            .basic_auth("admin", "secret")
+            .user_agent("wadk-mcp/test");
+        assert_eq!(api.headers["Authorization"], "Basic YWRtaW46c2VjcmV0");
+        assert_eq!(api.headers["User-Agent"], "wadk-mcp/test");
+        assert_eq!(
+            JsonApi::new().bearer_auth("tok").headers["Authorization"],
+            "Bearer tok"
+        );
+
+        assert_eq!(default_error(404, "gone\n"), "[NOT_FOUND] HTTP 404: gone");
+        let api = api.error_mapper(|status, _| format!("custom {}", status));
+        assert_eq!((api.error_mapper)(500, ""), "custom 500");
+    }
+
+    /// Rate limits and gateway errors are retried up to the budget; other responses come back
+    /// on the first attempt.
+    #[test]
+    fn transient_responses_are_retried_within_budget() {
+        let mut statuses = vec![429u16, 503, 201].into_iter();
+        let mut calls = 0;
+        let response = retry_while_transient(
+            2,
+            HttpMethod::Get,
+            || {
+                calls += 1;
+                Ok(statuses.next().unwrap())
+            },
+            |status| *status,
+        );
+        assert_eq!(response, Ok(201));
+        assert_eq!(calls, 3);
+
+        let mut calls = 0;
+        let response = retry_while_transient(
+            1,
+            HttpMethod::Delete,
+            || {
+                calls += 1;
+                Ok(429u16)
+            },
+            |status| *status,
+        );
+        assert_eq!(response, Ok(429));
+        assert_eq!(calls, 2);
+
+        let mut calls = 0;
+        let response = retry_while_transient(
+            3,
+            HttpMethod::Put,
+            || {
+                calls += 1;
+                Ok(500u16)
+            },
+            |status| *status,
+        );
+        assert_eq!(response, Ok(500));
+        assert_eq!(calls, 1);
+    }
+
+    /// A POST or PATCH answered with a gateway error may already have been applied, so it is
+    /// sent once whatever the budget.
+    #[test]
+    fn non_idempotent_requests_are_not_repeated() {
+        for method in [HttpMethod::Post, HttpMethod::Patch] {
+            let mut calls = 0;
+            let response = retry_while_transient(
+                3,
+                method,
+                || {
+                    calls += 1;
+                    Ok(502u16)
+                },
+                |status| *status,
+            );
+            assert_eq!(response, Ok(502));
+            assert_eq!(calls, 1);
+        }
+    }
+}
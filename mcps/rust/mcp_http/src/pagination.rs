@@ -0,0 +1,141 @@
+//! A guard for listings that are followed page by page.
+//!
+//! A backend that keeps handing out another page (a `next` link, a full page suggesting more
+//! behind it) would otherwise keep a de-paginating call looping forever. [`drain_pages`] follows
+//! pages up to [`PaginationLimits`] and then fails with
+//! [`PaginationError::PaginationLimitExceeded`], carrying how much was collected, rather than
+//! stopping silently.
+
+/// Most pages a single listing may follow before giving up.
+pub const MAX_PAGES: usize = 500;
+
+/// Most items a single listing may collect before giving up.
+pub const MAX_ITEMS: usize = 25_000;
+
+/// Bounds on how far `drain_pages` follows a listing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaginationLimits {
+    pub max_pages: usize,
+    pub max_items: usize,
+}
+
+impl Default for PaginationLimits {
+    fn default() -> Self {
+        PaginationLimits {
+            max_pages: MAX_PAGES,
+            max_items: MAX_ITEMS,
+        }
+    }
+}
+
+/// Why draining a paginated listing stopped short.
+#[derive(Debug, PartialEq)]
+pub enum PaginationError {
+    /// Fetching a follow-up page failed.
+    Request(String),
+    /// The backend kept offering more pages past the limits.
+    PaginationLimitExceeded { pages: usize, items: usize },
+}
+
+impl std::fmt::Display for PaginationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaginationError::Request(err) => write!(f, "{}", err),
+            PaginationError::PaginationLimitExceeded { pages, items } => write!(
+                f,
+                "PaginationLimitExceeded: listing still had more results after {} pages ({} items collected)",
+                pages, items
+            ),
+        }
+    }
+}
+
+impl From<PaginationError> for String {
+    fn from(err: PaginationError) -> Self {
+        err.to_string()
+    }
+}
+
+/// Collect a paginated listing starting from its first page, calling `fetch_next` with the
+/// cursor of each following page (a `next` link, an offset, ...) until there is none.
+///
+/// Fails with `PaginationLimitExceeded` instead of looping forever when a backend keeps offering
+/// pages past `limits`.
+pub fn drain_pages<C, T, F>(
+    first_page: Vec<T>,
+    mut next: Option<C>,
+    limits: PaginationLimits,
+    mut fetch_next: F,
+) -> Result<Vec<T>, PaginationError>
+where
+    F: FnMut(C) -> Result<(Vec<T>, Option<C>), String>,
+{
+    let mut items = first_page;
+    let mut pages = 1;
+
+    while let Some(cursor) = next {
+        if pages >= limits.max_pages || items.len() >= limits.max_items {
+            return Err(PaginationError::PaginationLimitExceeded {
+                pages,
+                items: items.len(),
+            });
+        }
+        let (page_items, following) = fetch_next(cursor).map_err(PaginationError::Request)?;
+        items.extend(page_items);
+        pages += 1;
+        next = following;
+    }
+
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A backend that always offers another page trips the page cap with the count collected so
+    /// far, while a finite listing is drained completely.
+    #[test]
+    fn pagination_guard_trips_on_endless_listing() {
+        let limits = PaginationLimits {
+            max_pages: 5,
+            max_items: 100,
+        };
+        let mut calls = 0;
+        let endless = drain_pages(vec![0], Some("next"), limits, |_| {
+            calls += 1;
+            Ok((vec![calls, calls], Some("next")))
+        });
+        assert_eq!(
+            endless,
+            Err(PaginationError::PaginationLimitExceeded { pages: 5, items: 9 })
+        );
+        assert_eq!(calls, 4);
+
+        let item_capped = drain_pages(
+            vec![0; 3],
+            Some(3u32),
+            PaginationLimits {
+                max_pages: 100,
+                max_items: 6,
+            },
+            |offset| Ok((vec![1; 3], Some(offset + 3))),
+        );
+        assert_eq!(
+            item_capped,
+            Err(PaginationError::PaginationLimitExceeded { pages: 2, items: 6 })
+        );
+
+        let finite = drain_pages(vec![0], Some(2), limits, |remaining| {
+            Ok((
+                vec![remaining - 1],
+                (remaining > 1).then_some(remaining - 1),
+            ))
+        });
+        assert_eq!(finite, Ok(vec![0, 1, 0]));
+        assert_eq!(
+            String::from(PaginationError::Request("HTTP 500".to_string())),
+            "HTTP 500"
+        );
+    }
+}
@@ -0,0 +1,73 @@
+//! Switching tools off through a server's `disabled_tools` config.
+//!
+//! A disabled tool is dropped from the `tools()` listing and refuses to run. The refusal is
+//! wired up once per contract with `#[smart_contract(tool_gate = ...)]`, whose gate method calls
+//! [`check_tool_enabled`] before every exported method.
+
+/// Error returned by every tool switched off through `disabled_tools`.
+pub const TOOL_DISABLED: &str = "tool disabled by configuration";
+
+/// Fails with [`TOOL_DISABLED`] if `tool` is one of `disabled_tools`.
+pub fn check_tool_enabled(disabled_tools: &[String], tool: &str) -> Result<(), String> {
+    if disabled_tools
+        .iter()
+        .any(|disabled| disabled.trim() == tool)
+    {
+        Err(TOOL_DISABLED.to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// The `tools()` listing without the entries for `disabled_tools`.
+pub fn filter_tools(tools: &str, disabled_tools: &[String]) -> String {
+    if disabled_tools.is_empty() {
+        return tools.to_string();
+    }
+    let Ok(serde_json::Value::Array(entries)) = serde_json::from_str::<serde_json::Value>(tools)
+    else {
+        return tools.to_string();
+    };
+    let enabled: Vec<serde_json::Value> = entries
+        .into_iter()
+        .filter(|entry| {
+            entry["function"]["name"]
+                .as_str()
+                .is_none_or(|name| check_tool_enabled(disabled_tools, name).is_ok())
+        })
+        .collect();
+    serde_json::to_string_pretty(&enabled).unwrap_or_else(|_| tools.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A disabled tool refuses to run and is dropped from the tool list, while the other tools
+    /// stay listed and callable.
+    #[test]
+    fn disabled_tool_errors_and_is_hidden() {
+        let disabled = vec!["delete_incident".to_string()];
+        assert_eq!(
+            check_tool_enabled(&disabled, "delete_incident"),
+            Err(TOOL_DISABLED.to_string())
+        );
+        assert!(check_tool_enabled(&disabled, "get_incident").is_ok());
+
+        let tools = r#"[
+  {"type": "function", "function": {"name": "get_incident", "parameters": {}}},
+  {"type": "function", "function": {"name": "delete_incident", "parameters": {}}}
+]"#;
+        let listed: serde_json::Value =
+            serde_json::from_str(&filter_tools(tools, &disabled)).unwrap();
+        let names: Vec<&str> = listed
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|entry| entry["function"]["name"].as_str())
+            .collect();
+        assert_eq!(names, vec!["get_incident"]);
+
+        assert_eq!(filter_tools(tools, &[]), tools);
+    }
+}
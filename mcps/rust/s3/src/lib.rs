@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use weil_macros::{WeilType, constructor, query, smart_contract};
 use weil_rs::collections::WeilId;
 use weil_rs::config::Secrets;
+use weil_rs::errors::ErrorCode;
 use weil_rs::runtime::Runtime;
 use weil_rs::s3::S3;
 use weil_rs::webserver::WebServer;
@@ -220,7 +221,7 @@ impl AwsS3 for AwsS3ContractState {
             bucket,
             key,
         };
-        S3::download(params).map_err(|e| e.to_string())
+        S3::download(params).map_err(|e| ErrorCode::Upstream.tag(e))
     }
 
     /// Lists objects in an S3 bucket with optional prefix filter.
@@ -252,7 +253,7 @@ impl AwsS3 for AwsS3ContractState {
             bucket,
             prefix,
         };
-        let keys = S3::list(params).map_err(|e| e.to_string())?;
+        let keys = S3::list(params).map_err(|e| ErrorCode::Upstream.tag(e))?;
         Ok(keys
             .into_iter()
             .map(|key| S3ObjectInfo {
@@ -288,7 +289,7 @@ impl AwsS3 for AwsS3ContractState {
             bucket,
             key,
         };
-        S3::delete(params).map(|_| ()).map_err(|e| e.to_string())
+        S3::delete(params).map(|_| ()).map_err(|e| ErrorCode::Upstream.tag(e))
     }
 
     /// Downloads content from an external URL and uploads it to S3.
@@ -333,7 +334,7 @@ impl AwsS3 for AwsS3ContractState {
             region: config.region,
             session_token: None,
         };
-        weil_rs::s3::S3::list_buckets(creds).map_err(|e| e.to_string())
+        weil_rs::s3::S3::list_buckets(creds).map_err(|e| ErrorCode::Upstream.tag(e))
     }
 
     /// Creates a new S3 bucket.
@@ -362,7 +363,7 @@ impl AwsS3 for AwsS3ContractState {
             bucket,
             region: None,
         };
-        weil_rs::s3::S3::create_bucket(sdk_params).map_err(|e| e.to_string())
+        weil_rs::s3::S3::create_bucket(sdk_params).map_err(|e| ErrorCode::Upstream.tag(e))
     }
 
     #[query]
@@ -380,7 +381,7 @@ impl AwsS3 for AwsS3ContractState {
             credentials: creds,
             bucket,
         };
-        weil_rs::s3::S3::delete_bucket(sdk_params).map_err(|e| e.to_string())
+        weil_rs::s3::S3::delete_bucket(sdk_params).map_err(|e| ErrorCode::Upstream.tag(e))
     }
 
     /// Gets the location constraint of an S3 bucket.
@@ -408,7 +409,7 @@ impl AwsS3 for AwsS3ContractState {
             credentials: creds,
             bucket,
         };
-        weil_rs::s3::S3::get_bucket_location(sdk_params).map_err(|e| e.to_string())
+        weil_rs::s3::S3::get_bucket_location(sdk_params).map_err(|e| ErrorCode::Upstream.tag(e))
     }
 
     /// Gets the access control list (ACL) of an S3 bucket.
@@ -436,7 +437,7 @@ impl AwsS3 for AwsS3ContractState {
             credentials: creds,
             bucket,
         };
-        weil_rs::s3::S3::get_bucket_acl(sdk_params).map_err(|e| e.to_string())
+        weil_rs::s3::S3::get_bucket_acl(sdk_params).map_err(|e| ErrorCode::Upstream.tag(e))
     }
 
     /// Gets the versioning status of an S3 bucket.
@@ -464,7 +465,7 @@ impl AwsS3 for AwsS3ContractState {
             credentials: creds,
             bucket,
         };
-        weil_rs::s3::S3::get_bucket_versioning(sdk_params).map_err(|e| e.to_string())
+        weil_rs::s3::S3::get_bucket_versioning(sdk_params).map_err(|e| ErrorCode::Upstream.tag(e))
     }
 
     /// Sets the versioning status of an S3 bucket.
@@ -494,7 +495,7 @@ impl AwsS3 for AwsS3ContractState {
             bucket,
             enabled,
         };
-        weil_rs::s3::S3::set_bucket_versioning(sdk_params).map_err(|e| e.to_string())
+        weil_rs::s3::S3::set_bucket_versioning(sdk_params).map_err(|e| ErrorCode::Upstream.tag(e))
     }
 
     /// Uploads text content to an S3 bucket.
@@ -523,8 +524,8 @@ impl AwsS3 for AwsS3ContractState {
             region: config.region,
             session_token: None,
         };
-        let result =
-            weil_rs::s3::S3::upload_text(creds, bucket, key, &text).map_err(|e| e.to_string())?;
+        let result = weil_rs::s3::S3::upload_text(creds, bucket, key, &text)
+            .map_err(|e| ErrorCode::Upstream.tag(e))?;
 
         // Try to shorten the URL, if it fails, return the original
         match shorten_url(&result) {
@@ -898,7 +899,7 @@ impl AwsS3ContractState {
         let file_size = self
             .web_server
             .size_bytes(file_path.clone())
-            .map_err(|e| format!("File not found: {}", e))?;
+            .map_err(|e| ErrorCode::NotFound.tag(format!("File not found: {}", e)))?;
 
         let chunk_size = self.web_server.get_chunk_size();
         let total_chunks = (file_size + chunk_size - 1) / chunk_size;
@@ -913,10 +914,10 @@ impl AwsS3ContractState {
             if status_code == 200 {
                 file_data.extend_from_slice(&chunk_data);
             } else {
-                return Err(format!(
+                return Err(ErrorCode::from_http_status(status_code).tag(format!(
                     "Failed to read chunk {}: HTTP {}",
                     chunk_index, status_code
-                ));
+                )));
             }
         }
 
@@ -945,7 +946,7 @@ impl AwsS3ContractState {
             key,
             content: data,
         };
-        S3::upload(params).map(|_| ()).map_err(|e| e.to_string())
+        S3::upload(params).map(|_| ()).map_err(|e| ErrorCode::Upstream.tag(e))
     }
 
     /// Downloads a file from an external URL as a stream and uploads it to S3 as a single object.
@@ -957,7 +958,9 @@ impl AwsS3ContractState {
     ) -> Result<String, String> {
         // Download file as a stream from the external URL
         let file = S3::download_file_stream(&url)
-            .map_err(|e| format!("Failed to download file stream: {}", e))?;
+            .map_err(|e| {
+                ErrorCode::Upstream.tag(format!("Failed to download file stream: {}", e))
+            })?;
 
         let config = self.secrets.config();
         let credentials = S3Credentials {
@@ -974,7 +977,9 @@ impl AwsS3ContractState {
         };
         // Upload the file stream to S3
         S3::upload_file_stream(&file, params)
-            .map_err(|e| format!("Failed to upload file stream to S3: {}", e))
+            .map_err(|e| {
+                ErrorCode::Upstream.tag(format!("Failed to upload file stream to S3: {}", e))
+            })
     }
 
     // Method to register uploaded files (call this when files are uploaded to WebServer)
@@ -982,7 +987,7 @@ impl AwsS3ContractState {
         // Check if file exists in WebServer
         self.web_server
             .size_bytes(file_path.clone())
-            .map_err(|e| format!("File not found: {}", e))?;
+            .map_err(|e| ErrorCode::NotFound.tag(format!("File not found: {}", e)))?;
 
         // Add to tracking if not already present
         if !self.uploaded_file_paths.contains(&file_path) {
@@ -1015,7 +1020,7 @@ impl AwsS3ContractState {
         let size_bytes = self
             .web_server
             .size_bytes(file_path.clone())
-            .map_err(|e| format!("File not found: {}", e))?;
+            .map_err(|e| ErrorCode::NotFound.tag(format!("File not found: {}", e)))?;
         let chunk_size = self.web_server.get_chunk_size();
         let total_chunks = (size_bytes + chunk_size - 1) / chunk_size;
 
@@ -1,5 +1,10 @@
+use std::collections::HashMap;
+use weil_rs::errors::ErrorCode;
 use weil_rs::http::{HttpClient, HttpMethod};
 
+/// User-Agent identifying this applet to the shortening service.
+const USER_AGENT: &str = concat!("wadk-mcp/", env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
 /// Simple URL shortener using TinyURL API
 pub fn shorten_url(long_url: &str) -> Result<String, String> {
     // Basic URL validation
@@ -14,14 +19,17 @@ pub fn shorten_url(long_url: &str) -> Result<String, String> {
     let query_params = vec![("url".to_string(), long_url.to_string())];
 
     // Make HTTP request
+    let headers = HashMap::from([("User-Agent".to_string(), USER_AGENT.to_string())]);
     let response = HttpClient::request(api_url, HttpMethod::Get)
+        .headers(headers)
         .query(query_params)
         .send()
         .map_err(|e| format!("HTTP error: {}", e))?;
 
     // Check if request was successful
     if response.status() != 200 {
-        return Err(format!("TinyURL API returned status: {}", response.status()));
+        let status = response.status();
+        return Err(ErrorCode::from_http_status(status).tag(format!("TinyURL API returned status: {}", status)));
     }
 
     let shortened_url = response.text();
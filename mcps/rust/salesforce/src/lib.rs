@@ -28,6 +28,7 @@ use std::collections::HashMap;
 use weil_macros::{WeilType, constructor, query, smart_contract};
 use weil_rs::config::Secrets;
 use weil_rs::crm::{CrudOperation, Salesforce};
+use weil_rs::errors::ErrorCode;
 
 /// OAuth and user credentials required to authenticate with Salesforce.
 ///
@@ -161,7 +162,9 @@ impl SalesforceCRM for SalesforceCRMContractState {
     ) -> Result<String, String> {
         let credentials = self.secrets.config();
         if field_names.len() != field_values.len() {
-            return Err("Field names and values must have the same length".to_string());
+            return Err(
+                ErrorCode::Validation.tag("Field names and values must have the same length")
+            );
         }
         let fields_json = self.create_fields_json(&field_names, &field_values)?;
         let sf = Salesforce {
@@ -177,7 +180,7 @@ impl SalesforceCRM for SalesforceCRMContractState {
             &fields_json,
             CrudOperation::Create,
         )
-        .map_err(|e| e.to_string())
+        .map_err(|e| ErrorCode::Upstream.tag(e))
     }
 
     /// See [`SalesforceCRM::read`].
@@ -185,7 +188,7 @@ impl SalesforceCRM for SalesforceCRMContractState {
     async fn read(&self, object_type: String, record_id: String) -> Result<String, String> {
         let credentials = self.secrets.config();
         if record_id.is_empty() {
-            return Err("Record ID is required for read operation".to_string());
+            return Err(ErrorCode::Validation.tag("Record ID is required for read operation"));
         }
 
         let sf = Salesforce {
@@ -202,7 +205,7 @@ impl SalesforceCRM for SalesforceCRMContractState {
             "", // No fields needed for read
             CrudOperation::Read,
         )
-        .map_err(|e| e.to_string())
+        .map_err(|e| ErrorCode::Upstream.tag(e))
     }
 
     /// See [`SalesforceCRM::update`].
@@ -216,7 +219,7 @@ impl SalesforceCRM for SalesforceCRMContractState {
     ) -> Result<String, String> {
         let credentials = self.secrets.config();
         if record_id.is_empty() {
-            return Err("Record ID is required for read operation".to_string());
+            return Err(ErrorCode::Validation.tag("Record ID is required for read operation"));
         }
 
         let fields_json = self.create_fields_json(&field_names, &field_values)?;
@@ -235,7 +238,7 @@ impl SalesforceCRM for SalesforceCRMContractState {
             &fields_json,
             CrudOperation::Update,
         )
-        .map_err(|e| e.to_string())
+        .map_err(|e| ErrorCode::Upstream.tag(e))
     }
 
     /// See [`SalesforceCRM::delete`].
@@ -243,7 +246,7 @@ impl SalesforceCRM for SalesforceCRMContractState {
     async fn delete(&self, object_type: String, record_id: String) -> Result<String, String> {
         let credentials = self.secrets.config();
         if record_id.is_empty() {
-            return Err("Record ID is required for read operation".to_string());
+            return Err(ErrorCode::Validation.tag("Record ID is required for read operation"));
         }
 
         let sf = Salesforce {
@@ -260,7 +263,7 @@ impl SalesforceCRM for SalesforceCRMContractState {
             "", // No fields needed for delete
             CrudOperation::Delete,
         )
-        .map_err(|e| e.to_string())
+        .map_err(|e| ErrorCode::Upstream.tag(e))
     }
 
     /// See [`SalesforceCRM::execute_soql_query`].
@@ -276,7 +279,7 @@ impl SalesforceCRM for SalesforceCRMContractState {
         };
 
         sf.call_salesforce_soql_query(&soql_query)
-            .map_err(|e| e.to_string())
+            .map_err(|e| ErrorCode::Upstream.tag(e))
     }
 
     /// See [`SalesforceCRM::get_object_metadata`].
@@ -292,7 +295,7 @@ impl SalesforceCRM for SalesforceCRMContractState {
         };
 
         sf.call_salesforce_describe_object(&object_name)
-            .map_err(|e| e.to_string())
+            .map_err(|e| ErrorCode::Upstream.tag(e))
     }
 
     /// See [`SalesforceCRM::run_apex_test`].
@@ -304,7 +307,7 @@ impl SalesforceCRM for SalesforceCRMContractState {
     #[query]
     async fn run_apex_test(&self, test_class_name: String) -> Result<String, String> {
         if test_class_name.is_empty() {
-            return Err("Test class name is required".to_string());
+            return Err(ErrorCode::Validation.tag("Test class name is required"));
         }
 
         // First, find the test class ID
@@ -316,15 +319,18 @@ impl SalesforceCRM for SalesforceCRMContractState {
         let class_result = self.execute_soql_query(find_class_query).await?;
 
         // Parse the result to extract class ID
-        let parsed: serde_json::Value = serde_json::from_str(&class_result)
-            .map_err(|e| format!("Failed to parse class query result: {}", e))?;
+        let parsed: serde_json::Value = serde_json::from_str(&class_result).map_err(|e| {
+            ErrorCode::Upstream.tag(format!("Failed to parse class query result: {}", e))
+        })?;
 
         let class_id = parsed
             .as_array()
             .and_then(|arr| arr.first())
             .and_then(|record| record.get("Id"))
             .and_then(|id| id.as_str())
-            .ok_or_else(|| format!("Test class '{}' not found", test_class_name))?;
+            .ok_or_else(|| {
+                ErrorCode::NotFound.tag(format!("Test class '{}' not found", test_class_name))
+            })?;
 
         // Create test queue item to run the specific test
         let queue_item_id = self
@@ -342,15 +348,16 @@ impl SalesforceCRM for SalesforceCRMContractState {
         );
 
         let job_result = self.execute_soql_query(job_query).await?;
-        let job_parsed: serde_json::Value = serde_json::from_str(&job_result)
-            .map_err(|e| format!("Failed to parse job query result: {}", e))?;
+        let job_parsed: serde_json::Value = serde_json::from_str(&job_result).map_err(|e| {
+            ErrorCode::Upstream.tag(format!("Failed to parse job query result: {}", e))
+        })?;
 
         let job_id = job_parsed
             .as_array()
             .and_then(|arr| arr.first())
             .and_then(|record| record.get("ParentJobId"))
             .and_then(|id| id.as_str())
-            .ok_or_else(|| "Failed to get test job ID from queue item".to_string())?;
+            .ok_or_else(|| ErrorCode::Upstream.tag("Failed to get test job ID from queue item"))?;
 
         Ok(job_id.to_string())
     }
@@ -359,7 +366,7 @@ impl SalesforceCRM for SalesforceCRMContractState {
     #[query]
     async fn get_test_status(&self, job_id: String) -> Result<String, String> {
         if job_id.is_empty() {
-            return Err("Job ID is required".to_string());
+            return Err(ErrorCode::Validation.tag("Job ID is required"));
         }
 
         let status_query = format!(
@@ -370,14 +377,15 @@ impl SalesforceCRM for SalesforceCRMContractState {
         let result = self.execute_soql_query(status_query).await?;
 
         // Parse and format the status information
-        let parsed: serde_json::Value = serde_json::from_str(&result)
-            .map_err(|e| format!("Failed to parse status query result: {}", e))?;
+        let parsed: serde_json::Value = serde_json::from_str(&result).map_err(|e| {
+            ErrorCode::Upstream.tag(format!("Failed to parse status query result: {}", e))
+        })?;
 
         if let Some(job) = parsed.as_array().and_then(|arr| arr.first()) {
             Ok(serde_json::to_string_pretty(job)
                 .unwrap_or_else(|_| "Failed to format job status".to_string()))
         } else {
-            Err("Job not found".to_string())
+            Err(ErrorCode::NotFound.tag("Job not found"))
         }
     }
 
@@ -393,12 +401,13 @@ impl SalesforceCRM for SalesforceCRMContractState {
             .execute_soql_query(test_classes_query.to_string())
             .await?;
 
-        let parsed: serde_json::Value = serde_json::from_str(&classes_result)
-            .map_err(|e| format!("Failed to parse test classes result: {}", e))?;
+        let parsed: serde_json::Value = serde_json::from_str(&classes_result).map_err(|e| {
+            ErrorCode::Upstream.tag(format!("Failed to parse test classes result: {}", e))
+        })?;
 
         let test_classes = parsed
             .as_array()
-            .ok_or_else(|| "Invalid test classes response format".to_string())?;
+            .ok_or_else(|| ErrorCode::Upstream.tag("Invalid test classes response format"))?;
 
         if test_classes.is_empty() {
             return Ok("No test classes found in the org".to_string());
@@ -430,8 +439,9 @@ impl SalesforceCRM for SalesforceCRMContractState {
         );
 
         let job_result = self.execute_soql_query(job_query).await?;
-        let job_parsed: serde_json::Value = serde_json::from_str(&job_result)
-            .map_err(|e| format!("Failed to parse job query result: {}", e))?;
+        let job_parsed: serde_json::Value = serde_json::from_str(&job_result).map_err(|e| {
+            ErrorCode::Upstream.tag(format!("Failed to parse job query result: {}", e))
+        })?;
 
         let job_ids: Vec<String> = job_parsed
             .as_array()
@@ -443,7 +453,7 @@ impl SalesforceCRM for SalesforceCRMContractState {
             .collect();
 
         if job_ids.is_empty() {
-            return Err("Failed to get any test job IDs".to_string());
+            return Err(ErrorCode::Upstream.tag("Failed to get any test job IDs"));
         }
 
         let unique_job_ids: std::collections::HashSet<String> = job_ids.into_iter().collect();
@@ -476,7 +486,7 @@ impl SalesforceCRM for SalesforceCRMContractState {
         permission_set_name: String,
     ) -> Result<String, String> {
         if permission_set_name.is_empty() {
-            return Err("Permission set name is required".to_string());
+            return Err(ErrorCode::Validation.tag("Permission set name is required"));
         }
 
         let perm_set_query = format!(
@@ -486,18 +496,24 @@ impl SalesforceCRM for SalesforceCRMContractState {
 
         let perm_set_result = self.execute_soql_query(perm_set_query).await?;
 
-        let parsed: serde_json::Value = serde_json::from_str(&perm_set_result)
-            .map_err(|e| format!("Failed to parse permission set result: {}", e))?;
+        let parsed: serde_json::Value = serde_json::from_str(&perm_set_result).map_err(|e| {
+            ErrorCode::Upstream.tag(format!("Failed to parse permission set result: {}", e))
+        })?;
 
         let perm_set = parsed
             .as_array()
             .and_then(|arr| arr.first())
-            .ok_or_else(|| format!("Permission set '{}' not found", permission_set_name))?;
+            .ok_or_else(|| {
+                ErrorCode::NotFound.tag(format!(
+                    "Permission set '{}' not found",
+                    permission_set_name
+                ))
+            })?;
 
         let perm_set_id = perm_set
             .get("Id")
             .and_then(|id| id.as_str())
-            .ok_or_else(|| "Permission set ID not found".to_string())?;
+            .ok_or_else(|| ErrorCode::Upstream.tag("Permission set ID not found"))?;
 
         let assignments_query = format!(
             "SELECT Assignee.Id, Assignee.Username, Assignee.Name FROM PermissionSetAssignment WHERE PermissionSetId = '{}'",
@@ -510,8 +526,10 @@ impl SalesforceCRM for SalesforceCRMContractState {
         let mut result = serde_json::Map::new();
         result.insert("permission_set".to_string(), perm_set.clone());
 
-        let assignments: serde_json::Value = serde_json::from_str(&assignments_result)
-            .map_err(|e| format!("Failed to parse assignments result: {}", e))?;
+        let assignments: serde_json::Value =
+            serde_json::from_str(&assignments_result).map_err(|e| {
+                ErrorCode::Upstream.tag(format!("Failed to parse assignments result: {}", e))
+            })?;
         result.insert("assigned_users".to_string(), assignments);
 
         Ok(serde_json::to_string_pretty(&result)
@@ -766,7 +784,9 @@ impl SalesforceCRMContractState {
         field_values: &[String],
     ) -> Result<String, String> {
         if field_names.len() != field_values.len() {
-            return Err("Field names and values must have the same length".to_string());
+            return Err(
+                ErrorCode::Validation.tag("Field names and values must have the same length")
+            );
         }
 
         let mut fields = HashMap::new();
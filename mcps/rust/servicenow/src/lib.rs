@@ -1,68 +1,562 @@
 use base64::{Engine as _, engine::general_purpose};
+use mcp_http::JsonApi;
+use mcp_http::ids::id_text;
+use mcp_http::pagination::{PaginationLimits, drain_pages};
+use mcp_http::tools::{TOOL_DISABLED, check_tool_enabled, filter_tools};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use weil_macros::{WeilType, constructor, query, smart_contract};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use weil_macros::{WeilType, constructor, query, smart_contract, tool_schema};
 use weil_rs::config::Secrets;
-use weil_rs::http::{HttpClient, HttpMethod};
+use weil_rs::errors::{DiagnosisRule, ErrorCode, diagnosis_for};
+use weil_rs::http::HttpMethod;
+use weil_rs::runtime::Runtime;
 
 mod structs;
 use structs::*;
 
+#[tool_schema]
 trait Servicenow {
     fn new() -> Result<Self, String>
     where
         Self: Sized;
 
     // Incident Management
+    /// create an incident on servicenow
+    ///
+    /// # Parameters
+    /// - `short_description`: a short description for the incident
+    /// - `description`: a description for the incident
+    /// - `priority`: priority for the incident
+    /// - `external_ref`: reference of the record in an external system (optional), stored as
+    ///   correlation_id/correlation_display so it can be found with find_by_external_ref
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn create_incident(
         &self,
         short_description: String,
         description: String,
         priority: String,
+        external_ref: Option<String>,
+        instance: Option<String>,
+    ) -> Result<Incident, String>;
+    /// create several incidents in one round-trip through the batch api; each incident gets its own
+    /// result, so some can fail while the rest are created
+    ///
+    /// # Parameters
+    /// - `incidents`: incidents to create
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn create_incidents_bulk(
+        &self,
+        #[schema(r#"{"type": "array", "items": {"type": "object", "properties": {"short_description": {"type": "string", "description": "a short description for the incident"}, "description": {"type": "string", "description": "a description for the incident"}, "priority": {"type": "string", "description": "priority for the incident"}, "caller_id": {"type": "string", "description": "system id of the user reporting the incident (optional)"}, "assignment_group": {"type": "string", "description": "system id of the group to assign the incident to (optional)"}}, "required": ["short_description", "description", "priority"]}}"#)]
+        incidents: Vec<NewIncident>,
+        instance: Option<String>,
+    ) -> Result<Vec<BulkIncidentResult>, String>;
+    /// create an incident from a servicenow template (sys_template), optionally overriding some of
+    /// its fields
+    ///
+    /// # Parameters
+    /// - `template_sys_id`: system id of the incident template
+    /// - `overrides`: JSON object of incident fields that replace or add to the template's values,
+    ///   e.g. {"priority": "1"}; an empty string applies the template as is
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn create_incident_from_template(
+        &self,
+        template_sys_id: String,
+        overrides: String,
+        instance: Option<String>,
+    ) -> Result<Incident, String>;
+    /// create an incident from a raw alert email: the subject becomes the short description, the
+    /// body the description, and severity keywords (critical, sev1, outage, urgent, warning, ...)
+    /// suggest the priority
+    ///
+    /// # Parameters
+    /// - `raw_email`: the raw email, with or without its headers
+    /// - `external_ref`: reference of the record in an external system (optional), stored as
+    ///   correlation_id/correlation_display so it can be found with find_by_external_ref
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn create_incident_from_email(
+        &self,
+        raw_email: String,
+        external_ref: Option<String>,
+        instance: Option<String>,
+    ) -> Result<Incident, String>;
+    /// get an incident from servicenow
+    ///
+    /// # Parameters
+    /// - `sys_id`: id of the incident
+    /// - `fields`: incident fields to return, e.g. ["number", "state"] (optional, all fields when
+    ///   omitted); sys_id is always returned
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn get_incident(
+        &self,
+        sys_id: String,
+        fields: Option<Vec<String>>,
+        instance: Option<String>,
     ) -> Result<Incident, String>;
-    async fn get_incident(&self, sys_id: String) -> Result<Incident, String>;
-    async fn delete_incident(&self, sys_id: String) -> Result<(), String>;
-    async fn query_incidents(&self, query_str: String, limit: u32)
-    -> Result<Vec<Incident>, String>;
+    /// fetch several incidents by system id in as few requests as possible; they come back in the
+    /// requested order and ids that don't exist are skipped
+    ///
+    /// # Parameters
+    /// - `sys_ids`: system ids of the incidents
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn get_incidents_by_ids(
+        &self,
+        sys_ids: Vec<String>,
+        instance: Option<String>,
+    ) -> Result<Vec<Incident>, String>;
+    /// delete an incident on servicenow
+    ///
+    /// # Parameters
+    /// - `sys_id`: id of the incident
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn delete_incident(&self, sys_id: String, instance: Option<String>)
+    -> Result<(), String>;
+    /// delete an incident together with its tasks, affected-CI links and attachments (as
+    /// configured), removing those first; journal entries are kept. Refuses when a table has too
+    /// many related records
+    ///
+    /// # Parameters
+    /// - `sys_id`: id of the incident
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn delete_incident_cascade(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<CascadeDeleteReport, String>;
+    /// query incidents on servicenow
+    ///
+    /// # Parameters
+    /// - `query_str`: the query to run
+    /// - `limit`: the limit on the number of results
+    /// - `offset`: number of matching records to skip (optional, default 0)
+    /// - `fetch_all`: keep fetching pages of limit records until none are left and return them all
+    ///   (optional, default false)
+    /// - `fields`: incident fields to return, e.g. ["number", "state"] (optional, all fields when
+    ///   omitted); sys_id is always returned
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn query_incidents(
+        &self,
+        query_str: String,
+        limit: u32,
+        offset: Option<u32>,
+        fetch_all: Option<bool>,
+        fields: Option<Vec<String>>,
+        instance: Option<String>,
+    ) -> Result<Vec<Incident>, String>;
+    /// add a comment to an incident
+    ///
+    /// # Parameters
+    /// - `incident_sys_id`: system id of the incident
+    /// - `comment`: comment text to add
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn add_comment(
         &self,
         incident_sys_id: String,
         comment: String,
+        instance: Option<String>,
+    ) -> Result<Comment, String>;
+    /// add a work note (visible to the support team only) to an incident
+    ///
+    /// # Parameters
+    /// - `incident_sys_id`: system id of the incident
+    /// - `note`: work note text to add
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn add_work_note(
+        &self,
+        incident_sys_id: String,
+        note: String,
+        instance: Option<String>,
     ) -> Result<Comment, String>;
+    /// post the same comment or work note to several records at once, e.g. to update every incident
+    /// affected by a major incident; reports which records got it and which failed
+    ///
+    /// # Parameters
+    /// - `sys_ids`: system ids of the records to post to
+    /// - `comment`: text to post
+    /// - `visibility`: Comment for a customer-visible comment, WorkNote for a work note visible to
+    ///   the support team only
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn add_comment_bulk(
+        &self,
+        sys_ids: Vec<String>,
+        comment: String,
+        #[schema(r#"{"type": "string", "enum": ["Comment", "WorkNote"]}"#)]
+        visibility: CommentVisibility,
+        instance: Option<String>,
+    ) -> Result<BulkResult<String>, String>;
+    /// read back an incident's comments and work notes, oldest first
+    ///
+    /// # Parameters
+    /// - `incident_sys_id`: system id of the incident
+    /// - `field`: journal field to read, comments or work_notes (optional, both when omitted)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn get_incident_journal(
+        &self,
+        incident_sys_id: String,
+        field: Option<String>,
+        instance: Option<String>,
+    ) -> Result<Vec<JournalEntry>, String>;
+    /// move an incident to another state (e.g. 2 for In Progress, 6 for Resolved); refused if the
+    /// configured incident_transitions don't allow it from the current state
+    ///
+    /// # Parameters
+    /// - `sys_id`: system id of the incident
+    /// - `to_state`: the state to move the incident to
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn transition_incident(
+        &self,
+        sys_id: String,
+        to_state: String,
+        instance: Option<String>,
+    ) -> Result<Incident, String>;
+    /// resolve an incident
+    ///
+    /// # Parameters
+    /// - `sys_id`: system id of the incident
+    /// - `resolution_notes`: resolution notes
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn resolve_incident(
         &self,
         sys_id: String,
         resolution_notes: String,
+        instance: Option<String>,
+    ) -> Result<Incident, String>;
+    /// update any fields of an incident (e.g. assigned_to, category, urgency) in a single call
+    ///
+    /// # Parameters
+    /// - `sys_id`: system id of the incident
+    /// - `fields`: incident field names mapped to their new values, e.g. {"urgency": "1",
+    ///   "category": "network"}
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn update_incident(
+        &self,
+        sys_id: String,
+        fields: HashMap<String, String>,
+        instance: Option<String>,
     ) -> Result<Incident, String>;
+    /// attach a file stored in IMFS (e.g. a log or screenshot) to an incident
+    ///
+    /// # Parameters
+    /// - `incident_sys_id`: system id of the incident
+    /// - `file_name`: file name to give the attachment, e.g. disk.log
+    /// - `content_type`: MIME type of the file, e.g. text/plain or image/png
+    /// - `file_descriptor`: IMFS file descriptor of the file to attach
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn attach_file_to_incident(
+        &self,
+        incident_sys_id: String,
+        file_name: String,
+        content_type: String,
+        file_descriptor: String,
+        instance: Option<String>,
+    ) -> Result<Attachment, String>;
+    /// list incidents from servicenow
+    ///
+    /// # Parameters
+    /// - `query_str`: query string (optional)
+    /// - `limit`: limit on number of results (optional)
+    /// - `offset`: number of matching records to skip (optional, default 0)
+    /// - `fetch_all`: keep fetching pages of limit records until none are left and return them all
+    ///   (optional, default false)
+    /// - `fields`: incident fields to return, e.g. ["number", "state"] (optional, all fields when
+    ///   omitted); sys_id is always returned
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn list_incidents(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
+        offset: Option<u32>,
+        fetch_all: Option<bool>,
+        fields: Option<Vec<String>>,
+        instance: Option<String>,
+    ) -> Result<Vec<Incident>, String>;
+    /// list one page of incidents; pass the returned next_offset as offset to get the following
+    /// page, until next_offset is null
+    ///
+    /// # Parameters
+    /// - `query_str`: query string (optional)
+    /// - `limit`: page size (optional, defaults to 100)
+    /// - `offset`: offset of the first record of the page (optional, defaults to 0)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn list_incidents_paged(
+        &self,
+        query_str: Option<String>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        instance: Option<String>,
+    ) -> Result<IncidentPage, String>;
+    /// find records in a table that were created with the given external reference (matched on
+    /// correlation_id)
+    ///
+    /// # Parameters
+    /// - `table`: name of the table to search, e.g. incident or change_request
+    /// - `external_ref`: reference of the record in the external system
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn find_by_external_ref(
+        &self,
+        table: String,
+        external_ref: String,
+        instance: Option<String>,
+    ) -> Result<Vec<serde_json::Value>, String>;
+    /// find clusters of duplicate incidents among those matching a query, grouped by one or more
+    /// fields, with the earliest incident of each cluster suggested as the primary
+    ///
+    /// # Parameters
+    /// - `query`: encoded query selecting the incidents to check, e.g.
+    ///   active=true^sys_created_on>javascript:gs.hoursAgo(24)
+    /// - `group_by_field`: field, or comma-separated fields, whose values must match for incidents
+    ///   to be duplicates, e.g. cmdb_ci,short_description
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn dedupe_incidents(
+        &self,
+        query: String,
+        group_by_field: String,
+        instance: Option<String>,
+    ) -> Result<Vec<DedupeGroup>, String>;
+    /// mark incidents as duplicates of a primary incident by setting their parent incident
+    ///
+    /// # Parameters
+    /// - `primary_sys_id`: system id of the primary incident
+    /// - `duplicate_sys_ids`: system ids of the duplicate incidents
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn link_duplicate_incidents(
+        &self,
+        primary_sys_id: String,
+        duplicate_sys_ids: Vec<String>,
+        instance: Option<String>,
     ) -> Result<Vec<Incident>, String>;
+    /// list the active slas of an incident, most urgent (highest business percentage) first
+    ///
+    /// # Parameters
+    /// - `incident_sys_id`: system id of the incident
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn list_active_slas_for_incident(
+        &self,
+        incident_sys_id: String,
+        instance: Option<String>,
+    ) -> Result<Vec<TaskSla>, String>;
+    /// list active slas that have already breached, most overdue (highest business percentage)
+    /// first
+    ///
+    /// # Parameters
+    /// - `limit`: maximum number of slas to return (optional, defaults to 100)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn list_breaching_slas(
+        &self,
+        limit: Option<u32>,
+        instance: Option<String>,
+    ) -> Result<Vec<TaskSla>, String>;
+    /// find incidents whose active slas have not breached yet but have used more than a given share
+    /// of their allowed time, most urgent first, each with the sla at risk; meant for periodic
+    /// sweeps
+    ///
+    /// # Parameters
+    /// - `threshold_percent`: only slas that have used more than this percentage of their business
+    ///   time (0 to 100), e.g. 80
+    /// - `limit`: maximum number of slas to return
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn incidents_near_sla_breach(
+        &self,
+        threshold_percent: u8,
+        limit: u32,
+        instance: Option<String>,
+    ) -> Result<Vec<SlaBreachRisk>, String>;
+    /// list the related lists on a table's form (e.g. task_ci for affected CIs,
+    /// sysapproval_approver for approvals) with the child table and how it relates, to guide what
+    /// else to fetch for a record
+    ///
+    /// # Parameters
+    /// - `table`: the table whose form to inspect, e.g. incident
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn get_related_lists(
+        &self,
+        table: String,
+        instance: Option<String>,
+    ) -> Result<Vec<RelatedList>, String>;
+    /// list records of any table as CSV with a header row, selecting only the given fields (in that
+    /// column order); reference fields hold the referenced sys_id
+    ///
+    /// # Parameters
+    /// - `table`: the table to read, e.g. incident
+    /// - `query`: encoded query to filter the records (optional)
+    /// - `fields`: fields to include as columns, in order; dot-walked fields such as
+    ///   caller_id.email are allowed
+    /// - `limit`: maximum number of records (optional, defaults to 100)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn list_records_csv(
+        &self,
+        table: String,
+        query: Option<String>,
+        fields: Vec<String>,
+        limit: Option<u32>,
+        instance: Option<String>,
+    ) -> Result<String, String>;
+    /// update the record of a table whose match field has the given value, or create it if there is
+    /// none; fails if several records match. Returns the record and whether it was created
+    ///
+    /// # Parameters
+    /// - `table`: the table to write to, e.g. cmdb_ci_computer
+    /// - `match_field`: field identifying the record, e.g. correlation_id or u_asset_tag
+    /// - `match_value`: value of the match field; set on the record when it is created
+    /// - `fields`: JSON object of field values to write, e.g. {"name": "Laptop"}
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn upsert_record(
+        &self,
+        table: String,
+        match_field: String,
+        match_value: String,
+        fields: String,
+        instance: Option<String>,
+    ) -> Result<(serde_json::Value, bool), String>;
 
     // Service Catalog
+    /// list service catalog items
+    ///
+    /// # Parameters
+    /// - `query_str`: query string (optional)
+    /// - `limit`: limit on number of results (optional)
+    /// - `offset`: number of matching records to skip (optional, default 0)
+    /// - `fetch_all`: keep fetching pages of limit records until none are left and return them all
+    ///   (optional, default false)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn list_catalog_items(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
+        offset: Option<u32>,
+        fetch_all: Option<bool>,
+        instance: Option<String>,
     ) -> Result<Vec<CatalogItem>, String>;
-    async fn get_catalog_item(&self, sys_id: String) -> Result<CatalogItem, String>;
+    /// get a specific catalog item
+    ///
+    /// # Parameters
+    /// - `sys_id`: system id of the catalog item
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn get_catalog_item(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<CatalogItem, String>;
+    /// get the picture of a catalog item as a data URI, for rendering the catalog to a user
+    ///
+    /// # Parameters
+    /// - `item_sys_id`: system id of the catalog item
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn get_catalog_item_picture(
+        &self,
+        item_sys_id: String,
+        instance: Option<String>,
+    ) -> Result<String, String>;
+    /// get the antivirus scan status of an attachment: clean, pending, infected or unknown. Don't
+    /// open or forward attachments that are not clean
+    ///
+    /// # Parameters
+    /// - `attachment_sys_id`: system id of the attachment
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn get_attachment_scan_status(
+        &self,
+        attachment_sys_id: String,
+        instance: Option<String>,
+    ) -> Result<String, String>;
+    /// get the catalog variables answered on a requested item (RITM), as question and value pairs
+    ///
+    /// # Parameters
+    /// - `ritm_sys_id`: system id of the requested item (sc_req_item)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn get_request_variables(
+        &self,
+        ritm_sys_id: String,
+        instance: Option<String>,
+    ) -> Result<Vec<(String, String)>, String>;
+    /// list catalog categories
+    ///
+    /// # Parameters
+    /// - `query_str`: query string (optional)
+    /// - `limit`: limit on number of results (optional)
+    /// - `offset`: number of matching records to skip (optional, default 0)
+    /// - `fetch_all`: keep fetching pages of limit records until none are left and return them all
+    ///   (optional, default false)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn list_catalog_categories(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
+        offset: Option<u32>,
+        fetch_all: Option<bool>,
+        instance: Option<String>,
     ) -> Result<Vec<CatalogCategory>, String>;
+    /// create a new catalog category
+    ///
+    /// # Parameters
+    /// - `title`: category title
+    /// - `description`: category description (optional)
+    /// - `parent_category_id`: parent category id (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn create_catalog_category(
         &self,
         title: String,
         description: Option<String>,
-        parent: Option<String>,
+        parent_category_id: Option<String>,
+        instance: Option<String>,
     ) -> Result<CatalogCategory, String>;
+    /// move catalog items between categories
+    ///
+    /// # Parameters
+    /// - `item_sys_ids`: array of catalog item system ids
+    /// - `target_category_sys_id`: target category system id
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn move_catalog_items(
         &self,
         item_sys_ids: Vec<String>,
         target_category_sys_id: String,
+        instance: Option<String>,
     ) -> Result<(), String>;
+    /// create a new variable for a catalog item
+    ///
+    /// # Parameters
+    /// - `catalog_item_sys_id`: catalog item system id
+    /// - `name`: variable name
+    /// - `question_text`: question text for the variable
+    /// - `var_type`: variable type
+    /// - `mandatory`: whether the variable is mandatory
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn create_catalog_item_variable(
         &self,
         catalog_item_sys_id: String,
@@ -70,24 +564,65 @@ trait Servicenow {
         question_text: String,
         var_type: String,
         mandatory: bool,
+        instance: Option<String>,
     ) -> Result<CatalogVariable, String>;
+    /// list variables for a catalog item
+    ///
+    /// # Parameters
+    /// - `catalog_item_sys_id`: catalog item system id
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn list_catalog_item_variables(
         &self,
         catalog_item_sys_id: String,
+        instance: Option<String>,
     ) -> Result<Vec<CatalogVariable>, String>;
+    /// list service catalogs
+    ///
+    /// # Parameters
+    /// - `query_str`: query string (optional)
+    /// - `limit`: limit on number of results (optional)
+    /// - `offset`: number of matching records to skip (optional, default 0)
+    /// - `fetch_all`: keep fetching pages of limit records until none are left and return them all
+    ///   (optional, default false)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn list_catalogs(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
+        offset: Option<u32>,
+        fetch_all: Option<bool>,
+        instance: Option<String>,
     ) -> Result<Vec<serde_json::Value>, String>;
 
     // Catalog Optimization
+    /// get optimization recommendations for catalog items
+    ///
+    /// # Parameters
+    /// - `catalog_item_sys_id`: catalog item system id (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn get_optimization_recommendations(
         &self,
         catalog_item_sys_id: Option<String>,
+        instance: Option<String>,
     ) -> Result<Vec<serde_json::Value>, String>;
 
     // Change Management
+    #[allow(clippy::too_many_arguments)]
+    /// create a new change request
+    ///
+    /// # Parameters
+    /// - `short_description`: short description
+    /// - `description`: description
+    /// - `priority`: priority
+    /// - `risk`: risk level (optional)
+    /// - `impact`: impact level (optional)
+    /// - `external_ref`: reference of the record in an external system (optional), stored as
+    ///   correlation_id/correlation_display so it can be found with find_by_external_ref
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn create_change_request(
         &self,
         short_description: String,
@@ -95,33 +630,107 @@ trait Servicenow {
         priority: String,
         risk: Option<String>,
         impact: Option<String>,
+        external_ref: Option<String>,
+        instance: Option<String>,
     ) -> Result<ChangeRequest, String>;
+    /// list change requests
+    ///
+    /// # Parameters
+    /// - `query_str`: query string (optional)
+    /// - `limit`: limit on number of results (optional)
+    /// - `offset`: number of matching records to skip (optional, default 0)
+    /// - `fetch_all`: keep fetching pages of limit records until none are left and return them all
+    ///   (optional, default false)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn list_change_requests(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
+        offset: Option<u32>,
+        fetch_all: Option<bool>,
+        instance: Option<String>,
     ) -> Result<Vec<ChangeRequest>, String>;
-    async fn get_change_request_details(&self, sys_id: String) -> Result<ChangeRequest, String>;
+    /// get detailed information about a change request
+    ///
+    /// # Parameters
+    /// - `sys_id`: system id of the change request
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn get_change_request_details(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<ChangeRequest, String>;
+    /// add a task to a change request
+    ///
+    /// # Parameters
+    /// - `change_request_sys_id`: change request system id
+    /// - `short_description`: task short description
+    /// - `description`: task description
+    /// - `assigned_to`: assigned user (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn add_change_task(
         &self,
         change_request_sys_id: String,
         short_description: String,
         description: String,
         assigned_to: Option<String>,
+        instance: Option<String>,
     ) -> Result<ChangeTask, String>;
-    async fn submit_change_for_approval(&self, sys_id: String) -> Result<ChangeRequest, String>;
+    /// submit a change request for approval
+    ///
+    /// # Parameters
+    /// - `sys_id`: system id of the change request
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn submit_change_for_approval(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<ChangeRequest, String>;
+    /// approve a change request
+    ///
+    /// # Parameters
+    /// - `sys_id`: system id of the change request
+    /// - `approval_notes`: approval notes (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn approve_change(
         &self,
         sys_id: String,
         approval_notes: Option<String>,
+        instance: Option<String>,
     ) -> Result<ChangeRequest, String>;
+    /// reject a change request
+    ///
+    /// # Parameters
+    /// - `sys_id`: system id of the change request
+    /// - `rejection_notes`: rejection notes
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn reject_change(
         &self,
         sys_id: String,
         rejection_notes: String,
+        instance: Option<String>,
     ) -> Result<ChangeRequest, String>;
 
     // Agile Story Management
+    #[allow(clippy::too_many_arguments)]
+    /// create a new user story
+    ///
+    /// # Parameters
+    /// - `short_description`: short description
+    /// - `description`: description
+    /// - `priority`: priority (optional)
+    /// - `story_points`: story points (optional)
+    /// - `epic_sys_id`: epic system id (optional)
+    /// - `external_ref`: reference of the record in an external system (optional), stored as
+    ///   correlation_id/correlation_display so it can be found with find_by_external_ref
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn create_story(
         &self,
         short_description: String,
@@ -129,127 +738,406 @@ trait Servicenow {
         priority: Option<String>,
         story_points: Option<String>,
         epic_sys_id: Option<String>,
+        external_ref: Option<String>,
+        instance: Option<String>,
     ) -> Result<Story, String>;
+    /// list user stories
+    ///
+    /// # Parameters
+    /// - `query_str`: query string (optional)
+    /// - `limit`: limit on number of results (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn list_stories(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
+        instance: Option<String>,
     ) -> Result<Vec<Story>, String>;
-    async fn delete_story_dependency(&self, dependency_sys_id: String) -> Result<(), String>;
+    /// delete a dependency between stories
+    ///
+    /// # Parameters
+    /// - `dependency_sys_id`: dependency system id
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn delete_story_dependency(
+        &self,
+        dependency_sys_id: String,
+        instance: Option<String>,
+    ) -> Result<(), String>;
 
     // Agile Epic Management
+    /// create a new epic
+    ///
+    /// # Parameters
+    /// - `short_description`: short description
+    /// - `description`: description
+    /// - `priority`: priority (optional)
+    /// - `external_ref`: reference of the record in an external system (optional), stored as
+    ///   correlation_id/correlation_display so it can be found with find_by_external_ref
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn create_epic(
         &self,
         short_description: String,
         description: String,
         priority: Option<String>,
+        external_ref: Option<String>,
+        instance: Option<String>,
     ) -> Result<Epic, String>;
+    /// list epics
+    ///
+    /// # Parameters
+    /// - `query_str`: query string (optional)
+    /// - `limit`: limit on number of results (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn list_epics(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
+        instance: Option<String>,
     ) -> Result<Vec<Epic>, String>;
 
     // Scrum Task Management
+    /// create a new scrum task
+    ///
+    /// # Parameters
+    /// - `short_description`: short description
+    /// - `description`: description
+    /// - `story_sys_id`: story system id (optional)
+    /// - `assigned_to`: assigned user (optional)
+    /// - `external_ref`: reference of the record in an external system (optional), stored as
+    ///   correlation_id/correlation_display so it can be found with find_by_external_ref
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn create_scrum_task(
         &self,
         short_description: String,
         description: String,
         story_sys_id: Option<String>,
         assigned_to: Option<String>,
+        external_ref: Option<String>,
+        instance: Option<String>,
     ) -> Result<ScrumTask, String>;
+    /// list scrum tasks
+    ///
+    /// # Parameters
+    /// - `query_str`: query string (optional)
+    /// - `limit`: limit on number of results (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn list_scrum_tasks(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
+        instance: Option<String>,
     ) -> Result<Vec<ScrumTask>, String>;
 
     // Project Management
+    /// create a new project
+    ///
+    /// # Parameters
+    /// - `name`: project name
+    /// - `short_description`: short description
+    /// - `goal`: project goal (optional)
+    /// - `external_ref`: reference of the record in an external system (optional), stored as
+    ///   correlation_id/correlation_display so it can be found with find_by_external_ref
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn create_project(
         &self,
         name: String,
         short_description: String,
         goal: Option<String>,
+        external_ref: Option<String>,
+        instance: Option<String>,
     ) -> Result<Project, String>;
+    /// list projects
+    ///
+    /// # Parameters
+    /// - `query_str`: query string (optional)
+    /// - `limit`: limit on number of results (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn list_projects(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
+        instance: Option<String>,
     ) -> Result<Vec<Project>, String>;
 
     // Workflow Management
+    /// list workflows
+    ///
+    /// # Parameters
+    /// - `query_str`: query string (optional)
+    /// - `limit`: limit on number of results (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn list_workflows(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
+        instance: Option<String>,
     ) -> Result<Vec<Workflow>, String>;
-    async fn get_workflow(&self, sys_id: String) -> Result<Workflow, String>;
+    /// get a specific workflow
+    ///
+    /// # Parameters
+    /// - `sys_id`: system id of the workflow
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn get_workflow(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<Workflow, String>;
+    /// create a new workflow
+    ///
+    /// # Parameters
+    /// - `name`: workflow name
+    /// - `description`: description (optional)
+    /// - `table`: table name
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn create_workflow(
         &self,
         name: String,
         description: Option<String>,
         table: String,
+        instance: Option<String>,
     ) -> Result<Workflow, String>;
-    async fn delete_workflow(&self, sys_id: String) -> Result<(), String>;
+    /// delete a workflow
+    ///
+    /// # Parameters
+    /// - `sys_id`: system id of the workflow
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn delete_workflow(&self, sys_id: String, instance: Option<String>)
+    -> Result<(), String>;
 
     // Script Include Management
+    /// list script includes
+    ///
+    /// # Parameters
+    /// - `query_str`: query string (optional)
+    /// - `limit`: limit on number of results (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn list_script_includes(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
+        instance: Option<String>,
     ) -> Result<Vec<ScriptInclude>, String>;
-    async fn get_script_include(&self, sys_id: String) -> Result<ScriptInclude, String>;
+    /// get a specific script include
+    ///
+    /// # Parameters
+    /// - `sys_id`: system id of the script include
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn get_script_include(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<ScriptInclude, String>;
+    /// create a new script include
+    ///
+    /// # Parameters
+    /// - `name`: script include name
+    /// - `description`: description (optional)
+    /// - `script`: script content
+    /// - `api_name`: api name (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn create_script_include(
         &self,
         name: String,
         description: Option<String>,
         script: String,
         api_name: Option<String>,
+        instance: Option<String>,
     ) -> Result<ScriptInclude, String>;
-    async fn delete_script_include(&self, sys_id: String) -> Result<(), String>;
+    /// delete a script include
+    ///
+    /// # Parameters
+    /// - `sys_id`: system id of the script include
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn delete_script_include(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<(), String>;
 
     // Changeset Management
+    /// list changesets
+    ///
+    /// # Parameters
+    /// - `query_str`: query string (optional)
+    /// - `limit`: limit on number of results (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn list_changesets(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
+        instance: Option<String>,
     ) -> Result<Vec<Changeset>, String>;
-    async fn get_changeset_details(&self, sys_id: String) -> Result<Changeset, String>;
+    /// get detailed information about a changeset
+    ///
+    /// # Parameters
+    /// - `sys_id`: system id of the changeset
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn get_changeset_details(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<Changeset, String>;
+    /// create a new changeset
+    ///
+    /// # Parameters
+    /// - `name`: changeset name
+    /// - `description`: description (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn create_changeset(
         &self,
         name: String,
         description: Option<String>,
+        instance: Option<String>,
+    ) -> Result<Changeset, String>;
+    /// commit a changeset
+    ///
+    /// # Parameters
+    /// - `sys_id`: system id of the changeset
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn commit_changeset(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<Changeset, String>;
+    /// publish a changeset
+    ///
+    /// # Parameters
+    /// - `sys_id`: system id of the changeset
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn publish_changeset(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
     ) -> Result<Changeset, String>;
-    async fn commit_changeset(&self, sys_id: String) -> Result<Changeset, String>;
-    async fn publish_changeset(&self, sys_id: String) -> Result<Changeset, String>;
 
     // Knowledge Base Management
+    /// Knowledge Base Management create a new knowledge base
+    ///
+    /// # Parameters
+    /// - `title`: knowledge base title
+    /// - `description`: description (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn create_knowledge_base(
         &self,
         title: String,
         description: Option<String>,
+        instance: Option<String>,
     ) -> Result<KnowledgeBase, String>;
+    /// list knowledge bases
+    ///
+    /// # Parameters
+    /// - `query_str`: query string (optional)
+    /// - `limit`: limit on number of results (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn list_knowledge_bases(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
+        instance: Option<String>,
     ) -> Result<Vec<KnowledgeBase>, String>;
+    /// create a new knowledge article
+    ///
+    /// # Parameters
+    /// - `short_description`: short description
+    /// - `text`: article content
+    /// - `knowledge_base_sys_id`: knowledge base system id
+    /// - `category_sys_id`: category system id (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn create_article(
         &self,
         short_description: String,
         text: String,
         knowledge_base_sys_id: String,
         category_sys_id: Option<String>,
+        instance: Option<String>,
+    ) -> Result<KnowledgeArticle, String>;
+    /// publish a knowledge article
+    ///
+    /// # Parameters
+    /// - `sys_id`: system id of the article
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn publish_article(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
     ) -> Result<KnowledgeArticle, String>;
-    async fn publish_article(&self, sys_id: String) -> Result<KnowledgeArticle, String>;
+    /// list knowledge articles
+    ///
+    /// # Parameters
+    /// - `query_str`: query string (optional)
+    /// - `limit`: limit on number of results (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn list_articles(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
+        instance: Option<String>,
     ) -> Result<Vec<KnowledgeArticle>, String>;
-    async fn get_article(&self, sys_id: String) -> Result<KnowledgeArticle, String>;
+    /// get a specific knowledge article
+    ///
+    /// # Parameters
+    /// - `sys_id`: system id of the article
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn get_article(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<KnowledgeArticle, String>;
+    /// export the articles of a knowledge base to an IMFS file as newline-delimited JSON (one
+    /// object with sys_id, short_description and text per line) and return the file descriptor
+    ///
+    /// # Parameters
+    /// - `kb_sys_id`: system id of the knowledge base
+    /// - `file_name`: path of the IMFS file to write
+    /// - `max`: maximum number of articles to export
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn export_knowledge_to_imfs(
+        &self,
+        kb_sys_id: String,
+        file_name: String,
+        max: u32,
+        instance: Option<String>,
+    ) -> Result<String, String>;
 
     // User Management
+    /// create a new user
+    ///
+    /// # Parameters
+    /// - `user_name`: username
+    /// - `first_name`: first name
+    /// - `last_name`: last name
+    /// - `email`: email address
+    /// - `department`: department (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn create_user(
         &self,
         user_name: String,
@@ -257,43 +1145,121 @@ trait Servicenow {
         last_name: String,
         email: String,
         department: Option<String>,
+        instance: Option<String>,
     ) -> Result<User, String>;
-    async fn get_user(&self, identifier: String) -> Result<User, String>;
+    /// get a specific user
+    ///
+    /// # Parameters
+    /// - `identifier`: user id, username, or email
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn get_user(&self, identifier: String, instance: Option<String>) -> Result<User, String>;
+    /// list users
+    ///
+    /// # Parameters
+    /// - `query_str`: query string (optional)
+    /// - `limit`: limit on number of results (optional)
+    /// - `offset`: number of matching records to skip (optional, default 0)
+    /// - `fetch_all`: keep fetching pages of limit records until none are left and return them all
+    ///   (optional, default false)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn list_users(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
+        offset: Option<u32>,
+        fetch_all: Option<bool>,
+        instance: Option<String>,
     ) -> Result<Vec<User>, String>;
+    /// create a new group
+    ///
+    /// # Parameters
+    /// - `name`: group name
+    /// - `description`: description (optional)
+    /// - `manager`: group manager (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn create_group(
         &self,
         name: String,
         description: Option<String>,
         manager: Option<String>,
+        instance: Option<String>,
     ) -> Result<Group, String>;
+    /// add members to a group
+    ///
+    /// # Parameters
+    /// - `group_sys_id`: group system id
+    /// - `user_sys_ids`: array of user system ids
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn add_group_members(
         &self,
         group_sys_id: String,
         user_sys_ids: Vec<String>,
+        instance: Option<String>,
     ) -> Result<(), String>;
+    /// remove members from a group
+    ///
+    /// # Parameters
+    /// - `group_sys_id`: group system id
+    /// - `user_sys_ids`: array of user system ids
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn remove_group_members(
         &self,
         group_sys_id: String,
         user_sys_ids: Vec<String>,
+        instance: Option<String>,
     ) -> Result<(), String>;
+    /// list groups
+    ///
+    /// # Parameters
+    /// - `query_str`: query string (optional)
+    /// - `limit`: limit on number of results (optional)
+    /// - `offset`: number of matching records to skip (optional, default 0)
+    /// - `fetch_all`: keep fetching pages of limit records until none are left and return them all
+    ///   (optional, default false)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn list_groups(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
+        offset: Option<u32>,
+        fetch_all: Option<bool>,
+        instance: Option<String>,
     ) -> Result<Vec<Group>, String>;
 
     // UI Policy Tools
+    /// UI Policy Management create a ui policy
+    ///
+    /// # Parameters
+    /// - `name`: policy name
+    /// - `description`: description (optional)
+    /// - `table`: table name
+    /// - `catalog_item_sys_id`: catalog item system id (optional)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn create_ui_policy(
         &self,
         name: String,
         description: Option<String>,
         table: String,
         catalog_item_sys_id: Option<String>,
+        instance: Option<String>,
     ) -> Result<UIPolicy, String>;
+    /// create a ui policy action
+    ///
+    /// # Parameters
+    /// - `ui_policy_sys_id`: ui policy system id
+    /// - `name`: action name
+    /// - `description`: description (optional)
+    /// - `field_name`: field name
+    /// - `action`: action type
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
     async fn create_ui_policy_action(
         &self,
         ui_policy_sys_id: String,
@@ -301,691 +1267,1878 @@ trait Servicenow {
         description: Option<String>,
         field_name: String,
         action: String,
+        instance: Option<String>,
     ) -> Result<UIPolicyAction, String>;
 
+    // CMDB
+    /// get a configuration item from the cmdb by system id, whatever its class
+    ///
+    /// # Parameters
+    /// - `sys_id`: system id of the configuration item
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn get_cmdb_ci(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<ConfigurationItem, String>;
+    /// list configuration items of one cmdb class
+    ///
+    /// # Parameters
+    /// - `class_name`: cmdb class (table) to list, e.g. cmdb_ci_server, cmdb_ci_appl or cmdb_ci for
+    ///   every class
+    /// - `query_str`: encoded query to filter the items, e.g. operational_status=1 (optional)
+    /// - `limit`: maximum number of items to return (optional, defaults to 100)
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn list_cmdb_cis(
+        &self,
+        class_name: String,
+        query_str: Option<String>,
+        limit: Option<u32>,
+        instance: Option<String>,
+    ) -> Result<Vec<ConfigurationItem>, String>;
+    /// record a configuration item as the one affected by an incident (sets the incident's cmdb_ci
+    /// field)
+    ///
+    /// # Parameters
+    /// - `incident_sys_id`: system id of the incident
+    /// - `ci_sys_id`: system id of the configuration item
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn link_ci_to_incident(
+        &self,
+        incident_sys_id: String,
+        ci_sys_id: String,
+        instance: Option<String>,
+    ) -> Result<Incident, String>;
+
+    // Record following
+    /// follow a record so the configured user is notified of its updates (adds them to the record's
+    /// watch list); following an already followed record changes nothing
+    ///
+    /// # Parameters
+    /// - `table`: name of the table, e.g. incident or change_request
+    /// - `sys_id`: system id of the record
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn follow_record(
+        &self,
+        table: String,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<FollowStatus, String>;
+    /// stop following a record (removes the configured user from the record's watch list);
+    /// unfollowing a record that is not followed changes nothing
+    ///
+    /// # Parameters
+    /// - `table`: name of the table, e.g. incident or change_request
+    /// - `sys_id`: system id of the record
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn unfollow_record(
+        &self,
+        table: String,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<FollowStatus, String>;
+
+    // Batch API
+    /// send several REST operations to servicenow in a single round-trip using the batch api; each
+    /// operation gets its own status and body back
+    ///
+    /// # Parameters
+    /// - `requests`: operations to run in the batch
+    /// - `instance`: name of the configured instance to use, e.g. dev (optional, the primary
+    ///   instance by default)
+    async fn batch_rest(
+        &self,
+        #[schema(r#"{"type": "array", "items": {"type": "object", "properties": {"id": {"type": "string", "description": "caller-chosen id used to match the response to this operation"}, "method": {"type": "string", "description": "http method: GET, POST, PUT, PATCH or DELETE"}, "url": {"type": "string", "description": "instance-relative url, e.g. /api/now/table/incident?sysparm_limit=1"}, "body": {"type": "string", "description": "JSON request body as a string (optional)"}}, "required": ["id", "method", "url"]}}"#)]
+        requests: Vec<BatchRequest>,
+        instance: Option<String>,
+    ) -> Result<Vec<BatchResponse>, String>;
+
     fn tools(&self) -> String;
     fn prompts(&self) -> String;
+    /// explain an error message returned by another tool and suggest how to fix it, e.g. rotating
+    /// credentials after an auth failure or slowing down after a rate limit
+    ///
+    /// # Parameters
+    /// - `error_message`: the error message exactly as the failed tool returned it
+    fn diagnose(&self, error_message: String) -> String;
 }
 
 #[derive(Serialize, Deserialize, WeilType)]
 pub struct ServicenowContractState {
     // define your contract state here!
     secrets: Secrets<ServicenowConfig>,
+    /// Instance the current tool call was routed to; set by `select_instance`, never persisted.
+    #[serde(skip)]
+    instance: SelectedInstance,
 }
 
+/// Name of the configured instance requests go to, `None` for the primary one.
+///
+/// Queries only get `&self`, hence the `RefCell`.
+#[derive(Serialize, Deserialize, Default, Debug)]
+struct SelectedInstance(RefCell<Option<String>>);
+
+impl weil_rs::traits::WeilType for SelectedInstance {}
+
 impl ServicenowContractState {
-    /// Creates a Basic Authentication header for ServiceNow API requests.
-    ///
-    /// Basic Authentication requires base64 encoding of the credentials in the format "username:password".
-    /// This is mandated by the HTTP Basic Authentication standard (RFC 7617) and is what ServiceNow expects.
-    /// Unlike reqwest which has a built-in .basic_auth() method, weil_rs::http::HttpClient requires us to
-    /// manually construct the Authorization header with base64-encoded credentials.
-    fn create_auth_header(&self) -> Result<String, String> {
+    /// Route this call's requests to the configured instance named `instance`, or to the primary
+    /// instance when it is `None`. Every tool calls this first, so no selection outlives a call.
+    fn select_instance(&self, instance: Option<&str>) -> Result<(), String> {
+        self.secrets.config().connection(instance)?;
+        *self.instance.0.borrow_mut() = instance.map(str::to_string);
+        Ok(())
+    }
+
+    /// URL and credentials of the instance this call was routed to.
+    fn connection(&self) -> Result<InstanceConfig, String> {
+        self.secrets
+            .config()
+            .connection(self.instance.0.borrow().as_deref())
+    }
+
+    /// Client for the ServiceNow REST APIs: Basic auth with the configured credentials, the
+    /// configured User-Agent and retry budget, responses read from their `result` envelope and
+    /// failures decoded from ServiceNow's error body.
+    fn api(&self) -> Result<JsonApi, String> {
         let config = self.secrets.config();
-        let credentials = format!("{}:{}", config.username, config.password);
-        let encoded = general_purpose::STANDARD.encode(credentials.as_bytes());
-        Ok(format!("Basic {}", encoded))
+        let connection = self.connection()?;
+        Ok(JsonApi::new()
+            .basic_auth(&connection.username, &connection.password)
+            .user_agent(&resolve_user_agent(config.user_agent.as_deref()))
+            .max_retries(retry_budget(config.max_retries))
+            .envelope("result")
+            .error_mapper(|status, body| status_error(status, body).to_string()))
     }
 
     fn get_base_url(&self) -> Result<String, String> {
-        let config = self.secrets.config();
-        Ok(config.base_url.clone())
+        Ok(self.connection()?.base_url)
     }
-}
 
-#[smart_contract]
-impl Servicenow for ServicenowContractState {
-    #[constructor]
-    fn new() -> Result<Self, String>
-    where
-        Self: Sized,
-    {
-        Ok(Self {
-            secrets: Secrets::<ServicenowConfig>::new(),
-        })
+    /// Incident state transitions allowed by `incident_transitions` in the config, or `None` if
+    /// every transition is allowed.
+    fn incident_transitions(&self) -> Result<Option<BTreeMap<String, Vec<String>>>, String> {
+        match self
+            .secrets
+            .config()
+            .incident_transitions
+            .as_deref()
+            .map(str::trim)
+        {
+            Some(map) if !map.is_empty() => parse_transition_map(map).map(Some),
+            _ => Ok(None),
+        }
     }
 
-    #[query]
-    async fn create_incident(
-        &self,
-        short_description: String,
-        description: String,
-        priority: String,
-    ) -> Result<Incident, String> {
-        let url = format!("{}/api/now/table/incident", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
+    /// Refuse to move incident `sys_id` to `to_state` unless the configured transition map allows
+    /// it from the incident's current state. Without a map nothing is checked or fetched.
+    fn check_incident_transition(&self, sys_id: &str, to_state: &str) -> Result<(), String> {
+        let Some(transitions) = self.incident_transitions()? else {
+            return Ok(());
+        };
 
-        let payload = serde_json::json!({
-            "short_description": short_description,
-            "description": description,
-            "priority": priority
-        });
+        let url = format!("{}/api/now/table/incident/{}", self.get_base_url()?, sys_id);
+        let api = self.api()?;
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        let result: serde_json::Value = api.get_json(
+            &url,
+            vec![("sysparm_fields".to_string(), "state".to_string())],
+        )?;
 
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
+        let from_state = record_field(&result, "state")
+            .ok_or_else(|| format!("Incident {} has no state", sys_id))?;
+        check_transition(&transitions, &from_state, to_state)
+    }
 
-        let response_text = response.text();
+    /// Run `requests` through the Batch API in one round-trip.
+    fn send_batch(&self, requests: &[BatchRequest]) -> Result<Vec<BatchResponse>, String> {
+        let payload = build_batch_body(requests)?;
+        let url = format!("{}/api/now/v1/batch", self.get_base_url()?);
+        let api = self.api()?;
 
-        // ServiceNow returns the result in a "result" field
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Incident,
-        }
+        let response_text = api.send(HttpMethod::Post, &url, vec![], Some(payload.to_string()))?;
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        parse_batch_response(&response_text)
+    }
 
-        Ok(sn_response.result)
+    /// Refuse to run a tool listed in `disabled_tools` in the config. This is the contract's
+    /// `tool_gate`, so every exported method goes through it before doing anything else.
+    fn ensure_tool_enabled(&self, tool: &str) -> Result<(), String> {
+        check_tool_enabled(&self.secrets.config().disabled_tools, tool)
     }
 
-    #[query]
-    async fn get_incident(&self, sys_id: String) -> Result<Incident, String> {
-        let url = format!("{}/api/now/table/incident/{}", self.get_base_url()?, sys_id);
-        let auth_header = self.create_auth_header()?;
+    /// Write `value` to the `element` journal field (comments or work_notes) of a record.
+    fn add_journal_entry(
+        &self,
+        element_id: String,
+        element: &str,
+        value: String,
+    ) -> Result<Comment, String> {
+        let url = format!("{}/api/now/table/sys_journal_field", self.get_base_url()?);
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+        let payload = serde_json::json!({
+            "element": element,
+            "element_id": element_id,
+            "value": value
+        });
 
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .send()
-            .map_err(|err| err.to_string())?;
+        let api = self.api()?;
 
-        let response_text = response.text();
+        api.post_json(&url, vec![], &payload)
+    }
 
-        // ServiceNow returns the result in a "result" field
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Incident,
+    /// Records of `table` matching `query`: one page of up to `limit` records starting at
+    /// `offset`, or with `fetch_all` every page from `offset` on. `fields` is passed on as
+    /// `sysparm_fields` to return only those columns.
+    fn list_records<T>(
+        &self,
+        table: &str,
+        query: Option<String>,
+        limit: u32,
+        offset: u32,
+        fetch_all: bool,
+        fields: Option<String>,
+    ) -> Result<Vec<T>, String>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        if limit == 0 {
+            return Err("limit must be greater than 0".to_string());
         }
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        let url = format!("{}/api/now/table/{}", self.get_base_url()?, table);
+        let api = self.api()?;
+
+        collect_pages(limit, offset, fetch_all, |page_offset| {
+            let mut query_params = Vec::new();
+            if let Some(query) = &query {
+                query_params.push(("sysparm_query".to_string(), query.clone()));
+            }
+            if let Some(fields) = &fields {
+                query_params.push(("sysparm_fields".to_string(), fields.clone()));
+            }
+            query_params.push(("sysparm_limit".to_string(), limit.to_string()));
+            query_params.push(("sysparm_offset".to_string(), page_offset.to_string()));
 
-        Ok(sn_response.result)
+            api.get_json(&url, query_params)
+        })
     }
 
-    #[query]
-    async fn delete_incident(&self, sys_id: String) -> Result<(), String> {
-        let url = format!("{}/api/now/table/incident/{}", self.get_base_url()?, sys_id);
-        let auth_header = self.create_auth_header()?;
+    /// Read a configuration item from the base `cmdb_ci` table, which holds every class.
+    fn fetch_cmdb_ci(&self, sys_id: &str) -> Result<ConfigurationItem, String> {
+        let url = format!("{}/api/now/table/cmdb_ci/{}", self.get_base_url()?, sys_id);
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+        let api = self.api()?;
 
-        let response = HttpClient::request(&url, HttpMethod::Delete)
-            .headers(headers)
-            .send()
-            .map_err(|err| err.to_string())?;
+        let query_params = vec![("sysparm_fields".to_string(), CMDB_CI_FIELDS.to_string())];
 
-        // Check if the deletion was successful (204 No Content is expected)
-        if response.status() >= 204 && response.status() < 300 {
-            Ok(())
-        } else {
-            Err(format!(
-                "Failed to delete incident. Status: {}",
-                response.status()
-            ))
-        }
+        api.get_json(&url, query_params)
     }
 
-    #[query]
-    async fn query_incidents(
-        &self,
-        query_str: String,
-        limit: u32,
-    ) -> Result<Vec<Incident>, String> {
-        let url = format!("{}/api/now/table/incident", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+    /// sys_id of the `sys_user` the configured credentials belong to.
+    fn configured_user_sys_id(&self) -> Result<String, String> {
+        let connection = self.connection()?;
+        let url = format!("{}/api/now/table/sys_user", connection.base_url);
 
-        // Add query parameters
-        let mut query_params = Vec::new();
-        query_params.push(("sysparm_query".to_string(), query_str));
-        query_params.push(("sysparm_limit".to_string(), limit.to_string()));
+        let api = self.api()?;
 
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .query(query_params)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
+        let query_params = vec![
+            (
+                "sysparm_query".to_string(),
+                format!("user_name={}", connection.username),
+            ),
+            ("sysparm_fields".to_string(), "sys_id".to_string()),
+            ("sysparm_limit".to_string(), "1".to_string()),
+        ];
 
-        // ServiceNow returns the results in a "result" field as an array
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Vec<Incident>,
-        }
+        let result: Vec<serde_json::Value> = api.get_json(&url, query_params)?;
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
+        result
+            .first()
+            .and_then(|user| record_field(user, "sys_id"))
+            .ok_or_else(|| {
                 format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
+                    "No sys_user found for configured user {}",
+                    connection.username
                 )
-            })?;
-
-        Ok(sn_response.result)
+            })
     }
 
-    #[query]
-    async fn add_comment(
+    /// Add the configured user to, or remove them from, a record's watch list, leaving the
+    /// record untouched if it is already in the requested state.
+    fn set_following(
         &self,
-        incident_sys_id: String,
-        comment: String,
-    ) -> Result<Comment, String> {
-        let url = format!("{}/api/now/table/sys_journal_field", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
+        table: String,
+        sys_id: String,
+        follow: bool,
+    ) -> Result<FollowStatus, String> {
+        validate_table_name(&table)?;
+        let user_sys_id = self.configured_user_sys_id()?;
+        let url = format!(
+            "{}/api/now/table/{}/{}",
+            self.get_base_url()?,
+            table,
+            sys_id
+        );
 
-        let payload = serde_json::json!({
-            "element": "comments",
-            "element_id": incident_sys_id,
-            "value": comment
-        });
+        let api = self.api()?;
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        let result: serde_json::Value = api.get_json(
+            &url,
+            vec![("sysparm_fields".to_string(), "watch_list".to_string())],
+        )?;
 
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
+        let watch_list = record_field(&result, "watch_list").unwrap_or_default();
+        let Some(updated) = update_watch_list(&watch_list, &user_sys_id, follow) else {
+            return Ok(FollowStatus {
+                table,
+                sys_id,
+                following: follow,
+                changed: false,
+            });
+        };
+
+        api.send(
+            HttpMethod::Patch,
+            &url,
+            vec![],
+            Some(serde_json::json!({ "watch_list": updated }).to_string()),
+        )
+        .map_err(|err| {
+            format!(
+                "Failed to update watch list of {} {}: {}",
+                table, sys_id, err
+            )
+        })?;
+
+        Ok(FollowStatus {
+            table,
+            sys_id,
+            following: follow,
+            changed: true,
+        })
+    }
+}
 
-        let response_text = response.text();
+/// User-Agent sent with every request unless the config overrides it.
+const DEFAULT_USER_AGENT: &str = concat!(
+    "wadk-mcp/",
+    env!("CARGO_PKG_NAME"),
+    "/",
+    env!("CARGO_PKG_VERSION")
+);
+
+/// Retries per idempotent request when `max_retries` isn't configured. POST and PATCH requests
+/// (creates, batches, attachment uploads) are sent once, since a gateway error can follow a write
+/// the instance already applied.
+const DEFAULT_MAX_RETRIES: u32 = 2;
+
+/// Most retries per request, whatever is configured.
+///
+/// The runtime exposes neither response headers nor a timer, so `Retry-After` can't be honoured
+/// and retries can't be spaced out beyond the round-trip itself; a small cap keeps a throttled
+/// call from hammering the instance or running on indefinitely.
+const MAX_RETRIES_CAP: u32 = 5;
+
+/// Retries allowed per request for the configured `max_retries`.
+fn retry_budget(configured: Option<u32>) -> u32 {
+    configured
+        .unwrap_or(DEFAULT_MAX_RETRIES)
+        .min(MAX_RETRIES_CAP)
+}
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Comment,
-        }
+/// The configured User-Agent, or `DEFAULT_USER_AGENT` when none (or a blank one) is set.
+fn resolve_user_agent(configured: Option<&str>) -> String {
+    match configured.map(str::trim) {
+        Some(user_agent) if !user_agent.is_empty() => user_agent.to_string(),
+        _ => DEFAULT_USER_AGENT.to_string(),
+    }
+}
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+/// Fields read for a `ConfigurationItem`.
+const CMDB_CI_FIELDS: &str = "sys_id,name,sys_class_name,operational_status,asset_tag";
 
-        Ok(sn_response.result)
+/// Checks that `class_name` is a CMDB class: `cmdb_ci` itself or a table extending it
+/// (`cmdb_ci_server`, `cmdb_ci_appl`, ...), so the CMDB tools can't be pointed at other tables.
+fn validate_ci_class(class_name: &str) -> Result<(), String> {
+    validate_table_name(class_name)?;
+    if class_name == "cmdb_ci" || class_name.starts_with("cmdb_ci_") {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} is not a CMDB class; expected cmdb_ci or a cmdb_ci_* table",
+            class_name
+        ))
     }
+}
 
-    #[query]
-    async fn resolve_incident(
-        &self,
-        sys_id: String,
-        resolution_notes: String,
-    ) -> Result<Incident, String> {
-        let url = format!("{}/api/now/table/incident/{}", self.get_base_url()?, sys_id);
-        let auth_header = self.create_auth_header()?;
+/// Most sys_ids put in one `sys_idIN` query, which keeps the request URL (32 characters per id)
+/// well under the few-kilobyte limits of instances and proxies.
+const SYS_ID_IN_CHUNK: u32 = 100;
+
+/// `sys_idIN...` queries covering `sys_ids` (duplicates dropped) in chunks of at most `chunk`
+/// ids, each with the number of ids it asks for.
+fn sys_id_in_queries(sys_ids: &[String], chunk: u32) -> Result<Vec<(String, u32)>, String> {
+    let mut unique: Vec<&str> = Vec::new();
+    for sys_id in sys_ids {
+        let sys_id = sys_id.trim();
+        validate_sys_id(sys_id)?;
+        if !unique.contains(&sys_id) {
+            unique.push(sys_id);
+        }
+    }
+    Ok(unique
+        .chunks(chunk.max(1) as usize)
+        .map(|ids| (format!("sys_idIN{}", ids.join(",")), ids.len() as u32))
+        .collect())
+}
 
-        let payload = serde_json::json!({
-            "state": "6", // Resolved state
-            "close_notes": resolution_notes
-        });
+/// `records` arranged in the order their sys_ids appear in `sys_ids`; records that weren't asked
+/// for are dropped, and ids without a record are skipped.
+fn order_by_sys_ids(records: Vec<serde_json::Value>, sys_ids: &[String]) -> Vec<serde_json::Value> {
+    let mut by_id: HashMap<String, serde_json::Value> = records
+        .into_iter()
+        .filter_map(|record| record_field(&record, "sys_id").map(|sys_id| (sys_id, record)))
+        .collect();
+    sys_ids
+        .iter()
+        .filter_map(|sys_id| by_id.remove(sys_id.trim()))
+        .collect()
+}
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
+/// Checks that `sys_id` is a bare record id (letters and digits), so it can't change the meaning
+/// of an encoded query it is put into.
+fn validate_sys_id(sys_id: &str) -> Result<(), String> {
+    if !sys_id.is_empty() && sys_id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        Ok(())
+    } else {
+        Err(format!("Invalid sys_id: {}", sys_id))
+    }
+}
 
-        let response = HttpClient::request(&url, HttpMethod::Put)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
+/// A table whose records belong to an incident and are deleted with it.
+struct CascadeTarget {
+    table: &'static str,
+    /// Field referencing the incident
+    reference_field: &'static str,
+    /// Extra encoded-query term, for tables shared by every record type
+    scope: Option<&'static str>,
+}
 
-        let response_text = response.text();
+impl CascadeTarget {
+    fn query(&self, incident_sys_id: &str) -> String {
+        let reference = format!("{}={}", self.reference_field, incident_sys_id);
+        match self.scope {
+            Some(scope) => format!("{}^{}", scope, reference),
+            None => reference,
+        }
+    }
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Incident,
+    /// Attachments go through the Attachment API, which also removes the stored file.
+    fn delete_url(&self, base_url: &str, sys_id: &str) -> String {
+        match self.table {
+            "sys_attachment" => format!("{}/api/now/attachment/{}", base_url, sys_id),
+            table => format!("{}/api/now/table/{}/{}", base_url, table, sys_id),
         }
+    }
+}
+
+/// Tables `delete_incident_cascade` can clean up, in deletion order. Journal entries are not
+/// among them, so comments and work notes stay in the audit trail.
+const CASCADE_TARGETS: &[CascadeTarget] = &[
+    CascadeTarget {
+        table: "incident_task",
+        reference_field: "incident",
+        scope: None,
+    },
+    CascadeTarget {
+        table: "task_ci",
+        reference_field: "task",
+        scope: None,
+    },
+    CascadeTarget {
+        table: "sys_attachment",
+        reference_field: "table_sys_id",
+        scope: Some("table_name=incident"),
+    },
+];
+
+/// Most records deleted from one related table. A cascade that would remove more is refused
+/// before anything is deleted, as that many children suggests the wrong incident.
+const MAX_CASCADE_RECORDS: u32 = 100;
+
+/// Cascade targets for the configured `cascade_delete_tables`, in deletion order.
+fn cascade_targets(configured: Option<&[String]>) -> Result<Vec<&'static CascadeTarget>, String> {
+    let Some(tables) = configured else {
+        return Ok(CASCADE_TARGETS.iter().collect());
+    };
+    if let Some(unknown) = tables.iter().find(|table| {
+        !CASCADE_TARGETS
+            .iter()
+            .any(|target| target.table == table.as_str())
+    }) {
+        let supported: Vec<&str> = CASCADE_TARGETS.iter().map(|target| target.table).collect();
+        return Err(format!(
+            "cascade_delete_tables: {} is not supported, expected any of {}",
+            unknown,
+            supported.join(", ")
+        ));
+    }
+    Ok(CASCADE_TARGETS
+        .iter()
+        .filter(|target| tables.iter().any(|table| table == target.table))
+        .collect())
+}
+
+/// sys_ids of the related records found in `table`, refusing more than `MAX_CASCADE_RECORDS`.
+fn cascade_sys_ids(table: &str, records: &[serde_json::Value]) -> Result<Vec<String>, String> {
+    if records.len() > MAX_CASCADE_RECORDS as usize {
+        return Err(format!(
+            "Refusing to cascade: more than {} related {} records",
+            MAX_CASCADE_RECORDS, table
+        ));
+    }
+    Ok(records
+        .iter()
+        .filter_map(|record| record_field(record, "sys_id"))
+        .collect())
+}
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
+/// Delete every planned related record, then the incident, stopping at the first failure so
+/// the incident is only removed once nothing refers to it.
+fn run_cascade_delete<F>(
+    incident_sys_id: &str,
+    plan: Vec<(&CascadeTarget, Vec<String>)>,
+    mut delete: F,
+) -> Result<CascadeDeleteReport, String>
+where
+    F: FnMut(&CascadeTarget, &str) -> Result<(), String>,
+{
+    let mut related = Vec::new();
+    let mut deleted = 0;
+    for (target, sys_ids) in plan {
+        for sys_id in &sys_ids {
+            delete(target, sys_id).map_err(|err| {
                 format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
+                    "Failed to delete {} {} ({} related records already deleted, incident {} kept): {}",
+                    target.table, sys_id, deleted, incident_sys_id, err
                 )
             })?;
-
-        Ok(sn_response.result)
+            deleted += 1;
+        }
+        related.push(CascadeDeletion {
+            table: target.table.to_string(),
+            sys_ids,
+        });
     }
 
-    #[query]
-    async fn list_incidents(
-        &self,
-        query_str: Option<String>,
-        limit: Option<u32>,
-    ) -> Result<Vec<Incident>, String> {
-        let query = query_str.unwrap_or_default();
-        let limit_val = limit.unwrap_or(100);
-        self.query_incidents(query, limit_val).await
-    }
+    let incident = CascadeTarget {
+        table: "incident",
+        reference_field: "sys_id",
+        scope: None,
+    };
+    delete(&incident, incident_sys_id)?;
+    Ok(CascadeDeleteReport {
+        incident_sys_id: incident_sys_id.to_string(),
+        related,
+    })
+}
 
-    // Service Catalog Functions
-    #[query]
-    async fn list_catalog_items(
-        &self,
-        query_str: Option<String>,
-        limit: Option<u32>,
-    ) -> Result<Vec<CatalogItem>, String> {
-        let url = format!("{}/api/now/table/sc_cat_item", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
+/// Fields read for a `TaskSla`.
+const TASK_SLA_FIELDS: &str =
+    "sys_id,task,sla,stage,business_percentage,has_breached,planned_end_time";
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+/// Page size used when reading every SLA of a task.
+const TASK_SLA_FETCH_LIMIT: u32 = 100;
 
-        let mut query_params = Vec::new();
-        if let Some(query) = query_str {
-            query_params.push(("sysparm_query".to_string(), query));
-        }
-        query_params.push((
-            "sysparm_limit".to_string(),
-            limit.unwrap_or(100).to_string(),
+/// Put SLAs in order of urgency: highest business percentage first, SLAs without one last.
+fn sort_slas_by_urgency(slas: &mut [TaskSla]) {
+    slas.sort_by(|a, b| {
+        let percentage = |sla: &TaskSla| sla.business_percentage.unwrap_or(f64::NEG_INFINITY);
+        percentage(b).total_cmp(&percentage(a))
+    });
+}
+
+/// `task_sla` query for active, not yet breached incident SLAs that have used more than
+/// `threshold_percent` of their business time, most used first.
+fn near_breach_sla_query(threshold_percent: u8) -> Result<String, String> {
+    if threshold_percent > 100 {
+        return Err(format!(
+            "threshold_percent must be between 0 and 100, got {}",
+            threshold_percent
         ));
+    }
+    Ok(format!(
+        "active=true^has_breached=false^task.sys_class_name=incident^business_percentage>{}^ORDERBYDESCbusiness_percentage",
+        threshold_percent
+    ))
+}
 
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .query(query_params)
-            .send()
-            .map_err(|err| err.to_string())?;
+/// Pair each SLA over `threshold_percent` with its incident from `incidents`, most urgent
+/// first. SLAs whose incident wasn't returned, or that aren't over the threshold, are dropped.
+fn join_slas_with_incidents(
+    mut slas: Vec<TaskSla>,
+    incidents: Vec<serde_json::Value>,
+    threshold_percent: u8,
+) -> Result<Vec<SlaBreachRisk>, String> {
+    slas.retain(|sla| {
+        sla.business_percentage
+            .is_some_and(|percentage| percentage > f64::from(threshold_percent))
+    });
+    sort_slas_by_urgency(&mut slas);
+    let by_id: HashMap<String, serde_json::Value> = incidents
+        .into_iter()
+        .filter_map(|record| record_field(&record, "sys_id").map(|sys_id| (sys_id, record)))
+        .collect();
+    slas.into_iter()
+        .filter_map(|sla| {
+            let incident = by_id.get(sla.task.as_deref()?)?.clone();
+            Some((incident, sla))
+        })
+        .map(|(incident, sla)| {
+            let incident = serde_json::from_value(incident).map_err(|err| {
+                String::from(ServiceNowError::Parse(format!(
+                    "Failed to parse incident: {}",
+                    err
+                )))
+            })?;
+            Ok(SlaBreachRisk { incident, sla })
+        })
+        .collect()
+}
 
-        let response_text = response.text();
+/// Prefix of related list definitions backed by a `sys_relationship` record.
+const RELATIONSHIP_PREFIX: &str = "REL:";
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Vec<CatalogItem>,
-        }
+/// Page size used when reading a form's related list entries.
+const RELATED_LIST_FETCH_LIMIT: u32 = 100;
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+/// sys_ids of the `sys_relationship` records referenced by `definitions`.
+fn relationship_ids(definitions: &[String]) -> Vec<String> {
+    definitions
+        .iter()
+        .filter_map(|definition| definition.strip_prefix(RELATIONSHIP_PREFIX))
+        .filter(|sys_id| validate_sys_id(sys_id).is_ok())
+        .map(str::to_string)
+        .collect()
+}
 
-        Ok(sn_response.result)
+/// Related lists for the form's `definitions` (duplicates across views dropped, first position
+/// kept). `child_table.field` entries are split directly; `REL:<sys_id>` entries take their name
+/// and child table (`basic_query_from`) from the matching `relationships` record.
+fn build_related_lists(
+    definitions: Vec<String>,
+    relationships: &[serde_json::Value],
+) -> Vec<RelatedList> {
+    let mut seen = Vec::new();
+    let mut related_lists = Vec::new();
+    for definition in definitions {
+        let definition = definition.trim().to_string();
+        if definition.is_empty() || seen.contains(&definition) {
+            continue;
+        }
+        seen.push(definition.clone());
+
+        let related_list = match definition.strip_prefix(RELATIONSHIP_PREFIX) {
+            Some(sys_id) => {
+                let relationship = relationships
+                    .iter()
+                    .find(|record| record_field(record, "sys_id").as_deref() == Some(sys_id));
+                RelatedList {
+                    child_table: relationship
+                        .and_then(|record| record_field(record, "basic_query_from")),
+                    relationship: relationship.and_then(|record| record_field(record, "name")),
+                    kind: "relationship".to_string(),
+                    definition,
+                }
+            }
+            None => {
+                let (child_table, field) = match definition.split_once('.') {
+                    Some((child_table, field)) => (Some(child_table), Some(field)),
+                    None => (Some(definition.as_str()), None),
+                };
+                RelatedList {
+                    child_table: child_table.map(str::to_string),
+                    relationship: field.map(str::to_string),
+                    kind: "reference".to_string(),
+                    definition: definition.clone(),
+                }
+            }
+        };
+        related_lists.push(related_list);
     }
+    related_lists
+}
 
-    #[query]
-    async fn get_catalog_item(&self, sys_id: String) -> Result<CatalogItem, String> {
-        let url = format!(
-            "{}/api/now/table/sc_cat_item/{}",
-            self.get_base_url()?,
-            sys_id
-        );
-        let auth_header = self.create_auth_header()?;
+/// Checks that `fields` is a non-empty list of field names, each plain or dot-walked
+/// (`caller_id.email`).
+fn validate_field_list(fields: &[String]) -> Result<(), String> {
+    if fields.is_empty() {
+        return Err("fields must name at least one field".to_string());
+    }
+    match fields
+        .iter()
+        .find(|field| !field.split('.').all(is_plain_identifier))
+    {
+        Some(field) => Err(format!("Invalid field name: {}", field)),
+        None => Ok(()),
+    }
+}
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+/// A value quoted per RFC 4180 when it holds a comma, quote or line break.
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
 
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .send()
-            .map_err(|err| err.to_string())?;
+/// RFC 4180 CSV of `records`: a header row of `fields`, then one row per record with its values
+/// in the same order (reference fields as their sys_id, missing values empty), CRLF-terminated.
+fn records_to_csv(records: &[serde_json::Value], fields: &[String]) -> String {
+    let mut csv = String::new();
+    let header: Vec<String> = fields.iter().map(|field| csv_escape(field)).collect();
+    csv.push_str(&header.join(","));
+    csv.push_str("\r\n");
+    for record in records {
+        let row: Vec<String> = fields
+            .iter()
+            .map(|field| csv_escape(&record_field(record, field).unwrap_or_default()))
+            .collect();
+        csv.push_str(&row.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
 
-        let response_text = response.text();
+/// Journal fields read by `get_incident_journal` when none is named.
+const JOURNAL_FIELDS: [&str; 2] = ["comments", "work_notes"];
+
+/// `sys_journal_field` query for the entries of a record, limited to `field` if given and to
+/// comments and work notes otherwise.
+fn journal_query(element_id: &str, field: Option<&str>) -> Result<String, String> {
+    validate_sys_id(element_id)?;
+    let elements = match field.map(str::trim).filter(|field| !field.is_empty()) {
+        Some(field) if is_plain_identifier(field) => field.to_string(),
+        Some(field) => return Err(format!("Invalid journal field: {}", field)),
+        None => JOURNAL_FIELDS.join(","),
+    };
+    Ok(format!(
+        "element_id={}^elementIN{}^ORDERBYsys_created_on",
+        element_id, elements
+    ))
+}
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: CatalogItem,
+/// Apply `add_entry` to each record in `sys_ids`, collecting which records took it and which
+/// didn't. An invalid sys_id fails on its own without reaching `add_entry`.
+fn add_to_each<F>(sys_ids: Vec<String>, mut add_entry: F) -> BulkResult<String>
+where
+    F: FnMut(&str) -> Result<(), String>,
+{
+    let mut result = BulkResult {
+        succeeded: Vec::new(),
+        failed: Vec::new(),
+    };
+    for sys_id in sys_ids {
+        match validate_sys_id(&sys_id).and_then(|()| add_entry(&sys_id)) {
+            Ok(()) => result.succeeded.push(sys_id),
+            Err(error) => result.failed.push(BulkFailure {
+                item: sys_id,
+                error,
+            }),
         }
+    }
+    result
+}
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+/// Put journal entries in chronological order. `sys_created_on` is `YYYY-MM-DD HH:MM:SS`, so
+/// comparing it as text orders by time; entries from the same second keep their order.
+fn sort_journal(entries: &mut [JournalEntry]) {
+    entries.sort_by(|a, b| a.sys_created_on.cmp(&b.sys_created_on));
+}
 
-        Ok(sn_response.result)
+/// `sysparm_fields` value projecting records onto `fields`, or `None` to return every field.
+///
+/// `sys_id` is always requested so projected records can still be addressed; the other fields
+/// are returned only if asked for (and `Incident` only carries sys_id, number,
+/// short_description, description, priority and state). Dot-walked names such as
+/// `assigned_to.name` are accepted.
+fn sysparm_fields(fields: Option<&[String]>) -> Result<Option<String>, String> {
+    let Some(fields) = fields.filter(|fields| !fields.is_empty()) else {
+        return Ok(None);
+    };
+    let mut projection = vec!["sys_id"];
+    for field in fields {
+        let field = field.trim();
+        if !field.split('.').all(is_plain_identifier) {
+            return Err(format!("Invalid field name: {}", field));
+        }
+        if !projection.contains(&field) {
+            projection.push(field);
+        }
     }
+    Ok(Some(projection.join(",")))
+}
 
-    #[query]
-    async fn list_catalog_categories(
-        &self,
-        query_str: Option<String>,
-        limit: Option<u32>,
-    ) -> Result<Vec<CatalogCategory>, String> {
-        let url = format!("{}/api/now/table/sc_category", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
+/// Pass a 2xx body through; otherwise classify the status and keep ServiceNow's explanation.
+fn check_response(status: u16, body: String) -> Result<String, ServiceNowError> {
+    if (200..300).contains(&status) {
+        Ok(body)
+    } else {
+        Err(status_error(status, &body))
+    }
+}
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+/// The error for a non-2xx response, classified by status.
+fn status_error(status: u16, body: &str) -> ServiceNowError {
+    let message = error_message(body);
+    match status {
+        401 | 403 => ServiceNowError::Auth(message),
+        404 => ServiceNowError::NotFound(message),
+        429 => ServiceNowError::RateLimited(message),
+        _ => ServiceNowError::Api { status, message },
+    }
+}
 
-        let mut query_params = Vec::new();
-        if let Some(query) = query_str {
-            query_params.push(("sysparm_query".to_string(), query));
-        }
-        query_params.push((
-            "sysparm_limit".to_string(),
-            limit.unwrap_or(100).to_string(),
-        ));
+/// Explanation from ServiceNow's error envelope (`{"error": {"message": ..., "detail": ...}}`),
+/// falling back to the raw body when it isn't one.
+fn error_message(body: &str) -> String {
+    let envelope = serde_json::from_str::<serde_json::Value>(body).ok();
+    let error = envelope.as_ref().map(|value| &value["error"]);
+    let field = |name: &str| {
+        error
+            .and_then(|error| error[name].as_str())
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+    };
+
+    match (field("message"), field("detail")) {
+        (Some(message), Some(detail)) if message != detail => format!("{} ({})", message, detail),
+        (Some(message), _) => message.to_string(),
+        (None, Some(detail)) => detail.to_string(),
+        (None, None) if body.trim().is_empty() => "no response body".to_string(),
+        (None, None) => body.trim().to_string(),
+    }
+}
 
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .query(query_params)
-            .send()
-            .map_err(|err| err.to_string())?;
+/// Offset of the page after one that started at `offset` and returned `returned` of at most
+/// `limit` records.
+///
+/// A short page means the table is exhausted; a full page may be followed by more records, so
+/// the caller is pointed past it (and may see an empty final page).
+fn next_page_offset(offset: u32, limit: u32, returned: usize) -> Option<u32> {
+    if limit > 0 && returned >= limit as usize {
+        Some(offset.saturating_add(returned as u32))
+    } else {
+        None
+    }
+}
 
-        let response_text = response.text();
+/// Fetch one page of records from `offset` or, with `fetch_all`, keep fetching until a page
+/// comes back short.
+///
+/// The Table API gives no reliable end marker in the body, so a page of exactly `limit` records
+/// is always followed by another request; if the table ended there, that page is simply empty.
+/// A listing that still has more records past the shared `PaginationLimits` fails with
+/// `PaginationLimitExceeded` rather than being cut short silently.
+fn collect_pages<T, F>(
+    limit: u32,
+    offset: u32,
+    fetch_all: bool,
+    mut fetch_page: F,
+) -> Result<Vec<T>, String>
+where
+    F: FnMut(u32) -> Result<Vec<T>, String>,
+{
+    let first_page = fetch_page(offset)?;
+    let next_offset = next_page_offset(offset, limit, first_page.len()).filter(|_| fetch_all);
+    let records = drain_pages(
+        first_page,
+        next_offset,
+        PaginationLimits::default(),
+        |page_offset| {
+            let page = fetch_page(page_offset)?;
+            let next_offset = next_page_offset(page_offset, limit, page.len());
+            Ok((page, next_offset))
+        },
+    )?;
+    Ok(records)
+}
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Vec<CatalogCategory>,
+/// Fetch records a page of at most `page_size` at a time until `max` have been collected or a
+/// page comes back short. `fetch_page` is given the offset and limit of each page.
+fn collect_up_to<T, F>(max: u32, page_size: u32, mut fetch_page: F) -> Result<Vec<T>, String>
+where
+    F: FnMut(u32, u32) -> Result<Vec<T>, String>,
+{
+    let mut records = Vec::new();
+    let mut offset = 0;
+    while (records.len() as u32) < max {
+        let limit = page_size.min(max - records.len() as u32);
+        let mut page = fetch_page(offset, limit)?;
+        page.truncate(limit as usize);
+        let returned = page.len();
+        records.extend(page);
+        match next_page_offset(offset, limit, returned) {
+            Some(next_offset) => offset = next_offset,
+            None => break,
         }
+    }
+    Ok(records)
+}
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+/// Page size used when exporting a knowledge base.
+const KB_EXPORT_PAGE_SIZE: u32 = 100;
+
+/// Fields of `kb_knowledge` written by `export_knowledge_to_imfs`.
+const KB_EXPORT_FIELDS: &str = "sys_id,short_description,text";
+
+/// One JSON object per line for each article, holding its sys_id, short description and text.
+fn articles_to_ndjson(articles: &[serde_json::Value]) -> String {
+    articles
+        .iter()
+        .map(|article| {
+            let line = serde_json::json!({
+                "sys_id": record_field(article, "sys_id"),
+                "short_description": record_field(article, "short_description"),
+                "text": record_field(article, "text"),
+            });
+            format!("{}\n", line)
+        })
+        .collect()
+}
 
-        Ok(sn_response.result)
+/// Write `content` to `filepath` in IMFS, returning the file descriptor.
+fn write_imfs_file(filepath: String, content: String) -> Result<String, String> {
+    // parameter definition for the cross contract call to imfs
+    #[derive(Serialize, Deserialize)]
+    struct Args {
+        filepath: String,
+        content: String,
     }
 
-    #[query]
-    async fn create_catalog_category(
-        &self,
-        title: String,
-        description: Option<String>,
-        parent: Option<String>,
-    ) -> Result<CatalogCategory, String> {
-        let url = format!("{}/api/now/table/sc_category", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
+    let args = Args { filepath, content };
 
-        let mut payload = serde_json::json!({
-            "title": title
-        });
+    // SAFETY: `imfs` is a systemic applet
+    let contract_addr = Runtime::contract_id_for_name("imfs").unwrap();
 
-        if let Some(desc) = description {
-            payload["description"] = serde_json::Value::String(desc);
-        }
-        if let Some(parent_id) = parent {
-            payload["parent"] = serde_json::Value::String(parent_id);
-        }
+    Runtime::call_contract::<String>(
+        contract_addr,
+        "write".to_string(),
+        Some(serde_json::to_string(&args).map_err(|err| err.to_string())?),
+    )
+    .map_err(|err| err.to_string())
+}
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
+/// Maximum length of the `correlation_id` and `correlation_display` columns on task tables.
+const CORRELATION_FIELD_MAX_LEN: usize = 100;
 
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
+/// Checks that an external reference fits ServiceNow's correlation columns.
+fn validate_external_ref(external_ref: &str) -> Result<(), String> {
+    if external_ref.trim().is_empty() {
+        return Err("external_ref must not be empty".to_string());
+    }
+    if external_ref.chars().count() > CORRELATION_FIELD_MAX_LEN {
+        return Err(format!(
+            "external_ref must be at most {} characters",
+            CORRELATION_FIELD_MAX_LEN
+        ));
+    }
+    Ok(())
+}
 
-        let response_text = response.text();
+/// Stamps an external system's reference onto a create payload.
+///
+/// ServiceNow does not accept client-supplied sys_ids, so the task-level `correlation_id` and
+/// `correlation_display` columns carry the caller's ID instead. That lets external systems find
+/// (and dedupe against) records they created earlier via `find_by_external_ref`.
+fn apply_external_ref(
+    payload: &mut serde_json::Value,
+    external_ref: Option<String>,
+) -> Result<(), String> {
+    if let Some(external_ref) = external_ref {
+        validate_external_ref(&external_ref)?;
+        payload["correlation_id"] = serde_json::Value::String(external_ref.clone());
+        payload["correlation_display"] = serde_json::Value::String(external_ref);
+    }
+    Ok(())
+}
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: CatalogCategory,
-        }
+/// Builds the encoded query matching records stamped with `external_ref`.
+///
+/// `^` separates encoded-query terms, so any in the value is doubled to keep it literal.
+fn external_ref_query(external_ref: &str) -> String {
+    field_equals_query("correlation_id", external_ref)
+}
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+/// Builds the encoded query `field=value`, doubling any `^` in the value to keep it literal.
+fn field_equals_query(field: &str, value: &str) -> String {
+    format!("{}={}", field, value.replace('^', "^^"))
+}
 
-        Ok(sn_response.result)
+/// Maximum number of incidents fetched when looking for duplicates.
+const DEDUPE_FETCH_LIMIT: u32 = 1000;
+
+/// Reads a field from a Table API record as a string, unwrapping `{link, value}` reference
+/// objects to their value.
+fn record_field(record: &serde_json::Value, field: &str) -> Option<String> {
+    let value = match &record[field] {
+        serde_json::Value::Object(reference) => reference.get("value")?,
+        other => other,
+    };
+    match value {
+        serde_json::Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
     }
+}
 
-    #[query]
-    async fn move_catalog_items(
-        &self,
-        item_sys_ids: Vec<String>,
-        target_category_sys_id: String,
-    ) -> Result<(), String> {
-        for item_sys_id in item_sys_ids {
-            let url = format!(
-                "{}/api/now/table/sc_cat_item/{}",
-                self.get_base_url()?,
-                item_sys_id
-            );
-            let auth_header = self.create_auth_header()?;
+/// Groups incident records by the values of `fields` and returns the clusters with more than
+/// one member.
+///
+/// Records missing any grouping field are left out, as an empty key would lump unrelated
+/// incidents together. Within a cluster the earliest created incident (ties broken by number)
+/// is suggested as the primary. Clusters are ordered by key.
+fn group_duplicates(records: &[serde_json::Value], fields: &[&str]) -> Vec<DedupeGroup> {
+    let mut clusters: std::collections::BTreeMap<String, Vec<&serde_json::Value>> =
+        std::collections::BTreeMap::new();
+    for record in records {
+        let Some(values) = fields
+            .iter()
+            .map(|field| record_field(record, field))
+            .collect::<Option<Vec<_>>>()
+        else {
+            continue;
+        };
+        if record_field(record, "sys_id").is_none() {
+            continue;
+        }
+        clusters.entry(values.join(" | ")).or_default().push(record);
+    }
 
-            let payload = serde_json::json!({
-                "category": target_category_sys_id
+    clusters
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(key, mut members)| {
+            members.sort_by_key(|record| {
+                (
+                    record_field(record, "sys_created_on"),
+                    record_field(record, "number"),
+                )
             });
+            let primary = members.remove(0);
+            DedupeGroup {
+                key,
+                primary_sys_id: record_field(primary, "sys_id").unwrap_or_default(),
+                primary_number: record_field(primary, "number"),
+                duplicate_sys_ids: members
+                    .iter()
+                    .filter_map(|record| record_field(record, "sys_id"))
+                    .collect(),
+                duplicate_numbers: members
+                    .iter()
+                    .filter_map(|record| record_field(record, "number"))
+                    .collect(),
+            }
+        })
+        .collect()
+}
 
-            let mut headers = HashMap::new();
-            headers.insert("Authorization".to_string(), auth_header);
-            headers.insert("Content-Type".to_string(), "application/json".to_string());
+/// Field values stored in a `sys_template` record's `template` column.
+///
+/// Templates are encoded queries of `field=value` terms joined by `^` and closed by `EQ`, e.g.
+/// `short_description=Email down^priority=2^EQ`.
+fn parse_template_fields(template: &str) -> serde_json::Map<String, serde_json::Value> {
+    template
+        .split('^')
+        .filter(|term| !term.is_empty() && *term != "EQ")
+        .filter_map(|term| term.split_once('='))
+        .filter(|(field, _)| !field.is_empty())
+        .map(|(field, value)| {
+            (
+                field.to_string(),
+                serde_json::Value::String(value.to_string()),
+            )
+        })
+        .collect()
+}
 
-            let _response = HttpClient::request(&url, HttpMethod::Put)
-                .headers(headers)
-                .json(&payload)
-                .send()
-                .map_err(|err| err.to_string())?;
-        }
-        Ok(())
+/// Incident payload from a template's fields with `overrides` (a JSON object, or empty for none)
+/// applied on top.
+fn merge_template_overrides(template: &str, overrides: &str) -> Result<serde_json::Value, String> {
+    let mut fields = parse_template_fields(template);
+    if !overrides.trim().is_empty() {
+        let overrides: serde_json::Value = serde_json::from_str(overrides)
+            .map_err(|err| format!("overrides must be a JSON object: {}", err))?;
+        let serde_json::Value::Object(overrides) = overrides else {
+            return Err("overrides must be a JSON object".to_string());
+        };
+        fields.extend(overrides);
     }
+    Ok(serde_json::Value::Object(fields))
+}
 
-    #[query]
-    async fn create_catalog_item_variable(
-        &self,
-        catalog_item_sys_id: String,
-        name: String,
-        question_text: String,
-        var_type: String,
-        mandatory: bool,
-    ) -> Result<CatalogVariable, String> {
-        let url = format!("{}/api/now/table/item_option_new", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
-
-        let payload = serde_json::json!({
-            "name": name,
-            "question_text": question_text,
-            "type": var_type,
-            "mandatory": mandatory,
-            "catalog_item": catalog_item_sys_id
-        });
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
+/// Parse a transition map: a JSON object from each state to the list of states it may move to.
+fn parse_transition_map(map: &str) -> Result<BTreeMap<String, Vec<String>>, String> {
+    serde_json::from_str(map).map_err(|err| {
+        format!(
+            "incident_transitions must be a JSON object of state to list of states: {}",
+            err
+        )
+    })
+}
 
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
+/// Whether moving from `from_state` to `to_state` is allowed by `transitions`.
+///
+/// Staying in the same state is always allowed; a state missing from the map can't be left.
+fn check_transition(
+    transitions: &BTreeMap<String, Vec<String>>,
+    from_state: &str,
+    to_state: &str,
+) -> Result<(), String> {
+    if from_state == to_state
+        || transitions
+            .get(from_state)
+            .is_some_and(|targets| targets.iter().any(|target| target == to_state))
+    {
+        return Ok(());
+    }
+    Err(format!(
+        "Illegal incident state transition {} -> {}; allowed from {}: {}",
+        from_state,
+        to_state,
+        from_state,
+        transitions
+            .get(from_state)
+            .filter(|targets| !targets.is_empty())
+            .map(|targets| targets.join(", "))
+            .unwrap_or_else(|| "none".to_string())
+    ))
+}
 
-        let response_text = response.text();
+/// JSON body for an incident update, refusing an empty update or a field name that isn't a plain
+/// column identifier.
+fn build_incident_update(fields: &HashMap<String, String>) -> Result<serde_json::Value, String> {
+    if fields.is_empty() {
+        return Err("No fields given to update".to_string());
+    }
+    if let Some(field) = fields.keys().find(|field| !is_plain_identifier(field)) {
+        return Err(format!("Invalid incident field name: {}", field));
+    }
+    Ok(serde_json::json!(fields))
+}
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: CatalogVariable,
+/// Longest `short_description` ServiceNow stores.
+const SHORT_DESCRIPTION_MAX_CHARS: usize = 160;
+
+/// Severity keywords and the priority they suggest, most severe first. Keywords are matched as
+/// whole words (or word sequences), case-insensitively.
+const PRIORITY_KEYWORDS: [(&str, &[&str]); 3] = [
+    (
+        "1",
+        &[
+            "critical",
+            "sev1",
+            "sev 1",
+            "p1",
+            "outage",
+            "down",
+            "emergency",
+        ],
+    ),
+    ("2", &["high", "sev2", "sev 2", "p2", "urgent", "major"]),
+    (
+        "3",
+        &["warning", "sev3", "sev 3", "p3", "degraded", "minor"],
+    ),
+];
+
+/// Priority suggested when no severity keyword is found.
+const DEFAULT_EMAIL_PRIORITY: &str = "4";
+
+/// Split a raw email into its `Subject` and body.
+///
+/// The text before the first blank line is taken as the header block if every line in it is a
+/// `Name: value` header or a folded continuation of one; otherwise the email is treated as having
+/// no headers and its first non-empty line stands in for the subject. `Re:`/`Fwd:` prefixes are
+/// dropped from the subject. MIME parts and transfer encodings are not decoded.
+fn split_email(raw_email: &str) -> (String, String) {
+    let normalized = raw_email.replace("\r\n", "\n");
+    let (head, body) = normalized.split_once("\n\n").unwrap_or((&normalized, ""));
+
+    let is_header_block = head.lines().all(|line| {
+        line.starts_with([' ', '\t'])
+            || line
+                .split_once(':')
+                .is_some_and(|(name, _)| !name.is_empty() && !name.contains(' '))
+    });
+
+    let (subject, body) = if is_header_block && !head.trim().is_empty() {
+        let mut subject: Option<String> = None;
+        let mut in_subject = false;
+        for line in head.lines() {
+            if line.starts_with([' ', '\t']) {
+                if let (true, Some(subject)) = (in_subject, subject.as_mut()) {
+                    subject.push(' ');
+                    subject.push_str(line.trim());
+                }
+                continue;
+            }
+            in_subject = false;
+            if let Some((name, value)) = line.split_once(':')
+                && name.eq_ignore_ascii_case("subject")
+                && subject.is_none()
+            {
+                subject = Some(value.trim().to_string());
+                in_subject = true;
+            }
         }
+        (subject.unwrap_or_default(), body.trim().to_string())
+    } else {
+        let text = normalized.trim();
+        let (first, rest) = text.split_once('\n').unwrap_or((text, ""));
+        (first.trim().to_string(), rest.trim().to_string())
+    };
+
+    (strip_reply_prefixes(&subject), body)
+}
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
-
-        Ok(sn_response.result)
+/// Drop any leading `Re:`, `Fwd:`, `Fw:` markers from an email subject.
+fn strip_reply_prefixes(subject: &str) -> String {
+    let mut subject = subject.trim();
+    while let Some((prefix, rest)) = subject.split_once(':') {
+        if !["re", "fw", "fwd"].contains(&prefix.trim().to_ascii_lowercase().as_str()) {
+            break;
+        }
+        subject = rest.trim_start();
     }
+    subject.to_string()
+}
 
-    #[query]
-    async fn list_catalog_item_variables(
-        &self,
-        catalog_item_sys_id: String,
-    ) -> Result<Vec<CatalogVariable>, String> {
-        let url = format!("{}/api/now/table/item_option_new", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
+/// Priority suggested by the most severe keyword in `text`, or `DEFAULT_EMAIL_PRIORITY`.
+fn detect_priority(text: &str) -> &'static str {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect();
+    let padded = format!(" {} ", words.join(" "));
+
+    PRIORITY_KEYWORDS
+        .iter()
+        .find(|(_, keywords)| {
+            keywords
+                .iter()
+                .any(|keyword| padded.contains(&format!(" {} ", keyword)))
+        })
+        .map(|(priority, _)| *priority)
+        .unwrap_or(DEFAULT_EMAIL_PRIORITY)
+}
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+/// Incident fields for a raw alert email: subject as short description (cut to 160
+/// characters), body as description (the subject if the body is empty), and a priority
+/// suggested from severity keywords in either.
+fn parse_email_to_incident(raw_email: &str) -> IncidentInput {
+    let (subject, body) = split_email(raw_email);
+    let priority = detect_priority(&format!("{}\n{}", subject, body)).to_string();
+    let short_description: String = subject.chars().take(SHORT_DESCRIPTION_MAX_CHARS).collect();
+    let description = if body.is_empty() { subject } else { body };
+
+    IncidentInput {
+        short_description,
+        description,
+        priority,
+    }
+}
 
-        let query_params = vec![
-            (
-                "sysparm_query".to_string(),
-                format!("catalog_item={}", catalog_item_sys_id),
-            ),
-            ("sysparm_limit".to_string(), "100".to_string()),
-        ];
+/// Methods the Batch API will service.
+const BATCH_METHODS: [&str; 5] = ["GET", "POST", "PUT", "PATCH", "DELETE"];
 
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .query(query_params)
-            .send()
-            .map_err(|err| err.to_string())?;
+/// Assemble the `/api/now/v1/batch` payload for `requests`.
+///
+/// Each operation carries its own JSON headers and, if present, a base64-encoded body, which is
+/// how the Batch API expects nested request bodies to be sent.
+fn build_batch_body(requests: &[BatchRequest]) -> Result<serde_json::Value, String> {
+    if requests.is_empty() {
+        return Err("A batch needs at least one request".to_string());
+    }
 
-        let response_text = response.text();
+    let mut seen_ids = std::collections::HashSet::new();
+    let mut rest_requests = Vec::with_capacity(requests.len());
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Vec<CatalogVariable>,
+    for request in requests {
+        if request.id.trim().is_empty() {
+            return Err("Every batch request needs a non-empty id".to_string());
+        }
+        if !seen_ids.insert(request.id.as_str()) {
+            return Err(format!("Duplicate batch request id: {}", request.id));
+        }
+        let method = request.method.to_uppercase();
+        if !BATCH_METHODS.contains(&method.as_str()) {
+            return Err(format!(
+                "Unsupported method {} for batch request {}",
+                request.method, request.id
+            ));
+        }
+        if !request.url.starts_with("/api/") {
+            return Err(format!(
+                "Batch request {} must use an instance-relative url starting with /api/, got {}",
+                request.id, request.url
+            ));
         }
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
+        let mut rest_request = serde_json::json!({
+            "id": request.id,
+            "method": method,
+            "url": request.url,
+            "headers": [
+                {"name": "Content-Type", "value": "application/json"},
+                {"name": "Accept", "value": "application/json"}
+            ],
+            "exclude_response_headers": true
+        });
+        if let Some(body) = &request.body {
+            serde_json::from_str::<serde_json::Value>(body).map_err(|err| {
                 format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
+                    "Body of batch request {} is not valid JSON: {}",
+                    request.id, err
                 )
             })?;
+            rest_request["body"] =
+                serde_json::Value::String(general_purpose::STANDARD.encode(body.as_bytes()));
+        }
+        rest_requests.push(rest_request);
+    }
+
+    Ok(serde_json::json!({
+        "batch_request_id": "1",
+        "rest_requests": rest_requests
+    }))
+}
+
+/// Batch operations inserting `incidents`, with ids `incident-0`, `incident-1`, ... in input
+/// order.
+fn bulk_incident_requests(incidents: &[NewIncident]) -> Vec<BatchRequest> {
+    incidents
+        .iter()
+        .enumerate()
+        .map(|(index, incident)| {
+            let mut payload = serde_json::json!({
+                "short_description": incident.short_description,
+                "description": incident.description,
+                "priority": incident.priority
+            });
+            if let Some(caller_id) = &incident.caller_id {
+                payload["caller_id"] = serde_json::Value::String(caller_id.clone());
+            }
+            if let Some(assignment_group) = &incident.assignment_group {
+                payload["assignment_group"] = serde_json::Value::String(assignment_group.clone());
+            }
+            BatchRequest {
+                id: format!("incident-{}", index),
+                method: "POST".to_string(),
+                url: "/api/now/table/incident".to_string(),
+                body: Some(payload.to_string()),
+            }
+        })
+        .collect()
+}
 
-        Ok(sn_response.result)
+/// Each request's outcome in request order, matched to `responses` by batch id.
+///
+/// Operations that failed or were never serviced become per-item errors instead of failing
+/// the whole batch.
+fn correlate_bulk_incidents(
+    requests: &[BatchRequest],
+    responses: Vec<BatchResponse>,
+) -> Vec<(String, Result<Incident, ServiceNowError>)> {
+    #[derive(Deserialize)]
+    struct ServiceNowResponse {
+        result: Incident,
     }
 
-    #[query]
-    async fn list_catalogs(
-        &self,
-        query_str: Option<String>,
-        limit: Option<u32>,
-    ) -> Result<Vec<serde_json::Value>, String> {
-        let url = format!("{}/api/now/table/sc_catalog", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
+    let mut by_id: HashMap<String, BatchResponse> = responses
+        .into_iter()
+        .map(|response| (response.id.clone(), response))
+        .collect();
+
+    requests
+        .iter()
+        .map(|request| {
+            let outcome = match by_id.remove(&request.id) {
+                Some(BatchResponse {
+                    serviced: true,
+                    status_code: Some(status),
+                    body,
+                    ..
+                }) => check_response(status as u16, body.unwrap_or_default()).and_then(|body| {
+                    serde_json::from_str::<ServiceNowResponse>(&body)
+                        .map(|response| response.result)
+                        .map_err(|err| {
+                            ServiceNowError::Parse(format!(
+                                "Failed to parse response: {}. Response was: {}",
+                                err, body
+                            ))
+                        })
+                }),
+                Some(_) => Err(ServiceNowError::Unserviced(format!(
+                    "batch request {} was not run",
+                    request.id
+                ))),
+                None => Err(ServiceNowError::Unserviced(format!(
+                    "no response for batch request {}",
+                    request.id
+                ))),
+            };
+            (request.id.clone(), outcome)
+        })
+        .collect()
+}
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+/// Map a Batch API response onto one `BatchResponse` per operation.
+///
+/// Serviced operations come back with a base64-encoded body which is decoded here; operations
+/// listed under `unserviced_requests` (e.g. because the batch timed out) are reported with
+/// `serviced: false` so callers can retry them.
+fn parse_batch_response(response_text: &str) -> Result<Vec<BatchResponse>, String> {
+    #[derive(Deserialize)]
+    struct ServicedRequest {
+        id: String,
+        status_code: u32,
+        status_text: Option<String>,
+        body: Option<String>,
+    }
 
-        let mut query_params = Vec::new();
-        if let Some(query) = query_str {
-            query_params.push(("sysparm_query".to_string(), query));
-        }
-        query_params.push((
-            "sysparm_limit".to_string(),
-            limit.unwrap_or(100).to_string(),
-        ));
+    #[derive(Deserialize)]
+    struct BatchApiResponse {
+        #[serde(default)]
+        serviced_requests: Vec<ServicedRequest>,
+        #[serde(default)]
+        unserviced_requests: Vec<String>,
+    }
 
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .query(query_params)
-            .send()
-            .map_err(|err| err.to_string())?;
+    let batch: BatchApiResponse = serde_json::from_str(response_text).map_err(|err| {
+        format!(
+            "Failed to parse batch response: {}. Response was: {}",
+            err, response_text
+        )
+    })?;
+
+    let mut responses = Vec::new();
+    for serviced in batch.serviced_requests {
+        let body = match serviced.body.filter(|body| !body.is_empty()) {
+            Some(encoded) => {
+                let decoded = general_purpose::STANDARD.decode(&encoded).map_err(|err| {
+                    format!(
+                        "Failed to decode body of batch request {}: {}",
+                        serviced.id, err
+                    )
+                })?;
+                Some(String::from_utf8_lossy(&decoded).into_owned())
+            }
+            None => None,
+        };
+        responses.push(BatchResponse {
+            id: serviced.id,
+            serviced: true,
+            status_code: Some(serviced.status_code),
+            status_text: serviced.status_text,
+            body,
+        });
+    }
+    for id in batch.unserviced_requests {
+        responses.push(BatchResponse {
+            id,
+            serviced: false,
+            status_code: None,
+            status_text: None,
+            body: None,
+        });
+    }
 
-        let response_text = response.text();
+    Ok(responses)
+}
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Vec<serde_json::Value>,
+/// Watch list (comma-separated user sys_ids or email addresses) with `user_sys_id` added when
+/// `follow` is set and removed otherwise, or `None` if the list already says the same.
+fn update_watch_list(watch_list: &str, user_sys_id: &str, follow: bool) -> Option<String> {
+    let mut entries: Vec<&str> = watch_list
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .collect();
+    let present = entries.contains(&user_sys_id);
+
+    match (follow, present) {
+        (true, true) | (false, false) => None,
+        (true, false) => {
+            entries.push(user_sys_id);
+            Some(entries.join(","))
         }
+        (false, true) => {
+            entries.retain(|entry| *entry != user_sys_id);
+            Some(entries.join(","))
+        }
+    }
+}
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+/// Fetch a file's content from the IMFS contract by its file descriptor.
+async fn get_imfs_file_content(file_descriptor: String) -> Result<String, String> {
+    // parameter definition for the cross contract call to imfs
+    #[derive(Serialize, Deserialize)]
+    struct Args {
+        file_descriptor: String,
+    }
+
+    let args = Args { file_descriptor };
 
-        Ok(sn_response.result)
+    // SAFETY: `imfs` is a systemic applet
+    let contract_addr = Runtime::contract_id_for_name("imfs").unwrap();
+
+    Runtime::call_contract::<String>(
+        contract_addr,
+        "read".to_string(),
+        Some(serde_json::to_string(&args).unwrap()),
+    )
+    .map_err(|err| err.to_string())
+}
+
+/// Query parameters of an Attachment API upload to an incident. The content type is sent as a
+/// header, so one that could break out of it is refused along with an empty file name.
+fn incident_attachment_params(
+    incident_sys_id: &str,
+    file_name: &str,
+    content_type: &str,
+) -> Result<Vec<(String, String)>, String> {
+    if file_name.trim().is_empty() {
+        return Err("file_name must not be empty".to_string());
+    }
+    if content_type.trim().is_empty() || content_type.contains(['\r', '\n']) {
+        return Err(format!("Invalid content type: {:?}", content_type));
     }
+    Ok(vec![
+        ("table_name".to_string(), "incident".to_string()),
+        ("table_sys_id".to_string(), incident_sys_id.to_string()),
+        ("file_name".to_string(), file_name.trim().to_string()),
+    ])
+}
 
-    // Catalog Optimization Functions
-    #[query]
-    async fn get_optimization_recommendations(
-        &self,
-        catalog_item_sys_id: Option<String>,
-    ) -> Result<Vec<serde_json::Value>, String> {
-        let url = format!(
-            "{}/api/now/table/sc_cat_item_optimization",
-            self.get_base_url()?
-        );
-        let auth_header = self.create_auth_header()?;
+/// Table that image fields of catalog items store their attachments under.
+const CATALOG_ITEM_IMAGE_TABLE: &str = "ZZ_YYsc_cat_item";
+
+/// Pick the attachment backing a catalog item's `picture` field, returning its sys_id and
+/// content type.
+///
+/// Image fields are stored as attachments on the `ZZ_YY`-prefixed table, named after the field.
+fn find_picture_attachment(attachments: &[serde_json::Value]) -> Option<(String, String)> {
+    attachments
+        .iter()
+        .find(|attachment| record_field(attachment, "file_name").as_deref() == Some("picture"))
+        .and_then(|attachment| {
+            let sys_id = record_field(attachment, "sys_id")?;
+            let content_type = record_field(attachment, "content_type")
+                .unwrap_or_else(|| "application/octet-stream".to_string());
+            Some((sys_id, content_type))
+        })
+}
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+/// Encode attachment bytes as a `data:` URI.
+fn attachment_data_uri(content_type: &str, content: &[u8]) -> String {
+    format!(
+        "data:{};base64,{}",
+        content_type,
+        general_purpose::STANDARD.encode(content)
+    )
+}
 
-        let mut query_params = Vec::new();
-        if let Some(item_id) = catalog_item_sys_id {
-            query_params.push((
-                "sysparm_query".to_string(),
-                format!("catalog_item={}", item_id),
+/// Map an attachment's `state` (set by the antivirus scan) to `clean`, `pending`, `infected`
+/// or `unknown`. Instances without scanning leave `state` empty, which is reported as `unknown`
+/// rather than assumed clean.
+fn attachment_scan_status(state: Option<&str>) -> &'static str {
+    match state.map(str::to_ascii_lowercase).as_deref() {
+        Some("available") | Some("clean") => "clean",
+        Some("pending") | Some("pending_scan") => "pending",
+        Some("not_available") | Some("quarantined") | Some("infected") => "infected",
+        _ => "unknown",
+    }
+}
+
+/// Dot-walked `sc_item_option_mtom` fields read for the variables of a requested item.
+const REQUEST_VARIABLE_FIELDS: &str = "sc_item_option.item_option_new.question_text,\
+sc_item_option.item_option_new.name,sc_item_option.value";
+
+/// Upper bound on the variables read for one requested item.
+const REQUEST_VARIABLE_FETCH_LIMIT: u32 = 500;
+
+/// Turn `sc_item_option_mtom` rows (dot-walked through `sc_item_option`) into question → value
+/// pairs, in row order.
+///
+/// The question falls back to the variable name when it has no question text, rows with
+/// neither are skipped, and an unanswered variable gets an empty value.
+fn request_variable_pairs(rows: &[serde_json::Value]) -> Vec<(String, String)> {
+    rows.iter()
+        .filter_map(|row| {
+            let question = record_field(row, "sc_item_option.item_option_new.question_text")
+                .or_else(|| record_field(row, "sc_item_option.item_option_new.name"))?;
+            let value = record_field(row, "sc_item_option.value").unwrap_or_default();
+            Some((question, value))
+        })
+        .collect()
+}
+
+/// Whether `name` is a plain ServiceNow table or column identifier (e.g. `incident`,
+/// `u_custom`, `cmdb_ci`).
+fn is_plain_identifier(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Parses the `fields` of an upsert into the record payload, setting the match field to
+/// `match_value` so a created record matches the next upsert. A payload giving the match field a
+/// different value is refused, as the record would stop matching.
+fn upsert_payload(
+    fields: &str,
+    match_field: &str,
+    match_value: &str,
+) -> Result<serde_json::Value, String> {
+    let mut payload: serde_json::Map<String, serde_json::Value> = serde_json::from_str(fields)
+        .map_err(|err| format!("fields must be a JSON object: {}", err))?;
+
+    match payload.get(match_field) {
+        Some(serde_json::Value::String(value)) if value == match_value => {}
+        Some(value) => {
+            return Err(format!(
+                "fields sets {} to {}, which differs from match_value {}",
+                match_field, value, match_value
             ));
         }
-        query_params.push(("sysparm_limit".to_string(), "100".to_string()));
-
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .query(query_params)
-            .send()
-            .map_err(|err| err.to_string())?;
+        None => {
+            payload.insert(
+                match_field.to_string(),
+                serde_json::Value::String(match_value.to_string()),
+            );
+        }
+    }
 
-        let response_text = response.text();
+    Ok(serde_json::Value::Object(payload))
+}
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Vec<serde_json::Value>,
+/// sys_id of the record an upsert should update, or `None` if it should create one. More than
+/// one match is a conflict, as there is no telling which record the caller meant.
+fn upsert_target(
+    matches: &[serde_json::Value],
+    match_field: &str,
+    match_value: &str,
+) -> Result<Option<String>, String> {
+    match matches {
+        [] => Ok(None),
+        [record] => record_field(record, "sys_id")
+            .map(Some)
+            .ok_or_else(|| "Matching record has no sys_id".to_string()),
+        _ => {
+            let sys_ids: Vec<String> = matches
+                .iter()
+                .filter_map(|record| record_field(record, "sys_id"))
+                .collect();
+            Err(ErrorCode::Conflict.tag(format!(
+                "{} records have {}={}: {}",
+                matches.len(),
+                match_field,
+                match_value,
+                sys_ids.join(", ")
+            )))
         }
+    }
+}
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+/// Checks that a table name is a plain ServiceNow table identifier.
+fn validate_table_name(table: &str) -> Result<(), String> {
+    if is_plain_identifier(table) {
+        Ok(())
+    } else {
+        Err(format!("Invalid table name: {}", table))
+    }
+}
 
-        Ok(sn_response.result)
+/// MCP prompt templates for common ServiceNow flows. Each message text names the tools to call,
+/// with `{{argument}}` placeholders for the prompt's arguments.
+const PROMPTS: &str = r#"{
+  "prompts": [
+    {
+      "name": "triage_incident",
+      "description": "Assess an incident's impact and urgency, find related context and propose the next steps",
+      "arguments": [
+        {
+          "name": "sys_id",
+          "description": "system id of the incident to triage",
+          "required": true
+        },
+        {
+          "name": "assignment_group",
+          "description": "group the incident would be assigned to, if already known",
+          "required": false
+        }
+      ],
+      "messages": [
+        {
+          "role": "user",
+          "content": {
+            "type": "text",
+            "text": "Triage the ServiceNow incident with sys_id {{sys_id}}.\n1. Fetch it with `get_incident` and read its history with `get_incident_journal` (incident_sys_id {{sys_id}}).\n2. Check its SLAs with `list_active_slas_for_incident` and flag any close to breaching.\n3. If it names a configuration item, look it up with `get_cmdb_ci`.\n4. Look for open incidents with the same configuration item or short description using `list_incidents`, and say whether this one looks like a duplicate.\n5. Propose an impact, urgency and priority with a one-line justification each, and an assignment group (prefer this one if given: {{assignment_group}}).\nDo not change the incident; end with the suggested `update_incident` fields and a work note for `add_work_note`, and wait for confirmation."
+          }
+        }
+      ]
+    },
+    {
+      "name": "summarize_change_request",
+      "description": "Summarize a change request's scope, risk, schedule, tasks and approval state",
+      "arguments": [
+        {
+          "name": "sys_id",
+          "description": "system id of the change request",
+          "required": true
+        },
+        {
+          "name": "audience",
+          "description": "who the summary is for, e.g. CAB or on-call engineers",
+          "required": false
+        }
+      ],
+      "messages": [
+        {
+          "role": "user",
+          "content": {
+            "type": "text",
+            "text": "Summarize the ServiceNow change request with sys_id {{sys_id}}, written for this audience if one is given: {{audience}}.\n1. Fetch it with `get_change_request_details`.\n2. List its change tasks with `list_records_csv` on the change_task table, query change_request={{sys_id}}, fields number,short_description,state,assigned_to.\n3. Write a summary covering: what changes and why, affected services or configuration items, risk and impact, planned start and end, implementation and backout plans, task progress, and the approval state.\nCall out anything missing (no backout plan, no schedule, unassigned tasks) as a risk."
+          }
+        }
+      ]
+    },
+    {
+      "name": "draft_knowledge_article_from_incident",
+      "description": "Turn a resolved incident into a draft knowledge article describing the symptoms, cause and fix",
+      "arguments": [
+        {
+          "name": "sys_id",
+          "description": "system id of the resolved incident",
+          "required": true
+        },
+        {
+          "name": "knowledge_base_sys_id",
+          "description": "system id of the knowledge base to draft the article in",
+          "required": true
+        }
+      ],
+      "messages": [
+        {
+          "role": "user",
+          "content": {
+            "type": "text",
+            "text": "Draft a knowledge article from the ServiceNow incident with sys_id {{sys_id}}.\n1. Fetch it with `get_incident` and its comments and work notes with `get_incident_journal` (incident_sys_id {{sys_id}}).\n2. Write the article in HTML with the sections Symptoms, Cause, Resolution and Workaround. Generalize it: leave out caller names, internal hostnames, credentials and anything else specific to this one incident.\n3. Show the draft, then on confirmation create it with `create_article` (knowledge_base_sys_id {{knowledge_base_sys_id}}, a short_description phrased as the problem a reader would search for). Leave it unpublished so it can be reviewed."
+          }
+        }
+      ]
+    }
+  ]
+}"#;
+
+/// Remediation hints for the errors tools return, matched by `diagnose`. Rules for specific
+/// messages come before the catch-all rule of their error code.
+const DIAGNOSIS_RULES: &[DiagnosisRule] = &[
+    DiagnosisRule {
+        code: None,
+        patterns: &["config field"],
+        guidance: "Set base_url, username and password in the MCP secrets; base_url is the instance URL, e.g. https://<instance>.service-now.com.",
+    },
+    DiagnosisRule {
+        code: None,
+        patterns: &[TOOL_DISABLED],
+        guidance: "This tool is listed in disabled_tools for this deployment. Use another tool, or ask the administrator to enable it.",
+    },
+    DiagnosisRule {
+        code: None,
+        patterns: &["Invalid sys_id"],
+        guidance: "Pass the record's 32-character sys_id rather than its number (e.g. INC0010001); look the sys_id up with list_incidents or a query on the number first.",
+    },
+    DiagnosisRule {
+        code: None,
+        patterns: &["Invalid table name"],
+        guidance: "Use the table's internal name, e.g. incident or change_request, not its label.",
+    },
+    DiagnosisRule {
+        code: None,
+        patterns: &["Illegal incident state transition"],
+        guidance: "Move the incident through one of the allowed states listed in the message first; the allowed transitions come from incident_transitions in the MCP config.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::Auth),
+        patterns: &["HTTP 401", "HTTP 403", "User Not Authenticated"],
+        guidance: "Check the credentials: the username or password may be wrong, expired or locked out, so rotate the password and update the MCP secrets. A 403 means the user lacks the role or ACL access for this table, e.g. itil for incidents.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::NotFound),
+        patterns: &["HTTP 404", "No Record found"],
+        guidance: "Verify the sys_id and table exist on this instance. ACLs also report records the integration user can't read as not found.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::RateLimited),
+        patterns: &["HTTP 429", "rate limit"],
+        guidance: "Reduce the call rate: wait before retrying, combine calls with batch_rest and fetch fewer records per call. Raising max_retries absorbs short bursts.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::Conflict),
+        patterns: &[],
+        guidance: "The record matched more than expected or changed meanwhile; re-read it and retry with a more specific match or the current values.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::Timeout),
+        patterns: &[],
+        guidance: "The instance took too long to answer; narrow the query with an encoded query or a lower limit, then retry.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::Validation),
+        patterns: &[],
+        guidance: "ServiceNow rejected the request; check the field names and values against the table's dictionary and the tool's parameters.",
+    },
+    DiagnosisRule {
+        code: Some(ErrorCode::Upstream),
+        patterns: &[],
+        guidance: "The instance failed or answered unexpectedly; retry shortly, and check whether the instance is up or being upgraded if it keeps failing.",
+    },
+];
+
+#[smart_contract(tool_gate = ensure_tool_enabled)]
+impl Servicenow for ServicenowContractState {
+    #[constructor]
+    fn new() -> Result<Self, String>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            secrets: Secrets::<ServicenowConfig>::new(),
+            instance: SelectedInstance::default(),
+        })
     }
 
-    // Change Management Functions
     #[query]
-    async fn create_change_request(
+    async fn create_incident(
         &self,
         short_description: String,
         description: String,
         priority: String,
-        risk: Option<String>,
-        impact: Option<String>,
-    ) -> Result<ChangeRequest, String> {
-        let url = format!("{}/api/now/table/change_request", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
+        external_ref: Option<String>,
+        instance: Option<String>,
+    ) -> Result<Incident, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/incident", self.get_base_url()?);
 
         let mut payload = serde_json::json!({
             "short_description": short_description,
@@ -993,1296 +3146,1204 @@ impl Servicenow for ServicenowContractState {
             "priority": priority
         });
 
-        if let Some(risk_val) = risk {
-            payload["risk"] = serde_json::Value::String(risk_val);
-        }
-        if let Some(impact_val) = impact {
-            payload["impact"] = serde_json::Value::String(impact_val);
-        }
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
-
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: ChangeRequest,
-        }
+        apply_external_ref(&mut payload, external_ref)?;
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        let api = self.api()?;
 
-        Ok(sn_response.result)
+        api.post_json(&url, vec![], &payload)
     }
 
     #[query]
-    async fn list_change_requests(
+    async fn create_incidents_bulk(
         &self,
-        query_str: Option<String>,
-        limit: Option<u32>,
-    ) -> Result<Vec<ChangeRequest>, String> {
-        let url = format!("{}/api/now/table/change_request", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
+        incidents: Vec<NewIncident>,
+        instance: Option<String>,
+    ) -> Result<Vec<BulkIncidentResult>, String> {
+        self.select_instance(instance.as_deref())?;
+        let requests = bulk_incident_requests(&incidents);
+        let responses = self.send_batch(&requests)?;
+
+        Ok(correlate_bulk_incidents(&requests, responses)
+            .into_iter()
+            .map(|(id, outcome)| match outcome {
+                Ok(incident) => BulkIncidentResult {
+                    id,
+                    incident: Some(incident),
+                    error: None,
+                },
+                Err(err) => BulkIncidentResult {
+                    id,
+                    incident: None,
+                    error: Some(err.to_string()),
+                },
+            })
+            .collect())
+    }
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+    #[query]
+    async fn create_incident_from_template(
+        &self,
+        template_sys_id: String,
+        overrides: String,
+        instance: Option<String>,
+    ) -> Result<Incident, String> {
+        self.select_instance(instance.as_deref())?;
+        let base_url = self.get_base_url()?;
+        let api = self.api()?;
 
-        let mut query_params = Vec::new();
-        if let Some(query) = query_str {
-            query_params.push(("sysparm_query".to_string(), query));
+        let template_url = format!(
+            "{}/api/now/table/sys_template/{}",
+            base_url, template_sys_id
+        );
+        #[derive(Deserialize)]
+        struct Template {
+            table: Option<String>,
+            template: Option<String>,
         }
-        query_params.push((
-            "sysparm_limit".to_string(),
-            limit.unwrap_or(100).to_string(),
-        ));
-
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .query(query_params)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Vec<ChangeRequest>,
+        let query_params = vec![(
+            "sysparm_fields".to_string(),
+            "name,table,template".to_string(),
+        )];
+        let template: Template = api
+            .get_json(&template_url, query_params)
+            .map_err(|err| format!("Failed to fetch template {}: {}", template_sys_id, err))?;
+
+        if let Some(table) = template
+            .table
+            .as_deref()
+            .filter(|table| !table.is_empty() && *table != "incident")
+        {
+            return Err(format!(
+                "Template {} is for table {}, not incident",
+                template_sys_id, table
+            ));
         }
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        let payload =
+            merge_template_overrides(template.template.as_deref().unwrap_or(""), &overrides)?;
 
-        Ok(sn_response.result)
+        api.post_json(
+            &format!("{}/api/now/table/incident", base_url),
+            vec![],
+            &payload,
+        )
     }
 
     #[query]
-    async fn get_change_request_details(&self, sys_id: String) -> Result<ChangeRequest, String> {
-        let url = format!(
-            "{}/api/now/table/change_request/{}",
-            self.get_base_url()?,
-            sys_id
-        );
-        let auth_header = self.create_auth_header()?;
+    async fn create_incident_from_email(
+        &self,
+        raw_email: String,
+        external_ref: Option<String>,
+        instance: Option<String>,
+    ) -> Result<Incident, String> {
+        self.select_instance(instance.as_deref())?;
+        let input = parse_email_to_incident(&raw_email);
+        if input.short_description.is_empty() {
+            return Err("Email has no subject or text to describe the incident".to_string());
+        }
+        self.create_incident(
+            input.short_description,
+            input.description,
+            input.priority,
+            external_ref,
+            instance,
+        )
+        .await
+    }
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+    #[query]
+    async fn get_incident(
+        &self,
+        sys_id: String,
+        fields: Option<Vec<String>>,
+        instance: Option<String>,
+    ) -> Result<Incident, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/incident/{}", self.get_base_url()?, sys_id);
+        let api = self.api()?;
 
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .send()
-            .map_err(|err| err.to_string())?;
+        let mut query_params = Vec::new();
+        if let Some(fields) = sysparm_fields(fields.as_deref())? {
+            query_params.push(("sysparm_fields".to_string(), fields));
+        }
 
-        let response_text = response.text();
+        api.get_json(&url, query_params)
+    }
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: ChangeRequest,
+    #[query]
+    async fn get_incidents_by_ids(
+        &self,
+        sys_ids: Vec<String>,
+        instance: Option<String>,
+    ) -> Result<Vec<Incident>, String> {
+        self.select_instance(instance.as_deref())?;
+        let mut records = Vec::new();
+        for (query, count) in sys_id_in_queries(&sys_ids, SYS_ID_IN_CHUNK)? {
+            let chunk: Vec<serde_json::Value> =
+                self.list_records("incident", Some(query), count, 0, false, None)?;
+            records.extend(chunk);
         }
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
-
-        Ok(sn_response.result)
+        order_by_sys_ids(records, &sys_ids)
+            .into_iter()
+            .map(|record| {
+                serde_json::from_value(record).map_err(|err| {
+                    ServiceNowError::Parse(format!("Failed to parse incident: {}", err)).into()
+                })
+            })
+            .collect()
     }
 
     #[query]
-    async fn add_change_task(
+    async fn delete_incident(
         &self,
-        change_request_sys_id: String,
-        short_description: String,
-        description: String,
-        assigned_to: Option<String>,
-    ) -> Result<ChangeTask, String> {
-        let url = format!("{}/api/now/table/change_task", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
-
-        let mut payload = serde_json::json!({
-            "short_description": short_description,
-            "description": description,
-            "change_request": change_request_sys_id
-        });
-
-        if let Some(assignee) = assigned_to {
-            payload["assigned_to"] = serde_json::Value::String(assignee);
-        }
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
-
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<(), String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/incident/{}", self.get_base_url()?, sys_id);
+        let api = self.api()?;
 
-        let response_text = response.text();
+        api.delete(&url, vec![])?;
+        Ok(())
+    }
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: ChangeTask,
+    #[query]
+    async fn delete_incident_cascade(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<CascadeDeleteReport, String> {
+        self.select_instance(instance.as_deref())?;
+        validate_sys_id(&sys_id)?;
+        let targets = cascade_targets(self.secrets.config().cascade_delete_tables.as_deref())?;
+
+        // Find everything first, so a guard that trips leaves the incident untouched
+        let mut plan = Vec::new();
+        for target in targets {
+            let records: Vec<serde_json::Value> = self.list_records(
+                target.table,
+                Some(target.query(&sys_id)),
+                MAX_CASCADE_RECORDS + 1,
+                0,
+                false,
+                Some("sys_id".to_string()),
+            )?;
+            plan.push((target, cascade_sys_ids(target.table, &records)?));
         }
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
-
-        Ok(sn_response.result)
+        let base_url = self.get_base_url()?;
+        let api = self.api()?;
+        run_cascade_delete(&sys_id, plan, |target, record_sys_id| {
+            api.delete(&target.delete_url(&base_url, record_sys_id), vec![])
+        })
     }
 
     #[query]
-    async fn submit_change_for_approval(&self, sys_id: String) -> Result<ChangeRequest, String> {
-        let url = format!(
-            "{}/api/now/table/change_request/{}",
-            self.get_base_url()?,
-            sys_id
-        );
-        let auth_header = self.create_auth_header()?;
-
-        let payload = serde_json::json!({
-            "state": "1" // Submitted for approval
-        });
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
+    async fn query_incidents(
+        &self,
+        query_str: String,
+        limit: u32,
+        offset: Option<u32>,
+        fetch_all: Option<bool>,
+        fields: Option<Vec<String>>,
+        instance: Option<String>,
+    ) -> Result<Vec<Incident>, String> {
+        self.select_instance(instance.as_deref())?;
+        self.list_records(
+            "incident",
+            Some(query_str),
+            limit,
+            offset.unwrap_or(0),
+            fetch_all.unwrap_or(false),
+            sysparm_fields(fields.as_deref())?,
+        )
+    }
 
-        let response = HttpClient::request(&url, HttpMethod::Put)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
+    #[query]
+    async fn add_comment(
+        &self,
+        incident_sys_id: String,
+        comment: String,
+        instance: Option<String>,
+    ) -> Result<Comment, String> {
+        self.select_instance(instance.as_deref())?;
+        self.add_journal_entry(incident_sys_id, "comments", comment)
+    }
 
-        let response_text = response.text();
+    #[query]
+    async fn add_work_note(
+        &self,
+        incident_sys_id: String,
+        note: String,
+        instance: Option<String>,
+    ) -> Result<Comment, String> {
+        self.select_instance(instance.as_deref())?;
+        self.add_journal_entry(incident_sys_id, "work_notes", note)
+    }
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: ChangeRequest,
+    #[query]
+    async fn add_comment_bulk(
+        &self,
+        sys_ids: Vec<String>,
+        comment: String,
+        visibility: CommentVisibility,
+        instance: Option<String>,
+    ) -> Result<BulkResult<String>, String> {
+        self.select_instance(instance.as_deref())?;
+        if comment.trim().is_empty() {
+            return Err("comment must not be empty".to_string());
         }
+        let element = visibility.journal_field();
+        Ok(add_to_each(sys_ids, |sys_id| {
+            self.add_journal_entry(sys_id.to_string(), element, comment.clone())
+                .map(|_| ())
+        }))
+    }
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
-
-        Ok(sn_response.result)
+    #[query]
+    async fn get_incident_journal(
+        &self,
+        incident_sys_id: String,
+        field: Option<String>,
+        instance: Option<String>,
+    ) -> Result<Vec<JournalEntry>, String> {
+        self.select_instance(instance.as_deref())?;
+        let query = journal_query(&incident_sys_id, field.as_deref())?;
+        let mut entries: Vec<JournalEntry> = self.list_records(
+            "sys_journal_field",
+            Some(query),
+            100,
+            0,
+            true,
+            Some("sys_created_on,sys_created_by,element,value".to_string()),
+        )?;
+        sort_journal(&mut entries);
+        Ok(entries)
     }
 
     #[query]
-    async fn approve_change(
+    async fn transition_incident(
         &self,
         sys_id: String,
-        approval_notes: Option<String>,
-    ) -> Result<ChangeRequest, String> {
-        let url = format!(
-            "{}/api/now/table/change_request/{}",
-            self.get_base_url()?,
-            sys_id
-        );
-        let auth_header = self.create_auth_header()?;
-
-        let mut payload = serde_json::json!({
-            "state": "2" // Approved
-        });
-
-        if let Some(notes) = approval_notes {
-            payload["approval_notes"] = serde_json::Value::String(notes);
-        }
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
-
-        let response = HttpClient::request(&url, HttpMethod::Put)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
+        to_state: String,
+        instance: Option<String>,
+    ) -> Result<Incident, String> {
+        self.select_instance(instance.as_deref())?;
+        self.check_incident_transition(&sys_id, &to_state)?;
 
-        let response_text = response.text();
+        let url = format!("{}/api/now/table/incident/{}", self.get_base_url()?, sys_id);
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: ChangeRequest,
-        }
+        let payload = serde_json::json!({ "state": to_state });
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        let api = self.api()?;
 
-        Ok(sn_response.result)
+        api.put_json(&url, vec![], &payload)
     }
 
     #[query]
-    async fn reject_change(
+    async fn resolve_incident(
         &self,
         sys_id: String,
-        rejection_notes: String,
-    ) -> Result<ChangeRequest, String> {
-        let url = format!(
-            "{}/api/now/table/change_request/{}",
-            self.get_base_url()?,
-            sys_id
-        );
-        let auth_header = self.create_auth_header()?;
+        resolution_notes: String,
+        instance: Option<String>,
+    ) -> Result<Incident, String> {
+        self.select_instance(instance.as_deref())?;
+        self.check_incident_transition(&sys_id, "6")?;
+
+        let url = format!("{}/api/now/table/incident/{}", self.get_base_url()?, sys_id);
 
         let payload = serde_json::json!({
-            "state": "3", // Rejected
-            "rejection_notes": rejection_notes
+            "state": "6", // Resolved state
+            "close_notes": resolution_notes
         });
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
-
-        let response = HttpClient::request(&url, HttpMethod::Put)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: ChangeRequest,
-        }
-
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        let api = self.api()?;
 
-        Ok(sn_response.result)
+        api.put_json(&url, vec![], &payload)
     }
 
-    // Agile Story Management Functions
     #[query]
-    async fn create_story(
+    async fn update_incident(
         &self,
-        short_description: String,
-        description: String,
-        priority: Option<String>,
-        story_points: Option<String>,
-        epic_sys_id: Option<String>,
-    ) -> Result<Story, String> {
-        let url = format!("{}/api/now/table/rm_story", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
-
-        let mut payload = serde_json::json!({
-            "short_description": short_description,
-            "description": description
-        });
-
-        if let Some(pri) = priority {
-            payload["priority"] = serde_json::Value::String(pri);
-        }
-        if let Some(points) = story_points {
-            payload["story_points"] = serde_json::Value::String(points);
-        }
-        if let Some(epic) = epic_sys_id {
-            payload["epic"] = serde_json::Value::String(epic);
-        }
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
-
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
+        sys_id: String,
+        fields: HashMap<String, String>,
+        instance: Option<String>,
+    ) -> Result<Incident, String> {
+        self.select_instance(instance.as_deref())?;
+        let payload = build_incident_update(&fields)?;
+        let url = format!("{}/api/now/table/incident/{}", self.get_base_url()?, sys_id);
+        let api = self.api()?;
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Story,
-        }
+        api.patch_json(&url, vec![], &payload)
+    }
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
+    /// Upload an IMFS file as an attachment of an incident. The file is sent as is, with
+    /// `content_type` as its `Content-Type`.
+    #[query]
+    async fn attach_file_to_incident(
+        &self,
+        incident_sys_id: String,
+        file_name: String,
+        content_type: String,
+        file_descriptor: String,
+        instance: Option<String>,
+    ) -> Result<Attachment, String> {
+        self.select_instance(instance.as_deref())?;
+        let query_params = incident_attachment_params(&incident_sys_id, &file_name, &content_type)?;
+        let content = get_imfs_file_content(file_descriptor).await?;
+        let url = format!("{}/api/now/attachment/file", self.get_base_url()?);
+        let api = self.api()?.header("Content-Type", content_type.trim());
+
+        let response = api
+            .send(HttpMethod::Post, &url, query_params, Some(content))
+            .map_err(|err| {
                 format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
+                    "Failed to attach {} to incident {}: {}",
+                    file_name, incident_sys_id, err
                 )
             })?;
+        api.parse(&response)
+    }
 
-        Ok(sn_response.result)
+    #[query]
+    async fn list_incidents(
+        &self,
+        query_str: Option<String>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        fetch_all: Option<bool>,
+        fields: Option<Vec<String>>,
+        instance: Option<String>,
+    ) -> Result<Vec<Incident>, String> {
+        self.select_instance(instance.as_deref())?;
+        let query = query_str.unwrap_or_default();
+        let limit_val = limit.unwrap_or(100);
+        self.query_incidents(query, limit_val, offset, fetch_all, fields, instance)
+            .await
     }
 
     #[query]
-    async fn list_stories(
+    async fn list_incidents_paged(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
-    ) -> Result<Vec<Story>, String> {
-        let url = format!("{}/api/now/table/rm_story", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
+        offset: Option<u32>,
+        instance: Option<String>,
+    ) -> Result<IncidentPage, String> {
+        self.select_instance(instance.as_deref())?;
+        let limit = limit.unwrap_or(100);
+        let offset = offset.unwrap_or(0);
+        if limit == 0 {
+            return Err("limit must be greater than 0".to_string());
+        }
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+        let url = format!("{}/api/now/table/incident", self.get_base_url()?);
+        let api = self.api()?;
 
         let mut query_params = Vec::new();
         if let Some(query) = query_str {
             query_params.push(("sysparm_query".to_string(), query));
         }
-        query_params.push((
-            "sysparm_limit".to_string(),
-            limit.unwrap_or(100).to_string(),
-        ));
-
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .query(query_params)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Vec<Story>,
-        }
+        query_params.push(("sysparm_limit".to_string(), limit.to_string()));
+        query_params.push(("sysparm_offset".to_string(), offset.to_string()));
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        let result: Vec<Incident> = api.get_json(&url, query_params)?;
 
-        Ok(sn_response.result)
+        Ok(IncidentPage {
+            next_offset: next_page_offset(offset, limit, result.len()),
+            results: result,
+        })
     }
 
     #[query]
-    async fn delete_story_dependency(&self, dependency_sys_id: String) -> Result<(), String> {
-        let url = format!(
-            "{}/api/now/table/rm_story_dependency/{}",
-            self.get_base_url()?,
-            dependency_sys_id
-        );
-        let auth_header = self.create_auth_header()?;
+    async fn find_by_external_ref(
+        &self,
+        table: String,
+        external_ref: String,
+        instance: Option<String>,
+    ) -> Result<Vec<serde_json::Value>, String> {
+        self.select_instance(instance.as_deref())?;
+        validate_table_name(&table)?;
+        validate_external_ref(&external_ref)?;
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+        let url = format!("{}/api/now/table/{}", self.get_base_url()?, table);
+        let api = self.api()?;
 
-        let response = HttpClient::request(&url, HttpMethod::Delete)
-            .headers(headers)
-            .send()
-            .map_err(|err| err.to_string())?;
+        let query_params = vec![
+            (
+                "sysparm_query".to_string(),
+                external_ref_query(&external_ref),
+            ),
+            ("sysparm_limit".to_string(), "100".to_string()),
+        ];
 
-        if response.status() >= 204 && response.status() < 300 {
-            Ok(())
-        } else {
-            Err(format!(
-                "Failed to delete story dependency. Status: {}",
-                response.status()
-            ))
-        }
+        api.get_json(&url, query_params)
     }
 
-    // Agile Epic Management Functions
     #[query]
-    async fn create_epic(
+    async fn dedupe_incidents(
         &self,
-        short_description: String,
-        description: String,
-        priority: Option<String>,
-    ) -> Result<Epic, String> {
-        let url = format!("{}/api/now/table/rm_epic", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
-
-        let mut payload = serde_json::json!({
-            "short_description": short_description,
-            "description": description
-        });
-
-        if let Some(pri) = priority {
-            payload["priority"] = serde_json::Value::String(pri);
+        query: String,
+        group_by_field: String,
+        instance: Option<String>,
+    ) -> Result<Vec<DedupeGroup>, String> {
+        self.select_instance(instance.as_deref())?;
+        let fields: Vec<&str> = group_by_field
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .collect();
+        if fields.is_empty() {
+            return Err("group_by_field must name at least one field".to_string());
+        }
+        if let Some(field) = fields.iter().find(|field| !is_plain_identifier(field)) {
+            return Err(format!("Invalid field name: {}", field));
         }
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
-
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
+        let url = format!("{}/api/now/table/incident", self.get_base_url()?);
+        let api = self.api()?;
 
-        let response_text = response.text();
+        let mut sysparm_fields = vec!["sys_id", "number", "sys_created_on"];
+        sysparm_fields.extend(fields.iter().copied());
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Epic,
-        }
+        let query_params = vec![
+            ("sysparm_query".to_string(), query),
+            ("sysparm_fields".to_string(), sysparm_fields.join(",")),
+            ("sysparm_limit".to_string(), DEDUPE_FETCH_LIMIT.to_string()),
+        ];
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        let result: Vec<serde_json::Value> = api.get_json(&url, query_params)?;
 
-        Ok(sn_response.result)
+        Ok(group_duplicates(&result, &fields))
     }
 
     #[query]
-    async fn list_epics(
+    async fn link_duplicate_incidents(
         &self,
-        query_str: Option<String>,
-        limit: Option<u32>,
-    ) -> Result<Vec<Epic>, String> {
-        let url = format!("{}/api/now/table/rm_epic", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
+        primary_sys_id: String,
+        duplicate_sys_ids: Vec<String>,
+        instance: Option<String>,
+    ) -> Result<Vec<Incident>, String> {
+        self.select_instance(instance.as_deref())?;
+        if duplicate_sys_ids.contains(&primary_sys_id) {
+            return Err("An incident cannot be linked as a duplicate of itself".to_string());
+        }
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+        let api = self.api()?;
+        let mut linked = Vec::new();
 
-        let mut query_params = Vec::new();
-        if let Some(query) = query_str {
-            query_params.push(("sysparm_query".to_string(), query));
-        }
-        query_params.push((
-            "sysparm_limit".to_string(),
-            limit.unwrap_or(100).to_string(),
-        ));
+        for sys_id in duplicate_sys_ids {
+            let url = format!("{}/api/now/table/incident/{}", self.get_base_url()?, sys_id);
 
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .query(query_params)
-            .send()
-            .map_err(|err| err.to_string())?;
+            let payload = serde_json::json!({
+                "parent_incident": primary_sys_id
+            });
 
-        let response_text = response.text();
+            let incident: Incident = api
+                .patch_json(&url, vec![], &payload)
+                .map_err(|err| format!("Failed to link incident {}: {}", sys_id, err))?;
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Vec<Epic>,
+            linked.push(incident);
         }
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        Ok(linked)
+    }
 
-        Ok(sn_response.result)
+    #[query]
+    async fn list_active_slas_for_incident(
+        &self,
+        incident_sys_id: String,
+        instance: Option<String>,
+    ) -> Result<Vec<TaskSla>, String> {
+        self.select_instance(instance.as_deref())?;
+        validate_sys_id(&incident_sys_id)?;
+        let mut slas = self.list_records(
+            "task_sla",
+            Some(format!("task={}^active=true", incident_sys_id)),
+            TASK_SLA_FETCH_LIMIT,
+            0,
+            true,
+            Some(TASK_SLA_FIELDS.to_string()),
+        )?;
+        sort_slas_by_urgency(&mut slas);
+        Ok(slas)
     }
 
-    // Scrum Task Management Functions
     #[query]
-    async fn create_scrum_task(
+    async fn list_breaching_slas(
         &self,
-        short_description: String,
-        description: String,
-        story_sys_id: Option<String>,
-        assigned_to: Option<String>,
-    ) -> Result<ScrumTask, String> {
-        let url = format!("{}/api/now/table/rm_scrum_task", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
-
-        let mut payload = serde_json::json!({
-            "short_description": short_description,
-            "description": description
-        });
-
-        if let Some(story) = story_sys_id {
-            payload["story"] = serde_json::Value::String(story);
-        }
-        if let Some(assignee) = assigned_to {
-            payload["assigned_to"] = serde_json::Value::String(assignee);
-        }
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
-
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: ScrumTask,
-        }
-
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
-
-        Ok(sn_response.result)
+        limit: Option<u32>,
+        instance: Option<String>,
+    ) -> Result<Vec<TaskSla>, String> {
+        self.select_instance(instance.as_deref())?;
+        let mut slas = self.list_records(
+            "task_sla",
+            Some("has_breached=true^active=true^ORDERBYDESCbusiness_percentage".to_string()),
+            limit.unwrap_or(100),
+            0,
+            false,
+            Some(TASK_SLA_FIELDS.to_string()),
+        )?;
+        sort_slas_by_urgency(&mut slas);
+        Ok(slas)
     }
 
     #[query]
-    async fn list_scrum_tasks(
+    async fn incidents_near_sla_breach(
         &self,
-        query_str: Option<String>,
-        limit: Option<u32>,
-    ) -> Result<Vec<ScrumTask>, String> {
-        let url = format!("{}/api/now/table/rm_scrum_task", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-
-        let mut query_params = Vec::new();
-        if let Some(query) = query_str {
-            query_params.push(("sysparm_query".to_string(), query));
-        }
-        query_params.push((
-            "sysparm_limit".to_string(),
-            limit.unwrap_or(100).to_string(),
-        ));
-
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .query(query_params)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Vec<ScrumTask>,
+        threshold_percent: u8,
+        limit: u32,
+        instance: Option<String>,
+    ) -> Result<Vec<SlaBreachRisk>, String> {
+        self.select_instance(instance.as_deref())?;
+        let slas: Vec<TaskSla> = self.list_records(
+            "task_sla",
+            Some(near_breach_sla_query(threshold_percent)?),
+            limit,
+            0,
+            false,
+            Some(TASK_SLA_FIELDS.to_string()),
+        )?;
+
+        let incident_ids: Vec<String> = slas.iter().filter_map(|sla| sla.task.clone()).collect();
+        let mut incidents = Vec::new();
+        for (query, count) in sys_id_in_queries(&incident_ids, SYS_ID_IN_CHUNK)? {
+            let chunk: Vec<serde_json::Value> =
+                self.list_records("incident", Some(query), count, 0, false, None)?;
+            incidents.extend(chunk);
         }
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
-
-        Ok(sn_response.result)
+        join_slas_with_incidents(slas, incidents, threshold_percent)
     }
 
-    // Project Management Functions
     #[query]
-    async fn create_project(
+    async fn get_related_lists(
         &self,
-        name: String,
-        short_description: String,
-        goal: Option<String>,
-    ) -> Result<Project, String> {
-        let url = format!("{}/api/now/table/promin_project", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
-
-        let mut payload = serde_json::json!({
-            "name": name,
-            "short_description": short_description
-        });
-
-        if let Some(goal_val) = goal {
-            payload["goal"] = serde_json::Value::String(goal_val);
-        }
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
-
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Project,
+        table: String,
+        instance: Option<String>,
+    ) -> Result<Vec<RelatedList>, String> {
+        self.select_instance(instance.as_deref())?;
+        validate_table_name(&table)?;
+        let entries: Vec<serde_json::Value> = self.list_records(
+            "sys_ui_related_list_entry",
+            Some(format!("list_id.name={}^ORDERBYposition", table)),
+            RELATED_LIST_FETCH_LIMIT,
+            0,
+            true,
+            Some("related_list,position".to_string()),
+        )?;
+        let definitions: Vec<String> = entries
+            .iter()
+            .filter_map(|entry| record_field(entry, "related_list"))
+            .collect();
+
+        let mut relationships = Vec::new();
+        for (query, count) in sys_id_in_queries(&relationship_ids(&definitions), SYS_ID_IN_CHUNK)? {
+            let chunk: Vec<serde_json::Value> = self.list_records(
+                "sys_relationship",
+                Some(query),
+                count,
+                0,
+                false,
+                Some("sys_id,name,basic_query_from".to_string()),
+            )?;
+            relationships.extend(chunk);
         }
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
-
-        Ok(sn_response.result)
+        Ok(build_related_lists(definitions, &relationships))
     }
 
     #[query]
-    async fn list_projects(
+    async fn list_records_csv(
         &self,
-        query_str: Option<String>,
+        table: String,
+        query: Option<String>,
+        fields: Vec<String>,
         limit: Option<u32>,
-    ) -> Result<Vec<Project>, String> {
-        let url = format!("{}/api/now/table/promin_project", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+        instance: Option<String>,
+    ) -> Result<String, String> {
+        self.select_instance(instance.as_deref())?;
+        validate_table_name(&table)?;
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|field| field.trim().to_string())
+            .collect();
+        validate_field_list(&fields)?;
+        let records: Vec<serde_json::Value> = self.list_records(
+            &table,
+            query,
+            limit.unwrap_or(100),
+            0,
+            false,
+            Some(fields.join(",")),
+        )?;
+        Ok(records_to_csv(&records, &fields))
+    }
 
-        let mut query_params = Vec::new();
-        if let Some(query) = query_str {
-            query_params.push(("sysparm_query".to_string(), query));
+    #[query]
+    async fn upsert_record(
+        &self,
+        table: String,
+        match_field: String,
+        match_value: String,
+        fields: String,
+        instance: Option<String>,
+    ) -> Result<(serde_json::Value, bool), String> {
+        self.select_instance(instance.as_deref())?;
+        validate_table_name(&table)?;
+        if !is_plain_identifier(&match_field) {
+            return Err(format!("Invalid field name: {}", match_field));
         }
-        query_params.push((
-            "sysparm_limit".to_string(),
-            limit.unwrap_or(100).to_string(),
-        ));
-
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .query(query_params)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Vec<Project>,
+        let payload = upsert_payload(&fields, &match_field, &match_value)?;
+
+        // Two rows are enough to tell a unique match from an ambiguous one
+        let matches: Vec<serde_json::Value> = self.list_records(
+            &table,
+            Some(field_equals_query(&match_field, &match_value)),
+            2,
+            0,
+            false,
+            Some("sys_id".to_string()),
+        )?;
+
+        let url = format!("{}/api/now/table/{}", self.get_base_url()?, table);
+        let api = self.api()?;
+
+        match upsert_target(&matches, &match_field, &match_value)? {
+            Some(sys_id) => {
+                let record = api.patch_json(&format!("{}/{}", url, sys_id), vec![], &payload)?;
+                Ok((record, false))
+            }
+            None => {
+                let record = api.post_json(&url, vec![], &payload)?;
+                Ok((record, true))
+            }
         }
-
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
-
-        Ok(sn_response.result)
     }
 
-    // Workflow Management Functions
+    // Service Catalog Functions
     #[query]
-    async fn list_workflows(
+    async fn list_catalog_items(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
-    ) -> Result<Vec<Workflow>, String> {
-        let url = format!("{}/api/now/table/wf_workflow", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-
-        let mut query_params = Vec::new();
-        if let Some(query) = query_str {
-            query_params.push(("sysparm_query".to_string(), query));
-        }
-        query_params.push((
-            "sysparm_limit".to_string(),
-            limit.unwrap_or(100).to_string(),
-        ));
-
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .query(query_params)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Vec<Workflow>,
-        }
-
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
-
-        Ok(sn_response.result)
+        offset: Option<u32>,
+        fetch_all: Option<bool>,
+        instance: Option<String>,
+    ) -> Result<Vec<CatalogItem>, String> {
+        self.select_instance(instance.as_deref())?;
+        self.list_records(
+            "sc_cat_item",
+            query_str,
+            limit.unwrap_or(100),
+            offset.unwrap_or(0),
+            fetch_all.unwrap_or(false),
+            None,
+        )
     }
 
     #[query]
-    async fn get_workflow(&self, sys_id: String) -> Result<Workflow, String> {
+    async fn get_catalog_item(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<CatalogItem, String> {
+        self.select_instance(instance.as_deref())?;
         let url = format!(
-            "{}/api/now/table/wf_workflow/{}",
+            "{}/api/now/table/sc_cat_item/{}",
             self.get_base_url()?,
             sys_id
         );
-        let auth_header = self.create_auth_header()?;
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Workflow,
-        }
-
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        let api = self.api()?;
 
-        Ok(sn_response.result)
+        api.get_json(&url, vec![])
     }
 
     #[query]
-    async fn create_workflow(
+    async fn get_catalog_item_picture(
         &self,
-        name: String,
-        description: Option<String>,
-        table: String,
-    ) -> Result<Workflow, String> {
-        let url = format!("{}/api/now/table/wf_workflow", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
-
-        let mut payload = serde_json::json!({
-            "name": name,
-            "table": table
-        });
+        item_sys_id: String,
+        instance: Option<String>,
+    ) -> Result<String, String> {
+        self.select_instance(instance.as_deref())?;
+        let base_url = self.get_base_url()?;
+        let api = self.api()?;
 
-        if let Some(desc) = description {
-            payload["description"] = serde_json::Value::String(desc);
-        }
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
-
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
+        let query_params = vec![
+            (
+                "sysparm_query".to_string(),
+                format!(
+                    "table_name={}^table_sys_id={}",
+                    CATALOG_ITEM_IMAGE_TABLE, item_sys_id
+                ),
+            ),
+            (
+                "sysparm_fields".to_string(),
+                "sys_id,file_name,content_type".to_string(),
+            ),
+        ];
 
-        let response_text = response.text();
+        let result: Vec<serde_json::Value> =
+            api.get_json(&format!("{}/api/now/attachment", base_url), query_params)?;
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Workflow,
-        }
+        let Some((attachment_sys_id, content_type)) = find_picture_attachment(&result) else {
+            return Err(format!("Catalog item {} has no picture", item_sys_id));
+        };
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
+        let file_url = format!("{}/api/now/attachment/{}/file", base_url, attachment_sys_id);
+        let content = api
+            .send(HttpMethod::Get, &file_url, vec![], None)
+            .map_err(|err| {
                 format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
+                    "Failed to download picture of catalog item {}: {}",
+                    item_sys_id, err
                 )
             })?;
+        // Outcall responses arrive as text, so bytes that are not valid UTF-8 have already been
+        // replaced by the time they reach us; refuse to hand out a corrupted image.
+        if content.contains('\u{FFFD}') {
+            return Err(format!(
+                "Picture of catalog item {} ({}) could not be transferred intact; download it from {}",
+                item_sys_id, content_type, file_url
+            ));
+        }
 
-        Ok(sn_response.result)
+        Ok(attachment_data_uri(&content_type, content.as_bytes()))
     }
 
     #[query]
-    async fn delete_workflow(&self, sys_id: String) -> Result<(), String> {
+    async fn get_attachment_scan_status(
+        &self,
+        attachment_sys_id: String,
+        instance: Option<String>,
+    ) -> Result<String, String> {
+        self.select_instance(instance.as_deref())?;
+        validate_sys_id(&attachment_sys_id)?;
         let url = format!(
-            "{}/api/now/table/wf_workflow/{}",
+            "{}/api/now/table/sys_attachment/{}",
             self.get_base_url()?,
-            sys_id
+            attachment_sys_id
         );
-        let auth_header = self.create_auth_header()?;
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+        let api = self.api()?;
 
-        let response = HttpClient::request(&url, HttpMethod::Delete)
-            .headers(headers)
-            .send()
-            .map_err(|err| err.to_string())?;
+        let result: serde_json::Value = api.get_json(
+            &url,
+            vec![("sysparm_fields".to_string(), "state".to_string())],
+        )?;
 
-        if response.status() >= 204 && response.status() < 300 {
-            Ok(())
-        } else {
-            Err(format!(
-                "Failed to delete workflow. Status: {}",
-                response.status()
-            ))
-        }
+        Ok(attachment_scan_status(record_field(&result, "state").as_deref()).to_string())
     }
 
-    // Script Include Management Functions
     #[query]
-    async fn list_script_includes(
+    async fn get_request_variables(
         &self,
-        query_str: Option<String>,
-        limit: Option<u32>,
-    ) -> Result<Vec<ScriptInclude>, String> {
-        let url = format!("{}/api/now/table/sys_script_include", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-
-        let mut query_params = Vec::new();
-        if let Some(query) = query_str {
-            query_params.push(("sysparm_query".to_string(), query));
-        }
-        query_params.push((
-            "sysparm_limit".to_string(),
-            limit.unwrap_or(100).to_string(),
-        ));
-
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .query(query_params)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Vec<ScriptInclude>,
-        }
-
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
-
-        Ok(sn_response.result)
+        ritm_sys_id: String,
+        instance: Option<String>,
+    ) -> Result<Vec<(String, String)>, String> {
+        self.select_instance(instance.as_deref())?;
+        validate_sys_id(&ritm_sys_id)?;
+        let rows: Vec<serde_json::Value> = self.list_records(
+            "sc_item_option_mtom",
+            Some(format!(
+                "request_item={}^ORDERBYsc_item_option.order",
+                ritm_sys_id
+            )),
+            REQUEST_VARIABLE_FETCH_LIMIT,
+            0,
+            true,
+            Some(REQUEST_VARIABLE_FIELDS.to_string()),
+        )?;
+        Ok(request_variable_pairs(&rows))
     }
 
     #[query]
-    async fn get_script_include(&self, sys_id: String) -> Result<ScriptInclude, String> {
-        let url = format!(
-            "{}/api/now/table/sys_script_include/{}",
-            self.get_base_url()?,
-            sys_id
-        );
-        let auth_header = self.create_auth_header()?;
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: ScriptInclude,
-        }
-
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
-
-        Ok(sn_response.result)
+    async fn list_catalog_categories(
+        &self,
+        query_str: Option<String>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        fetch_all: Option<bool>,
+        instance: Option<String>,
+    ) -> Result<Vec<CatalogCategory>, String> {
+        self.select_instance(instance.as_deref())?;
+        self.list_records(
+            "sc_category",
+            query_str,
+            limit.unwrap_or(100),
+            offset.unwrap_or(0),
+            fetch_all.unwrap_or(false),
+            None,
+        )
     }
 
     #[query]
-    async fn create_script_include(
+    async fn create_catalog_category(
         &self,
-        name: String,
+        title: String,
         description: Option<String>,
-        script: String,
-        api_name: Option<String>,
-    ) -> Result<ScriptInclude, String> {
-        let url = format!("{}/api/now/table/sys_script_include", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
+        parent_category_id: Option<String>,
+        instance: Option<String>,
+    ) -> Result<CatalogCategory, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/sc_category", self.get_base_url()?);
 
         let mut payload = serde_json::json!({
-            "name": name,
-            "script": script
+            "title": title
         });
 
         if let Some(desc) = description {
             payload["description"] = serde_json::Value::String(desc);
         }
-        if let Some(api) = api_name {
-            payload["api_name"] = serde_json::Value::String(api);
-        }
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
-
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: ScriptInclude,
+        if let Some(parent_id) = parent_category_id {
+            payload["parent"] = serde_json::Value::String(parent_id);
         }
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        let api = self.api()?;
 
-        Ok(sn_response.result)
+        api.post_json(&url, vec![], &payload)
     }
 
     #[query]
-    async fn delete_script_include(&self, sys_id: String) -> Result<(), String> {
-        let url = format!(
-            "{}/api/now/table/sys_script_include/{}",
-            self.get_base_url()?,
-            sys_id
-        );
-        let auth_header = self.create_auth_header()?;
+    async fn move_catalog_items(
+        &self,
+        item_sys_ids: Vec<String>,
+        target_category_sys_id: String,
+        instance: Option<String>,
+    ) -> Result<(), String> {
+        self.select_instance(instance.as_deref())?;
+        for item_sys_id in item_sys_ids {
+            let url = format!(
+                "{}/api/now/table/sc_cat_item/{}",
+                self.get_base_url()?,
+                item_sys_id
+            );
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+            let payload = serde_json::json!({
+                "category": target_category_sys_id
+            });
 
-        let response = HttpClient::request(&url, HttpMethod::Delete)
-            .headers(headers)
-            .send()
-            .map_err(|err| err.to_string())?;
+            let api = self.api()?;
 
-        if response.status() >= 204 && response.status() < 300 {
-            Ok(())
-        } else {
-            Err(format!(
-                "Failed to delete script include. Status: {}",
-                response.status()
-            ))
+            api.send(HttpMethod::Put, &url, vec![], Some(payload.to_string()))?;
         }
+        Ok(())
     }
 
-    // Changeset Management Functions
     #[query]
-    async fn list_changesets(
+    async fn create_catalog_item_variable(
         &self,
-        query_str: Option<String>,
-        limit: Option<u32>,
-    ) -> Result<Vec<Changeset>, String> {
-        let url = format!("{}/api/now/table/sys_update_set", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
+        catalog_item_sys_id: String,
+        name: String,
+        question_text: String,
+        var_type: String,
+        mandatory: bool,
+        instance: Option<String>,
+    ) -> Result<CatalogVariable, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/item_option_new", self.get_base_url()?);
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+        let payload = serde_json::json!({
+            "name": name,
+            "question_text": question_text,
+            "type": var_type,
+            "mandatory": mandatory,
+            "catalog_item": catalog_item_sys_id
+        });
 
-        let mut query_params = Vec::new();
-        if let Some(query) = query_str {
-            query_params.push(("sysparm_query".to_string(), query));
-        }
-        query_params.push((
-            "sysparm_limit".to_string(),
-            limit.unwrap_or(100).to_string(),
-        ));
+        let api = self.api()?;
 
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .query(query_params)
-            .send()
-            .map_err(|err| err.to_string())?;
+        api.post_json(&url, vec![], &payload)
+    }
 
-        let response_text = response.text();
+    #[query]
+    async fn list_catalog_item_variables(
+        &self,
+        catalog_item_sys_id: String,
+        instance: Option<String>,
+    ) -> Result<Vec<CatalogVariable>, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/item_option_new", self.get_base_url()?);
+        let api = self.api()?;
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Vec<Changeset>,
-        }
+        let query_params = vec![
+            (
+                "sysparm_query".to_string(),
+                format!("catalog_item={}", catalog_item_sys_id),
+            ),
+            ("sysparm_limit".to_string(), "100".to_string()),
+        ];
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        api.get_json(&url, query_params)
+    }
 
-        Ok(sn_response.result)
+    #[query]
+    async fn list_catalogs(
+        &self,
+        query_str: Option<String>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        fetch_all: Option<bool>,
+        instance: Option<String>,
+    ) -> Result<Vec<serde_json::Value>, String> {
+        self.select_instance(instance.as_deref())?;
+        self.list_records(
+            "sc_catalog",
+            query_str,
+            limit.unwrap_or(100),
+            offset.unwrap_or(0),
+            fetch_all.unwrap_or(false),
+            None,
+        )
     }
 
+    // Catalog Optimization Functions
     #[query]
-    async fn get_changeset_details(&self, sys_id: String) -> Result<Changeset, String> {
+    async fn get_optimization_recommendations(
+        &self,
+        catalog_item_sys_id: Option<String>,
+        instance: Option<String>,
+    ) -> Result<Vec<serde_json::Value>, String> {
+        self.select_instance(instance.as_deref())?;
         let url = format!(
-            "{}/api/now/table/sys_update_set/{}",
-            self.get_base_url()?,
-            sys_id
+            "{}/api/now/table/sc_cat_item_optimization",
+            self.get_base_url()?
         );
-        let auth_header = self.create_auth_header()?;
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+        let api = self.api()?;
 
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Changeset,
+        let mut query_params = Vec::new();
+        if let Some(item_id) = catalog_item_sys_id {
+            query_params.push((
+                "sysparm_query".to_string(),
+                format!("catalog_item={}", item_id),
+            ));
         }
+        query_params.push(("sysparm_limit".to_string(), "100".to_string()));
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
-
-        Ok(sn_response.result)
+        api.get_json(&url, query_params)
     }
 
+    // Change Management Functions
     #[query]
-    async fn create_changeset(
+    async fn create_change_request(
         &self,
-        name: String,
-        description: Option<String>,
-    ) -> Result<Changeset, String> {
-        let url = format!("{}/api/now/table/sys_update_set", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
+        short_description: String,
+        description: String,
+        priority: String,
+        risk: Option<String>,
+        impact: Option<String>,
+        external_ref: Option<String>,
+        instance: Option<String>,
+    ) -> Result<ChangeRequest, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/change_request", self.get_base_url()?);
 
         let mut payload = serde_json::json!({
-            "name": name
+            "short_description": short_description,
+            "description": description,
+            "priority": priority
         });
 
-        if let Some(desc) = description {
-            payload["description"] = serde_json::Value::String(desc);
+        if let Some(risk_val) = risk {
+            payload["risk"] = serde_json::Value::String(risk_val);
+        }
+        if let Some(impact_val) = impact {
+            payload["impact"] = serde_json::Value::String(impact_val);
         }
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
-
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
+        apply_external_ref(&mut payload, external_ref)?;
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Changeset,
-        }
+        let api = self.api()?;
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        api.post_json(&url, vec![], &payload)
+    }
 
-        Ok(sn_response.result)
+    #[query]
+    async fn list_change_requests(
+        &self,
+        query_str: Option<String>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        fetch_all: Option<bool>,
+        instance: Option<String>,
+    ) -> Result<Vec<ChangeRequest>, String> {
+        self.select_instance(instance.as_deref())?;
+        self.list_records(
+            "change_request",
+            query_str,
+            limit.unwrap_or(100),
+            offset.unwrap_or(0),
+            fetch_all.unwrap_or(false),
+            None,
+        )
     }
 
     #[query]
-    async fn commit_changeset(&self, sys_id: String) -> Result<Changeset, String> {
+    async fn get_change_request_details(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<ChangeRequest, String> {
+        self.select_instance(instance.as_deref())?;
         let url = format!(
-            "{}/api/now/table/sys_update_set/{}",
+            "{}/api/now/table/change_request/{}",
             self.get_base_url()?,
             sys_id
         );
-        let auth_header = self.create_auth_header()?;
-
-        let payload = serde_json::json!({
-            "state": "committed"
-        });
+        let api = self.api()?;
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        api.get_json(&url, vec![])
+    }
 
-        let response = HttpClient::request(&url, HttpMethod::Put)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
+    #[query]
+    async fn add_change_task(
+        &self,
+        change_request_sys_id: String,
+        short_description: String,
+        description: String,
+        assigned_to: Option<String>,
+        instance: Option<String>,
+    ) -> Result<ChangeTask, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/change_task", self.get_base_url()?);
 
-        let response_text = response.text();
+        let mut payload = serde_json::json!({
+            "short_description": short_description,
+            "description": description,
+            "change_request": change_request_sys_id
+        });
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Changeset,
+        if let Some(assignee) = assigned_to {
+            payload["assigned_to"] = serde_json::Value::String(assignee);
         }
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        let api = self.api()?;
 
-        Ok(sn_response.result)
+        api.post_json(&url, vec![], &payload)
     }
 
     #[query]
-    async fn publish_changeset(&self, sys_id: String) -> Result<Changeset, String> {
+    async fn submit_change_for_approval(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<ChangeRequest, String> {
+        self.select_instance(instance.as_deref())?;
         let url = format!(
-            "{}/api/now/table/sys_update_set/{}",
+            "{}/api/now/table/change_request/{}",
             self.get_base_url()?,
             sys_id
         );
-        let auth_header = self.create_auth_header()?;
 
         let payload = serde_json::json!({
-            "state": "published"
+            "state": "1" // Submitted for approval
         });
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        let api = self.api()?;
 
-        let response = HttpClient::request(&url, HttpMethod::Put)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
+        api.put_json(&url, vec![], &payload)
+    }
 
-        let response_text = response.text();
+    #[query]
+    async fn approve_change(
+        &self,
+        sys_id: String,
+        approval_notes: Option<String>,
+        instance: Option<String>,
+    ) -> Result<ChangeRequest, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!(
+            "{}/api/now/table/change_request/{}",
+            self.get_base_url()?,
+            sys_id
+        );
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Changeset,
+        let mut payload = serde_json::json!({
+            "state": "2" // Approved
+        });
+
+        if let Some(notes) = approval_notes {
+            payload["approval_notes"] = serde_json::Value::String(notes);
         }
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        let api = self.api()?;
 
-        Ok(sn_response.result)
+        api.put_json(&url, vec![], &payload)
     }
 
-    // Knowledge Base Management Functions
     #[query]
-    async fn create_knowledge_base(
+    async fn reject_change(
         &self,
-        title: String,
-        description: Option<String>,
-    ) -> Result<KnowledgeBase, String> {
-        let url = format!("{}/api/now/table/kb_knowledge_base", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
+        sys_id: String,
+        rejection_notes: String,
+        instance: Option<String>,
+    ) -> Result<ChangeRequest, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!(
+            "{}/api/now/table/change_request/{}",
+            self.get_base_url()?,
+            sys_id
+        );
 
-        let mut payload = serde_json::json!({
-            "title": title
+        let payload = serde_json::json!({
+            "state": "3", // Rejected
+            "rejection_notes": rejection_notes
         });
 
-        if let Some(desc) = description {
-            payload["description"] = serde_json::Value::String(desc);
-        }
+        let api = self.api()?;
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        api.put_json(&url, vec![], &payload)
+    }
 
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
+    // Agile Story Management Functions
+    #[query]
+    async fn create_story(
+        &self,
+        short_description: String,
+        description: String,
+        priority: Option<String>,
+        story_points: Option<String>,
+        epic_sys_id: Option<String>,
+        external_ref: Option<String>,
+        instance: Option<String>,
+    ) -> Result<Story, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/rm_story", self.get_base_url()?);
 
-        let response_text = response.text();
+        let mut payload = serde_json::json!({
+            "short_description": short_description,
+            "description": description
+        });
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: KnowledgeBase,
+        if let Some(pri) = priority {
+            payload["priority"] = serde_json::Value::String(pri);
+        }
+        if let Some(points) = story_points {
+            payload["story_points"] = serde_json::Value::String(points);
+        }
+        if let Some(epic) = epic_sys_id {
+            payload["epic"] = serde_json::Value::String(epic);
         }
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        apply_external_ref(&mut payload, external_ref)?;
+
+        let api = self.api()?;
 
-        Ok(sn_response.result)
+        api.post_json(&url, vec![], &payload)
     }
 
     #[query]
-    async fn list_knowledge_bases(
+    async fn list_stories(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
-    ) -> Result<Vec<KnowledgeBase>, String> {
-        let url = format!("{}/api/now/table/kb_knowledge_base", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+        instance: Option<String>,
+    ) -> Result<Vec<Story>, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/rm_story", self.get_base_url()?);
+        let api = self.api()?;
 
         let mut query_params = Vec::new();
         if let Some(query) = query_str {
@@ -2293,131 +4354,122 @@ impl Servicenow for ServicenowContractState {
             limit.unwrap_or(100).to_string(),
         ));
 
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .query(query_params)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Vec<KnowledgeBase>,
-        }
+        api.get_json(&url, query_params)
+    }
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+    #[query]
+    async fn delete_story_dependency(
+        &self,
+        dependency_sys_id: String,
+        instance: Option<String>,
+    ) -> Result<(), String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!(
+            "{}/api/now/table/rm_story_dependency/{}",
+            self.get_base_url()?,
+            dependency_sys_id
+        );
+        let api = self.api()?;
 
-        Ok(sn_response.result)
+        api.delete(&url, vec![])?;
+        Ok(())
     }
 
+    // Agile Epic Management Functions
     #[query]
-    async fn create_article(
+    async fn create_epic(
         &self,
         short_description: String,
-        text: String,
-        knowledge_base_sys_id: String,
-        category_sys_id: Option<String>,
-    ) -> Result<KnowledgeArticle, String> {
-        let url = format!("{}/api/now/table/kb_knowledge", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
+        description: String,
+        priority: Option<String>,
+        external_ref: Option<String>,
+        instance: Option<String>,
+    ) -> Result<Epic, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/rm_epic", self.get_base_url()?);
 
         let mut payload = serde_json::json!({
             "short_description": short_description,
-            "text": text,
-            "kb_knowledge_base": knowledge_base_sys_id
+            "description": description
         });
 
-        if let Some(category) = category_sys_id {
-            payload["kb_category"] = serde_json::Value::String(category);
+        if let Some(pri) = priority {
+            payload["priority"] = serde_json::Value::String(pri);
         }
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        apply_external_ref(&mut payload, external_ref)?;
 
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
+        let api = self.api()?;
 
-        let response_text = response.text();
+        api.post_json(&url, vec![], &payload)
+    }
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: KnowledgeArticle,
-        }
+    #[query]
+    async fn list_epics(
+        &self,
+        query_str: Option<String>,
+        limit: Option<u32>,
+        instance: Option<String>,
+    ) -> Result<Vec<Epic>, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/rm_epic", self.get_base_url()?);
+        let api = self.api()?;
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        let mut query_params = Vec::new();
+        if let Some(query) = query_str {
+            query_params.push(("sysparm_query".to_string(), query));
+        }
+        query_params.push((
+            "sysparm_limit".to_string(),
+            limit.unwrap_or(100).to_string(),
+        ));
 
-        Ok(sn_response.result)
+        api.get_json(&url, query_params)
     }
 
+    // Scrum Task Management Functions
     #[query]
-    async fn publish_article(&self, sys_id: String) -> Result<KnowledgeArticle, String> {
-        let url = format!(
-            "{}/api/now/table/kb_knowledge/{}",
-            self.get_base_url()?,
-            sys_id
-        );
-        let auth_header = self.create_auth_header()?;
+    async fn create_scrum_task(
+        &self,
+        short_description: String,
+        description: String,
+        story_sys_id: Option<String>,
+        assigned_to: Option<String>,
+        external_ref: Option<String>,
+        instance: Option<String>,
+    ) -> Result<ScrumTask, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/rm_scrum_task", self.get_base_url()?);
 
-        let payload = serde_json::json!({
-            "workflow_state": "published"
+        let mut payload = serde_json::json!({
+            "short_description": short_description,
+            "description": description
         });
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
-
-        let response = HttpClient::request(&url, HttpMethod::Put)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: KnowledgeArticle,
+        if let Some(story) = story_sys_id {
+            payload["story"] = serde_json::Value::String(story);
+        }
+        if let Some(assignee) = assigned_to {
+            payload["assigned_to"] = serde_json::Value::String(assignee);
         }
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        apply_external_ref(&mut payload, external_ref)?;
+
+        let api = self.api()?;
 
-        Ok(sn_response.result)
+        api.post_json(&url, vec![], &payload)
     }
 
     #[query]
-    async fn list_articles(
+    async fn list_scrum_tasks(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
-    ) -> Result<Vec<KnowledgeArticle>, String> {
-        let url = format!("{}/api/now/table/kb_knowledge", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+        instance: Option<String>,
+    ) -> Result<Vec<ScrumTask>, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/rm_scrum_task", self.get_base_url()?);
+        let api = self.api()?;
 
         let mut query_params = Vec::new();
         if let Some(query) = query_str {
@@ -2428,176 +4480,72 @@ impl Servicenow for ServicenowContractState {
             limit.unwrap_or(100).to_string(),
         ));
 
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .query(query_params)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Vec<KnowledgeArticle>,
-        }
-
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
-
-        Ok(sn_response.result)
-    }
-
-    #[query]
-    async fn get_article(&self, sys_id: String) -> Result<KnowledgeArticle, String> {
-        let url = format!(
-            "{}/api/now/table/kb_knowledge/{}",
-            self.get_base_url()?,
-            sys_id
-        );
-        let auth_header = self.create_auth_header()?;
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: KnowledgeArticle,
-        }
-
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
-
-        Ok(sn_response.result)
+        api.get_json(&url, query_params)
     }
 
-    // User Management Functions
+    // Project Management Functions
     #[query]
-    async fn create_user(
+    async fn create_project(
         &self,
-        user_name: String,
-        first_name: String,
-        last_name: String,
-        email: String,
-        department: Option<String>,
-    ) -> Result<User, String> {
-        let url = format!("{}/api/now/table/sys_user", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
+        name: String,
+        short_description: String,
+        goal: Option<String>,
+        external_ref: Option<String>,
+        instance: Option<String>,
+    ) -> Result<Project, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/promin_project", self.get_base_url()?);
 
         let mut payload = serde_json::json!({
-            "user_name": user_name,
-            "first_name": first_name,
-            "last_name": last_name,
-            "email": email
+            "name": name,
+            "short_description": short_description
         });
 
-        if let Some(dept) = department {
-            payload["department"] = serde_json::Value::String(dept);
+        if let Some(goal_val) = goal {
+            payload["goal"] = serde_json::Value::String(goal_val);
         }
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
-
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: User,
-        }
+        apply_external_ref(&mut payload, external_ref)?;
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        let api = self.api()?;
 
-        Ok(sn_response.result)
+        api.post_json(&url, vec![], &payload)
     }
 
     #[query]
-    async fn get_user(&self, identifier: String) -> Result<User, String> {
-        let url = format!("{}/api/now/table/sys_user", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-
-        // Try to find user by sys_id, user_name, or email
-        let query_params = vec![
-            (
-                "sysparm_query".to_string(),
-                format!(
-                    "sys_id={} OR user_name={} OR email={}",
-                    identifier, identifier, identifier
-                ),
-            ),
-            ("sysparm_limit".to_string(), "1".to_string()),
-        ];
-
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .query(query_params)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
+    async fn list_projects(
+        &self,
+        query_str: Option<String>,
+        limit: Option<u32>,
+        instance: Option<String>,
+    ) -> Result<Vec<Project>, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/promin_project", self.get_base_url()?);
+        let api = self.api()?;
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Vec<User>,
+        let mut query_params = Vec::new();
+        if let Some(query) = query_str {
+            query_params.push(("sysparm_query".to_string(), query));
         }
+        query_params.push((
+            "sysparm_limit".to_string(),
+            limit.unwrap_or(100).to_string(),
+        ));
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
-
-        sn_response
-            .result
-            .into_iter()
-            .next()
-            .ok_or_else(|| "User not found".to_string())
+        api.get_json(&url, query_params)
     }
 
+    // Workflow Management Functions
     #[query]
-    async fn list_users(
+    async fn list_workflows(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
-    ) -> Result<Vec<User>, String> {
-        let url = format!("{}/api/now/table/sys_user", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+        instance: Option<String>,
+    ) -> Result<Vec<Workflow>, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/wf_workflow", self.get_base_url()?);
+        let api = self.api()?;
 
         let mut query_params = Vec::new();
         if let Some(query) = query_str {
@@ -2608,179 +4556,168 @@ impl Servicenow for ServicenowContractState {
             limit.unwrap_or(100).to_string(),
         ));
 
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .query(query_params)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Vec<User>,
-        }
+        api.get_json(&url, query_params)
+    }
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+    #[query]
+    async fn get_workflow(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<Workflow, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!(
+            "{}/api/now/table/wf_workflow/{}",
+            self.get_base_url()?,
+            sys_id
+        );
+        let api = self.api()?;
 
-        Ok(sn_response.result)
+        api.get_json(&url, vec![])
     }
 
     #[query]
-    async fn create_group(
+    async fn create_workflow(
         &self,
         name: String,
         description: Option<String>,
-        manager: Option<String>,
-    ) -> Result<Group, String> {
-        let url = format!("{}/api/now/table/sys_user_group", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
+        table: String,
+        instance: Option<String>,
+    ) -> Result<Workflow, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/wf_workflow", self.get_base_url()?);
 
         let mut payload = serde_json::json!({
-            "name": name
+            "name": name,
+            "table": table
         });
 
         if let Some(desc) = description {
             payload["description"] = serde_json::Value::String(desc);
         }
-        if let Some(mgr) = manager {
-            payload["manager"] = serde_json::Value::String(mgr);
-        }
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
-
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Group,
-        }
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        let api = self.api()?;
 
-        Ok(sn_response.result)
+        api.post_json(&url, vec![], &payload)
     }
 
     #[query]
-    async fn add_group_members(
+    async fn delete_workflow(
         &self,
-        group_sys_id: String,
-        user_sys_ids: Vec<String>,
+        sys_id: String,
+        instance: Option<String>,
     ) -> Result<(), String> {
-        for user_sys_id in user_sys_ids {
-            let url = format!("{}/api/now/table/sys_user_grmember", self.get_base_url()?);
-            let auth_header = self.create_auth_header()?;
-
-            let payload = serde_json::json!({
-                "group": group_sys_id,
-                "user": user_sys_id
-            });
-
-            let mut headers = HashMap::new();
-            headers.insert("Authorization".to_string(), auth_header);
-            headers.insert("Content-Type".to_string(), "application/json".to_string());
+        self.select_instance(instance.as_deref())?;
+        let url = format!(
+            "{}/api/now/table/wf_workflow/{}",
+            self.get_base_url()?,
+            sys_id
+        );
+        let api = self.api()?;
 
-            let _response = HttpClient::request(&url, HttpMethod::Post)
-                .headers(headers)
-                .json(&payload)
-                .send()
-                .map_err(|err| err.to_string())?;
-        }
+        api.delete(&url, vec![])?;
         Ok(())
     }
 
+    // Script Include Management Functions
     #[query]
-    async fn remove_group_members(
+    async fn list_script_includes(
         &self,
-        group_sys_id: String,
-        user_sys_ids: Vec<String>,
-    ) -> Result<(), String> {
-        for user_sys_id in user_sys_ids {
-            let url = format!("{}/api/now/table/sys_user_grmember", self.get_base_url()?);
-            let auth_header = self.create_auth_header()?;
+        query_str: Option<String>,
+        limit: Option<u32>,
+        instance: Option<String>,
+    ) -> Result<Vec<ScriptInclude>, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/sys_script_include", self.get_base_url()?);
+        let api = self.api()?;
 
-            let mut headers = HashMap::new();
-            headers.insert("Authorization".to_string(), auth_header);
+        let mut query_params = Vec::new();
+        if let Some(query) = query_str {
+            query_params.push(("sysparm_query".to_string(), query));
+        }
+        query_params.push((
+            "sysparm_limit".to_string(),
+            limit.unwrap_or(100).to_string(),
+        ));
 
-            let query_params = vec![(
-                "sysparm_query".to_string(),
-                format!("group={} AND user={}", group_sys_id, user_sys_id),
-            )];
+        api.get_json(&url, query_params)
+    }
 
-            let response = HttpClient::request(&url, HttpMethod::Get)
-                .headers(headers)
-                .query(query_params)
-                .send()
-                .map_err(|err| err.to_string())?;
+    #[query]
+    async fn get_script_include(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<ScriptInclude, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!(
+            "{}/api/now/table/sys_script_include/{}",
+            self.get_base_url()?,
+            sys_id
+        );
+        let api = self.api()?;
 
-            let response_text = response.text();
+        api.get_json(&url, vec![])
+    }
 
-            #[derive(Deserialize)]
-            struct ServiceNowResponse {
-                result: Vec<serde_json::Value>,
-            }
+    #[query]
+    async fn create_script_include(
+        &self,
+        name: String,
+        description: Option<String>,
+        script: String,
+        api_name: Option<String>,
+        instance: Option<String>,
+    ) -> Result<ScriptInclude, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/sys_script_include", self.get_base_url()?);
 
-            let sn_response: ServiceNowResponse =
-                serde_json::from_str(&response_text).map_err(|err| {
-                    format!(
-                        "Failed to parse response: {}. Response was: {}",
-                        err, response_text
-                    )
-                })?;
+        let mut payload = serde_json::json!({
+            "name": name,
+            "script": script
+        });
 
-            if let Some(member) = sn_response.result.first() {
-                if let Some(sys_id) = member.get("sys_id").and_then(|v| v.as_str()) {
-                    let delete_url = format!(
-                        "{}/api/now/table/sys_user_grmember/{}",
-                        self.get_base_url()?,
-                        sys_id
-                    );
-                    let delete_auth_header = self.create_auth_header()?;
-
-                    let mut delete_headers = HashMap::new();
-                    delete_headers.insert("Authorization".to_string(), delete_auth_header);
-
-                    let _delete_response = HttpClient::request(&delete_url, HttpMethod::Delete)
-                        .headers(delete_headers)
-                        .send()
-                        .map_err(|err| err.to_string())?;
-                }
-            }
+        if let Some(desc) = description {
+            payload["description"] = serde_json::Value::String(desc);
         }
+        if let Some(api) = api_name {
+            payload["api_name"] = serde_json::Value::String(api);
+        }
+
+        let api = self.api()?;
+
+        api.post_json(&url, vec![], &payload)
+    }
+
+    #[query]
+    async fn delete_script_include(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<(), String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!(
+            "{}/api/now/table/sys_script_include/{}",
+            self.get_base_url()?,
+            sys_id
+        );
+        let api = self.api()?;
+
+        api.delete(&url, vec![])?;
         Ok(())
     }
 
+    // Changeset Management Functions
     #[query]
-    async fn list_groups(
+    async fn list_changesets(
         &self,
         query_str: Option<String>,
         limit: Option<u32>,
-    ) -> Result<Vec<Group>, String> {
-        let url = format!("{}/api/now/table/sys_user_group", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
-
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
+        instance: Option<String>,
+    ) -> Result<Vec<Changeset>, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/sys_update_set", self.get_base_url()?);
+        let api = self.api()?;
 
         let mut query_params = Vec::new();
         if let Some(query) = query_str {
@@ -2791,1578 +4728,1775 @@ impl Servicenow for ServicenowContractState {
             limit.unwrap_or(100).to_string(),
         ));
 
-        let response = HttpClient::request(&url, HttpMethod::Get)
-            .headers(headers)
-            .query(query_params)
-            .send()
-            .map_err(|err| err.to_string())?;
-
-        let response_text = response.text();
-
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: Vec<Group>,
-        }
+        api.get_json(&url, query_params)
+    }
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+    #[query]
+    async fn get_changeset_details(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<Changeset, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!(
+            "{}/api/now/table/sys_update_set/{}",
+            self.get_base_url()?,
+            sys_id
+        );
+        let api = self.api()?;
 
-        Ok(sn_response.result)
+        api.get_json(&url, vec![])
     }
 
-    // UI Policy Functions
     #[query]
-    async fn create_ui_policy(
+    async fn create_changeset(
         &self,
         name: String,
         description: Option<String>,
-        table: String,
-        catalog_item_sys_id: Option<String>,
-    ) -> Result<UIPolicy, String> {
-        let url = format!("{}/api/now/table/sys_ui_policy", self.get_base_url()?);
-        let auth_header = self.create_auth_header()?;
+        instance: Option<String>,
+    ) -> Result<Changeset, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/sys_update_set", self.get_base_url()?);
 
         let mut payload = serde_json::json!({
-            "name": name,
-            "table": table
+            "name": name
         });
 
         if let Some(desc) = description {
             payload["description"] = serde_json::Value::String(desc);
         }
-        if let Some(catalog_item) = catalog_item_sys_id {
-            payload["catalog_item"] = serde_json::Value::String(catalog_item);
-        }
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        let api = self.api()?;
 
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
+        api.post_json(&url, vec![], &payload)
+    }
 
-        let response_text = response.text();
+    #[query]
+    async fn commit_changeset(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<Changeset, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!(
+            "{}/api/now/table/sys_update_set/{}",
+            self.get_base_url()?,
+            sys_id
+        );
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: UIPolicy,
-        }
+        let payload = serde_json::json!({
+            "state": "committed"
+        });
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        let api = self.api()?;
 
-        Ok(sn_response.result)
+        api.put_json(&url, vec![], &payload)
     }
 
     #[query]
-    async fn create_ui_policy_action(
+    async fn publish_changeset(
         &self,
-        ui_policy_sys_id: String,
-        name: String,
-        description: Option<String>,
-        field_name: String,
-        action: String,
-    ) -> Result<UIPolicyAction, String> {
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<Changeset, String> {
+        self.select_instance(instance.as_deref())?;
         let url = format!(
-            "{}/api/now/table/sys_ui_policy_action",
-            self.get_base_url()?
+            "{}/api/now/table/sys_update_set/{}",
+            self.get_base_url()?,
+            sys_id
         );
-        let auth_header = self.create_auth_header()?;
+
+        let payload = serde_json::json!({
+            "state": "published"
+        });
+
+        let api = self.api()?;
+
+        api.put_json(&url, vec![], &payload)
+    }
+
+    // Knowledge Base Management Functions
+    #[query]
+    async fn create_knowledge_base(
+        &self,
+        title: String,
+        description: Option<String>,
+        instance: Option<String>,
+    ) -> Result<KnowledgeBase, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/kb_knowledge_base", self.get_base_url()?);
 
         let mut payload = serde_json::json!({
-            "name": name,
-            "ui_policy": ui_policy_sys_id,
-            "field_name": field_name,
-            "action": action
+            "title": title
         });
 
         if let Some(desc) = description {
             payload["description"] = serde_json::Value::String(desc);
         }
 
-        let mut headers = HashMap::new();
-        headers.insert("Authorization".to_string(), auth_header);
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        let api = self.api()?;
 
-        let response = HttpClient::request(&url, HttpMethod::Post)
-            .headers(headers)
-            .json(&payload)
-            .send()
-            .map_err(|err| err.to_string())?;
+        api.post_json(&url, vec![], &payload)
+    }
 
-        let response_text = response.text();
+    #[query]
+    async fn list_knowledge_bases(
+        &self,
+        query_str: Option<String>,
+        limit: Option<u32>,
+        instance: Option<String>,
+    ) -> Result<Vec<KnowledgeBase>, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/kb_knowledge_base", self.get_base_url()?);
+        let api = self.api()?;
 
-        #[derive(Deserialize)]
-        struct ServiceNowResponse {
-            result: UIPolicyAction,
+        let mut query_params = Vec::new();
+        if let Some(query) = query_str {
+            query_params.push(("sysparm_query".to_string(), query));
         }
+        query_params.push((
+            "sysparm_limit".to_string(),
+            limit.unwrap_or(100).to_string(),
+        ));
 
-        let sn_response: ServiceNowResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                format!(
-                    "Failed to parse response: {}. Response was: {}",
-                    err, response_text
-                )
-            })?;
+        api.get_json(&url, query_params)
+    }
+
+    #[query]
+    async fn create_article(
+        &self,
+        short_description: String,
+        text: String,
+        knowledge_base_sys_id: String,
+        category_sys_id: Option<String>,
+        instance: Option<String>,
+    ) -> Result<KnowledgeArticle, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/kb_knowledge", self.get_base_url()?);
+
+        let mut payload = serde_json::json!({
+            "short_description": short_description,
+            "text": text,
+            "kb_knowledge_base": knowledge_base_sys_id
+        });
+
+        if let Some(category) = category_sys_id {
+            payload["kb_category"] = serde_json::Value::String(category);
+        }
 
-        Ok(sn_response.result)
+        let api = self.api()?;
+
+        api.post_json(&url, vec![], &payload)
     }
 
     #[query]
-    fn tools(&self) -> String {
-        r#"[
-  {
-    "type": "function",
-    "function": {
-      "name": "create_incident",
-      "description": "create an incident on servicenow\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "short_description": {
-            "type": "string",
-            "description": "a short description for the incident\n"
-          },
-          "description": {
-            "type": "string",
-            "description": "a description for the incident\n"
-          },
-          "priority": {
-            "type": "string",
-            "description": "priority for the incident\n"
-          }
-        },
-        "required": [
-          "short_description",
-          "description",
-          "priority"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "get_incident",
-      "description": "get an incident from servicenow\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "sys_id": {
-            "type": "string",
-            "description": "id of the incident\n"
-          }
-        },
-        "required": [
-          "sys_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "delete_incident",
-      "description": "delete an incident on servicenow\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "sys_id": {
-            "type": "string",
-            "description": "id of the incident\n"
-          }
-        },
-        "required": [
-          "sys_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "query_incidents",
-      "description": "query incidents on servicenow\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "query_str": {
-            "type": "string",
-            "description": "the query to run\n"
-          },
-          "limit": {
-            "type": "integer",
-            "description": "the limit on the number of results\n"
-          }
-        },
-        "required": [
-          "query_str",
-          "limit"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "add_comment",
-      "description": "add a comment to an incident\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "incident_sys_id": {
-            "type": "string",
-            "description": "system id of the incident\n"
-          },
-          "comment": {
-            "type": "string",
-            "description": "comment text to add\n"
-          }
-        },
-        "required": [
-          "incident_sys_id",
-          "comment"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "resolve_incident",
-      "description": "resolve an incident\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "sys_id": {
-            "type": "string",
-            "description": "system id of the incident\n"
-          },
-          "resolution_notes": {
-            "type": "string",
-            "description": "resolution notes\n"
-          }
-        },
-        "required": [
-          "sys_id",
-          "resolution_notes"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "list_incidents",
-      "description": "list incidents from servicenow\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "query_str": {
-            "type": "string",
-            "description": "query string (optional)\n"
-          },
-          "limit": {
-            "type": "integer",
-            "description": "limit on number of results (optional)\n"
-          }
-        },
-        "required": []
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "list_catalog_items",
-      "description": "list service catalog items\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "query_str": {
-            "type": "string",
-            "description": "query string (optional)\n"
-          },
-          "limit": {
-            "type": "integer",
-            "description": "limit on number of results (optional)\n"
-          }
-        },
-        "required": []
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "get_catalog_item",
-      "description": "get a specific catalog item\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "sys_id": {
-            "type": "string",
-            "description": "system id of the catalog item\n"
-          }
-        },
-        "required": [
-          "sys_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "list_catalog_categories",
-      "description": "list catalog categories\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "query_str": {
-            "type": "string",
-            "description": "query string (optional)\n"
-          },
-          "limit": {
-            "type": "integer",
-            "description": "limit on number of results (optional)\n"
-          }
-        },
-        "required": []
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "create_catalog_category",
-      "description": "create a new catalog category\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "title": {
-            "type": "string",
-            "description": "category title\n"
-          },
-          "description": {
-            "type": "string",
-            "description": "category description (optional)\n"
-          },
-          "parent_category_id": {
-            "type": "string",
-            "description": "parent category id (optional)\n"
-          }
-        },
-        "required": [
-          "title"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "move_catalog_items",
-      "description": "move catalog items between categories\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "catalog_item_ids": {
-            "type": "array",
-            "description": "array of catalog item system ids\n"
-          },
-          "target_category_id": {
-            "type": "string",
-            "description": "target category system id\n"
-          }
-        },
-        "required": [
-          "catalog_item_ids",
-          "target_category_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "create_catalog_item_variable",
-      "description": "create a new variable for a catalog item\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "catalog_item_id": {
-            "type": "string",
-            "description": "catalog item system id\n"
-          },
-          "variable_name": {
-            "type": "string",
-            "description": "variable name\n"
-          },
-          "question_text": {
-            "type": "string",
-            "description": "question text for the variable\n"
-          },
-          "variable_type": {
-            "type": "string",
-            "description": "variable type\n"
-          },
-          "is_mandatory": {
-            "type": "boolean",
-            "description": "whether the variable is mandatory\n"
-          }
-        },
-        "required": [
-          "catalog_item_id",
-          "variable_name",
-          "question_text",
-          "variable_type",
-          "is_mandatory"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "list_catalog_item_variables",
-      "description": "list variables for a catalog item\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "catalog_item_id": {
-            "type": "string",
-            "description": "catalog item system id\n"
-          }
-        },
-        "required": [
-          "catalog_item_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "list_catalogs",
-      "description": "list service catalogs\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "query_str": {
-            "type": "string",
-            "description": "query string (optional)\n"
-          },
-          "limit": {
-            "type": "integer",
-            "description": "limit on number of results (optional)\n"
-          }
-        },
-        "required": []
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "get_optimization_recommendations",
-      "description": "get optimization recommendations for catalog items\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "catalog_item_id": {
-            "type": "string",
-            "description": "catalog item system id (optional)\n"
-          }
-        },
-        "required": []
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "create_change_request",
-      "description": "create a new change request\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "short_description": {
-            "type": "string",
-            "description": "short description\n"
-          },
-          "description": {
-            "type": "string",
-            "description": "description\n"
-          },
-          "priority": {
-            "type": "string",
-            "description": "priority\n"
-          },
-          "risk": {
-            "type": "string",
-            "description": "risk level (optional)\n"
-          },
-          "impact": {
-            "type": "string",
-            "description": "impact level (optional)\n"
-          }
-        },
-        "required": [
-          "short_description",
-          "description",
-          "priority"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "list_change_requests",
-      "description": "list change requests\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "query_str": {
-            "type": "string",
-            "description": "query string (optional)\n"
-          },
-          "limit": {
-            "type": "integer",
-            "description": "limit on number of results (optional)\n"
-          }
-        },
-        "required": []
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "get_change_request_details",
-      "description": "get detailed information about a change request\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "sys_id": {
-            "type": "string",
-            "description": "system id of the change request\n"
-          }
-        },
-        "required": [
-          "sys_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "add_change_task",
-      "description": "add a task to a change request\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "change_request_sys_id": {
-            "type": "string",
-            "description": "change request system id\n"
-          },
-          "short_description": {
-            "type": "string",
-            "description": "task short description\n"
-          },
-          "description": {
-            "type": "string",
-            "description": "task description\n"
-          },
-          "assigned_to": {
-            "type": "string",
-            "description": "assigned user (optional)\n"
-          }
-        },
-        "required": [
-          "change_request_sys_id",
-          "short_description",
-          "description"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "submit_change_for_approval",
-      "description": "submit a change request for approval\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "sys_id": {
-            "type": "string",
-            "description": "system id of the change request\n"
-          }
-        },
-        "required": [
-          "sys_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "approve_change",
-      "description": "approve a change request\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "sys_id": {
-            "type": "string",
-            "description": "system id of the change request\n"
-          },
-          "notes": {
-            "type": "string",
-            "description": "approval notes (optional)\n"
-          }
-        },
-        "required": [
-          "sys_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "reject_change",
-      "description": "reject a change request\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "sys_id": {
-            "type": "string",
-            "description": "system id of the change request\n"
-          },
-          "notes": {
-            "type": "string",
-            "description": "rejection notes\n"
-          }
-        },
-        "required": [
-          "sys_id",
-          "notes"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "create_story",
-      "description": "create a new user story\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "short_description": {
-            "type": "string",
-            "description": "short description\n"
-          },
-          "description": {
-            "type": "string",
-            "description": "description\n"
-          },
-          "priority": {
-            "type": "string",
-            "description": "priority (optional)\n"
-          },
-          "story_points": {
-            "type": "string",
-            "description": "story points (optional)\n"
-          },
-          "epic_sys_id": {
-            "type": "string",
-            "description": "epic system id (optional)\n"
-          }
-        },
-        "required": [
-          "short_description",
-          "description"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "list_stories",
-      "description": "list user stories\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "query_str": {
-            "type": "string",
-            "description": "query string (optional)\n"
-          },
-          "limit": {
-            "type": "integer",
-            "description": "limit on number of results (optional)\n"
-          }
-        },
-        "required": []
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "delete_story_dependency",
-      "description": "delete a dependency between stories\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "dependency_id": {
-            "type": "string",
-            "description": "dependency system id\n"
-          }
-        },
-        "required": [
-          "dependency_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "create_epic",
-      "description": "create a new epic\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "short_description": {
-            "type": "string",
-            "description": "short description\n"
-          },
-          "description": {
-            "type": "string",
-            "description": "description\n"
-          },
-          "priority": {
-            "type": "string",
-            "description": "priority (optional)\n"
-          }
-        },
-        "required": [
-          "short_description",
-          "description"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "list_epics",
-      "description": "list epics\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "query_str": {
-            "type": "string",
-            "description": "query string (optional)\n"
-          },
-          "limit": {
-            "type": "integer",
-            "description": "limit on number of results (optional)\n"
-          }
-        },
-        "required": []
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "create_scrum_task",
-      "description": "create a new scrum task\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "short_description": {
-            "type": "string",
-            "description": "short description\n"
-          },
-          "description": {
-            "type": "string",
-            "description": "description\n"
-          },
-          "story_id": {
-            "type": "string",
-            "description": "story system id (optional)\n"
-          },
-          "assigned_to": {
-            "type": "string",
-            "description": "assigned user (optional)\n"
-          }
-        },
-        "required": [
-          "short_description",
-          "description"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "list_scrum_tasks",
-      "description": "list scrum tasks\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "query_str": {
-            "type": "string",
-            "description": "query string (optional)\n"
-          },
-          "limit": {
-            "type": "integer",
-            "description": "limit on number of results (optional)\n"
-          }
-        },
-        "required": []
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "create_project",
-      "description": "create a new project\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "name": {
-            "type": "string",
-            "description": "project name\n"
-          },
-          "short_description": {
-            "type": "string",
-            "description": "short description\n"
-          },
-          "goal": {
-            "type": "string",
-            "description": "project goal (optional)\n"
-          }
-        },
-        "required": [
-          "name",
-          "short_description"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "list_projects",
-      "description": "list projects\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "query_str": {
-            "type": "string",
-            "description": "query string (optional)\n"
-          },
-          "limit": {
-            "type": "integer",
-            "description": "limit on number of results (optional)\n"
-          }
-        },
-        "required": []
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "list_workflows",
-      "description": "list workflows\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "query_str": {
-            "type": "string",
-            "description": "query string (optional)\n"
-          },
-          "limit": {
-            "type": "integer",
-            "description": "limit on number of results (optional)\n"
-          }
-        },
-        "required": []
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "get_workflow",
-      "description": "get a specific workflow\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "sys_id": {
-            "type": "string",
-            "description": "system id of the workflow\n"
-          }
-        },
-        "required": [
-          "sys_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "create_workflow",
-      "description": "create a new workflow\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "name": {
-            "type": "string",
-            "description": "workflow name\n"
-          },
-          "description": {
-            "type": "string",
-            "description": "description (optional)\n"
-          },
-          "table": {
-            "type": "string",
-            "description": "table name\n"
-          }
-        },
-        "required": [
-          "name",
-          "table"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "delete_workflow",
-      "description": "delete a workflow\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "sys_id": {
-            "type": "string",
-            "description": "system id of the workflow\n"
-          }
-        },
-        "required": [
-          "sys_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "list_script_includes",
-      "description": "list script includes\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "query_str": {
-            "type": "string",
-            "description": "query string (optional)\n"
-          },
-          "limit": {
-            "type": "integer",
-            "description": "limit on number of results (optional)\n"
-          }
-        },
-        "required": []
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "get_script_include",
-      "description": "get a specific script include\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "sys_id": {
-            "type": "string",
-            "description": "system id of the script include\n"
-          }
-        },
-        "required": [
-          "sys_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "create_script_include",
-      "description": "create a new script include\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "name": {
-            "type": "string",
-            "description": "script include name\n"
-          },
-          "description": {
-            "type": "string",
-            "description": "description (optional)\n"
-          },
-          "script": {
-            "type": "string",
-            "description": "script content\n"
-          },
-          "api_name": {
-            "type": "string",
-            "description": "api name (optional)\n"
-          }
-        },
-        "required": [
-          "name",
-          "script"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "delete_script_include",
-      "description": "delete a script include\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "sys_id": {
-            "type": "string",
-            "description": "system id of the script include\n"
-          }
-        },
-        "required": [
-          "sys_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "list_changesets",
-      "description": "list changesets\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "query_str": {
-            "type": "string",
-            "description": "query string (optional)\n"
-          },
-          "limit": {
-            "type": "integer",
-            "description": "limit on number of results (optional)\n"
-          }
-        },
-        "required": []
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "get_changeset_details",
-      "description": "get detailed information about a changeset\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "sys_id": {
-            "type": "string",
-            "description": "system id of the changeset\n"
-          }
-        },
-        "required": [
-          "sys_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "create_changeset",
-      "description": "create a new changeset\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "name": {
-            "type": "string",
-            "description": "changeset name\n"
-          },
-          "description": {
-            "type": "string",
-            "description": "description (optional)\n"
-          }
-        },
-        "required": [
-          "name"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "commit_changeset",
-      "description": "commit a changeset\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "sys_id": {
-            "type": "string",
-            "description": "system id of the changeset\n"
-          }
-        },
-        "required": [
-          "sys_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "publish_changeset",
-      "description": "publish a changeset\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "sys_id": {
-            "type": "string",
-            "description": "system id of the changeset\n"
-          }
-        },
-        "required": [
-          "sys_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "create_knowledge_base",
-      "description": "Knowledge Base Management\ncreate a new knowledge base\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "title": {
-            "type": "string",
-            "description": "knowledge base title\n"
-          },
-          "description": {
-            "type": "string",
-            "description": "description (optional)\n"
-          }
-        },
-        "required": [
-          "title"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "list_knowledge_bases",
-      "description": "list knowledge bases\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "query_str": {
-            "type": "string",
-            "description": "query string (optional)\n"
-          },
-          "limit": {
-            "type": "integer",
-            "description": "limit on number of results (optional)\n"
-          }
-        },
-        "required": []
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "create_article",
-      "description": "create a new knowledge article\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "short_description": {
-            "type": "string",
-            "description": "short description\n"
-          },
-          "text": {
-            "type": "string",
-            "description": "article content\n"
-          },
-          "knowledge_base_sys_id": {
-            "type": "string",
-            "description": "knowledge base system id\n"
-          },
-          "category_sys_id": {
-            "type": "string",
-            "description": "category system id (optional)\n"
-          }
-        },
-        "required": [
-          "short_description",
-          "text",
-          "knowledge_base_sys_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "publish_article",
-      "description": "publish a knowledge article\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "sys_id": {
-            "type": "string",
-            "description": "system id of the article\n"
-          }
-        },
-        "required": [
-          "sys_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "list_articles",
-      "description": "list knowledge articles\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "query_str": {
-            "type": "string",
-            "description": "query string (optional)\n"
-          },
-          "limit": {
-            "type": "integer",
-            "description": "limit on number of results (optional)\n"
-          }
-        },
-        "required": []
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "get_article",
-      "description": "get a specific knowledge article\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "sys_id": {
-            "type": "string",
-            "description": "system id of the article\n"
-          }
-        },
-        "required": [
-          "sys_id"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "create_user",
-      "description": "create a new user\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "user_name": {
-            "type": "string",
-            "description": "username\n"
-          },
-          "first_name": {
-            "type": "string",
-            "description": "first name\n"
-          },
-          "last_name": {
-            "type": "string",
-            "description": "last name\n"
-          },
-          "email": {
-            "type": "string",
-            "description": "email address\n"
-          },
-          "department": {
-            "type": "string",
-            "description": "department (optional)\n"
-          }
-        },
-        "required": [
-          "user_name",
-          "first_name",
-          "last_name",
-          "email"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "get_user",
-      "description": "get a specific user\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "identifier": {
-            "type": "string",
-            "description": "user id, username, or email\n"
-          }
-        },
-        "required": [
-          "identifier"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "list_users",
-      "description": "list users\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "query_str": {
-            "type": "string",
-            "description": "query string (optional)\n"
-          },
-          "limit": {
-            "type": "integer",
-            "description": "limit on number of results (optional)\n"
-          }
-        },
-        "required": []
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "create_group",
-      "description": "create a new group\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "name": {
-            "type": "string",
-            "description": "group name\n"
-          },
-          "description": {
-            "type": "string",
-            "description": "description (optional)\n"
-          },
-          "manager": {
-            "type": "string",
-            "description": "group manager (optional)\n"
-          }
-        },
-        "required": [
-          "name"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "add_group_members",
-      "description": "add members to a group\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "group_id": {
-            "type": "string",
-            "description": "group system id\n"
-          },
-          "user_ids": {
-            "type": "array",
-            "description": "array of user system ids\n"
-          }
-        },
-        "required": [
-          "group_id",
-          "user_ids"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "remove_group_members",
-      "description": "remove members from a group\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "group_id": {
-            "type": "string",
-            "description": "group system id\n"
-          },
-          "user_ids": {
-            "type": "array",
-            "description": "array of user system ids\n"
-          }
-        },
-        "required": [
-          "group_id",
-          "user_ids"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "list_groups",
-      "description": "list groups\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "query_str": {
-            "type": "string",
-            "description": "query string (optional)\n"
-          },
-          "limit": {
-            "type": "integer",
-            "description": "limit on number of results (optional)\n"
-          }
-        },
-        "required": []
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "create_ui_policy",
-      "description": "UI Policy Management\ncreate a ui policy\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "name": {
-            "type": "string",
-            "description": "policy name\n"
-          },
-          "description": {
-            "type": "string",
-            "description": "description (optional)\n"
-          },
-          "table": {
-            "type": "string",
-            "description": "table name\n"
-          },
-          "catalog_item_id": {
-            "type": "string",
-            "description": "catalog item system id (optional)\n"
-          }
-        },
-        "required": [
-          "name",
-          "table"
-        ]
-      }
-    }
-  },
-  {
-    "type": "function",
-    "function": {
-      "name": "create_ui_policy_action",
-      "description": "create a ui policy action\n",
-      "parameters": {
-        "type": "object",
-        "properties": {
-          "ui_policy_sys_id": {
-            "type": "string",
-            "description": "ui policy system id\n"
-          },
-          "name": {
-            "type": "string",
-            "description": "action name\n"
-          },
-          "description": {
-            "type": "string",
-            "description": "description (optional)\n"
-          },
-          "field_name": {
-            "type": "string",
-            "description": "field name\n"
-          },
-          "action": {
-            "type": "string",
-            "description": "action type\n"
-          }
-        },
-        "required": [
-          "ui_policy_sys_id",
-          "name",
-          "field_name",
-          "action"
-        ]
-      }
+    async fn publish_article(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<KnowledgeArticle, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!(
+            "{}/api/now/table/kb_knowledge/{}",
+            self.get_base_url()?,
+            sys_id
+        );
+
+        let payload = serde_json::json!({
+            "workflow_state": "published"
+        });
+
+        let api = self.api()?;
+
+        api.put_json(&url, vec![], &payload)
+    }
+
+    #[query]
+    async fn list_articles(
+        &self,
+        query_str: Option<String>,
+        limit: Option<u32>,
+        instance: Option<String>,
+    ) -> Result<Vec<KnowledgeArticle>, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/kb_knowledge", self.get_base_url()?);
+        let api = self.api()?;
+
+        let mut query_params = Vec::new();
+        if let Some(query) = query_str {
+            query_params.push(("sysparm_query".to_string(), query));
+        }
+        query_params.push((
+            "sysparm_limit".to_string(),
+            limit.unwrap_or(100).to_string(),
+        ));
+
+        api.get_json(&url, query_params)
+    }
+
+    #[query]
+    async fn get_article(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<KnowledgeArticle, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!(
+            "{}/api/now/table/kb_knowledge/{}",
+            self.get_base_url()?,
+            sys_id
+        );
+        let api = self.api()?;
+
+        api.get_json(&url, vec![])
+    }
+
+    #[query]
+    async fn export_knowledge_to_imfs(
+        &self,
+        kb_sys_id: String,
+        file_name: String,
+        max: u32,
+        instance: Option<String>,
+    ) -> Result<String, String> {
+        self.select_instance(instance.as_deref())?;
+        validate_sys_id(&kb_sys_id)?;
+        if file_name.trim().is_empty() {
+            return Err("file_name must not be empty".to_string());
+        }
+        if max == 0 {
+            return Err("max must be greater than 0".to_string());
+        }
+
+        let query = format!("kb_knowledge_base={}^ORDERBYsys_created_on", kb_sys_id);
+        let articles: Vec<serde_json::Value> =
+            collect_up_to(max, KB_EXPORT_PAGE_SIZE, |offset, limit| {
+                self.list_records(
+                    "kb_knowledge",
+                    Some(query.clone()),
+                    limit,
+                    offset,
+                    false,
+                    Some(KB_EXPORT_FIELDS.to_string()),
+                )
+            })?;
+
+        write_imfs_file(file_name, articles_to_ndjson(&articles))
+    }
+
+    // User Management Functions
+    #[query]
+    async fn create_user(
+        &self,
+        user_name: String,
+        first_name: String,
+        last_name: String,
+        email: String,
+        department: Option<String>,
+        instance: Option<String>,
+    ) -> Result<User, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/sys_user", self.get_base_url()?);
+
+        let mut payload = serde_json::json!({
+            "user_name": user_name,
+            "first_name": first_name,
+            "last_name": last_name,
+            "email": email
+        });
+
+        if let Some(dept) = department {
+            payload["department"] = serde_json::Value::String(dept);
+        }
+
+        let api = self.api()?;
+
+        api.post_json(&url, vec![], &payload)
+    }
+
+    #[query]
+    async fn get_user(&self, identifier: String, instance: Option<String>) -> Result<User, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/sys_user", self.get_base_url()?);
+        let api = self.api()?;
+
+        // Try to find user by sys_id, user_name, or email
+        let query_params = vec![
+            (
+                "sysparm_query".to_string(),
+                format!(
+                    "sys_id={} OR user_name={} OR email={}",
+                    identifier, identifier, identifier
+                ),
+            ),
+            ("sysparm_limit".to_string(), "1".to_string()),
+        ];
+
+        let result: Vec<User> = api.get_json(&url, query_params)?;
+
+        result
+            .into_iter()
+            .next()
+            .ok_or_else(|| "User not found".to_string())
+    }
+
+    #[query]
+    async fn list_users(
+        &self,
+        query_str: Option<String>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        fetch_all: Option<bool>,
+        instance: Option<String>,
+    ) -> Result<Vec<User>, String> {
+        self.select_instance(instance.as_deref())?;
+        self.list_records(
+            "sys_user",
+            query_str,
+            limit.unwrap_or(100),
+            offset.unwrap_or(0),
+            fetch_all.unwrap_or(false),
+            None,
+        )
+    }
+
+    #[query]
+    async fn create_group(
+        &self,
+        name: String,
+        description: Option<String>,
+        manager: Option<String>,
+        instance: Option<String>,
+    ) -> Result<Group, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/sys_user_group", self.get_base_url()?);
+
+        let mut payload = serde_json::json!({
+            "name": name
+        });
+
+        if let Some(desc) = description {
+            payload["description"] = serde_json::Value::String(desc);
+        }
+        if let Some(mgr) = manager {
+            payload["manager"] = serde_json::Value::String(mgr);
+        }
+
+        let api = self.api()?;
+
+        api.post_json(&url, vec![], &payload)
+    }
+
+    #[query]
+    async fn add_group_members(
+        &self,
+        group_sys_id: String,
+        user_sys_ids: Vec<String>,
+        instance: Option<String>,
+    ) -> Result<(), String> {
+        self.select_instance(instance.as_deref())?;
+        for user_sys_id in user_sys_ids {
+            let url = format!("{}/api/now/table/sys_user_grmember", self.get_base_url()?);
+
+            let payload = serde_json::json!({
+                "group": group_sys_id,
+                "user": user_sys_id
+            });
+
+            let api = self.api()?;
+
+            api.send(HttpMethod::Post, &url, vec![], Some(payload.to_string()))?;
+        }
+        Ok(())
+    }
+
+    #[query]
+    async fn remove_group_members(
+        &self,
+        group_sys_id: String,
+        user_sys_ids: Vec<String>,
+        instance: Option<String>,
+    ) -> Result<(), String> {
+        self.select_instance(instance.as_deref())?;
+        for user_sys_id in user_sys_ids {
+            let url = format!("{}/api/now/table/sys_user_grmember", self.get_base_url()?);
+            let api = self.api()?;
+
+            let query_params = vec![(
+                "sysparm_query".to_string(),
+                format!("group={} AND user={}", group_sys_id, user_sys_id),
+            )];
+
+            let result: Vec<serde_json::Value> = api.get_json(&url, query_params)?;
+
+            if let Some(member) = result.first() {
+                let sys_id = id_text(&member["sys_id"])?;
+                validate_sys_id(&sys_id)?;
+                let delete_url = format!(
+                    "{}/api/now/table/sys_user_grmember/{}",
+                    self.get_base_url()?,
+                    sys_id
+                );
+                api.delete(&delete_url, vec![])?;
+            }
+        }
+        Ok(())
+    }
+
+    #[query]
+    async fn list_groups(
+        &self,
+        query_str: Option<String>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        fetch_all: Option<bool>,
+        instance: Option<String>,
+    ) -> Result<Vec<Group>, String> {
+        self.select_instance(instance.as_deref())?;
+        self.list_records(
+            "sys_user_group",
+            query_str,
+            limit.unwrap_or(100),
+            offset.unwrap_or(0),
+            fetch_all.unwrap_or(false),
+            None,
+        )
+    }
+
+    // UI Policy Functions
+    #[query]
+    async fn create_ui_policy(
+        &self,
+        name: String,
+        description: Option<String>,
+        table: String,
+        catalog_item_sys_id: Option<String>,
+        instance: Option<String>,
+    ) -> Result<UIPolicy, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!("{}/api/now/table/sys_ui_policy", self.get_base_url()?);
+
+        let mut payload = serde_json::json!({
+            "name": name,
+            "table": table
+        });
+
+        if let Some(desc) = description {
+            payload["description"] = serde_json::Value::String(desc);
+        }
+        if let Some(catalog_item) = catalog_item_sys_id {
+            payload["catalog_item"] = serde_json::Value::String(catalog_item);
+        }
+
+        let api = self.api()?;
+
+        api.post_json(&url, vec![], &payload)
+    }
+
+    #[query]
+    async fn create_ui_policy_action(
+        &self,
+        ui_policy_sys_id: String,
+        name: String,
+        description: Option<String>,
+        field_name: String,
+        action: String,
+        instance: Option<String>,
+    ) -> Result<UIPolicyAction, String> {
+        self.select_instance(instance.as_deref())?;
+        let url = format!(
+            "{}/api/now/table/sys_ui_policy_action",
+            self.get_base_url()?
+        );
+
+        let mut payload = serde_json::json!({
+            "name": name,
+            "ui_policy": ui_policy_sys_id,
+            "field_name": field_name,
+            "action": action
+        });
+
+        if let Some(desc) = description {
+            payload["description"] = serde_json::Value::String(desc);
+        }
+
+        let api = self.api()?;
+
+        api.post_json(&url, vec![], &payload)
+    }
+
+    // CMDB
+    #[query]
+    async fn get_cmdb_ci(
+        &self,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<ConfigurationItem, String> {
+        self.select_instance(instance.as_deref())?;
+        self.fetch_cmdb_ci(&sys_id)
+    }
+
+    #[query]
+    async fn list_cmdb_cis(
+        &self,
+        class_name: String,
+        query_str: Option<String>,
+        limit: Option<u32>,
+        instance: Option<String>,
+    ) -> Result<Vec<ConfigurationItem>, String> {
+        self.select_instance(instance.as_deref())?;
+        validate_ci_class(&class_name)?;
+        self.list_records(
+            &class_name,
+            query_str,
+            limit.unwrap_or(100),
+            0,
+            false,
+            Some(CMDB_CI_FIELDS.to_string()),
+        )
+    }
+
+    #[query]
+    async fn link_ci_to_incident(
+        &self,
+        incident_sys_id: String,
+        ci_sys_id: String,
+        instance: Option<String>,
+    ) -> Result<Incident, String> {
+        self.select_instance(instance.as_deref())?;
+        // ServiceNow stores any value in a reference field, so check the item exists first
+        let ci = self.fetch_cmdb_ci(&ci_sys_id)?;
+        let ci_sys_id = ci.sys_id.unwrap_or(ci_sys_id);
+
+        let url = format!(
+            "{}/api/now/table/incident/{}",
+            self.get_base_url()?,
+            incident_sys_id
+        );
+        let api = self.api()?;
+
+        let payload = serde_json::json!({ "cmdb_ci": ci_sys_id });
+
+        api.patch_json(&url, vec![], &payload)
+    }
+
+    // Record following
+    #[query]
+    async fn follow_record(
+        &self,
+        table: String,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<FollowStatus, String> {
+        self.select_instance(instance.as_deref())?;
+        self.set_following(table, sys_id, true)
+    }
+
+    #[query]
+    async fn unfollow_record(
+        &self,
+        table: String,
+        sys_id: String,
+        instance: Option<String>,
+    ) -> Result<FollowStatus, String> {
+        self.select_instance(instance.as_deref())?;
+        self.set_following(table, sys_id, false)
     }
-  }
-]"#
-        .to_string()
+
+    // Batch API
+    #[query]
+    async fn batch_rest(
+        &self,
+        requests: Vec<BatchRequest>,
+        instance: Option<String>,
+    ) -> Result<Vec<BatchResponse>, String> {
+        self.select_instance(instance.as_deref())?;
+        self.send_batch(&requests)
+    }
+
+    #[query]
+    fn tools(&self) -> String {
+        filter_tools(Self::TOOLS, &self.secrets.config().disabled_tools)
     }
 
     #[query]
     fn prompts(&self) -> String {
-        r#"{
-  "prompts": []
-}"#
-        .to_string()
+        PROMPTS.to_string()
+    }
+
+    /// Suggest what to do about an error message returned by one of the tools.
+    #[query]
+    fn diagnose(&self, error_message: String) -> String {
+        diagnosis_for(&error_message, DIAGNOSIS_RULES)
+            .unwrap_or("No specific guidance for this error; check the tool's arguments against its schema and the message for the field ServiceNow rejected.")
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use weil_rs::errors::ErrorCode;
+
+    /// Ids are deduplicated and split into IN-queries of at most the chunk size, and results are
+    /// put back in the requested order.
+    #[test]
+    fn sys_id_in_query_chunking() {
+        let ids: Vec<String> = ["a1", "b2", "c3", "a1", "d4", "e5"]
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+        assert_eq!(
+            sys_id_in_queries(&ids, 2).unwrap(),
+            vec![
+                ("sys_idINa1,b2".to_string(), 2),
+                ("sys_idINc3,d4".to_string(), 2),
+                ("sys_idINe5".to_string(), 1),
+            ]
+        );
+        assert_eq!(
+            sys_id_in_queries(&ids, SYS_ID_IN_CHUNK).unwrap(),
+            vec![("sys_idINa1,b2,c3,d4,e5".to_string(), 5)]
+        );
+        assert!(sys_id_in_queries(&["a1^ORactive=true".to_string()], 2).is_err());
+
+        let records = vec![
+            serde_json::json!({"sys_id": "c3"}),
+            serde_json::json!({"sys_id": "a1"}),
+            serde_json::json!({"sys_id": "zz"}),
+        ];
+        let ordered = order_by_sys_ids(records, &ids[..3]);
+        let order: Vec<&str> = ordered
+            .iter()
+            .filter_map(|record| record["sys_id"].as_str())
+            .collect();
+        assert_eq!(order, vec!["a1", "c3"]);
+    }
+
+    /// The journal query covers comments and work notes unless one field is named, and entries
+    /// come back oldest first.
+    #[test]
+    fn incident_journal_query_and_order() {
+        assert_eq!(
+            journal_query("46b66a40a9fe198101f243dfbc79033d", None).unwrap(),
+            "element_id=46b66a40a9fe198101f243dfbc79033d^elementINcomments,work_notes^ORDERBYsys_created_on"
+        );
+        assert_eq!(
+            journal_query("abc123", Some("work_notes")).unwrap(),
+            "element_id=abc123^elementINwork_notes^ORDERBYsys_created_on"
+        );
+        assert!(journal_query("abc^ORactive=true", None).is_err());
+        assert!(journal_query("abc123", Some("comments^OR")).is_err());
+
+        let entry = |on: &str, value: &str| JournalEntry {
+            sys_created_on: on.to_string(),
+            sys_created_by: Some("admin".to_string()),
+            element: "comments".to_string(),
+            value: value.to_string(),
+        };
+        let mut entries = vec![
+            entry("2024-03-02 09:00:00", "third"),
+            entry("2024-03-01 17:30:00", "first"),
+            entry("2024-03-01 18:05:12", "second"),
+        ];
+        sort_journal(&mut entries);
+        let values: Vec<&str> = entries.iter().map(|entry| entry.value.as_str()).collect();
+        assert_eq!(values, vec!["first", "second", "third"]);
+    }
+
+    /// Each record gets the entry independently: a rejected post or a malformed sys_id is
+    /// reported for that record while the others still succeed.
+    #[test]
+    fn add_comment_bulk_reports_each_record() {
+        let sys_ids = vec![
+            "a1".to_string(),
+            "b2".to_string(),
+            "c3^ORactive=true".to_string(),
+            "d4".to_string(),
+        ];
+        let mut posted = Vec::new();
+        let result = add_to_each(sys_ids, |sys_id| {
+            posted.push(sys_id.to_string());
+            if sys_id == "b2" {
+                Err(ServiceNowError::NotFound("No Record found".to_string()).to_string())
+            } else {
+                Ok(())
+            }
+        });
+
+        assert_eq!(posted, vec!["a1", "b2", "d4"]);
+        assert_eq!(result.succeeded, vec!["a1", "d4"]);
+        assert_eq!(
+            result.failed,
+            vec![
+                BulkFailure {
+                    item: "b2".to_string(),
+                    error: "[NOT_FOUND] Not found: No Record found".to_string(),
+                },
+                BulkFailure {
+                    item: "c3^ORactive=true".to_string(),
+                    error: "Invalid sys_id: c3^ORactive=true".to_string(),
+                },
+            ]
+        );
+        assert_eq!(CommentVisibility::WorkNote.journal_field(), "work_notes");
+    }
+
+    /// A projection always includes sys_id once, keeps the requested order, and rejects names
+    /// that aren't (dot-walked) field identifiers; no or empty fields means no projection.
+    #[test]
+    fn incident_field_projection() {
+        let fields = vec![
+            "number".to_string(),
+            "state".to_string(),
+            "sys_id".to_string(),
+            "assigned_to.name".to_string(),
+        ];
+        assert_eq!(
+            sysparm_fields(Some(&fields)).unwrap().as_deref(),
+            Some("sys_id,number,state,assigned_to.name")
+        );
+        assert_eq!(sysparm_fields(None).unwrap(), None);
+        assert_eq!(sysparm_fields(Some(&[])).unwrap(), None);
+        assert!(sysparm_fields(Some(&["state^ORactive".to_string()])).is_err());
+
+        let projected: Incident =
+            serde_json::from_str(r#"{"sys_id": "abc", "number": "INC0010001"}"#).unwrap();
+        assert!(format!("{:?}", projected).contains("state: None"));
+    }
+
+    /// A config with a blank credential or an instance URL without a scheme is rejected by name.
+    #[test]
+    fn config_validation_names_missing_field() {
+        let valid = || ServicenowConfig {
+            base_url: "https://dev1234.service-now.com".to_string(),
+            username: "admin".to_string(),
+            password: "secret".to_string(),
+            ..Default::default()
+        };
+        assert!(valid().validate().is_ok());
+
+        let cases = [
+            (
+                ServicenowConfig {
+                    base_url: "dev1234.service-now.com".to_string(),
+                    ..valid()
+                },
+                "base_url",
+            ),
+            (
+                ServicenowConfig {
+                    username: String::new(),
+                    ..valid()
+                },
+                "username",
+            ),
+            (
+                ServicenowConfig {
+                    password: "  ".to_string(),
+                    ..valid()
+                },
+                "password",
+            ),
+        ];
+        for (config, field) in cases {
+            let err = config.validate().unwrap_err();
+            assert!(err.contains(&format!("`{}`", field)), "{}", err);
+            assert_eq!(ErrorCode::of_tagged(&err), Some(ErrorCode::Validation));
+        }
+    }
+
+    /// Every generated tool carries its description and one for each parameter.
+    #[test]
+    fn tool_schema_is_documented() {
+        let tools: Vec<serde_json::Value> =
+            serde_json::from_str(<ServicenowContractState as Servicenow>::TOOLS).unwrap();
+        assert!(!tools.is_empty());
+        for tool in &tools {
+            let function = &tool["function"];
+            let name = function["name"].as_str().unwrap();
+            assert!(
+                !function["description"].as_str().unwrap().is_empty(),
+                "{name} has no description"
+            );
+            for (param, schema) in function["parameters"]["properties"].as_object().unwrap() {
+                assert!(
+                    schema["description"].as_str().is_some_and(|d| !d.is_empty()),
+                    "{name}.{param} has no description"
+                );
+            }
+        }
+    }
+
+    /// Non-2xx responses are classified by status and keep the message and detail from
+    /// ServiceNow's error envelope, or the raw body when there is no envelope.
+    #[test]
+    fn error_responses_keep_servicenow_explanation() {
+        assert_eq!(check_response(201, "{}".to_string()), Ok("{}".to_string()));
+
+        let not_found = r#"{"error":{"message":"No Record found","detail":"Record doesn't exist or ACL restricts the record retrieval"},"status":"failure"}"#;
+        assert_eq!(
+            check_response(404, not_found.to_string()),
+            Err(ServiceNowError::NotFound(
+                "No Record found (Record doesn't exist or ACL restricts the record retrieval)"
+                    .to_string()
+            ))
+        );
+
+        let auth = r#"{"error":{"message":"User Not Authenticated","detail":"Required to provide Auth information"}}"#;
+        assert!(matches!(
+            check_response(401, auth.to_string()),
+            Err(ServiceNowError::Auth(_))
+        ));
+        assert!(matches!(
+            check_response(429, String::new()),
+            Err(ServiceNowError::RateLimited(message)) if message == "no response body"
+        ));
+
+        let err = check_response(500, "upstream timeout".to_string()).unwrap_err();
+        assert_eq!(err.to_string(), "[UPSTREAM] HTTP 500: upstream timeout");
+        assert_eq!(
+            check_response(409, "duplicate".to_string())
+                .unwrap_err()
+                .code(),
+            ErrorCode::Conflict
+        );
+        assert_eq!(
+            check_response(404, not_found.to_string())
+                .unwrap_err()
+                .to_string(),
+            "[NOT_FOUND] Not found: No Record found (Record doesn't exist or ACL restricts the record retrieval)"
+        );
+    }
+
+    /// Listed transitions go through, New -> Closed skipping Resolved is refused, and states
+    /// without an entry can't be left.
+    #[test]
+    fn incident_transition_map() {
+        let transitions =
+            parse_transition_map(r#"{"1": ["2", "6"], "2": ["6"], "6": ["7", "2"]}"#).unwrap();
+
+        assert!(check_transition(&transitions, "1", "2").is_ok());
+        assert!(check_transition(&transitions, "6", "7").is_ok());
+        assert!(check_transition(&transitions, "2", "2").is_ok());
+
+        let err = check_transition(&transitions, "1", "7").unwrap_err();
+        assert_eq!(
+            err,
+            "Illegal incident state transition 1 -> 7; allowed from 1: 2, 6"
+        );
+        assert!(check_transition(&transitions, "7", "2").is_err());
+
+        assert!(parse_transition_map(r#"{"1": "2"}"#).is_err());
+    }
+
+    /// A final page of exactly `limit` records costs one more (empty) request, a short page
+    /// ends the listing, and without `fetch_all` only the page at `offset` is fetched.
+    #[test]
+    fn fetch_all_stops_after_exactly_full_last_page() {
+        let table: Vec<u32> = (0..10).collect();
+        let mut offsets = Vec::new();
+        let records = collect_pages(5, 0, true, |offset| {
+            offsets.push(offset);
+            Ok(table
+                .iter()
+                .skip(offset as usize)
+                .take(5)
+                .copied()
+                .collect())
+        })
+        .unwrap();
+        assert_eq!(records, table);
+        assert_eq!(offsets, vec![0, 5, 10]);
+
+        let mut offsets = Vec::new();
+        let records = collect_pages(4, 2, true, |offset| {
+            offsets.push(offset);
+            Ok(table
+                .iter()
+                .skip(offset as usize)
+                .take(4)
+                .copied()
+                .collect())
+        })
+        .unwrap();
+        assert_eq!(records, (2..10).collect::<Vec<u32>>());
+        assert_eq!(offsets, vec![2, 6, 10]);
+
+        let mut offsets = Vec::new();
+        collect_pages(4, 0, true, |offset| {
+            offsets.push(offset);
+            Ok(table
+                .iter()
+                .skip(offset as usize)
+                .take(4)
+                .copied()
+                .collect())
+        })
+        .unwrap();
+        assert_eq!(offsets, vec![0, 4, 8]);
+
+        let mut calls = 0;
+        let records = collect_pages(5, 5, false, |offset| {
+            calls += 1;
+            Ok(table
+                .iter()
+                .skip(offset as usize)
+                .take(5)
+                .copied()
+                .collect())
+        })
+        .unwrap();
+        assert_eq!(records, vec![5, 6, 7, 8, 9]);
+        assert_eq!(calls, 1);
+
+        let endless = collect_pages(2, 0, true, |_| Ok(vec![1, 2]));
+        assert!(endless.unwrap_err().starts_with("PaginationLimitExceeded"));
+    }
+
+    /// An update carries exactly the given fields; an empty map or an odd field name is refused
+    /// before anything is sent.
+    #[test]
+    fn incident_update_body() {
+        let fields = HashMap::from([
+            ("assigned_to".to_string(), "abel.tuter".to_string()),
+            ("urgency".to_string(), "2".to_string()),
+        ]);
+        assert_eq!(
+            build_incident_update(&fields).unwrap(),
+            serde_json::json!({"assigned_to": "abel.tuter", "urgency": "2"})
+        );
+
+        assert!(build_incident_update(&HashMap::new()).is_err());
+        let odd = HashMap::from([("state^ORactive".to_string(), "6".to_string())]);
+        assert!(build_incident_update(&odd).is_err());
+    }
+
+    /// Uploads target the incident table with the trimmed file name, and a blank name or a
+    /// content type that would break its header is refused.
+    #[test]
+    fn incident_attachment_upload_params() {
+        assert_eq!(
+            incident_attachment_params("46d44a5d", " disk.log ", "text/plain").unwrap(),
+            vec![
+                ("table_name".to_string(), "incident".to_string()),
+                ("table_sys_id".to_string(), "46d44a5d".to_string()),
+                ("file_name".to_string(), "disk.log".to_string()),
+            ]
+        );
+        assert!(incident_attachment_params("46d44a5d", " ", "text/plain").is_err());
+        assert!(incident_attachment_params("46d44a5d", "disk.log", "").is_err());
+        assert!(incident_attachment_params("46d44a5d", "disk.log", "text/plain\r\nX: y").is_err());
+
+        let attachment: Attachment = serde_json::from_str(
+            r#"{"sys_id": "a83820b5", "file_name": "disk.log", "size_bytes": "2048",
+                "download_link": "https://dev.service-now.com/api/now/attachment/a83820b5/file"}"#,
+        )
+        .unwrap();
+        assert_eq!(attachment.size_bytes.as_deref(), Some("2048"));
+    }
+
+    /// The Subject header (unfolded, without reply markers) and the text after the headers are
+    /// picked up; without headers the first line stands in for the subject.
+    #[test]
+    fn email_subject_and_body_are_extracted() {
+        let raw = "From: alerts@example.com\r\nSubject: Re: FW: Disk usage at 91%\r\n on db-01\r\nTo: ops@example.com\r\n\r\nVolume /data is nearly full.\r\nCleanup needed.\r\n";
+        let input = parse_email_to_incident(raw);
+        assert_eq!(input.short_description, "Disk usage at 91% on db-01");
+        assert_eq!(
+            input.description,
+            "Volume /data is nearly full.\nCleanup needed."
+        );
+
+        let headerless =
+            parse_email_to_incident("Nightly backup failed\n\nJob exited with code 2.");
+        assert_eq!(headerless.short_description, "Nightly backup failed");
+        assert_eq!(headerless.description, "Job exited with code 2.");
+
+        let subject_only = parse_email_to_incident(&format!("Subject: {}\n\n", "x".repeat(200)));
+        assert_eq!(subject_only.short_description.len(), 160);
+        assert_eq!(subject_only.description.len(), 200);
+    }
+
+    /// The most severe keyword anywhere in the email wins, keywords only match whole words, and
+    /// emails without any fall back to low priority.
+    #[test]
+    fn email_priority_follows_severity_keywords() {
+        assert_eq!(detect_priority("[CRITICAL] payments API"), "1");
+        assert_eq!(detect_priority("Sev 1: checkout is down"), "1");
+        assert_eq!(detect_priority("warning: latency is urgent"), "2");
+        assert_eq!(detect_priority("Service degraded"), "3");
+        assert_eq!(
+            detect_priority("Download report ready, shutdown scheduled"),
+            "4"
+        );
+        assert_eq!(detect_priority("Updated p10 dashboard"), "4");
+
+        let input =
+            parse_email_to_incident("Subject: Heads up\n\nPrimary database outage in eu-west.");
+        assert_eq!(input.priority, "1");
+    }
+
+    /// Following adds the user to the watch list once; repeating it, or unfollowing a record
+    /// that isn't followed, asks for no update.
+    #[test]
+    fn follow_is_idempotent_on_watch_list() {
+        let followed = update_watch_list("ops@example.com", "u1", true).unwrap();
+        assert_eq!(followed, "ops@example.com,u1");
+        assert_eq!(update_watch_list(&followed, "u1", true), None);
+
+        assert_eq!(update_watch_list("", "u1", true).as_deref(), Some("u1"));
+        assert_eq!(
+            update_watch_list(&followed, "u1", false).as_deref(),
+            Some("ops@example.com")
+        );
+        assert_eq!(update_watch_list("ops@example.com", "u1", false), None);
+    }
+
+    /// Template fields are applied, and overrides replace or add to them.
+    #[test]
+    fn template_fields_merge_with_overrides() {
+        let template = "short_description=Email down^priority=3^category=network^EQ";
+
+        let payload = merge_template_overrides(template, "").unwrap();
+        assert_eq!(
+            payload,
+            serde_json::json!({
+                "short_description": "Email down",
+                "priority": "3",
+                "category": "network"
+            })
+        );
+
+        let payload =
+            merge_template_overrides(template, r#"{"priority": "1", "assignment_group": "ops"}"#)
+                .unwrap();
+        assert_eq!(payload["short_description"], "Email down");
+        assert_eq!(payload["category"], "network");
+        assert_eq!(payload["priority"], "1");
+        assert_eq!(payload["assignment_group"], "ops");
+
+        assert!(merge_template_overrides(template, r#"["priority"]"#).is_err());
+        assert!(merge_template_overrides(template, "priority=1").is_err());
+    }
+
+    /// Exports page through the knowledge base until `max` articles are fetched and write one
+    /// NDJSON line per article.
+    #[test]
+    fn knowledge_export_writes_a_line_per_fetched_article() {
+        let knowledge_base: Vec<serde_json::Value> = (0..250)
+            .map(|index| {
+                serde_json::json!({
+                    "sys_id": format!("kb{}", index),
+                    "short_description": format!("Article {}", index),
+                    "text": "<p>line one\nline two</p>"
+                })
+            })
+            .collect();
+        let fetch = |requests: &mut Vec<(u32, u32)>, offset: u32, limit: u32| {
+            requests.push((offset, limit));
+            Ok(knowledge_base
+                .iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .cloned()
+                .collect())
+        };
+
+        let mut requests = Vec::new();
+        let articles = collect_up_to(230, KB_EXPORT_PAGE_SIZE, |offset, limit| {
+            fetch(&mut requests, offset, limit)
+        })
+        .unwrap();
+        assert_eq!(requests, vec![(0, 100), (100, 100), (200, 30)]);
+        let ndjson = articles_to_ndjson(&articles);
+        assert_eq!(ndjson.lines().count(), articles.len());
+        assert_eq!(articles.len(), 230);
+        let first: serde_json::Value =
+            serde_json::from_str(ndjson.lines().next().unwrap()).unwrap();
+        assert_eq!(first["sys_id"], "kb0");
+        assert_eq!(first["text"], "<p>line one\nline two</p>");
+
+        let mut requests = Vec::new();
+        let articles = collect_up_to(1000, KB_EXPORT_PAGE_SIZE, |offset, limit| {
+            fetch(&mut requests, offset, limit)
+        })
+        .unwrap();
+        assert_eq!(articles_to_ndjson(&articles).lines().count(), 250);
+        assert_eq!(requests.len(), 3);
+    }
+
+    /// Columns follow the requested field order, and values with commas, quotes or line breaks
+    /// are quoted with inner quotes doubled.
+    #[test]
+    fn records_render_as_csv() {
+        let fields: Vec<String> = ["short_description", "number", "caller_id", "priority"]
+            .iter()
+            .map(|field| field.to_string())
+            .collect();
+        let records = vec![
+            serde_json::json!({
+                "number": "INC0010001",
+                "short_description": "Printer \"LP-4\" jammed, again",
+                "caller_id": {"link": "https://dev.service-now.com/api/now/table/sys_user/6816f79c", "value": "6816f79c"},
+                "priority": "2"
+            }),
+            serde_json::json!({"number": "INC0010002", "short_description": "VPN down\nsince 9am", "caller_id": ""}),
+        ];
+        assert_eq!(
+            records_to_csv(&records, &fields),
+            "short_description,number,caller_id,priority\r\n\
+             \"Printer \"\"LP-4\"\" jammed, again\",INC0010001,6816f79c,2\r\n\
+             \"VPN down\nsince 9am\",INC0010002,,\r\n"
+        );
+        assert_eq!(
+            records_to_csv(&[], &fields),
+            "short_description,number,caller_id,priority\r\n"
+        );
+
+        assert!(validate_field_list(&fields).is_ok());
+        assert!(validate_field_list(&["caller_id.email".to_string()]).is_ok());
+        assert!(validate_field_list(&[]).is_err());
+        assert!(validate_field_list(&["number^ORactive=true".to_string()]).is_err());
+    }
+
+    /// Reference lists split into child table and field, REL: lists resolve through their
+    /// sys_relationship, and lists repeated across views appear once.
+    #[test]
+    fn related_list_definitions_parse() {
+        let definitions: Vec<String> = [
+            "task_ci.task",
+            "sysapproval_approver.sysapproval",
+            "REL:b9edf0ca0a0a0b010035de2d6b579a03",
+            "task_ci.task",
+            "REL:0123456789abcdef0123456789abcdef",
+        ]
+        .iter()
+        .map(|definition| definition.to_string())
+        .collect();
+        assert_eq!(
+            relationship_ids(&definitions),
+            vec![
+                "b9edf0ca0a0a0b010035de2d6b579a03",
+                "0123456789abcdef0123456789abcdef"
+            ]
+        );
+
+        let relationships = vec![serde_json::json!({
+            "sys_id": "b9edf0ca0a0a0b010035de2d6b579a03",
+            "name": "Incidents by Same Caller",
+            "basic_query_from": "incident"
+        })];
+        let related_lists = build_related_lists(definitions, &relationships);
+        assert_eq!(related_lists.len(), 4);
+        assert_eq!(
+            related_lists[0],
+            RelatedList {
+                definition: "task_ci.task".to_string(),
+                child_table: Some("task_ci".to_string()),
+                relationship: Some("task".to_string()),
+                kind: "reference".to_string(),
+            }
+        );
+        assert_eq!(
+            related_lists[1].child_table.as_deref(),
+            Some("sysapproval_approver")
+        );
+        assert_eq!(
+            related_lists[2],
+            RelatedList {
+                definition: "REL:b9edf0ca0a0a0b010035de2d6b579a03".to_string(),
+                child_table: Some("incident".to_string()),
+                relationship: Some("Incidents by Same Caller".to_string()),
+                kind: "relationship".to_string(),
+            }
+        );
+        assert_eq!(related_lists[3].kind, "relationship");
+        assert_eq!(related_lists[3].child_table, None);
+    }
+
+    /// task_sla records decode their text percentage and flag, and sort most urgent first.
+    #[test]
+    fn task_slas_parse_and_sort_by_urgency() {
+        let mut slas: Vec<TaskSla> = serde_json::from_value(serde_json::json!([
+            {"sys_id": "a", "sla": {"link": "https://x/contract_sla/s1", "value": "s1"},
+             "stage": "in_progress", "business_percentage": "45.5", "has_breached": "false",
+             "planned_end_time": "2026-01-02 10:00:00"},
+            {"sys_id": "b", "sla": "s2", "stage": "breached", "business_percentage": "112.25",
+             "has_breached": "true", "planned_end_time": "2026-01-01 09:00:00"},
+            {"sys_id": "c", "sla": "s3", "stage": "paused", "business_percentage": "",
+             "has_breached": "false", "planned_end_time": ""}
+        ]))
+        .unwrap();
+        assert_eq!(slas[0].sla.as_deref(), Some("s1"));
+        assert_eq!(slas[0].business_percentage, Some(45.5));
+        assert_eq!(slas[1].has_breached, Some(true));
+        assert_eq!(slas[2].business_percentage, None);
+
+        sort_slas_by_urgency(&mut slas);
+        let order: Vec<&str> = slas
+            .iter()
+            .filter_map(|sla| sla.sys_id.as_deref())
+            .collect();
+        assert_eq!(order, vec!["b", "a", "c"]);
+
+        assert!(validate_sys_id("46b66a40a9fe198101f243dfbc79033d").is_ok());
+        assert!(validate_sys_id("abc^active=false").is_err());
+        assert!(validate_sys_id("").is_err());
+    }
+
+    /// The sweep asks for unbreached incident SLAs over the threshold, keeps only those over it,
+    /// and pairs each with its incident, most used first.
+    #[test]
+    fn near_breach_slas_filter_by_percentage() {
+        assert_eq!(
+            near_breach_sla_query(80).unwrap(),
+            "active=true^has_breached=false^task.sys_class_name=incident^business_percentage>80^ORDERBYDESCbusiness_percentage"
+        );
+        assert!(near_breach_sla_query(100).is_ok());
+        assert!(near_breach_sla_query(101).is_err());
+
+        let slas: Vec<TaskSla> = serde_json::from_value(serde_json::json!([
+            {"sys_id": "s1", "task": {"link": "https://x/incident/i1", "value": "i1"},
+             "business_percentage": "85.0", "has_breached": "false"},
+            {"sys_id": "s2", "task": "i2", "business_percentage": "80", "has_breached": "false"},
+            {"sys_id": "s3", "task": "i3", "business_percentage": "97.5", "has_breached": "false"},
+            {"sys_id": "s4", "task": "i9", "business_percentage": "90", "has_breached": "false"}
+        ]))
+        .unwrap();
+        let incidents = vec![
+            serde_json::json!({"sys_id": "i1", "number": "INC0001"}),
+            serde_json::json!({"sys_id": "i2", "number": "INC0002"}),
+            serde_json::json!({"sys_id": "i3", "number": "INC0003"}),
+        ];
+
+        let at_risk = join_slas_with_incidents(slas, incidents, 80).unwrap();
+        let pairs: Vec<(&str, &str)> = at_risk
+            .iter()
+            .map(|risk| {
+                (
+                    risk.sla.sys_id.as_deref().unwrap(),
+                    risk.sla.task.as_deref().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(pairs, vec![("s3", "i3"), ("s1", "i1")]);
+    }
+
+    /// Only cmdb_ci and the tables extending it are accepted as CMDB classes.
+    #[test]
+    fn ci_class_must_be_a_cmdb_table() {
+        assert!(validate_ci_class("cmdb_ci").is_ok());
+        assert!(validate_ci_class("cmdb_ci_server").is_ok());
+        assert!(validate_ci_class("cmdb_ci_linux_server").is_ok());
+        assert!(validate_ci_class("incident").is_err());
+        assert!(validate_ci_class("cmdb_cix").is_err());
+        assert!(validate_ci_class("cmdb_ci_server^ORsys_id=1").is_err());
+    }
+
+    /// The retry budget defaults when unset and is capped.
+    #[test]
+    fn retry_budget_is_capped() {
+        assert_eq!(retry_budget(None), DEFAULT_MAX_RETRIES);
+        assert_eq!(retry_budget(Some(0)), 0);
+        assert_eq!(retry_budget(Some(50)), MAX_RETRIES_CAP);
+    }
+
+    /// Requests identify the crate and its version unless a User-Agent is configured.
+    #[test]
+    fn user_agent_defaults_to_crate_version() {
+        assert_eq!(
+            resolve_user_agent(None),
+            format!("wadk-mcp/servicenow/{}", env!("CARGO_PKG_VERSION"))
+        );
+        assert_eq!(resolve_user_agent(Some("  ")), DEFAULT_USER_AGENT);
+        assert_eq!(resolve_user_agent(Some("acme-agent/2.0")), "acme-agent/2.0");
+    }
+
+    /// Creates stamp both correlation columns when an external reference is given, and nothing otherwise.
+    #[test]
+    fn test_apply_external_ref() {
+        let mut payload = serde_json::json!({ "short_description": "Disk full" });
+        apply_external_ref(&mut payload, Some("JIRA-42".to_string())).unwrap();
+        assert_eq!(payload["correlation_id"], "JIRA-42");
+        assert_eq!(payload["correlation_display"], "JIRA-42");
+
+        let mut untouched = serde_json::json!({ "short_description": "Disk full" });
+        apply_external_ref(&mut untouched, None).unwrap();
+        assert!(untouched.get("correlation_id").is_none());
+        assert!(untouched.get("correlation_display").is_none());
+
+        assert!(apply_external_ref(&mut payload, Some("   ".to_string())).is_err());
+        assert!(apply_external_ref(&mut payload, Some("x".repeat(101))).is_err());
+    }
+
+    /// Incidents sharing every grouping value cluster together, oldest first, and singletons
+    /// or records missing a grouping field are not reported.
+    #[test]
+    fn test_group_duplicates() {
+        let records = vec![
+            serde_json::json!({"sys_id": "b", "number": "INC002", "sys_created_on": "2024-05-01 10:05:00",
+                "cmdb_ci": {"link": "https://x/cmdb_ci/db1", "value": "db1"}, "short_description": "DB down"}),
+            serde_json::json!({"sys_id": "a", "number": "INC001", "sys_created_on": "2024-05-01 10:00:00",
+                "cmdb_ci": {"link": "https://x/cmdb_ci/db1", "value": "db1"}, "short_description": "DB down"}),
+            serde_json::json!({"sys_id": "c", "number": "INC003", "sys_created_on": "2024-05-01 10:07:00",
+                "cmdb_ci": "db1", "short_description": "DB down"}),
+            serde_json::json!({"sys_id": "d", "number": "INC004", "sys_created_on": "2024-05-01 10:08:00",
+                "cmdb_ci": "web1", "short_description": "DB down"}),
+            serde_json::json!({"sys_id": "e", "number": "INC005", "sys_created_on": "2024-05-01 10:09:00",
+                "cmdb_ci": "", "short_description": "DB down"}),
+            serde_json::json!({"sys_id": "f", "number": "INC006", "sys_created_on": "2024-05-01 10:10:00",
+                "cmdb_ci": "", "short_description": "DB down"}),
+        ];
+
+        let groups = group_duplicates(&records, &["cmdb_ci", "short_description"]);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].key, "db1 | DB down");
+        assert_eq!(groups[0].primary_sys_id, "a");
+        assert_eq!(groups[0].primary_number.as_deref(), Some("INC001"));
+        assert_eq!(groups[0].duplicate_sys_ids, vec!["b", "c"]);
+        assert_eq!(groups[0].duplicate_numbers, vec!["INC002", "INC003"]);
+
+        let by_description = group_duplicates(&records, &["short_description"]);
+        assert_eq!(by_description.len(), 1);
+        assert_eq!(by_description[0].duplicate_sys_ids.len(), 5);
+    }
+
+    /// A full page points at the next offset; a short or empty page ends the iteration.
+    #[test]
+    fn test_next_page_offset() {
+        assert_eq!(next_page_offset(0, 50, 50), Some(50));
+        assert_eq!(next_page_offset(100, 50, 50), Some(150));
+        assert_eq!(next_page_offset(150, 50, 12), None);
+        assert_eq!(next_page_offset(200, 50, 0), None);
+    }
+
+    /// Lookups match on correlation_id, keep `^` literal and reject non-identifier table names.
+    #[test]
+    fn test_find_by_external_ref_query() {
+        assert_eq!(external_ref_query("JIRA-42"), "correlation_id=JIRA-42");
+        assert_eq!(
+            external_ref_query("ext^ORshort_description=x"),
+            "correlation_id=ext^^ORshort_description=x"
+        );
+
+        assert!(validate_table_name("incident").is_ok());
+        assert!(validate_table_name("u_custom_table2").is_ok());
+        assert!(validate_table_name("incident/../sys_user").is_err());
+        assert!(validate_table_name("").is_err());
+    }
+
+    /// The picture is found among an item's image attachments by field name and encoded as a data URI.
+    #[test]
+    fn catalog_item_picture_resolves_to_data_uri() {
+        let attachments = vec![
+            serde_json::json!({
+                "sys_id": "icon1",
+                "file_name": "icon",
+                "content_type": "image/png"
+            }),
+            serde_json::json!({
+                "sys_id": "pic1",
+                "file_name": "picture",
+                "content_type": "image/svg+xml"
+            }),
+        ];
+
+        let (sys_id, content_type) = find_picture_attachment(&attachments).unwrap();
+        assert_eq!(sys_id, "pic1");
+        assert_eq!(content_type, "image/svg+xml");
+        assert_eq!(
+            attachment_data_uri(&content_type, b"<svg/>"),
+            "data:image/svg+xml;base64,PHN2Zy8+"
+        );
+
+        assert!(find_picture_attachment(&attachments[..1]).is_none());
+        assert!(find_picture_attachment(&[]).is_none());
+    }
+
+    /// Every scan state maps to one of the four statuses, and anything unrecognised is `unknown`.
+    #[test]
+    fn attachment_scan_states_map_to_status() {
+        for (state, status) in [
+            ("available", "clean"),
+            ("pending", "pending"),
+            ("not_available", "infected"),
+            ("Quarantined", "infected"),
+            ("available_conditionally", "unknown"),
+        ] {
+            let record = serde_json::json!({ "state": state });
+            assert_eq!(
+                attachment_scan_status(record_field(&record, "state").as_deref()),
+                status
+            );
+        }
+        assert_eq!(attachment_scan_status(None), "unknown");
+    }
+
+    /// Each mtom row becomes a question → value pair; the variable name stands in for missing
+    /// question text and rows naming no variable are dropped.
+    #[test]
+    fn request_variables_pair_questions_with_values() {
+        let rows: Vec<serde_json::Value> = serde_json::from_str(
+            r#"[
+                {"sc_item_option.item_option_new.question_text": "Laptop model",
+                 "sc_item_option.item_option_new.name": "laptop_model",
+                 "sc_item_option.value": "MacBook Pro 14"},
+                {"sc_item_option.item_option_new.question_text": "",
+                 "sc_item_option.item_option_new.name": "cost_center",
+                 "sc_item_option.value": "CC-1200"},
+                {"sc_item_option.item_option_new.question_text": "Justification",
+                 "sc_item_option.item_option_new.name": "justification",
+                 "sc_item_option.value": ""},
+                {"sc_item_option.item_option_new.question_text": "",
+                 "sc_item_option.item_option_new.name": "",
+                 "sc_item_option.value": "orphan"}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            request_variable_pairs(&rows),
+            vec![
+                ("Laptop model".to_string(), "MacBook Pro 14".to_string()),
+                ("cost_center".to_string(), "CC-1200".to_string()),
+                ("Justification".to_string(), String::new()),
+            ]
+        );
+    }
+
+    /// No match creates a record carrying the match value, one match updates it, and several
+    /// matches are a conflict naming each record.
+    #[test]
+    fn upsert_creates_updates_or_refuses_ambiguous_matches() {
+        assert_eq!(upsert_target(&[], "u_asset_tag", "A-1"), Ok(None));
+        let payload = upsert_payload(r#"{"name": "Laptop"}"#, "u_asset_tag", "A-1").unwrap();
+        assert_eq!(
+            payload,
+            serde_json::json!({"name": "Laptop", "u_asset_tag": "A-1"})
+        );
+
+        let one = vec![serde_json::json!({"sys_id": "abc"})];
+        assert_eq!(
+            upsert_target(&one, "u_asset_tag", "A-1"),
+            Ok(Some("abc".to_string()))
+        );
+        assert!(upsert_payload(r#"{"u_asset_tag": "A-1"}"#, "u_asset_tag", "A-1").is_ok());
+
+        let two = vec![
+            serde_json::json!({"sys_id": "abc"}),
+            serde_json::json!({"sys_id": "def"}),
+        ];
+        let err = upsert_target(&two, "u_asset_tag", "A-1").unwrap_err();
+        assert_eq!(ErrorCode::of_tagged(&err), Some(ErrorCode::Conflict));
+        assert!(
+            err.ends_with("2 records have u_asset_tag=A-1: abc, def"),
+            "{err}"
+        );
+
+        assert!(upsert_payload(r#"{"u_asset_tag": "A-2"}"#, "u_asset_tag", "A-1").is_err());
+        assert!(upsert_payload("[1]", "u_asset_tag", "A-1").is_err());
+        assert_eq!(field_equals_query("u_asset_tag", "A^1"), "u_asset_tag=A^^1");
+    }
+
+    /// Every prompt has the MCP fields, and its messages only use placeholders for arguments it
+    /// declares.
+    #[test]
+    fn prompts_declare_their_placeholders() {
+        let prompts: serde_json::Value = serde_json::from_str(PROMPTS).unwrap();
+        let prompts = prompts["prompts"].as_array().unwrap();
+        assert_eq!(prompts.len(), 3);
+
+        for prompt in prompts {
+            let name = prompt["name"].as_str().unwrap();
+            assert!(prompt["description"].is_string(), "{name}");
+            let arguments: Vec<&str> = prompt["arguments"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|argument| {
+                    assert!(argument["required"].is_boolean(), "{name}");
+                    argument["name"].as_str().unwrap()
+                })
+                .collect();
+            assert!(arguments.contains(&"sys_id"), "{name}");
+
+            for message in prompt["messages"].as_array().unwrap() {
+                let text = message["content"]["text"].as_str().unwrap();
+                for placeholder in text.split("{{").skip(1) {
+                    let (argument, _) = placeholder.split_once("}}").unwrap();
+                    assert!(arguments.contains(&argument), "{name}: {argument}");
+                }
+            }
+        }
+    }
+
+    /// Representative tool errors get the hint for their cause, with specific messages winning
+    /// over the generic hint for their code.
+    #[test]
+    fn diagnose_maps_errors_to_guidance() {
+        let guidance = |message: &str| diagnosis_for(message, DIAGNOSIS_RULES).unwrap_or_default();
+        let unauthenticated = ServiceNowError::Auth("User Not Authenticated".to_string());
+        assert!(guidance(&String::from(unauthenticated)).contains("rotate the password"));
+        assert!(guidance("HTTP 403: Insufficient rights").contains("ACL access"));
+        let missing = ServiceNowError::NotFound("No Record found".to_string());
+        assert!(guidance(&String::from(missing)).contains("Verify the sys_id"));
+        let throttled = ServiceNowError::RateLimited("Maximum per-user rate exceeded".to_string());
+        assert!(guidance(&String::from(throttled)).contains("Reduce the call rate"));
+        assert!(guidance("Invalid sys_id: INC0010001").contains("32-character sys_id"));
+        assert!(guidance(TOOL_DISABLED).contains("disabled_tools"));
+        assert!(
+            guidance("[VALIDATION] config field `password` is required but empty")
+                .contains("MCP secrets")
+        );
+        assert_eq!(diagnosis_for("something unexpected", DIAGNOSIS_RULES), None);
+    }
+
+    /// Requests go to the named instance's URL with its credentials, to the primary instance
+    /// when no name is given, and an unknown or incomplete instance is refused.
+    #[test]
+    fn requests_route_to_selected_instance() {
+        let instance = |base_url: &str, username: &str| InstanceConfig {
+            base_url: base_url.to_string(),
+            username: username.to_string(),
+            password: "secret".to_string(),
+        };
+        let mut config = ServicenowConfig {
+            base_url: "https://acme.service-now.com".to_string(),
+            username: "svc-prod".to_string(),
+            password: "secret".to_string(),
+            instances: Some(BTreeMap::from([
+                (
+                    "dev".to_string(),
+                    instance("https://acmedev.service-now.com", "svc-dev"),
+                ),
+                (
+                    "test".to_string(),
+                    instance("https://acmetest.service-now.com", "svc-test"),
+                ),
+            ])),
+            ..Default::default()
+        };
+
+        let dev = config.connection(Some("dev")).unwrap();
+        assert_eq!(dev.username, "svc-dev");
+        assert_eq!(
+            CASCADE_TARGETS[0].delete_url(&dev.base_url, "abc123"),
+            "https://acmedev.service-now.com/api/now/table/incident_task/abc123"
+        );
+        assert_eq!(
+            config.connection(None).unwrap(),
+            instance("https://acme.service-now.com", "svc-prod")
+        );
+
+        let err = config.connection(Some("staging")).unwrap_err();
+        assert_eq!(ErrorCode::of_tagged(&err), Some(ErrorCode::Validation));
+        assert!(err.contains("configured instances: dev, test"), "{err}");
+
+        config
+            .instances
+            .as_mut()
+            .unwrap()
+            .insert("qa".to_string(), instance("", "svc-qa"));
+        assert!(
+            config
+                .connection(None)
+                .unwrap_err()
+                .contains("instances.qa.base_url")
+        );
+    }
+
+    /// Related records are deleted table by table before the incident, a failure keeps the
+    /// incident, and the guards refuse unknown tables and oversized cascades.
+    #[test]
+    fn cascade_deletes_children_before_incident() {
+        let targets = cascade_targets(None).unwrap();
+        let plan = vec![
+            (targets[0], vec!["task1".to_string(), "task2".to_string()]),
+            (targets[1], vec![]),
+            (targets[2], vec!["att1".to_string()]),
+        ];
+        let mut deleted = Vec::new();
+        let report = run_cascade_delete("inc1", plan, |target, sys_id| {
+            deleted.push(target.delete_url("https://x", sys_id));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(
+            deleted,
+            vec![
+                "https://x/api/now/table/incident_task/task1",
+                "https://x/api/now/table/incident_task/task2",
+                "https://x/api/now/attachment/att1",
+                "https://x/api/now/table/incident/inc1",
+            ]
+        );
+        assert_eq!(report.related[1].table, "task_ci");
+        assert_eq!(report.related[2].sys_ids, vec!["att1".to_string()]);
+
+        let plan = vec![(targets[0], vec!["task1".to_string(), "task2".to_string()])];
+        let mut deleted = Vec::new();
+        let err = run_cascade_delete("inc1", plan, |_, sys_id| {
+            deleted.push(sys_id.to_string());
+            if sys_id == "task2" {
+                Err("[AUTH] Not authorized".to_string())
+            } else {
+                Ok(())
+            }
+        })
+        .unwrap_err();
+        assert_eq!(deleted, vec!["task1", "task2"]);
+        assert!(
+            err.contains("1 related records already deleted, incident inc1 kept"),
+            "{err}"
+        );
+
+        assert_eq!(
+            targets[2].query("inc1"),
+            "table_name=incident^table_sys_id=inc1"
+        );
+        let configured = cascade_targets(Some(&["sys_attachment".to_string()])).unwrap();
+        assert_eq!(configured.len(), 1);
+        assert!(cascade_targets(Some(&["sys_journal_field".to_string()])).is_err());
+        let too_many = vec![serde_json::json!({"sys_id": "t"}); MAX_CASCADE_RECORDS as usize + 1];
+        assert!(cascade_sys_ids("incident_task", &too_many).is_err());
+    }
+
+    /// A two-operation batch is encoded with per-operation headers and base64 bodies, and the
+    /// serviced/unserviced split of the reply is mapped back per operation.
+    #[test]
+    fn batch_body_and_response_round_trip() {
+        let requests = vec![
+            BatchRequest {
+                id: "create".to_string(),
+                method: "post".to_string(),
+                url: "/api/now/table/incident".to_string(),
+                body: Some(r#"{"short_description":"Disk full"}"#.to_string()),
+            },
+            BatchRequest {
+                id: "read".to_string(),
+                method: "GET".to_string(),
+                url: "/api/now/table/incident?sysparm_limit=1".to_string(),
+                body: None,
+            },
+        ];
+
+        let body = build_batch_body(&requests).unwrap();
+        let ops = body["rest_requests"].as_array().unwrap();
+        assert_eq!(ops.len(), 2);
+        assert_eq!(ops[0]["method"], "POST");
+        assert_eq!(ops[0]["url"], "/api/now/table/incident");
+        assert_eq!(
+            ops[0]["body"],
+            general_purpose::STANDARD.encode(r#"{"short_description":"Disk full"}"#)
+        );
+        assert_eq!(ops[0]["headers"][0]["name"], "Content-Type");
+        assert!(ops[1].get("body").is_none());
+
+        let reply = serde_json::json!({
+            "batch_request_id": "1",
+            "serviced_requests": [{
+                "id": "create",
+                "status_code": 201,
+                "status_text": "Created",
+                "body": general_purpose::STANDARD.encode(r#"{"result":{"number":"INC0010001"}}"#),
+                "execution_time": 42
+            }],
+            "unserviced_requests": ["read"]
+        })
+        .to_string();
+
+        let responses = parse_batch_response(&reply).unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0].id, "create");
+        assert!(responses[0].serviced);
+        assert_eq!(responses[0].status_code, Some(201));
+        assert_eq!(
+            responses[0].body.as_deref(),
+            Some(r#"{"result":{"number":"INC0010001"}}"#)
+        );
+        assert_eq!(responses[1].id, "read");
+        assert!(!responses[1].serviced);
+        assert_eq!(responses[1].status_code, None);
+    }
+
+    /// Bulk-created incidents are sent as numbered POSTs and matched back by id, with failed and
+    /// unserviced operations reported per item.
+    #[test]
+    fn bulk_incidents_correlate_by_batch_id() {
+        let new_incident = |short_description: &str, caller_id: Option<&str>| NewIncident {
+            short_description: short_description.to_string(),
+            description: "from alerting".to_string(),
+            priority: "2".to_string(),
+            caller_id: caller_id.map(str::to_string),
+            assignment_group: None,
+        };
+        let requests = bulk_incident_requests(&[
+            new_incident("Disk full", Some("u1")),
+            new_incident("CPU high", None),
+            new_incident("Link down", None),
+        ]);
+        let ids: Vec<&str> = requests.iter().map(|request| request.id.as_str()).collect();
+        assert_eq!(ids, vec!["incident-0", "incident-1", "incident-2"]);
+        assert!(build_batch_body(&requests).is_ok());
+        let body: serde_json::Value =
+            serde_json::from_str(requests[0].body.as_deref().unwrap()).unwrap();
+        assert_eq!(body["caller_id"], "u1");
+        assert!(body.get("assignment_group").is_none());
+
+        let response = |id: &str, status: Option<u32>, body: &str| BatchResponse {
+            id: id.to_string(),
+            serviced: status.is_some(),
+            status_code: status,
+            status_text: None,
+            body: Some(body.to_string()),
+        };
+        let responses = vec![
+            response(
+                "incident-1",
+                Some(400),
+                r#"{"error":{"message":"Invalid priority"}}"#,
+            ),
+            response("incident-2", None, ""),
+            response(
+                "incident-0",
+                Some(201),
+                r#"{"result":{"sys_id":"abc","number":"INC0010001"}}"#,
+            ),
+        ];
+
+        let outcomes = correlate_bulk_incidents(&requests, responses);
+        assert_eq!(outcomes.len(), 3);
+        assert_eq!(outcomes[0].0, "incident-0");
+        assert!(outcomes[0].1.is_ok());
+        assert_eq!(
+            outcomes[1].1.as_ref().unwrap_err(),
+            &ServiceNowError::Api {
+                status: 400,
+                message: "Invalid priority".to_string()
+            }
+        );
+        assert!(matches!(outcomes[2].1, Err(ServiceNowError::Unserviced(_))));
+    }
+
+    /// Requests the Batch API would reject are caught before anything is sent.
+    #[test]
+    fn batch_body_rejects_invalid_requests() {
+        let request = |id: &str, method: &str, url: &str| BatchRequest {
+            id: id.to_string(),
+            method: method.to_string(),
+            url: url.to_string(),
+            body: None,
+        };
+        assert!(build_batch_body(&[]).is_err());
+        assert!(build_batch_body(&[request("1", "TRACE", "/api/now/table/incident")]).is_err());
+        assert!(build_batch_body(&[request("1", "GET", "https://evil.example/api")]).is_err());
+        assert!(
+            build_batch_body(&[
+                request("1", "GET", "/api/now/table/incident"),
+                request("1", "GET", "/api/now/table/problem"),
+            ])
+            .is_err()
+        );
     }
 }
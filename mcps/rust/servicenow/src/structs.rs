@@ -10,7 +10,10 @@
 //! - **Proper Error Handling**: Graceful handling of missing or malformed fields
 
 use serde::{Deserialize, Serialize, Deserializer};
+use std::collections::BTreeMap;
 use weil_macros::WeilType;
+use weil_rs::config::{require_http_url, require_non_empty};
+use weil_rs::errors::ErrorCode;
 
 /// Custom deserializer for ServiceNow boolean fields that are returned as strings
 /// 
@@ -48,6 +51,19 @@ where
     }
 }
 
+/// Custom deserializer for ServiceNow numeric fields that are returned as strings
+///
+/// Decimal and percentage columns such as `business_percentage` come back as text
+/// (`"112.35"`); this parses them into `Some(f64)`, with empty strings, nulls and anything that
+/// isn't a number becoming `None`.
+fn deserialize_string_to_f64<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: Option<String> = Option::deserialize(deserializer)?;
+    Ok(s.and_then(|s| s.trim().parse::<f64>().ok()))
+}
+
 /// Custom deserializer for ServiceNow reference fields that can be strings or objects
 /// 
 /// **Why we need this:**
@@ -155,6 +171,61 @@ where
     deserializer.deserialize_any(ReferenceFieldVisitor)
 }
 
+// ============================================================================
+// ERROR STRUCTURES
+// ============================================================================
+
+/// Why a call to ServiceNow failed, with the explanation ServiceNow gave where there is one
+#[derive(Debug, PartialEq)]
+pub enum ServiceNowError {
+    /// 401 or 403: the credentials were rejected or lack the required role
+    Auth(String),
+    /// 404: the record or table doesn't exist (or isn't visible to the user)
+    NotFound(String),
+    /// 429: the instance's rate limit was hit
+    RateLimited(String),
+    /// Any other non-2xx response
+    Api { status: u16, message: String },
+    /// A batch operation ServiceNow didn't get to before the batch ended
+    Unserviced(String),
+    /// The response body couldn't be decoded
+    Parse(String),
+}
+
+impl ServiceNowError {
+    /// Category the error is tagged with when surfaced to the agent
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ServiceNowError::Auth(_) => ErrorCode::Auth,
+            ServiceNowError::NotFound(_) => ErrorCode::NotFound,
+            ServiceNowError::RateLimited(_) => ErrorCode::RateLimited,
+            ServiceNowError::Api { status, .. } => ErrorCode::from_http_status(*status),
+            ServiceNowError::Unserviced(_) | ServiceNowError::Parse(_) => ErrorCode::Upstream,
+        }
+    }
+}
+
+/// Rendered as `[CODE] message` so agents can branch on the category
+impl std::fmt::Display for ServiceNowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] ", self.code())?;
+        match self {
+            ServiceNowError::Auth(message) => write!(f, "Not authorized: {}", message),
+            ServiceNowError::NotFound(message) => write!(f, "Not found: {}", message),
+            ServiceNowError::RateLimited(message) => write!(f, "Rate limited: {}", message),
+            ServiceNowError::Api { status, message } => write!(f, "HTTP {}: {}", status, message),
+            ServiceNowError::Unserviced(message) => write!(f, "Not processed: {}", message),
+            ServiceNowError::Parse(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<ServiceNowError> for String {
+    fn from(err: ServiceNowError) -> String {
+        err.to_string()
+    }
+}
+
 // ============================================================================
 // CONFIGURATION STRUCTURES
 // ============================================================================
@@ -165,6 +236,73 @@ pub struct ServicenowConfig {
     pub base_url: String,
     pub username: String,
     pub password: String,
+    /// User-Agent to send instead of the default `wadk-mcp/servicenow/<version>`
+    pub user_agent: Option<String>,
+    /// Allowed incident state transitions as a JSON object of state to reachable states, e.g.
+    /// `{"1": ["2"], "2": ["6"], "6": ["7", "2"]}`; any transition is allowed when unset
+    pub incident_transitions: Option<String>,
+    /// Times a GET, PUT or DELETE request is retried after a 429, 502, 503 or 504 response
+    /// (default 2, at most 5); POST and PATCH requests, e.g. creates, are never resent
+    pub max_retries: Option<u32>,
+    /// Tools that refuse to run and are left out of `tools()`, e.g. `["delete_incident"]` for a
+    /// read-only deployment
+    #[serde(default)]
+    pub disabled_tools: Vec<String>,
+    /// Related tables `delete_incident_cascade` cleans up before deleting the incident, e.g.
+    /// `["incident_task", "sys_attachment"]`; every supported table when unset
+    pub cascade_delete_tables: Option<Vec<String>>,
+    /// Further instances by name, e.g. `dev` and `test`, which tools reach through their
+    /// `instance` argument; `base_url`, `username` and `password` above are the primary instance
+    pub instances: Option<BTreeMap<String, InstanceConfig>>,
+}
+
+/// URL and credentials of one ServiceNow instance
+#[derive(Debug, Clone, Serialize, Deserialize, WeilType, Default, PartialEq)]
+pub struct InstanceConfig {
+    pub base_url: String,
+    pub username: String,
+    pub password: String,
+}
+
+impl ServicenowConfig {
+    /// Check that the instance URL and credentials are set, naming the first field that isn't
+    pub fn validate(&self) -> Result<(), String> {
+        require_http_url("base_url", &self.base_url)?;
+        require_non_empty("username", &self.username)?;
+        require_non_empty("password", &self.password)?;
+        for (name, instance) in self.instances.iter().flatten() {
+            require_http_url(&format!("instances.{}.base_url", name), &instance.base_url)?;
+            require_non_empty(&format!("instances.{}.username", name), &instance.username)?;
+            require_non_empty(&format!("instances.{}.password", name), &instance.password)?;
+        }
+        Ok(())
+    }
+
+    /// The validated instance named `instance`, or the primary one when no name is given
+    pub fn connection(&self, instance: Option<&str>) -> Result<InstanceConfig, String> {
+        self.validate()?;
+        match instance.map(str::trim).filter(|name| !name.is_empty()) {
+            None => Ok(InstanceConfig {
+                base_url: self.base_url.clone(),
+                username: self.username.clone(),
+                password: self.password.clone(),
+            }),
+            Some(name) => self
+                .instances
+                .as_ref()
+                .and_then(|instances| instances.get(name))
+                .cloned()
+                .ok_or_else(|| {
+                    let known: Vec<&str> =
+                        self.instances.iter().flatten().map(|(name, _)| name.as_str()).collect();
+                    let known = if known.is_empty() { "none".to_string() } else { known.join(", ") };
+                    ErrorCode::Validation.tag(format!(
+                        "unknown instance '{}'; configured instances: {}",
+                        name, known
+                    ))
+                }),
+        }
+    }
 }
 
 // ============================================================================
@@ -172,6 +310,9 @@ pub struct ServicenowConfig {
 // ============================================================================
 
 /// ServiceNow Incident record structure
+///
+/// Every field is optional so reads projected with `fields` still deserialize; of these, only
+/// `sys_id` is always returned.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Incident {
     sys_id: Option<String>,
@@ -182,6 +323,18 @@ pub struct Incident {
     state: Option<String>,
 }
 
+/// One incident to create with `create_incidents_bulk`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewIncident {
+    pub short_description: String,
+    pub description: String,
+    pub priority: String,
+    /// sys_id of the user reporting the incident
+    pub caller_id: Option<String>,
+    /// sys_id of the group the incident is assigned to
+    pub assignment_group: Option<String>,
+}
+
 /// ServiceNow Comment record structure
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Comment {
@@ -192,6 +345,119 @@ pub struct Comment {
     sys_created_by: Option<String>,
 }
 
+/// File attached to a record, as returned by the Attachment API
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Attachment {
+    pub sys_id: Option<String>,
+    pub file_name: Option<String>,
+    /// Size of the file in bytes; ServiceNow sends it as a string
+    pub size_bytes: Option<String>,
+    /// URL that downloads the file's content
+    pub download_link: Option<String>,
+}
+
+/// Who can see a journal entry: customer-visible comments or work notes for the support team
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CommentVisibility {
+    Comment,
+    WorkNote,
+}
+
+impl CommentVisibility {
+    /// Journal field the entry is written to
+    pub fn journal_field(self) -> &'static str {
+        match self {
+            CommentVisibility::Comment => "comments",
+            CommentVisibility::WorkNote => "work_notes",
+        }
+    }
+}
+
+/// One comment or work note in a record's journal
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub sys_created_on: String,
+    #[serde(default, deserialize_with = "deserialize_reference_field")]
+    pub sys_created_by: Option<String>,
+    /// Journal field the entry belongs to: comments or work_notes
+    pub element: String,
+    pub value: String,
+}
+
+/// Incident fields extracted from a raw email by `parse_email_to_incident`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IncidentInput {
+    pub short_description: String,
+    pub description: String,
+    /// Suggested priority, "1" (critical) to "4" (low)
+    pub priority: String,
+}
+
+/// One page of incidents, with the offset to request the next page from
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IncidentPage {
+    pub results: Vec<Incident>,
+    /// Offset of the next page, or None once the last page has been returned
+    pub next_offset: Option<u32>,
+}
+
+/// An SLA attached to a task, from the `task_sla` table
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskSla {
+    pub sys_id: Option<String>,
+    /// sys_id of the task (e.g. incident) the SLA is attached to
+    #[serde(default, deserialize_with = "deserialize_reference_field")]
+    pub task: Option<String>,
+    /// sys_id of the SLA definition (`contract_sla`)
+    #[serde(default, deserialize_with = "deserialize_reference_field")]
+    pub sla: Option<String>,
+    /// in_progress, paused, completed, cancelled or breached
+    pub stage: Option<String>,
+    /// Share of the allowed business time used so far; above 100 once breached
+    #[serde(default, deserialize_with = "deserialize_string_to_f64")]
+    pub business_percentage: Option<f64>,
+    #[serde(default, deserialize_with = "deserialize_string_to_bool")]
+    pub has_breached: Option<bool>,
+    /// When the SLA is due to breach
+    pub planned_end_time: Option<String>,
+}
+
+/// An active incident SLA past a usage threshold, with the incident it belongs to
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SlaBreachRisk {
+    pub incident: Incident,
+    pub sla: TaskSla,
+}
+
+/// A related list shown on a table's form, from `sys_ui_related_list_entry`
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct RelatedList {
+    /// Definition as stored on the form, e.g. `task_ci.task` or `REL:<sys_id>`
+    pub definition: String,
+    /// Table the list's records come from; unknown for a relationship that couldn't be read
+    pub child_table: Option<String>,
+    /// Reference field on the child table pointing back at the record, or the name of the
+    /// `sys_relationship` defining the list
+    pub relationship: Option<String>,
+    /// "reference" for a child table's reference field, "relationship" for a `sys_relationship`
+    pub kind: String,
+}
+
+/// A cluster of incidents sharing the same grouping key, with a suggested primary
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DedupeGroup {
+    /// Values of the grouping fields, joined with " | "
+    pub key: String,
+    /// sys_id of the suggested primary (the earliest created incident)
+    pub primary_sys_id: String,
+    /// Number of the suggested primary, e.g. INC0010001
+    pub primary_number: Option<String>,
+    /// sys_ids of the other incidents in the cluster, oldest first
+    pub duplicate_sys_ids: Vec<String>,
+    /// Numbers of the other incidents in the cluster, oldest first
+    pub duplicate_numbers: Vec<String>,
+}
+
 // ============================================================================
 // SERVICE CATALOG STRUCTURES
 // ============================================================================
@@ -467,3 +733,106 @@ pub struct UIPolicyAction {
     field_name: Option<String>,
     action: Option<String>,
 }
+
+// ============================================================================
+// CMDB STRUCTURES
+// ============================================================================
+
+/// A configuration item from the CMDB (`cmdb_ci` or one of its classes)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfigurationItem {
+    pub sys_id: Option<String>,
+    pub name: Option<String>,
+    /// Class of the item, e.g. cmdb_ci_server or cmdb_ci_appl
+    pub sys_class_name: Option<String>,
+    /// "1" operational, "2" non-operational, "3" repair in progress, ...
+    pub operational_status: Option<String>,
+    pub asset_tag: Option<String>,
+}
+
+// ============================================================================
+// RECORD FOLLOWING STRUCTURES
+// ============================================================================
+
+/// Whether the configured user follows a record after a follow/unfollow call
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FollowStatus {
+    pub table: String,
+    pub sys_id: String,
+    /// True if the user is on the record's watch list
+    pub following: bool,
+    /// False if the record was already in the requested state and was left untouched
+    pub changed: bool,
+}
+
+// ============================================================================
+// CASCADE DELETE STRUCTURES
+// ============================================================================
+
+/// Records of one related table removed along with an incident
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct CascadeDeletion {
+    pub table: String,
+    pub sys_ids: Vec<String>,
+}
+
+/// What `delete_incident_cascade` removed, related records first
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct CascadeDeleteReport {
+    pub incident_sys_id: String,
+    /// One entry per cleaned-up table, in deletion order, including tables with nothing to delete
+    pub related: Vec<CascadeDeletion>,
+}
+
+// ============================================================================
+// BATCH API STRUCTURES
+// ============================================================================
+
+/// A single REST operation to run through the Batch API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchRequest {
+    /// Caller-chosen id, echoed back on the matching response
+    pub id: String,
+    /// HTTP method (GET, POST, PUT, PATCH or DELETE)
+    pub method: String,
+    /// Instance-relative URL including any query string, e.g. /api/now/table/incident
+    pub url: String,
+    /// JSON request body as a string
+    pub body: Option<String>,
+}
+
+/// Outcome of one operation from a Batch API call
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchResponse {
+    pub id: String,
+    /// False if ServiceNow did not get to this operation before the batch ended
+    pub serviced: bool,
+    pub status_code: Option<u32>,
+    pub status_text: Option<String>,
+    /// Decoded response body of the operation
+    pub body: Option<String>,
+}
+
+/// An item a bulk operation failed on, with the reason
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct BulkFailure<T> {
+    pub item: T,
+    pub error: String,
+}
+
+/// Per-item outcome of applying one operation to many items
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct BulkResult<T> {
+    pub succeeded: Vec<T>,
+    pub failed: Vec<BulkFailure<T>>,
+}
+
+/// Outcome of one incident from `create_incidents_bulk`; exactly one of `incident` and `error`
+/// is set
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkIncidentResult {
+    /// Batch request id the incident was sent under
+    pub id: String,
+    pub incident: Option<Incident>,
+    pub error: Option<String>,
+}
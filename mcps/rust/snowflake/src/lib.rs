@@ -42,6 +42,7 @@ use std::fmt;
 use weil_macros::{WeilType, constructor, query, smart_contract};
 use weil_rs::config::Secrets;
 use weil_rs::{
+    errors::ErrorCode,
     http::{HttpClient, HttpMethod},
     runtime::Runtime,
 };
@@ -54,6 +55,14 @@ mod parser;
 /// Snowflake or JSON parsing stages.
 const INVALID_DATA_RECEIVED: &str = "invalid data received; ";
 
+/// User-Agent sent with every request unless `user_agent` is set in the config.
+const DEFAULT_USER_AGENT: &str = concat!(
+    "wadk-mcp/",
+    env!("CARGO_PKG_NAME"),
+    "/",
+    env!("CARGO_PKG_VERSION")
+);
+
 /// Configuration for connecting to a Snowflake account.
 ///
 /// Fields are sourced from the contract's secret store. `account_identifier` is the
@@ -68,6 +77,8 @@ pub struct SnowflakeConfig {
     pub pat_token: String,
     /// Snowflake role name under which statements will execute.
     pub role: String,
+    /// User-Agent to send instead of `DEFAULT_USER_AGENT`.
+    pub user_agent: Option<String>,
 }
 
 /// JSON body sent to the Snowflake SQL API `/api/v2/statements`.
@@ -200,8 +211,9 @@ fn handle_option_str(val_opt: Option<&String>) -> Result<String, String> {
 
 /// Build Snowflake API request headers, including PAT authentication and token type.
 ///
-/// Adds a descriptive `User-Agent` for observability and best practices.
-fn get_header(pat_token: String) -> HashMap<String, String> {
+/// Adds a descriptive `User-Agent` for observability and best practices: the configured one, or
+/// `DEFAULT_USER_AGENT` when none is set.
+fn get_header(pat_token: String, user_agent: Option<String>) -> HashMap<String, String> {
     let mut header = HashMap::new();
     header.insert("Authorization".to_owned(), format!("Bearer {}", pat_token));
     header.insert("Content-Type".to_owned(), "application/json".to_owned());
@@ -210,7 +222,10 @@ fn get_header(pat_token: String) -> HashMap<String, String> {
         "X-Snowflake-Authorization-Token-Type".to_owned(),
         "PROGRAMMATIC_ACCESS_TOKEN".to_owned(),
     );
-    header.insert("User-Agent".to_owned(), "snowflake-mcp/1.0".to_owned()); // Good practice
+    let user_agent = user_agent
+        .filter(|user_agent| !user_agent.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_USER_AGENT.to_owned());
+    header.insert("User-Agent".to_owned(), user_agent);
 
     header
 }
@@ -364,24 +379,28 @@ impl SnowflakeContractState {
         };
 
         let response = HttpClient::request(&query_url, HttpMethod::Post)
-            .headers(get_header(self.secrets.config().pat_token.clone()))
+            .headers(get_header(
+                self.secrets.config().pat_token.clone(),
+                self.secrets.config().user_agent.clone(),
+            ))
             .json(&request_body)
             .send()
             .map_err(|err| INVALID_DATA_RECEIVED.to_owned() + &err.to_string())?;
 
         // handling query result which contains handle for Asynchronous execution
         if response.status() == 202 {
-            return Err(format!(
+            return Err(ErrorCode::Timeout.tag(format!(
                 "The Query Execution is taking more than 45 seconds. Therefore it can not be processed via the model. SQL statement (shown by the model) {}, in schema {} of database {}, warehouse {}",
                 statement,
                 schema_name.unwrap_or("not needed"),
                 database,
                 warehouse
-            ));
+            )));
         }
 
-        if !(200..300).contains(&response.status()) {
-            return Err(response.text());
+        let status = response.status();
+        if !(200..300).contains(&status) {
+            return Err(ErrorCode::from_http_status(status).tag(response.text()));
         }
 
         Ok(response.text())
@@ -18,6 +18,7 @@
 use serde::{Deserialize, Serialize};
 use weil_macros::{constructor, mutate, query, smart_contract, WeilType};
 use weil_rs::config::Secrets;
+use weil_rs::errors::ErrorCode;
 use weil_rs::sqs::SQS;
 use weil_rs::mcp::sqs::{
     CreateQueueParams, Credentials, DeleteMessagesParams, ListQueuesParams, ReceiveMessagesParams,
@@ -189,7 +190,7 @@ impl AwsSQS for AwsSQSContractState {
             next_token,
             max_results,
         };
-        let response = SQS::list_queues(params).map_err(|e| e.to_string())?;
+        let response = SQS::list_queues(params).map_err(|e| ErrorCode::Upstream.tag(e))?;
         Ok((response.queues, response.next_token))
     }
 
@@ -204,7 +205,7 @@ impl AwsSQS for AwsSQSContractState {
             session_token: None,
         };
         let params = CreateQueueParams { credentials, name };
-        let resp = SQS::create_queue(params).map_err(|e| e.to_string())?;
+        let resp = SQS::create_queue(params).map_err(|e| ErrorCode::Upstream.tag(e))?;
         Ok(resp)
     }
 
@@ -219,7 +220,7 @@ impl AwsSQS for AwsSQSContractState {
             session_token: None,
         };
         let params = CreateQueueParams { credentials, name };
-        let resp = SQS::delete_queue(params).map_err(|e| e.to_string())?;
+        let resp = SQS::delete_queue(params).map_err(|e| ErrorCode::Upstream.tag(e))?;
         Ok(Some(resp))
     }
 
@@ -242,7 +243,7 @@ impl AwsSQS for AwsSQSContractState {
             queue,
             messages,
         };
-        let response = SQS::send_messages(params).map_err(|e| e.to_string())?;
+        let response = SQS::send_messages(params).map_err(|e| ErrorCode::Upstream.tag(e))?;
         Ok(response.failed)
     }
 
@@ -265,7 +266,7 @@ impl AwsSQS for AwsSQSContractState {
             queue,
             max_results,
         };
-        let response = SQS::receive_messages(params).map_err(|e| e.to_string())?;
+        let response = SQS::receive_messages(params).map_err(|e| ErrorCode::Upstream.tag(e))?;
         Ok(response.received)
     }
 
@@ -302,7 +303,7 @@ impl AwsSQS for AwsSQSContractState {
             queue,
             handles,
         };
-        let resp = SQS::delete_messages(params).map_err(|e| e.to_string())?;
+        let resp = SQS::delete_messages(params).map_err(|e| ErrorCode::Upstream.tag(e))?;
         Ok(resp.failed)
     }
 
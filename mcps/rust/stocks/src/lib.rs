@@ -6,23 +6,42 @@ use urlencoding::{decode, encode};
 use weil_macros::{WeilType, constructor, query, smart_contract};
 use weil_rs::collections::plottable::Plottable;
 use weil_rs::config::Secrets;
+use weil_rs::errors::ErrorCode;
 use weil_rs::http::{HttpClient, HttpMethod};
 
+/// User-Agent sent with every request unless `user_agent` is set in the config.
+const DEFAULT_USER_AGENT: &str = concat!(
+    "wadk-mcp/",
+    env!("CARGO_PKG_NAME"),
+    "/",
+    env!("CARGO_PKG_VERSION")
+);
+
 /// Gets the current timestamp in milliseconds from the timestamp API.
 /// Returns the timestamp as a u64 value.
 async fn get_current_timestamp() -> Result<u64, String> {
     let url = "https://aisenseapi.com/services/v1/timestamp";
 
+    let headers = HashMap::from([("User-Agent".to_string(), DEFAULT_USER_AGENT.to_string())]);
     let response = HttpClient::request(url, HttpMethod::Get)
+        .headers(headers)
         .send()
         .map_err(|e| format!("HTTP request failed: {}", e))?;
 
+    let status = response.status();
     let response_text = response.text();
+    if !(200..300).contains(&status) {
+        return Err(ErrorCode::from_http_status(status).tag(format!(
+            "Timestamp API returned status {}: {}",
+            status, response_text
+        )));
+    }
 
     // Parse the JSON response to extract timestamp
     use serde_json::Value;
-    let json: Value = serde_json::from_str(&response_text)
-        .map_err(|e| format!("Failed to parse timestamp response: {}", e))?;
+    let json: Value = serde_json::from_str(&response_text).map_err(|e| {
+        ErrorCode::Upstream.tag(format!("Failed to parse timestamp response: {}", e))
+    })?;
 
     let timestamp_seconds = json["timestamp"]
         .as_u64()
@@ -67,6 +86,8 @@ pub struct StocksConfig {
     api_key_3: String,
     api_key_4: String,
     api_key_5: String,
+    /// User-Agent to send instead of `DEFAULT_USER_AGENT`.
+    user_agent: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1254,6 +1275,14 @@ impl StocksContractState {
         Ok(plot)
     }
 
+    /// User-Agent for outbound requests: the configured override, or `DEFAULT_USER_AGENT`.
+    fn user_agent(&self) -> String {
+        match self.secrets.config().user_agent.as_deref().map(str::trim) {
+            Some(user_agent) if !user_agent.is_empty() => user_agent.to_string(),
+            _ => DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+
     /// Helper method to make HTTP requests to Alpha Vantage API.
     ///
     /// # Arguments
@@ -1265,25 +1294,39 @@ impl StocksContractState {
     /// * `Ok(String)` - The response text from the API
     /// * `Err(String)` - Error message if the request fails
     async fn make_api_request(&self, url: &str) -> Result<String, String> {
+        let headers = HashMap::from([("User-Agent".to_string(), self.user_agent())]);
         let response = HttpClient::request(url, HttpMethod::Get)
+            .headers(headers)
             .send()
             .map_err(|e| format!("HTTP request failed: {}", e))?;
 
+        let status = response.status();
         let response_text = response.text();
+        if !(200..300).contains(&status) {
+            return Err(ErrorCode::from_http_status(status).tag(format!(
+                "Alpha Vantage API returned status {}: {}",
+                status, response_text
+            )));
+        }
 
-        // Check if the response contains an error message
-        if response_text.contains("Error Message") || response_text.contains("Note:") {
-            return Err(format!("Alpha Vantage API error: {}", response_text));
+        // Alpha Vantage answers 200 even for a bad request ("Error Message") or throttling ("Note:")
+        if response_text.contains("Error Message") {
+            return Err(
+                ErrorCode::Validation.tag(format!("Alpha Vantage API error: {}", response_text))
+            );
+        }
+        if response_text.contains("Note:") {
+            return Err(
+                ErrorCode::RateLimited.tag(format!("Alpha Vantage API error: {}", response_text))
+            );
         }
 
         // Detect Alpha Vantage daily rate limit informational response and return standardized error
         if response_text.contains("\"Information\"")
             && response_text.contains("rate limit is 25 requests per day")
         {
-            return Err(
-                "Alpha Vantage API error: API rate limit of 25 requests per day is reached"
-                    .to_string(),
-            );
+            return Err(ErrorCode::RateLimited
+                .tag("Alpha Vantage API error: API rate limit of 25 requests per day is reached"));
         }
 
         Ok(response_text)
@@ -27,6 +27,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use weil_macros::{WeilType, constructor, mutate, query, smart_contract};
 use weil_rs::config::Secrets;
+use weil_rs::errors::ErrorCode;
 use weil_rs::http::{HttpClient, HttpMethod};
 
 /// Twilio credentials and default sender configuration.
@@ -42,8 +43,18 @@ pub struct TwilioConfig {
     twilio_account_sid: String,
     twilio_auth_token: String,
     twilio_phone_number: String,
+    /// User-Agent to send instead of `DEFAULT_USER_AGENT`.
+    user_agent: Option<String>,
 }
 
+/// User-Agent sent with every request unless `user_agent` is set in the config.
+const DEFAULT_USER_AGENT: &str = concat!(
+    "wadk-mcp/",
+    env!("CARGO_PKG_NAME"),
+    "/",
+    env!("CARGO_PKG_VERSION")
+);
+
 /// Shape of a Twilio Message resource as returned by `/Messages.json`.
 ///
 /// Not all possible Twilio fields are represented—only common ones used by the contract.
@@ -111,7 +122,8 @@ trait Twilio {
     /// - `body`: Text content.
     ///
     /// Returns a success string mentioning the created Message SID if parsing succeeds,
-    /// otherwise returns the raw Twilio response body for diagnostics.
+    /// otherwise returns the raw Twilio response body for diagnostics. A non-2xx status is an
+    /// error tagged with its `ErrorCode`.
     async fn send_sms(&mut self, from: String, to: String, body: String) -> Result<String, String>;
 
     /// Initiate a voice call using Twilio's `POST /Calls.json` with a TwiML URL.
@@ -121,7 +133,8 @@ trait Twilio {
     /// - `twiml_url`: Publicly reachable URL that returns TwiML `<Response>`.
     ///
     /// Returns a success string with Call SID if the response parses as `TwilioCall`,
-    /// otherwise returns the raw response for diagnostics.
+    /// otherwise returns the raw response for diagnostics. A non-2xx status is an error tagged
+    /// with its `ErrorCode`.
     async fn send_voice_note(
         &mut self,
         from: String,
@@ -175,6 +188,24 @@ impl TwilioContractState {
             config.twilio_account_sid
         ))
     }
+
+    /// User-Agent for outbound requests: the configured override, or `DEFAULT_USER_AGENT`.
+    fn user_agent(&self) -> String {
+        match self.secrets.config().user_agent.as_deref().map(str::trim) {
+            Some(user_agent) if !user_agent.is_empty() => user_agent.to_string(),
+            _ => DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+}
+
+/// Twilio's response body on a 2xx status; any other status is an error tagged with its
+/// `ErrorCode`, carrying Twilio's explanation.
+fn check_response(status: u16, body: String) -> Result<String, String> {
+    if (200..300).contains(&status) {
+        Ok(body)
+    } else {
+        Err(ErrorCode::from_http_status(status).tag(format!("HTTP {}: {}", status, body.trim())))
+    }
 }
 
 #[smart_contract]
@@ -196,7 +227,8 @@ impl Twilio for TwilioContractState {
     /// **Auth**: `Authorization: Basic <base64(account_sid:auth_token)>`
     ///
     /// Returns `"SMS sent successfully. SID: <sid>"` if the JSON decodes into `TwilioMessage`,
-    /// otherwise returns the raw response string for easier debugging of Twilio-side errors.
+    /// otherwise returns the raw response string for easier debugging. A non-2xx status is an
+    /// error tagged with its `ErrorCode`, e.g. `[AUTH] HTTP 401: ...`.
     #[mutate]
     async fn send_sms(&mut self, from: String, to: String, body: String) -> Result<String, String> {
         let url = format!("{}/Messages.json", self.get_base_url()?);
@@ -210,6 +242,7 @@ impl Twilio for TwilioContractState {
 
         let mut headers = HashMap::new();
         headers.insert("Authorization".to_string(), auth_header);
+        headers.insert("User-Agent".to_string(), self.user_agent());
 
         let response = HttpClient::request(&url, HttpMethod::Post)
             .headers(headers)
@@ -217,7 +250,7 @@ impl Twilio for TwilioContractState {
             .send()
             .map_err(|err| err.to_string())?;
 
-        let response_text = response.text();
+        let response_text = check_response(response.status(), response.text())?;
 
         // Try to parse the response to check for success
         match serde_json::from_str::<TwilioMessage>(&response_text) {
@@ -236,7 +269,8 @@ impl Twilio for TwilioContractState {
     /// **Auth**: `Authorization: Basic <base64(account_sid:auth_token)>`
     ///
     /// Returns `"Call initiated successfully. SID: <sid>"` on parsed success, otherwise
-    /// returns the raw Twilio response string.
+    /// returns the raw Twilio response string. A non-2xx status is a tagged error, as for
+    /// `send_sms`.
     #[mutate]
     async fn send_voice_note(
         &mut self,
@@ -255,6 +289,7 @@ impl Twilio for TwilioContractState {
 
         let mut headers = HashMap::new();
         headers.insert("Authorization".to_string(), auth_header);
+        headers.insert("User-Agent".to_string(), self.user_agent());
         headers.insert(
             "Content-Type".to_string(),
             "application/x-www-form-urlencoded".to_string(),
@@ -266,7 +301,7 @@ impl Twilio for TwilioContractState {
             .send()
             .map_err(|err| err.to_string())?;
 
-        let response_text = response.text();
+        let response_text = check_response(response.status(), response.text())?;
 
         // Try to parse the response to check for success
         match serde_json::from_str::<TwilioCall>(&response_text) {
@@ -291,6 +326,7 @@ impl Twilio for TwilioContractState {
 
         let mut headers = HashMap::new();
         headers.insert("Authorization".to_string(), auth_header);
+        headers.insert("User-Agent".to_string(), self.user_agent());
 
         let mut request_builder = HttpClient::request(&url, HttpMethod::Get).headers(headers);
 
@@ -303,7 +339,7 @@ impl Twilio for TwilioContractState {
 
         let response = request_builder.send().map_err(|err| err.to_string())?;
 
-        let response_text = response.text();
+        let response_text = check_response(response.status(), response.text())?;
 
         #[derive(Deserialize)]
         struct MessagesResponse {
@@ -312,10 +348,10 @@ impl Twilio for TwilioContractState {
 
         let messages_response: MessagesResponse =
             serde_json::from_str(&response_text).map_err(|err| {
-                format!(
+                ErrorCode::Upstream.tag(format!(
                     "Failed to parse response: {}. Response was: {}",
                     err, response_text
-                )
+                ))
             })?;
 
         Ok(messages_response.messages)
@@ -331,18 +367,19 @@ impl Twilio for TwilioContractState {
 
         let mut headers = HashMap::new();
         headers.insert("Authorization".to_string(), auth_header);
+        headers.insert("User-Agent".to_string(), self.user_agent());
 
         let response = HttpClient::request(&url, HttpMethod::Get)
             .headers(headers)
             .send()
             .map_err(|err| err.to_string())?;
 
-        let response_text = response.text();
+        let response_text = check_response(response.status(), response.text())?;
         serde_json::from_str::<AccountInfo>(&response_text).map_err(|err| {
-            format!(
+            ErrorCode::Upstream.tag(format!(
                 "Failed to parse response: {}. Response was: {}",
                 err, response_text
-            )
+            ))
         })
     }
 